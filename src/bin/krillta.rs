@@ -0,0 +1,18 @@
+extern crate krill;
+
+use krill::cli::ta_signer::{TrustAnchorSignerClient, TrustAnchorSignerOptions};
+
+fn main() {
+    match TrustAnchorSignerOptions::from_args() {
+        Ok(options) => {
+            if let Err(e) = TrustAnchorSignerClient::report(options) {
+                eprintln!("{}", e);
+                ::std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ::std::process::exit(1);
+        }
+    }
+}
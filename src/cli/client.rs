@@ -2,18 +2,22 @@ use std::{env, fmt};
 
 use serde::{de::DeserializeOwned, Serialize};
 
-use rpki::uri;
+use rpki::{repository::x509::Time, uri};
 
 use crate::{
     cli::{
-        options::{BulkCaCommand, CaCommand, Command, KrillInitDetails, Options, PubServerCommand},
+        options::{
+            AuditCommand, BulkCaCommand, CaCommand, Command, KrillInitDetails, Options, PubServerCommand,
+            RoaImportMode, TrustAnchorCommand,
+        },
         report::{ApiResponse, ReportError},
     },
     commons::{
         api::{
-            AllCertAuthIssues, AspaDefinitionUpdates, CaRepoDetails, CertAuthIssues, ChildCaInfo,
-            ChildrenConnectionStats, ParentCaContact, ParentStatuses, PublisherDetails, PublisherList, RepoStatus,
-            Token,
+            AllCertAuthIssues, AspaDefinitionUpdates, CaRepoDetails, CertAuthIssues, ChildCaInfo, ChildHandle,
+            ChildRequestList, ChildShrinkList, ChildrenConnectionStats, Handle, ParentCaContact, ParentStatuses,
+            PublisherDetails, PublisherList, RepoStatus, RepositoryMirrorList, ResourceTemplateList, RoaDefinition,
+            RoaDefinitionUpdates, Token, UndelegatedResources,
         },
         bgp::BgpAnalysisAdvice,
         error::KrillIoError,
@@ -21,12 +25,13 @@ use crate::{
         util::{file, httpclient},
     },
     constants::KRILL_CLI_API_ENV,
-    daemon::config::Config,
+    daemon::{audit::verify_audit_log, config::Config},
 };
 
 #[cfg(feature = "multi-user")]
 use crate::{
-    cli::options::KrillUserDetails,
+    cli::options::{KrillUserDetails, PolicyCommand, TenantCommand, TokenCommand, UserCommand},
+    commons::api::{LocalUserRequest, PasswordChange},
     constants::{PW_HASH_LOG_N, PW_HASH_P, PW_HASH_R},
 };
 
@@ -86,6 +91,13 @@ async fn delete(server: &uri::Https, token: &Token, uri: &str) -> Result<(), Err
         .map_err(Error::HttpClientError)
 }
 
+fn suspension_policy_uri(ca: &Handle, child: Option<&ChildHandle>) -> String {
+    match child {
+        Some(child) => format!("api/v1/cas/{}/children/{}/suspension_policy", ca, child),
+        None => format!("api/v1/cas/{}/suspension_policy", ca),
+    }
+}
+
 /// Command line tool for Krill admin tasks
 pub struct KrillClient {
     server: uri::Https,
@@ -128,9 +140,19 @@ impl KrillClient {
             Command::Bulk(cmd) => client.bulk(cmd).await,
             Command::CertAuth(cmd) => client.certauth(cmd).await,
             Command::PubServer(cmd) => client.publishers(cmd).await,
+            Command::TrustAnchor(cmd) => client.trust_anchor(cmd).await,
             Command::Init(details) => client.init_config(details),
             #[cfg(feature = "multi-user")]
             Command::User(cmd) => client.user(cmd),
+            #[cfg(feature = "multi-user")]
+            Command::Token(cmd) => client.token(cmd).await,
+            #[cfg(feature = "multi-user")]
+            Command::Policy(cmd) => client.policy(cmd).await,
+            #[cfg(feature = "multi-user")]
+            Command::Tenants(cmd) => client.tenants(cmd).await,
+            #[cfg(feature = "multi-user")]
+            Command::Users(cmd) => client.users(cmd).await,
+            Command::Audit(cmd) => client.audit(cmd),
             Command::NotSet => Err(Error::MissingCommand),
         }
     }
@@ -159,6 +181,149 @@ impl KrillClient {
                 post_empty(&self.server, &self.token, "api/v1/bulk/cas/sync/repo").await?;
                 Ok(ApiResponse::Empty)
             }
+            BulkCaCommand::Simulate(request) => {
+                let uri = "api/v1/bulk/cas/bgp/simulate";
+                let report = post_json_with_response(&self.server, &self.token, uri, request).await?;
+                Ok(ApiResponse::BgpSimulation(report))
+            }
+        }
+    }
+
+    #[cfg(feature = "multi-user")]
+    async fn token(&self, command: TokenCommand) -> Result<ApiResponse, Error> {
+        match command {
+            TokenCommand::Create(request) => {
+                let created = post_json_with_response(&self.server, &self.token, "api/v1/tokens", request).await?;
+                Ok(ApiResponse::ApiTokenCreated(created))
+            }
+            TokenCommand::List => {
+                let list = get_json(&self.server, &self.token, "api/v1/tokens").await?;
+                Ok(ApiResponse::ApiTokens(list))
+            }
+            TokenCommand::Revoke(name) => {
+                let uri = format!("api/v1/tokens/{}", name);
+                delete(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+        }
+    }
+
+    #[cfg(feature = "multi-user")]
+    async fn policy(&self, command: PolicyCommand) -> Result<ApiResponse, Error> {
+        match command {
+            PolicyCommand::Reload => {
+                post_empty(&self.server, &self.token, "api/v1/policy/reload").await?;
+                Ok(ApiResponse::Empty)
+            }
+            PolicyCommand::Explain(request) => {
+                let explanation =
+                    post_json_with_response(&self.server, &self.token, "api/v1/policy/explain", request).await?;
+                Ok(ApiResponse::PolicyExplanation(explanation))
+            }
+        }
+    }
+
+    #[cfg(feature = "multi-user")]
+    async fn tenants(&self, command: TenantCommand) -> Result<ApiResponse, Error> {
+        match command {
+            TenantCommand::List => {
+                let list = get_json(&self.server, &self.token, "api/v1/tenants").await?;
+                Ok(ApiResponse::Tenants(list))
+            }
+            TenantCommand::Show(name) => {
+                let uri = format!("api/v1/tenants/{}", name);
+                let tenant = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Tenant(tenant))
+            }
+            TenantCommand::Add(definition) => {
+                let tenant = post_json_with_response(&self.server, &self.token, "api/v1/tenants", definition).await?;
+                Ok(ApiResponse::Tenant(tenant))
+            }
+            TenantCommand::Update(definition) => {
+                let uri = format!("api/v1/tenants/{}", definition.name());
+                let tenant = post_json_with_response(&self.server, &self.token, &uri, definition).await?;
+                Ok(ApiResponse::Tenant(tenant))
+            }
+            TenantCommand::Remove(name) => {
+                let uri = format!("api/v1/tenants/{}", name);
+                delete(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+            TenantCommand::AddUser(name, user) => {
+                let uri = format!("api/v1/tenants/{}/users/{}", name, user);
+                post_empty(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+            TenantCommand::RemoveUser(name, user) => {
+                let uri = format!("api/v1/tenants/{}/users/{}", name, user);
+                delete(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+        }
+    }
+
+    #[cfg(feature = "multi-user")]
+    async fn users(&self, command: UserCommand) -> Result<ApiResponse, Error> {
+        match command {
+            UserCommand::List => {
+                let list = get_json(&self.server, &self.token, "api/v1/users").await?;
+                Ok(ApiResponse::Users(list))
+            }
+            UserCommand::Add(id, attributes) => {
+                let request = LocalUserRequest::new(id, Self::read_new_password()?, attributes);
+                let user = post_json_with_response(&self.server, &self.token, "api/v1/users", request).await?;
+                Ok(ApiResponse::User(user))
+            }
+            UserCommand::Remove(id) => {
+                let uri = format!("api/v1/users/{}", id);
+                delete(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+            UserCommand::Disable(id) => {
+                let uri = format!("api/v1/users/{}/disable", id);
+                let user = post_json_with_response(&self.server, &self.token, &uri, ()).await?;
+                Ok(ApiResponse::User(user))
+            }
+            UserCommand::Enable(id) => {
+                let uri = format!("api/v1/users/{}/enable", id);
+                let user = post_json_with_response(&self.server, &self.token, &uri, ()).await?;
+                Ok(ApiResponse::User(user))
+            }
+            UserCommand::ResetPassword(id) => {
+                let uri = format!("api/v1/users/{}/password", id);
+                let change = PasswordChange::new(Self::read_new_password()?);
+                post_json(&self.server, &self.token, &uri, change).await?;
+                Ok(ApiResponse::Empty)
+            }
+            UserCommand::ResetMfa(id) => {
+                let uri = format!("api/v1/users/{}/mfa", id);
+                delete(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+        }
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn read_new_password() -> Result<String, Error> {
+        let read = |prompt| {
+            rpassword::read_password_from_tty(Some(prompt))
+                .map_err(|e| Error::InputError(format!("Could not read the password: {}", e)))
+        };
+
+        let password = read("Enter the password: ")?;
+        let confirm = read("Confirm the password: ")?;
+        if password != confirm {
+            return Err(Error::InputError("The passwords do not match".to_string()));
+        }
+        Ok(password)
+    }
+
+    fn audit(&self, command: AuditCommand) -> Result<ApiResponse, Error> {
+        match command {
+            AuditCommand::Verify(path) => {
+                let verification = verify_audit_log(&path).map_err(|e| Error::InputError(e.to_string()))?;
+                Ok(ApiResponse::AuditLogVerification(verification))
+            }
         }
     }
 
@@ -176,6 +341,12 @@ impl KrillClient {
                 Ok(ApiResponse::Empty)
             }
 
+            CaCommand::Rename(ca, rename) => {
+                let uri = format!("api/v1/cas/{}/rename", ca);
+                let report = post_json_with_response(&self.server, &self.token, &uri, rename).await?;
+                Ok(ApiResponse::CertAuthRenamed(report))
+            }
+
             CaCommand::UpdateId(handle) => {
                 let uri = format!("api/v1/cas/{}/id", handle);
                 post_empty(&self.server, &self.token, &uri).await?;
@@ -218,6 +389,24 @@ impl KrillClient {
                 Ok(ApiResponse::Empty)
             }
 
+            CaCommand::RepoMirrorsList(handle) => {
+                let uri = format!("api/v1/cas/{}/repo/mirrors", handle);
+                let mirrors: RepositoryMirrorList = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RepoMirrors(mirrors))
+            }
+
+            CaCommand::RepoMirrorAdd(handle, name, contact) => {
+                let uri = format!("api/v1/cas/{}/repo/mirrors/{}", handle, name);
+                post_json(&self.server, &self.token, &uri, contact).await?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::RepoMirrorRemove(handle, name) => {
+                let uri = format!("api/v1/cas/{}/repo/mirrors/{}", handle, name);
+                delete(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+
             CaCommand::AddParent(handle, parent_req) => {
                 let uri = format!("api/v1/cas/{}/parents", handle);
                 post_json(&self.server, &self.token, &uri, parent_req).await?;
@@ -269,6 +458,46 @@ impl KrillClient {
                 delete(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::Empty)
             }
+            CaCommand::ChildRequests(handle) => {
+                let uri = format!("api/v1/cas/{}/child_requests", handle);
+                let list: ChildRequestList = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ChildRequests(list))
+            }
+            CaCommand::ChildRequestApprove(handle, child, resources) => {
+                let uri = format!("api/v1/cas/{}/child_requests/{}/approve", handle, child);
+                let info: ParentCaContact = post_json_with_response(&self.server, &self.token, &uri, resources).await?;
+                Ok(ApiResponse::ParentCaContact(info))
+            }
+            CaCommand::ChildRequestReject(handle, child) => {
+                let uri = format!("api/v1/cas/{}/child_requests/{}", handle, child);
+                delete(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+            CaCommand::ChildUndelegated(handle) => {
+                let uri = format!("api/v1/cas/{}/stats/children/undelegated", handle);
+                let undelegated: UndelegatedResources = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::UndelegatedResources(undelegated))
+            }
+            CaCommand::ChildShrinks(handle) => {
+                let uri = format!("api/v1/cas/{}/stats/children/shrinks", handle);
+                let shrinks: ChildShrinkList = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ChildShrinks(shrinks))
+            }
+            CaCommand::ResourceTemplatesList(handle) => {
+                let uri = format!("api/v1/cas/{}/resource_templates", handle);
+                let list: ResourceTemplateList = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::ResourceTemplates(list))
+            }
+            CaCommand::ResourceTemplateUpdate(handle, template) => {
+                let uri = format!("api/v1/cas/{}/resource_templates", handle);
+                post_json(&self.server, &self.token, &uri, template).await?;
+                Ok(ApiResponse::Empty)
+            }
+            CaCommand::ResourceTemplateRemove(handle, name) => {
+                let uri = format!("api/v1/cas/{}/resource_templates/{}", handle, name);
+                delete(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
             CaCommand::ChildConnections(handle) => {
                 let uri = format!("api/v1/cas/{}/stats/children/connections", handle);
                 let stats: ChildrenConnectionStats = get_json(&self.server, &self.token, &uri).await?;
@@ -280,11 +509,40 @@ impl KrillClient {
                 post_empty(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::Empty)
             }
-            CaCommand::KeyRollActivate(handle) => {
-                let uri = format!("api/v1/cas/{}/keys/roll_activate", handle);
+            CaCommand::KeyRollActivate(handle, force) => {
+                let uri = if force {
+                    format!("api/v1/cas/{}/keys/roll_activate/force", handle)
+                } else {
+                    format!("api/v1/cas/{}/keys/roll_activate", handle)
+                };
                 post_empty(&self.server, &self.token, &uri).await?;
                 Ok(ApiResponse::Empty)
             }
+            CaCommand::KeyRollStatus(handle) => {
+                let uri = format!("api/v1/cas/{}/keys/roll_status", handle);
+                let status = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::KeyRollStatus(status))
+            }
+            CaCommand::KeyRollPolicySet(handle, policy) => {
+                let uri = format!("api/v1/cas/{}/keys/roll_policy", handle);
+                post_json(&self.server, &self.token, &uri, policy).await?;
+                Ok(ApiResponse::Empty)
+            }
+            CaCommand::KeyRollPolicyClear(handle) => {
+                let uri = format!("api/v1/cas/{}/keys/roll_policy", handle);
+                delete(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
+            CaCommand::SuspensionPolicySet(handle, child, policy) => {
+                let uri = suspension_policy_uri(&handle, child.as_ref());
+                post_json(&self.server, &self.token, &uri, policy).await?;
+                Ok(ApiResponse::Empty)
+            }
+            CaCommand::SuspensionPolicyClear(handle, child) => {
+                let uri = suspension_policy_uri(&handle, child.as_ref());
+                delete(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::Empty)
+            }
 
             CaCommand::RouteAuthorizationsList(handle) => {
                 let uri = format!("api/v1/cas/{}/routes", handle);
@@ -314,6 +572,61 @@ impl KrillClient {
                 Ok(ApiResponse::BgpAnalysisFull(report))
             }
 
+            CaCommand::RouteAuthorizationsHistory(handle, prefix) => {
+                let uri = match prefix {
+                    Some(prefix) => format!("api/v1/cas/{}/routes/history/{}", handle, prefix),
+                    None => format!("api/v1/cas/{}/routes/history", handle),
+                };
+                let history = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RouteAuthorizationsHistory(history))
+            }
+
+            CaCommand::RouteAuthorizationsDiff(handle, from, to) => {
+                let to = to.unwrap_or_else(Time::now);
+                let uri = format!(
+                    "api/v1/cas/{}/routes/diff/{}/{}",
+                    handle,
+                    from.timestamp(),
+                    to.timestamp()
+                );
+                let diff = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::RouteAuthorizationsDiff(diff))
+            }
+
+            CaCommand::RouteAuthorizationsImport(handle, definitions, mode) => {
+                let uri = format!("api/v1/cas/{}/routes", handle);
+                let current: Vec<RoaDefinition> = get_json(&self.server, &self.token, &uri).await?;
+                let updates = RoaDefinitionUpdates::between(&current, &definitions);
+
+                if updates.is_empty() {
+                    return Ok(ApiResponse::Empty);
+                }
+
+                match mode {
+                    RoaImportMode::Preview => Ok(ApiResponse::RouteAuthorizationsDiff(updates)),
+                    RoaImportMode::DryRun => {
+                        let uri = format!("api/v1/cas/{}/routes/analysis/dryrun", handle);
+                        let report = post_json_with_response(&self.server, &self.token, &uri, updates).await?;
+                        Ok(ApiResponse::BgpAnalysisFull(report))
+                    }
+                    RoaImportMode::Try => {
+                        let uri = format!("api/v1/cas/{}/routes/try", handle);
+                        let advice_opt: Option<BgpAnalysisAdvice> =
+                            post_json_with_opt_response(&self.server, &self.token, &uri, updates).await?;
+                        match advice_opt {
+                            None => Ok(ApiResponse::Empty),
+                            Some(advice) => Ok(ApiResponse::BgpAnalysisAdvice(advice)),
+                        }
+                    }
+                }
+            }
+
+            CaCommand::RouteAuthorizationsExport(handle, format) => {
+                let uri = format!("api/v1/cas/{}/routes", handle);
+                let definitions: Vec<RoaDefinition> = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::GenericBody(format.write(&definitions)))
+            }
+
             CaCommand::BgpAnalysisFull(handle) => {
                 let uri = format!("api/v1/cas/{}/routes/analysis/full", handle);
                 let report = get_json(&self.server, &self.token, &uri).await?;
@@ -490,6 +803,71 @@ impl KrillClient {
         }
     }
 
+    pub async fn trust_anchor(&self, command: TrustAnchorCommand) -> Result<ApiResponse, Error> {
+        match command {
+            TrustAnchorCommand::ProxyInit => {
+                post_empty(&self.server, &self.token, "api/v1/ta/proxy/init").await?;
+                Ok(ApiResponse::Empty)
+            }
+            TrustAnchorCommand::ProxyInfo => {
+                let info = get_json(&self.server, &self.token, "api/v1/ta/proxy/info").await?;
+                Ok(ApiResponse::TrustAnchorProxyInfo(info))
+            }
+            TrustAnchorCommand::ProxyRepoRequest => {
+                let uri = "api/v1/ta/proxy/repo/request.json";
+                let req = get_json(&self.server, &self.token, uri).await?;
+                Ok(ApiResponse::Rfc8183PublisherRequest(req))
+            }
+            TrustAnchorCommand::ProxyRepoConfigure(contact) => {
+                post_json(&self.server, &self.token, "api/v1/ta/proxy/repo", contact).await?;
+                Ok(ApiResponse::Empty)
+            }
+            TrustAnchorCommand::ProxySignerAdd(info) => {
+                post_json(&self.server, &self.token, "api/v1/ta/proxy/signer/add", info).await?;
+                Ok(ApiResponse::Empty)
+            }
+            TrustAnchorCommand::ProxySignerMakeRequest => {
+                let uri = resolve_uri(&self.server, "api/v1/ta/proxy/signer/request");
+                let request = httpclient::post_empty_with_response(&uri, Some(&self.token)).await?;
+                Ok(ApiResponse::TrustAnchorSignedRequest(request))
+            }
+            TrustAnchorCommand::ProxySignerShowRequest => {
+                let request = get_json(&self.server, &self.token, "api/v1/ta/proxy/signer/request").await?;
+                Ok(ApiResponse::TrustAnchorSignedRequest(request))
+            }
+            TrustAnchorCommand::ProxySignerProcessResponse(response) => {
+                post_json(&self.server, &self.token, "api/v1/ta/proxy/signer/response", response).await?;
+                Ok(ApiResponse::Empty)
+            }
+            TrustAnchorCommand::ProxyChildAdd(req) => {
+                let uri = "api/v1/ta/proxy/children";
+                let info: ParentCaContact = post_json_with_response(&self.server, &self.token, uri, req).await?;
+                Ok(ApiResponse::ParentCaContact(info))
+            }
+            TrustAnchorCommand::ProxyHistory(options) => {
+                let uri = format!("api/v1/ta/proxy/history/{}", options.url_path_parameters());
+                let history = get_json(&self.server, &self.token, &uri).await?;
+                Ok(ApiResponse::CertAuthHistory(history))
+            }
+            TrustAnchorCommand::KeyRollStatus => {
+                let status = get_json(&self.server, &self.token, "api/v1/ta/keyroll").await?;
+                Ok(ApiResponse::TaKeyRollStatus(status))
+            }
+            TrustAnchorCommand::KeyRollInit => {
+                post_empty(&self.server, &self.token, "api/v1/ta/keyroll/init").await?;
+                Ok(ApiResponse::Empty)
+            }
+            TrustAnchorCommand::KeyRollActivate => {
+                post_empty(&self.server, &self.token, "api/v1/ta/keyroll/activate").await?;
+                Ok(ApiResponse::Empty)
+            }
+            TrustAnchorCommand::KeyRollFinish => {
+                post_empty(&self.server, &self.token, "api/v1/ta/keyroll/finish").await?;
+                Ok(ApiResponse::Empty)
+            }
+        }
+    }
+
     fn init_config(&self, details: KrillInitDetails) -> Result<ApiResponse, Error> {
         let defaults = include_str!("../../defaults/krill.conf");
         let multi_add_on = include_str!("../../defaults/krill-multi-user.conf");
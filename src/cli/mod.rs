@@ -1,5 +1,6 @@
 pub mod options;
 pub mod report;
+pub mod ta_signer;
 
 mod client;
 pub use self::client::Error;
@@ -1,6 +1,9 @@
 #[cfg(feature = "multi-user")]
 use std::collections::HashMap;
 
+#[cfg(feature = "multi-user")]
+use crate::commons::api::{ApiTokenRequest, PolicyExplainRequest, TenantDefinition};
+
 use std::{
     convert::TryFrom,
     path::PathBuf,
@@ -25,10 +28,13 @@ use crate::{
     commons::{
         api::{
             AddChildRequest, AspaCustomer, AspaDefinition, AspaDefinitionFormatError, AspaProvidersUpdate,
-            AuthorizationFmtError, CertAuthInit, ChildHandle, Handle, ParentCaContact, ParentCaReq, ParentHandle,
-            PublicationServerUris, PublisherHandle, RepositoryContact, ResourceSet, ResourceSetError, RoaDefinition,
-            RoaDefinitionUpdates, RtaName, Token, UpdateChildRequest,
+            AuthorizationFmtError, CertAuthInit, CertAuthRename, ChildHandle, ChildSuspensionPolicy, Handle,
+            KeyRollPolicy, ParentCaContact, ParentCaReq, ParentHandle, PublicationServerUris, PublisherHandle,
+            RepositoryContact, RepositoryMirrorName, ResourceSet, ResourceSetError, ResourceTemplate,
+            ResourceTemplateName, RoaDefinition, RoaDefinitionUpdates, RoaFileFormat, RtaName, Token, TypedPrefix,
+            UpdateChildRequest,
         },
+        bgp::BgpSimulationRequest,
         crypto::{IdCert, SignSupport},
         error::KrillIoError,
         remote::rfc8183,
@@ -36,6 +42,7 @@ use crate::{
     },
     constants::*,
     daemon::ca::{ResourceTaggedAttestation, RtaContentRequest, RtaPrepareRequest},
+    ta::{TrustAnchorSignedResponse, TrustAnchorSignerInfo},
 };
 
 struct GeneralArgs {
@@ -340,43 +347,43 @@ impl Options {
         app.subcommand(sub)
     }
 
-    fn make_cas_show_history_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("commands").about("Show the commands sent to a CA");
-
-        sub = Self::add_general_args(sub);
-        sub = Self::add_my_ca_arg(sub);
-
-        sub = sub.arg(
+    fn add_history_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
             Arg::with_name("rows")
                 .long("rows")
                 .help("Number of rows (max 250)")
                 .value_name("<number>")
                 .required(false),
-        );
-
-        sub = sub.arg(
+        )
+        .arg(
             Arg::with_name("offset")
                 .long("offset")
                 .help("Number of results to skip")
                 .value_name("<number>")
                 .required(false),
-        );
-
-        sub = sub.arg(
+        )
+        .arg(
             Arg::with_name("after")
                 .long("after")
                 .help("Show commands issued after date/time in RFC 3339 format, e.g. 2020-04-09T19:37:02Z")
                 .value_name("<RFC 3339 DateTime>")
                 .required(false),
-        );
-
-        sub = sub.arg(
+        )
+        .arg(
             Arg::with_name("before")
                 .long("before")
                 .help("Show commands issued after date/time in RFC 3339 format, e.g. 2020-04-09T19:37:02Z")
                 .value_name("<RFC 3339 DateTime>")
                 .required(false),
-        );
+        )
+    }
+
+    fn make_cas_show_history_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("commands").about("Show the commands sent to a CA");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_history_args(sub);
 
         app.subcommand(sub)
     }
@@ -409,6 +416,23 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_rename_ca_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("rename")
+            .about("Rename a CA. Shows which steps remain for remote parents, children and repositories.");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        let sub = sub.arg(
+            Arg::with_name("new")
+                .long("new")
+                .value_name("name")
+                .help("The new name for the CA")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_children_add_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("add").about("Add a child to a CA");
 
@@ -442,6 +466,20 @@ impl Options {
                 .value_name("DER encoded certificate")
                 .required(false),
         );
+        sub = sub.arg(
+            Arg::with_name("template")
+                .long("template")
+                .help("Use the resources of this resource template for the child")
+                .value_name("name")
+                .required(false),
+        );
+        sub = sub.arg(
+            Arg::with_name("shrink_notice")
+                .long("shrink-notice")
+                .help("Days notice before resources are removed from the child, 0 to remove them immediately")
+                .value_name("days")
+                .required(false),
+        );
 
         app.subcommand(sub)
     }
@@ -506,6 +544,173 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_children_pending_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("pending").about("List requests submitted by prospective children");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_approve_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("approve").about("Approve a child request and add the child to a CA");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_child_arg(sub);
+        sub = Self::add_resource_args(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_reject_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("reject").about("Reject a pending child request");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_child_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_undelegated_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub =
+            SubCommand::with_name("undelegated").about("Show resources of a CA which are not delegated to any child");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn add_template_name_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name("name")
+                .long("name")
+                .help("The name of the resource template")
+                .value_name("name")
+                .required(true),
+        )
+    }
+
+    fn make_cas_children_templates_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list").about("List the resource templates of a CA");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_templates_set_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("set")
+            .about("Add or update a resource template, this also updates children using the template");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_template_name_arg(sub);
+        sub = Self::add_resource_args(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_templates_remove_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub =
+            SubCommand::with_name("remove").about("Remove a resource template which is not used by any child");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_template_name_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_templates_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("templates").about("Manage resource templates for children");
+
+        sub = Self::make_cas_children_templates_list_sc(sub);
+        sub = Self::make_cas_children_templates_set_sc(sub);
+        sub = Self::make_cas_children_templates_remove_sc(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_shrinks_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("shrinks")
+            .about("Show scheduled resource removals for children, and child ROAs which will become invalid");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn add_opt_child_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name("child")
+                .value_name("name")
+                .long("child")
+                .help("Optional. The name of a child CA, if the policy is only for this child")
+                .required(false),
+        )
+    }
+
+    fn make_cas_children_suspension_policy_set_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub =
+            SubCommand::with_name("set").about("Set the suspension policy for inactive children of a CA, or one child");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_opt_child_arg(sub);
+
+        sub = sub
+            .arg(
+                Arg::with_name("exempt")
+                    .long("exempt")
+                    .help("Never suspend inactive children")
+                    .conflicts_with_all(&["suspend_after_hours", "warn_after_hours"])
+                    .required_unless("suspend_after_hours"),
+            )
+            .arg(
+                Arg::with_name("suspend_after_hours")
+                    .long("suspend-after-hours")
+                    .value_name("hours")
+                    .help("Suspend children which were inactive for more than this number of hours")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("warn_after_hours")
+                    .long("warn-after-hours")
+                    .value_name("hours")
+                    .help("Optional. Warn about children which were inactive for more than this number of hours")
+                    .requires("suspend_after_hours")
+                    .required(false),
+            );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_suspension_policy_clear_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("clear")
+            .about("Remove the suspension policy for inactive children of a CA, or one child");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_opt_child_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_suspension_policy_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("suspension-policy").about("Manage when inactive children are suspended");
+
+        sub = Self::make_cas_children_suspension_policy_set_sc(sub);
+        sub = Self::make_cas_children_suspension_policy_clear_sc(sub);
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_children_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("children").about("Manage children for a CA");
 
@@ -517,6 +722,13 @@ impl Options {
         sub = Self::make_cas_children_connections_sc(sub);
         sub = Self::make_cas_children_suspend_sc(sub);
         sub = Self::make_cas_children_unsuspend_sc(sub);
+        sub = Self::make_cas_children_suspension_policy_sc(sub);
+        sub = Self::make_cas_children_pending_sc(sub);
+        sub = Self::make_cas_children_approve_sc(sub);
+        sub = Self::make_cas_children_reject_sc(sub);
+        sub = Self::make_cas_children_undelegated_sc(sub);
+        sub = Self::make_cas_children_shrinks_sc(sub);
+        sub = Self::make_cas_children_templates_sc(sub);
 
         app.subcommand(sub)
     }
@@ -604,6 +816,72 @@ impl Options {
         sub = Self::add_general_args(sub);
         sub = Self::add_my_ca_arg(sub);
 
+        sub = sub.arg(
+            Arg::with_name("force")
+                .long("force")
+                .help("Activate even if the staging period did not pass, or the new objects were not yet published")
+                .required(false),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_keyroll_status_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("status").about("Show the key roll status for all keys held by a CA");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_keyroll_policy_set_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("set").about("Set the policy for automated key rolls for a CA");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub
+            .arg(
+                Arg::with_name("max_age_days")
+                    .long("max-age-days")
+                    .value_name("days")
+                    .help("Roll keys when they are older than this number of days")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("staging_hours")
+                    .long("staging-hours")
+                    .value_name("hours")
+                    .help("Publish new keys for at least this number of hours before activating them")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("skip_publication_check")
+                    .long("skip-publication-check")
+                    .help("Do not wait for the repository to confirm publication before activating new keys")
+                    .required(false),
+            );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_keyroll_policy_clear_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub =
+            SubCommand::with_name("clear").about("Remove the key roll policy for a CA, keys are then rolled manually");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_keyroll_policy_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("policy").about("Manage the policy for automated key rolls for a CA");
+
+        sub = Self::make_cas_keyroll_policy_set_sc(sub);
+        sub = Self::make_cas_keyroll_policy_clear_sc(sub);
+
         app.subcommand(sub)
     }
 
@@ -612,6 +890,8 @@ impl Options {
 
         sub = Self::make_cas_keyroll_init_sc(sub);
         sub = Self::make_cas_keyroll_activate_sc(sub);
+        sub = Self::make_cas_keyroll_status_sc(sub);
+        sub = Self::make_cas_keyroll_policy_sc(sub);
 
         app.subcommand(sub)
     }
@@ -682,46 +962,145 @@ impl Options {
         app.subcommand(sub)
     }
 
-    fn make_cas_routes_bgp_full_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("analyze").about("Show full report of ROAs vs known BGP announcements");
+    fn add_roa_file_format_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name("file_format")
+                .long("file-format")
+                .possible_values(&["csv", "json", "rpsl"])
+                .help(concat!(
+                    "The file format: csv (ASN,IP Prefix,Max Length), json (RFC 8416 SLURM prefixAssertions), ",
+                    "or rpsl (route and route6 objects, without max length)"
+                ))
+                .value_name("<format>")
+                .required(true),
+        )
+    }
+
+    fn make_cas_routes_import_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("import")
+            .about("Replace all authorizations with the authorizations in a file, using 'update --try' by default");
 
         sub = Self::add_general_args(sub);
         sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_roa_file_format_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("file")
+                .long("file")
+                .help("The file with the wanted authorizations")
+                .value_name("<file>")
+                .required(true),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("preview")
+                .long("preview")
+                .help("Only show the authorizations that would be added and removed")
+                .required(false),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("dryrun")
+                .long("dryrun")
+                .help("Perform a dry run of the resulting update and return the BGP analysis")
+                .required(false),
+        );
+
         app.subcommand(sub)
     }
 
-    fn make_cas_routes_bgp_suggestions_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub = SubCommand::with_name("suggest").about("Show ROA suggestions based on known BGP announcements");
+    fn make_cas_routes_export_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("export").about("Export current authorizations to a file format");
 
         sub = Self::add_general_args(sub);
         sub = Self::add_my_ca_arg(sub);
-
-        sub = sub
-            .arg(
-                Arg::with_name("ipv4")
-                    .short("4")
-                    .long("ipv4")
-                    .value_name("IPv4 resources")
-                    .help("Scope to these IPv4 resources")
-                    .required(false),
-            )
-            .arg(
-                Arg::with_name("ipv6")
-                    .short("6")
-                    .long("ipv6")
-                    .value_name("IPv6 resources")
-                    .help("Scope to these IPv6 resources")
-                    .required(false),
-            );
+        sub = Self::add_roa_file_format_arg(sub);
 
         app.subcommand(sub)
     }
 
-    fn make_cas_routes_bgp_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub =
-            SubCommand::with_name("bgp").about("Show current authorizations in relation to known announcements");
+    fn make_cas_routes_history_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("history").about("Show when authorizations were added or removed");
 
-        sub = Self::make_cas_routes_bgp_full_sc(sub);
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("prefix")
+                .long("prefix")
+                .help("Only show authorizations covering this prefix, e.g. 192.168.0.0/24")
+                .value_name("<prefix>")
+                .required(false),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_diff_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("diff").about("Show the changes in authorizations between two dates");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("from")
+                .long("from")
+                .help("Compare authorizations from date/time in RFC 3339 format, e.g. 2020-04-09T19:37:02Z")
+                .value_name("<RFC 3339 DateTime>")
+                .required(true),
+        );
+
+        sub = sub.arg(
+            Arg::with_name("to")
+                .long("to")
+                .help("Compare to authorizations at date/time in RFC 3339 format, defaults to now")
+                .value_name("<RFC 3339 DateTime>")
+                .required(false),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_bgp_full_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("analyze").about("Show full report of ROAs vs known BGP announcements");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_bgp_suggestions_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("suggest").about("Show ROA suggestions based on known BGP announcements");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub
+            .arg(
+                Arg::with_name("ipv4")
+                    .short("4")
+                    .long("ipv4")
+                    .value_name("IPv4 resources")
+                    .help("Scope to these IPv4 resources")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("ipv6")
+                    .short("6")
+                    .long("ipv6")
+                    .value_name("IPv6 resources")
+                    .help("Scope to these IPv6 resources")
+                    .required(false),
+            );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_bgp_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub =
+            SubCommand::with_name("bgp").about("Show current authorizations in relation to known announcements");
+
+        sub = Self::make_cas_routes_bgp_full_sc(sub);
         sub = Self::make_cas_routes_bgp_suggestions_sc(sub);
 
         app.subcommand(sub)
@@ -733,6 +1112,10 @@ impl Options {
         sub = Self::make_cas_routes_list_sc(sub);
         sub = Self::make_cas_routes_update_sc(sub);
         sub = Self::make_cas_routes_bgp_sc(sub);
+        sub = Self::make_cas_routes_history_sc(sub);
+        sub = Self::make_cas_routes_diff_sc(sub);
+        sub = Self::make_cas_routes_import_sc(sub);
+        sub = Self::make_cas_routes_export_sc(sub);
 
         app.subcommand(sub)
     }
@@ -875,6 +1258,63 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn add_mirror_name_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name("name")
+                .long("name")
+                .help("The name of the repository mirror")
+                .value_name("name")
+                .required(true),
+        )
+    }
+
+    fn make_cas_repo_mirrors_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("list").about("List the repository mirrors of a CA, and their status");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_mirrors_add_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("add").about("Add a repository to which all objects are mirrored");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_mirror_name_arg(sub);
+        sub = sub.arg(
+            Arg::with_name("response")
+                .value_name("file")
+                .long("response")
+                .short("r")
+                .help("The location of the RFC8183 Publisher Response XML file of the mirror")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_mirrors_remove_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("remove").about("Remove a repository mirror");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_mirror_name_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_repo_mirrors_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("mirrors").about("Manage secondary repositories for a CA");
+
+        sub = Self::make_cas_repo_mirrors_list_sc(sub);
+        sub = Self::make_cas_repo_mirrors_add_sc(sub);
+        sub = Self::make_cas_repo_mirrors_remove_sc(sub);
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_repo_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("repo").about("Manage the repository for a CA");
 
@@ -882,6 +1322,7 @@ impl Options {
         sub = Self::make_cas_repo_show_sc(sub);
         sub = Self::make_cas_repo_status_sc(sub);
         sub = Self::make_cas_repo_configure_sc(sub);
+        sub = Self::make_cas_repo_mirrors_sc(sub);
 
         app.subcommand(sub)
     }
@@ -1074,7 +1515,307 @@ impl Options {
         let mut resync = SubCommand::with_name("sync").about("Force that all CAs sync with their repo server");
         resync = Self::add_general_args(resync);
 
-        sub = sub.subcommand(refresh).subcommand(republish).subcommand(resync);
+        let mut simulate = SubCommand::with_name("simulate")
+            .about("Simulate the effect of ROA and resource changes for several CAs on announcements for all CAs");
+        simulate = Self::add_general_args(simulate);
+        simulate = simulate.arg(
+            Arg::with_name("request")
+                .long("request")
+                .help(concat!(
+                    "Provide a JSON file with the proposed changes, e.g.:\n",
+                    "{ \"changes\": [\n",
+                    "  { \"ca\": \"parent\", \"resources\": { \"asn\": \"\", \"v4\": \"10.0.0.0/16\", \"v6\": \"\" } },\n",
+                    "  { \"ca\": \"child\", \"roas\": { \"added\": [ { \"asn\": 64496, \"prefix\": \"10.0.0.0/24\" } ], \"removed\": [] } }\n",
+                    "] }\n",
+                ))
+                .value_name("<file>")
+                .required(true),
+        );
+
+        sub = sub
+            .subcommand(refresh)
+            .subcommand(republish)
+            .subcommand(resync)
+            .subcommand(simulate);
+
+        app.subcommand(sub)
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn make_token_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("token").about("Manage scoped API tokens");
+
+        let mut create = SubCommand::with_name("create").about("Create a new scoped API token");
+        create = Self::add_general_args(create);
+        create = create
+            .arg(
+                Arg::with_name("name")
+                    .long("name")
+                    .value_name("name")
+                    .help("The unique name for the token")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("permission")
+                    .long("permission")
+                    .value_name("permission")
+                    .help("A permission to grant, e.g. ROUTES_UPDATE. Note that CA_READ is needed to access any CA")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("ca")
+                    .long("ca")
+                    .value_name("name")
+                    .help("Limit the token to this CA. If omitted, the token is not limited to specific CAs")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("expires")
+                    .long("expires")
+                    .value_name("RFC3339 time")
+                    .help("The time at which the token expires, e.g. 2022-12-31T23:59:59Z")
+                    .required(false),
+            );
+
+        let mut list = SubCommand::with_name("list").about("List all scoped API tokens");
+        list = Self::add_general_args(list);
+
+        let mut revoke = SubCommand::with_name("revoke").about("Revoke a scoped API token");
+        revoke = Self::add_general_args(revoke);
+        revoke = revoke.arg(
+            Arg::with_name("name")
+                .long("name")
+                .value_name("name")
+                .help("The name of the token to revoke")
+                .required(true),
+        );
+
+        sub = sub.subcommand(create).subcommand(list).subcommand(revoke);
+
+        app.subcommand(sub)
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn make_policy_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("policy").about("Manage the authorization policy");
+
+        let mut reload = SubCommand::with_name("reload")
+            .about("Reload the authorization policy files, keeping the current policy if they are invalid");
+        reload = Self::add_general_args(reload);
+
+        let mut explain = SubCommand::with_name("explain")
+            .about("Explain whether an actor may perform a permission, and which rule decided it");
+        explain = Self::add_general_args(explain);
+        explain = explain
+            .arg(
+                Arg::with_name("actor")
+                    .long("actor")
+                    .value_name("name")
+                    .help("The name of the actor, e.g. a user id")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("attr")
+                    .short("a")
+                    .long("attribute")
+                    .value_name("attr")
+                    .help("Specify key=value pair attributes of the actor")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("permission")
+                    .long("permission")
+                    .value_name("permission")
+                    .help("The permission to check, e.g. ROUTES_UPDATE")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("ca")
+                    .long("ca")
+                    .value_name("name")
+                    .help("The CA to check the permission for. If omitted, no CA is checked")
+                    .required(false),
+            );
+
+        sub = sub.subcommand(reload).subcommand(explain);
+
+        app.subcommand(sub)
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn make_tenants_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        fn add_name_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+            app.arg(
+                Arg::with_name("name")
+                    .long("name")
+                    .value_name("name")
+                    .help("The name of the tenant")
+                    .required(true),
+            )
+        }
+
+        fn add_cas_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+            app.arg(
+                Arg::with_name("ca")
+                    .long("ca")
+                    .value_name("name")
+                    .help("A CA owned by the tenant")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .required(false),
+            )
+        }
+
+        fn add_user_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+            app.arg(
+                Arg::with_name("user")
+                    .long("user")
+                    .value_name("id")
+                    .help("The id of the user, as used to login")
+                    .required(true),
+            )
+        }
+
+        let mut sub = SubCommand::with_name("tenants").about("Manage tenants, which limit users to a set of CAs");
+
+        let mut list = SubCommand::with_name("list").about("List all tenants");
+        list = Self::add_general_args(list);
+
+        let mut show = SubCommand::with_name("show").about("Show the CAs and users of a tenant");
+        show = Self::add_general_args(show);
+        show = add_name_arg(show);
+
+        let mut add = SubCommand::with_name("add").about("Add a tenant");
+        add = Self::add_general_args(add);
+        add = add_name_arg(add);
+        add = add_cas_arg(add);
+
+        let mut update = SubCommand::with_name("update").about("Replace the CAs of a tenant");
+        update = Self::add_general_args(update);
+        update = add_name_arg(update);
+        update = add_cas_arg(update);
+
+        let mut remove = SubCommand::with_name("remove").about("Remove a tenant");
+        remove = Self::add_general_args(remove);
+        remove = add_name_arg(remove);
+
+        let mut add_user = SubCommand::with_name("add-user").about("Bind a user to a tenant");
+        add_user = Self::add_general_args(add_user);
+        add_user = add_name_arg(add_user);
+        add_user = add_user_arg(add_user);
+
+        let mut remove_user = SubCommand::with_name("remove-user").about("Remove a user from a tenant");
+        remove_user = Self::add_general_args(remove_user);
+        remove_user = add_name_arg(remove_user);
+        remove_user = add_user_arg(remove_user);
+
+        sub = sub
+            .subcommand(list)
+            .subcommand(show)
+            .subcommand(add)
+            .subcommand(update)
+            .subcommand(remove)
+            .subcommand(add_user)
+            .subcommand(remove_user);
+
+        app.subcommand(sub)
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn make_users_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        fn add_id_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+            app.arg(
+                Arg::with_name("id")
+                    .long("id")
+                    .value_name("id")
+                    .help("The id of the user (e.g. username, email), as used to login")
+                    .required(true),
+            )
+        }
+
+        let mut sub = SubCommand::with_name("users").about("Manage local users, when auth_type is 'config-file'");
+
+        let mut list = SubCommand::with_name("list").about("List all users");
+        list = Self::add_general_args(list);
+
+        let mut add = SubCommand::with_name("add")
+            .about("Add a user, you will be prompted for a password that the user must change at the next login");
+        add = Self::add_general_args(add);
+        add = add_id_arg(add);
+        add = add.arg(
+            Arg::with_name("attr")
+                .short("a")
+                .long("attribute")
+                .value_name("attr")
+                .help("Specify key=value pair attributes to give the user")
+                .multiple(true)
+                .number_of_values(1)
+                .required(false),
+        );
+
+        let mut remove = SubCommand::with_name("remove").about("Remove a user");
+        remove = Self::add_general_args(remove);
+        remove = add_id_arg(remove);
+
+        let mut disable = SubCommand::with_name("disable").about("Disable a user, so that they can no longer login");
+        disable = Self::add_general_args(disable);
+        disable = add_id_arg(disable);
+
+        let mut enable = SubCommand::with_name("enable").about("Enable a disabled user");
+        enable = Self::add_general_args(enable);
+        enable = add_id_arg(enable);
+
+        let mut reset_password = SubCommand::with_name("reset-password")
+            .about("Reset the password of a user, who must change it at the next login");
+        reset_password = Self::add_general_args(reset_password);
+        reset_password = add_id_arg(reset_password);
+
+        let mut reset_mfa = SubCommand::with_name("reset-mfa")
+            .about("Remove the second factor of a user who lost it, so that they can enrol again");
+        reset_mfa = Self::add_general_args(reset_mfa);
+        reset_mfa = add_id_arg(reset_mfa);
+
+        sub = sub
+            .subcommand(list)
+            .subcommand(add)
+            .subcommand(remove)
+            .subcommand(disable)
+            .subcommand(enable)
+            .subcommand(reset_password)
+            .subcommand(reset_mfa);
+
+        app.subcommand(sub)
+    }
+
+    fn make_audit_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("audit").about("Manage the audit log");
+
+        let mut verify = SubCommand::with_name("verify")
+            .about("Verify the hash chain of an audit log file, locally without contacting the server");
+        verify = verify
+            .arg(
+                Arg::with_name("file")
+                    .long("file")
+                    .value_name("path")
+                    .help("The audit log file written using audit_log_type \"file\"")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name(KRILL_CLI_FORMAT_ARG)
+                    .short("f")
+                    .long(KRILL_CLI_FORMAT_ARG)
+                    .value_name("type")
+                    .help("Report format: none|json|text (default)")
+                    .required(false),
+            );
+
+        sub = sub.subcommand(verify);
 
         app.subcommand(sub)
     }
@@ -1244,6 +1985,151 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_ta_proxy_init_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("init").about("Initialize the Trust Anchor proxy");
+        sub = Self::add_general_args(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_ta_proxy_info_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("info")
+            .about("Show the Trust Anchor proxy info needed to initialize the signer (use --format json)");
+        sub = Self::add_general_args(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_ta_proxy_repo_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("repo").about("Manage the repository for the Trust Anchor proxy");
+
+        let mut request = SubCommand::with_name("request").about("Show RFC8183 Publisher Request XML");
+        request = Self::add_general_args(request);
+        sub = sub.subcommand(request);
+
+        let mut configure = SubCommand::with_name("configure").about("Configure the repository");
+        configure = Self::add_general_args(configure);
+        configure = configure.arg(
+            Arg::with_name("response")
+                .value_name("file")
+                .long("response")
+                .short("r")
+                .help("The location of the RFC8183 Publisher Response XML file")
+                .required(true),
+        );
+        sub = sub.subcommand(configure);
+
+        app.subcommand(sub)
+    }
+
+    fn make_ta_proxy_signer_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("signer").about("Manage the exchanges with the Trust Anchor signer");
+
+        let mut add = SubCommand::with_name("add").about("Add the signer, using the output of 'krillta show'");
+        add = Self::add_general_args(add);
+        add = add.arg(
+            Arg::with_name("info")
+                .value_name("file")
+                .long("info")
+                .short("i")
+                .help("The location of the signer info JSON file")
+                .required(true),
+        );
+        sub = sub.subcommand(add);
+
+        let mut make_request =
+            SubCommand::with_name("make-request").about("Create a new request for the signer (use --format json)");
+        make_request = Self::add_general_args(make_request);
+        sub = sub.subcommand(make_request);
+
+        let mut show_request =
+            SubCommand::with_name("show-request").about("Show the open request for the signer (use --format json)");
+        show_request = Self::add_general_args(show_request);
+        sub = sub.subcommand(show_request);
+
+        let mut process_response =
+            SubCommand::with_name("process-response").about("Process the response from the signer");
+        process_response = Self::add_general_args(process_response);
+        process_response = process_response.arg(
+            Arg::with_name("response")
+                .value_name("file")
+                .long("response")
+                .short("r")
+                .help("The location of the signer response JSON file")
+                .required(true),
+        );
+        sub = sub.subcommand(process_response);
+
+        app.subcommand(sub)
+    }
+
+    fn make_ta_proxy_children_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("children").about("Manage children of the Trust Anchor");
+
+        let mut add = SubCommand::with_name("add").about("Add a child to the Trust Anchor");
+        add = Self::add_general_args(add);
+        add = Self::add_child_arg(add);
+        add = Self::add_resource_args(add);
+        add = add.arg(
+            Arg::with_name("request")
+                .long("request")
+                .short("r")
+                .help("The location of the RFC8183 Child Request XML file")
+                .value_name("<XML file>")
+                .required(true),
+        );
+        sub = sub.subcommand(add);
+
+        app.subcommand(sub)
+    }
+
+    fn make_ta_proxy_history_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("history").about("Show the commands sent to the Trust Anchor proxy");
+        sub = Self::add_general_args(sub);
+        sub = Self::add_history_args(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_ta_keyroll_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("keyroll").about("Roll the key of the embedded (test) Trust Anchor");
+
+        let mut status = SubCommand::with_name("status").about("Show the status of the Trust Anchor key roll");
+        status = Self::add_general_args(status);
+        sub = sub.subcommand(status);
+
+        let mut init = SubCommand::with_name("init").about("Create a successor key and publish TAK objects");
+        init = Self::add_general_args(init);
+        sub = sub.subcommand(init);
+
+        let mut activate = SubCommand::with_name("activate")
+            .about("Activate the successor key, after the transition period since init");
+        activate = Self::add_general_args(activate);
+        sub = sub.subcommand(activate);
+
+        let mut finish = SubCommand::with_name("finish")
+            .about("Retire the predecessor key, after the transition period since activation");
+        finish = Self::add_general_args(finish);
+        sub = sub.subcommand(finish);
+
+        app.subcommand(sub)
+    }
+
+    fn make_ta_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut proxy = SubCommand::with_name("proxy").about("Manage the online proxy for an offline Trust Anchor");
+
+        proxy = Self::make_ta_proxy_init_sc(proxy);
+        proxy = Self::make_ta_proxy_info_sc(proxy);
+        proxy = Self::make_ta_proxy_repo_sc(proxy);
+        proxy = Self::make_ta_proxy_signer_sc(proxy);
+        proxy = Self::make_ta_proxy_children_sc(proxy);
+        proxy = Self::make_ta_proxy_history_sc(proxy);
+
+        let mut ta = SubCommand::with_name("ta")
+            .about("Manage an offline Trust Anchor (only needed if you operate your own TA)")
+            .subcommand(proxy);
+        ta = Self::make_ta_keyroll_sc(ta);
+
+        app.subcommand(ta)
+    }
+
     fn make_matches<'a>() -> ArgMatches<'a> {
         let mut app = App::new(KRILL_CLIENT_APP).version(KRILL_VERSION);
 
@@ -1253,6 +2139,7 @@ impl Options {
         app = Self::make_cas_show_history_sc(app);
         app = Self::make_cas_add_ca_sc(app);
         app = Self::make_cas_delete_ca_sc(app);
+        app = Self::make_cas_rename_ca_sc(app);
         app = Self::make_cas_children_sc(app);
         app = Self::make_cas_parents_sc(app);
         app = Self::make_cas_keyroll_sc(app);
@@ -1260,6 +2147,7 @@ impl Options {
         app = Self::make_cas_repo_sc(app);
         app = Self::make_cas_issues_sc(app);
         app = Self::make_pubserver_sc(app);
+        app = Self::make_ta_sc(app);
 
         #[cfg(feature = "aspa")]
         {
@@ -1277,6 +2165,16 @@ impl Options {
 
         app = Self::make_bulk_sc(app);
 
+        app = Self::make_audit_sc(app);
+
+        #[cfg(feature = "multi-user")]
+        {
+            app = Self::make_token_sc(app);
+            app = Self::make_policy_sc(app);
+            app = Self::make_tenants_sc(app);
+            app = Self::make_users_sc(app);
+        }
+
         app.get_matches()
     }
 
@@ -1408,6 +2306,18 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_rename(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let new_handle = matches.value_of("new").unwrap();
+        let new_handle = Handle::from_str(new_handle).map_err(|_| Error::InvalidHandle)?;
+
+        let command = Command::CertAuth(CaCommand::Rename(my_ca, CertAuthRename::new(new_handle)));
+
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_cas_show(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
@@ -1425,10 +2335,7 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
-    fn parse_matches_cas_history_commands(matches: &ArgMatches) -> Result<Options, Error> {
-        let general_args = GeneralArgs::from_matches(matches)?;
-        let my_ca = Self::parse_my_ca(matches)?;
-
+    fn parse_history_args(matches: &ArgMatches) -> Result<HistoryOptions, Error> {
         let mut options = HistoryOptions::default();
 
         if let Some(offset) = matches.value_of("offset") {
@@ -1458,6 +2365,14 @@ impl Options {
             options.before = Some(time);
         }
 
+        Ok(options)
+    }
+
+    fn parse_matches_cas_history_commands(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        let options = Self::parse_history_args(matches)?;
+
         let command = Command::CertAuth(CaCommand::ShowHistoryCommands(my_ca, options));
         Ok(Options::make(general_args, command))
     }
@@ -1508,8 +2423,13 @@ impl Options {
             }
         };
         let resources = Self::parse_resource_args(matches)?;
+        let template = matches.value_of("template").map(|name| name.to_string());
+        let shrink_notice_days = match matches.value_of("shrink_notice") {
+            Some(days) => Some(u32::from_str(days).map_err(|e| Error::general(&format!("invalid number: {}", e)))?),
+            None => None,
+        };
 
-        let update = UpdateChildRequest::new(id_cert, resources, None);
+        let update = UpdateChildRequest::new(id_cert, resources, None, template, shrink_notice_days);
 
         let command = Command::CertAuth(CaCommand::ChildUpdate(my_ca, child, update));
         Ok(Options::make(general_args, command))
@@ -1582,6 +2502,149 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_children_pending(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::ChildRequests(my_ca));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_approve(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let child = matches.value_of("child").unwrap();
+        let child = Handle::from_str(child).map_err(|_| Error::InvalidHandle)?;
+
+        let resources = Self::parse_resource_args(matches)?.ok_or(Error::MissingResources)?;
+
+        let command = Command::CertAuth(CaCommand::ChildRequestApprove(my_ca, child, resources));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_reject(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let child = matches.value_of("child").unwrap();
+        let child = Handle::from_str(child).map_err(|_| Error::InvalidHandle)?;
+
+        let command = Command::CertAuth(CaCommand::ChildRequestReject(my_ca, child));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_undelegated(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::ChildUndelegated(my_ca));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_shrinks(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::ChildShrinks(my_ca));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_templates_list(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::ResourceTemplatesList(my_ca));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_templates_set(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let name = matches.value_of("name").unwrap().to_string();
+        let resources = Self::parse_resource_args(matches)?.ok_or(Error::MissingResources)?;
+
+        let template = ResourceTemplate::new(name, resources);
+        let command = Command::CertAuth(CaCommand::ResourceTemplateUpdate(my_ca, template));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_templates_remove(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let name = matches.value_of("name").unwrap().to_string();
+
+        let command = Command::CertAuth(CaCommand::ResourceTemplateRemove(my_ca, name));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_templates(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            Self::parse_matches_cas_children_templates_list(m)
+        } else if let Some(m) = matches.subcommand_matches("set") {
+            Self::parse_matches_cas_children_templates_set(m)
+        } else if let Some(m) = matches.subcommand_matches("remove") {
+            Self::parse_matches_cas_children_templates_remove(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_opt_child(matches: &ArgMatches) -> Result<Option<ChildHandle>, Error> {
+        match matches.value_of("child") {
+            Some(child) => Ok(Some(Handle::from_str(child).map_err(|_| Error::InvalidHandle)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn parse_matches_cas_children_suspension_policy_set(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        let child = Self::parse_opt_child(matches)?;
+
+        let policy = if matches.is_present("exempt") {
+            ChildSuspensionPolicy::exempt()
+        } else {
+            let suspend_after_hours = matches.value_of("suspend_after_hours").unwrap();
+            let suspend_after_hours = u32::from_str(suspend_after_hours)
+                .map_err(|e| Error::general(&format!("invalid number of hours: {}", e.to_string())))?;
+
+            let warn_after_hours = match matches.value_of("warn_after_hours") {
+                Some(hours) => Some(
+                    u32::from_str(hours)
+                        .map_err(|e| Error::general(&format!("invalid number of hours: {}", e.to_string())))?,
+                ),
+                None => None,
+            };
+
+            ChildSuspensionPolicy::inactive(suspend_after_hours, warn_after_hours)
+        };
+
+        let command = Command::CertAuth(CaCommand::SuspensionPolicySet(my_ca, child, policy));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_suspension_policy_clear(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        let child = Self::parse_opt_child(matches)?;
+
+        let command = Command::CertAuth(CaCommand::SuspensionPolicyClear(my_ca, child));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_suspension_policy(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("set") {
+            Self::parse_matches_cas_children_suspension_policy_set(m)
+        } else if let Some(m) = matches.subcommand_matches("clear") {
+            Self::parse_matches_cas_children_suspension_policy_clear(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
     fn parse_matches_cas_children(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("add") {
             Self::parse_matches_cas_children_add(m)
@@ -1599,6 +2662,20 @@ impl Options {
             Self::parse_matches_cas_children_suspend(m)
         } else if let Some(m) = matches.subcommand_matches("unsuspend") {
             Self::parse_matches_cas_children_unsuspend(m)
+        } else if let Some(m) = matches.subcommand_matches("suspension-policy") {
+            Self::parse_matches_cas_children_suspension_policy(m)
+        } else if let Some(m) = matches.subcommand_matches("pending") {
+            Self::parse_matches_cas_children_pending(m)
+        } else if let Some(m) = matches.subcommand_matches("approve") {
+            Self::parse_matches_cas_children_approve(m)
+        } else if let Some(m) = matches.subcommand_matches("reject") {
+            Self::parse_matches_cas_children_reject(m)
+        } else if let Some(m) = matches.subcommand_matches("undelegated") {
+            Self::parse_matches_cas_children_undelegated(m)
+        } else if let Some(m) = matches.subcommand_matches("shrinks") {
+            Self::parse_matches_cas_children_shrinks(m)
+        } else if let Some(m) = matches.subcommand_matches("templates") {
+            Self::parse_matches_cas_children_templates(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -1674,29 +2751,84 @@ impl Options {
         }
     }
 
-    fn parse_matches_cas_keyroll_init(matches: &ArgMatches) -> Result<Options, Error> {
-        let general_args = GeneralArgs::from_matches(matches)?;
-        let my_ca = Self::parse_my_ca(matches)?;
+    fn parse_matches_cas_keyroll_init(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::KeyRollInit(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_keyroll_activate(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let force = matches.is_present("force");
+
+        let command = Command::CertAuth(CaCommand::KeyRollActivate(my_ca, force));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_keyroll_status(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::KeyRollStatus(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_keyroll_policy_set(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let max_age_days = matches.value_of("max_age_days").unwrap();
+        let max_age_days = u32::from_str(max_age_days)
+            .map_err(|e| Error::general(&format!("invalid number of days: {}", e.to_string())))?;
+
+        let staging_hours = matches.value_of("staging_hours").unwrap();
+        let staging_hours = u32::from_str(staging_hours)
+            .map_err(|e| Error::general(&format!("invalid number of hours: {}", e.to_string())))?;
+
+        let require_publication = !matches.is_present("skip_publication_check");
+
+        let policy = KeyRollPolicy::new(max_age_days, staging_hours, require_publication);
 
-        let command = Command::CertAuth(CaCommand::KeyRollInit(my_ca));
+        let command = Command::CertAuth(CaCommand::KeyRollPolicySet(my_ca, policy));
 
         Ok(Options::make(general_args, command))
     }
 
-    fn parse_matches_cas_keyroll_activate(matches: &ArgMatches) -> Result<Options, Error> {
+    fn parse_matches_cas_keyroll_policy_clear(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
 
-        let command = Command::CertAuth(CaCommand::KeyRollActivate(my_ca));
+        let command = Command::CertAuth(CaCommand::KeyRollPolicyClear(my_ca));
 
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_keyroll_policy(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("set") {
+            Self::parse_matches_cas_keyroll_policy_set(m)
+        } else if let Some(m) = matches.subcommand_matches("clear") {
+            Self::parse_matches_cas_keyroll_policy_clear(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
     fn parse_matches_cas_keyroll(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("init") {
             Self::parse_matches_cas_keyroll_init(m)
         } else if let Some(m) = matches.subcommand_matches("activate") {
             Self::parse_matches_cas_keyroll_activate(m)
+        } else if let Some(m) = matches.subcommand_matches("status") {
+            Self::parse_matches_cas_keyroll_status(m)
+        } else if let Some(m) = matches.subcommand_matches("policy") {
+            Self::parse_matches_cas_keyroll_policy(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -1765,6 +2897,70 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_roa_file_format(matches: &ArgMatches) -> Result<RoaFileFormat, Error> {
+        let format = matches.value_of("file_format").unwrap(); // required argument
+        Ok(RoaFileFormat::from_str(format)?)
+    }
+
+    fn parse_matches_cas_routes_import(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        let format = Self::parse_roa_file_format(matches)?;
+
+        let path = matches.value_of("file").unwrap(); // required argument
+        let bytes = Self::read_file_arg(path)?;
+        let content = String::from_utf8(bytes.to_vec()).map_err(|_| Error::general("File is not valid UTF-8"))?;
+        let definitions = format.parse(&content)?;
+
+        let mode = match (matches.is_present("preview"), matches.is_present("dryrun")) {
+            (true, true) => return Err(Error::general("You cannot use both --preview and --dryrun")),
+            (true, false) => RoaImportMode::Preview,
+            (false, true) => RoaImportMode::DryRun,
+            (false, false) => RoaImportMode::Try,
+        };
+
+        let command = Command::CertAuth(CaCommand::RouteAuthorizationsImport(my_ca, definitions, mode));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_routes_export(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        let format = Self::parse_roa_file_format(matches)?;
+
+        let command = Command::CertAuth(CaCommand::RouteAuthorizationsExport(my_ca, format));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_routes_history(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let prefix = match matches.value_of("prefix") {
+            Some(prefix) => Some(TypedPrefix::from_str(prefix)?),
+            None => None,
+        };
+
+        let command = Command::CertAuth(CaCommand::RouteAuthorizationsHistory(my_ca, prefix));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_routes_diff(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let from = matches.value_of("from").unwrap(); // required argument
+        let from = Time::from_str(from).map_err(|e| Error::general(&format!("invalid date format: {}", e)))?;
+
+        let to = match matches.value_of("to") {
+            Some(to) => Some(Time::from_str(to).map_err(|e| Error::general(&format!("invalid date format: {}", e)))?),
+            None => None,
+        };
+
+        let command = Command::CertAuth(CaCommand::RouteAuthorizationsDiff(my_ca, from, to));
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_cas_routes_bgp_full(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
@@ -1809,6 +3005,14 @@ impl Options {
             Self::parse_matches_cas_routes_update(m)
         } else if let Some(m) = matches.subcommand_matches("bgp") {
             Self::parse_matches_cas_routes_bgp(m)
+        } else if let Some(m) = matches.subcommand_matches("history") {
+            Self::parse_matches_cas_routes_history(m)
+        } else if let Some(m) = matches.subcommand_matches("diff") {
+            Self::parse_matches_cas_routes_diff(m)
+        } else if let Some(m) = matches.subcommand_matches("import") {
+            Self::parse_matches_cas_routes_import(m)
+        } else if let Some(m) = matches.subcommand_matches("export") {
+            Self::parse_matches_cas_routes_export(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -1940,6 +3144,51 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_repo_mirrors_list(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::RepoMirrorsList(my_ca));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_repo_mirrors_add(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let name = matches.value_of("name").unwrap().to_string();
+
+        let path = matches.value_of("response").unwrap();
+        let bytes = Self::read_file_arg(path)?;
+        let response = rfc8183::RepositoryResponse::validate(bytes.as_ref())?;
+
+        let repo_contact = RepositoryContact::new(response);
+        let command = Command::CertAuth(CaCommand::RepoMirrorAdd(my_ca, name, repo_contact));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_repo_mirrors_remove(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let name = matches.value_of("name").unwrap().to_string();
+
+        let command = Command::CertAuth(CaCommand::RepoMirrorRemove(my_ca, name));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_repo_mirrors(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("list") {
+            Self::parse_matches_cas_repo_mirrors_list(m)
+        } else if let Some(m) = matches.subcommand_matches("add") {
+            Self::parse_matches_cas_repo_mirrors_add(m)
+        } else if let Some(m) = matches.subcommand_matches("remove") {
+            Self::parse_matches_cas_repo_mirrors_remove(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
     fn parse_matches_cas_repo(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("request") {
             Self::parse_matches_cas_repo_request(m)
@@ -1949,6 +3198,8 @@ impl Options {
             Self::parse_matches_cas_repo_status(m)
         } else if let Some(m) = matches.subcommand_matches("configure") {
             Self::parse_matches_cas_repo_configure(m)
+        } else if let Some(m) = matches.subcommand_matches("mirrors") {
+            Self::parse_matches_cas_repo_mirrors(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -2114,11 +3365,178 @@ impl Options {
             let general_args = GeneralArgs::from_matches(m)?;
             let command = Command::Bulk(BulkCaCommand::Sync);
             Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("simulate") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let path = m.value_of("request").unwrap();
+            let bytes = Self::read_file_arg(path)?;
+            let request: BgpSimulationRequest = serde_json::from_slice(&bytes)
+                .map_err(|e| Error::GeneralArgumentError(format!("Invalid simulation request: {}", e)))?;
+            let command = Command::Bulk(BulkCaCommand::Simulate(request));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn parse_matches_token(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("create") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let name = m.value_of("name").unwrap().to_string();
+            let permissions = m.values_of("permission").unwrap().map(|p| p.to_string()).collect();
+
+            let cas = match m.values_of("ca") {
+                Some(cas) => Some(
+                    cas.map(|ca| Handle::from_str(ca).map_err(|_| Error::InvalidHandle))
+                        .collect::<Result<Vec<_>, _>>()?,
+                ),
+                None => None,
+            };
+
+            let expires = match m.value_of("expires") {
+                Some(expires) => Some(
+                    Time::from_str(expires)
+                        .map_err(|e| Error::general(&format!("invalid date format: {}", e)))?
+                        .into(),
+                ),
+                None => None,
+            };
+
+            let request = ApiTokenRequest::new(name, permissions, cas, expires);
+            let command = Command::Token(TokenCommand::Create(request));
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("list") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Token(TokenCommand::List);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("revoke") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let name = m.value_of("name").unwrap().to_string();
+            let command = Command::Token(TokenCommand::Revoke(name));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn parse_matches_policy(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("reload") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::Policy(PolicyCommand::Reload);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("explain") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let actor = m.value_of("actor").unwrap().to_string();
+            let permission = m.value_of("permission").unwrap().to_string();
+
+            let mut attributes = HashMap::new();
+            if let Some(attr_iter) = m.values_of("attr") {
+                for attr in attr_iter {
+                    let mut iter = attr.split('=');
+                    let k = iter.next().ok_or_else(|| {
+                        Error::general(&format!("attribute '{}' must be of the form key=value", attr))
+                    })?;
+                    let v = iter.next().ok_or_else(|| {
+                        Error::general(&format!("attribute '{}' must be of the form key=value", attr))
+                    })?;
+                    attributes.insert(k.to_string(), v.to_string());
+                }
+            }
+
+            let ca = match m.value_of("ca") {
+                Some(ca) => Some(Handle::from_str(ca).map_err(|_| Error::InvalidHandle)?),
+                None => None,
+            };
+
+            let request = PolicyExplainRequest::new(actor, attributes, permission, ca);
+            let command = Command::Policy(PolicyCommand::Explain(request));
+            Ok(Options::make(general_args, command))
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
     }
 
+    #[cfg(feature = "multi-user")]
+    fn parse_matches_tenants(matches: &ArgMatches) -> Result<Options, Error> {
+        fn definition(m: &ArgMatches) -> Result<TenantDefinition, Error> {
+            let name = m.value_of("name").unwrap().to_string();
+            let cas = match m.values_of("ca") {
+                Some(cas) => cas
+                    .map(|ca| Handle::from_str(ca).map_err(|_| Error::InvalidHandle))
+                    .collect::<Result<Vec<_>, _>>()?,
+                None => vec![],
+            };
+            Ok(TenantDefinition::new(name, cas))
+        }
+
+        let (m, command) = if let Some(m) = matches.subcommand_matches("list") {
+            (m, TenantCommand::List)
+        } else if let Some(m) = matches.subcommand_matches("show") {
+            (m, TenantCommand::Show(m.value_of("name").unwrap().to_string()))
+        } else if let Some(m) = matches.subcommand_matches("add") {
+            (m, TenantCommand::Add(definition(m)?))
+        } else if let Some(m) = matches.subcommand_matches("update") {
+            (m, TenantCommand::Update(definition(m)?))
+        } else if let Some(m) = matches.subcommand_matches("remove") {
+            (m, TenantCommand::Remove(m.value_of("name").unwrap().to_string()))
+        } else if let Some(m) = matches.subcommand_matches("add-user") {
+            let name = m.value_of("name").unwrap().to_string();
+            let user = m.value_of("user").unwrap().to_string();
+            (m, TenantCommand::AddUser(name, user))
+        } else if let Some(m) = matches.subcommand_matches("remove-user") {
+            let name = m.value_of("name").unwrap().to_string();
+            let user = m.value_of("user").unwrap().to_string();
+            (m, TenantCommand::RemoveUser(name, user))
+        } else {
+            return Err(Error::UnrecognizedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::Tenants(command)))
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn parse_matches_users(matches: &ArgMatches) -> Result<Options, Error> {
+        let id = |m: &ArgMatches| m.value_of("id").unwrap().to_string();
+
+        let (m, command) = if let Some(m) = matches.subcommand_matches("list") {
+            (m, UserCommand::List)
+        } else if let Some(m) = matches.subcommand_matches("add") {
+            let mut attributes = HashMap::new();
+            if let Some(attr_iter) = m.values_of("attr") {
+                for attr in attr_iter {
+                    let mut iter = attr.splitn(2, '=');
+                    match (iter.next(), iter.next()) {
+                        (Some(k), Some(v)) => attributes.insert(k.to_string(), v.to_string()),
+                        _ => {
+                            return Err(Error::general(&format!(
+                                "attribute '{}' must be of the form key=value",
+                                attr
+                            )))
+                        }
+                    };
+                }
+            }
+            (m, UserCommand::Add(id(m), attributes))
+        } else if let Some(m) = matches.subcommand_matches("remove") {
+            (m, UserCommand::Remove(id(m)))
+        } else if let Some(m) = matches.subcommand_matches("disable") {
+            (m, UserCommand::Disable(id(m)))
+        } else if let Some(m) = matches.subcommand_matches("enable") {
+            (m, UserCommand::Enable(id(m)))
+        } else if let Some(m) = matches.subcommand_matches("reset-password") {
+            (m, UserCommand::ResetPassword(id(m)))
+        } else if let Some(m) = matches.subcommand_matches("reset-mfa") {
+            (m, UserCommand::ResetMfa(id(m)))
+        } else {
+            return Err(Error::UnrecognizedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::Users(command)))
+    }
+
     fn parse_matches_health(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let command = Command::Health;
@@ -2256,6 +3674,139 @@ impl Options {
         }
     }
 
+    fn parse_matches_ta_proxy_repo(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("request") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::TrustAnchor(TrustAnchorCommand::ProxyRepoRequest);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("configure") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let bytes = Self::read_file_arg(m.value_of("response").unwrap())?;
+            let response = rfc8183::RepositoryResponse::validate(bytes.as_ref())?;
+            let command =
+                Command::TrustAnchor(TrustAnchorCommand::ProxyRepoConfigure(RepositoryContact::new(response)));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_ta_proxy_signer(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("add") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let bytes = Self::read_file_arg(m.value_of("info").unwrap())?;
+            let info = serde_json::from_slice(&bytes)
+                .map_err(|e| Error::GeneralArgumentError(format!("Invalid signer info: {}", e)))?;
+            let command = Command::TrustAnchor(TrustAnchorCommand::ProxySignerAdd(info));
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("make-request") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::TrustAnchor(TrustAnchorCommand::ProxySignerMakeRequest);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("show-request") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::TrustAnchor(TrustAnchorCommand::ProxySignerShowRequest);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("process-response") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let bytes = Self::read_file_arg(m.value_of("response").unwrap())?;
+            let response = serde_json::from_slice(&bytes)
+                .map_err(|e| Error::GeneralArgumentError(format!("Invalid signer response: {}", e)))?;
+            let command = Command::TrustAnchor(TrustAnchorCommand::ProxySignerProcessResponse(response));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_ta_proxy_children(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("add") {
+            let general_args = GeneralArgs::from_matches(m)?;
+
+            let bytes = Self::read_file_arg(m.value_of("request").unwrap())?;
+            let child_request = rfc8183::ChildRequest::validate(bytes.as_ref())?;
+
+            let child = m.value_of("child").unwrap();
+            let child = Handle::from_str(child).map_err(|_| Error::InvalidHandle)?;
+
+            let resources = Self::parse_resource_args(m)?.ok_or(Error::MissingResources)?;
+
+            let (_, _, id_cert) = child_request.unpack();
+            let add_child_request = AddChildRequest::new(child, resources, id_cert);
+            let command = Command::TrustAnchor(TrustAnchorCommand::ProxyChildAdd(add_child_request));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_ta_proxy(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("init") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::TrustAnchor(TrustAnchorCommand::ProxyInit);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("info") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let command = Command::TrustAnchor(TrustAnchorCommand::ProxyInfo);
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("repo") {
+            Self::parse_matches_ta_proxy_repo(m)
+        } else if let Some(m) = matches.subcommand_matches("signer") {
+            Self::parse_matches_ta_proxy_signer(m)
+        } else if let Some(m) = matches.subcommand_matches("children") {
+            Self::parse_matches_ta_proxy_children(m)
+        } else if let Some(m) = matches.subcommand_matches("history") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let options = Self::parse_history_args(m)?;
+            let command = Command::TrustAnchor(TrustAnchorCommand::ProxyHistory(options));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_ta_keyroll(matches: &ArgMatches) -> Result<Options, Error> {
+        let (command, m) = if let Some(m) = matches.subcommand_matches("status") {
+            (TrustAnchorCommand::KeyRollStatus, m)
+        } else if let Some(m) = matches.subcommand_matches("init") {
+            (TrustAnchorCommand::KeyRollInit, m)
+        } else if let Some(m) = matches.subcommand_matches("activate") {
+            (TrustAnchorCommand::KeyRollActivate, m)
+        } else if let Some(m) = matches.subcommand_matches("finish") {
+            (TrustAnchorCommand::KeyRollFinish, m)
+        } else {
+            return Err(Error::UnrecognizedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::TrustAnchor(command)))
+    }
+
+    fn parse_matches_ta(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("proxy") {
+            Self::parse_matches_ta_proxy(m)
+        } else if let Some(m) = matches.subcommand_matches("keyroll") {
+            Self::parse_matches_ta_keyroll(m)
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
+    fn parse_matches_audit(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("verify") {
+            let mut general_args = GeneralArgs::default();
+            if let Some(fmt_str) = m.value_of(KRILL_CLI_FORMAT_ARG) {
+                general_args.format = ReportFormat::from_str(fmt_str)?;
+            }
+
+            let file = PathBuf::from(m.value_of("file").unwrap());
+            let command = Command::Audit(AuditCommand::Verify(file));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognizedSubCommand)
+        }
+    }
+
     fn parse_matches_pubserver(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("publishers") {
             Self::parse_matches_publishers(m)
@@ -2267,14 +3818,34 @@ impl Options {
     }
 
     fn parse_matches(matches: ArgMatches) -> Result<Options, Error> {
+        #[cfg(feature = "multi-user")]
+        {
+            if let Some(m) = matches.subcommand_matches("token") {
+                return Self::parse_matches_token(m);
+            }
+            if let Some(m) = matches.subcommand_matches("policy") {
+                return Self::parse_matches_policy(m);
+            }
+            if let Some(m) = matches.subcommand_matches("tenants") {
+                return Self::parse_matches_tenants(m);
+            }
+            if let Some(m) = matches.subcommand_matches("users") {
+                return Self::parse_matches_users(m);
+            }
+        }
+
         if let Some(m) = matches.subcommand_matches("config") {
             Self::parse_matches_config(m)
+        } else if let Some(m) = matches.subcommand_matches("audit") {
+            Self::parse_matches_audit(m)
         } else if let Some(m) = matches.subcommand_matches("list") {
             Self::parse_matches_cas_list(m)
         } else if let Some(m) = matches.subcommand_matches("add") {
             Self::parse_matches_cas_add(m)
         } else if let Some(m) = matches.subcommand_matches("delete") {
             Self::parse_matches_cas_delete(m)
+        } else if let Some(m) = matches.subcommand_matches("rename") {
+            Self::parse_matches_cas_rename(m)
         } else if let Some(m) = matches.subcommand_matches("show") {
             Self::parse_matches_cas_show(m)
         } else if let Some(m) = matches.subcommand_matches("history") {
@@ -2303,6 +3874,8 @@ impl Options {
             Self::parse_matches_info(m)
         } else if let Some(m) = matches.subcommand_matches("pubserver") {
             Self::parse_matches_pubserver(m)
+        } else if let Some(m) = matches.subcommand_matches("ta") {
+            Self::parse_matches_ta(m)
         } else {
             Err(Error::UnrecognizedSubCommand)
         }
@@ -2323,23 +3896,37 @@ pub enum Command {
     Bulk(BulkCaCommand),
     CertAuth(CaCommand),
     PubServer(PubServerCommand),
+    TrustAnchor(TrustAnchorCommand),
     Init(KrillInitDetails),
     #[cfg(feature = "multi-user")]
     User(KrillUserDetails),
+    #[cfg(feature = "multi-user")]
+    Token(TokenCommand),
+    #[cfg(feature = "multi-user")]
+    Policy(PolicyCommand),
+    #[cfg(feature = "multi-user")]
+    Tenants(TenantCommand),
+    #[cfg(feature = "multi-user")]
+    Users(UserCommand),
+    Audit(AuditCommand),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[allow(clippy::large_enum_variant)]
 pub enum CaCommand {
-    Init(CertAuthInit), // Initialize a CA
-    UpdateId(Handle),   // Update CA id
-    Delete(Handle),     // Delete the CA -> let it withdraw and request revocation as well
+    Init(CertAuthInit),             // Initialize a CA
+    UpdateId(Handle),               // Update CA id
+    Delete(Handle),                 // Delete the CA -> let it withdraw and request revocation as well
+    Rename(Handle, CertAuthRename), // Rename the CA and report remaining steps
 
     // Publishing
     RepoPublisherRequest(Handle), // Get the RFC8183 publisher request
     RepoDetails(Handle),
     RepoUpdate(Handle, RepositoryContact),
     RepoStatus(Handle),
+    RepoMirrorsList(Handle),
+    RepoMirrorAdd(Handle, RepositoryMirrorName, RepositoryContact),
+    RepoMirrorRemove(Handle, RepositoryMirrorName),
 
     // Parents (to this CA)
     ChildRequest(Handle), // Get the RFC8183 child request
@@ -2356,16 +3943,36 @@ pub enum CaCommand {
     ChildUpdate(Handle, ChildHandle, UpdateChildRequest),
     ChildDelete(Handle, ChildHandle),
     ChildConnections(Handle),
+    ChildRequests(Handle), // List pending requests submitted by prospective children
+    ChildRequestApprove(Handle, ChildHandle, ResourceSet),
+    ChildRequestReject(Handle, ChildHandle),
+    ChildUndelegated(Handle), // Show resources not delegated to any child
+    ChildShrinks(Handle),     // Show scheduled resource removals for children
+    ResourceTemplatesList(Handle),
+    ResourceTemplateUpdate(Handle, ResourceTemplate),
+    ResourceTemplateRemove(Handle, ResourceTemplateName),
 
     // Key Management
     KeyRollInit(Handle),
-    KeyRollActivate(Handle),
+    KeyRollActivate(Handle, bool), // bool: force
+    KeyRollStatus(Handle),
+    KeyRollPolicySet(Handle, KeyRollPolicy),
+    KeyRollPolicyClear(Handle),
+
+    // Set or clear the suspension policy for inactive children of a CA, or of
+    // a single child
+    SuspensionPolicySet(Handle, Option<ChildHandle>, ChildSuspensionPolicy),
+    SuspensionPolicyClear(Handle, Option<ChildHandle>),
 
     // Authorizations
     RouteAuthorizationsList(Handle),
     RouteAuthorizationsUpdate(Handle, RoaDefinitionUpdates),
     RouteAuthorizationsTryUpdate(Handle, RoaDefinitionUpdates),
     RouteAuthorizationsDryRunUpdate(Handle, RoaDefinitionUpdates),
+    RouteAuthorizationsHistory(Handle, Option<TypedPrefix>),
+    RouteAuthorizationsDiff(Handle, Time, Option<Time>),
+    RouteAuthorizationsImport(Handle, Vec<RoaDefinition>, RoaImportMode),
+    RouteAuthorizationsExport(Handle, RoaFileFormat),
     BgpAnalysisFull(Handle),
     BgpAnalysisSuggest(Handle, Option<ResourceSet>),
 
@@ -2392,6 +3999,17 @@ pub enum CaCommand {
     List,
 }
 
+/// How to apply the authorizations imported from a file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RoaImportMode {
+    /// Only show the updates to current authorizations.
+    Preview,
+    /// Show the BGP analysis for the updates.
+    DryRun,
+    /// Apply the updates, unless they would result in invalids.
+    Try,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct HistoryOptions {
     pub offset: u64,
@@ -2433,6 +4051,54 @@ pub enum BulkCaCommand {
     Refresh,
     Publish,
     Sync,
+    Simulate(BgpSimulationRequest),
+}
+
+#[cfg(feature = "multi-user")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TokenCommand {
+    Create(ApiTokenRequest),
+    List,
+    Revoke(String),
+}
+
+#[cfg(feature = "multi-user")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PolicyCommand {
+    Reload,
+    Explain(PolicyExplainRequest),
+}
+
+#[cfg(feature = "multi-user")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TenantCommand {
+    List,
+    Show(String),
+    Add(TenantDefinition),
+    Update(TenantDefinition),
+    Remove(String),
+    AddUser(String, String),
+    RemoveUser(String, String),
+}
+
+/// Commands to manage local users. Passwords are not given as arguments, so
+/// that they do not end up in the shell history. Instead the client prompts
+/// for them.
+#[cfg(feature = "multi-user")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UserCommand {
+    List,
+    Add(String, HashMap<String, String>),
+    Remove(String),
+    Disable(String),
+    Enable(String),
+    ResetPassword(String),
+    ResetMfa(String),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AuditCommand {
+    Verify(PathBuf),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -2530,6 +4196,25 @@ pub enum PubServerCommand {
     RepositoryClear,
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum TrustAnchorCommand {
+    ProxyInit,
+    ProxyInfo,
+    ProxyRepoRequest,
+    ProxyRepoConfigure(RepositoryContact),
+    ProxySignerAdd(TrustAnchorSignerInfo),
+    ProxySignerMakeRequest,
+    ProxySignerShowRequest,
+    ProxySignerProcessResponse(TrustAnchorSignedResponse),
+    ProxyChildAdd(AddChildRequest),
+    ProxyHistory(HistoryOptions),
+    KeyRollStatus,
+    KeyRollInit,
+    KeyRollActivate,
+    KeyRollFinish,
+}
+
 //------------ Error ---------------------------------------------------------
 
 #[derive(Debug)]
@@ -6,15 +6,20 @@ use serde::Serialize;
 use crate::{
     commons::{
         api::{
-            AllCertAuthIssues, AspaDefinitionList, CaCommandDetails, CaRepoDetails, CertAuthInfo, CertAuthIssues,
-            CertAuthList, ChildCaInfo, ChildrenConnectionStats, CommandHistory, ParentCaContact, ParentStatuses,
-            PublisherDetails, PublisherList, RepoStatus, RoaDefinitions, RtaList, RtaPrepResponse, ServerInfo,
+            AllCertAuthIssues, ApiTokenCreated, ApiTokenList, AspaDefinitionList, AuditLogVerification,
+            CaCommandDetails, CaRepoDetails, CertAuthInfo, CertAuthIssues, CertAuthList, CertAuthRenameReport,
+            ChildCaInfo, ChildRequestList, ChildShrinkList, ChildrenConnectionStats, CommandHistory, KeyRollStatus,
+            LocalUserInfo, LocalUserList, ParentCaContact, ParentStatuses, PolicyExplanation, PublisherDetails,
+            PublisherList, RepoStatus, RepositoryMirrorList, ResourceTemplateList, RoaDefinitionUpdates,
+            RoaDefinitions, RoaHistory, RtaList, RtaPrepResponse, ServerInfo, TaKeyRollStatus, TenantInfo, TenantList,
+            UndelegatedResources,
         },
-        bgp::{BgpAnalysisAdvice, BgpAnalysisReport, BgpAnalysisSuggestion},
+        bgp::{BgpAnalysisAdvice, BgpAnalysisReport, BgpAnalysisSuggestion, BgpSimulationReport},
         remote::{api::ClientInfos, rfc8183},
     },
     daemon::ca::ResourceTaggedAttestation,
     pubd::RepoStats,
+    ta::{TrustAnchorProxyInfo, TrustAnchorSignedRequest},
 };
 
 //------------ ApiResponse ---------------------------------------------------
@@ -30,12 +35,16 @@ pub enum ApiResponse {
     CertAuthHistory(CommandHistory),
     CertAuthAction(CaCommandDetails),
     CertAuths(CertAuthList),
+    CertAuthRenamed(CertAuthRenameReport),
 
     // ROA related
     RouteAuthorizations(RoaDefinitions),
+    RouteAuthorizationsHistory(RoaHistory),
+    RouteAuthorizationsDiff(RoaDefinitionUpdates),
     BgpAnalysisAdvice(BgpAnalysisAdvice),
     BgpAnalysisFull(BgpAnalysisReport),
     BgpAnalysisSuggestions(BgpAnalysisSuggestion),
+    BgpSimulation(BgpSimulationReport),
 
     // ASPA related
     AspaDefinitions(AspaDefinitionList),
@@ -45,6 +54,10 @@ pub enum ApiResponse {
 
     ChildInfo(ChildCaInfo),
     ChildrenStats(ChildrenConnectionStats),
+    ChildRequests(ChildRequestList),
+    ChildShrinks(ChildShrinkList),
+    ResourceTemplates(ResourceTemplateList),
+    UndelegatedResources(UndelegatedResources),
 
     PublisherDetails(PublisherDetails),
     PublisherList(PublisherList),
@@ -57,6 +70,10 @@ pub enum ApiResponse {
 
     RepoDetails(CaRepoDetails),
     RepoStatus(RepoStatus),
+    RepoMirrors(RepositoryMirrorList),
+
+    KeyRollStatus(KeyRollStatus),
+    TaKeyRollStatus(TaKeyRollStatus),
 
     CertAuthIssues(CertAuthIssues),
     AllCertAuthIssues(AllCertAuthIssues),
@@ -65,6 +82,21 @@ pub enum ApiResponse {
     RtaMultiPrep(RtaPrepResponse),
     Rta(ResourceTaggedAttestation),
 
+    ApiTokens(ApiTokenList),
+    ApiTokenCreated(ApiTokenCreated),
+
+    PolicyExplanation(PolicyExplanation),
+
+    Tenants(TenantList),
+    Tenant(TenantInfo),
+    Users(LocalUserList),
+    User(LocalUserInfo),
+
+    AuditLogVerification(AuditLogVerification),
+
+    TrustAnchorProxyInfo(TrustAnchorProxyInfo),
+    TrustAnchorSignedRequest(TrustAnchorSignedRequest),
+
     Empty,               // Typically a successful post just gets an empty 200 response
     GenericBody(String), // For when the server echos Json to a successful post
 }
@@ -81,17 +113,25 @@ impl ApiResponse {
                 ApiResponse::CertAuthInfo(info) => Ok(Some(info.report(fmt)?)),
                 ApiResponse::CertAuthHistory(history) => Ok(Some(history.report(fmt)?)),
                 ApiResponse::CertAuthAction(details) => Ok(Some(details.report(fmt)?)),
+                ApiResponse::CertAuthRenamed(report) => Ok(Some(report.report(fmt)?)),
                 ApiResponse::CertAuthIssues(issues) => Ok(Some(issues.report(fmt)?)),
                 ApiResponse::AllCertAuthIssues(issues) => Ok(Some(issues.report(fmt)?)),
                 ApiResponse::RouteAuthorizations(definitions) => Ok(Some(definitions.report(fmt)?)),
+                ApiResponse::RouteAuthorizationsHistory(history) => Ok(Some(history.report(fmt)?)),
+                ApiResponse::RouteAuthorizationsDiff(diff) => Ok(Some(diff.report(fmt)?)),
                 ApiResponse::BgpAnalysisAdvice(analysis) => Ok(Some(analysis.report(fmt)?)),
                 ApiResponse::BgpAnalysisFull(table) => Ok(Some(table.report(fmt)?)),
                 ApiResponse::BgpAnalysisSuggestions(suggestions) => Ok(Some(suggestions.report(fmt)?)),
+                ApiResponse::BgpSimulation(report) => Ok(Some(report.report(fmt)?)),
                 ApiResponse::AspaDefinitions(definitions) => Ok(Some(definitions.report(fmt)?)),
                 ApiResponse::ParentCaContact(contact) => Ok(Some(contact.report(fmt)?)),
                 ApiResponse::ParentStatuses(statuses) => Ok(Some(statuses.report(fmt)?)),
                 ApiResponse::ChildInfo(info) => Ok(Some(info.report(fmt)?)),
                 ApiResponse::ChildrenStats(stats) => Ok(Some(stats.report(fmt)?)),
+                ApiResponse::ChildRequests(list) => Ok(Some(list.report(fmt)?)),
+                ApiResponse::ChildShrinks(list) => Ok(Some(list.report(fmt)?)),
+                ApiResponse::ResourceTemplates(list) => Ok(Some(list.report(fmt)?)),
+                ApiResponse::UndelegatedResources(undelegated) => Ok(Some(undelegated.report(fmt)?)),
                 ApiResponse::PublisherList(list) => Ok(Some(list.report(fmt)?)),
                 ApiResponse::PublisherDetails(details) => Ok(Some(details.report(fmt)?)),
                 ApiResponse::RepoStats(stats) => Ok(Some(stats.report(fmt)?)),
@@ -101,9 +141,22 @@ impl ApiResponse {
                 ApiResponse::Rfc8183RepositoryResponse(res) => Ok(Some(res.report(fmt)?)),
                 ApiResponse::RepoDetails(details) => Ok(Some(details.report(fmt)?)),
                 ApiResponse::RepoStatus(status) => Ok(Some(status.report(fmt)?)),
+                ApiResponse::RepoMirrors(mirrors) => Ok(Some(mirrors.report(fmt)?)),
+                ApiResponse::KeyRollStatus(status) => Ok(Some(status.report(fmt)?)),
+                ApiResponse::TaKeyRollStatus(status) => Ok(Some(status.report(fmt)?)),
                 ApiResponse::Rta(rta) => Ok(Some(rta.report(fmt)?)),
                 ApiResponse::RtaList(list) => Ok(Some(list.report(fmt)?)),
                 ApiResponse::RtaMultiPrep(res) => Ok(Some(res.report(fmt)?)),
+                ApiResponse::ApiTokens(list) => Ok(Some(list.report(fmt)?)),
+                ApiResponse::ApiTokenCreated(created) => Ok(Some(created.report(fmt)?)),
+                ApiResponse::PolicyExplanation(explanation) => Ok(Some(explanation.report(fmt)?)),
+                ApiResponse::Tenants(list) => Ok(Some(list.report(fmt)?)),
+                ApiResponse::Tenant(tenant) => Ok(Some(tenant.report(fmt)?)),
+                ApiResponse::Users(list) => Ok(Some(list.report(fmt)?)),
+                ApiResponse::User(user) => Ok(Some(user.report(fmt)?)),
+                ApiResponse::AuditLogVerification(verification) => Ok(Some(verification.report(fmt)?)),
+                ApiResponse::TrustAnchorProxyInfo(info) => Ok(Some(info.report(fmt)?)),
+                ApiResponse::TrustAnchorSignedRequest(request) => Ok(Some(request.report(fmt)?)),
                 ApiResponse::GenericBody(body) => Ok(Some(body.clone())),
                 ApiResponse::Empty => Ok(None),
             }
@@ -176,8 +229,14 @@ trait Report: Serialize + ToString {
 
 impl Report for CertAuthList {}
 impl Report for CertAuthInfo {}
+impl Report for CertAuthRenameReport {}
 
 impl Report for ChildCaInfo {}
+impl Report for ChildRequestList {}
+impl Report for ChildShrinkList {}
+impl Report for ResourceTemplateList {}
+impl Report for RepositoryMirrorList {}
+impl Report for UndelegatedResources {}
 
 impl Report for ParentCaContact {}
 impl Report for ParentStatuses {}
@@ -199,16 +258,22 @@ impl Report for rfc8183::ChildRequest {}
 impl Report for rfc8183::PublisherRequest {}
 
 impl Report for RoaDefinitions {}
+impl Report for RoaHistory {}
+impl Report for RoaDefinitionUpdates {}
 
 impl Report for BgpAnalysisAdvice {}
 impl Report for BgpAnalysisReport {}
 impl Report for BgpAnalysisSuggestion {}
+impl Report for BgpSimulationReport {}
 
 impl Report for AspaDefinitionList {}
 
 impl Report for CaRepoDetails {}
 impl Report for RepoStatus {}
 
+impl Report for KeyRollStatus {}
+impl Report for TaKeyRollStatus {}
+
 impl Report for CertAuthIssues {}
 
 impl Report for AllCertAuthIssues {}
@@ -218,3 +283,18 @@ impl Report for ServerInfo {}
 impl Report for ResourceTaggedAttestation {}
 impl Report for RtaList {}
 impl Report for RtaPrepResponse {}
+
+impl Report for ApiTokenList {}
+impl Report for ApiTokenCreated {}
+
+impl Report for PolicyExplanation {}
+
+impl Report for TenantList {}
+impl Report for TenantInfo {}
+impl Report for LocalUserList {}
+impl Report for LocalUserInfo {}
+
+impl Report for AuditLogVerification {}
+
+impl Report for TrustAnchorProxyInfo {}
+impl Report for TrustAnchorSignedRequest {}
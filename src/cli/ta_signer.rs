@@ -0,0 +1,342 @@
+//! Support for the `krillta` command line tool, which manages an offline
+//! Trust Anchor signer in a local data directory.
+use std::{env, fmt, path::PathBuf, str::FromStr};
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde::Serialize;
+
+use rpki::uri;
+
+use crate::{
+    cli::report::{ReportError, ReportFormat},
+    commons::{api::CommandHistoryCriteria, error::KrillIoError, util::file},
+    constants::*,
+    daemon::config::IssuanceTimingConfig,
+    ta::{TrustAnchorProxyInfo, TrustAnchorSignedRequest, TrustAnchorSignerManager},
+};
+
+const KRILL_TA_DATA_ENV: &str = "KRILL_TA_DATA";
+const KRILL_TA_DATA_ARG: &str = "data";
+
+//------------ TrustAnchorSignerOptions --------------------------------------
+
+#[derive(Clone, Debug)]
+pub struct TrustAnchorSignerOptions {
+    pub data_dir: PathBuf,
+    pub format: ReportFormat,
+    pub command: TrustAnchorSignerCommand,
+}
+
+impl TrustAnchorSignerOptions {
+    pub fn from_args() -> Result<Self, Error> {
+        let matches = Self::make_matches();
+        Self::parse_matches(matches)
+    }
+
+    fn add_general_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name(KRILL_TA_DATA_ARG)
+                .short("d")
+                .long(KRILL_TA_DATA_ARG)
+                .value_name("path")
+                .help("Data directory for the signer. Or set env: KRILL_TA_DATA")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("format")
+                .short("f")
+                .long("format")
+                .value_name("type")
+                .help("Report format: none|json|text (default)")
+                .required(false),
+        )
+    }
+
+    fn make_matches<'a>() -> ArgMatches<'a> {
+        let mut app = App::new(KRILL_TA_SIGNER_APP).version(KRILL_VERSION);
+
+        let mut init = SubCommand::with_name("init").about("Initialize the Trust Anchor signer");
+        init = Self::add_general_args(init);
+        init = init
+            .arg(
+                Arg::with_name("proxy_info")
+                    .long("proxy-info")
+                    .value_name("file")
+                    .help("JSON file with the proxy info, see 'krillc ta proxy info --format json'")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("tal_https")
+                    .long("tal-https")
+                    .value_name("HTTPS URI")
+                    .help("HTTPS URI where the TA certificate will be published. May be repeated.")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("tal_rsync")
+                    .long("tal-rsync")
+                    .value_name("rsync URI")
+                    .help("Rsync URI where the TA certificate will be published")
+                    .required(true),
+            );
+        app = app.subcommand(init);
+
+        let mut show = SubCommand::with_name("show").about("Show the signer info, used to add it to the proxy");
+        show = Self::add_general_args(show);
+        app = app.subcommand(show);
+
+        let mut process = SubCommand::with_name("process").about("Process a signed request from the proxy");
+        process = Self::add_general_args(process);
+        process = process
+            .arg(
+                Arg::with_name("request")
+                    .long("request")
+                    .value_name("file")
+                    .help("JSON file with the signed request, see 'krillc ta proxy signer show-request'")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("mft_next_update_weeks")
+                    .long("mft-next-update-weeks")
+                    .value_name("weeks")
+                    .help("Weeks until the next update of the manifest and CRL (default 12)")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("child_cert_valid_weeks")
+                    .long("child-cert-valid-weeks")
+                    .value_name("weeks")
+                    .help("Validity in weeks of issued child certificates (default 52)")
+                    .required(false),
+            );
+        app = app.subcommand(process);
+
+        let mut last = SubCommand::with_name("last-response").about("Show the last signed response");
+        last = Self::add_general_args(last);
+        app = app.subcommand(last);
+
+        let mut exchanges = SubCommand::with_name("exchanges").about("Show all processed requests and responses");
+        exchanges = Self::add_general_args(exchanges);
+        app = app.subcommand(exchanges);
+
+        let mut history = SubCommand::with_name("history").about("Show the history of the signer");
+        history = Self::add_general_args(history);
+        history = history
+            .arg(
+                Arg::with_name("rows")
+                    .long("rows")
+                    .help("Number of rows (max 250)")
+                    .value_name("<number>")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("offset")
+                    .long("offset")
+                    .help("Number of results to skip")
+                    .value_name("<number>")
+                    .required(false),
+            );
+        app = app.subcommand(history);
+
+        app.get_matches()
+    }
+
+    fn parse_matches(matches: ArgMatches) -> Result<Self, Error> {
+        let (name, sub) = matches.subcommand();
+        let sub = sub.ok_or(Error::UnrecognizedSubCommand)?;
+
+        let data_dir = match sub.value_of(KRILL_TA_DATA_ARG) {
+            Some(path) => PathBuf::from(path),
+            None => env::var(KRILL_TA_DATA_ENV)
+                .map(PathBuf::from)
+                .map_err(|_| Error::MissingArgWithEnv(KRILL_TA_DATA_ARG.to_string(), KRILL_TA_DATA_ENV.to_string()))?,
+        };
+
+        let format = match sub.value_of("format") {
+            Some(fmt) => ReportFormat::from_str(fmt)?,
+            None => ReportFormat::Text,
+        };
+
+        let command = match name {
+            "init" => {
+                let proxy_info = file::load_json(&PathBuf::from(sub.value_of("proxy_info").unwrap()))?;
+
+                let mut tal_uris = vec![];
+                for uri in sub.values_of("tal_https").unwrap() {
+                    tal_uris.push(uri::Https::from_str(uri)?);
+                }
+                let tal_rsync_uri = uri::Rsync::from_str(sub.value_of("tal_rsync").unwrap())?;
+
+                TrustAnchorSignerCommand::Init(proxy_info, tal_uris, tal_rsync_uri)
+            }
+            "show" => TrustAnchorSignerCommand::Show,
+            "process" => {
+                let request = file::load_json(&PathBuf::from(sub.value_of("request").unwrap()))?;
+                let mft_weeks = Self::parse_weeks(sub, "mft_next_update_weeks", TA_SIGNER_MFT_NEXT_UPDATE_WEEKS_DFLT)?;
+                let cert_weeks =
+                    Self::parse_weeks(sub, "child_cert_valid_weeks", TA_SIGNER_CHILD_CERT_VALID_WEEKS_DFLT)?;
+                let timing = IssuanceTimingConfig::for_ta_signer(mft_weeks, cert_weeks);
+
+                TrustAnchorSignerCommand::Process(request, timing)
+            }
+            "last-response" => TrustAnchorSignerCommand::LastResponse,
+            "exchanges" => TrustAnchorSignerCommand::Exchanges,
+            "history" => {
+                let mut crit = CommandHistoryCriteria::default();
+                if let Some(rows) = sub.value_of("rows") {
+                    let rows = usize::from_str(rows).map_err(|e| Error::GeneralArgumentError(e.to_string()))?;
+                    if rows > 250 {
+                        return Err(Error::GeneralArgumentError(
+                            "No more than 250 rows allowed in history".to_string(),
+                        ));
+                    }
+                    crit.set_rows(rows);
+                }
+                if let Some(offset) = sub.value_of("offset") {
+                    let offset = usize::from_str(offset).map_err(|e| Error::GeneralArgumentError(e.to_string()))?;
+                    crit.set_offset(offset);
+                }
+                TrustAnchorSignerCommand::History(crit)
+            }
+            _ => return Err(Error::UnrecognizedSubCommand),
+        };
+
+        Ok(TrustAnchorSignerOptions {
+            data_dir,
+            format,
+            command,
+        })
+    }
+
+    fn parse_weeks(matches: &ArgMatches, arg: &str, default: i64) -> Result<i64, Error> {
+        match matches.value_of(arg) {
+            None => Ok(default),
+            Some(weeks) => match i64::from_str(weeks) {
+                Ok(weeks) if weeks > 0 => Ok(weeks),
+                _ => Err(Error::GeneralArgumentError(format!(
+                    "Invalid number of weeks: {}",
+                    weeks
+                ))),
+            },
+        }
+    }
+}
+
+//------------ TrustAnchorSignerCommand --------------------------------------
+
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum TrustAnchorSignerCommand {
+    Init(TrustAnchorProxyInfo, Vec<uri::Https>, uri::Rsync),
+    Show,
+    Process(TrustAnchorSignedRequest, IssuanceTimingConfig),
+    LastResponse,
+    Exchanges,
+    History(CommandHistoryCriteria),
+}
+
+//------------ TrustAnchorSignerClient ---------------------------------------
+
+/// Executes a [`TrustAnchorSignerCommand`] against the signer in the local
+/// data directory and prints the result.
+pub struct TrustAnchorSignerClient;
+
+impl TrustAnchorSignerClient {
+    pub fn report(options: TrustAnchorSignerOptions) -> Result<(), Error> {
+        let format = options.format;
+        let manager = TrustAnchorSignerManager::create(&options.data_dir)?;
+
+        let res = match options.command {
+            TrustAnchorSignerCommand::Init(proxy_info, tal_uris, tal_rsync_uri) => {
+                // The timing is only used for the initial (empty) manifest and CRL.
+                let timing = IssuanceTimingConfig::for_ta_signer(
+                    TA_SIGNER_MFT_NEXT_UPDATE_WEEKS_DFLT,
+                    TA_SIGNER_CHILD_CERT_VALID_WEEKS_DFLT,
+                );
+                Self::format(&manager.init(proxy_info, tal_uris, tal_rsync_uri, &timing)?, format)
+            }
+            TrustAnchorSignerCommand::Show => Self::format(&manager.show()?, format),
+            TrustAnchorSignerCommand::Process(request, timing) => {
+                // Always print the signed response, as this needs to be
+                // saved and given to the proxy.
+                let exchange = manager.process(request, timing)?;
+                Self::format(&exchange.signed_response, ReportFormat::Json)
+            }
+            TrustAnchorSignerCommand::LastResponse => {
+                Self::format(&manager.last_exchange()?.signed_response, ReportFormat::Json)
+            }
+            TrustAnchorSignerCommand::Exchanges => Self::format(&manager.exchanges()?, format),
+            TrustAnchorSignerCommand::History(crit) => Self::format(&manager.history(crit)?, format),
+        };
+
+        if !res.is_empty() {
+            println!("{}", res);
+        }
+        Ok(())
+    }
+
+    fn format<R: Serialize + fmt::Display>(res: &R, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::None => String::new(),
+            ReportFormat::Json => serde_json::to_string_pretty(res).unwrap(),
+            ReportFormat::Text => res.to_string(),
+        }
+    }
+}
+
+//------------ Error ---------------------------------------------------------
+
+#[derive(Debug)]
+pub enum Error {
+    IoError(KrillIoError),
+    UriError(uri::Error),
+    ReportError(ReportError),
+    KrillError(crate::commons::error::Error),
+    MissingArgWithEnv(String, String),
+    UnrecognizedSubCommand,
+    GeneralArgumentError(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::IoError(e) => e.fmt(f),
+            Error::UriError(e) => e.fmt(f),
+            Error::ReportError(e) => e.fmt(f),
+            Error::KrillError(e) => e.fmt(f),
+            Error::MissingArgWithEnv(arg, env) => write!(
+                f,
+                "Missing argument: --{}, alternatively you may use env var: {}",
+                arg, env
+            ),
+            Error::UnrecognizedSubCommand => write!(f, "Unrecognized sub-command. Use 'help'"),
+            Error::GeneralArgumentError(s) => s.fmt(f),
+        }
+    }
+}
+
+impl From<KrillIoError> for Error {
+    fn from(e: KrillIoError) -> Self {
+        Error::IoError(e)
+    }
+}
+
+impl From<uri::Error> for Error {
+    fn from(e: uri::Error) -> Self {
+        Error::UriError(e)
+    }
+}
+
+impl From<ReportError> for Error {
+    fn from(e: ReportError) -> Self {
+        Error::ReportError(e)
+    }
+}
+
+impl From<crate::commons::error::Error> for Error {
+    fn from(e: crate::commons::error::Error) -> Self {
+        Error::KrillError(e)
+    }
+}
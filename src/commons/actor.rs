@@ -25,7 +25,10 @@ use crate::{
         KrillResult,
     },
     constants::ACTOR_DEF_ANON,
-    daemon::auth::{policy::AuthPolicy, Auth},
+    daemon::{
+        audit::{AuditCheck, AuditTrail},
+        auth::{policy::AuthPolicy, Auth},
+    },
 };
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -104,6 +107,22 @@ impl ActorDef {
         }
     }
 
+    /// Sets an attribute, replacing any existing value.
+    pub fn with_attribute(mut self, name: &str, value: &str) -> Self {
+        let mut attributes = self.attributes.as_map();
+        attributes.insert(name.to_string(), value.to_string());
+        self.attributes = Attributes::UserDefined(attributes);
+        self
+    }
+
+    /// Removes an attribute, if present.
+    pub fn without_attribute(mut self, name: &str) -> Self {
+        if let Attributes::UserDefined(attributes) = &mut self.attributes {
+            attributes.remove(name);
+        }
+        self
+    }
+
     // Takes either a ApiAuthError or a commons::error::Error
     pub fn with_auth_error(mut self, api_error: Error) -> Self {
         self.auth_error = Some(api_error.into());
@@ -119,6 +138,7 @@ pub struct Actor {
     new_auth: Option<Auth>,
     policy: Option<AuthPolicy>,
     auth_error: Option<ApiAuthError>,
+    audit_trail: Option<AuditTrail>,
 }
 
 impl PartialEq for Actor {
@@ -143,10 +163,12 @@ impl Actor {
             new_auth: None,
             auth_error: None,
             policy: None,
+            audit_trail: None,
         }
     }
 
-    /// Only for use in testing
+    /// Only for use in testing, and for explaining policy decisions for
+    /// hypothetical actors.
     pub fn test_from_details(name: String, attrs: HashMap<String, String>) -> Actor {
         Actor {
             name: ActorName::AsString(name),
@@ -155,6 +177,22 @@ impl Actor {
             new_auth: None,
             auth_error: None,
             policy: None,
+            audit_trail: None,
+        }
+    }
+
+    /// Creates an actor for a command line tool that works on its own data
+    /// directly, so without an authorization policy. E.g. the offline Trust
+    /// Anchor signer.
+    pub fn local(actor_def: ActorDef) -> Actor {
+        Actor {
+            name: actor_def.name.clone(),
+            is_user: actor_def.is_user,
+            attributes: actor_def.attributes,
+            new_auth: None,
+            auth_error: None,
+            policy: None,
+            audit_trail: None,
         }
     }
 
@@ -166,9 +204,21 @@ impl Actor {
             new_auth: actor_def.new_auth.clone(),
             auth_error: actor_def.auth_error,
             policy: Some(policy),
+            audit_trail: None,
         }
     }
 
+    /// Records all permission checks for this actor, and its clones, in the
+    /// given trail.
+    pub fn with_audit_trail(mut self, trail: AuditTrail) -> Self {
+        self.audit_trail = Some(trail);
+        self
+    }
+
+    pub fn audit_trail(&self) -> Option<AuditTrail> {
+        self.audit_trail.clone()
+    }
+
     pub fn is_user(&self) -> bool {
         self.is_user
     }
@@ -199,15 +249,38 @@ impl Actor {
     }
 
     #[cfg(not(feature = "multi-user"))]
-    pub fn is_allowed<A, R>(&self, _: A, _: R) -> KrillResult<bool> {
+    pub fn is_allowed<A, R>(&self, action: A, resource: R) -> KrillResult<bool>
+    where
+        A: fmt::Display,
+        R: fmt::Display,
+    {
         // When not in multi-user mode we only have two states: authenticated or not authenticated (aka anonymous).
         // Only authenticated (i.e. not anonymous) actors are permitted to perform restricted actions, i.e. those for
         // which this fn is invoked.
-        Ok(!self.is_anonymous())
+        let res = Ok(!self.is_anonymous());
+        self.audit(&action, &resource, &res);
+        res
     }
 
     #[cfg(feature = "multi-user")]
     pub fn is_allowed<A, R>(&self, action: A, resource: R) -> KrillResult<bool>
+    where
+        A: ToPolar + Display + Debug + Clone,
+        R: ToPolar + Display + Debug + Clone,
+    {
+        let res = self.check_allowed(action.clone(), resource.clone());
+        self.audit(&action, &resource, &res);
+        res
+    }
+
+    fn audit(&self, action: &impl fmt::Display, resource: &impl fmt::Display, res: &KrillResult<bool>) {
+        if let Some(trail) = &self.audit_trail {
+            trail.record(AuditCheck::new(action, resource, res));
+        }
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn check_allowed<A, R>(&self, action: A, resource: R) -> KrillResult<bool>
     where
         A: ToPolar + Display + Debug + Clone,
         R: ToPolar + Display + Debug + Clone,
@@ -17,7 +17,7 @@ use rpki::{repository::cert::Cert, uri};
 
 use crate::commons::{
     api::{
-        ca::{ResourceSet, TrustAnchorLocator},
+        ca::{RepoStatus, ResourceSet, ResourceTemplateName, TrustAnchorLocator},
         rrdp::PublishElement,
         RepoInfo, Timestamp,
     },
@@ -354,6 +354,79 @@ impl std::cmp::PartialEq for RepositoryContact {
 
 impl std::cmp::Eq for RepositoryContact {}
 
+//------------ RepositoryMirror ----------------------------------------------
+
+pub type RepositoryMirrorName = String;
+
+/// A secondary repository to which a CA publishes copies of all the objects
+/// which it publishes in its primary repository. Mirrors are synchronized
+/// independently, so an unreachable mirror does not hold up publication in
+/// the primary repository.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepositoryMirror {
+    name: RepositoryMirrorName,
+    service_uri: ServiceUri,
+    status: RepoStatus,
+}
+
+impl RepositoryMirror {
+    pub fn new(name: RepositoryMirrorName, service_uri: ServiceUri, status: RepoStatus) -> Self {
+        RepositoryMirror {
+            name,
+            service_uri,
+            status,
+        }
+    }
+
+    pub fn name(&self) -> &RepositoryMirrorName {
+        &self.name
+    }
+
+    pub fn service_uri(&self) -> &ServiceUri {
+        &self.service_uri
+    }
+
+    pub fn status(&self) -> &RepoStatus {
+        &self.status
+    }
+}
+
+impl fmt::Display for RepositoryMirror {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Mirror: {}", self.name)?;
+        writeln!(f, "Service URI: {}", self.service_uri)?;
+        self.status.fmt(f)
+    }
+}
+
+//------------ RepositoryMirrorList ------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepositoryMirrorList(Vec<RepositoryMirror>);
+
+impl RepositoryMirrorList {
+    pub fn new(mirrors: Vec<RepositoryMirror>) -> Self {
+        RepositoryMirrorList(mirrors)
+    }
+
+    pub fn mirrors(&self) -> &Vec<RepositoryMirror> {
+        &self.0
+    }
+}
+
+impl fmt::Display for RepositoryMirrorList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            writeln!(f, "No repository mirrors configured")
+        } else {
+            for mirror in &self.0 {
+                writeln!(f, "{}", mirror)?;
+            }
+            Ok(())
+        }
+    }
+}
+
 //------------ ParentCaReq ---------------------------------------------------
 
 /// This type defines all parent ca details needed to add a parent to a CA
@@ -534,6 +607,213 @@ impl CertAuthInit {
     }
 }
 
+//------------ CertAuthRename ------------------------------------------------
+
+/// A request to rename a CA, containing its new handle.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CertAuthRename {
+    handle: Handle,
+}
+
+impl fmt::Display for CertAuthRename {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.handle)
+    }
+}
+
+impl CertAuthRename {
+    pub fn new(handle: Handle) -> Self {
+        CertAuthRename { handle }
+    }
+
+    pub fn unpack(self) -> Handle {
+        self.handle
+    }
+}
+
+//------------ CertAuthRenameReport ------------------------------------------
+
+/// Describes what was updated in this Krill instance when a CA was renamed,
+/// and which steps remain for the operator. Remote parties know a CA by the
+/// names and service URIs exchanged through RFC 8183, and those can only be
+/// changed by doing new exchanges.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CertAuthRenameReport {
+    previous: Handle,
+    handle: Handle,
+    updated: Vec<CertAuthRenameUpdate>,
+    next_steps: Vec<CertAuthRenameStep>,
+}
+
+impl CertAuthRenameReport {
+    pub fn new(
+        previous: Handle,
+        handle: Handle,
+        updated: Vec<CertAuthRenameUpdate>,
+        next_steps: Vec<CertAuthRenameStep>,
+    ) -> Self {
+        CertAuthRenameReport {
+            previous,
+            handle,
+            updated,
+            next_steps,
+        }
+    }
+
+    pub fn previous(&self) -> &Handle {
+        &self.previous
+    }
+
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    pub fn updated(&self) -> &Vec<CertAuthRenameUpdate> {
+        &self.updated
+    }
+
+    pub fn next_steps(&self) -> &Vec<CertAuthRenameStep> {
+        &self.next_steps
+    }
+
+    pub fn add_update(&mut self, update: CertAuthRenameUpdate) {
+        self.updated.push(update);
+    }
+
+    pub fn add_next_step(&mut self, step: CertAuthRenameStep) {
+        self.next_steps.push(step);
+    }
+}
+
+impl fmt::Display for CertAuthRenameReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "CA '{}' was renamed to '{}'", self.previous, self.handle)?;
+        if !self.updated.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Updated in this Krill instance:")?;
+            for update in &self.updated {
+                writeln!(f, "  - {}", update)?;
+            }
+        }
+        writeln!(f)?;
+        if self.next_steps.is_empty() {
+            writeln!(f, "No further steps are needed.")?;
+        } else {
+            writeln!(f, "Next steps:")?;
+            for step in &self.next_steps {
+                writeln!(f, "  - {}", step)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//------------ CertAuthRenameUpdate ------------------------------------------
+
+/// Something that was updated in this Krill instance when a CA was renamed.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "update")]
+pub enum CertAuthRenameUpdate {
+    /// A local child CA got a new parent contact, using the new service URI.
+    ChildParentContact { child: Handle, parent: ParentHandle },
+
+    /// A publisher for the new handle was added to the local repository, and
+    /// the CA started key rolls to move there.
+    Publisher { publisher: PublisherHandle },
+
+    /// A tenant owning the CA now refers to it by its new handle.
+    Tenant { tenant: String },
+
+    /// A scoped API token limited to the CA now refers to it by its new handle.
+    ApiToken { token: String },
+}
+
+impl fmt::Display for CertAuthRenameUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CertAuthRenameUpdate::ChildParentContact { child, parent } => write!(
+                f,
+                "updated the contact for parent '{}' in local child CA '{}'",
+                parent, child
+            ),
+            CertAuthRenameUpdate::Publisher { publisher } => write!(
+                f,
+                "added publisher '{}' to the local repository, and started key rolls to move there",
+                publisher
+            ),
+            CertAuthRenameUpdate::Tenant { tenant } => write!(f, "updated the CAs of tenant '{}'", tenant),
+            CertAuthRenameUpdate::ApiToken { token } => write!(f, "updated the CAs of API token '{}'", token),
+        }
+    }
+}
+
+//------------ CertAuthRenameStep --------------------------------------------
+
+/// A step that remains for the operator after a CA was renamed.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "step")]
+pub enum CertAuthRenameStep {
+    /// A remote child must get a new parent response, because the service
+    /// URI of the renamed CA changed.
+    ChildParentResponse { child: ChildHandle },
+
+    /// A parent knows the CA by its previous name. This keeps working, but a
+    /// new exchange is needed if the parent should use the new name.
+    ParentChildRequest { parent: ParentHandle, child: ChildHandle },
+
+    /// A repository knows the CA by its previous name. This keeps working,
+    /// but a new exchange is needed if the repository should use the new name.
+    RepositoryPublisherRequest { publisher: PublisherHandle },
+
+    /// The previous publisher in the local repository can be removed, once
+    /// the key rolls to the new publisher have finished.
+    RemovePublisher { publisher: PublisherHandle },
+
+    /// The publisher in the local repository could not be moved.
+    PublisherNotMoved { publisher: PublisherHandle, reason: String },
+
+    /// The tenants or scoped API tokens referring to the CA could not be
+    /// updated, so they no longer give access to it.
+    AccessNotUpdated { reason: String },
+}
+
+impl fmt::Display for CertAuthRenameStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CertAuthRenameStep::ChildParentResponse { child } => write!(
+                f,
+                "give child '{}' a new parent response, because the service URI of this CA changed",
+                child
+            ),
+            CertAuthRenameStep::ParentChildRequest { parent, child } => write!(
+                f,
+                "parent '{}' knows this CA as child '{}', which keeps working. To use the new name there, give the parent a new child request, and update the parent with its response",
+                parent, child
+            ),
+            CertAuthRenameStep::RepositoryPublisherRequest { publisher } => write!(
+                f,
+                "the repository knows this CA as publisher '{}', which keeps working. To use the new name there, give the repository a new publisher request, and update the repository with its response. This starts key rolls",
+                publisher
+            ),
+            CertAuthRenameStep::RemovePublisher { publisher } => write!(
+                f,
+                "remove publisher '{}' from the local repository, once the key rolls to the new publisher have finished",
+                publisher
+            ),
+            CertAuthRenameStep::PublisherNotMoved { publisher, reason } => write!(
+                f,
+                "publisher '{}' in the local repository was kept: {}",
+                publisher, reason
+            ),
+            CertAuthRenameStep::AccessNotUpdated { reason } => write!(
+                f,
+                "update tenants and API tokens that refer to the previous name of this CA, they could not be updated: {}",
+                reason
+            ),
+        }
+    }
+}
+
 //------------ AddChildRequest -----------------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -575,14 +855,30 @@ pub struct UpdateChildRequest {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     suspend: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    template: Option<ResourceTemplateName>,
+
+    /// The number of days notice given before resources are removed from the
+    /// child, use 0 to remove resources immediately.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    shrink_notice_days: Option<u32>,
 }
 
 impl UpdateChildRequest {
-    pub fn new(id_cert: Option<IdCert>, resources: Option<ResourceSet>, suspend: Option<bool>) -> Self {
+    pub fn new(
+        id_cert: Option<IdCert>,
+        resources: Option<ResourceSet>,
+        suspend: Option<bool>,
+        template: Option<ResourceTemplateName>,
+        shrink_notice_days: Option<u32>,
+    ) -> Self {
         UpdateChildRequest {
             id_cert,
             resources,
             suspend,
+            template,
+            shrink_notice_days,
         }
     }
     pub fn id_cert(id_cert: IdCert) -> Self {
@@ -590,6 +886,8 @@ impl UpdateChildRequest {
             id_cert: Some(id_cert),
             resources: None,
             suspend: None,
+            template: None,
+            shrink_notice_days: None,
         }
     }
 
@@ -598,6 +896,8 @@ impl UpdateChildRequest {
             id_cert: None,
             resources: Some(resources),
             suspend: None,
+            template: None,
+            shrink_notice_days: None,
         }
     }
 
@@ -606,6 +906,8 @@ impl UpdateChildRequest {
             id_cert: None,
             resources: None,
             suspend: Some(true),
+            template: None,
+            shrink_notice_days: None,
         }
     }
 
@@ -614,11 +916,39 @@ impl UpdateChildRequest {
             id_cert: None,
             resources: None,
             suspend: Some(false),
+            template: None,
+            shrink_notice_days: None,
         }
     }
 
-    pub fn unpack(self) -> (Option<IdCert>, Option<ResourceSet>, Option<bool>) {
-        (self.id_cert, self.resources, self.suspend)
+    /// Links the child to a resource template, and sets its resources to
+    /// those of the template.
+    pub fn template(template: ResourceTemplateName) -> Self {
+        UpdateChildRequest {
+            id_cert: None,
+            resources: None,
+            suspend: None,
+            template: Some(template),
+            shrink_notice_days: None,
+        }
+    }
+
+    pub fn unpack(
+        self,
+    ) -> (
+        Option<IdCert>,
+        Option<ResourceSet>,
+        Option<bool>,
+        Option<ResourceTemplateName>,
+        Option<u32>,
+    ) {
+        (
+            self.id_cert,
+            self.resources,
+            self.suspend,
+            self.template,
+            self.shrink_notice_days,
+        )
     }
 }
 
@@ -630,6 +960,12 @@ impl fmt::Display for UpdateChildRequest {
         if let Some(resources) = &self.resources {
             write!(f, "new resources: {} ", resources)?;
         }
+        if let Some(template) = &self.template {
+            write!(f, "apply resource template: {} ", template)?;
+        }
+        if let Some(days) = self.shrink_notice_days {
+            write!(f, "notice period for resource removals: {} days ", days)?;
+        }
         if let Some(suspend) = self.suspend {
             write!(f, "change suspend status to: {}", suspend)?;
         }
@@ -0,0 +1,97 @@
+//! Records in the audit log of authorization checks.
+
+use std::fmt;
+
+use crate::commons::util::sha256;
+
+//------------ AuditDecision -------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditDecision {
+    Granted,
+    Denied,
+    Error,
+}
+
+impl fmt::Display for AuditDecision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuditDecision::Granted => write!(f, "granted"),
+            AuditDecision::Denied => write!(f, "denied"),
+            AuditDecision::Error => write!(f, "error"),
+        }
+    }
+}
+
+//------------ AuditRecord ---------------------------------------------------
+
+/// A single authorization check made while handling an API request, along
+/// with the HTTP status of the response to that request.
+///
+/// When written to a hash-chained audit log file, each record includes the
+/// hash of the previous record, and its own hash. The hash of a record is the
+/// hex encoded SHA-256 of its JSON representation without the hash.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub time: String,
+    pub actor: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub permission: String,
+    pub resource: String,
+    pub decision: AuditDecision,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub outcome: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+impl AuditRecord {
+    /// Computes the hash of this record, ignoring any hash already set.
+    pub fn compute_hash(&self) -> String {
+        let mut unhashed = self.clone();
+        unhashed.hash = None;
+        let json = serde_json::to_string(&unhashed).unwrap();
+        hex::encode(sha256(json.as_bytes()))
+    }
+}
+
+//------------ AuditLogVerification ------------------------------------------
+
+/// The result of a successful verification of a hash-chained audit log.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditLogVerification {
+    records: u64,
+    last_hash: Option<String>,
+}
+
+impl AuditLogVerification {
+    pub fn new(records: u64, last_hash: Option<String>) -> Self {
+        AuditLogVerification { records, last_hash }
+    }
+
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    pub fn last_hash(&self) -> Option<&String> {
+        self.last_hash.as_ref()
+    }
+}
+
+impl fmt::Display for AuditLogVerification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Audit log chain is intact, records: {}", self.records)?;
+        if let Some(hash) = &self.last_hash {
+            writeln!(f, "Hash of the last record: {}", hash)?;
+        }
+        Ok(())
+    }
+}
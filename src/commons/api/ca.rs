@@ -31,9 +31,10 @@ use crate::commons::util::KrillVersion;
 use crate::{
     commons::{
         api::{
-            rrdp::PublishElement, AspaDefinition, Base64, ChildHandle, EntitlementClass, Entitlements, ErrorResponse,
-            Handle, HexEncodedHash, IssuanceRequest, ParentCaContact, ParentHandle, RepositoryContact,
-            RequestResourceLimit, RoaAggregateKey, RoaDefinition, SigningCert,
+            rrdp::PublishElement, AspaDefinition, Base64, ChildHandle, ChildSuspension, ChildSuspensionPolicy,
+            EntitlementClass, Entitlements, ErrorResponse, Handle, HexEncodedHash, IssuanceRequest, KeyRollPolicy,
+            ParentCaContact, ParentHandle, RepositoryContact, RepositoryMirrorName, RequestResourceLimit,
+            RoaAggregateKey, RoaDefinition, SigningCert, Token,
         },
         crypto::IdCert,
         remote::rfc8183::ServiceUri,
@@ -185,17 +186,56 @@ pub struct ChildCaInfo {
     state: ChildState,
     id_cert: IdCertPem,
     entitled_resources: ResourceSet,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    template: Option<ResourceTemplateName>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    shrink_notice_days: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    shrink: Option<ChildResourceShrink>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    suspension_policy: Option<ChildSuspensionPolicy>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    suspension: Option<ChildSuspension>,
 }
 
 impl ChildCaInfo {
-    pub fn new(state: ChildState, id_cert: IdCertPem, entitled_resources: ResourceSet) -> Self {
+    pub fn new(
+        state: ChildState,
+        id_cert: IdCertPem,
+        entitled_resources: ResourceSet,
+        template: Option<ResourceTemplateName>,
+        shrink_notice_days: Option<u32>,
+        shrink: Option<ChildResourceShrink>,
+    ) -> Self {
         ChildCaInfo {
             state,
             id_cert,
             entitled_resources,
+            template,
+            shrink_notice_days,
+            shrink,
+            suspension_policy: None,
+            suspension: None,
         }
     }
 
+    /// Adds the suspension policy specific to this child, and the reason
+    /// why it is suspended, if it is.
+    pub fn with_suspension(
+        mut self,
+        suspension_policy: Option<ChildSuspensionPolicy>,
+        suspension: Option<ChildSuspension>,
+    ) -> Self {
+        self.suspension_policy = suspension_policy;
+        self.suspension = suspension;
+        self
+    }
+
     pub fn state(&self) -> ChildState {
         self.state
     }
@@ -207,6 +247,26 @@ impl ChildCaInfo {
     pub fn entitled_resources(&self) -> &ResourceSet {
         &self.entitled_resources
     }
+
+    pub fn template(&self) -> Option<&ResourceTemplateName> {
+        self.template.as_ref()
+    }
+
+    pub fn shrink_notice_days(&self) -> Option<u32> {
+        self.shrink_notice_days
+    }
+
+    pub fn shrink(&self) -> Option<&ChildResourceShrink> {
+        self.shrink.as_ref()
+    }
+
+    pub fn suspension_policy(&self) -> Option<&ChildSuspensionPolicy> {
+        self.suspension_policy.as_ref()
+    }
+
+    pub fn suspension(&self) -> Option<&ChildSuspension> {
+        self.suspension.as_ref()
+    }
 }
 
 impl fmt::Display for ChildCaInfo {
@@ -214,7 +274,380 @@ impl fmt::Display for ChildCaInfo {
         writeln!(f, "{}", self.id_cert.pem())?;
         writeln!(f, "SHA256 hash of PEM encoded certificate: {}", self.id_cert.hash())?;
         writeln!(f, "resources: {}", self.entitled_resources)?;
-        writeln!(f, "state: {}", self.state)
+        if let Some(template) = &self.template {
+            writeln!(f, "resource template: {}", template)?;
+        }
+        if let Some(days) = self.shrink_notice_days {
+            writeln!(f, "notice period for resource removals: {} days", days)?;
+        }
+        if let Some(shrink) = &self.shrink {
+            writeln!(f, "scheduled resource removal: {}", shrink)?;
+        }
+        if let Some(policy) = &self.suspension_policy {
+            writeln!(f, "suspension policy: {}", policy)?;
+        }
+        writeln!(f, "state: {}", self.state)?;
+        if let Some(suspension) = &self.suspension {
+            writeln!(f, "suspended since: {}", suspension)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ ChildResourceShrink -------------------------------------------
+
+/// A scheduled removal of resources from a child CA. Until the removal is
+/// effective the child remains entitled to the removed resources, but it
+/// will not get certificates for them which are valid beyond that time.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildResourceShrink {
+    resources: ResourceSet,
+    removed: ResourceSet,
+    effective: Timestamp,
+}
+
+impl ChildResourceShrink {
+    pub fn new(resources: ResourceSet, removed: ResourceSet, effective: Timestamp) -> Self {
+        ChildResourceShrink {
+            resources,
+            removed,
+            effective,
+        }
+    }
+
+    /// The resources of the child after the removal.
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
+    }
+
+    pub fn removed(&self) -> &ResourceSet {
+        &self.removed
+    }
+
+    pub fn effective(&self) -> Timestamp {
+        self.effective
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.effective <= Timestamp::now()
+    }
+}
+
+impl fmt::Display for ChildResourceShrink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.removed, self.effective.to_rfc3339())
+    }
+}
+
+//------------ ChildShrinkInfo -----------------------------------------------
+
+/// Shows a scheduled resource removal for a child, and the ROAs of the
+/// child which will become invalid. The ROAs are only known if the child
+/// CA lives in this same Krill instance.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildShrinkInfo {
+    child: ChildHandle,
+    shrink: ChildResourceShrink,
+    invalidated_roas: Option<Vec<RoaDefinition>>,
+}
+
+impl ChildShrinkInfo {
+    pub fn new(child: ChildHandle, shrink: ChildResourceShrink, invalidated_roas: Option<Vec<RoaDefinition>>) -> Self {
+        ChildShrinkInfo {
+            child,
+            shrink,
+            invalidated_roas,
+        }
+    }
+
+    pub fn child(&self) -> &ChildHandle {
+        &self.child
+    }
+
+    pub fn shrink(&self) -> &ChildResourceShrink {
+        &self.shrink
+    }
+
+    pub fn invalidated_roas(&self) -> Option<&Vec<RoaDefinition>> {
+        self.invalidated_roas.as_ref()
+    }
+}
+
+impl fmt::Display for ChildShrinkInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "child '{}' will lose: {}", self.child, self.shrink)?;
+        match &self.invalidated_roas {
+            None => writeln!(f, "  ROAs unknown, child CA is not hosted here"),
+            Some(roas) if roas.is_empty() => writeln!(f, "  no ROAs will become invalid"),
+            Some(roas) => {
+                writeln!(f, "  ROAs which will become invalid:")?;
+                for roa in roas {
+                    writeln!(f, "    {}", roa)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+//------------ ChildShrinkList -----------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildShrinkList(Vec<ChildShrinkInfo>);
+
+impl ChildShrinkList {
+    pub fn new(shrinks: Vec<ChildShrinkInfo>) -> Self {
+        ChildShrinkList(shrinks)
+    }
+
+    pub fn shrinks(&self) -> &Vec<ChildShrinkInfo> {
+        &self.0
+    }
+}
+
+impl fmt::Display for ChildShrinkList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            writeln!(f, "No scheduled resource removals for children")
+        } else {
+            for shrink in self.0.iter() {
+                write!(f, "{}", shrink)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+//------------ ChildRequestInfo ----------------------------------------------
+
+/// A request by a prospective child CA to be added under a parent CA. The
+/// request is kept until it is rejected, or until the child has retrieved
+/// the parent response after the request was approved.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildRequestInfo {
+    child: ChildHandle,
+    id_cert: IdCertPem,
+    submitted: Timestamp,
+    approved: bool,
+}
+
+impl ChildRequestInfo {
+    pub fn new(child: ChildHandle, id_cert: IdCertPem, submitted: Timestamp, approved: bool) -> Self {
+        ChildRequestInfo {
+            child,
+            id_cert,
+            submitted,
+            approved,
+        }
+    }
+
+    pub fn child(&self) -> &ChildHandle {
+        &self.child
+    }
+
+    pub fn id_cert(&self) -> &IdCertPem {
+        &self.id_cert
+    }
+
+    pub fn submitted(&self) -> Timestamp {
+        self.submitted
+    }
+
+    pub fn approved(&self) -> bool {
+        self.approved
+    }
+}
+
+impl fmt::Display for ChildRequestInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = if self.approved { "approved" } else { "pending" };
+        write!(
+            f,
+            "{}  submitted: {}  state: {}  id cert hash: {}",
+            self.child,
+            self.submitted.to_rfc3339(),
+            state,
+            self.id_cert.hash()
+        )
+    }
+}
+
+//------------ ChildRequestList ----------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildRequestList(Vec<ChildRequestInfo>);
+
+impl ChildRequestList {
+    pub fn new(requests: Vec<ChildRequestInfo>) -> Self {
+        ChildRequestList(requests)
+    }
+
+    pub fn requests(&self) -> &Vec<ChildRequestInfo> {
+        &self.0
+    }
+}
+
+impl fmt::Display for ChildRequestList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            writeln!(f, "There are no pending child requests")
+        } else {
+            for request in &self.0 {
+                writeln!(f, "{}", request)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+//------------ ChildRequestReceipt -------------------------------------------
+
+/// Returned to a prospective child CA when its request was accepted. The
+/// token is needed to retrieve the parent response once the request has
+/// been approved, and it can only be used once.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildRequestReceipt {
+    parent: Handle,
+    child: ChildHandle,
+    token: Token,
+}
+
+impl ChildRequestReceipt {
+    pub fn new(parent: Handle, child: ChildHandle, token: Token) -> Self {
+        ChildRequestReceipt { parent, child, token }
+    }
+
+    pub fn parent(&self) -> &Handle {
+        &self.parent
+    }
+
+    pub fn child(&self) -> &ChildHandle {
+        &self.child
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+}
+
+impl fmt::Display for ChildRequestReceipt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Request for child '{}' submitted to parent '{}'",
+            self.child, self.parent
+        )?;
+        writeln!(
+            f,
+            "Use the following token to retrieve the parent response once approved:"
+        )?;
+        writeln!(f, "{}", self.token)
+    }
+}
+
+//------------ ResourceTemplate ----------------------------------------------
+
+pub type ResourceTemplateName = String;
+
+/// A named set of resources which can be assigned to many children of a CA.
+/// Children which use a template get updated resources when the template
+/// is updated.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResourceTemplate {
+    name: ResourceTemplateName,
+    resources: ResourceSet,
+}
+
+impl ResourceTemplate {
+    pub fn new(name: ResourceTemplateName, resources: ResourceSet) -> Self {
+        ResourceTemplate { name, resources }
+    }
+
+    pub fn name(&self) -> &ResourceTemplateName {
+        &self.name
+    }
+
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
+    }
+
+    pub fn unpack(self) -> (ResourceTemplateName, ResourceSet) {
+        (self.name, self.resources)
+    }
+}
+
+impl fmt::Display for ResourceTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.resources)
+    }
+}
+
+//------------ ResourceTemplateList ------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResourceTemplateList(Vec<ResourceTemplate>);
+
+impl ResourceTemplateList {
+    pub fn new(templates: Vec<ResourceTemplate>) -> Self {
+        ResourceTemplateList(templates)
+    }
+
+    pub fn templates(&self) -> &Vec<ResourceTemplate> {
+        &self.0
+    }
+}
+
+impl fmt::Display for ResourceTemplateList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for template in &self.0 {
+            writeln!(f, "{}", template)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ UndelegatedResources ------------------------------------------
+
+/// Shows which of the resources held by a CA are not delegated to any of
+/// its children, e.g. for capacity planning.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct UndelegatedResources {
+    held: ResourceSet,
+    delegated: ResourceSet,
+    undelegated: ResourceSet,
+}
+
+impl UndelegatedResources {
+    pub fn new(held: ResourceSet, delegated: ResourceSet) -> Self {
+        let undelegated = held.difference(&delegated).added().clone();
+        UndelegatedResources {
+            held,
+            delegated,
+            undelegated,
+        }
+    }
+
+    pub fn held(&self) -> &ResourceSet {
+        &self.held
+    }
+
+    pub fn delegated(&self) -> &ResourceSet {
+        &self.delegated
+    }
+
+    pub fn undelegated(&self) -> &ResourceSet {
+        &self.undelegated
+    }
+}
+
+impl fmt::Display for UndelegatedResources {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Held by CA:")?;
+        writeln!(f, "  {}", self.held)?;
+        writeln!(f, "Delegated to children:")?;
+        writeln!(f, "  {}", self.delegated)?;
+        writeln!(f, "Not delegated to any child:")?;
+        writeln!(f, "  {}", self.undelegated)
     }
 }
 
@@ -494,6 +927,14 @@ impl TrustAnchorLocator {
             encoded_ski,
         }
     }
+
+    pub fn uris(&self) -> &Vec<uri::Https> {
+        &self.uris
+    }
+
+    pub fn rsync_uri(&self) -> Option<&uri::Rsync> {
+        self.rsync_uri.as_ref()
+    }
 }
 
 impl fmt::Display for TrustAnchorLocator {
@@ -1124,6 +1565,16 @@ impl ResourceSetDiff {
     pub fn is_empty(&self) -> bool {
         self.added.is_empty() && self.removed.is_empty()
     }
+
+    /// Resources found in the new set, but not in the old.
+    pub fn added(&self) -> &ResourceSet {
+        &self.added
+    }
+
+    /// Resources found in the old set, but not in the new.
+    pub fn removed(&self) -> &ResourceSet {
+        &self.removed
+    }
 }
 
 impl fmt::Display for ResourceSetDiff {
@@ -1607,6 +2058,12 @@ impl RepoStatus {
     pub fn to_failure_opt(&self) -> Option<ErrorResponse> {
         self.last_exchange.as_ref().map(|e| e.to_failure_opt()).flatten()
     }
+
+    /// Returns true if the repository was synchronized successfully at or after
+    /// the given time, i.e. if objects staged at that time have been published.
+    pub fn published_since(&self, since: Timestamp) -> bool {
+        self.last_success.map(|last| last >= since).unwrap_or(false)
+    }
 }
 
 impl RepoStatus {
@@ -1818,6 +2275,8 @@ pub struct ChildStatus {
     last_exchange: Option<ChildExchange>,
     last_success: Option<Timestamp>,
     suspended: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    inactivity_warning: Option<ErrorResponse>,
 }
 
 impl ChildStatus {
@@ -1830,6 +2289,7 @@ impl ChildStatus {
         });
         self.last_success = Some(timestamp);
         self.suspended = None;
+        self.inactivity_warning = None;
     }
 
     pub fn set_failure(&mut self, user_agent: Option<String>, error_response: ErrorResponse) {
@@ -1839,10 +2299,22 @@ impl ChildStatus {
             user_agent,
         });
         self.suspended = None;
+        self.inactivity_warning = None;
     }
 
     pub fn set_suspended(&mut self) {
-        self.suspended = Some(Timestamp::now())
+        self.suspended = Some(Timestamp::now());
+        self.inactivity_warning = None;
+    }
+
+    /// Records a warning that the child will be suspended if it remains
+    /// inactive. The warning is cleared when the child is seen again.
+    pub fn set_inactivity_warning(&mut self, warning: ErrorResponse) {
+        self.inactivity_warning = Some(warning);
+    }
+
+    pub fn clear_inactivity_warning(&mut self) {
+        self.inactivity_warning = None;
     }
 
     pub fn last_exchange(&self) -> Option<&ChildExchange> {
@@ -1857,6 +2329,10 @@ impl ChildStatus {
         self.suspended
     }
 
+    pub fn inactivity_warning(&self) -> Option<&ErrorResponse> {
+        self.inactivity_warning.as_ref()
+    }
+
     pub fn child_state(&self) -> ChildState {
         if self.suspended.is_none() {
             ChildState::Active
@@ -1872,6 +2348,7 @@ impl Default for ChildStatus {
             last_exchange: None,
             last_success: None,
             suspended: None,
+            inactivity_warning: None,
         }
     }
 }
@@ -1923,10 +2400,14 @@ impl ChildExchange {
 //------------ Timestamp -----------------------------------------------------
 
 /// A wrapper for unix timestamps with second precision, with some convenient stuff.
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct Timestamp(i64);
 
 impl Timestamp {
+    pub fn new(secs: i64) -> Self {
+        Timestamp(secs)
+    }
+
     pub fn now() -> Self {
         Timestamp(Time::now().timestamp())
     }
@@ -2048,9 +2529,14 @@ pub struct CertAuthInfo {
     resource_classes: HashMap<ResourceClassName, ResourceClassInfo>,
     children: Vec<ChildHandle>,
     suspended_children: Vec<ChildHandle>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    keyroll_policy: Option<KeyRollPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    suspension_policy: Option<ChildSuspensionPolicy>,
 }
 
 impl CertAuthInfo {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         handle: Handle,
         id_cert: IdCertPem,
@@ -2059,6 +2545,8 @@ impl CertAuthInfo {
         resource_classes: HashMap<ResourceClassName, ResourceClassInfo>,
         children: Vec<ChildHandle>,
         suspended_children: Vec<ChildHandle>,
+        keyroll_policy: Option<KeyRollPolicy>,
+        suspension_policy: Option<ChildSuspensionPolicy>,
     ) -> Self {
         let parents = parents
             .into_iter()
@@ -2080,6 +2568,8 @@ impl CertAuthInfo {
             resource_classes,
             children,
             suspended_children,
+            keyroll_policy,
+            suspension_policy,
         }
     }
 
@@ -2114,6 +2604,14 @@ impl CertAuthInfo {
     pub fn suspended_children(&self) -> &Vec<ChildHandle> {
         &self.suspended_children
     }
+
+    pub fn keyroll_policy(&self) -> Option<&KeyRollPolicy> {
+        self.keyroll_policy.as_ref()
+    }
+
+    pub fn suspension_policy(&self) -> Option<&ChildSuspensionPolicy> {
+        self.suspension_policy.as_ref()
+    }
 }
 
 impl fmt::Display for CertAuthInfo {
@@ -2162,6 +2660,15 @@ impl fmt::Display for CertAuthInfo {
             writeln!(f, "{}", rc.keys())?;
         }
 
+        match self.keyroll_policy() {
+            Some(policy) => writeln!(f, "Key roll policy: {}", policy)?,
+            None => writeln!(f, "Key roll policy: <none>, keys are rolled manually")?,
+        }
+        if let Some(policy) = self.suspension_policy() {
+            writeln!(f, "Suspension policy for inactive children: {}", policy)?;
+        }
+        writeln!(f)?;
+
         writeln!(f, "Children:")?;
         if !self.children().is_empty() {
             for child_handle in self.children() {
@@ -2387,6 +2894,9 @@ impl fmt::Display for AllCertAuthIssues {
                         )?;
                     }
                 }
+                if let Some(keyroll_issue) = issues.keyroll_issue() {
+                    writeln!(f, "   Key Roll Issue: {}", keyroll_issue)?;
+                }
             }
         }
         Ok(())
@@ -2399,6 +2909,12 @@ impl fmt::Display for AllCertAuthIssues {
 pub struct CertAuthIssues {
     repo_issue: Option<ErrorResponse>,
     parent_issues: Vec<CertAuthParentIssue>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    keyroll_issue: Option<ErrorResponse>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    child_issues: Vec<CertAuthChildIssue>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    repo_mirror_issues: Vec<CertAuthRepoMirrorIssue>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -2407,11 +2923,26 @@ pub struct CertAuthParentIssue {
     pub issue: ErrorResponse,
 }
 
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CertAuthChildIssue {
+    pub child: ChildHandle,
+    pub issue: ErrorResponse,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CertAuthRepoMirrorIssue {
+    pub mirror: RepositoryMirrorName,
+    pub issue: ErrorResponse,
+}
+
 impl Default for CertAuthIssues {
     fn default() -> Self {
         CertAuthIssues {
             repo_issue: None,
             parent_issues: vec![],
+            keyroll_issue: None,
+            child_issues: vec![],
+            repo_mirror_issues: vec![],
         }
     }
 }
@@ -2434,8 +2965,38 @@ impl CertAuthIssues {
         &self.parent_issues
     }
 
+    pub fn add_keyroll_issue(&mut self, issue: ErrorResponse) {
+        self.keyroll_issue = Some(issue);
+    }
+
+    pub fn keyroll_issue(&self) -> Option<&ErrorResponse> {
+        self.keyroll_issue.as_ref()
+    }
+
+    pub fn add_child_issue(&mut self, child: ChildHandle, issue: ErrorResponse) {
+        let child_issue = CertAuthChildIssue { child, issue };
+        self.child_issues.push(child_issue);
+    }
+
+    pub fn child_issues(&self) -> &Vec<CertAuthChildIssue> {
+        &self.child_issues
+    }
+
+    pub fn add_repo_mirror_issue(&mut self, mirror: RepositoryMirrorName, issue: ErrorResponse) {
+        let mirror_issue = CertAuthRepoMirrorIssue { mirror, issue };
+        self.repo_mirror_issues.push(mirror_issue);
+    }
+
+    pub fn repo_mirror_issues(&self) -> &Vec<CertAuthRepoMirrorIssue> {
+        &self.repo_mirror_issues
+    }
+
     pub fn is_empty(&self) -> bool {
-        self.repo_issue.is_none() && self.parent_issues.is_empty()
+        self.repo_issue.is_none()
+            && self.parent_issues.is_empty()
+            && self.keyroll_issue.is_none()
+            && self.child_issues.is_empty()
+            && self.repo_mirror_issues.is_empty()
     }
 }
 
@@ -2447,12 +3008,25 @@ impl fmt::Display for CertAuthIssues {
             if let Some(repo_issue) = self.repo_issue() {
                 writeln!(f, "Repository Issue: {}", repo_issue)?;
             }
+            for mirror_issue in self.repo_mirror_issues().iter() {
+                writeln!(
+                    f,
+                    "Repository mirror '{}' has issue: {}",
+                    mirror_issue.mirror, mirror_issue.issue
+                )?;
+            }
             let parent_issues = self.parent_issues();
             if !parent_issues.is_empty() {
                 for parent_issue in parent_issues.iter() {
                     writeln!(f, "Parent '{}' has issue: {}", parent_issue.parent, parent_issue.issue)?;
                 }
             }
+            if let Some(keyroll_issue) = self.keyroll_issue() {
+                writeln!(f, "Key Roll Issue: {}", keyroll_issue)?;
+            }
+            for child_issue in self.child_issues().iter() {
+                writeln!(f, "Child '{}' has issue: {}", child_issue.child, child_issue.issue)?;
+            }
         }
         Ok(())
     }
@@ -2882,6 +3456,17 @@ mod test {
         assert_eq!(expected_diff, diff);
     }
 
+    #[test]
+    fn undelegated_resources() {
+        let held = ResourceSet::from_strs("AS65000-AS65003", "10.0.0.0/8", "2001:db8::/32").unwrap();
+        let delegated = ResourceSet::from_strs("AS65001", "10.0.0.0/9", "2001:db8::/32").unwrap();
+
+        let undelegated = UndelegatedResources::new(held, delegated);
+
+        let expected = ResourceSet::from_strs("AS65000, AS65002-AS65003", "10.128.0.0/9", "").unwrap();
+        assert_eq!(&expected, undelegated.undelegated());
+    }
+
     #[test]
     fn serde_cert_auth_issues() {
         let mut issues = CertAuthIssues::default();
@@ -7,8 +7,9 @@ use rpki::repository::{crypto::KeyIdentifier, x509::Time};
 use crate::{
     commons::{
         api::{
-            ArgKey, ArgVal, AspaCustomer, AspaProvidersUpdate, ChildHandle, Handle, Label, Message, ParentHandle,
-            PublisherHandle, RequestResourceLimit, ResourceClassName, ResourceSet, RevocationRequest,
+            ArgKey, ArgVal, AspaCustomer, AspaProvidersUpdate, ChildHandle, ChildSuspendReason, ChildSuspensionPolicy,
+            Handle, KeyRollPolicy, Label, Message, ParentHandle, PublisherHandle, RepositoryMirrorName,
+            RequestResourceLimit, ResourceClassName, ResourceSet, ResourceTemplateName, RevocationRequest,
             RoaDefinitionUpdates, RtaName, StorableParentContact,
         },
         eventsourcing::{CommandKey, CommandKeyError, StoredCommand, WithStorableDetails},
@@ -403,6 +404,9 @@ impl Default for CommandHistoryCriteria {
 #[serde(tag = "type")]
 pub enum StorableCaCommand {
     MakeTrustAnchor,
+    TaKeyRollInitiate,
+    TaKeyRollActivate,
+    TaKeyRollFinish,
     ChildAdd {
         child: ChildHandle,
         ski: String,
@@ -431,10 +435,51 @@ pub enum StorableCaCommand {
     },
     ChildSuspendInactive {
         child: ChildHandle,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        reason: Option<ChildSuspendReason>,
     },
     ChildUnsuspend {
         child: ChildHandle,
     },
+    SuspensionPolicyUpdate {
+        policy: Option<ChildSuspensionPolicy>,
+    },
+    ChildSuspensionPolicyUpdate {
+        child: ChildHandle,
+        policy: Option<ChildSuspensionPolicy>,
+    },
+    ChildApplyTemplate {
+        child: ChildHandle,
+        template: ResourceTemplateName,
+    },
+    ChildShrinkNoticeSet {
+        child: ChildHandle,
+        days: Option<u32>,
+    },
+    ChildShrinkEnforce {
+        child: ChildHandle,
+    },
+    ResourceTemplateUpdate {
+        name: ResourceTemplateName,
+        resources: ResourceSet,
+    },
+    ResourceTemplateRemove {
+        name: ResourceTemplateName,
+    },
+    ChildRequestSubmit {
+        child: ChildHandle,
+        ski: String,
+    },
+    ChildRequestApprove {
+        child: ChildHandle,
+        resources: ResourceSet,
+    },
+    ChildRequestReject {
+        child: ChildHandle,
+    },
+    ChildRequestComplete {
+        child: ChildHandle,
+    },
     GenerateNewIdKey,
     AddParent {
         parent: ParentHandle,
@@ -468,6 +513,9 @@ pub enum StorableCaCommand {
     KeyRollFinish {
         resource_class_name: ResourceClassName,
     },
+    KeyRollPolicyUpdate {
+        policy: Option<KeyRollPolicy>,
+    },
     RoaDefinitionUpdates {
         updates: RoaDefinitionUpdates,
     },
@@ -486,6 +534,16 @@ pub enum StorableCaCommand {
     RepoUpdate {
         service_uri: ServiceUri,
     },
+    RepoMirrorAdd {
+        name: RepositoryMirrorName,
+        service_uri: ServiceUri,
+    },
+    RepoMirrorRemove {
+        name: RepositoryMirrorName,
+    },
+    Renamed {
+        previous: Handle,
+    },
     RtaPrepare {
         name: RtaName,
     },
@@ -508,6 +566,9 @@ impl WithStorableDetails for StorableCaCommand {
     fn summary(&self) -> CommandSummary {
         match self {
             StorableCaCommand::MakeTrustAnchor => CommandSummary::new("cmd-ca-make-ta", &self),
+            StorableCaCommand::TaKeyRollInitiate => CommandSummary::new("cmd-ta-keyroll-init", &self),
+            StorableCaCommand::TaKeyRollActivate => CommandSummary::new("cmd-ta-keyroll-activate", &self),
+            StorableCaCommand::TaKeyRollFinish => CommandSummary::new("cmd-ta-keyroll-finish", &self),
             StorableCaCommand::ChildAdd { child, ski, resources } => CommandSummary::new("cmd-ca-child-add", &self)
                 .with_child(child)
                 .with_id_ski(ski.as_ref())
@@ -532,12 +593,55 @@ impl WithStorableDetails for StorableCaCommand {
             StorableCaCommand::ChildRemove { child } => {
                 CommandSummary::new("cmd-ca-child-remove", &self).with_child(child)
             }
-            StorableCaCommand::ChildSuspendInactive { child } => {
+            StorableCaCommand::ChildSuspendInactive { child, .. } => {
                 CommandSummary::new("cmd-ca-child-suspend-inactive", &self).with_child(child)
             }
             StorableCaCommand::ChildUnsuspend { child } => {
                 CommandSummary::new("cmd-ca-child-unsuspend", &self).with_child(child)
             }
+            StorableCaCommand::SuspensionPolicyUpdate { .. } => CommandSummary::new("cmd-ca-suspension-policy", &self),
+            StorableCaCommand::ChildSuspensionPolicyUpdate { child, .. } => {
+                CommandSummary::new("cmd-ca-child-suspension-policy", &self).with_child(child)
+            }
+            StorableCaCommand::ChildApplyTemplate { child, template } => {
+                CommandSummary::new("cmd-ca-child-apply-template", &self)
+                    .with_child(child)
+                    .with_arg("template", template)
+            }
+            StorableCaCommand::ChildShrinkNoticeSet { child, days } => {
+                let summary = CommandSummary::new("cmd-ca-child-shrink-notice", &self).with_child(child);
+                match days {
+                    Some(days) => summary.with_arg("days", days),
+                    None => summary,
+                }
+            }
+            StorableCaCommand::ChildShrinkEnforce { child } => {
+                CommandSummary::new("cmd-ca-child-shrink-enforce", &self).with_child(child)
+            }
+            StorableCaCommand::ResourceTemplateUpdate { name, resources } => {
+                CommandSummary::new("cmd-ca-resource-template-update", &self)
+                    .with_arg("template", name)
+                    .with_resources(resources)
+            }
+            StorableCaCommand::ResourceTemplateRemove { name } => {
+                CommandSummary::new("cmd-ca-resource-template-remove", &self).with_arg("template", name)
+            }
+            StorableCaCommand::ChildRequestSubmit { child, ski } => {
+                CommandSummary::new("cmd-ca-child-request-submit", &self)
+                    .with_child(child)
+                    .with_id_ski(ski)
+            }
+            StorableCaCommand::ChildRequestApprove { child, resources } => {
+                CommandSummary::new("cmd-ca-child-request-approve", &self)
+                    .with_child(child)
+                    .with_resources(resources)
+            }
+            StorableCaCommand::ChildRequestReject { child } => {
+                CommandSummary::new("cmd-ca-child-request-reject", &self).with_child(child)
+            }
+            StorableCaCommand::ChildRequestComplete { child } => {
+                CommandSummary::new("cmd-ca-child-request-complete", &self).with_child(child)
+            }
             StorableCaCommand::ChildRevokeKey { child, revoke_req } => {
                 CommandSummary::new("cmd-ca-child-revoke", &self)
                     .with_child(child)
@@ -582,6 +686,7 @@ impl WithStorableDetails for StorableCaCommand {
             StorableCaCommand::KeyRollFinish { resource_class_name } => {
                 CommandSummary::new("cmd-ca-keyroll-finish", &self).with_rcn(resource_class_name)
             }
+            StorableCaCommand::KeyRollPolicyUpdate { .. } => CommandSummary::new("cmd-ca-keyroll-policy", &self),
 
             // ROA
             StorableCaCommand::RoaDefinitionUpdates { updates } => CommandSummary::new("cmd-ca-roas-updated", &self)
@@ -597,6 +702,17 @@ impl WithStorableDetails for StorableCaCommand {
             StorableCaCommand::RepoUpdate { service_uri } => {
                 CommandSummary::new("cmd-ca-repo-update", &self).with_service_uri(service_uri)
             }
+            StorableCaCommand::RepoMirrorAdd { name, service_uri } => {
+                CommandSummary::new("cmd-ca-repo-mirror-add", &self)
+                    .with_arg("mirror", name)
+                    .with_service_uri(service_uri)
+            }
+            StorableCaCommand::RepoMirrorRemove { name } => {
+                CommandSummary::new("cmd-ca-repo-mirror-remove", &self).with_arg("mirror", name)
+            }
+            StorableCaCommand::Renamed { previous } => {
+                CommandSummary::new("cmd-ca-renamed", &self).with_arg("previous", previous)
+            }
 
             StorableCaCommand::ReissueBeforeExpiring => CommandSummary::new("cmd-ca-reissue-before-expiring", &self),
             StorableCaCommand::ForceReissue => CommandSummary::new("cmd-ca-force-reissue", &self),
@@ -623,6 +739,9 @@ impl fmt::Display for StorableCaCommand {
             // Becoming a trust anchor
             // ------------------------------------------------------------
             StorableCaCommand::MakeTrustAnchor => write!(f, "Turn into Trust Anchor"),
+            StorableCaCommand::TaKeyRollInitiate => write!(f, "Initiate Trust Anchor key roll"),
+            StorableCaCommand::TaKeyRollActivate => write!(f, "Activate Trust Anchor successor key"),
+            StorableCaCommand::TaKeyRollFinish => write!(f, "Retire Trust Anchor predecessor key"),
 
             // ------------------------------------------------------------
             // Being a parent
@@ -653,12 +772,52 @@ impl fmt::Display for StorableCaCommand {
             StorableCaCommand::ChildRemove { child } => {
                 write!(f, "Remove child '{}' and revoke & remove its certs", child)
             }
-            StorableCaCommand::ChildSuspendInactive { child } => {
-                write!(f, "Suspend inactive child '{}': stop publishing its certs", child)
-            }
+            StorableCaCommand::ChildSuspendInactive { child, reason } => match reason {
+                Some(reason) => write!(f, "Suspend child '{}' ({}): stop publishing its certs", child, reason),
+                None => write!(f, "Suspend inactive child '{}': stop publishing its certs", child),
+            },
             StorableCaCommand::ChildUnsuspend { child } => {
                 write!(f, "Unsuspend child '{}': publish its unexpired certs", child)
             }
+            StorableCaCommand::SuspensionPolicyUpdate { policy } => match policy {
+                Some(policy) => write!(f, "Set suspension policy for inactive children: {}", policy),
+                None => write!(f, "Remove suspension policy for inactive children"),
+            },
+            StorableCaCommand::ChildSuspensionPolicyUpdate { child, policy } => match policy {
+                Some(policy) => write!(f, "Set suspension policy for child '{}': {}", child, policy),
+                None => write!(f, "Remove suspension policy for child '{}'", child),
+            },
+            StorableCaCommand::ChildApplyTemplate { child, template } => {
+                write!(f, "Use resource template '{}' for child '{}'", template, child)
+            }
+            StorableCaCommand::ChildShrinkNoticeSet { child, days } => match days {
+                Some(days) => write!(
+                    f,
+                    "Set notice period for resource removals from child '{}' to {} days",
+                    child, days
+                ),
+                None => write!(f, "Remove notice period for resource removals from child '{}'", child),
+            },
+            StorableCaCommand::ChildShrinkEnforce { child } => {
+                write!(f, "Remove resources from child '{}' after notice period", child)
+            }
+            StorableCaCommand::ResourceTemplateUpdate { name, resources } => {
+                write!(f, "Update resource template '{}' to: {}", name, resources.summary())
+            }
+            StorableCaCommand::ResourceTemplateRemove { name } => write!(f, "Remove resource template '{}'", name),
+            StorableCaCommand::ChildRequestSubmit { child, ski } => {
+                write!(f, "Receive request for child '{}' with RFC8183 key '{}'", child, ski)
+            }
+            StorableCaCommand::ChildRequestApprove { child, resources } => write!(
+                f,
+                "Approve request for child '{}' with resources '{}'",
+                child,
+                resources.summary()
+            ),
+            StorableCaCommand::ChildRequestReject { child } => write!(f, "Reject request for child '{}'", child),
+            StorableCaCommand::ChildRequestComplete { child } => {
+                write!(f, "Hand out parent response to requesting child '{}'", child)
+            }
 
             // ------------------------------------------------------------
             // Being a child (only allowed if this CA is not self-signed)
@@ -722,6 +881,10 @@ impl fmt::Display for StorableCaCommand {
             StorableCaCommand::KeyRollFinish { resource_class_name } => {
                 write!(f, "Retire old revoked key in RC '{}'", resource_class_name)
             }
+            StorableCaCommand::KeyRollPolicyUpdate { policy } => match policy {
+                Some(policy) => write!(f, "Set key roll policy: {}", policy),
+                None => write!(f, "Remove key roll policy"),
+            },
 
             // ------------------------------------------------------------
             // ROA Support
@@ -766,6 +929,15 @@ impl fmt::Display for StorableCaCommand {
             // Publishing
             // ------------------------------------------------------------
             StorableCaCommand::RepoUpdate { service_uri } => write!(f, "Update repo to server at: {}", service_uri),
+            StorableCaCommand::RepoMirrorAdd { name, service_uri } => {
+                write!(f, "Add repo mirror '{}' at server: {}", name, service_uri)
+            }
+            StorableCaCommand::RepoMirrorRemove { name } => write!(f, "Remove repo mirror '{}'", name),
+
+            // ------------------------------------------------------------
+            // Rename
+            // ------------------------------------------------------------
+            StorableCaCommand::Renamed { previous } => write!(f, "Renamed CA from '{}'", previous),
 
             // ------------------------------------------------------------
             // RTA
@@ -815,3 +987,140 @@ impl fmt::Display for StorableRepositoryCommand {
         }
     }
 }
+
+//------------ StorableTrustAnchorProxyCommand -------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum StorableTrustAnchorProxyCommand {
+    AddRepository {
+        service_uri: ServiceUri,
+    },
+    AddSigner {
+        ski: String,
+    },
+    AddChild {
+        child: ChildHandle,
+        ski: String,
+        resources: ResourceSet,
+    },
+    AddChildIssueRequest {
+        child: ChildHandle,
+        resource_class_name: ResourceClassName,
+        ki: KeyIdentifier,
+    },
+    AddChildRevokeRequest {
+        child: ChildHandle,
+        resource_class_name: ResourceClassName,
+        ki: KeyIdentifier,
+    },
+    MakeSignerRequest,
+    ProcessSignerResponse {
+        nonce: String,
+    },
+}
+
+impl WithStorableDetails for StorableTrustAnchorProxyCommand {
+    fn summary(&self) -> CommandSummary {
+        match self {
+            StorableTrustAnchorProxyCommand::AddRepository { service_uri } => {
+                CommandSummary::new("ta-proxy-repo-add", &self).with_service_uri(service_uri)
+            }
+            StorableTrustAnchorProxyCommand::AddSigner { ski } => {
+                CommandSummary::new("ta-proxy-signer-add", &self).with_id_ski(ski)
+            }
+            StorableTrustAnchorProxyCommand::AddChild { child, ski, resources } => {
+                CommandSummary::new("ta-proxy-child-add", &self)
+                    .with_child(child)
+                    .with_id_ski(ski)
+                    .with_resources(resources)
+            }
+            StorableTrustAnchorProxyCommand::AddChildIssueRequest {
+                child,
+                resource_class_name,
+                ki,
+            } => CommandSummary::new("ta-proxy-child-issue-request", &self)
+                .with_child(child)
+                .with_rcn(resource_class_name)
+                .with_key(ki),
+            StorableTrustAnchorProxyCommand::AddChildRevokeRequest {
+                child,
+                resource_class_name,
+                ki,
+            } => CommandSummary::new("ta-proxy-child-revoke-request", &self)
+                .with_child(child)
+                .with_rcn(resource_class_name)
+                .with_key(ki),
+            StorableTrustAnchorProxyCommand::MakeSignerRequest => {
+                CommandSummary::new("ta-proxy-signer-request-make", &self)
+            }
+            StorableTrustAnchorProxyCommand::ProcessSignerResponse { nonce } => {
+                CommandSummary::new("ta-proxy-signer-response-process", &self).with_arg("nonce", nonce)
+            }
+        }
+    }
+}
+
+impl fmt::Display for StorableTrustAnchorProxyCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorableTrustAnchorProxyCommand::AddRepository { service_uri } => {
+                write!(f, "Added repository with service uri: {}", service_uri)
+            }
+            StorableTrustAnchorProxyCommand::AddSigner { ski } => {
+                write!(f, "Added Trust Anchor signer with ID key '{}'", ski)
+            }
+            StorableTrustAnchorProxyCommand::AddChild { child, ski, resources } => write!(
+                f,
+                "Added child '{}' with RFC8183 key '{}' and resources '{}'",
+                child, ski, resources
+            ),
+            StorableTrustAnchorProxyCommand::AddChildIssueRequest {
+                child,
+                resource_class_name,
+                ki,
+            } => write!(
+                f,
+                "Queued certificate request from child '{}' for key '{}' in RC '{}'",
+                child, ki, resource_class_name
+            ),
+            StorableTrustAnchorProxyCommand::AddChildRevokeRequest {
+                child,
+                resource_class_name,
+                ki,
+            } => write!(
+                f,
+                "Queued revocation request from child '{}' for key '{}' in RC '{}'",
+                child, ki, resource_class_name
+            ),
+            StorableTrustAnchorProxyCommand::MakeSignerRequest => write!(f, "Created request for Trust Anchor signer"),
+            StorableTrustAnchorProxyCommand::ProcessSignerResponse { nonce } => {
+                write!(f, "Processed response from Trust Anchor signer for request '{}'", nonce)
+            }
+        }
+    }
+}
+
+//------------ StorableTrustAnchorSignerCommand ------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum StorableTrustAnchorSignerCommand {
+    ProcessRequest,
+}
+
+impl WithStorableDetails for StorableTrustAnchorSignerCommand {
+    fn summary(&self) -> CommandSummary {
+        match self {
+            StorableTrustAnchorSignerCommand::ProcessRequest => CommandSummary::new("ta-signer-request-process", &self),
+        }
+    }
+}
+
+impl fmt::Display for StorableTrustAnchorSignerCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorableTrustAnchorSignerCommand::ProcessRequest => write!(f, "Processed request from Trust Anchor proxy"),
+        }
+    }
+}
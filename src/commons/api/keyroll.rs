@@ -0,0 +1,529 @@
+//! Policies for automated key rollovers, the status of key rolls, and key
+//! rolls of the embedded Trust Anchor.
+
+use std::fmt;
+
+use chrono::Duration;
+
+use rpki::repository::crypto::KeyIdentifier;
+
+use crate::commons::api::{Handle, ParentHandle, ResourceClassName, TaCertDetails, Timestamp};
+
+//------------ KeyRollPolicy -------------------------------------------------
+
+/// Defines when the keys of a CA are rolled automatically. A roll is started
+/// when the current key is older than the maximum age. The new key is then
+/// staged for at least the staging period before it is activated, following
+/// RFC 6489. The old key is retired when the parent confirms its revocation.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct KeyRollPolicy {
+    max_age_days: u32,
+    staging_hours: u32,
+
+    // Only activate a new key after the repository confirmed that its
+    // manifest and CRL were published.
+    #[serde(default = "default_require_publication")]
+    require_publication: bool,
+}
+
+fn default_require_publication() -> bool {
+    true
+}
+
+impl KeyRollPolicy {
+    pub fn new(max_age_days: u32, staging_hours: u32, require_publication: bool) -> Self {
+        KeyRollPolicy {
+            max_age_days,
+            staging_hours,
+            require_publication,
+        }
+    }
+
+    pub fn max_age_days(&self) -> u32 {
+        self.max_age_days
+    }
+
+    pub fn staging_hours(&self) -> u32 {
+        self.staging_hours
+    }
+
+    pub fn require_publication(&self) -> bool {
+        self.require_publication
+    }
+
+    pub fn max_age(&self) -> Duration {
+        Duration::days(self.max_age_days.into())
+    }
+
+    pub fn staging(&self) -> Duration {
+        Duration::hours(self.staging_hours.into())
+    }
+}
+
+impl fmt::Display for KeyRollPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "roll keys after {} days, stage new keys for {} hours",
+            self.max_age_days, self.staging_hours
+        )?;
+        if self.require_publication {
+            write!(f, ", activate only after publication is confirmed")?;
+        }
+        Ok(())
+    }
+}
+
+//------------ KeyRollStatus -------------------------------------------------
+
+/// Shows the state of the key roll in each resource class of a CA, and
+/// anything that currently blocks its next step.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct KeyRollStatus {
+    ca: Handle,
+    resource_classes: Vec<ResourceClassKeyRollStatus>,
+}
+
+impl KeyRollStatus {
+    pub fn new(ca: Handle, resource_classes: Vec<ResourceClassKeyRollStatus>) -> Self {
+        KeyRollStatus { ca, resource_classes }
+    }
+
+    pub fn ca(&self) -> &Handle {
+        &self.ca
+    }
+
+    pub fn resource_classes(&self) -> &Vec<ResourceClassKeyRollStatus> {
+        &self.resource_classes
+    }
+
+    pub fn resource_classes_mut(&mut self) -> impl Iterator<Item = &mut ResourceClassKeyRollStatus> {
+        self.resource_classes.iter_mut()
+    }
+
+    /// Returns the reason why new keys cannot be activated safely yet, if any.
+    /// Resource classes which are not rolling are ignored, but activation is
+    /// refused if none of them has a new key.
+    pub fn activation_issue(&self) -> Option<String> {
+        let rolling: Vec<&ResourceClassKeyRollStatus> = self
+            .resource_classes
+            .iter()
+            .filter(|rc| rc.phase == KeyRollPhase::RollPending || rc.phase == KeyRollPhase::RollNew)
+            .collect();
+
+        if rolling.is_empty() {
+            return Some("there is no new key to activate".to_string());
+        }
+
+        let issues: Vec<String> = rolling
+            .into_iter()
+            .flat_map(|rc| {
+                rc.blockers
+                    .iter()
+                    .map(move |blocker| format!("resource class '{}': {}", rc.name, blocker))
+            })
+            .collect();
+
+        if issues.is_empty() {
+            None
+        } else {
+            Some(issues.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for KeyRollStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Key roll status for CA '{}':", self.ca)?;
+        for rc in &self.resource_classes {
+            writeln!(f)?;
+            write!(f, "{}", rc)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ ResourceClassKeyRollStatus ------------------------------------
+
+/// The key roll status of a single resource class.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResourceClassKeyRollStatus {
+    name: ResourceClassName,
+    parent: ParentHandle,
+    phase: KeyRollPhase,
+    phase_since: Timestamp,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    current_key: Option<KeyIdentifier>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    new_key: Option<KeyIdentifier>,
+    new_key_certified: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    old_key: Option<KeyIdentifier>,
+
+    // Only known while a new key is staged.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    staged_objects_published: Option<bool>,
+
+    blockers: Vec<KeyRollBlocker>,
+}
+
+impl ResourceClassKeyRollStatus {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: ResourceClassName,
+        parent: ParentHandle,
+        phase: KeyRollPhase,
+        phase_since: Timestamp,
+        current_key: Option<KeyIdentifier>,
+        new_key: Option<KeyIdentifier>,
+        new_key_certified: bool,
+        old_key: Option<KeyIdentifier>,
+    ) -> Self {
+        ResourceClassKeyRollStatus {
+            name,
+            parent,
+            phase,
+            phase_since,
+            current_key,
+            new_key,
+            new_key_certified,
+            old_key,
+            staged_objects_published: None,
+            blockers: vec![],
+        }
+    }
+
+    pub fn name(&self) -> &ResourceClassName {
+        &self.name
+    }
+
+    pub fn parent(&self) -> &ParentHandle {
+        &self.parent
+    }
+
+    pub fn phase(&self) -> KeyRollPhase {
+        self.phase
+    }
+
+    pub fn phase_since(&self) -> Timestamp {
+        self.phase_since
+    }
+
+    pub fn current_key(&self) -> Option<&KeyIdentifier> {
+        self.current_key.as_ref()
+    }
+
+    pub fn new_key(&self) -> Option<&KeyIdentifier> {
+        self.new_key.as_ref()
+    }
+
+    pub fn new_key_certified(&self) -> bool {
+        self.new_key_certified
+    }
+
+    pub fn old_key(&self) -> Option<&KeyIdentifier> {
+        self.old_key.as_ref()
+    }
+
+    pub fn staged_objects_published(&self) -> Option<bool> {
+        self.staged_objects_published
+    }
+
+    pub fn set_staged_objects_published(&mut self, published: bool) {
+        self.staged_objects_published = Some(published);
+    }
+
+    pub fn blockers(&self) -> &Vec<KeyRollBlocker> {
+        &self.blockers
+    }
+
+    pub fn add_blocker(&mut self, blocker: KeyRollBlocker) {
+        self.blockers.push(blocker);
+    }
+}
+
+impl fmt::Display for ResourceClassKeyRollStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn key_opt(key: Option<&KeyIdentifier>) -> String {
+            key.map(|k| k.to_string()).unwrap_or_else(|| "<none>".to_string())
+        }
+
+        writeln!(f, "Resource class: {}", self.name)?;
+        writeln!(f, "  Parent:        {}", self.parent)?;
+        writeln!(
+            f,
+            "  Phase:         {} (since {})",
+            self.phase,
+            self.phase_since.to_rfc3339()
+        )?;
+        writeln!(f, "  Current key:   {}", key_opt(self.current_key()))?;
+        match self.new_key() {
+            None => writeln!(f, "  New key:       <none>")?,
+            Some(key) if self.new_key_certified => writeln!(f, "  New key:       {} (certified)", key)?,
+            Some(key) => writeln!(f, "  New key:       {} (awaiting certificate)", key)?,
+        }
+        writeln!(f, "  Old key:       {}", key_opt(self.old_key()))?;
+        if let Some(published) = self.staged_objects_published {
+            writeln!(
+                f,
+                "  Staged objects published: {}",
+                if published { "yes" } else { "no" }
+            )?;
+        }
+        if self.blockers.is_empty() {
+            writeln!(f, "  Blockers:      <none>")?;
+        } else {
+            writeln!(f, "  Blockers:")?;
+            for blocker in &self.blockers {
+                writeln!(f, "    - {}", blocker)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//------------ KeyRollPhase --------------------------------------------------
+
+/// The phase of a key roll in a resource class, following its key state.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyRollPhase {
+    /// A new resource class waits for the certificate for its first key.
+    Pending,
+    /// There is only a current key, no roll is in progress.
+    Active,
+    /// A new key was created and waits for a certificate from the parent.
+    RollPending,
+    /// The new key is certified and its objects are staged for publication.
+    RollNew,
+    /// The new key is current, the old key waits for its revocation.
+    RollOld,
+}
+
+impl fmt::Display for KeyRollPhase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            KeyRollPhase::Pending => "pending",
+            KeyRollPhase::Active => "active",
+            KeyRollPhase::RollPending => "roll pending",
+            KeyRollPhase::RollNew => "roll new",
+            KeyRollPhase::RollOld => "roll old",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+//------------ KeyRollBlocker ------------------------------------------------
+
+/// Something that prevents the next step of a key roll in a resource class.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "blocker")]
+pub enum KeyRollBlocker {
+    AwaitingCertificate,
+    ParentUnreachable { parent: ParentHandle, reason: String },
+    Staging { until: Timestamp },
+    AwaitingPublication,
+    RepositoryUnreachable { reason: String },
+    AwaitingRevocation,
+}
+
+impl fmt::Display for KeyRollBlocker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyRollBlocker::AwaitingCertificate => write!(f, "waiting for the parent to certify the new key"),
+            KeyRollBlocker::ParentUnreachable { parent, reason } => {
+                write!(f, "parent '{}' is unreachable: {}", parent, reason)
+            }
+            KeyRollBlocker::Staging { until } => write!(f, "staging period ends at {}", until.to_rfc3339()),
+            KeyRollBlocker::AwaitingPublication => write!(f, "waiting for publication of the staged objects"),
+            KeyRollBlocker::RepositoryUnreachable { reason } => write!(f, "repository is unreachable: {}", reason),
+            KeyRollBlocker::AwaitingRevocation => write!(f, "waiting for the parent to revoke the old key"),
+        }
+    }
+}
+
+//------------ TaKeyRoll -----------------------------------------------------
+
+/// A key roll of the embedded Trust Anchor, following RFC 9691. While a roll
+/// is in progress both keys publish a Trust Anchor Key (TAK) object which
+/// refers to the other key, so that relying parties can update their TAL.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "phase")]
+pub enum TaKeyRoll {
+    /// A successor key and certificate were created, the current key is
+    /// still used to sign the certificates of children.
+    Staging { successor: TaCertDetails, since: Timestamp },
+
+    /// The successor became the current key. The predecessor is still
+    /// published until it is retired.
+    Activated {
+        predecessor: TaCertDetails,
+        since: Timestamp,
+    },
+}
+
+impl TaKeyRoll {
+    pub fn since(&self) -> Timestamp {
+        match self {
+            TaKeyRoll::Staging { since, .. } | TaKeyRoll::Activated { since, .. } => *since,
+        }
+    }
+}
+
+//------------ TaKeyRollStatus -----------------------------------------------
+
+/// Shows the state of the key roll of the embedded Trust Anchor, and when
+/// the next phase may be started.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TaKeyRollStatus {
+    current_key: KeyIdentifier,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    roll: Option<TaKeyRoll>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    next_phase_after: Option<Timestamp>,
+}
+
+impl TaKeyRollStatus {
+    pub fn new(current_key: KeyIdentifier, roll: Option<TaKeyRoll>, transition_days: i64) -> Self {
+        let next_phase_after = roll.as_ref().map(|roll| roll.since() + Duration::days(transition_days));
+
+        TaKeyRollStatus {
+            current_key,
+            roll,
+            next_phase_after,
+        }
+    }
+
+    pub fn current_key(&self) -> &KeyIdentifier {
+        &self.current_key
+    }
+
+    pub fn roll(&self) -> Option<&TaKeyRoll> {
+        self.roll.as_ref()
+    }
+
+    pub fn next_phase_after(&self) -> Option<Timestamp> {
+        self.next_phase_after
+    }
+}
+
+impl fmt::Display for TaKeyRollStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Trust Anchor key roll status:")?;
+        writeln!(f, "  Current key:      {}", self.current_key)?;
+        match &self.roll {
+            None => writeln!(f, "  Phase:            no key roll in progress")?,
+            Some(TaKeyRoll::Staging { successor, since }) => {
+                writeln!(f, "  Phase:            successor staged (since {})", since.to_rfc3339())?;
+                writeln!(f, "  Successor key:    {}", successor.cert().subject_key_identifier())?;
+            }
+            Some(TaKeyRoll::Activated { predecessor, since }) => {
+                writeln!(
+                    f,
+                    "  Phase:            successor activated (since {})",
+                    since.to_rfc3339()
+                )?;
+                writeln!(f, "  Predecessor key:  {}", predecessor.cert().subject_key_identifier())?;
+            }
+        }
+        match (&self.roll, self.next_phase_after) {
+            (Some(TaKeyRoll::Staging { .. }), Some(after)) => writeln!(
+                f,
+                "  Next step:        activate the successor after {}",
+                after.to_rfc3339()
+            )?,
+            (Some(TaKeyRoll::Activated { .. }), Some(after)) => writeln!(
+                f,
+                "  Next step:        retire the predecessor after {}",
+                after.to_rfc3339()
+            )?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn rc_status(name: u32, phase: KeyRollPhase, blockers: Vec<KeyRollBlocker>) -> ResourceClassKeyRollStatus {
+        let mut status = ResourceClassKeyRollStatus::new(
+            ResourceClassName::from(name),
+            ParentHandle::from_str("parent").unwrap(),
+            phase,
+            Timestamp::now(),
+            None,
+            None,
+            phase == KeyRollPhase::RollNew,
+            None,
+        );
+        for blocker in blockers {
+            status.add_blocker(blocker);
+        }
+        status
+    }
+
+    fn status(resource_classes: Vec<ResourceClassKeyRollStatus>) -> KeyRollStatus {
+        KeyRollStatus::new(Handle::from_str("ca").unwrap(), resource_classes)
+    }
+
+    #[test]
+    fn activation_refused_while_staging_or_unpublished() {
+        let staging = KeyRollBlocker::Staging {
+            until: Timestamp::now_plus_hours(1),
+        };
+
+        let issue = status(vec![rc_status(0, KeyRollPhase::RollNew, vec![staging])])
+            .activation_issue()
+            .unwrap();
+        assert!(issue.contains("resource class '0': staging period ends at"));
+
+        let issue = status(vec![
+            rc_status(0, KeyRollPhase::RollNew, vec![]),
+            rc_status(1, KeyRollPhase::RollNew, vec![KeyRollBlocker::AwaitingPublication]),
+        ])
+        .activation_issue()
+        .unwrap();
+        assert_eq!(
+            "resource class '1': waiting for publication of the staged objects",
+            issue
+        );
+
+        let issue = status(vec![rc_status(
+            0,
+            KeyRollPhase::RollPending,
+            vec![KeyRollBlocker::AwaitingCertificate],
+        )])
+        .activation_issue()
+        .unwrap();
+        assert!(issue.contains("waiting for the parent to certify the new key"));
+    }
+
+    #[test]
+    fn activation_allowed_without_blockers() {
+        // Blockers of resource classes which are not rolling do not matter
+        let status = status(vec![
+            rc_status(0, KeyRollPhase::RollNew, vec![]),
+            rc_status(1, KeyRollPhase::RollOld, vec![KeyRollBlocker::AwaitingRevocation]),
+        ]);
+        assert_eq!(None, status.activation_issue());
+    }
+
+    #[test]
+    fn activation_refused_without_new_key() {
+        assert_eq!(
+            Some("there is no new key to activate".to_string()),
+            status(vec![rc_status(0, KeyRollPhase::Active, vec![])]).activation_issue()
+        );
+    }
+}
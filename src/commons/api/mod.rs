@@ -6,21 +6,45 @@ pub use self::admin::*;
 mod aspa;
 pub use self::aspa::*;
 
+mod audit;
+pub use self::audit::*;
+
 mod ca;
 pub use self::ca::*;
 
 mod history;
 pub use self::history::*;
 
+mod keyroll;
+pub use self::keyroll::*;
+
+mod policy;
+pub use self::policy::*;
+
 mod provisioning;
 pub use self::provisioning::*;
 
 mod publication;
 pub use self::publication::*;
 
+mod roafile;
+pub use self::roafile::*;
+
 mod roas;
 pub use self::roas::*;
 
+mod suspension;
+pub use self::suspension::*;
+
+mod tenants;
+pub use self::tenants::*;
+
+mod tokens;
+pub use self::tokens::*;
+
+mod users;
+pub use self::users::*;
+
 pub mod rrdp;
 
 use std::{collections::HashMap, fmt, sync::Arc};
@@ -277,6 +301,18 @@ impl ErrorResponse {
         self.with_arg("ca", ca)
     }
 
+    pub fn with_token_name(self, name: &str) -> Self {
+        self.with_arg("token", name)
+    }
+
+    pub fn with_tenant_name(self, name: &str) -> Self {
+        self.with_arg("tenant", name)
+    }
+
+    pub fn with_user_id(self, id: &str) -> Self {
+        self.with_arg("user", id)
+    }
+
     pub fn with_parent(self, parent: &ParentHandle) -> Self {
         self.with_arg("parent", parent)
     }
@@ -317,6 +353,18 @@ impl ErrorResponse {
         self.with_arg("class_name", class_name)
     }
 
+    pub fn with_resources(self, resources: &ResourceSet) -> Self {
+        self.with_arg("resources", resources)
+    }
+
+    pub fn with_template(self, name: &str) -> Self {
+        self.with_arg("template", name)
+    }
+
+    pub fn with_mirror(self, name: &str) -> Self {
+        self.with_arg("mirror", name)
+    }
+
     pub fn label(&self) -> &str {
         &self.label
     }
@@ -0,0 +1,125 @@
+//! Explanations of authorization policy decisions.
+
+use std::{collections::HashMap, fmt};
+
+use crate::commons::api::Handle;
+
+//------------ PolicyExplainRequest ------------------------------------------
+
+/// Asks whether an actor with the given attributes may perform the given
+/// permission, optionally on a CA.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PolicyExplainRequest {
+    actor: String,
+    #[serde(default)]
+    attributes: HashMap<String, String>,
+    permission: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    ca: Option<Handle>,
+}
+
+impl PolicyExplainRequest {
+    pub fn new(actor: String, attributes: HashMap<String, String>, permission: String, ca: Option<Handle>) -> Self {
+        PolicyExplainRequest {
+            actor,
+            attributes,
+            permission,
+            ca,
+        }
+    }
+
+    pub fn actor(&self) -> &str {
+        &self.actor
+    }
+
+    pub fn attributes(&self) -> &HashMap<String, String> {
+        &self.attributes
+    }
+
+    pub fn permission(&self) -> &str {
+        &self.permission
+    }
+
+    pub fn ca(&self) -> Option<&Handle> {
+        self.ca.as_ref()
+    }
+}
+
+//------------ PolicyRuleResult ----------------------------------------------
+
+/// Whether a rule of the policy holds for the actor, permission and CA.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PolicyRuleResult {
+    rule: String,
+    holds: bool,
+}
+
+impl PolicyRuleResult {
+    pub fn new(rule: String, holds: bool) -> Self {
+        PolicyRuleResult { rule, holds }
+    }
+
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+
+    pub fn holds(&self) -> bool {
+        self.holds
+    }
+}
+
+//------------ PolicyExplanation ---------------------------------------------
+
+/// The decision of the policy, the rule that decided it, and the results of
+/// the rules of the built-in policy that were evaluated to find it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PolicyExplanation {
+    allowed: bool,
+    decided_by: String,
+    roles: Vec<String>,
+    rules: Vec<PolicyRuleResult>,
+}
+
+impl PolicyExplanation {
+    pub fn new(allowed: bool, decided_by: String, roles: Vec<String>, rules: Vec<PolicyRuleResult>) -> Self {
+        PolicyExplanation {
+            allowed,
+            decided_by,
+            roles,
+            rules,
+        }
+    }
+
+    pub fn allowed(&self) -> bool {
+        self.allowed
+    }
+
+    pub fn decided_by(&self) -> &str {
+        &self.decided_by
+    }
+
+    pub fn roles(&self) -> &Vec<String> {
+        &self.roles
+    }
+
+    pub fn rules(&self) -> &Vec<PolicyRuleResult> {
+        &self.rules
+    }
+}
+
+impl fmt::Display for PolicyExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Allowed: {}", if self.allowed { "yes" } else { "no" })?;
+        writeln!(f, "Decided by: {}", self.decided_by)?;
+        if self.roles.is_empty() {
+            writeln!(f, "Roles: none")?;
+        } else {
+            writeln!(f, "Roles: {}", self.roles.join(", "))?;
+        }
+        writeln!(f, "Rules:")?;
+        for rule in &self.rules {
+            writeln!(f, "  {}: {}", rule.rule, if rule.holds { "holds" } else { "fails" })?;
+        }
+        Ok(())
+    }
+}
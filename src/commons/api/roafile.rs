@@ -0,0 +1,326 @@
+//! Import and export ROA definitions in formats used by other tools.
+//!
+//! The CSV format uses one definition per line, with the ASN, prefix and
+//! optional max length, as used by most relying party software, e.g.:
+//!
+//!   ASN,IP Prefix,Max Length
+//!   AS64496,10.0.0.0/16,24
+//!   AS64497,2001:db8::/32,
+//!
+//! The JSON format follows the `prefixAssertions` of a SLURM file as
+//! defined in RFC 8416. The RPSL format uses `route` and `route6` objects
+//! with an `origin` attribute. Note that RPSL cannot express a max length,
+//! so it is lost on export, and definitions imported from RPSL have none.
+
+use std::{fmt, str::FromStr};
+
+use crate::commons::api::{AsNumber, AuthorizationFmtError, RoaDefinition, TypedPrefix};
+
+//------------ RoaFileFormat -------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RoaFileFormat {
+    Csv,
+    Json,
+    Rpsl,
+}
+
+impl RoaFileFormat {
+    /// Parses the ROA definitions from the content of a file in this format.
+    pub fn parse(self, s: &str) -> Result<Vec<RoaDefinition>, AuthorizationFmtError> {
+        match self {
+            RoaFileFormat::Csv => Self::parse_csv(s),
+            RoaFileFormat::Json => Self::parse_json(s),
+            RoaFileFormat::Rpsl => Self::parse_rpsl(s),
+        }
+    }
+
+    /// Writes the ROA definitions as the content of a file in this format.
+    pub fn write(self, definitions: &[RoaDefinition]) -> String {
+        match self {
+            RoaFileFormat::Csv => Self::write_csv(definitions),
+            RoaFileFormat::Json => Self::write_json(definitions),
+            RoaFileFormat::Rpsl => Self::write_rpsl(definitions),
+        }
+    }
+
+    fn parse_asn(s: &str) -> Result<AsNumber, AuthorizationFmtError> {
+        let s = s.trim();
+        let s = s.strip_prefix("AS").or_else(|| s.strip_prefix("as")).unwrap_or(s);
+        AsNumber::from_str(s)
+    }
+
+    fn parse_csv(s: &str) -> Result<Vec<RoaDefinition>, AuthorizationFmtError> {
+        let mut res = vec![];
+        for (nr, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut values = line.split(',').map(str::trim);
+
+            let asn_str = values.next().unwrap_or_default();
+            if nr == 0 && asn_str.eq_ignore_ascii_case("asn") {
+                continue; // header
+            }
+
+            let prefix_str = values
+                .next()
+                .ok_or_else(|| AuthorizationFmtError::file(&format!("missing prefix column on line {}", nr + 1)))?;
+
+            let asn = Self::parse_asn(asn_str)?;
+            let prefix = TypedPrefix::from_str(prefix_str)?;
+            let max_length = match values.next().filter(|s| !s.is_empty()) {
+                Some(max_length_str) => Some(
+                    u8::from_str(max_length_str)
+                        .map_err(|_| AuthorizationFmtError::file(&format!("invalid max length on line {}", nr + 1)))?,
+                ),
+                None => None,
+            };
+
+            res.push(RoaDefinition::new(asn, prefix, max_length));
+        }
+        Ok(res)
+    }
+
+    fn write_csv(definitions: &[RoaDefinition]) -> String {
+        let mut res = "ASN,IP Prefix,Max Length\n".to_string();
+        for def in definitions {
+            let max_length = def.max_length().map(|l| l.to_string()).unwrap_or_default();
+            res.push_str(&format!("AS{},{},{}\n", def.asn(), def.prefix(), max_length));
+        }
+        res
+    }
+
+    fn parse_json(s: &str) -> Result<Vec<RoaDefinition>, AuthorizationFmtError> {
+        let slurm: SlurmFile = serde_json::from_str(s).map_err(|e| AuthorizationFmtError::file(&e.to_string()))?;
+        Ok(slurm
+            .locally_added_assertions
+            .prefix_assertions
+            .into_iter()
+            .map(|assertion| RoaDefinition::new(assertion.asn, assertion.prefix, assertion.max_prefix_length))
+            .collect())
+    }
+
+    fn write_json(definitions: &[RoaDefinition]) -> String {
+        let prefix_assertions = definitions
+            .iter()
+            .map(|def| SlurmPrefixAssertion {
+                asn: def.asn(),
+                prefix: def.prefix(),
+                max_prefix_length: def.max_length(),
+            })
+            .collect();
+
+        let slurm = SlurmFile {
+            slurm_version: 1,
+            validation_output_filters: SlurmFilters::default(),
+            locally_added_assertions: SlurmAssertions {
+                prefix_assertions,
+                bgpsec_assertions: vec![],
+            },
+        };
+
+        serde_json::to_string_pretty(&slurm).unwrap()
+    }
+
+    fn parse_rpsl(s: &str) -> Result<Vec<RoaDefinition>, AuthorizationFmtError> {
+        let mut res = vec![];
+
+        // Objects are separated by empty lines, and start with an attribute
+        // which names their class. We only care about route(6) objects.
+        let mut prefix: Option<TypedPrefix> = None;
+        let mut origin: Option<AsNumber> = None;
+        let mut in_object = false;
+
+        for line in s.lines().chain(std::iter::once("")) {
+            if line.starts_with('#') || line.starts_with('%') {
+                continue;
+            }
+
+            if line.trim().is_empty() {
+                if let Some(prefix) = prefix.take() {
+                    let origin = origin
+                        .take()
+                        .ok_or_else(|| AuthorizationFmtError::file(&format!("missing origin for route {}", prefix)))?;
+                    res.push(RoaDefinition::new(origin, prefix, None));
+                }
+                origin = None;
+                in_object = false;
+                continue;
+            }
+
+            // Continuation lines start with white space or '+'
+            if line.starts_with(|c: char| c.is_whitespace() || c == '+') {
+                continue;
+            }
+
+            let (attr, value) = match line.find(':') {
+                Some(pos) => (line[..pos].trim(), line[pos + 1..].trim()),
+                None => return Err(AuthorizationFmtError::file(&format!("invalid rpsl line: {}", line))),
+            };
+
+            if !in_object {
+                in_object = true;
+                if attr.eq_ignore_ascii_case("route") || attr.eq_ignore_ascii_case("route6") {
+                    prefix = Some(TypedPrefix::from_str(value)?);
+                }
+            } else if prefix.is_some() && attr.eq_ignore_ascii_case("origin") {
+                origin = Some(Self::parse_asn(value)?);
+            }
+        }
+
+        Ok(res)
+    }
+
+    fn write_rpsl(definitions: &[RoaDefinition]) -> String {
+        let mut res = String::new();
+        for def in definitions {
+            let class = match def.prefix() {
+                TypedPrefix::V4(_) => "route:",
+                TypedPrefix::V6(_) => "route6:",
+            };
+            res.push_str(&format!("{:<16}{}\n", class, def.prefix()));
+            res.push_str(&format!("{:<16}AS{}\n", "origin:", def.asn()));
+            res.push('\n');
+        }
+        res
+    }
+}
+
+impl FromStr for RoaFileFormat {
+    type Err = AuthorizationFmtError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(RoaFileFormat::Csv),
+            "json" => Ok(RoaFileFormat::Json),
+            "rpsl" => Ok(RoaFileFormat::Rpsl),
+            _ => Err(AuthorizationFmtError::file(&format!("unsupported format: {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for RoaFileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoaFileFormat::Csv => write!(f, "csv"),
+            RoaFileFormat::Json => write!(f, "json"),
+            RoaFileFormat::Rpsl => write!(f, "rpsl"),
+        }
+    }
+}
+
+//------------ SLURM ---------------------------------------------------------
+
+/// A SLURM file as defined in RFC 8416. We only use the prefix assertions,
+/// the other parts are only included to produce a complete file on export.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlurmFile {
+    #[serde(default = "SlurmFile::version")]
+    slurm_version: u8,
+    #[serde(default)]
+    validation_output_filters: SlurmFilters,
+    locally_added_assertions: SlurmAssertions,
+}
+
+impl SlurmFile {
+    fn version() -> u8 {
+        1
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlurmFilters {
+    #[serde(default)]
+    prefix_filters: Vec<serde_json::Value>,
+    #[serde(default)]
+    bgpsec_filters: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlurmAssertions {
+    prefix_assertions: Vec<SlurmPrefixAssertion>,
+    #[serde(default)]
+    bgpsec_assertions: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlurmPrefixAssertion {
+    asn: AsNumber,
+    prefix: TypedPrefix,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    max_prefix_length: Option<u8>,
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test::definition;
+
+    fn definitions() -> Vec<RoaDefinition> {
+        vec![
+            definition("10.0.0.0/16-24 => 64496"),
+            definition("192.168.0.0/24 => 64497"),
+            definition("2001:db8::/32 => 64498"),
+        ]
+    }
+
+    fn check_file(format: RoaFileFormat, file: &str) {
+        let content = std::fs::read_to_string(file).unwrap();
+        assert_eq!(format.parse(&content).unwrap(), definitions());
+    }
+
+    #[test]
+    fn parse_csv_file() {
+        check_file(RoaFileFormat::Csv, "test-resources/roas/roas.csv");
+    }
+
+    #[test]
+    fn parse_slurm_file() {
+        check_file(RoaFileFormat::Json, "test-resources/roas/roas.slurm.json");
+    }
+
+    #[test]
+    fn parse_rpsl_file() {
+        let content = std::fs::read_to_string("test-resources/roas/roas.rpsl").unwrap();
+        let parsed = RoaFileFormat::Rpsl.parse(&content).unwrap();
+
+        // RPSL has no max length
+        let expected: Vec<_> = definitions()
+            .into_iter()
+            .map(|def| RoaDefinition::new(def.asn(), def.prefix(), None))
+            .collect();
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn write_and_parse_again() {
+        for format in &[RoaFileFormat::Csv, RoaFileFormat::Json] {
+            let written = format.write(&definitions());
+            assert_eq!(format.parse(&written).unwrap(), definitions());
+        }
+
+        let written = RoaFileFormat::Rpsl.write(&definitions());
+        assert_eq!(RoaFileFormat::Rpsl.parse(&written).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn reject_invalid_files() {
+        assert!(RoaFileFormat::Csv.parse("AS64496").is_err());
+        assert!(RoaFileFormat::Csv.parse("ASfoo,10.0.0.0/24").is_err());
+        assert!(RoaFileFormat::Csv.parse("AS64496,10.0.0.0/24,long").is_err());
+        assert!(RoaFileFormat::Json.parse("[]").is_err());
+        assert!(RoaFileFormat::Rpsl
+            .parse("route: 10.0.0.0/24\nmnt-by: MAINT\n")
+            .is_err());
+    }
+}
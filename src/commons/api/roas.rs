@@ -1,4 +1,4 @@
-use std::{cmp::Ordering, fmt, net::IpAddr, ops::Deref, str::FromStr};
+use std::{cmp::Ordering, collections::BTreeSet, fmt, net::IpAddr, ops::Deref, str::FromStr};
 
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
@@ -7,7 +7,10 @@ use rpki::repository::{
     roa::RoaIpAddress,
 };
 
-use crate::{commons::api::ResourceSet, daemon::ca::RouteAuthorizationUpdates};
+use crate::{
+    commons::api::{ResourceSet, Timestamp},
+    daemon::ca::RouteAuthorizationUpdates,
+};
 
 //------------ RoaAggregateKey ---------------------------------------------
 
@@ -335,6 +338,33 @@ impl RoaDefinitionUpdates {
         (self.added, self.removed)
     }
 
+    /// Returns the updates needed to get from the current definitions to the
+    /// wanted definitions. Definitions which only differ in whether their max
+    /// length is explicit are considered equal.
+    pub fn between(current: &[RoaDefinition], wanted: &[RoaDefinition]) -> Self {
+        let current_explicit: Vec<_> = current.iter().map(|def| def.explicit_max_length()).collect();
+        let wanted_explicit: Vec<_> = wanted.iter().map(|def| def.explicit_max_length()).collect();
+
+        let mut added: Vec<RoaDefinition> = vec![];
+        for def in wanted {
+            if !current_explicit.contains(&def.explicit_max_length())
+                && !added
+                    .iter()
+                    .any(|a| a.explicit_max_length() == def.explicit_max_length())
+            {
+                added.push(*def);
+            }
+        }
+
+        let removed = current
+            .iter()
+            .filter(|def| !wanted_explicit.contains(&def.explicit_max_length()))
+            .copied()
+            .collect();
+
+        RoaDefinitionUpdates { added, removed }
+    }
+
     pub fn empty() -> Self {
         Self::default()
     }
@@ -417,6 +447,158 @@ impl From<RouteAuthorizationUpdates> for RoaDefinitionUpdates {
     }
 }
 
+//------------ RoaHistory --------------------------------------------------
+
+/// The history of all changes to the ROA definitions of a CA, oldest first.
+/// This is reconstructed from the events following the ROA update commands
+/// of the CA, so that it can tell when each definition was added or removed,
+/// and by whom.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaHistory {
+    changes: Vec<RoaHistoryChange>,
+}
+
+impl RoaHistory {
+    pub fn new(changes: Vec<RoaHistoryChange>) -> Self {
+        RoaHistory { changes }
+    }
+
+    pub fn changes(&self) -> &Vec<RoaHistoryChange> {
+        &self.changes
+    }
+
+    pub fn push(&mut self, change: RoaHistoryChange) {
+        self.changes.push(change);
+    }
+
+    /// Returns the history for the definitions which cover the given prefix,
+    /// i.e. definitions for the same prefix or a less specific prefix.
+    pub fn covering(&self, prefix: &TypedPrefix) -> Self {
+        let changes = self
+            .changes
+            .iter()
+            .filter(|change| change.definition.prefix().matching_or_less_specific(prefix))
+            .cloned()
+            .collect();
+        RoaHistory { changes }
+    }
+
+    /// Returns the definitions which were configured at the given time.
+    pub fn definitions_at(&self, time: Timestamp) -> Vec<RoaDefinition> {
+        let mut definitions = BTreeSet::new();
+        for change in self.changes.iter().take_while(|change| change.time <= time) {
+            match change.change {
+                RoaChangeType::Added => definitions.insert(change.definition),
+                RoaChangeType::Removed => definitions.remove(&change.definition),
+            };
+        }
+        definitions.into_iter().collect()
+    }
+
+    /// Returns the updates needed to get from the definitions configured at
+    /// the 'from' time, to the definitions configured at the 'to' time.
+    pub fn diff(&self, from: Timestamp, to: Timestamp) -> RoaDefinitionUpdates {
+        let before = self.definitions_at(from);
+        let after = self.definitions_at(to);
+
+        let added = after.iter().filter(|def| !before.contains(def)).copied().collect();
+        let removed = before.iter().filter(|def| !after.contains(def)).copied().collect();
+
+        RoaDefinitionUpdates::new(added, removed)
+    }
+}
+
+impl fmt::Display for RoaHistory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ RoaHistoryChange --------------------------------------------
+
+/// A single ROA definition which was added or removed by a command.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaHistoryChange {
+    time: Timestamp,
+    actor: String,
+    command: String,
+    change: RoaChangeType,
+    definition: RoaDefinition,
+}
+
+impl RoaHistoryChange {
+    pub fn new(
+        time: Timestamp,
+        actor: String,
+        command: String,
+        change: RoaChangeType,
+        definition: RoaDefinition,
+    ) -> Self {
+        RoaHistoryChange {
+            time,
+            actor,
+            command,
+            change,
+            definition,
+        }
+    }
+
+    pub fn time(&self) -> Timestamp {
+        self.time
+    }
+
+    pub fn actor(&self) -> &str {
+        &self.actor
+    }
+
+    /// The key of the command which resulted in this change.
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub fn change(&self) -> RoaChangeType {
+        self.change
+    }
+
+    pub fn definition(&self) -> RoaDefinition {
+        self.definition
+    }
+}
+
+impl fmt::Display for RoaHistoryChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} '{}' by {}",
+            self.time.to_rfc3339(),
+            self.change,
+            self.definition,
+            self.actor
+        )
+    }
+}
+
+//------------ RoaChangeType -----------------------------------------------
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoaChangeType {
+    Added,
+    Removed,
+}
+
+impl fmt::Display for RoaChangeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoaChangeType::Added => write!(f, "added"),
+            RoaChangeType::Removed => write!(f, "removed"),
+        }
+    }
+}
+
 //------------ TypedPrefix -------------------------------------------------
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
 pub enum TypedPrefix {
@@ -667,6 +849,7 @@ pub enum AuthorizationFmtError {
     Asn(String),
     Auth(String),
     Delta(String),
+    File(String),
 }
 
 impl fmt::Display for AuthorizationFmtError {
@@ -676,6 +859,7 @@ impl fmt::Display for AuthorizationFmtError {
             AuthorizationFmtError::Asn(s) => write!(f, "Invalid asn in string: {}", s),
             AuthorizationFmtError::Auth(s) => write!(f, "Invalid authorization string: {}", s),
             AuthorizationFmtError::Delta(s) => write!(f, "Invalid authorization delta string: {}", s),
+            AuthorizationFmtError::File(s) => write!(f, "Invalid authorizations file: {}", s),
         }
     }
 }
@@ -696,6 +880,10 @@ impl AuthorizationFmtError {
     pub fn delta(s: &str) -> Self {
         AuthorizationFmtError::Delta(s.to_string())
     }
+
+    pub fn file(s: &str) -> Self {
+        AuthorizationFmtError::File(s.to_string())
+    }
 }
 
 //------------ Tests -------------------------------------------------------
@@ -842,4 +1030,71 @@ mod tests {
         check("10.0.0.0/15-17 => 64496", 4);
         check("10.0.0.0/15-18 => 64496", 8);
     }
+
+    #[test]
+    fn roa_history_covering_and_diff() {
+        fn change(time: i64, change: RoaChangeType, def: &str) -> RoaHistoryChange {
+            RoaHistoryChange::new(
+                Timestamp::new(time),
+                "admin".to_string(),
+                format!("cmd-{}", time),
+                change,
+                definition(def),
+            )
+        }
+
+        let history = RoaHistory::new(vec![
+            change(100, RoaChangeType::Added, "10.0.0.0/16 => 64496"),
+            change(100, RoaChangeType::Added, "10.1.0.0/24 => 64496"),
+            change(200, RoaChangeType::Added, "10.0.0.0/24 => 64497"),
+            change(300, RoaChangeType::Removed, "10.0.0.0/16 => 64496"),
+            change(400, RoaChangeType::Added, "10.0.0.0/16-24 => 64496"),
+        ]);
+
+        let covering = history.covering(&TypedPrefix::from_str("10.0.0.0/24").unwrap());
+        assert_eq!(covering.changes().len(), 4);
+        assert!(covering
+            .changes()
+            .iter()
+            .all(|change| change.definition() != definition("10.1.0.0/24 => 64496")));
+
+        assert!(history.definitions_at(Timestamp::new(50)).is_empty());
+        assert_eq!(
+            history.definitions_at(Timestamp::new(300)),
+            vec![definition("10.0.0.0/24 => 64497"), definition("10.1.0.0/24 => 64496")]
+        );
+
+        let diff = history.diff(Timestamp::new(150), Timestamp::new(400));
+        assert_eq!(
+            diff,
+            RoaDefinitionUpdates::new(
+                vec![
+                    definition("10.0.0.0/16-24 => 64496"),
+                    definition("10.0.0.0/24 => 64497")
+                ],
+                vec![definition("10.0.0.0/16 => 64496")]
+            )
+        );
+    }
+
+    #[test]
+    fn updates_between_definitions() {
+        let current = vec![definition("10.0.0.0/24 => 64496"), definition("10.0.1.0/24 => 64496")];
+        let wanted = vec![
+            definition("10.0.0.0/24-24 => 64496"),
+            definition("10.0.2.0/24 => 64496"),
+            definition("10.0.2.0/24 => 64496"),
+        ];
+
+        let updates = RoaDefinitionUpdates::between(&current, &wanted);
+        assert_eq!(
+            updates,
+            RoaDefinitionUpdates::new(
+                vec![definition("10.0.2.0/24 => 64496")],
+                vec![definition("10.0.1.0/24 => 64496")]
+            )
+        );
+
+        assert!(RoaDefinitionUpdates::between(&current, &current).is_empty());
+    }
 }
@@ -0,0 +1,262 @@
+//! Policies for suspending inactive child CAs, the reasons why children were
+//! suspended, and the notifications sent about inactive children.
+
+use std::fmt;
+
+use crate::commons::api::{ChildHandle, Handle, Timestamp};
+
+//------------ ChildSuspensionPolicy -----------------------------------------
+
+/// Defines when an inactive child CA is suspended. A policy can be set for a
+/// CA as a whole, and for individual children. A policy for a child takes
+/// precedence over the policy of its parent CA, which in turn takes
+/// precedence over 'suspend_child_after_inactive_hours' in the configuration.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ChildSuspensionPolicy {
+    /// Never suspend the child, however long it is inactive.
+    Exempt,
+
+    /// Suspend the child when it has been inactive for more than the given
+    /// number of hours, and optionally warn when it has been inactive for
+    /// more than the given (lower) number of hours.
+    Inactive {
+        suspend_after_hours: u32,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        warn_after_hours: Option<u32>,
+    },
+}
+
+impl ChildSuspensionPolicy {
+    pub fn exempt() -> Self {
+        ChildSuspensionPolicy::Exempt
+    }
+
+    pub fn inactive(suspend_after_hours: u32, warn_after_hours: Option<u32>) -> Self {
+        ChildSuspensionPolicy::Inactive {
+            suspend_after_hours,
+            warn_after_hours,
+        }
+    }
+
+    pub fn is_exempt(&self) -> bool {
+        matches!(self, ChildSuspensionPolicy::Exempt)
+    }
+
+    /// Returns the thresholds in seconds following from this policy, or None
+    /// if children are exempt from suspension.
+    pub fn thresholds(&self) -> Option<ChildSuspensionThresholds> {
+        match self {
+            ChildSuspensionPolicy::Exempt => None,
+            ChildSuspensionPolicy::Inactive {
+                suspend_after_hours,
+                warn_after_hours,
+            } => Some(ChildSuspensionThresholds::new(
+                i64::from(*suspend_after_hours) * 3600,
+                warn_after_hours.map(|hours| i64::from(hours) * 3600),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ChildSuspensionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChildSuspensionPolicy::Exempt => write!(f, "never suspend inactive children"),
+            ChildSuspensionPolicy::Inactive {
+                suspend_after_hours,
+                warn_after_hours,
+            } => {
+                write!(f, "suspend children after {} hours of inactivity", suspend_after_hours)?;
+                if let Some(warn_after_hours) = warn_after_hours {
+                    write!(f, ", warn after {} hours", warn_after_hours)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+//------------ ChildSuspensionThresholds -------------------------------------
+
+/// The number of seconds of inactivity after which a child is suspended,
+/// and after which a warning is raised about it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ChildSuspensionThresholds {
+    suspend_seconds: i64,
+    warn_seconds: Option<i64>,
+}
+
+impl ChildSuspensionThresholds {
+    pub fn new(suspend_seconds: i64, warn_seconds: Option<i64>) -> Self {
+        ChildSuspensionThresholds {
+            suspend_seconds,
+            warn_seconds,
+        }
+    }
+
+    pub fn suspend_seconds(&self) -> i64 {
+        self.suspend_seconds
+    }
+
+    pub fn warn_seconds(&self) -> Option<i64> {
+        self.warn_seconds
+    }
+}
+
+//------------ ChildSuspendReason --------------------------------------------
+
+/// Explains why a child was suspended.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ChildSuspendReason {
+    /// The child was suspended by an operator.
+    Manual,
+
+    /// The child did not contact its parent for longer than the threshold.
+    Inactive {
+        last_seen: Timestamp,
+        threshold_seconds: i64,
+    },
+}
+
+impl fmt::Display for ChildSuspendReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChildSuspendReason::Manual => write!(f, "suspended by operator"),
+            ChildSuspendReason::Inactive {
+                last_seen,
+                threshold_seconds,
+            } => write!(
+                f,
+                "inactive for more than {}, last seen at {}",
+                threshold_string(*threshold_seconds),
+                last_seen.to_rfc3339()
+            ),
+        }
+    }
+}
+
+//------------ ChildSuspension -----------------------------------------------
+
+/// Records when and why a child was suspended.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildSuspension {
+    reason: ChildSuspendReason,
+    suspended: Timestamp,
+}
+
+impl ChildSuspension {
+    pub fn new(reason: ChildSuspendReason) -> Self {
+        ChildSuspension {
+            reason,
+            suspended: Timestamp::now(),
+        }
+    }
+
+    pub fn reason(&self) -> &ChildSuspendReason {
+        &self.reason
+    }
+
+    pub fn suspended(&self) -> Timestamp {
+        self.suspended
+    }
+}
+
+impl fmt::Display for ChildSuspension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.suspended.to_rfc3339(), self.reason)
+    }
+}
+
+//------------ ChildInactivityNotification -----------------------------------
+
+/// The JSON body posted to the configured webhook when a child is found to be
+/// inactive, either because it will be suspended soon or because it was
+/// suspended.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildInactivityNotification {
+    event: ChildInactivityEvent,
+    ca: Handle,
+    child: ChildHandle,
+    last_seen: Timestamp,
+    suspend_after_seconds: i64,
+}
+
+impl ChildInactivityNotification {
+    pub fn new(
+        event: ChildInactivityEvent,
+        ca: Handle,
+        child: ChildHandle,
+        last_seen: Timestamp,
+        suspend_after_seconds: i64,
+    ) -> Self {
+        ChildInactivityNotification {
+            event,
+            ca,
+            child,
+            last_seen,
+            suspend_after_seconds,
+        }
+    }
+
+    pub fn event(&self) -> ChildInactivityEvent {
+        self.event
+    }
+
+    pub fn ca(&self) -> &Handle {
+        &self.ca
+    }
+
+    pub fn child(&self) -> &ChildHandle {
+        &self.child
+    }
+
+    pub fn last_seen(&self) -> Timestamp {
+        self.last_seen
+    }
+
+    pub fn suspend_after_seconds(&self) -> i64 {
+        self.suspend_after_seconds
+    }
+}
+
+impl fmt::Display for ChildInactivityNotification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "child '{}' under CA '{}' was last seen at {}: {}",
+            self.child,
+            self.ca,
+            self.last_seen.to_rfc3339(),
+            self.event
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChildInactivityEvent {
+    Warning,
+    Suspended,
+}
+
+impl fmt::Display for ChildInactivityEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChildInactivityEvent::Warning => "will be suspended if it stays inactive",
+            ChildInactivityEvent::Suspended => "suspended",
+        }
+        .fmt(f)
+    }
+}
+
+/// Formats a number of seconds of inactivity as hours, or as seconds when
+/// it is less than an hour, which is only used in testing.
+pub fn threshold_string(seconds: i64) -> String {
+    if seconds >= 3600 {
+        format!("{} hours", seconds / 3600)
+    } else {
+        format!("{} seconds", seconds)
+    }
+}
@@ -0,0 +1,134 @@
+//! Tenants, which bind users to a set of CAs for delegated administration.
+
+use std::fmt;
+
+use crate::commons::api::Handle;
+
+//------------ TenantDefinition ----------------------------------------------
+
+/// Defines a tenant by its name and the CAs it owns. Used to create a tenant,
+/// and to replace the CAs of an existing tenant.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TenantDefinition {
+    name: String,
+    cas: Vec<Handle>,
+}
+
+impl TenantDefinition {
+    pub fn new(name: String, cas: Vec<Handle>) -> Self {
+        TenantDefinition { name, cas }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn cas(&self) -> &Vec<Handle> {
+        &self.cas
+    }
+}
+
+//------------ TenantInfo ----------------------------------------------------
+
+/// The details of a tenant: the CAs it owns and the users bound to it. Users
+/// bound to a tenant can only see and act on the CAs of the tenant.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TenantInfo {
+    name: String,
+    cas: Vec<Handle>,
+    users: Vec<String>,
+}
+
+impl TenantInfo {
+    pub fn new(definition: TenantDefinition) -> Self {
+        TenantInfo {
+            name: definition.name,
+            cas: definition.cas,
+            users: vec![],
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn cas(&self) -> &Vec<Handle> {
+        &self.cas
+    }
+
+    pub fn users(&self) -> &Vec<String> {
+        &self.users
+    }
+
+    pub fn set_cas(&mut self, cas: Vec<Handle>) {
+        self.cas = cas;
+    }
+
+    /// Replaces the previous handle of a renamed CA. Returns true if the
+    /// tenant owned the CA.
+    pub fn rename_ca(&mut self, from: &Handle, to: &Handle) -> bool {
+        match self.cas.iter_mut().find(|ca| *ca == from) {
+            Some(ca) => {
+                *ca = to.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn add_user(&mut self, user: String) {
+        if !self.users.contains(&user) {
+            self.users.push(user);
+            self.users.sort();
+        }
+    }
+
+    pub fn remove_user(&mut self, user: &str) -> bool {
+        let before = self.users.len();
+        self.users.retain(|u| u != user);
+        self.users.len() != before
+    }
+
+    pub fn has_user(&self, user: &str) -> bool {
+        self.users.iter().any(|u| u == user)
+    }
+}
+
+impl fmt::Display for TenantInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cas: Vec<&str> = self.cas.iter().map(|ca| ca.as_str()).collect();
+        write!(
+            f,
+            "{} cas: {} users: {}",
+            self.name,
+            cas.join(","),
+            self.users.join(",")
+        )
+    }
+}
+
+//------------ TenantList ----------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TenantList {
+    tenants: Vec<TenantInfo>,
+}
+
+impl TenantList {
+    pub fn new(tenants: Vec<TenantInfo>) -> Self {
+        TenantList { tenants }
+    }
+
+    pub fn tenants(&self) -> &Vec<TenantInfo> {
+        &self.tenants
+    }
+}
+
+impl fmt::Display for TenantList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for tenant in &self.tenants {
+            writeln!(f, "{}", tenant)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+//! Scoped API tokens, which are limited to specific permissions and CAs.
+
+use std::fmt;
+
+use crate::commons::api::{Handle, Timestamp, Token};
+
+//------------ ApiTokenRequest -----------------------------------------------
+
+/// Request to create a new scoped API token. If no CAs are given, then the
+/// token is not limited to specific CAs.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokenRequest {
+    name: String,
+    permissions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    cas: Option<Vec<Handle>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    expires: Option<Timestamp>,
+}
+
+impl ApiTokenRequest {
+    pub fn new(name: String, permissions: Vec<String>, cas: Option<Vec<Handle>>, expires: Option<Timestamp>) -> Self {
+        ApiTokenRequest {
+            name,
+            permissions,
+            cas,
+            expires,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn permissions(&self) -> &Vec<String> {
+        &self.permissions
+    }
+
+    pub fn cas(&self) -> Option<&Vec<Handle>> {
+        self.cas.as_ref()
+    }
+
+    pub fn expires(&self) -> Option<Timestamp> {
+        self.expires
+    }
+}
+
+//------------ ApiTokenInfo --------------------------------------------------
+
+/// The details of a scoped API token. This never includes the token itself,
+/// as Krill only keeps a hash of it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokenInfo {
+    name: String,
+    permissions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    cas: Option<Vec<Handle>>,
+    created: Timestamp,
+    created_by: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    expires: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    revoked: Option<Timestamp>,
+}
+
+impl ApiTokenInfo {
+    pub fn new(request: ApiTokenRequest, created_by: String) -> Self {
+        ApiTokenInfo {
+            name: request.name,
+            permissions: request.permissions,
+            cas: request.cas,
+            created: Timestamp::now(),
+            created_by,
+            expires: request.expires,
+            revoked: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn permissions(&self) -> &Vec<String> {
+        &self.permissions
+    }
+
+    pub fn cas(&self) -> Option<&Vec<Handle>> {
+        self.cas.as_ref()
+    }
+
+    pub fn created(&self) -> Timestamp {
+        self.created
+    }
+
+    pub fn created_by(&self) -> &str {
+        &self.created_by
+    }
+
+    pub fn expires(&self) -> Option<Timestamp> {
+        self.expires
+    }
+
+    pub fn revoked(&self) -> Option<Timestamp> {
+        self.revoked
+    }
+
+    pub fn revoke(&mut self) {
+        self.revoked = Some(Timestamp::now());
+    }
+
+    /// Replaces the previous handle of a renamed CA, if the token is limited
+    /// to it. Returns true if the token was updated.
+    pub fn rename_ca(&mut self, from: &Handle, to: &Handle) -> bool {
+        match self.cas.iter_mut().flatten().find(|ca| *ca == from) {
+            Some(ca) => {
+                *ca = to.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires.map(|expires| expires < Timestamp::now()).unwrap_or(false)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.revoked.is_none() && !self.is_expired()
+    }
+}
+
+impl fmt::Display for ApiTokenInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = if self.revoked.is_some() {
+            "revoked"
+        } else if self.is_expired() {
+            "expired"
+        } else {
+            "active"
+        };
+
+        write!(
+            f,
+            "{} ({}) permissions: {}",
+            self.name,
+            state,
+            self.permissions.join(",")
+        )?;
+        match &self.cas {
+            Some(cas) => {
+                let cas: Vec<&str> = cas.iter().map(|ca| ca.as_str()).collect();
+                write!(f, " cas: {}", cas.join(","))?;
+            }
+            None => write!(f, " cas: all")?,
+        }
+        if let Some(expires) = self.expires {
+            write!(f, " expires: {}", expires.to_rfc3339())?;
+        }
+        write!(f, " created: {} by {}", self.created.to_rfc3339(), self.created_by)
+    }
+}
+
+//------------ ApiTokenList --------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokenList {
+    tokens: Vec<ApiTokenInfo>,
+}
+
+impl ApiTokenList {
+    pub fn new(tokens: Vec<ApiTokenInfo>) -> Self {
+        ApiTokenList { tokens }
+    }
+
+    pub fn tokens(&self) -> &Vec<ApiTokenInfo> {
+        &self.tokens
+    }
+}
+
+impl fmt::Display for ApiTokenList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for token in &self.tokens {
+            writeln!(f, "{}", token)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ ApiTokenCreated -----------------------------------------------
+
+/// The response to creating a token. This is the only time that the token
+/// itself is shown.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApiTokenCreated {
+    token: Token,
+    info: ApiTokenInfo,
+}
+
+impl ApiTokenCreated {
+    pub fn new(token: Token, info: ApiTokenInfo) -> Self {
+        ApiTokenCreated { token, info }
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    pub fn info(&self) -> &ApiTokenInfo {
+        &self.info
+    }
+}
+
+impl fmt::Display for ApiTokenCreated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.info)?;
+        writeln!(f, "token: {}", self.token)?;
+        writeln!(f, "Note: this token cannot be shown again.")
+    }
+}
@@ -0,0 +1,335 @@
+//! Local users, which are managed through the API rather than the config file.
+
+use std::{collections::HashMap, fmt};
+
+use crate::commons::api::Timestamp;
+
+//------------ LocalUserRequest ----------------------------------------------
+
+/// Request to add a local user. The password is given in plain text, so that
+/// Krill can check it against the password policy, and is never stored.
+#[derive(Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LocalUserRequest {
+    id: String,
+    password: String,
+    #[serde(default)]
+    attributes: HashMap<String, String>,
+}
+
+impl LocalUserRequest {
+    pub fn new(id: String, password: String, attributes: HashMap<String, String>) -> Self {
+        LocalUserRequest {
+            id,
+            password,
+            attributes,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    pub fn attributes(&self) -> &HashMap<String, String> {
+        &self.attributes
+    }
+}
+
+impl fmt::Debug for LocalUserRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LocalUserRequest")
+            .field("id", &self.id)
+            .field("password", &"<redacted>")
+            .field("attributes", &self.attributes)
+            .finish()
+    }
+}
+
+//------------ PasswordChange ------------------------------------------------
+
+/// A new password for a local user, in plain text.
+#[derive(Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PasswordChange {
+    password: String,
+}
+
+impl PasswordChange {
+    pub fn new(password: String) -> Self {
+        PasswordChange { password }
+    }
+
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+}
+
+impl fmt::Debug for PasswordChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PasswordChange")
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+//------------ LocalUserSource -----------------------------------------------
+
+/// Where a user is defined. Users defined in the config file cannot be
+/// changed through the API.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalUserSource {
+    Api,
+    ConfigFile,
+}
+
+impl fmt::Display for LocalUserSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LocalUserSource::Api => write!(f, "api"),
+            LocalUserSource::ConfigFile => write!(f, "config file"),
+        }
+    }
+}
+
+//------------ LocalUserInfo -------------------------------------------------
+
+/// The details of a local user. This never includes the password hash.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LocalUserInfo {
+    id: String,
+    attributes: HashMap<String, String>,
+    source: LocalUserSource,
+    #[serde(default)]
+    disabled: bool,
+    #[serde(default)]
+    password_change_required: bool,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    created: Option<Timestamp>,
+}
+
+impl LocalUserInfo {
+    /// Creates the details for a new user added through the API. The user
+    /// must change the password, which was chosen by an admin, at the next
+    /// login.
+    pub fn new(id: String, attributes: HashMap<String, String>) -> Self {
+        LocalUserInfo {
+            id,
+            attributes,
+            source: LocalUserSource::Api,
+            disabled: false,
+            password_change_required: true,
+            created: Some(Timestamp::now()),
+        }
+    }
+
+    pub fn config_file(id: String, attributes: HashMap<String, String>) -> Self {
+        LocalUserInfo {
+            id,
+            attributes,
+            source: LocalUserSource::ConfigFile,
+            disabled: false,
+            password_change_required: false,
+            created: None,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn attributes(&self) -> &HashMap<String, String> {
+        &self.attributes
+    }
+
+    pub fn source(&self) -> LocalUserSource {
+        self.source
+    }
+
+    pub fn disabled(&self) -> bool {
+        self.disabled
+    }
+
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.disabled = disabled;
+    }
+
+    pub fn password_change_required(&self) -> bool {
+        self.password_change_required
+    }
+
+    pub fn set_password_change_required(&mut self, required: bool) {
+        self.password_change_required = required;
+    }
+
+    pub fn created(&self) -> Option<Timestamp> {
+        self.created
+    }
+}
+
+impl fmt::Display for LocalUserInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = if self.disabled {
+            "disabled"
+        } else if self.password_change_required {
+            "password change required"
+        } else {
+            "active"
+        };
+
+        let mut attributes: Vec<String> = self.attributes.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        attributes.sort();
+
+        write!(
+            f,
+            "{} ({}) source: {} attributes: {}",
+            self.id,
+            state,
+            self.source,
+            attributes.join(",")
+        )
+    }
+}
+
+//------------ LocalUserList -------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LocalUserList {
+    users: Vec<LocalUserInfo>,
+}
+
+impl LocalUserList {
+    pub fn new(users: Vec<LocalUserInfo>) -> Self {
+        LocalUserList { users }
+    }
+
+    pub fn users(&self) -> &Vec<LocalUserInfo> {
+        &self.users
+    }
+}
+
+impl fmt::Display for LocalUserList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for user in &self.users {
+            writeln!(f, "{}", user)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ MfaEnrolment --------------------------------------------------
+
+/// The details needed to set up an authenticator app for TOTP (RFC 6238), and
+/// the recovery codes that can be used once each instead of a TOTP code. This
+/// is only shown when the user enrols, as the codes are not kept.
+#[derive(Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MfaEnrolment {
+    secret: String,
+    uri: String,
+    recovery_codes: Vec<String>,
+}
+
+impl MfaEnrolment {
+    pub fn new(secret: String, uri: String, recovery_codes: Vec<String>) -> Self {
+        MfaEnrolment {
+            secret,
+            uri,
+            recovery_codes,
+        }
+    }
+
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn recovery_codes(&self) -> &Vec<String> {
+        &self.recovery_codes
+    }
+}
+
+impl fmt::Debug for MfaEnrolment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MfaEnrolment")
+            .field("secret", &"<redacted>")
+            .field("uri", &"<redacted>")
+            .field("recovery_codes", &"<redacted>")
+            .finish()
+    }
+}
+
+impl fmt::Display for MfaEnrolment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "secret: {}", self.secret)?;
+        writeln!(f, "uri: {}", self.uri)?;
+        writeln!(f, "recovery codes:")?;
+        for code in &self.recovery_codes {
+            writeln!(f, "  {}", code)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ MfaCode -------------------------------------------------------
+
+/// A TOTP code, or a recovery code.
+#[derive(Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MfaCode {
+    code: String,
+}
+
+impl MfaCode {
+    pub fn new(code: String) -> Self {
+        MfaCode { code }
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+}
+
+impl fmt::Debug for MfaCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MfaCode").field("code", &"<redacted>").finish()
+    }
+}
+
+//------------ MfaStatus -----------------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MfaStatus {
+    enabled: bool,
+    recovery_codes_left: usize,
+}
+
+impl MfaStatus {
+    pub fn new(enabled: bool, recovery_codes_left: usize) -> Self {
+        MfaStatus {
+            enabled,
+            recovery_codes_left,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn recovery_codes_left(&self) -> usize {
+        self.recovery_codes_left
+    }
+}
+
+impl fmt::Display for MfaStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.enabled {
+            write!(f, "enabled, {} recovery codes left", self.recovery_codes_left)
+        } else {
+            write!(f, "disabled")
+        }
+    }
+}
@@ -10,8 +10,8 @@ use crate::{
         api::{AsNumber, ResourceSet, RoaDefinition},
         bgp::{
             make_roa_tree, make_validated_announcement_tree, Announcement, AnnouncementValidity, Announcements,
-            BgpAnalysisEntry, BgpAnalysisReport, BgpAnalysisState, BgpAnalysisSuggestion, IpRange, RisDumpError,
-            RisDumpLoader, ValidatedAnnouncement,
+            BgpAnalysisEntry, BgpAnalysisReport, BgpAnalysisState, BgpAnalysisSuggestion, BgpAnnouncementSource,
+            BgpSimulationEntry, BgpSimulationReport, BgpSourceError, CaRoutes, IpRange, ValidatedAnnouncement,
         },
     },
     constants::{test_announcements_enabled, BGP_RIS_REFRESH_MINUTES},
@@ -21,43 +21,50 @@ use crate::{
 
 /// This type helps analyse ROAs vs BGP and vice versa.
 pub struct BgpAnalyser {
-    dump_loader: Option<RisDumpLoader>,
+    source: Option<BgpAnnouncementSource>,
+    refresh_minutes: i64,
+    min_peers: u32,
     seen: RwLock<Announcements>,
 }
 
 impl BgpAnalyser {
-    pub fn new(ris_enabled: bool, ris_v4_uri: &str, ris_v6_uri: &str) -> Self {
+    /// Creates a new analyser. Announcements which are known to be seen by
+    /// fewer than `min_peers` peers are ignored.
+    pub fn new(source: Option<BgpAnnouncementSource>, refresh_minutes: i64, min_peers: u32) -> Self {
         if test_announcements_enabled() {
             Self::with_test_announcements()
         } else {
-            let dump_loader = if ris_enabled {
-                Some(RisDumpLoader::new(ris_v4_uri, ris_v6_uri))
-            } else {
-                None
-            };
             BgpAnalyser {
-                dump_loader,
+                source,
+                refresh_minutes,
+                min_peers,
                 seen: RwLock::new(Announcements::default()),
             }
         }
     }
 
     pub async fn update(&self) -> Result<bool, BgpAnalyserError> {
-        if let Some(loader) = &self.dump_loader {
+        if let Some(source) = &self.source {
             let mut seen = self.seen.write().await;
             if let Some(last_time) = seen.last_checked() {
-                if (last_time + Duration::minutes(BGP_RIS_REFRESH_MINUTES)) > Time::now() {
-                    trace!("Will not check BGP Ris Dumps until the refresh interval has passed");
+                if (last_time + Duration::minutes(self.refresh_minutes)) > Time::now() {
+                    trace!("Will not check {} until the refresh interval has passed", source.name());
                     return Ok(false); // no need to update yet
                 }
             }
-            let announcements = loader.download_updates().await?;
+            let mut announcements = source.load().await?;
+            announcements.retain(|a| a.seen_by_at_least(self.min_peers));
+
             if seen.equivalent(&announcements) {
-                debug!("BGP Ris Dumps unchanged");
+                debug!("{} unchanged", source.name());
                 seen.update_checked();
                 Ok(false)
             } else {
-                info!("Updated announcements ({}) based on BGP Ris Dumps", announcements.len());
+                info!(
+                    "Updated announcements ({}) based on {}",
+                    announcements.len(),
+                    source.name()
+                );
                 seen.update(announcements);
                 Ok(true)
             }
@@ -287,6 +294,57 @@ impl BgpAnalyser {
         suggestion
     }
 
+    /// Compares the validity of all announcements for resources held by any
+    /// of the given CAs, before and after a proposed change, and reports the
+    /// announcements which would change between valid, invalid and not found.
+    pub async fn simulate(&self, before: &[CaRoutes], after: &[CaRoutes]) -> BgpSimulationReport {
+        let seen = self.seen.read().await;
+        if seen.last_checked().is_none() {
+            return BgpSimulationReport::no_announcement_info();
+        }
+
+        let mut scope = ResourceSet::default();
+        for ca in before.iter().chain(after.iter()) {
+            scope = scope.union(ca.resources());
+        }
+
+        let (v4_scope, v6_scope) = IpRange::for_resource_set(&scope);
+        let mut announcements = vec![];
+        for block in v4_scope.into_iter().chain(v6_scope.into_iter()) {
+            announcements.append(&mut seen.contained_by(block));
+        }
+
+        let published = |cas: &[CaRoutes]| -> Vec<RoaDefinition> {
+            cas.iter().flat_map(|ca| ca.published_roas()).cloned().collect()
+        };
+        let roas_before = make_roa_tree(&published(before));
+        let roas_after = make_roa_tree(&published(after));
+
+        let mut entries = vec![];
+        for announcement in announcements {
+            let validity_before = announcement.validate(&roas_before).validity();
+            let validity_after = announcement.validate(&roas_after).validity();
+
+            if !validity_before.same_outcome(validity_after) {
+                let cas = before
+                    .iter()
+                    .chain(after.iter())
+                    .filter(|ca| ca.covers(announcement))
+                    .map(|ca| ca.handle().clone())
+                    .collect();
+
+                entries.push(BgpSimulationEntry::new(
+                    *announcement,
+                    validity_before,
+                    validity_after,
+                    cas,
+                ));
+            }
+        }
+
+        BgpSimulationReport::new(entries)
+    }
+
     fn test_announcements() -> Vec<Announcement> {
         use crate::test::announcement;
 
@@ -307,7 +365,9 @@ impl BgpAnalyser {
         let mut announcements = Announcements::default();
         announcements.update(Self::test_announcements());
         BgpAnalyser {
-            dump_loader: None,
+            source: None,
+            refresh_minutes: BGP_RIS_REFRESH_MINUTES,
+            min_peers: 1,
             seen: RwLock::new(announcements),
         }
     }
@@ -317,20 +377,20 @@ impl BgpAnalyser {
 
 #[derive(Debug)]
 pub enum BgpAnalyserError {
-    RisDump(RisDumpError),
+    Source(BgpSourceError),
 }
 
 impl fmt::Display for BgpAnalyserError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            BgpAnalyserError::RisDump(e) => write!(f, "BGP RIS update error: {}", e),
+            BgpAnalyserError::Source(e) => write!(f, "BGP announcements update error: {}", e),
         }
     }
 }
 
-impl From<RisDumpError> for BgpAnalyserError {
-    fn from(e: RisDumpError) -> Self {
-        BgpAnalyserError::RisDump(e)
+impl From<BgpSourceError> for BgpAnalyserError {
+    fn from(e: BgpSourceError) -> Self {
+        BgpAnalyserError::Source(e)
     }
 }
 
@@ -339,8 +399,11 @@ impl From<RisDumpError> for BgpAnalyserError {
 #[cfg(test)]
 mod tests {
 
+    use std::path::PathBuf;
+
     use crate::commons::api::RoaDefinitionUpdates;
-    use crate::commons::bgp::BgpAnalysisState;
+    use crate::commons::bgp::{BgpAnalysisState, LocalFileFormat, LocalFileLoader, RisDumpLoader};
+    use crate::constants::BGP_RIS_MIN_PEERS;
     use crate::test::*;
 
     use super::*;
@@ -351,7 +414,12 @@ mod tests {
         let bgp_ris_dump_v4_uri = "http://www.ris.ripe.net/dumps/riswhoisdump.IPv4.gz";
         let bgp_ris_dump_v6_uri = "http://www.ris.ripe.net/dumps/riswhoisdump.IPv6.gz";
 
-        let analyser = BgpAnalyser::new(true, bgp_ris_dump_v4_uri, bgp_ris_dump_v6_uri);
+        let loader = RisDumpLoader::new(bgp_ris_dump_v4_uri, bgp_ris_dump_v6_uri);
+        let analyser = BgpAnalyser::new(
+            Some(BgpAnnouncementSource::RisDumps(loader)),
+            BGP_RIS_REFRESH_MINUTES,
+            BGP_RIS_MIN_PEERS,
+        );
 
         assert!(analyser.seen.read().await.is_empty());
         assert!(analyser.seen.read().await.last_checked().is_none());
@@ -360,6 +428,56 @@ mod tests {
         assert!(analyser.seen.read().await.last_checked().is_some());
     }
 
+    #[tokio::test]
+    async fn update_from_local_file() {
+        let loader = LocalFileLoader::new(
+            PathBuf::from("test-resources/bgp/announcements.csv"),
+            LocalFileFormat::Csv,
+        );
+        let analyser = BgpAnalyser {
+            source: Some(BgpAnnouncementSource::LocalFile(loader)),
+            refresh_minutes: BGP_RIS_REFRESH_MINUTES,
+            min_peers: 1,
+            seen: RwLock::new(Announcements::default()),
+        };
+
+        assert!(analyser.update().await.unwrap());
+        assert_eq!(4, analyser.seen.read().await.size());
+
+        // not updated again until the refresh interval has passed
+        assert!(!analyser.update().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn ignore_announcements_seen_by_few_peers() {
+        let loader = LocalFileLoader::new(
+            PathBuf::from("test-resources/bgp/announcements.csv"),
+            LocalFileFormat::Csv,
+        );
+        let analyser = BgpAnalyser {
+            source: Some(BgpAnnouncementSource::LocalFile(loader)),
+            refresh_minutes: BGP_RIS_REFRESH_MINUTES,
+            min_peers: 3,
+            seen: RwLock::new(Announcements::default()),
+        };
+
+        analyser.update().await.unwrap();
+
+        // 2001:db8::/32 is seen by 1 peer only, 192.168.0.0/24 has no peer information
+        let seen = analyser.seen.read().await;
+        let mut prefixes: Vec<String> = seen.all().iter().map(|a| a.prefix().to_string()).collect();
+        prefixes.sort();
+        assert_eq!(prefixes, vec!["10.0.0.0/22", "10.0.0.0/24", "192.168.0.0/24"]);
+
+        let resources_held = ResourceSet::from_strs("", "10.0.0.0/8", "").unwrap();
+        let report = analyser.analyse(&[], &resources_held, None).await;
+        for entry in report.entries() {
+            assert_eq!(entry.state(), BgpAnalysisState::AnnouncementNotFound);
+            assert!(entry.visibility().unwrap().peers() >= 3);
+            assert_eq!(entry.announcement().visibility(), entry.visibility());
+        }
+    }
+
     #[tokio::test]
     async fn analyse_bgp() {
         let roa_too_permissive = definition("10.0.0.0/22-23 => 64496");
@@ -430,6 +548,65 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn simulate_changes_across_cas() {
+        let analyser = BgpAnalyser::with_test_announcements();
+
+        let parent_roa = definition("10.0.0.0/22-24 => 64496");
+        let child_roa = definition("192.168.0.0/24 => 64497");
+        let child_as0 = definition("192.168.1.0/24 => 0");
+
+        let parent = CaRoutes::new(
+            handle("parent"),
+            ipv4_resources("10.0.0.0/16, 192.168.0.0/16"),
+            vec![parent_roa],
+        );
+        let child = CaRoutes::new(
+            handle("child"),
+            ipv4_resources("192.168.0.0/23"),
+            vec![child_roa, child_as0],
+        );
+        let before = vec![parent.clone(), child.clone()];
+
+        // Nothing changes, nothing is reported
+        let report = analyser.simulate(&before, &before).await;
+        assert!(report.announcement_info());
+        assert!(report.entries().is_empty());
+
+        // The child loses 192.168.0.0/24, so its ROA is no longer published.
+        // The parent ROA is replaced by a ROA for a different ASN.
+        let after = vec![
+            CaRoutes::new(
+                handle("parent"),
+                ipv4_resources("10.0.0.0/16, 192.168.0.0/16"),
+                vec![definition("10.0.0.0/22 => 64499")],
+            ),
+            CaRoutes::new(handle("child"), ipv4_resources("192.168.1.0/24"), child.roas().clone()),
+        ];
+
+        let report = analyser.simulate(&before, &after).await;
+        let flipped: Vec<String> = report.entries().iter().map(|e| e.to_string()).collect();
+
+        assert_eq!(
+            flipped,
+            vec![
+                "10.0.0.0/22 => 64496: valid -> invalid (asn) (CAs: parent)",
+                "10.0.0.0/24 => 64496: valid -> invalid (asn) (CAs: parent)",
+                "10.0.2.0/23 => 64496: valid -> invalid (asn) (CAs: parent)",
+                "192.168.0.0/24 => 64496: invalid (asn) -> not found (CAs: child, parent)",
+                "192.168.0.0/24 => 64497: valid -> not found (CAs: child, parent)",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn simulate_without_announcements() {
+        let analyser = BgpAnalyser::new(None, BGP_RIS_REFRESH_MINUTES, 1);
+        let ca = CaRoutes::new(handle("ca"), ipv4_resources("10.0.0.0/16"), vec![]);
+        let report = analyser.simulate(&[ca.clone()], &[ca]).await;
+        assert!(!report.announcement_info());
+    }
+
     #[tokio::test]
     async fn analyse_bgp_no_announcements() {
         let roa1 = definition("10.0.0.0/23-24 => 64496");
@@ -438,7 +615,7 @@ mod tests {
 
         let resources_held = ResourceSet::from_strs("", "10.0.0.0/16", "").unwrap();
 
-        let analyser = BgpAnalyser::new(false, "", "");
+        let analyser = BgpAnalyser::new(None, BGP_RIS_REFRESH_MINUTES, 1);
         let table = analyser.analyse(&[roa1, roa2, roa3], &resources_held, None).await;
         let table_entries = table.entries();
         assert_eq!(3, table_entries.len());
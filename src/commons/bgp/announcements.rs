@@ -1,9 +1,15 @@
-use std::{cmp::Ordering, collections::HashSet, fmt, str::FromStr};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
 
 use rpki::repository::x509::Time;
 
 use crate::commons::{
-    api::{AsNumber, RoaDefinition, TypedPrefix},
+    api::{AsNumber, RoaDefinition, Timestamp, TypedPrefix},
     bgp::{IpRange, TypedPrefixTree, TypedPrefixTreeBuilder},
 };
 
@@ -38,15 +44,31 @@ where
 
 //------------ Announcement --------------------------------------------------
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+/// An announcement of a prefix by an origin ASN, as seen in BGP.
+///
+/// Announcements may carry information about how widely they were seen.
+/// This information is informational only: two announcements for the same
+/// prefix and ASN are considered equal regardless of their visibility.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub struct Announcement {
     asn: AsNumber,
     prefix: TypedPrefix,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visibility: Option<AnnouncementVisibility>,
 }
 
 impl Announcement {
     pub fn new(asn: AsNumber, prefix: TypedPrefix) -> Self {
-        Announcement { asn, prefix }
+        Announcement {
+            asn,
+            prefix,
+            visibility: None,
+        }
+    }
+
+    pub fn with_visibility(mut self, visibility: AnnouncementVisibility) -> Self {
+        self.visibility = Some(visibility);
+        self
     }
 
     pub fn asn(&self) -> &AsNumber {
@@ -57,6 +79,17 @@ impl Announcement {
         &self.prefix
     }
 
+    pub fn visibility(&self) -> Option<AnnouncementVisibility> {
+        self.visibility
+    }
+
+    /// Returns true if this announcement was seen by at least the given
+    /// number of peers. Announcements without visibility information are
+    /// always considered to be visible enough.
+    pub fn seen_by_at_least(&self, peers: u32) -> bool {
+        self.visibility.map(|v| v.peers >= peers).unwrap_or(true)
+    }
+
     pub fn validate(&self, roas: &RoaTree) -> ValidatedAnnouncement {
         let covering = roas.matching_or_less_specific(&self.prefix);
         if covering.is_empty() {
@@ -130,6 +163,21 @@ impl fmt::Display for Announcement {
     }
 }
 
+impl PartialEq for Announcement {
+    fn eq(&self, other: &Self) -> bool {
+        self.asn == other.asn && self.prefix == other.prefix
+    }
+}
+
+impl Eq for Announcement {}
+
+impl Hash for Announcement {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.asn.hash(state);
+        self.prefix.hash(state);
+    }
+}
+
 impl Ord for Announcement {
     fn cmp(&self, other: &Self) -> Ordering {
         let mut ordering = self.prefix.cmp(other.prefix());
@@ -154,10 +202,7 @@ impl From<Announcement> for RoaDefinition {
 
 impl From<RoaDefinition> for Announcement {
     fn from(d: RoaDefinition) -> Self {
-        Announcement {
-            asn: d.asn(),
-            prefix: d.prefix(),
-        }
+        Announcement::new(d.asn(), d.prefix())
     }
 }
 
@@ -167,6 +212,57 @@ impl AsRef<TypedPrefix> for Announcement {
     }
 }
 
+//------------ AnnouncementVisibility ----------------------------------------
+
+/// Describes how widely an announcement was seen by the BGP source.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct AnnouncementVisibility {
+    peers: u32,
+    first_seen: Timestamp,
+    last_seen: Timestamp,
+}
+
+impl AnnouncementVisibility {
+    pub fn new(peers: u32, first_seen: Timestamp, last_seen: Timestamp) -> Self {
+        AnnouncementVisibility {
+            peers,
+            first_seen,
+            last_seen,
+        }
+    }
+
+    /// Visibility for an announcement seen by a number of peers just now,
+    /// for sources which do not keep track of time.
+    pub fn seen_now(peers: u32) -> Self {
+        let now = Timestamp::now();
+        AnnouncementVisibility::new(peers, now, now)
+    }
+
+    pub fn peers(&self) -> u32 {
+        self.peers
+    }
+
+    pub fn first_seen(&self) -> Timestamp {
+        self.first_seen
+    }
+
+    pub fn last_seen(&self) -> Timestamp {
+        self.last_seen
+    }
+}
+
+impl fmt::Display for AnnouncementVisibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "seen by {} peers, first seen: {}, last seen: {}",
+            self.peers,
+            self.first_seen.to_rfc3339(),
+            self.last_seen.to_rfc3339()
+        )
+    }
+}
+
 //------------ Announcements -------------------------------------------------
 
 pub struct Announcements {
@@ -176,9 +272,23 @@ pub struct Announcements {
 }
 
 impl Announcements {
+    /// Replaces the current announcements. If an announcement was seen
+    /// before, then its original first seen time is kept.
     pub fn update(&mut self, announcements: Vec<Announcement>) {
+        let previous: HashMap<&Announcement, Timestamp> = self
+            .seen
+            .all()
+            .into_iter()
+            .filter_map(|a| a.visibility.map(|v| (a, v.first_seen)))
+            .collect();
+
         let mut builder = TypedPrefixTreeBuilder::default();
-        for a in announcements {
+        for mut a in announcements {
+            if let Some(first_seen) = previous.get(&a).copied() {
+                if let Some(visibility) = a.visibility.as_mut() {
+                    visibility.first_seen = visibility.first_seen.min(first_seen);
+                }
+            }
             builder.add(a);
         }
         let tree = builder.build();
@@ -192,9 +302,14 @@ impl Announcements {
         self.last_checked = Some(Time::now())
     }
 
+    /// Returns true if the given announcements are the same as the current
+    /// announcements, and they are seen by the same number of peers.
     pub fn equivalent(&self, announcements: &[Announcement]) -> bool {
-        let current_set: HashSet<&Announcement> = self.seen.all().into_iter().collect();
-        let new_set: HashSet<&Announcement> = announcements.iter().collect();
+        fn peers(a: &Announcement) -> (&Announcement, Option<u32>) {
+            (a, a.visibility.map(|v| v.peers))
+        }
+        let current_set: HashSet<_> = self.seen.all().into_iter().map(peers).collect();
+        let new_set: HashSet<_> = announcements.iter().map(peers).collect();
         current_set == new_set
     }
 
@@ -281,6 +396,35 @@ pub enum AnnouncementValidity {
     NotFound,
 }
 
+impl AnnouncementValidity {
+    /// Returns true for any invalid state, including announcements that
+    /// are disallowed by AS0 ROAs.
+    pub fn is_invalid(self) -> bool {
+        matches!(
+            self,
+            AnnouncementValidity::InvalidLength | AnnouncementValidity::InvalidAsn | AnnouncementValidity::Disallowed
+        )
+    }
+
+    /// Returns true if the other state is the same in terms of RFC 6811,
+    /// i.e. both are valid, invalid or not found.
+    pub fn same_outcome(self, other: AnnouncementValidity) -> bool {
+        self == other || (self.is_invalid() && other.is_invalid())
+    }
+}
+
+impl fmt::Display for AnnouncementValidity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnnouncementValidity::Valid => write!(f, "valid"),
+            AnnouncementValidity::InvalidLength => write!(f, "invalid (length)"),
+            AnnouncementValidity::InvalidAsn => write!(f, "invalid (asn)"),
+            AnnouncementValidity::Disallowed => write!(f, "invalid (disallowed by AS0)"),
+            AnnouncementValidity::NotFound => write!(f, "not found"),
+        }
+    }
+}
+
 //------------ Tests --------------------------------------------------------
 
 #[cfg(test)]
@@ -333,4 +477,38 @@ mod tests {
         assert_state(&ann_il, &roas, AnnouncementValidity::InvalidLength);
         assert_state(&ann_nf, &roas, AnnouncementValidity::NotFound);
     }
+
+    #[test]
+    fn keep_first_seen_on_update() {
+        let first = Timestamp::now_minus_hours(24);
+        let later = Timestamp::now();
+
+        let ann = announcement("10.0.0.0/24 => 64496");
+
+        let mut announcements = Announcements::default();
+        announcements.update(vec![ann.with_visibility(AnnouncementVisibility::new(10, first, first))]);
+
+        let updated = vec![ann.with_visibility(AnnouncementVisibility::new(12, later, later))];
+        assert!(!announcements.equivalent(&updated));
+        announcements.update(updated);
+
+        let visibility = announcements.all()[0].visibility().unwrap();
+        assert_eq!(visibility.peers(), 12);
+        assert_eq!(visibility.first_seen(), first);
+        assert_eq!(visibility.last_seen(), later);
+    }
+
+    #[test]
+    fn visibility_does_not_affect_equality() {
+        let ann = announcement("10.0.0.0/24 => 64496");
+        let seen = ann.with_visibility(AnnouncementVisibility::seen_now(3));
+
+        assert_eq!(ann, seen);
+        assert!(seen.seen_by_at_least(3));
+        assert!(!seen.seen_by_at_least(4));
+        assert!(ann.seen_by_at_least(100));
+
+        let json = serde_json::to_string(&ann).unwrap();
+        assert!(!json.contains("visibility"));
+    }
 }
@@ -0,0 +1,429 @@
+//! Support learning announcements from routers using the BGP Monitoring
+//! Protocol (RFC 7854).
+//!
+//! Routers are configured to connect to the listener. We keep the routes
+//! reported in Route Monitoring messages per monitored peer, and forget
+//! all routes for a peer when it goes down, or when the router
+//! disconnects.
+
+use std::{
+    collections::HashMap,
+    io::{ErrorKind, Read},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, RwLock},
+    thread,
+};
+
+use crate::commons::{
+    api::{AsNumber, Timestamp, TypedPrefix},
+    bgp::{
+        wire::{PathAttributes, WireReader, AFI_IPV4},
+        Announcement, AnnouncementVisibility, BgpSourceError,
+    },
+};
+
+const BMP_VERSION: u8 = 3;
+const BMP_COMMON_HEADER_LEN: usize = 6;
+const BMP_PER_PEER_HEADER_LEN: usize = 42;
+
+// The largest message we accept. This leaves room for a route monitoring
+// message with a BGP UPDATE of up to 65535 bytes (RFC 8654). Anything bigger
+// is not BMP as we know it, and we will not allocate memory for it.
+const BMP_MAX_MSG_LEN: usize = BMP_COMMON_HEADER_LEN + BMP_PER_PEER_HEADER_LEN + 65_535;
+
+const BMP_MSG_ROUTE_MONITORING: u8 = 0;
+const BMP_MSG_PEER_DOWN: u8 = 2;
+const BMP_MSG_PEER_UP: u8 = 3;
+const BMP_MSG_TERMINATION: u8 = 5;
+
+const BMP_PEER_FLAG_POST_POLICY: u8 = 0x40;
+const BMP_PEER_FLAG_LEGACY_AS_PATH: u8 = 0x20;
+
+const BGP_HEADER_LEN: usize = 19;
+const BGP_MSG_UPDATE: u8 = 2;
+
+//------------ BmpListener ---------------------------------------------------
+
+/// Listens for BMP connections from routers, and keeps the routes they
+/// report in a shared [`BmpRib`].
+pub struct BmpListener {
+    addr: SocketAddr,
+    rib: Arc<RwLock<BmpRib>>,
+}
+
+impl BmpListener {
+    /// Binds to the given address, and starts accepting BMP connections
+    /// in a background thread.
+    pub fn start(addr: SocketAddr) -> Result<Self, BgpSourceError> {
+        let listener =
+            TcpListener::bind(addr).map_err(|e| BgpSourceError::Listener(format!("cannot bind to {}: {}", addr, e)))?;
+
+        let rib = Arc::new(RwLock::new(BmpRib::default()));
+
+        let accept_rib = rib.clone();
+        thread::Builder::new()
+            .name("bmp-listener".to_string())
+            .spawn(move || Self::accept_connections(listener, accept_rib))
+            .map_err(|e| BgpSourceError::Listener(format!("cannot start listener thread: {}", e)))?;
+
+        info!("Listening for BMP connections on {}", addr);
+
+        Ok(BmpListener { addr, rib })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns all announcements currently known from any router.
+    pub fn announcements(&self) -> Vec<Announcement> {
+        self.rib.read().unwrap().announcements()
+    }
+
+    fn accept_connections(listener: TcpListener, rib: Arc<RwLock<BmpRib>>) {
+        let mut next_router_id = 0;
+        for stream in listener.incoming() {
+            match stream {
+                Err(e) => warn!("Could not accept BMP connection: {}", e),
+                Ok(stream) => {
+                    let router = next_router_id;
+                    next_router_id += 1;
+
+                    let rib = rib.clone();
+                    let spawned = thread::Builder::new()
+                        .name(format!("bmp-router-{}", router))
+                        .spawn(move || Self::handle_connection(router, stream, rib));
+
+                    if let Err(e) = spawned {
+                        error!("Could not start thread for BMP connection: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_connection(router: u64, stream: TcpStream, rib: Arc<RwLock<BmpRib>>) {
+        let remote = stream
+            .peer_addr()
+            .map(|addr| addr.to_string())
+            .unwrap_or_else(|_| "<unknown>".to_string());
+
+        info!("Accepted BMP connection from router {}", remote);
+
+        match BmpRib::process_stream(&rib, router, stream) {
+            Ok(()) => info!("BMP connection from router {} closed", remote),
+            Err(e) => warn!("BMP connection from router {} dropped: {}", remote, e),
+        }
+
+        rib.write().unwrap().router_down(router);
+    }
+}
+
+//------------ BmpPeer -------------------------------------------------------
+
+/// Identifies a monitored BGP peer on a specific router connection.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct BmpPeer {
+    router: u64,
+    distinguisher: u64,
+    address: [u8; 16],
+    post_policy: bool,
+}
+
+//------------ BmpRib --------------------------------------------------------
+
+/// The routes currently reported by each monitored peer, with the origin
+/// ASN and the time that the peer started announcing it.
+#[derive(Default)]
+pub struct BmpRib {
+    peers: HashMap<BmpPeer, HashMap<TypedPrefix, (AsNumber, Timestamp)>>,
+}
+
+impl BmpRib {
+    /// Returns the announcements currently seen, with the number of peers
+    /// which carry them. Because the routes are still in the RIB they are
+    /// seen right now.
+    pub fn announcements(&self) -> Vec<Announcement> {
+        let now = Timestamp::now();
+        let mut seen: HashMap<Announcement, AnnouncementVisibility> = HashMap::new();
+
+        for routes in self.peers.values() {
+            for (prefix, (asn, since)) in routes.iter() {
+                seen.entry(Announcement::new(*asn, *prefix))
+                    .and_modify(|v| *v = AnnouncementVisibility::new(v.peers() + 1, v.first_seen().min(*since), now))
+                    .or_insert_with(|| AnnouncementVisibility::new(1, *since, now));
+            }
+        }
+
+        seen.into_iter()
+            .map(|(announcement, visibility)| announcement.with_visibility(visibility))
+            .collect()
+    }
+
+    /// Reads and processes BMP messages from the stream until it is closed
+    /// by the router, or until an error occurs.
+    pub fn process_stream(rib: &RwLock<BmpRib>, router: u64, mut stream: impl Read) -> Result<(), BgpSourceError> {
+        loop {
+            let mut header = [0u8; BMP_COMMON_HEADER_LEN];
+            match stream.read_exact(&mut header) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(BgpSourceError::Listener(e.to_string())),
+            }
+
+            let version = header[0];
+            let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+            let typ = header[5];
+
+            if version != BMP_VERSION {
+                return Err(BgpSourceError::parse_error(format!(
+                    "unsupported BMP version {}",
+                    version
+                )));
+            }
+            if !(BMP_COMMON_HEADER_LEN..=BMP_MAX_MSG_LEN).contains(&len) {
+                return Err(BgpSourceError::parse_error(format!(
+                    "invalid BMP message length {}",
+                    len
+                )));
+            }
+
+            let mut body = vec![0u8; len - BMP_COMMON_HEADER_LEN];
+            stream
+                .read_exact(&mut body)
+                .map_err(|e| BgpSourceError::Listener(e.to_string()))?;
+
+            if typ == BMP_MSG_TERMINATION {
+                return Ok(());
+            }
+
+            rib.write().unwrap().process_message(router, typ, &body)?;
+        }
+    }
+
+    fn process_message(&mut self, router: u64, typ: u8, body: &[u8]) -> Result<(), BgpSourceError> {
+        match typ {
+            BMP_MSG_ROUTE_MONITORING => {
+                let mut reader = WireReader::new(body);
+                let (peer, peer_as, legacy_as_path) = Self::parse_per_peer_header(router, &mut reader)?;
+                let update = reader.take(reader.remaining())?;
+                self.process_update(peer, peer_as, legacy_as_path, update)
+            }
+            BMP_MSG_PEER_UP | BMP_MSG_PEER_DOWN => {
+                // Either way we start from scratch for this peer. After a peer
+                // up the router will send all routes as route monitoring messages.
+                let mut reader = WireReader::new(body);
+                let (peer, _, _) = Self::parse_per_peer_header(router, &mut reader)?;
+                self.peers.remove(&peer);
+                Ok(())
+            }
+            _ => Ok(()), // statistics, initiation and route mirroring are of no interest
+        }
+    }
+
+    fn parse_per_peer_header(
+        router: u64,
+        reader: &mut WireReader,
+    ) -> Result<(BmpPeer, AsNumber, bool), BgpSourceError> {
+        let header = reader.take(BMP_PER_PEER_HEADER_LEN)?;
+        let mut header = WireReader::new(header);
+
+        let _peer_type = header.u8()?;
+        let flags = header.u8()?;
+        let distinguisher = (header.u32()? as u64) << 32 | header.u32()? as u64;
+        let mut address = [0u8; 16];
+        address.copy_from_slice(header.take(16)?);
+        let peer_as = AsNumber::new(header.u32()?);
+
+        let peer = BmpPeer {
+            router,
+            distinguisher,
+            address,
+            post_policy: flags & BMP_PEER_FLAG_POST_POLICY == BMP_PEER_FLAG_POST_POLICY,
+        };
+
+        let legacy_as_path = flags & BMP_PEER_FLAG_LEGACY_AS_PATH == BMP_PEER_FLAG_LEGACY_AS_PATH;
+
+        Ok((peer, peer_as, legacy_as_path))
+    }
+
+    fn process_update(
+        &mut self,
+        peer: BmpPeer,
+        peer_as: AsNumber,
+        legacy_as_path: bool,
+        bgp_msg: &[u8],
+    ) -> Result<(), BgpSourceError> {
+        let mut reader = WireReader::new(bgp_msg);
+        reader.skip(16)?; // marker
+        let len = reader.u16()? as usize;
+        let typ = reader.u8()?;
+
+        if typ != BGP_MSG_UPDATE {
+            return Ok(());
+        }
+        if len < BGP_HEADER_LEN {
+            return Err(BgpSourceError::parse_error(format!(
+                "invalid BGP message length {}",
+                len
+            )));
+        }
+
+        let mut reader = WireReader::new(reader.take(len - BGP_HEADER_LEN)?);
+
+        let withdrawn_len = reader.u16()? as usize;
+        let mut withdrawn_reader = WireReader::new(reader.take(withdrawn_len)?);
+        let attributes_len = reader.u16()? as usize;
+        let attributes = PathAttributes::parse(reader.take(attributes_len)?, !legacy_as_path, false)?;
+
+        let routes = self.peers.entry(peer).or_default();
+
+        while !withdrawn_reader.is_empty() {
+            routes.remove(&withdrawn_reader.prefix(AFI_IPV4)?);
+        }
+        for prefix in &attributes.mp_unreach {
+            routes.remove(prefix);
+        }
+
+        let origin = if attributes.empty_path {
+            Some(peer_as) // originated by the peer's own AS
+        } else {
+            attributes.origin
+        };
+
+        let mut announced = attributes.mp_reach;
+        while !reader.is_empty() {
+            announced.push(reader.prefix(AFI_IPV4)?);
+        }
+
+        for prefix in announced {
+            match origin {
+                Some(asn) => {
+                    // Keep the original time if this is an update for an
+                    // unchanged origin, e.g. because of a changed path.
+                    let since = match routes.get(&prefix) {
+                        Some((current, since)) if *current == asn => *since,
+                        _ => Timestamp::now(),
+                    };
+                    routes.insert(prefix, (asn, since));
+                }
+                None => {
+                    // We cannot determine the origin (AS_SET), so we cannot
+                    // use this route, but it does replace any previous route.
+                    routes.remove(&prefix);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn router_down(&mut self, router: u64) {
+        self.peers.retain(|peer, _| peer.router != router);
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, time::Duration};
+
+    use super::*;
+    use crate::test::*;
+
+    fn session() -> Vec<u8> {
+        std::fs::read("test-resources/bgp/bmp-session.bin").unwrap()
+    }
+
+    #[test]
+    fn process_recorded_bmp_session() {
+        let rib = RwLock::new(BmpRib::default());
+        BmpRib::process_stream(&rib, 0, session().as_slice()).unwrap();
+
+        let mut announcements = rib.read().unwrap().announcements();
+        announcements.sort();
+
+        // The recorded session contains:
+        // - peer up for peers A (AS64511) and B (AS64510)
+        // - A: 10.0.0.0/22 and 10.0.0.0/24 with path 64511 64496
+        // - A: 10.0.2.0/23 with path 64511 64497, later withdrawn
+        // - B: 10.0.2.0/23 with an empty path, using a legacy 2 byte AS_PATH
+        // - B: 2001:db8::/32 with path 64510 64498 (MP_REACH_NLRI)
+        // - A: 192.168.0.0/24 with path 64511 {64497 64499} (AS_SET)
+        let expected = vec![
+            announcement("10.0.0.0/22 => 64496"),
+            announcement("10.0.0.0/24 => 64496"),
+            announcement("10.0.2.0/23 => 64510"),
+            announcement("2001:db8::/32 => 64498"),
+        ];
+
+        assert_eq!(announcements, expected);
+    }
+
+    #[test]
+    fn forget_routes_when_router_disconnects() {
+        let rib = RwLock::new(BmpRib::default());
+        BmpRib::process_stream(&rib, 0, session().as_slice()).unwrap();
+        BmpRib::process_stream(&rib, 1, session().as_slice()).unwrap();
+        assert_eq!(4, rib.read().unwrap().announcements().len());
+
+        // every announcement is now seen by the same peer on both routers
+        for announcement in rib.read().unwrap().announcements() {
+            assert_eq!(2, announcement.visibility().unwrap().peers());
+        }
+
+        rib.write().unwrap().router_down(0);
+        assert_eq!(4, rib.read().unwrap().announcements().len());
+
+        rib.write().unwrap().router_down(1);
+        assert!(rib.read().unwrap().announcements().is_empty());
+    }
+
+    #[test]
+    fn reject_unsupported_bmp_version() {
+        let rib = RwLock::new(BmpRib::default());
+        let msg = [1u8, 0, 0, 0, 6, 4];
+        assert!(BmpRib::process_stream(&rib, 0, &msg[..]).is_err());
+    }
+
+    #[test]
+    fn reject_oversized_bmp_message() {
+        let rib = RwLock::new(BmpRib::default());
+
+        // Claims to be a message of 4 GiB - 1, but has no body. We should
+        // reject this based on the length, rather than try to read it.
+        let msg = [BMP_VERSION, 0xff, 0xff, 0xff, 0xff, BMP_MSG_ROUTE_MONITORING];
+        let err = BmpRib::process_stream(&rib, 0, &msg[..]).unwrap_err();
+        assert!(err.to_string().contains("invalid BMP message length"));
+
+        let len = (BMP_MAX_MSG_LEN as u32 + 1).to_be_bytes();
+        let msg = [BMP_VERSION, len[0], len[1], len[2], len[3], BMP_MSG_ROUTE_MONITORING];
+        let err = BmpRib::process_stream(&rib, 0, &msg[..]).unwrap_err();
+        assert!(err.to_string().contains("invalid BMP message length"));
+    }
+
+    #[test]
+    fn listen_for_bmp_connections() {
+        let free_addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let listener = BmpListener::start(free_addr).unwrap();
+
+        let mut stream = TcpStream::connect(listener.addr()).unwrap();
+        stream.write_all(&session()).unwrap();
+
+        let mut tries = 0;
+        while listener.announcements().len() < 4 && tries < 50 {
+            thread::sleep(Duration::from_millis(100));
+            tries += 1;
+        }
+        assert_eq!(4, listener.announcements().len());
+
+        drop(stream);
+        let mut tries = 0;
+        while !listener.announcements().is_empty() && tries < 50 {
+            thread::sleep(Duration::from_millis(100));
+            tries += 1;
+        }
+        assert!(listener.announcements().is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+//! Support reading announcements from a simple local CSV or JSON file.
+//!
+//! The CSV format expects one announcement per line, with the prefix and
+//! origin ASN in the first two columns, and optionally the number of peers
+//! that see the announcement in the third column, e.g.:
+//!
+//!   prefix,asn,peers
+//!   10.0.0.0/24,AS64496,12
+//!   2001:db8::/32,64497
+//!
+//! A header line and lines starting with '#' are ignored. The JSON format
+//! expects an array of objects, with optional visibility, e.g.:
+//!
+//!   [ { "prefix": "10.0.0.0/24", "asn": 64496 } ]
+
+use std::{io::BufRead, path::PathBuf, str::FromStr};
+
+use crate::commons::{
+    api::{AsNumber, TypedPrefix},
+    bgp::{read_maybe_gzipped, Announcement, AnnouncementVisibility, BgpSourceError},
+};
+
+//------------ LocalFileFormat -----------------------------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LocalFileFormat {
+    Csv,
+    Json,
+}
+
+//------------ LocalFileLoader -----------------------------------------------
+
+pub struct LocalFileLoader {
+    path: PathBuf,
+    format: LocalFileFormat,
+}
+
+impl LocalFileLoader {
+    pub fn new(path: PathBuf, format: LocalFileFormat) -> Self {
+        LocalFileLoader { path, format }
+    }
+
+    pub fn load(&self) -> Result<Vec<Announcement>, BgpSourceError> {
+        let bytes = read_maybe_gzipped(&self.path)?;
+        match self.format {
+            LocalFileFormat::Csv => Self::parse_csv(&bytes),
+            LocalFileFormat::Json => serde_json::from_slice(&bytes).map_err(BgpSourceError::parse_error),
+        }
+    }
+
+    fn parse_csv(bytes: &[u8]) -> Result<Vec<Announcement>, BgpSourceError> {
+        let mut res = vec![];
+        for (nr, line_res) in bytes.lines().enumerate() {
+            let line = line_res.map_err(BgpSourceError::parse_error)?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut values = line.split(',');
+
+            let prefix_str = values.next().unwrap_or_default().trim();
+            let asn_str = values
+                .next()
+                .ok_or_else(|| BgpSourceError::ParseError(format!("missing asn column on line {}", nr + 1)))?
+                .trim();
+
+            if nr == 0 && prefix_str.eq_ignore_ascii_case("prefix") {
+                continue; // header
+            }
+
+            let asn_str = asn_str
+                .strip_prefix("AS")
+                .or_else(|| asn_str.strip_prefix("as"))
+                .unwrap_or(asn_str);
+
+            let asn = AsNumber::from_str(asn_str)?;
+            let prefix = TypedPrefix::from_str(prefix_str)?;
+            let mut announcement = Announcement::new(asn, prefix);
+
+            if let Some(peers_str) = values.next().map(str::trim).filter(|s| !s.is_empty()) {
+                let peers = u32::from_str(peers_str).map_err(|e| {
+                    BgpSourceError::ParseError(format!("invalid peers column on line {}: {}", nr + 1, e))
+                })?;
+                announcement = announcement.with_visibility(AnnouncementVisibility::seen_now(peers));
+            }
+
+            res.push(announcement);
+        }
+        Ok(res)
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    fn expected() -> Vec<Announcement> {
+        let mut expected = vec![
+            announcement("10.0.0.0/22 => 64496"),
+            announcement("10.0.0.0/24 => 64496"),
+            announcement("192.168.0.0/24 => 64497"),
+            announcement("2001:db8::/32 => 64498"),
+        ];
+        expected.sort();
+        expected
+    }
+
+    #[test]
+    fn parse_csv_file() {
+        let loader = LocalFileLoader::new(
+            PathBuf::from("test-resources/bgp/announcements.csv"),
+            LocalFileFormat::Csv,
+        );
+        let mut announcements = loader.load().unwrap();
+        announcements.sort();
+        assert_eq!(announcements, expected());
+
+        let peers: Vec<Option<u32>> = announcements
+            .iter()
+            .map(|a| a.visibility().map(|v| v.peers()))
+            .collect();
+        assert_eq!(peers, vec![Some(12), Some(3), Some(1), None]);
+    }
+
+    #[test]
+    fn parse_json_file() {
+        let loader = LocalFileLoader::new(
+            PathBuf::from("test-resources/bgp/announcements.json"),
+            LocalFileFormat::Json,
+        );
+        let mut announcements = loader.load().unwrap();
+        announcements.sort();
+        assert_eq!(announcements, expected());
+    }
+
+    #[test]
+    fn reject_csv_with_bad_asn() {
+        assert!(LocalFileLoader::parse_csv(b"10.0.0.0/24,ASfoo").is_err());
+        assert!(LocalFileLoader::parse_csv(b"10.0.0.0/24").is_err());
+        assert!(LocalFileLoader::parse_csv(b"10.0.0.0/24,AS64496,many").is_err());
+    }
+}
@@ -4,11 +4,28 @@ pub use self::analyser::*;
 mod announcements;
 pub use self::announcements::*;
 
+mod bmp;
+pub use self::bmp::*;
+
 mod iptree;
 pub use self::iptree::*;
 
+mod localfile;
+pub use self::localfile::*;
+
+mod mrt;
+pub use self::mrt::*;
+
 mod risdumps;
 pub use self::risdumps::*;
 
 mod report;
 pub use self::report::*;
+
+mod simulation;
+pub use self::simulation::*;
+
+mod sources;
+pub use self::sources::*;
+
+mod wire;
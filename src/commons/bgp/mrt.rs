@@ -0,0 +1,197 @@
+//! Support parsing announcements in MRT RIB dumps (RFC 6396)
+//!
+//! Only the TABLE_DUMP_V2 type is supported, as produced by e.g. RIS, Route
+//! Views, BIRD and most router vendors. Both IPv4 and IPv6 unicast RIB
+//! entries are used, including the ADD-PATH variants defined in RFC 8050.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use crate::commons::{
+    api::Timestamp,
+    bgp::{
+        read_maybe_gzipped,
+        wire::{PathAttributes, WireReader, AFI_IPV4, AFI_IPV6},
+        Announcement, AnnouncementVisibility, BgpSourceError,
+    },
+};
+
+const MRT_TYPE_TABLE_DUMP_V2: u16 = 13;
+
+const SUBTYPE_RIB_IPV4_UNICAST: u16 = 2;
+const SUBTYPE_RIB_IPV6_UNICAST: u16 = 4;
+const SUBTYPE_RIB_IPV4_UNICAST_ADDPATH: u16 = 8;
+const SUBTYPE_RIB_IPV6_UNICAST_ADDPATH: u16 = 10;
+
+//------------ MrtSeen -------------------------------------------------------
+
+/// Keeps track of the peers that have seen an announcement in a dump, and
+/// when. Many peers will typically report the same prefix and origin.
+#[derive(Default)]
+struct MrtSeen {
+    peers: HashSet<u16>,
+    first_seen: Option<u32>,
+    last_seen: u32,
+}
+
+impl MrtSeen {
+    fn add(&mut self, peer_index: u16, originated: u32, dumped: u32) {
+        self.peers.insert(peer_index);
+        self.first_seen = Some(self.first_seen.map_or(originated, |first| first.min(originated)));
+        self.last_seen = self.last_seen.max(dumped);
+    }
+
+    fn visibility(&self) -> AnnouncementVisibility {
+        AnnouncementVisibility::new(
+            self.peers.len() as u32,
+            Timestamp::new(self.first_seen.unwrap_or(self.last_seen) as i64),
+            Timestamp::new(self.last_seen as i64),
+        )
+    }
+}
+
+//------------ MrtFileLoader -------------------------------------------------
+
+pub struct MrtFileLoader {
+    path: PathBuf,
+}
+
+impl MrtFileLoader {
+    pub fn new(path: PathBuf) -> Self {
+        MrtFileLoader { path }
+    }
+
+    /// Reads the MRT file, which may be gzipped, and returns all
+    /// announcements found in it.
+    pub fn load(&self) -> Result<Vec<Announcement>, BgpSourceError> {
+        let bytes = read_maybe_gzipped(&self.path)?;
+        Self::parse_dump(&bytes)
+    }
+
+    fn parse_dump(bytes: &[u8]) -> Result<Vec<Announcement>, BgpSourceError> {
+        let mut seen: HashMap<Announcement, MrtSeen> = HashMap::new();
+
+        let mut reader = WireReader::new(bytes);
+        while !reader.is_empty() {
+            let timestamp = reader.u32()?;
+            let typ = reader.u16()?;
+            let subtype = reader.u16()?;
+            let len = reader.u32()? as usize;
+            let body = reader.take(len)?;
+
+            if typ != MRT_TYPE_TABLE_DUMP_V2 {
+                continue;
+            }
+
+            let (afi, add_path) = match subtype {
+                SUBTYPE_RIB_IPV4_UNICAST => (AFI_IPV4, false),
+                SUBTYPE_RIB_IPV6_UNICAST => (AFI_IPV6, false),
+                SUBTYPE_RIB_IPV4_UNICAST_ADDPATH => (AFI_IPV4, true),
+                SUBTYPE_RIB_IPV6_UNICAST_ADDPATH => (AFI_IPV6, true),
+                _ => continue, // e.g. the PEER_INDEX_TABLE, or multicast RIBs
+            };
+
+            Self::parse_rib_entries(body, afi, add_path, timestamp, &mut seen)?;
+        }
+
+        Ok(seen
+            .into_iter()
+            .map(|(announcement, seen)| announcement.with_visibility(seen.visibility()))
+            .collect())
+    }
+
+    fn parse_rib_entries(
+        body: &[u8],
+        afi: u16,
+        add_path: bool,
+        dumped: u32,
+        seen: &mut HashMap<Announcement, MrtSeen>,
+    ) -> Result<(), BgpSourceError> {
+        let mut reader = WireReader::new(body);
+
+        let _sequence = reader.u32()?;
+        let prefix = reader.prefix(afi)?;
+        let entry_count = reader.u16()?;
+
+        for _ in 0..entry_count {
+            let peer_index = reader.u16()?;
+            let originated = reader.u32()?;
+            if add_path {
+                let _path_id = reader.u32()?;
+            }
+            let attributes_len = reader.u16()? as usize;
+            let attributes = reader.take(attributes_len)?;
+
+            // AS_PATH attributes in TABLE_DUMP_V2 always use 4 byte ASNs,
+            // and MP_REACH_NLRI only contains the next hop.
+            let attributes = PathAttributes::parse(attributes, true, true)?;
+
+            if let Some(origin) = attributes.origin {
+                seen.entry(Announcement::new(origin, prefix))
+                    .or_default()
+                    .add(peer_index, originated, dumped);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    #[test]
+    fn parse_mrt_table_dump_v2() {
+        let loader = MrtFileLoader::new(PathBuf::from("test-resources/bgp/rib.mrt"));
+        let mut announcements = loader.load().unwrap();
+        announcements.sort();
+
+        let expected = vec![
+            announcement("10.0.0.0/22 => 64496"),
+            announcement("10.0.0.0/24 => 64496"),
+            announcement("10.0.2.0/23 => 64497"),
+            announcement("2001:db8::/32 => 64498"),
+        ];
+
+        assert_eq!(announcements, expected);
+    }
+
+    #[test]
+    fn count_peers_in_mrt_table_dump_v2() {
+        let loader = MrtFileLoader::new(PathBuf::from("test-resources/bgp/rib.mrt"));
+        let mut announcements = loader.load().unwrap();
+        announcements.sort();
+
+        let peers: Vec<u32> = announcements.iter().map(|a| a.visibility().unwrap().peers()).collect();
+        assert_eq!(peers, vec![2, 2, 1, 1]);
+
+        let visibility = announcements[0].visibility().unwrap();
+        assert_eq!(visibility.first_seen(), Timestamp::new(1_600_000_000));
+        assert_eq!(visibility.last_seen(), Timestamp::new(1_600_000_000));
+    }
+
+    #[test]
+    fn parse_gzipped_mrt_table_dump_v2() {
+        let plain = MrtFileLoader::new(PathBuf::from("test-resources/bgp/rib.mrt"));
+        let gzipped = MrtFileLoader::new(PathBuf::from("test-resources/bgp/rib.mrt.gz"));
+
+        let mut plain = plain.load().unwrap();
+        let mut gzipped = gzipped.load().unwrap();
+        plain.sort();
+        gzipped.sort();
+
+        assert_eq!(plain, gzipped);
+    }
+
+    #[test]
+    fn reject_truncated_mrt() {
+        let bytes = std::fs::read("test-resources/bgp/rib.mrt").unwrap();
+        assert!(MrtFileLoader::parse_dump(&bytes[..bytes.len() - 3]).is_err());
+    }
+}
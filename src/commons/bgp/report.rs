@@ -2,7 +2,7 @@ use std::{cmp::Ordering, collections::HashMap, fmt};
 
 use crate::commons::{
     api::{BgpStats, RoaDefinition, RoaDefinitionUpdates},
-    bgp::Announcement,
+    bgp::{Announcement, AnnouncementVisibility},
 };
 
 //------------ BgpAnalysisAdvice -------------------------------------------
@@ -280,7 +280,7 @@ impl fmt::Display for BgpAnalysisSuggestion {
         if !self.not_found.is_empty() {
             writeln!(f, "Authorize these announcements which are currently not covered:")?;
             for auth in &self.not_found {
-                writeln!(f, "  {}", auth)?;
+                writeln!(f, "  {}{}", auth, VisibilityNote(auth.visibility()))?;
             }
             writeln!(f)?;
         }
@@ -291,7 +291,7 @@ impl fmt::Display for BgpAnalysisSuggestion {
                 "Authorize these announcements which are currently invalid because they are too specific:"
             )?;
             for auth in &self.invalid_length {
-                writeln!(f, "  {}", auth)?;
+                writeln!(f, "  {}{}", auth, VisibilityNote(auth.visibility()))?;
             }
             writeln!(f)?;
         }
@@ -302,7 +302,7 @@ impl fmt::Display for BgpAnalysisSuggestion {
                 "Authorize these announcements which are currently invalid because they are not allowed for these ASNs:"
             )?;
             for auth in &self.invalid_asn {
-                writeln!(f, "  {}", auth)?;
+                writeln!(f, "  {}{}", auth, VisibilityNote(auth.visibility()))?;
             }
             writeln!(f)?;
         }
@@ -548,7 +548,12 @@ impl fmt::Display for BgpAnalysisReport {
                 writeln!(f, "Announcements which are valid:")?;
                 writeln!(f)?;
                 for ann in valid {
-                    writeln!(f, "\tAnnouncement: {}", ann.definition)?;
+                    writeln!(
+                        f,
+                        "\tAnnouncement: {}{}",
+                        ann.definition,
+                        VisibilityNote(ann.visibility)
+                    )?;
                 }
                 writeln!(f)?;
             }
@@ -560,7 +565,12 @@ impl fmt::Display for BgpAnalysisReport {
                 )?;
                 for ann in invalid_length {
                     writeln!(f)?;
-                    writeln!(f, "\tAnnouncement: {}", ann.definition)?;
+                    writeln!(
+                        f,
+                        "\tAnnouncement: {}{}",
+                        ann.definition,
+                        VisibilityNote(ann.visibility)
+                    )?;
                     writeln!(f)?;
                     writeln!(f, "\t\tDisallowed by authorization(s):")?;
                     for roa in ann.disallowed_by.iter() {
@@ -574,7 +584,12 @@ impl fmt::Display for BgpAnalysisReport {
                 writeln!(f, "Announcements from an unauthorized ASN:")?;
                 for ann in invalid_asn {
                     writeln!(f)?;
-                    writeln!(f, "\tAnnouncement: {}", ann.definition)?;
+                    writeln!(
+                        f,
+                        "\tAnnouncement: {}{}",
+                        ann.definition,
+                        VisibilityNote(ann.visibility)
+                    )?;
                     writeln!(f)?;
                     writeln!(f, "\t\tDisallowed by authorization(s):")?;
                     for roa in ann.disallowed_by.iter() {
@@ -588,7 +603,12 @@ impl fmt::Display for BgpAnalysisReport {
                 writeln!(f, "Announcements disallowed by 'AS0' ROAs:")?;
                 writeln!(f)?;
                 for ann in disallowed {
-                    writeln!(f, "\tAnnouncement: {}", ann.definition)?;
+                    writeln!(
+                        f,
+                        "\tAnnouncement: {}{}",
+                        ann.definition,
+                        VisibilityNote(ann.visibility)
+                    )?;
                 }
                 writeln!(f)?;
             }
@@ -600,7 +620,12 @@ impl fmt::Display for BgpAnalysisReport {
                 )?;
                 writeln!(f)?;
                 for ann in not_found {
-                    writeln!(f, "\tAnnouncement: {}", ann.definition)?;
+                    writeln!(
+                        f,
+                        "\tAnnouncement: {}{}",
+                        ann.definition,
+                        VisibilityNote(ann.visibility)
+                    )?;
                 }
                 writeln!(f)?;
             }
@@ -610,6 +635,20 @@ impl fmt::Display for BgpAnalysisReport {
     }
 }
 
+//------------ VisibilityNote ----------------------------------------------
+
+/// Shows the number of peers that see an announcement, if known.
+struct VisibilityNote(Option<AnnouncementVisibility>);
+
+impl fmt::Display for VisibilityNote {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(visibility) => write!(f, " (seen by {} peers)", visibility.peers()),
+            None => Ok(()),
+        }
+    }
+}
+
 //------------ BgpAnalysisEntry --------------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
@@ -627,6 +666,8 @@ pub struct BgpAnalysisEntry {
     authorizes: Vec<Announcement>,
     #[serde(skip_serializing_if = "Vec::is_empty", default = "Vec::new")]
     disallows: Vec<Announcement>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    visibility: Option<AnnouncementVisibility>,
 }
 
 impl BgpAnalysisEntry {
@@ -635,7 +676,17 @@ impl BgpAnalysisEntry {
     }
 
     pub fn announcement(&self) -> Announcement {
-        self.definition.into()
+        let announcement = Announcement::from(self.definition);
+        match self.visibility {
+            Some(visibility) => announcement.with_visibility(visibility),
+            None => announcement,
+        }
+    }
+
+    /// The visibility of the announcement, for announcement entries, if
+    /// known by the BGP source.
+    pub fn visibility(&self) -> Option<AnnouncementVisibility> {
+        self.visibility
     }
 
     pub fn state(&self) -> BgpAnalysisState {
@@ -677,6 +728,7 @@ impl BgpAnalysisEntry {
             made_redundant_by: vec![],
             authorizes,
             disallows,
+            visibility: None,
         }
     }
 
@@ -690,6 +742,7 @@ impl BgpAnalysisEntry {
             made_redundant_by: vec![],
             authorizes: vec![],
             disallows,
+            visibility: None,
         }
     }
 
@@ -703,6 +756,7 @@ impl BgpAnalysisEntry {
             made_redundant_by: vec![],
             authorizes: vec![],
             disallows,
+            visibility: None,
         }
     }
 
@@ -716,6 +770,7 @@ impl BgpAnalysisEntry {
             made_redundant_by,
             authorizes: vec![],
             disallows: vec![],
+            visibility: None,
         }
     }
 
@@ -736,6 +791,7 @@ impl BgpAnalysisEntry {
             made_redundant_by,
             authorizes,
             disallows,
+            visibility: None,
         }
     }
 
@@ -754,6 +810,7 @@ impl BgpAnalysisEntry {
             made_redundant_by: vec![],
             authorizes,
             disallows,
+            visibility: None,
         }
     }
 
@@ -766,6 +823,7 @@ impl BgpAnalysisEntry {
             made_redundant_by: vec![],
             authorizes: vec![],
             disallows: vec![],
+            visibility: None,
         }
     }
 
@@ -778,6 +836,7 @@ impl BgpAnalysisEntry {
             made_redundant_by: vec![],
             authorizes: vec![],
             disallows: vec![],
+            visibility: None,
         }
     }
 
@@ -790,6 +849,7 @@ impl BgpAnalysisEntry {
             made_redundant_by: vec![],
             authorizes: vec![],
             disallows: vec![],
+            visibility: None,
         }
     }
 
@@ -802,6 +862,7 @@ impl BgpAnalysisEntry {
             made_redundant_by: vec![],
             authorizes: vec![],
             disallows: vec![],
+            visibility: announcement.visibility(),
         }
     }
 
@@ -831,6 +892,7 @@ impl BgpAnalysisEntry {
             made_redundant_by: vec![],
             authorizes: vec![],
             disallows: vec![],
+            visibility: announcement.visibility(),
         }
     }
 
@@ -843,6 +905,7 @@ impl BgpAnalysisEntry {
             made_redundant_by: vec![],
             authorizes: vec![],
             disallows: vec![],
+            visibility: announcement.visibility(),
         }
     }
 }
@@ -908,4 +971,25 @@ mod tests {
 
         assert_eq!(report.to_string(), expected);
     }
+
+    #[test]
+    fn print_announcement_visibility() {
+        let seen =
+            crate::test::announcement("10.0.0.0/24 => 64496").with_visibility(AnnouncementVisibility::seen_now(7));
+        let report = BgpAnalysisReport::new(vec![BgpAnalysisEntry::announcement_not_found(seen)]);
+
+        assert!(report
+            .to_string()
+            .contains("\tAnnouncement: 10.0.0.0/24 => 64496 (seen by 7 peers)"));
+
+        let json = serde_json::to_string(&report).unwrap();
+        let parsed: BgpAnalysisReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.entries()[0].visibility().unwrap().peers(), 7);
+
+        let mut suggestion = BgpAnalysisSuggestion::default();
+        suggestion.add_not_found(parsed.entries()[0].announcement());
+        assert!(suggestion
+            .to_string()
+            .contains("  10.0.0.0/24 => 64496 (seen by 7 peers)"));
+    }
 }
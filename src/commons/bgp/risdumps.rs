@@ -14,7 +14,7 @@ use libflate::gzip::Decoder;
 
 use crate::commons::{
     api::{AsNumber, AuthorizationFmtError, TypedPrefix},
-    bgp::Announcement,
+    bgp::{Announcement, AnnouncementVisibility},
     error::KrillIoError,
 };
 
@@ -72,10 +72,7 @@ impl RisDumpLoader {
             let asn_str = values.next().ok_or(RisDumpError::MissingColumn)?;
             let prefix_str = values.next().ok_or(RisDumpError::MissingColumn)?;
             let peers = values.next().ok_or(RisDumpError::MissingColumn)?;
-
-            if u32::from_str(peers)? <= 5 {
-                continue;
-            }
+            let peers = u32::from_str(peers)?;
 
             if asn_str.contains('{') {
                 continue; // assets not supported (not important here either)
@@ -84,7 +81,9 @@ impl RisDumpLoader {
             let asn = AsNumber::from_str(asn_str)?;
             let prefix = TypedPrefix::from_str(prefix_str)?;
 
-            let ann = Announcement::new(asn, prefix);
+            // The dumps only tell us how many RIS peers see an announcement
+            // at the time the dump was made.
+            let ann = Announcement::new(asn, prefix).with_visibility(AnnouncementVisibility::seen_now(peers));
             res.push(ann);
         }
         Ok(res)
@@ -161,4 +160,14 @@ mod tests {
 
         assert!(!announcements.is_empty())
     }
+
+    #[test]
+    fn keep_peer_count_from_dump() {
+        let dump = b"% RIS whois dump\n\n64496\t10.0.0.0/24\t3\n64497\t10.0.1.0/24\t250\n{64498}\t10.0.2.0/24\t10\n";
+        let announcements = RisDumpLoader::parse_dump(dump).unwrap();
+
+        assert_eq!(2, announcements.len());
+        assert_eq!(3, announcements[0].visibility().unwrap().peers());
+        assert_eq!(250, announcements[1].visibility().unwrap().peers());
+    }
 }
@@ -0,0 +1,379 @@
+//! Simulate the effect of ROA and resource changes for several CAs at once.
+//!
+//! Any ROA, issued by any CA, can make an announcement valid or invalid. So,
+//! changes in one CA may affect announcements seen for other CAs in this
+//! Krill instance. E.g. if a parent CA loses resources, then its children
+//! will lose them as well and their ROAs for these resources will no longer
+//! be published.
+
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+use crate::commons::{
+    api::{Handle, ParentHandle, ResourceSet, RoaDefinition, RoaDefinitionUpdates},
+    bgp::{Announcement, AnnouncementValidity},
+};
+
+//------------ BgpSimulationRequest ------------------------------------------
+
+/// Proposed changes to ROAs and/or resources for any number of CAs.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BgpSimulationRequest {
+    changes: Vec<CaSimulationChange>,
+}
+
+impl BgpSimulationRequest {
+    pub fn new(changes: Vec<CaSimulationChange>) -> Self {
+        BgpSimulationRequest { changes }
+    }
+
+    pub fn changes(&self) -> &Vec<CaSimulationChange> {
+        &self.changes
+    }
+}
+
+//------------ CaSimulationChange --------------------------------------------
+
+/// Proposed changes for a single CA. If resources are given then they
+/// replace all resources currently held by the CA.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CaSimulationChange {
+    ca: Handle,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    roas: Option<RoaDefinitionUpdates>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    resources: Option<ResourceSet>,
+}
+
+impl CaSimulationChange {
+    pub fn new(ca: Handle, roas: Option<RoaDefinitionUpdates>, resources: Option<ResourceSet>) -> Self {
+        CaSimulationChange { ca, roas, resources }
+    }
+
+    pub fn ca(&self) -> &Handle {
+        &self.ca
+    }
+
+    pub fn roas(&self) -> Option<&RoaDefinitionUpdates> {
+        self.roas.as_ref()
+    }
+
+    pub fn resources(&self) -> Option<&ResourceSet> {
+        self.resources.as_ref()
+    }
+}
+
+//------------ CaRoutes ------------------------------------------------------
+
+/// The resources held by a CA, and its configured ROAs.
+#[derive(Clone, Debug)]
+pub struct CaRoutes {
+    handle: Handle,
+    resources: ResourceSet,
+    roas: Vec<RoaDefinition>,
+}
+
+impl CaRoutes {
+    pub fn new(handle: Handle, resources: ResourceSet, roas: Vec<RoaDefinition>) -> Self {
+        CaRoutes {
+            handle,
+            resources,
+            roas,
+        }
+    }
+
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
+    }
+
+    pub fn roas(&self) -> &Vec<RoaDefinition> {
+        &self.roas
+    }
+
+    /// Returns the ROAs which would actually be published, i.e. the ROAs
+    /// for resources held by this CA.
+    pub fn published_roas(&self) -> impl Iterator<Item = &RoaDefinition> {
+        self.roas
+            .iter()
+            .filter(move |roa| self.resources.contains_roa_address(&roa.as_roa_ip_address()))
+    }
+
+    pub fn covers(&self, announcement: &Announcement) -> bool {
+        let definition = RoaDefinition::from(*announcement);
+        self.resources.contains_roa_address(&definition.as_roa_ip_address())
+    }
+}
+
+//------------ Resource propagation ------------------------------------------
+
+/// Returns the resources that each CA would hold after the proposed
+/// resource changes.
+///
+/// The `received` map contains the resources that each CA currently
+/// received from each of its parents. CAs with a proposed change get
+/// exactly the proposed resources. Other CAs keep what they received from
+/// their parents, but lose any resources that a parent in this Krill would
+/// no longer hold.
+pub fn resources_after(
+    received: &HashMap<Handle, HashMap<ParentHandle, ResourceSet>>,
+    proposed: &HashMap<Handle, ResourceSet>,
+) -> HashMap<Handle, ResourceSet> {
+    let mut res = HashMap::new();
+    for ca in received.keys() {
+        resolve_resources(ca, received, proposed, &mut res, &mut vec![]);
+    }
+    res
+}
+
+fn resolve_resources(
+    ca: &Handle,
+    received: &HashMap<Handle, HashMap<ParentHandle, ResourceSet>>,
+    proposed: &HashMap<Handle, ResourceSet>,
+    resolved: &mut HashMap<Handle, ResourceSet>,
+    visiting: &mut Vec<Handle>,
+) -> ResourceSet {
+    if let Some(resources) = resolved.get(ca) {
+        return resources.clone();
+    }
+
+    let resources = match proposed.get(ca) {
+        Some(resources) => resources.clone(),
+        None => {
+            visiting.push(ca.clone());
+
+            let mut resources = ResourceSet::default();
+            for (parent, from_parent) in received.get(ca).into_iter().flatten() {
+                // The TA is its own parent, so guard against loops.
+                let from_parent = if received.contains_key(parent) && !visiting.contains(parent) {
+                    from_parent.intersection(&resolve_resources(parent, received, proposed, resolved, visiting))
+                } else {
+                    from_parent.clone()
+                };
+                resources = resources.union(&from_parent);
+            }
+
+            visiting.pop();
+            resources
+        }
+    };
+
+    resolved.insert(ca.clone(), resources.clone());
+    resources
+}
+
+//------------ BgpSimulationReport -------------------------------------------
+
+/// The announcements which would change between valid, invalid and not
+/// found as a result of the proposed changes.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BgpSimulationReport {
+    announcement_info: bool,
+    entries: Vec<BgpSimulationEntry>,
+}
+
+impl BgpSimulationReport {
+    pub fn new(mut entries: Vec<BgpSimulationEntry>) -> Self {
+        entries.sort();
+        BgpSimulationReport {
+            announcement_info: true,
+            entries,
+        }
+    }
+
+    /// A report for when no BGP announcement information is available (yet).
+    pub fn no_announcement_info() -> Self {
+        BgpSimulationReport {
+            announcement_info: false,
+            entries: vec![],
+        }
+    }
+
+    pub fn announcement_info(&self) -> bool {
+        self.announcement_info
+    }
+
+    pub fn entries(&self) -> &Vec<BgpSimulationEntry> {
+        &self.entries
+    }
+}
+
+impl fmt::Display for BgpSimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.announcement_info {
+            writeln!(f, "No BGP announcement information available, cannot simulate impact.")
+        } else if self.entries.is_empty() {
+            writeln!(f, "No announcements would change state.")
+        } else {
+            writeln!(f, "Announcements which would change state:")?;
+            writeln!(f)?;
+            for entry in &self.entries {
+                writeln!(f, "\t{}", entry)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+//------------ BgpSimulationEntry --------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BgpSimulationEntry {
+    announcement: Announcement,
+    before: AnnouncementValidity,
+    after: AnnouncementValidity,
+    cas: Vec<Handle>,
+}
+
+impl BgpSimulationEntry {
+    pub fn new(
+        announcement: Announcement,
+        before: AnnouncementValidity,
+        after: AnnouncementValidity,
+        mut cas: Vec<Handle>,
+    ) -> Self {
+        cas.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        cas.dedup();
+        BgpSimulationEntry {
+            announcement,
+            before,
+            after,
+            cas,
+        }
+    }
+
+    pub fn announcement(&self) -> &Announcement {
+        &self.announcement
+    }
+
+    pub fn before(&self) -> AnnouncementValidity {
+        self.before
+    }
+
+    pub fn after(&self) -> AnnouncementValidity {
+        self.after
+    }
+
+    /// The CAs in this Krill which hold the resources for the announcement,
+    /// before or after the change.
+    pub fn cas(&self) -> &Vec<Handle> {
+        &self.cas
+    }
+}
+
+impl fmt::Display for BgpSimulationEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cas: Vec<&str> = self.cas.iter().map(|ca| ca.as_str()).collect();
+        write!(
+            f,
+            "{}: {} -> {} (CAs: {})",
+            self.announcement,
+            self.before,
+            self.after,
+            cas.join(", ")
+        )
+    }
+}
+
+impl Ord for BgpSimulationEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.announcement.cmp(&other.announcement)
+    }
+}
+
+impl PartialOrd for BgpSimulationEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    #[test]
+    fn children_lose_resources_of_shrunk_parent() {
+        let ta = handle("ta");
+        let parent = handle("parent");
+        let child = handle("child");
+        let grandchild = handle("grandchild");
+        let other = handle("other");
+
+        let mut received = HashMap::new();
+        received.insert(
+            ta.clone(),
+            vec![(ta.clone(), ipv4_resources("10.0.0.0/8"))].into_iter().collect(),
+        );
+        received.insert(
+            parent.clone(),
+            vec![(ta.clone(), ipv4_resources("10.0.0.0/16"))].into_iter().collect(),
+        );
+        received.insert(
+            child.clone(),
+            vec![(parent.clone(), ipv4_resources("10.0.0.0/23"))]
+                .into_iter()
+                .collect(),
+        );
+        received.insert(
+            grandchild.clone(),
+            vec![
+                (child.clone(), ipv4_resources("10.0.1.0/24")),
+                (handle("remote"), ipv4_resources("192.168.0.0/24")),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        received.insert(
+            other.clone(),
+            vec![(ta.clone(), ipv4_resources("10.1.0.0/16"))].into_iter().collect(),
+        );
+
+        let mut proposed = HashMap::new();
+        proposed.insert(parent.clone(), ipv4_resources("10.0.0.0/24"));
+
+        let after = resources_after(&received, &proposed);
+
+        assert_eq!(after.get(&ta), Some(&ipv4_resources("10.0.0.0/8")));
+        assert_eq!(after.get(&parent), Some(&ipv4_resources("10.0.0.0/24")));
+        assert_eq!(after.get(&child), Some(&ipv4_resources("10.0.0.0/24")));
+        assert_eq!(after.get(&grandchild), Some(&ipv4_resources("192.168.0.0/24")));
+        assert_eq!(after.get(&other), Some(&ipv4_resources("10.1.0.0/16")));
+    }
+
+    #[test]
+    fn parse_simulation_request() {
+        let json = r#"{ "changes": [
+            { "ca": "parent", "resources": { "asn": "", "v4": "10.0.0.0/16", "v6": "" } },
+            { "ca": "child", "roas": { "added": [ { "asn": 64496, "prefix": "10.0.0.0/24" } ], "removed": [] } }
+        ] }"#;
+
+        let request: BgpSimulationRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.changes().len(), 2);
+        assert_eq!(request.changes()[0].resources(), Some(&ipv4_resources("10.0.0.0/16")));
+        assert!(request.changes()[0].roas().is_none());
+        assert_eq!(request.changes()[1].ca(), &handle("child"));
+        assert!(request.changes()[1].roas().is_some());
+    }
+
+    #[test]
+    fn only_publish_roas_for_held_resources() {
+        let roa_held = definition("10.0.0.0/24 => 64496");
+        let roa_not_held = definition("10.0.1.0/24 => 64496");
+        let ca = CaRoutes::new(
+            handle("ca"),
+            ipv4_resources("10.0.0.0/24"),
+            vec![roa_held, roa_not_held],
+        );
+
+        let published: Vec<_> = ca.published_roas().collect();
+        assert_eq!(published, vec![&roa_held]);
+
+        assert!(ca.covers(&announcement("10.0.0.0/24 => 64497")));
+        assert!(!ca.covers(&announcement("10.0.1.0/24 => 64496")));
+    }
+}
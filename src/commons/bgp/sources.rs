@@ -0,0 +1,123 @@
+//! Support for the different sources of BGP announcement information.
+//!
+//! By default Krill downloads the RIPE RIS whois dumps, but operators may
+//! prefer to use their own view of BGP instead, e.g. because they cannot
+//! reach RIS or because they want to see what their own routers see.
+
+use std::{fmt, io::Read, path::Path};
+
+use libflate::gzip::Decoder;
+
+use crate::commons::{
+    api::AuthorizationFmtError,
+    bgp::{Announcement, BmpListener, LocalFileLoader, MrtFileLoader, RisDumpError, RisDumpLoader},
+    error::KrillIoError,
+};
+
+//------------ BgpAnnouncementSource -----------------------------------------
+
+/// The source that the BgpAnalyser uses to learn about announcements.
+pub enum BgpAnnouncementSource {
+    /// Downloads the RIPE RIS whois dumps over HTTP.
+    RisDumps(RisDumpLoader),
+
+    /// Reads a local MRT (TABLE_DUMP_V2) RIB dump file.
+    Mrt(MrtFileLoader),
+
+    /// Uses the routes learned by a BMP listener, fed by our own routers.
+    Bmp(BmpListener),
+
+    /// Reads a local CSV or JSON file with announcements.
+    LocalFile(LocalFileLoader),
+}
+
+impl BgpAnnouncementSource {
+    pub async fn load(&self) -> Result<Vec<Announcement>, BgpSourceError> {
+        match self {
+            BgpAnnouncementSource::RisDumps(loader) => Ok(loader.download_updates().await?),
+            BgpAnnouncementSource::Mrt(loader) => loader.load(),
+            BgpAnnouncementSource::Bmp(listener) => Ok(listener.announcements()),
+            BgpAnnouncementSource::LocalFile(loader) => loader.load(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            BgpAnnouncementSource::RisDumps(_) => "BGP RIS dumps",
+            BgpAnnouncementSource::Mrt(_) => "MRT RIB dump",
+            BgpAnnouncementSource::Bmp(_) => "BMP listener",
+            BgpAnnouncementSource::LocalFile(_) => "local announcements file",
+        }
+    }
+}
+
+/// Reads the file at the given path, and unzips it if it is gzipped.
+pub(super) fn read_maybe_gzipped(path: &Path) -> Result<Vec<u8>, BgpSourceError> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        KrillIoError::new(
+            format!("Could not read BGP source file '{}'", path.to_string_lossy()),
+            e,
+        )
+    })?;
+
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut gunzipped: Vec<u8> = vec![];
+        let mut decoder = Decoder::new(bytes.as_slice())
+            .map_err(|e| BgpSourceError::UnzipError(format!("Could not unzip file: {}", e)))?;
+
+        decoder
+            .read_to_end(&mut gunzipped)
+            .map_err(|e| BgpSourceError::UnzipError(format!("Could not unzip file: {}", e)))?;
+
+        Ok(gunzipped)
+    } else {
+        Ok(bytes)
+    }
+}
+
+//------------ Error --------------------------------------------------------
+
+#[derive(Debug)]
+pub enum BgpSourceError {
+    RisDump(RisDumpError),
+    IoError(KrillIoError),
+    UnzipError(String),
+    ParseError(String),
+    Listener(String),
+}
+
+impl fmt::Display for BgpSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BgpSourceError::RisDump(e) => e.fmt(f),
+            BgpSourceError::IoError(e) => write!(f, "IO error: {}", e),
+            BgpSourceError::UnzipError(s) => write!(f, "Error unzipping: {}", s),
+            BgpSourceError::ParseError(s) => write!(f, "Error parsing announcements: {}", s),
+            BgpSourceError::Listener(s) => write!(f, "BMP listener error: {}", s),
+        }
+    }
+}
+
+impl BgpSourceError {
+    pub fn parse_error(e: impl fmt::Display) -> Self {
+        BgpSourceError::ParseError(format!("{}", e))
+    }
+}
+
+impl From<RisDumpError> for BgpSourceError {
+    fn from(e: RisDumpError) -> Self {
+        BgpSourceError::RisDump(e)
+    }
+}
+
+impl From<KrillIoError> for BgpSourceError {
+    fn from(e: KrillIoError) -> Self {
+        BgpSourceError::IoError(e)
+    }
+}
+
+impl From<AuthorizationFmtError> for BgpSourceError {
+    fn from(e: AuthorizationFmtError) -> Self {
+        BgpSourceError::parse_error(e)
+    }
+}
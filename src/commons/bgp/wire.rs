@@ -0,0 +1,288 @@
+//! Helpers for parsing the BGP wire format, as used in MRT dumps and
+//! BMP messages. We only parse what we need to learn which prefixes are
+//! announced by which origin ASN.
+
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+use crate::commons::{
+    api::{AsNumber, TypedPrefix},
+    bgp::BgpSourceError,
+};
+
+pub const AFI_IPV4: u16 = 1;
+pub const AFI_IPV6: u16 = 2;
+pub const SAFI_UNICAST: u8 = 1;
+
+const ATTR_FLAG_EXTENDED_LENGTH: u8 = 0x10;
+
+const ATTR_TYPE_AS_PATH: u8 = 2;
+const ATTR_TYPE_MP_REACH_NLRI: u8 = 14;
+const ATTR_TYPE_MP_UNREACH_NLRI: u8 = 15;
+const ATTR_TYPE_AS4_PATH: u8 = 17;
+
+const AS_PATH_SEGMENT_SET: u8 = 1;
+const AS_PATH_SEGMENT_SEQUENCE: u8 = 2;
+
+//------------ WireReader ----------------------------------------------------
+
+/// Reads big-endian values from a byte slice, and returns a parse error
+/// rather than panicking if the input is truncated.
+pub struct WireReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> WireReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        WireReader { buf, pos: 0 }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], BgpSourceError> {
+        if self.remaining() < len {
+            Err(BgpSourceError::parse_error(format!(
+                "truncated input, need {} bytes, have {}",
+                len,
+                self.remaining()
+            )))
+        } else {
+            let res = &self.buf[self.pos..self.pos + len];
+            self.pos += len;
+            Ok(res)
+        }
+    }
+
+    pub fn skip(&mut self, len: usize) -> Result<(), BgpSourceError> {
+        self.take(len).map(|_| ())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, BgpSourceError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, BgpSourceError> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, BgpSourceError> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Reads a prefix encoded as a length in bits, followed by the minimal
+    /// number of octets needed to hold the prefix.
+    pub fn prefix(&mut self, afi: u16) -> Result<TypedPrefix, BgpSourceError> {
+        let len = self.u8()?;
+        let octets = self.take((len as usize + 7) / 8)?;
+
+        let max_len = if afi == AFI_IPV4 { 32 } else { 128 };
+        if len > max_len {
+            return Err(BgpSourceError::parse_error(format!("invalid prefix length: {}", len)));
+        }
+
+        let mut addr = [0u8; 16];
+        addr[..octets.len()].copy_from_slice(octets);
+
+        // Clear any bits beyond the prefix length, they are not part of the prefix.
+        let prefix_str = if afi == AFI_IPV4 {
+            let bits = u32::from_be_bytes([addr[0], addr[1], addr[2], addr[3]]);
+            let mask = u32::MAX.checked_shl(32 - len as u32).unwrap_or(0);
+            format!("{}/{}", Ipv4Addr::from(bits & mask), len)
+        } else {
+            let bits = u128::from_be_bytes(addr);
+            let mask = u128::MAX.checked_shl(128 - len as u32).unwrap_or(0);
+            format!("{}/{}", Ipv6Addr::from(bits & mask), len)
+        };
+
+        Ok(TypedPrefix::from_str(&prefix_str)?)
+    }
+}
+
+//------------ PathAttributes ------------------------------------------------
+
+/// The parts of the BGP path attributes that we care about.
+#[derive(Default)]
+pub struct PathAttributes {
+    /// The origin ASN as found in the AS_PATH. This is None if the path
+    /// was empty, or if the path ends in an AS_SET.
+    pub origin: Option<AsNumber>,
+
+    /// True if there was an AS_PATH attribute with no segments at all. This
+    /// happens for routes originated by the AS that the peer belongs to.
+    pub empty_path: bool,
+
+    /// Prefixes in the MP_REACH_NLRI attribute, if it was not abbreviated.
+    pub mp_reach: Vec<TypedPrefix>,
+
+    /// Prefixes in the MP_UNREACH_NLRI attribute.
+    pub mp_unreach: Vec<TypedPrefix>,
+}
+
+impl PathAttributes {
+    /// Parses path attributes.
+    ///
+    /// If `four_byte_asn` is false then ASNs in the AS_PATH are assumed to
+    /// be encoded in 2 bytes, and an AS4_PATH will take precedence.
+    ///
+    /// If `abbreviated_mp_reach` is true, then the MP_REACH_NLRI attribute
+    /// is assumed to hold the next hop only, as is the case in MRT
+    /// TABLE_DUMP_V2 RIB entries (RFC 6396 section 4.3.4).
+    pub fn parse(bytes: &[u8], four_byte_asn: bool, abbreviated_mp_reach: bool) -> Result<Self, BgpSourceError> {
+        let mut res = PathAttributes::default();
+        let mut as4_origin = None;
+
+        let mut reader = WireReader::new(bytes);
+        while !reader.is_empty() {
+            let flags = reader.u8()?;
+            let typ = reader.u8()?;
+            let len = if flags & ATTR_FLAG_EXTENDED_LENGTH == ATTR_FLAG_EXTENDED_LENGTH {
+                reader.u16()? as usize
+            } else {
+                reader.u8()? as usize
+            };
+            let value = reader.take(len)?;
+
+            match typ {
+                ATTR_TYPE_AS_PATH => {
+                    let (origin, empty) = Self::parse_as_path(value, four_byte_asn)?;
+                    res.origin = origin;
+                    res.empty_path = empty;
+                }
+                ATTR_TYPE_AS4_PATH if !four_byte_asn => {
+                    as4_origin = Self::parse_as_path(value, true)?.0;
+                }
+                ATTR_TYPE_MP_REACH_NLRI if !abbreviated_mp_reach => {
+                    res.mp_reach = Self::parse_mp_reach(value)?;
+                }
+                ATTR_TYPE_MP_UNREACH_NLRI => {
+                    res.mp_unreach = Self::parse_mp_unreach(value)?;
+                }
+                _ => {}
+            }
+        }
+
+        if as4_origin.is_some() {
+            res.origin = as4_origin;
+        }
+
+        Ok(res)
+    }
+
+    /// Returns the origin ASN, if the path ends in an AS_SEQUENCE, and
+    /// whether the path was empty.
+    fn parse_as_path(bytes: &[u8], four_byte_asn: bool) -> Result<(Option<AsNumber>, bool), BgpSourceError> {
+        let mut reader = WireReader::new(bytes);
+        let mut origin = None;
+        let mut empty = true;
+
+        while !reader.is_empty() {
+            let segment_type = reader.u8()?;
+            let count = reader.u8()? as usize;
+
+            let mut last = None;
+            for _ in 0..count {
+                last = Some(if four_byte_asn {
+                    reader.u32()?
+                } else {
+                    reader.u16()? as u32
+                });
+            }
+
+            // Confederation segments are local to the confederation and do
+            // not tell us anything about the origin.
+            match segment_type {
+                AS_PATH_SEGMENT_SEQUENCE => {
+                    if let Some(last) = last {
+                        origin = Some(AsNumber::new(last));
+                        empty = false;
+                    }
+                }
+                AS_PATH_SEGMENT_SET => {
+                    origin = None; // AS_SETs are not supported (not important here either)
+                    empty = false;
+                }
+                _ => {}
+            }
+        }
+
+        Ok((origin, empty))
+    }
+
+    fn parse_mp_reach(bytes: &[u8]) -> Result<Vec<TypedPrefix>, BgpSourceError> {
+        let mut reader = WireReader::new(bytes);
+        let afi = reader.u16()?;
+        let safi = reader.u8()?;
+        let next_hop_len = reader.u8()? as usize;
+        reader.skip(next_hop_len)?;
+        reader.skip(1)?; // reserved
+
+        Self::parse_nlri(&mut reader, afi, safi)
+    }
+
+    fn parse_mp_unreach(bytes: &[u8]) -> Result<Vec<TypedPrefix>, BgpSourceError> {
+        let mut reader = WireReader::new(bytes);
+        let afi = reader.u16()?;
+        let safi = reader.u8()?;
+
+        Self::parse_nlri(&mut reader, afi, safi)
+    }
+
+    fn parse_nlri(reader: &mut WireReader, afi: u16, safi: u8) -> Result<Vec<TypedPrefix>, BgpSourceError> {
+        let mut res = vec![];
+        if safi == SAFI_UNICAST && (afi == AFI_IPV4 || afi == AFI_IPV6) {
+            while !reader.is_empty() {
+                res.push(reader.prefix(afi)?);
+            }
+        }
+        Ok(res)
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_prefixes() {
+        let bytes = [24, 10, 0, 1, 31, 10, 0, 0, 3, 32, 0x20, 0x01, 0x0d, 0xb8];
+
+        let mut reader = WireReader::new(&bytes);
+        assert_eq!(reader.prefix(AFI_IPV4).unwrap().to_string(), "10.0.1.0/24");
+        assert_eq!(reader.prefix(AFI_IPV4).unwrap().to_string(), "10.0.0.2/31");
+        assert_eq!(reader.prefix(AFI_IPV6).unwrap().to_string(), "2001:db8::/32");
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn find_origin_in_as_path() {
+        // AS_PATH with a single AS_SEQUENCE of 64496 64497, using 4 byte ASNs
+        let attrs = [
+            0x40, 2, 10, 2, 2, 0, 0, 0xfb, 0xf0, 0, 0, 0xfb, 0xf1, // AS_PATH
+            0x40, 1, 1, 0, // ORIGIN (ignored)
+        ];
+        let parsed = PathAttributes::parse(&attrs, true, true).unwrap();
+        assert_eq!(parsed.origin, Some(AsNumber::new(64497)));
+
+        // AS_PATH ending in an AS_SET has no usable origin
+        let attrs = [0x40, 2, 8, 2, 1, 0xfb, 0xf0, 1, 1, 0xfb, 0xf1];
+        let parsed = PathAttributes::parse(&attrs, false, true).unwrap();
+        assert_eq!(parsed.origin, None);
+
+        // Truncated input results in an error, rather than a panic
+        let attrs = [0x40, 2, 10, 2, 2, 0, 0];
+        assert!(PathAttributes::parse(&attrs, true, true).is_err());
+    }
+}
@@ -6,7 +6,7 @@ use std::{
     {convert::TryFrom, path::Path},
 };
 
-use bcder::Captured;
+use bcder::{Captured, Oid};
 use bytes::Bytes;
 
 use rpki::{
@@ -19,7 +19,7 @@ use rpki::{
         manifest::{FileAndHash, Manifest, ManifestContent},
         roa::{Roa, RoaBuilder},
         rta,
-        sigobj::SignedObjectBuilder,
+        sigobj::{SignedObject, SignedObjectBuilder},
         x509::{Name, Serial, Time, Validity},
     },
     uri,
@@ -199,6 +199,21 @@ impl SignerProvider {
         .map_err(crypto::Error::signing)
     }
 
+    pub fn sign_object(
+        &self,
+        content_type: Oid<Bytes>,
+        content: Bytes,
+        object_builder: SignedObjectBuilder,
+        key_id: &KeyIdentifier,
+    ) -> CryptoResult<SignedObject> {
+        match self {
+            SignerProvider::OpenSsl(signer) => object_builder.finalize(content_type, content, signer.deref(), key_id),
+            #[cfg(feature = "hsm")]
+            SignerProvider::Dummy(signer) => object_builder.finalize(content_type, content, signer.deref(), key_id),
+        }
+        .map_err(crypto::Error::signing)
+    }
+
     pub fn sign_rta(&self, rta_builder: &mut rta::RtaBuilder, ee: Cert) -> CryptoResult<()> {
         let key = ee.subject_key_identifier();
         rta_builder.push_cert(ee);
@@ -331,6 +346,21 @@ impl KrillSigner {
             .sign_aspa(aspa_builder, object_builder, key_id)
     }
 
+    /// Signs a generic RPKI signed object, for object types which are not
+    /// (yet) supported by the rpki crate, such as TAK objects.
+    pub fn sign_object(
+        &self,
+        content_type: Oid<Bytes>,
+        content: Bytes,
+        object_builder: SignedObjectBuilder,
+        key_id: &KeyIdentifier,
+    ) -> CryptoResult<SignedObject> {
+        self.general_signer
+            .read()
+            .unwrap()
+            .sign_object(content_type, content, object_builder, key_id)
+    }
+
     pub fn sign_rta(&self, rta_builder: &mut rta::RtaBuilder, ee: Cert) -> CryptoResult<()> {
         self.general_signer.read().unwrap().sign_rta(rta_builder, ee)
     }
@@ -440,7 +470,7 @@ impl SignSupport {
         limit: RequestResourceLimit,
         replaces: Option<ReplacedObject>,
         signing_key: &CertifiedKey,
-        weeks: i64,
+        validity: Validity,
         signer: &KrillSigner,
     ) -> KrillResult<IssuedCert> {
         let signing_cert = signing_key.incoming_cert();
@@ -449,7 +479,6 @@ impl SignSupport {
             return Err(Error::MissingResources);
         }
 
-        let validity = Self::sign_validity_weeks(weeks);
         let request = CertRequest::Ca(csr, validity);
 
         let tbs = Self::make_tbs_cert(&resources, signing_cert, request, signer)?;
@@ -13,7 +13,8 @@ use crate::{
     commons::{
         api::{
             rrdp::PublicationDeltaError, AspaCustomer, AspaProvidersUpdateConflict, ChildHandle, ErrorResponse, Handle,
-            ParentHandle, PublisherHandle, ResourceClassName, ResourceSetError, RoaDefinition,
+            ParentHandle, PublisherHandle, RepositoryMirrorName, ResourceClassName, ResourceSet, ResourceSetError,
+            ResourceTemplateName, RoaDefinition,
         },
         eventsourcing::{AggregateStoreError, KeyValueError},
         remote::{
@@ -128,6 +129,7 @@ impl fmt::Display for RoaDeltaError {
 #[derive(Debug, Clone)]
 pub enum ApiAuthError {
     ApiInvalidCredentials(String),
+    ApiLoginThrottled(u64),
     ApiLoginError(String),
     ApiAuthPermanentError(String),
     ApiAuthTransientError(String),
@@ -144,6 +146,7 @@ impl Display for ApiAuthError {
             | ApiAuthError::ApiAuthTransientError(err)
             | ApiAuthError::ApiAuthSessionExpired(err)
             | ApiAuthError::ApiInsufficientRights(err) => write!(f, "{}", &err),
+            ApiAuthError::ApiLoginThrottled(secs) => write!(f, "try again in {} seconds", secs),
         }
     }
 }
@@ -157,6 +160,7 @@ impl From<Error> for ApiAuthError {
             Error::ApiAuthTransientError(e) => ApiAuthError::ApiAuthTransientError(e),
             Error::ApiAuthSessionExpired(e) => ApiAuthError::ApiAuthSessionExpired(e),
             Error::ApiInvalidCredentials(e) => ApiAuthError::ApiInvalidCredentials(e),
+            Error::ApiLoginThrottled(secs) => ApiAuthError::ApiLoginThrottled(secs),
             _ => ApiAuthError::ApiAuthPermanentError(e.to_string()),
         }
     }
@@ -189,15 +193,40 @@ pub enum Error {
     ApiUnknownResource,
     ApiInvalidHandle,
     ApiInvalidSeconds,
+    ApiInvalidPrefix,
+    ApiInvalidPermission(String),
     PostTooBig,
     PostCannotRead,
     ApiInvalidCredentials(String),
+    ApiMfaRequired,
+    ApiLoginThrottled(u64),
     ApiLoginError(String),
     ApiAuthPermanentError(String),
     ApiAuthTransientError(String),
     ApiAuthSessionExpired(String),
     ApiInsufficientRights(String),
 
+    //-----------------------------------------------------------------
+    // Scoped API Token Issues
+    //-----------------------------------------------------------------
+    ApiTokenUnknown(String),
+    ApiTokenDuplicate(String),
+    ApiTokenInvalid(String),
+
+    //-----------------------------------------------------------------
+    // Tenant Issues
+    //-----------------------------------------------------------------
+    TenantUnknown(String),
+    TenantDuplicate(String),
+    TenantInvalid(String),
+
+    //-----------------------------------------------------------------
+    // Local User Issues
+    //-----------------------------------------------------------------
+    UserUnknown(String),
+    UserDuplicate(String),
+    UserInvalid(String),
+
     //-----------------------------------------------------------------
     // Repository Issues
     //-----------------------------------------------------------------
@@ -232,12 +261,16 @@ pub enum Error {
     //-----------------------------------------------------------------
     CaDuplicate(Handle),
     CaUnknown(Handle),
+    CaRenameNotAllowed(Handle, String),
 
     // CA Repo Issues
     CaRepoInUse(Handle),
     CaRepoIssue(Handle, String),
     CaRepoResponseInvalidXml(Handle, String),
     CaRepoResponseWrongXml(Handle),
+    CaRepoMirrorDuplicate(Handle, RepositoryMirrorName),
+    CaRepoMirrorUnknown(Handle, RepositoryMirrorName),
+    CaRepoMirrorInvalid(Handle, RepositoryMirrorName, String),
 
     // CA Parent Issues
     CaParentDuplicateName(Handle, ParentHandle),
@@ -263,8 +296,20 @@ pub enum Error {
     CaChildDuplicate(Handle, ChildHandle),
     CaChildUnknown(Handle, ChildHandle),
     CaChildMustHaveResources(Handle, ChildHandle),
-    CaChildExtraResources(Handle, ChildHandle),
+    CaChildExtraResources(Handle, ChildHandle, ResourceSet),
     CaChildUnauthorized(Handle, ChildHandle),
+    CaChildInactive(Handle, ChildHandle, String),
+    CaChildSuspensionPolicyInvalid(String),
+    CaChildRequestDuplicate(Handle, ChildHandle),
+    CaChildRequestUnknown(Handle, ChildHandle),
+    CaChildRequestNotApproved(Handle, ChildHandle),
+    CaChildRequestsExceeded(Handle),
+    CaChildRequestsThrottled(Handle),
+    CaChildRequestInvalidXml(Handle, String),
+    CaResourceTemplateUnknown(Handle, ResourceTemplateName),
+    CaResourceTemplateInUse(Handle, ResourceTemplateName),
+    CaResourceTemplateExtraResources(Handle, ResourceTemplateName, ResourceSet),
+    ChildOnboardingDisabled,
 
     //-----------------------------------------------------------------
     // RouteAuthorizations - ROAs
@@ -294,6 +339,9 @@ pub enum Error {
     KeyUseNoIssuedCert,
     KeyUseNoMatch(KeyIdentifier),
     KeyRollNotAllowed,
+    KeyRollPolicyInvalid(String),
+    KeyRollPaused(Handle, String),
+    KeyRollActivateRefused(Handle, String),
 
     //-----------------------------------------------------------------
     // Resource Issues
@@ -308,6 +356,26 @@ pub enum Error {
     TaNotAllowed,
     TaNameReserved,
     TaAlreadyInitialized,
+    TaNotInitialized,
+    TaKeyRollInProgress,
+    TaKeyRollNotStaged,
+    TaKeyRollNotActivated,
+    TaKeyRollTooEarly(String),
+
+    //-----------------------------------------------------------------
+    // Trust Anchor proxy / signer issues
+    //-----------------------------------------------------------------
+    TaProxyNotInitialized,
+    TaProxyAlreadyInitialized,
+    TaProxyHasNoRepository,
+    TaProxyHasNoSigner,
+    TaProxyHasSigner,
+    TaProxyHasOpenRequest,
+    TaProxyNoOpenRequest,
+    TaProxyResponseInvalid(String),
+    TaSignerNotInitialized,
+    TaSignerAlreadyInitialized,
+    TaSignerRequestInvalid(String),
 
     //-----------------------------------------------------------------
     // Resource Tagged Attestation issues
@@ -346,15 +414,44 @@ impl fmt::Display for Error {
             Error::ApiUnknownResource => write!(f, "Unknown resource"),
             Error::ApiInvalidHandle => write!(f, "Invalid path argument for handle"),
             Error::ApiInvalidSeconds => write!(f, "Invalid path argument for seconds"),
+            Error::ApiInvalidPrefix => write!(f, "Invalid path argument for prefix"),
+            Error::ApiInvalidPermission(e) => write!(f, "Invalid permission: {}", e),
             Error::PostTooBig => write!(f, "POST body exceeds configured limit"),
             Error::PostCannotRead => write!(f, "POST body cannot be read"),
             Error::ApiInvalidCredentials(e) => write!(f, "Invalid credentials: {}", e),
+            Error::ApiMfaRequired => write!(f, "A TOTP or recovery code is required to login"),
+            Error::ApiLoginThrottled(secs) => write!(
+                f,
+                "Too many failed login attempts, try again in {} seconds",
+                secs
+            ),
             Error::ApiLoginError(e) => write!(f, "Login error: {}", e),
             Error::ApiAuthPermanentError(e) => write!(f, "Authentication error: {}", e),
             Error::ApiAuthTransientError(e) => write!(f, "Transient authentication error: {}", e),
             Error::ApiAuthSessionExpired(e) => write!(f, "Session expired: {}", e),
             Error::ApiInsufficientRights(e) => write!(f, "Insufficient rights: {}", e),
 
+            //-----------------------------------------------------------------
+            // Scoped API Token Issues
+            //-----------------------------------------------------------------
+            Error::ApiTokenUnknown(name) => write!(f, "Unknown API token '{}'", name),
+            Error::ApiTokenDuplicate(name) => write!(f, "Duplicate API token '{}'", name),
+            Error::ApiTokenInvalid(e) => write!(f, "Invalid API token request: {}", e),
+
+            //-----------------------------------------------------------------
+            // Tenant Issues
+            //-----------------------------------------------------------------
+            Error::TenantUnknown(name) => write!(f, "Unknown tenant '{}'", name),
+            Error::TenantDuplicate(name) => write!(f, "Duplicate tenant '{}'", name),
+            Error::TenantInvalid(e) => write!(f, "Invalid tenant request: {}", e),
+
+            //-----------------------------------------------------------------
+            // Local User Issues
+            //-----------------------------------------------------------------
+            Error::UserUnknown(id) => write!(f, "Unknown user '{}'", id),
+            Error::UserDuplicate(id) => write!(f, "Duplicate user '{}'", id),
+            Error::UserInvalid(e) => write!(f, "Invalid user request: {}", e),
+
             //-----------------------------------------------------------------
             // Repository Issues
             //-----------------------------------------------------------------
@@ -391,6 +488,7 @@ impl fmt::Display for Error {
             //-----------------------------------------------------------------
             Error::CaDuplicate(ca) => write!(f, "CA '{}' was already initialized", ca),
             Error::CaUnknown(ca) => write!(f, "CA '{}' is unknown", ca),
+            Error::CaRenameNotAllowed(ca, e) => write!(f, "CA '{}' cannot be renamed: {}", ca, e),
 
             // CA Repo Issues
             Error::CaRepoInUse(ca) => write!(f, "CA '{}' already uses this repository", ca),
@@ -398,6 +496,9 @@ impl fmt::Display for Error {
             re-install of Krill you will need to send XML to all other parties again: parent(s), children, and repository", ca,        e),
             Error::CaRepoResponseInvalidXml(ca, e) => write!(f, "CA '{}' got invalid repository response xml: {}", ca, e),
             Error::CaRepoResponseWrongXml(ca) => write!(f, "CA '{}' got parent instead of repository response", ca),
+            Error::CaRepoMirrorDuplicate(ca, name) => write!(f, "CA '{}' already has repository mirror '{}'", ca, name),
+            Error::CaRepoMirrorUnknown(ca, name) => write!(f, "CA '{}' does not have repository mirror '{}'", ca, name),
+            Error::CaRepoMirrorInvalid(ca, name, e) => write!(f, "Repository mirror '{}' cannot be used by CA '{}': {}", name, ca, e),
 
             // CA Parent Issues
             Error::CaParentDuplicateName(ca, parent) => write!(f, "CA '{}' already has a parent named '{}'", ca, parent),
@@ -433,8 +534,20 @@ impl fmt::Display for Error {
             Error::CaChildDuplicate(ca, child) => write!(f, "CA '{}' already has a child named '{}'", ca, child),
             Error::CaChildUnknown(ca, child) => write!(f, "CA '{}' does not have a child named '{}'", ca, child),
             Error::CaChildMustHaveResources(ca, child) => write!(f, "Child '{}' for CA '{}' MUST have resources specified", child, ca),
-            Error::CaChildExtraResources(ca, child) => write!(f, "Child '{}' cannot have resources not held by CA '{}'", child, ca),
+            Error::CaChildExtraResources(ca, child, not_held) => write!(f, "Child '{}' cannot have resources not held by CA '{}': {}", child, ca, not_held),
             Error::CaChildUnauthorized(ca, child) => write!(f, "CA '{}' does not know id certificate for child '{}'", ca, child),
+            Error::CaChildInactive(ca, child, e) => write!(f, "Child '{}' under CA '{}' is inactive: {}", child, ca, e),
+            Error::CaChildSuspensionPolicyInvalid(e) => write!(f, "Invalid child suspension policy: {}", e),
+            Error::CaChildRequestDuplicate(ca, child) => write!(f, "CA '{}' already has a pending request for child '{}'", ca, child),
+            Error::CaChildRequestUnknown(ca, child) => write!(f, "CA '{}' does not have a pending request for child '{}'", ca, child),
+            Error::CaChildRequestNotApproved(ca, child) => write!(f, "The request for child '{}' under CA '{}' was not yet approved", child, ca),
+            Error::CaChildRequestsExceeded(ca) => write!(f, "CA '{}' has too many pending child requests", ca),
+            Error::CaChildRequestsThrottled(ca) => write!(f, "CA '{}' received too many child requests, try again later", ca),
+            Error::CaChildRequestInvalidXml(ca, e) => write!(f, "CA '{}' got invalid child request xml: {}", ca, e),
+            Error::CaResourceTemplateUnknown(ca, name) => write!(f, "CA '{}' does not have resource template '{}'", ca, name),
+            Error::CaResourceTemplateInUse(ca, name) => write!(f, "Resource template '{}' of CA '{}' is still used by children", name, ca),
+            Error::CaResourceTemplateExtraResources(ca, name, not_held) => write!(f, "Resource template '{}' cannot have resources not held by CA '{}': {}", name, ca, not_held),
+            Error::ChildOnboardingDisabled => write!(f, "Child onboarding is not enabled on this server"),
 
             //-----------------------------------------------------------------
             // RouteAuthorizations - ROAs
@@ -464,6 +577,13 @@ impl fmt::Display for Error {
             Error::KeyUseNoIssuedCert => write!(f, "No issued cert matching pub key"),
             Error::KeyUseNoMatch(ki) => write!(f, "No key found matching key identifier: '{}'", ki),
             Error::KeyRollNotAllowed => write!(f, "Key roll in progress"),
+            Error::KeyRollPolicyInvalid(e) => write!(f, "Invalid key roll policy: {}", e),
+            Error::KeyRollPaused(ca, e) => write!(f, "Automated key roll for CA '{}' is paused: {}", ca, e),
+            Error::KeyRollActivateRefused(ca, e) => write!(
+                f,
+                "Refusing to activate new key(s) for CA '{}', use force to override: {}",
+                ca, e
+            ),
 
             //-----------------------------------------------------------------
             // Resource Issues
@@ -479,6 +599,28 @@ impl fmt::Display for Error {
             Error::TaNotAllowed => write!(f, "Functionality not supported for Trust Anchor"),
             Error::TaNameReserved => write!(f, "Name reserved for embedded Trust Anchor"),
             Error::TaAlreadyInitialized => write!(f, "TrustAnchor was already initialized"),
+            Error::TaNotInitialized => write!(f, "There is no embedded TrustAnchor"),
+            Error::TaKeyRollInProgress => write!(f, "A TrustAnchor key roll is already in progress"),
+            Error::TaKeyRollNotStaged => write!(f, "There is no staged TrustAnchor successor key to activate"),
+            Error::TaKeyRollNotActivated => write!(f, "There is no activated TrustAnchor successor key"),
+            Error::TaKeyRollTooEarly(time) => {
+                write!(f, "The TrustAnchor key roll transition period only ends at {}", time)
+            }
+
+            //-----------------------------------------------------------------
+            // Trust Anchor proxy / signer issues
+            //-----------------------------------------------------------------
+            Error::TaProxyNotInitialized => write!(f, "Trust Anchor proxy not initialized, see 'krillc ta proxy init --help'"),
+            Error::TaProxyAlreadyInitialized => write!(f, "Trust Anchor proxy was already initialized"),
+            Error::TaProxyHasNoRepository => write!(f, "Trust Anchor proxy has no repository"),
+            Error::TaProxyHasNoSigner => write!(f, "Trust Anchor proxy has no signer"),
+            Error::TaProxyHasSigner => write!(f, "Trust Anchor proxy already has a signer"),
+            Error::TaProxyHasOpenRequest => write!(f, "Trust Anchor proxy has an open request for the signer"),
+            Error::TaProxyNoOpenRequest => write!(f, "Trust Anchor proxy has no open request for the signer"),
+            Error::TaProxyResponseInvalid(e) => write!(f, "Invalid response from Trust Anchor signer: {}", e),
+            Error::TaSignerNotInitialized => write!(f, "Trust Anchor signer not initialized, see 'krillta init --help'"),
+            Error::TaSignerAlreadyInitialized => write!(f, "Trust Anchor signer was already initialized"),
+            Error::TaSignerRequestInvalid(e) => write!(f, "Invalid request from Trust Anchor proxy: {}", e),
 
             //-----------------------------------------------------------------
             // Resource Tagged Attestation issues
@@ -561,6 +703,7 @@ impl From<ApiAuthError> for Error {
             ApiAuthError::ApiAuthTransientError(e) => Error::ApiAuthTransientError(e),
             ApiAuthError::ApiAuthSessionExpired(e) => Error::ApiAuthSessionExpired(e),
             ApiAuthError::ApiInvalidCredentials(e) => Error::ApiInvalidCredentials(e),
+            ApiAuthError::ApiLoginThrottled(secs) => Error::ApiLoginThrottled(secs),
         }
     }
 }
@@ -616,15 +759,23 @@ impl Error {
             Error::PublisherUnknown(_)
             | Error::CaUnknown(_)
             | Error::CaChildUnknown(_, _)
+            | Error::CaChildRequestUnknown(_, _)
+            | Error::CaResourceTemplateUnknown(_, _)
+            | Error::CaRepoMirrorUnknown(_, _)
             | Error::CaParentUnknown(_, _)
+            | Error::ApiTokenUnknown(_)
+            | Error::TenantUnknown(_)
+            | Error::UserUnknown(_)
             | Error::ApiUnknownResource => StatusCode::NOT_FOUND,
 
             Error::ApiInvalidCredentials(_)
+            | Error::ApiMfaRequired
             | Error::ApiAuthPermanentError(_)
             | Error::ApiAuthTransientError(_)
             | Error::ApiAuthSessionExpired(_)
             | Error::ApiLoginError(_) => StatusCode::UNAUTHORIZED,
             Error::ApiInsufficientRights(_) => StatusCode::FORBIDDEN,
+            Error::ApiLoginThrottled(_) | Error::CaChildRequestsThrottled(_) => StatusCode::TOO_MANY_REQUESTS,
 
             _ => StatusCode::BAD_REQUEST,
         }
@@ -677,6 +828,9 @@ impl Error {
             Error::ApiInvalidHandle => ErrorResponse::new("api-invalid-path-handle", &self),
 
             Error::ApiInvalidSeconds => ErrorResponse::new("api-invalid-path-seconds", &self),
+            Error::ApiInvalidPrefix => ErrorResponse::new("api-invalid-path-prefix", &self),
+
+            Error::ApiInvalidPermission(e) => ErrorResponse::new("api-invalid-permission", &self).with_cause(e),
 
             Error::PostTooBig => ErrorResponse::new("api-post-body-exceeds-limit", &self),
 
@@ -684,6 +838,10 @@ impl Error {
 
             Error::ApiInvalidCredentials(e) => ErrorResponse::new("api-invalid-credentials", &self).with_cause(e),
 
+            Error::ApiMfaRequired => ErrorResponse::new("api-mfa-required", &self),
+
+            Error::ApiLoginThrottled(secs) => ErrorResponse::new("api-login-throttled", &self).with_cause(secs),
+
             Error::ApiLoginError(e) => ErrorResponse::new("api-login-error", &self).with_cause(e),
 
             Error::ApiAuthPermanentError(e) => ErrorResponse::new("api-auth-permanent-error", &self).with_cause(e),
@@ -694,6 +852,33 @@ impl Error {
 
             Error::ApiInsufficientRights(e) => ErrorResponse::new("api-insufficient-rights", &self).with_cause(e),
 
+            //-----------------------------------------------------------------
+            // Scoped API Token Issues (label: token-*)
+            //-----------------------------------------------------------------
+            Error::ApiTokenUnknown(name) => ErrorResponse::new("token-unknown", &self).with_token_name(name),
+
+            Error::ApiTokenDuplicate(name) => ErrorResponse::new("token-duplicate", &self).with_token_name(name),
+
+            Error::ApiTokenInvalid(e) => ErrorResponse::new("token-invalid", &self).with_cause(e),
+
+            //-----------------------------------------------------------------
+            // Tenant Issues (label: tenant-*)
+            //-----------------------------------------------------------------
+            Error::TenantUnknown(name) => ErrorResponse::new("tenant-unknown", &self).with_tenant_name(name),
+
+            Error::TenantDuplicate(name) => ErrorResponse::new("tenant-duplicate", &self).with_tenant_name(name),
+
+            Error::TenantInvalid(e) => ErrorResponse::new("tenant-invalid", &self).with_cause(e),
+
+            //-----------------------------------------------------------------
+            // Local User Issues (label: user-*)
+            //-----------------------------------------------------------------
+            Error::UserUnknown(id) => ErrorResponse::new("user-unknown", &self).with_user_id(id),
+
+            Error::UserDuplicate(id) => ErrorResponse::new("user-duplicate", &self).with_user_id(id),
+
+            Error::UserInvalid(e) => ErrorResponse::new("user-invalid", &self).with_cause(e),
+
             //-----------------------------------------------------------------
             // Repository Issues (label: repo-*)
             //-----------------------------------------------------------------
@@ -739,6 +924,10 @@ impl Error {
 
             Error::CaUnknown(ca) => ErrorResponse::new("ca-unknown", &self).with_ca(ca),
 
+            Error::CaRenameNotAllowed(ca, err) => ErrorResponse::new("ca-rename-not-allowed", &self)
+                .with_ca(ca)
+                .with_cause(err),
+
             Error::CaRepoInUse(ca) => ErrorResponse::new("ca-repo-same", &self).with_ca(ca),
 
             Error::CaRepoIssue(ca, err) => ErrorResponse::new("ca-repo-issue", &self).with_ca(ca).with_cause(err),
@@ -748,6 +937,16 @@ impl Error {
                 .with_cause(err),
 
             Error::CaRepoResponseWrongXml(ca) => ErrorResponse::new("ca-repo-response-wrong-xml", &self).with_ca(ca),
+            Error::CaRepoMirrorDuplicate(ca, name) => ErrorResponse::new("ca-repo-mirror-duplicate", &self)
+                .with_ca(ca)
+                .with_mirror(name),
+            Error::CaRepoMirrorUnknown(ca, name) => ErrorResponse::new("ca-repo-mirror-unknown", &self)
+                .with_ca(ca)
+                .with_mirror(name),
+            Error::CaRepoMirrorInvalid(ca, name, err) => ErrorResponse::new("ca-repo-mirror-invalid", &self)
+                .with_ca(ca)
+                .with_mirror(name)
+                .with_cause(err),
 
             Error::CaParentDuplicateName(ca, parent) => ErrorResponse::new("ca-parent-duplicate", &self)
                 .with_ca(ca)
@@ -801,12 +1000,47 @@ impl Error {
             Error::CaChildMustHaveResources(ca, child) => ErrorResponse::new("ca-child-resources-required", &self)
                 .with_ca(ca)
                 .with_child(child),
-            Error::CaChildExtraResources(ca, child) => ErrorResponse::new("ca-child-resources-extra", &self)
+            Error::CaChildExtraResources(ca, child, not_held) => ErrorResponse::new("ca-child-resources-extra", &self)
                 .with_ca(ca)
-                .with_child(child),
+                .with_child(child)
+                .with_resources(not_held),
             Error::CaChildUnauthorized(ca, child) => ErrorResponse::new("ca-child-unauthorized", &self)
                 .with_ca(ca)
                 .with_child(child),
+            Error::CaChildInactive(ca, child, e) => ErrorResponse::new("ca-child-inactive", &self)
+                .with_ca(ca)
+                .with_child(child)
+                .with_cause(e),
+            Error::CaChildSuspensionPolicyInvalid(e) => {
+                ErrorResponse::new("ca-child-suspension-policy-invalid", &self).with_cause(e)
+            }
+            Error::CaChildRequestDuplicate(ca, child) => ErrorResponse::new("ca-child-request-duplicate", &self)
+                .with_ca(ca)
+                .with_child(child),
+            Error::CaChildRequestUnknown(ca, child) => ErrorResponse::new("ca-child-request-unknown", &self)
+                .with_ca(ca)
+                .with_child(child),
+            Error::CaChildRequestNotApproved(ca, child) => ErrorResponse::new("ca-child-request-not-approved", &self)
+                .with_ca(ca)
+                .with_child(child),
+            Error::CaChildRequestsExceeded(ca) => ErrorResponse::new("ca-child-requests-exceeded", &self).with_ca(ca),
+            Error::CaChildRequestsThrottled(ca) => ErrorResponse::new("ca-child-requests-throttled", &self).with_ca(ca),
+            Error::CaChildRequestInvalidXml(ca, err) => ErrorResponse::new("ca-child-request-invalid-xml", &self)
+                .with_ca(ca)
+                .with_cause(err),
+            Error::CaResourceTemplateUnknown(ca, name) => ErrorResponse::new("ca-resource-template-unknown", &self)
+                .with_ca(ca)
+                .with_template(name),
+            Error::CaResourceTemplateInUse(ca, name) => ErrorResponse::new("ca-resource-template-in-use", &self)
+                .with_ca(ca)
+                .with_template(name),
+            Error::CaResourceTemplateExtraResources(ca, name, not_held) => {
+                ErrorResponse::new("ca-resource-template-resources-extra", &self)
+                    .with_ca(ca)
+                    .with_template(name)
+                    .with_resources(not_held)
+            }
+            Error::ChildOnboardingDisabled => ErrorResponse::new("ca-child-onboarding-disabled", &self),
 
             // RouteAuthorizations
             Error::CaAuthorizationUnknown(ca, auth) => {
@@ -857,6 +1091,11 @@ impl Error {
             Error::KeyUseNoIssuedCert => ErrorResponse::new("key-no-cert", &self),
             Error::KeyUseNoMatch(ki) => ErrorResponse::new("key-no-match", &self).with_key_identifier(ki),
             Error::KeyRollNotAllowed => ErrorResponse::new("key-roll-disallowed", &self),
+            Error::KeyRollPolicyInvalid(e) => ErrorResponse::new("key-roll-policy-invalid", &self).with_cause(e),
+            Error::KeyRollPaused(ca, e) => ErrorResponse::new("key-roll-paused", &self).with_ca(ca).with_cause(e),
+            Error::KeyRollActivateRefused(ca, e) => ErrorResponse::new("key-roll-activate-refused", &self)
+                .with_ca(ca)
+                .with_cause(e),
 
             //-----------------------------------------------------------------
             // Resource Issues (label: rc-*)
@@ -871,6 +1110,26 @@ impl Error {
             Error::TaNotAllowed => ErrorResponse::new("ta-not-allowed", &self),
             Error::TaNameReserved => ErrorResponse::new("ta-name-reserved", &self),
             Error::TaAlreadyInitialized => ErrorResponse::new("ta-initialized", &self),
+            Error::TaNotInitialized => ErrorResponse::new("ta-not-initialized", &self),
+            Error::TaKeyRollInProgress => ErrorResponse::new("ta-keyroll-in-progress", &self),
+            Error::TaKeyRollNotStaged => ErrorResponse::new("ta-keyroll-not-staged", &self),
+            Error::TaKeyRollNotActivated => ErrorResponse::new("ta-keyroll-not-activated", &self),
+            Error::TaKeyRollTooEarly(time) => ErrorResponse::new("ta-keyroll-too-early", &self).with_cause(time),
+
+            //-----------------------------------------------------------------
+            // Trust Anchor proxy / signer issues (label: ta-*)
+            //-----------------------------------------------------------------
+            Error::TaProxyNotInitialized => ErrorResponse::new("ta-proxy-not-initialized", &self),
+            Error::TaProxyAlreadyInitialized => ErrorResponse::new("ta-proxy-initialized", &self),
+            Error::TaProxyHasNoRepository => ErrorResponse::new("ta-proxy-no-repository", &self),
+            Error::TaProxyHasNoSigner => ErrorResponse::new("ta-proxy-no-signer", &self),
+            Error::TaProxyHasSigner => ErrorResponse::new("ta-proxy-has-signer", &self),
+            Error::TaProxyHasOpenRequest => ErrorResponse::new("ta-proxy-open-request", &self),
+            Error::TaProxyNoOpenRequest => ErrorResponse::new("ta-proxy-no-open-request", &self),
+            Error::TaProxyResponseInvalid(e) => ErrorResponse::new("ta-proxy-response-invalid", &self).with_cause(e),
+            Error::TaSignerNotInitialized => ErrorResponse::new("ta-signer-not-initialized", &self),
+            Error::TaSignerAlreadyInitialized => ErrorResponse::new("ta-signer-initialized", &self),
+            Error::TaSignerRequestInvalid(e) => ErrorResponse::new("ta-signer-request-invalid", &self).with_cause(e),
 
             //-----------------------------------------------------------------
             // Resource Tagged Attestation issues
@@ -999,6 +1258,42 @@ mod tests {
             Error::ApiUnknownResource,
         );
 
+        //-----------------------------------------------------------------
+        // Scoped API Token Issues
+        //-----------------------------------------------------------------
+        verify(
+            include_str!("../../test-resources/errors/token-unknown.json"),
+            Error::ApiTokenUnknown("automation".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/token-duplicate.json"),
+            Error::ApiTokenDuplicate("automation".to_string()),
+        );
+
+        //-----------------------------------------------------------------
+        // Tenant Issues
+        //-----------------------------------------------------------------
+        verify(
+            include_str!("../../test-resources/errors/tenant-unknown.json"),
+            Error::TenantUnknown("customer1".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/tenant-duplicate.json"),
+            Error::TenantDuplicate("customer1".to_string()),
+        );
+
+        //-----------------------------------------------------------------
+        // Local User Issues
+        //-----------------------------------------------------------------
+        verify(
+            include_str!("../../test-resources/errors/user-unknown.json"),
+            Error::UserUnknown("alice@example.com".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/user-duplicate.json"),
+            Error::UserDuplicate("alice@example.com".to_string()),
+        );
+
         //-----------------------------------------------------------------
         // Repository Issues
         //-----------------------------------------------------------------
@@ -1063,6 +1358,10 @@ mod tests {
             include_str!("../../test-resources/errors/ca-unknown.json"),
             Error::CaUnknown(ca.clone()),
         );
+        verify(
+            include_str!("../../test-resources/errors/ca-rename-not-allowed.json"),
+            Error::CaRenameNotAllowed(ca.clone(), "the Trust Anchor cannot be renamed".to_string()),
+        );
 
         verify(
             include_str!("../../test-resources/errors/ca-repo-same.json"),
@@ -1080,6 +1379,18 @@ mod tests {
             include_str!("../../test-resources/errors/ca-repo-response-wrong-xml.json"),
             Error::CaRepoResponseWrongXml(ca.clone()),
         );
+        verify(
+            include_str!("../../test-resources/errors/ca-repo-mirror-duplicate.json"),
+            Error::CaRepoMirrorDuplicate(ca.clone(), "backup".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-repo-mirror-unknown.json"),
+            Error::CaRepoMirrorUnknown(ca.clone(), "backup".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-repo-mirror-invalid.json"),
+            Error::CaRepoMirrorInvalid(ca.clone(), "backup".to_string(), "base uri differs".to_string()),
+        );
 
         verify(
             include_str!("../../test-resources/errors/ca-parent-duplicate.json"),
@@ -1129,11 +1440,71 @@ mod tests {
         );
         verify(
             include_str!("../../test-resources/errors/ca-child-resources-extra.json"),
-            Error::CaChildExtraResources(ca.clone(), child.clone()),
+            Error::CaChildExtraResources(
+                ca.clone(),
+                child.clone(),
+                ResourceSet::from_strs("", "10.0.0.0/8", "").unwrap(),
+            ),
         );
         verify(
             include_str!("../../test-resources/errors/ca-child-unauthorized.json"),
-            Error::CaChildUnauthorized(ca.clone(), child),
+            Error::CaChildUnauthorized(ca.clone(), child.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-inactive.json"),
+            Error::CaChildInactive(
+                ca.clone(),
+                child.clone(),
+                "last seen at 2021-01-01T00:00:00+00:00, will be suspended after 48 hours".to_string(),
+            ),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-suspension-policy-invalid.json"),
+            Error::CaChildSuspensionPolicyInvalid("the warning must come before the suspension".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-request-duplicate.json"),
+            Error::CaChildRequestDuplicate(ca.clone(), child.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-request-unknown.json"),
+            Error::CaChildRequestUnknown(ca.clone(), child.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-request-not-approved.json"),
+            Error::CaChildRequestNotApproved(ca.clone(), child),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-requests-exceeded.json"),
+            Error::CaChildRequestsExceeded(ca.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-requests-throttled.json"),
+            Error::CaChildRequestsThrottled(ca.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-request-invalid-xml.json"),
+            Error::CaChildRequestInvalidXml(ca.clone(), "expected something".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-resource-template-unknown.json"),
+            Error::CaResourceTemplateUnknown(ca.clone(), "customers".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-resource-template-in-use.json"),
+            Error::CaResourceTemplateInUse(ca.clone(), "customers".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-resource-template-resources-extra.json"),
+            Error::CaResourceTemplateExtraResources(
+                ca.clone(),
+                "customers".to_string(),
+                ResourceSet::from_strs("", "10.0.0.0/8", "").unwrap(),
+            ),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-onboarding-disabled.json"),
+            Error::ChildOnboardingDisabled,
         );
 
         verify(
@@ -1179,6 +1550,24 @@ mod tests {
             include_str!("../../test-resources/errors/key-no-match.json"),
             Error::KeyUseNoMatch(ki),
         );
+        verify(
+            include_str!("../../test-resources/errors/key-roll-policy-invalid.json"),
+            Error::KeyRollPolicyInvalid("the maximum key age must be at least one day".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/key-roll-paused.json"),
+            Error::KeyRollPaused(
+                Handle::from_str("ca").unwrap(),
+                "parent 'parent' is unreachable".to_string(),
+            ),
+        );
+        verify(
+            include_str!("../../test-resources/errors/key-roll-activate-refused.json"),
+            Error::KeyRollActivateRefused(
+                Handle::from_str("ca").unwrap(),
+                "resource class '0': waiting for publication of the staged objects".to_string(),
+            ),
+        );
 
         verify(
             include_str!("../../test-resources/errors/rc-unknown.json"),
@@ -1205,6 +1594,71 @@ mod tests {
             include_str!("../../test-resources/errors/ta-initialized.json"),
             Error::TaAlreadyInitialized,
         );
+        verify(
+            include_str!("../../test-resources/errors/ta-not-initialized.json"),
+            Error::TaNotInitialized,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-keyroll-in-progress.json"),
+            Error::TaKeyRollInProgress,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-keyroll-not-staged.json"),
+            Error::TaKeyRollNotStaged,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-keyroll-not-activated.json"),
+            Error::TaKeyRollNotActivated,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-keyroll-too-early.json"),
+            Error::TaKeyRollTooEarly("2022-01-31T00:00:00+00:00".to_string()),
+        );
+
+        verify(
+            include_str!("../../test-resources/errors/ta-proxy-not-initialized.json"),
+            Error::TaProxyNotInitialized,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-proxy-initialized.json"),
+            Error::TaProxyAlreadyInitialized,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-proxy-no-repository.json"),
+            Error::TaProxyHasNoRepository,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-proxy-no-signer.json"),
+            Error::TaProxyHasNoSigner,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-proxy-has-signer.json"),
+            Error::TaProxyHasSigner,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-proxy-open-request.json"),
+            Error::TaProxyHasOpenRequest,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-proxy-no-open-request.json"),
+            Error::TaProxyNoOpenRequest,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-proxy-response-invalid.json"),
+            Error::TaProxyResponseInvalid("invalid signature".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-signer-not-initialized.json"),
+            Error::TaSignerNotInitialized,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-signer-initialized.json"),
+            Error::TaSignerAlreadyInitialized,
+        );
+        verify(
+            include_str!("../../test-resources/errors/ta-signer-request-invalid.json"),
+            Error::TaSignerRequestInvalid("invalid signature".to_string()),
+        );
 
         verify(
             include_str!("../../test-resources/errors/general-error.json"),
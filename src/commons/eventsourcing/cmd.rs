@@ -188,6 +188,15 @@ impl<S: WithStorableDetails> StoredCommand<S> {
     pub fn effect(&self) -> &StoredEffect {
         &self.effect
     }
+
+    /// Returns this command for the aggregate with the given handle. This is
+    /// used when an aggregate is renamed.
+    pub fn with_handle(self, handle: &Handle) -> Self {
+        StoredCommand {
+            handle: handle.clone(),
+            ..self
+        }
+    }
 }
 impl<S: WithStorableDetails> From<StoredCommand<S>> for CommandHistoryRecord {
     fn from(command: StoredCommand<S>) -> Self {
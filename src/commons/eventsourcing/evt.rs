@@ -16,6 +16,10 @@ pub trait Event: fmt::Display + Eq + PartialEq + Send + Sync + Storable + 'stati
     /// is currently at version x, will get version x + 1, when the event for
     /// version x is applied.
     fn version(&self) -> u64;
+
+    /// Returns this event for the aggregate with the given handle. This is
+    /// used when an aggregate is renamed.
+    fn with_handle(self, handle: &Handle) -> Self;
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -57,6 +61,14 @@ impl<E: fmt::Display + Eq + PartialEq + Storable + Send + Sync + 'static> Event
     fn version(&self) -> u64 {
         self.version
     }
+
+    fn with_handle(self, handle: &Handle) -> Self {
+        StoredEvent {
+            id: handle.clone(),
+            version: self.version,
+            details: self.details,
+        }
+    }
 }
 
 impl<E: fmt::Display + Eq + PartialEq + Storable + Send + Sync + 'static> fmt::Display for StoredEvent<E> {
@@ -350,4 +350,64 @@ mod tests {
 
         let _ = fs::remove_dir_all(d);
     }
+
+    #[test]
+    fn copy_aggregate() {
+        let d = test::tmp_dir();
+
+        let manager = AggregateStore::<Person>::disk(&d, "person").unwrap();
+
+        let id_alice = Handle::from_str("alice").unwrap();
+        let id_bob = Handle::from_str("bob").unwrap();
+        let id_carol = Handle::from_str("carol").unwrap();
+
+        manager.add(InitPersonEvent::init(&id_alice, "alice smith")).unwrap();
+        manager.add(InitPersonEvent::init(&id_bob, "bob jones")).unwrap();
+
+        manager.command(PersonCommand::go_around_sun(&id_alice, None)).unwrap();
+        manager
+            .command(PersonCommand::change_name(&id_alice, None, "alice smith-doe"))
+            .unwrap();
+
+        // Cannot copy to a handle that is in use
+        assert!(manager.copy_aggregate(&id_alice, &id_bob).is_err());
+
+        // A copy that fails half-way leaves nothing behind under the new handle
+        let dave_dir = d.join("person").join("dave");
+        let delta = d.join("person").join("alice").join("delta-2.json");
+        let delta_bak = d.join("person").join("alice").join("delta-2.json.bak");
+        fs::rename(&delta, &delta_bak).unwrap();
+        assert!(manager
+            .copy_aggregate(&id_alice, &Handle::from_str("dave").unwrap())
+            .is_err());
+        assert!(!dave_dir.exists());
+        fs::rename(&delta_bak, &delta).unwrap();
+
+        let carol = manager.copy_aggregate(&id_alice, &id_carol).unwrap();
+        assert_eq!(&id_carol, carol.id());
+        assert_eq!("alice smith-doe", carol.name());
+        assert_eq!(1, carol.age());
+
+        // The original is kept until it is dropped explicitly
+        assert!(manager.has(&id_alice).unwrap());
+        assert!(manager.has(&id_carol).unwrap());
+
+        manager.drop_aggregate(&id_alice).unwrap();
+        assert!(!manager.has(&id_alice).unwrap());
+
+        // The copied aggregate can be read from disk, and takes new commands
+        let manager = AggregateStore::<Person>::disk(&d, "person").unwrap();
+        manager.warm().unwrap();
+
+        let carol = manager.command(PersonCommand::go_around_sun(&id_carol, None)).unwrap();
+        assert_eq!(&id_carol, carol.id());
+        assert_eq!(2, carol.age());
+
+        let history = manager
+            .command_history(&id_carol, CommandHistoryCriteria::default())
+            .unwrap();
+        assert_eq!(history.total(), 3);
+
+        let _ = fs::remove_dir_all(d);
+    }
 }
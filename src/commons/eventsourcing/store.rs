@@ -534,6 +534,73 @@ where
         let _lock = self.outer_lock.read().unwrap();
         self.aggregates()
     }
+
+    /// Copies an aggregate to a new handle. All its events and commands are
+    /// copied, and the aggregate is rebuilt from them so that its own state
+    /// reflects the new handle as well. The data under the old handle is
+    /// left alone, so that callers can move any other data that belongs to
+    /// it first, and only drop the old aggregate when everything succeeded.
+    ///
+    /// Returns an error if the new handle is already in use. If copying fails
+    /// half-way, then any data already stored under the new handle is removed
+    /// again, so that the copy can be retried.
+    pub fn copy_aggregate(&self, from: &Handle, to: &Handle) -> StoreResult<Arc<A>> {
+        let _lock = self.outer_lock.write().unwrap();
+
+        if self.kv.has_scope(to.to_string())? {
+            return Err(AggregateStoreError::DuplicateAggregate(to.clone()));
+        }
+
+        match self.copy_aggregate_data(from, to) {
+            Ok(aggregate) => {
+                let arc = Arc::new(aggregate);
+                self.cache_update(to, arc.clone());
+                Ok(arc)
+            }
+            Err(e) => {
+                if let Err(drop_err) = self.kv.drop_scope(to.as_str()) {
+                    warn!(
+                        "Could not clean up partial copy of '{}' to '{}': {}",
+                        from, to, drop_err
+                    );
+                }
+                Err(e)
+            }
+        }
+    }
+
+    fn copy_aggregate_data(&self, from: &Handle, to: &Handle) -> StoreResult<A> {
+        let mut info = self.get_info(from)?;
+
+        let init = self
+            .kv
+            .get::<A::InitEvent>(&Self::key_for_event(from, 0))?
+            .ok_or_else(|| AggregateStoreError::UnknownAggregate(from.clone()))?
+            .with_handle(to);
+        self.store_event(&init)?;
+
+        let mut aggregate = A::init(init).map_err(|_| AggregateStoreError::InitError(to.clone()))?;
+
+        for version in 1..info.last_event + 1 {
+            let event = self
+                .get_event::<A::Event>(from, version)?
+                .ok_or_else(|| AggregateStoreError::ReplayError(from.clone(), info.last_event, version))?
+                .with_handle(to);
+            self.store_event(&event)?;
+            aggregate.apply(event);
+        }
+
+        for command_key in self.command_keys_ascending(from, &CommandHistoryCriteria::default())? {
+            let command = self.get_command::<A::StorableCommandDetails>(from, &command_key)?;
+            self.store_command(command.with_handle(to))?;
+        }
+
+        info.snapshot_version = aggregate.version();
+        self.store_snapshot(to, &aggregate)?;
+        self.save_info(to, &info)?;
+
+        Ok(aggregate)
+    }
 }
 
 /// # Manage Commands
@@ -1002,6 +1069,7 @@ pub enum AggregateStoreError {
     KeyStoreError(KeyValueError),
     NotInitialized,
     UnknownAggregate(Handle),
+    DuplicateAggregate(Handle),
     InitError(Handle),
     ReplayError(Handle, u64, u64),
     InfoMissing(Handle),
@@ -1025,6 +1093,7 @@ impl fmt::Display for AggregateStoreError {
             AggregateStoreError::KeyStoreError(e) => write!(f, "KeyStore Error: {}", e),
             AggregateStoreError::NotInitialized => write!(f, "This aggregate store is not initialized"),
             AggregateStoreError::UnknownAggregate(handle) => write!(f, "unknown entity: {}", handle),
+            AggregateStoreError::DuplicateAggregate(handle) => write!(f, "entity already exists: {}", handle),
             AggregateStoreError::InitError(handle) => {
                 write!(f, "Init event exists for '{}', but cannot be applied", handle)
             }
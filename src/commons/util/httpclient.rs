@@ -179,6 +179,21 @@ pub async fn post_empty(uri: &str, token: Option<&Token>) -> Result<(), Error> {
     }
 }
 
+/// Performs a POST with no data to the given URI and expects a json response
+/// that can be deserialized into an owned value of the expected type.
+pub async fn post_empty_with_response<T: DeserializeOwned>(uri: &str, token: Option<&Token>) -> Result<T, Error> {
+    if env::var(KRILL_CLI_API_ENV).is_ok() {
+        report_post_and_exit(uri, None, token, "<empty>");
+    }
+
+    let headers = headers(Some(JSON_CONTENT), token)?;
+    let res = client(uri)?.post(uri).headers(headers).send().await?;
+    match process_opt_json_response(res).await? {
+        None => Err(Error::EmptyResponse),
+        Some(res) => Ok(res),
+    }
+}
+
 /// Posts binary data, and expects a binary response. Includes the full krill version
 /// as the user agent. Intended for sending RFC 6492 (provisioning) and 8181 (publication)
 /// to the trusted parent or publication server.
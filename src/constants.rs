@@ -7,6 +7,7 @@ pub const KRILL_VERSION_PATCH: &str = env!("CARGO_PKG_VERSION_PATCH");
 pub const KRILL_SERVER_APP: &str = "Krill";
 pub const KRILL_CLIENT_APP: &str = "Krill Client";
 pub const KRILL_PUBC_CLIENT_APP: &str = "Krill Publication Server Client";
+pub const KRILL_TA_SIGNER_APP: &str = "Krill Trust Anchor Signer";
 
 #[cfg(not(feature = "multi-user"))]
 pub const KRILL_DEFAULT_CONFIG_FILE: &str = "./defaults/krill.conf";
@@ -53,6 +54,12 @@ pub const REPOSITORY_RRDP_ARCHIVE_DIR: &str = "archive";
 pub const REPOSITORY_RSYNC_DIR: &str = "rsync";
 
 pub const STATUS_DIR: &str = "status";
+pub const TA_PROXY_DIR: &str = "ta_proxy";
+pub const TA_SIGNER_DIR: &str = "ta_signer";
+pub const API_TOKENS_DIR: &str = "api_tokens";
+pub const TENANTS_DIR: &str = "tenants";
+pub const USERS_DIR: &str = "users";
+pub const MFA_DIR: &str = "mfa";
 
 pub const KRILL_CLI_SERVER_ARG: &str = "server";
 pub const KRILL_CLI_SERVER_ENV: &str = "KRILL_CLI_SERVER";
@@ -71,12 +78,19 @@ pub const REQUEUE_DELAY_SECONDS: i64 = 300;
 pub const CA_REFRESH_SECONDS_MIN: u32 = 600;
 pub const CA_REFRESH_SECONDS_MAX: u32 = 3600;
 pub const CA_SUSPEND_MIN_HOURS: i64 = 2;
+pub const KEYROLL_STAGING_HOURS_DFLT: i64 = 24; // RFC 6489 staging period for manual key rolls
+pub const TA_SIGNER_MFT_NEXT_UPDATE_WEEKS_DFLT: i64 = 12;
+pub const TA_SIGNER_CHILD_CERT_VALID_WEEKS_DFLT: i64 = 52;
+pub const CHILD_REQUESTS_MAX: usize = 100; // Limit for pending child onboarding requests per CA
+pub const CHILD_REQUESTS_HOURLY_MAX: usize = 10; // Limit for child onboarding requests submitted per CA per hour
+pub const CHILD_REQUEST_EXPIRY_HOURS: i64 = 7 * 24; // Unapproved child onboarding requests expire after a week
 
 pub const KRILL_HTTPS_ROOT_CERTS_ENV: &str = "KRILL_HTTPS_ROOT_CERTS";
 
 pub const ID_CERTIFICATE_VALIDITY_YEARS: i32 = 15;
 
 pub const BGP_RIS_REFRESH_MINUTES: i64 = 60;
+pub const BGP_RIS_MIN_PEERS: u32 = 6; // RIS dumps include announcements seen by very few peers, these are mostly noise.
 
 pub const HTTP_CLIENT_TIMEOUT_SECS: u64 = 120;
 pub const HTTP_USER_AGENT_TRUNCATE: usize = 256; // Will truncate received user-agent values at this size.
@@ -88,9 +102,12 @@ pub const ACTOR_DEF_KRILL: ActorDef = ActorDef::system("krill", "admin");
 pub const ACTOR_DEF_ANON: ActorDef = ActorDef::anonymous();
 pub const ACTOR_DEF_ADMIN_TOKEN: ActorDef = ActorDef::system("admin-token", "admin");
 pub const ACTOR_DEF_TESTBED: ActorDef = ActorDef::system("testbed", "testbed");
+pub const ACTOR_DEF_KRILLTA: ActorDef = ActorDef::system("krillta", "admin");
 
 pub const SCHEDULER_INTERVAL_SECONDS_REPUBLISH: u32 = 600;
 pub const SCHEDULER_INTERVAL_SECONDS_ROA_RENEW: u32 = 3600;
+pub const SCHEDULER_INTERVAL_SECONDS_KEYROLL: u32 = 600;
+pub const SCHEDULER_INTERVAL_SECONDS_CHILD_SHRINK: u32 = 600;
 
 #[cfg(test)]
 pub const ACTOR_DEF_TEST: ActorDef = ActorDef::system("test", "admin");
@@ -0,0 +1,494 @@
+//! Audit log of the authorization checks made for API requests.
+//!
+//! Every permission check made for the actor of an API request is recorded
+//! in the [AuditTrail] of that actor. When the response to the request is
+//! known, these checks are written to the [AuditLog], together with the
+//! source address, method and path of the request and the HTTP status of the
+//! response.
+//!
+//! The audit log can be written to an append-only file in which each record
+//! includes the hash of the previous record, so that changes to, or removal
+//! of, records can be detected using [verify_audit_log]. Alternatively, the
+//! records can be written as plain JSON lines, or to syslog.
+
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    commons::{
+        actor::Actor,
+        api::{AuditDecision, AuditLogVerification, AuditRecord, Timestamp},
+        error::{Error, KrillIoError},
+        KrillResult,
+    },
+    daemon::{
+        config::{AuditLogType, Config},
+        http::{ClientAddr, RequestPath},
+    },
+};
+
+/// The previous hash used for the first record in a hash-chained log.
+pub const AUDIT_GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// The amount of data read from the end of an existing log file to find the
+// last record, which should be way more than the size of any one record.
+const AUDIT_TAIL_BYTES: u64 = 64 * 1024;
+
+//------------ AuditCheck ----------------------------------------------------
+
+/// The result of a single permission check.
+#[derive(Clone, Debug)]
+pub struct AuditCheck {
+    permission: String,
+    resource: String,
+    decision: AuditDecision,
+    reason: Option<String>,
+}
+
+impl AuditCheck {
+    pub fn new(permission: &impl fmt::Display, resource: &impl fmt::Display, result: &KrillResult<bool>) -> Self {
+        let (decision, reason) = match result {
+            Ok(true) => (AuditDecision::Granted, None),
+            Ok(false) => (AuditDecision::Denied, None),
+            Err(e) => (AuditDecision::Error, Some(e.to_string())),
+        };
+
+        AuditCheck {
+            permission: permission.to_string(),
+            resource: resource.to_string(),
+            decision,
+            reason,
+        }
+    }
+
+    /// A check that was denied before the policy was consulted, e.g. because
+    /// of too many failed login attempts.
+    pub fn denied(permission: &impl fmt::Display, resource: &impl fmt::Display, reason: String) -> Self {
+        AuditCheck {
+            permission: permission.to_string(),
+            resource: resource.to_string(),
+            decision: AuditDecision::Denied,
+            reason: Some(reason),
+        }
+    }
+}
+
+//------------ AuditTrail ----------------------------------------------------
+
+/// Collects the permission checks made for the actor of a single request.
+/// Clones share the same checks.
+#[derive(Clone, Debug, Default)]
+pub struct AuditTrail(Arc<Mutex<Vec<AuditCheck>>>);
+
+impl AuditTrail {
+    pub fn record(&self, check: AuditCheck) {
+        self.0.lock().unwrap().push(check);
+    }
+
+    pub fn take(&self) -> Vec<AuditCheck> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+//------------ AuditContext --------------------------------------------------
+
+/// The details of a request that are recorded with each check.
+pub struct AuditContext {
+    source: Option<String>,
+    method: String,
+    path: String,
+}
+
+impl AuditContext {
+    pub fn from_request(request: &hyper::Request<hyper::Body>) -> Self {
+        AuditContext {
+            source: ClientAddr::from_request(request).map(|addr| addr.to_string()),
+            method: request.method().to_string(),
+            path: RequestPath::from_request(request).full().to_string(),
+        }
+    }
+}
+
+//------------ AuditLog ------------------------------------------------------
+
+enum AuditTarget {
+    ChainedFile(File),
+    JsonFile(File),
+    Syslog(syslog::Logger<syslog::LoggerBackend, String, syslog::Formatter3164>),
+}
+
+struct AuditLogState {
+    target: AuditTarget,
+    seq: u64,
+    last_hash: String,
+}
+
+/// Writes audit records to the configured target.
+pub struct AuditLog {
+    state: Mutex<AuditLogState>,
+}
+
+impl AuditLog {
+    /// Creates the audit log for the configuration, if any. Records appended
+    /// to an existing file continue its sequence and hash chain.
+    pub fn create(config: &Config) -> KrillResult<Option<Self>> {
+        let mut seq = 0;
+        let mut last_hash = AUDIT_GENESIS_HASH.to_string();
+
+        let target = match config.audit_log_type {
+            AuditLogType::None => return Ok(None),
+            AuditLogType::File | AuditLogType::Json => {
+                let path = config.audit_log_file();
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .read(true)
+                    .append(true)
+                    .open(&path)
+                    .map_err(|e| KrillIoError::new(format!("Could not open audit log file '{}'", path.display()), e))?;
+
+                if let Some(last) = Self::read_last_record(&mut file, &path)? {
+                    seq = last.seq;
+                    if let Some(hash) = last.hash {
+                        last_hash = hash;
+                    }
+                }
+
+                info!("Writing audit log to '{}'", path.display());
+                if config.audit_log_type == AuditLogType::File {
+                    AuditTarget::ChainedFile(file)
+                } else {
+                    AuditTarget::JsonFile(file)
+                }
+            }
+            AuditLogType::Syslog => {
+                let facility = config
+                    .syslog_facility()
+                    .map_err(|e| Error::ConfigError(e.to_string()))?;
+                let logger = Config::syslog_connect(facility)
+                    .map_err(|e| Error::custom(format!("Cannot connect to syslog for audit log: {}", e)))?;
+                AuditTarget::Syslog(logger)
+            }
+        };
+
+        Ok(Some(AuditLog {
+            state: Mutex::new(AuditLogState { target, seq, last_hash }),
+        }))
+    }
+
+    fn read_last_record(file: &mut File, path: &Path) -> KrillResult<Option<AuditRecord>> {
+        let err = |e| KrillIoError::new(format!("Could not read audit log file '{}'", path.display()), e);
+
+        let len = file.seek(SeekFrom::End(0)).map_err(err)?;
+        let start = len.saturating_sub(AUDIT_TAIL_BYTES);
+        file.seek(SeekFrom::Start(start)).map_err(err)?;
+
+        let mut tail = vec![];
+        file.read_to_end(&mut tail).map_err(err)?;
+
+        // Records are only complete when their line is terminated. If Krill
+        // stopped while writing a record, e.g. because it crashed, then the
+        // last line is incomplete. It never became part of the chain, so we
+        // remove it rather than refuse to start.
+        if !tail.is_empty() && !tail.ends_with(b"\n") {
+            let complete_len = match tail.iter().rposition(|b| *b == b'\n') {
+                Some(pos) => pos + 1,
+                None if start == 0 => 0,
+                None => {
+                    return Err(Error::custom(format!(
+                        "Cannot find the last record of audit log file '{}'",
+                        path.display()
+                    )))
+                }
+            };
+
+            warn!(
+                "Removing incomplete last record from audit log file '{}': {}",
+                path.display(),
+                String::from_utf8_lossy(&tail[complete_len..])
+            );
+            file.set_len(start + complete_len as u64).map_err(err)?;
+            tail.truncate(complete_len);
+        }
+
+        let tail = String::from_utf8_lossy(&tail);
+
+        match tail.lines().rev().find(|line| !line.trim().is_empty()) {
+            None => Ok(None),
+            Some(line) => serde_json::from_str(line).map(Some).map_err(|e| {
+                Error::custom(format!(
+                    "Cannot parse last record of audit log file '{}': {}",
+                    path.display(),
+                    e
+                ))
+            }),
+        }
+    }
+
+    /// Writes the checks made for the actor of a request, if any.
+    pub fn record(&self, context: &AuditContext, actor: &Actor, outcome: u16) {
+        let checks = match actor.audit_trail() {
+            Some(trail) => trail.take(),
+            None => return,
+        };
+
+        if checks.is_empty() {
+            return;
+        }
+
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        for check in checks {
+            state.seq += 1;
+            let mut record = AuditRecord {
+                seq: state.seq,
+                time: Timestamp::now().to_rfc3339(),
+                actor: actor.name().to_string(),
+                source: context.source.clone(),
+                method: context.method.clone(),
+                path: context.path.clone(),
+                permission: check.permission,
+                resource: check.resource,
+                decision: check.decision,
+                reason: check.reason,
+                outcome,
+                prev_hash: None,
+                hash: None,
+            };
+
+            let res = match &mut state.target {
+                AuditTarget::ChainedFile(file) => {
+                    record.prev_hash = Some(state.last_hash.clone());
+                    let hash = record.compute_hash();
+                    record.hash = Some(hash.clone());
+                    let res = Self::write_line(file, &record);
+                    if res.is_ok() {
+                        state.last_hash = hash;
+                    }
+                    res
+                }
+                AuditTarget::JsonFile(file) => Self::write_line(file, &record),
+                AuditTarget::Syslog(logger) => logger
+                    .info(serde_json::to_string(&record).unwrap())
+                    .map_err(|e| e.to_string()),
+            };
+
+            if let Err(e) = res {
+                error!("Could not write audit log record {}: {}", record.seq, e);
+            }
+        }
+    }
+
+    fn write_line(file: &mut File, record: &AuditRecord) -> Result<(), String> {
+        let mut line = serde_json::to_string(record).unwrap();
+        line.push('\n');
+        file.write_all(line.as_bytes())
+            .and_then(|_| file.flush())
+            .map_err(|e| e.to_string())
+    }
+}
+
+//------------ verify_audit_log ----------------------------------------------
+
+/// Verifies the hash chain of an audit log file written with audit log type
+/// "file". Note that removal of records at the end of the file cannot be
+/// detected this way, so the hash of the last record should be kept
+/// elsewhere as well if that is a concern.
+pub fn verify_audit_log(path: &Path) -> KrillResult<AuditLogVerification> {
+    let file = File::open(path)
+        .map_err(|e| KrillIoError::new(format!("Could not open audit log file '{}'", path.display()), e))?;
+
+    let mut records = 0;
+    let mut last_hash: Option<String> = None;
+
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let line_nr = idx + 1;
+        let line =
+            line.map_err(|e| KrillIoError::new(format!("Could not read audit log file '{}'", path.display()), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let broken = |msg: &str| Error::custom(format!("Audit log chain broken at line {}: {}", line_nr, msg));
+
+        let record: AuditRecord = serde_json::from_str(&line).map_err(|e| broken(&e.to_string()))?;
+
+        let expected_prev = last_hash.as_deref().unwrap_or(AUDIT_GENESIS_HASH);
+        match &record.prev_hash {
+            Some(prev) if prev == expected_prev => {}
+            Some(_) => return Err(broken("previous hash does not match the previous record")),
+            None => return Err(broken("record has no previous hash")),
+        }
+
+        match &record.hash {
+            Some(hash) if hash == &record.compute_hash() => {}
+            Some(_) => return Err(broken("record content does not match its hash")),
+            None => return Err(broken("record has no hash")),
+        }
+
+        records += 1;
+        last_hash = record.hash;
+    }
+
+    Ok(AuditLogVerification::new(records, last_hash))
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use crate::{commons::actor::ActorDef, daemon::auth::common::permissions::Permission, test};
+
+    fn context() -> AuditContext {
+        AuditContext {
+            source: Some("192.0.2.1".to_string()),
+            method: "GET".to_string(),
+            path: "/api/v1/cas/ca1".to_string(),
+        }
+    }
+
+    fn actor_with_checks(checks: &[(Permission, KrillResult<bool>)]) -> Actor {
+        let actor = Actor::test_from_def(ActorDef::user("joe".to_string(), HashMap::new(), None))
+            .with_audit_trail(AuditTrail::default());
+        let trail = actor.audit_trail().unwrap();
+        for (permission, result) in checks {
+            trail.record(AuditCheck::new(permission, &"ca1", result));
+        }
+        actor
+    }
+
+    fn chained_log(path: &Path) -> AuditLog {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        let last = AuditLog::read_last_record(&mut file, path).unwrap();
+        AuditLog {
+            state: Mutex::new(AuditLogState {
+                target: AuditTarget::ChainedFile(file),
+                seq: last.as_ref().map(|r| r.seq).unwrap_or(0),
+                last_hash: last
+                    .and_then(|r| r.hash)
+                    .unwrap_or_else(|| AUDIT_GENESIS_HASH.to_string()),
+            }),
+        }
+    }
+
+    #[test]
+    fn write_and_verify_chain() {
+        test::test_under_tmp(|d| {
+            let path = d.join("audit.log");
+
+            let log = chained_log(&path);
+            let actor = actor_with_checks(&[(Permission::CA_READ, Ok(true)), (Permission::ROUTES_UPDATE, Ok(false))]);
+            log.record(&context(), &actor, 403);
+
+            // the checks are only written once
+            log.record(&context(), &actor, 403);
+
+            // the chain continues after a restart
+            let log = chained_log(&path);
+            let actor = actor_with_checks(&[(
+                Permission::CA_READ,
+                Err(Error::ApiInvalidCredentials("expired".to_string())),
+            )]);
+            log.record(&context(), &actor, 401);
+
+            let verification = verify_audit_log(&path).unwrap();
+            assert_eq!(verification.records(), 3);
+
+            let content = std::fs::read_to_string(&path).unwrap();
+            let records: Vec<AuditRecord> = content.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+            assert_eq!(records[1].decision, AuditDecision::Denied);
+            assert_eq!(records[1].outcome, 403);
+            assert_eq!(records[2].seq, 3);
+            assert_eq!(records[2].decision, AuditDecision::Error);
+            assert_eq!(records[2].source.as_deref(), Some("192.0.2.1"));
+        })
+    }
+
+    #[test]
+    fn detect_tampering() {
+        test::test_under_tmp(|d| {
+            let path = d.join("audit.log");
+
+            let log = chained_log(&path);
+            let actor = actor_with_checks(&[
+                (Permission::CA_READ, Ok(true)),
+                (Permission::ROUTES_UPDATE, Ok(false)),
+                (Permission::CA_READ, Ok(true)),
+            ]);
+            log.record(&context(), &actor, 403);
+
+            let content = std::fs::read_to_string(&path).unwrap();
+            let lines: Vec<&str> = content.lines().collect();
+
+            // changed record
+            let changed = content.replacen("\"denied\"", "\"granted\"", 1);
+            std::fs::write(&path, changed).unwrap();
+            assert!(verify_audit_log(&path).is_err());
+
+            // removed record
+            std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+            assert!(verify_audit_log(&path).is_err());
+
+            // reordered records
+            std::fs::write(&path, format!("{}\n{}\n{}\n", lines[1], lines[0], lines[2])).unwrap();
+            assert!(verify_audit_log(&path).is_err());
+        })
+    }
+
+    #[test]
+    fn remove_incomplete_last_record() {
+        test::test_under_tmp(|d| {
+            let path = d.join("audit.log");
+
+            let log = chained_log(&path);
+            let actor = actor_with_checks(&[(Permission::CA_READ, Ok(true)), (Permission::ROUTES_UPDATE, Ok(false))]);
+            log.record(&context(), &actor, 403);
+
+            // simulate a crash while writing the third record
+            let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+            file.write_all(b"{\"seq\":3,\"time\":\"2021-").unwrap();
+            drop(file);
+            assert!(verify_audit_log(&path).is_err());
+
+            // the chain continues after the last complete record
+            let log = chained_log(&path);
+            let actor = actor_with_checks(&[(Permission::CA_READ, Ok(true))]);
+            log.record(&context(), &actor, 200);
+
+            let verification = verify_audit_log(&path).unwrap();
+            assert_eq!(verification.records(), 3);
+
+            let content = std::fs::read_to_string(&path).unwrap();
+            let last: AuditRecord = serde_json::from_str(content.lines().last().unwrap()).unwrap();
+            assert_eq!(last.seq, 3);
+            assert_eq!(last.outcome, 200);
+        })
+    }
+
+    #[test]
+    fn remove_incomplete_only_record() {
+        test::test_under_tmp(|d| {
+            let path = d.join("audit.log");
+            std::fs::write(&path, "{\"seq\":1,").unwrap();
+
+            let log = chained_log(&path);
+            let actor = actor_with_checks(&[(Permission::CA_READ, Ok(true))]);
+            log.record(&context(), &actor, 200);
+
+            assert_eq!(verify_audit_log(&path).unwrap().records(), 1);
+        })
+    }
+}
@@ -7,18 +7,37 @@ use crate::{
         actor::{Actor, ActorDef},
         api::Token,
         error::Error,
+        util::httpclient,
         KrillResult,
     },
     constants::{ACTOR_DEF_ANON, NO_RESOURCE},
     daemon::{
-        auth::{common::permissions::Permission, policy::AuthPolicy, providers::AdminTokenAuthProvider},
+        audit::{AuditCheck, AuditTrail},
+        auth::{
+            common::{
+                permissions::Permission,
+                throttle::{LoginThrottle, LoginThrottleStats, ThrottleKey},
+            },
+            policy::AuthPolicy,
+            providers::AdminTokenAuthProvider,
+        },
         config::Config,
-        http::HttpResponse,
+        http::{auth::AUTH_PATH_PREFIX, ClientAddr, HttpResponse},
     },
 };
 
 #[cfg(feature = "multi-user")]
-use crate::daemon::auth::providers::{ConfigFileAuthProvider, OpenIDConnectAuthProvider};
+use std::str::FromStr;
+
+#[cfg(feature = "multi-user")]
+use crate::{
+    commons::api::{PolicyExplainRequest, PolicyExplanation},
+    constants::{API_TOKENS_DIR, TENANTS_DIR},
+    daemon::auth::{
+        common::{tenants::TenantStore, tokens::ApiTokenStore},
+        providers::{ClientCertAuthProvider, ConfigFileAuthProvider, LdapAuthProvider, OpenIDConnectAuthProvider},
+    },
+};
 
 //------------ Authorizer ----------------------------------------------------
 
@@ -44,6 +63,9 @@ pub enum AuthProvider {
 
     #[cfg(feature = "multi-user")]
     OpenIdConnect(OpenIDConnectAuthProvider),
+
+    #[cfg(feature = "multi-user")]
+    Ldap(LdapAuthProvider),
 }
 
 impl From<AdminTokenAuthProvider> for AuthProvider {
@@ -66,6 +88,13 @@ impl From<OpenIDConnectAuthProvider> for AuthProvider {
     }
 }
 
+#[cfg(feature = "multi-user")]
+impl From<LdapAuthProvider> for AuthProvider {
+    fn from(provider: LdapAuthProvider) -> Self {
+        AuthProvider::Ldap(provider)
+    }
+}
+
 impl AuthProvider {
     pub async fn authenticate(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<Option<ActorDef>> {
         match &self {
@@ -74,6 +103,8 @@ impl AuthProvider {
             AuthProvider::ConfigFile(provider) => provider.authenticate(request),
             #[cfg(feature = "multi-user")]
             AuthProvider::OpenIdConnect(provider) => provider.authenticate(request).await,
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.authenticate(request),
         }
     }
 
@@ -84,6 +115,8 @@ impl AuthProvider {
             AuthProvider::ConfigFile(provider) => provider.get_login_url(),
             #[cfg(feature = "multi-user")]
             AuthProvider::OpenIdConnect(provider) => provider.get_login_url().await,
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.get_login_url(),
         }
     }
 
@@ -94,6 +127,21 @@ impl AuthProvider {
             AuthProvider::ConfigFile(provider) => provider.login(request),
             #[cfg(feature = "multi-user")]
             AuthProvider::OpenIdConnect(provider) => provider.login(request).await,
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.login(request).await,
+        }
+    }
+
+    /// Returns the id of the user trying to login, if the provider can tell.
+    pub fn login_id(&self, request: &hyper::Request<hyper::Body>) -> Option<String> {
+        match &self {
+            AuthProvider::Token(_) => None,
+            #[cfg(feature = "multi-user")]
+            AuthProvider::ConfigFile(provider) => provider.login_id(request),
+            #[cfg(feature = "multi-user")]
+            AuthProvider::OpenIdConnect(_) => None,
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.login_id(request),
         }
     }
 
@@ -104,6 +152,8 @@ impl AuthProvider {
             AuthProvider::ConfigFile(provider) => provider.logout(request),
             #[cfg(feature = "multi-user")]
             AuthProvider::OpenIdConnect(provider) => provider.logout(request).await,
+            #[cfg(feature = "multi-user")]
+            AuthProvider::Ldap(provider) => provider.logout(request),
         }
     }
 }
@@ -113,8 +163,15 @@ impl AuthProvider {
 pub struct Authorizer {
     primary_provider: AuthProvider,
     legacy_provider: Option<AdminTokenAuthProvider>,
+    #[cfg(feature = "multi-user")]
+    api_tokens: ApiTokenStore,
+    #[cfg(feature = "multi-user")]
+    tenants: TenantStore,
+    #[cfg(feature = "multi-user")]
+    client_certs: Option<ClientCertAuthProvider>,
     policy: AuthPolicy,
     private_attributes: Vec<String>,
+    throttle: LoginThrottle,
 }
 
 impl Authorizer {
@@ -153,29 +210,64 @@ impl Authorizer {
         #[cfg(not(feature = "multi-user"))]
         let private_attributes = vec!["role".to_string()];
 
+        #[cfg(feature = "multi-user")]
+        let api_tokens = ApiTokenStore::new(&config.data_dir, API_TOKENS_DIR)?;
+        #[cfg(feature = "multi-user")]
+        let tenants = TenantStore::new(&config.data_dir, TENANTS_DIR)?;
+        #[cfg(feature = "multi-user")]
+        let client_certs = config.auth_client_certs.as_ref().map(ClientCertAuthProvider::new);
+
+        let throttle = LoginThrottle::new(config.login_throttle.clone());
+
         Ok(Authorizer {
             primary_provider,
             legacy_provider,
+            #[cfg(feature = "multi-user")]
+            api_tokens,
+            #[cfg(feature = "multi-user")]
+            tenants,
+            #[cfg(feature = "multi-user")]
+            client_certs,
             policy: AuthPolicy::new(config)?,
             private_attributes,
+            throttle,
         })
     }
 
-    pub async fn actor_from_request(&self, request: &hyper::Request<hyper::Body>) -> Actor {
+    /// Determines the actor for a request. Failed attempts to authenticate
+    /// with a bearer token are throttled per source address, and recorded in
+    /// the audit trail if given.
+    pub async fn actor_from_request(
+        &self,
+        request: &hyper::Request<hyper::Body>,
+        audit_trail: Option<&AuditTrail>,
+    ) -> Actor {
         trace!("Determining actor for request {:?}", &request);
 
-        // Try the legacy provider first, if any
-        let mut authenticate_res = match &self.legacy_provider {
-            Some(provider) => provider.authenticate(request),
-            None => Ok(None),
+        // Login requests carry credentials rather than a token in the bearer,
+        // and are throttled in Authorizer::login instead.
+        let throttle_keys = match Self::source_key(request) {
+            Some(key)
+                if httpclient::get_bearer_token(request).is_some()
+                    && !request.uri().path().starts_with(AUTH_PATH_PREFIX) =>
+            {
+                vec![key]
+            }
+            _ => vec![],
         };
 
-        // Try the real provider if we did not already successfully authenticate
-        authenticate_res = match authenticate_res {
-            Ok(Some(res)) => Ok(Some(res)),
-            _ => self.primary_provider.authenticate(request).await,
+        let authenticate_res = match self.throttle.check(&throttle_keys) {
+            Ok(()) => self.authenticate(request).await,
+            Err(err) => {
+                self.audit_throttled(audit_trail, &throttle_keys, &err);
+                Err(err)
+            }
         };
 
+        if let Err(Error::ApiInvalidCredentials(_)) = &authenticate_res {
+            self.record_failure(audit_trail, &throttle_keys);
+        }
+
         // Create an actor based on the authentication result
         let actor = match authenticate_res {
             // authentication success
@@ -196,7 +288,126 @@ impl Authorizer {
         actor
     }
 
+    async fn authenticate(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<Option<ActorDef>> {
+        // A client certificate may be required for the API, even if the
+        // client authenticates in some other way
+        #[cfg(feature = "multi-user")]
+        if let Some(provider) = &self.client_certs {
+            provider.check_required(request)?;
+        }
+
+        // Try scoped API tokens first, then client certificates, then the
+        // legacy provider, if any
+        #[cfg(feature = "multi-user")]
+        let mut authenticate_res = match httpclient::get_bearer_token(request) {
+            Some(token) => self.api_tokens.authenticate(&token),
+            None => Ok(None),
+        };
+        #[cfg(not(feature = "multi-user"))]
+        let mut authenticate_res = Ok(None);
+
+        #[cfg(feature = "multi-user")]
+        {
+            authenticate_res = match (authenticate_res, &self.client_certs) {
+                (Ok(None), Some(provider)) => provider.authenticate(request),
+                (res, _) => res,
+            };
+        }
+
+        authenticate_res = match authenticate_res {
+            Ok(None) => match &self.legacy_provider {
+                Some(provider) => provider.authenticate(request),
+                None => Ok(None),
+            },
+            res => res,
+        };
+
+        // Try the real provider if we did not already successfully authenticate
+        match authenticate_res {
+            Ok(Some(res)) => Ok(Some(res)),
+            _ => self.primary_provider.authenticate(request).await,
+        }
+    }
+
+    /// Returns statistics about failed and throttled authentication
+    /// attempts, for the metrics endpoint.
+    pub fn login_throttle_stats(&self) -> LoginThrottleStats {
+        self.throttle.stats()
+    }
+
+    /// Returns the key for the client address, see ClientAddr for how this
+    /// is determined when Krill is behind a proxy.
+    fn source_key(request: &hyper::Request<hyper::Body>) -> Option<ThrottleKey> {
+        ClientAddr::from_request(request).map(ThrottleKey::Source)
+    }
+
+    fn record_failure(&self, audit_trail: Option<&AuditTrail>, keys: &[ThrottleKey]) {
+        for failure in self.throttle.record_failure(keys) {
+            if failure.blocked_seconds > 0 {
+                warn!("{}", failure);
+                if let Some(trail) = audit_trail {
+                    trail.record(AuditCheck::denied(
+                        &Permission::LOGIN,
+                        &failure.key,
+                        failure.to_string(),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn audit_throttled(&self, audit_trail: Option<&AuditTrail>, keys: &[ThrottleKey], err: &Error) {
+        debug!("Authentication attempt throttled: {}", err);
+        if let Some(trail) = audit_trail {
+            for key in keys {
+                trail.record(AuditCheck::denied(&Permission::LOGIN, key, err.to_string()));
+            }
+        }
+    }
+
+    /// Returns the store of scoped API tokens.
+    #[cfg(feature = "multi-user")]
+    pub fn api_tokens(&self) -> &ApiTokenStore {
+        &self.api_tokens
+    }
+
+    /// Returns the store of tenants.
+    #[cfg(feature = "multi-user")]
+    pub fn tenants(&self) -> &TenantStore {
+        &self.tenants
+    }
+
+    /// Returns the provider of local users, which can only be managed through
+    /// the API if the config file provider is used.
+    #[cfg(feature = "multi-user")]
+    pub fn local_users(&self) -> KrillResult<&ConfigFileAuthProvider> {
+        match &self.primary_provider {
+            AuthProvider::ConfigFile(provider) => Ok(provider),
+            _ => Err(Error::UserInvalid(
+                "users can only be managed when auth_type is 'config-file'".to_string(),
+            )),
+        }
+    }
+
+    /// Reloads the authorization policy, see [AuthPolicy::reload].
+    #[cfg(feature = "multi-user")]
+    pub fn reload_policy(&self) -> KrillResult<()> {
+        self.policy.reload()
+    }
+
+    /// Explains a decision of the authorization policy for an actor with the
+    /// given name and attributes, see [AuthPolicy::explain].
+    #[cfg(feature = "multi-user")]
+    pub fn explain_policy(&self, request: PolicyExplainRequest) -> KrillResult<PolicyExplanation> {
+        let permission = Permission::from_str(request.permission()).map_err(Error::ApiInvalidPermission)?;
+        let actor = Actor::test_from_details(request.actor().to_string(), request.attributes().clone());
+        self.policy.explain(actor, permission, request.ca().cloned())
+    }
+
     pub fn actor_from_def(&self, def: ActorDef) -> Actor {
+        #[cfg(feature = "multi-user")]
+        let def = self.tenants.apply(def);
+
         Actor::new(def, self.policy.clone())
     }
 
@@ -208,8 +419,37 @@ impl Authorizer {
 
     /// Submit credentials directly to the configured provider to establish a
     /// login session, if supported by the configured provider.
-    pub async fn login(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<LoggedInUser> {
-        let user = self.primary_provider.login(request).await?;
+    ///
+    /// Failed attempts are throttled per user id, if known, and per source
+    /// address, and recorded in the audit trail if given.
+    pub async fn login(
+        &self,
+        request: &hyper::Request<hyper::Body>,
+        audit_trail: Option<&AuditTrail>,
+    ) -> KrillResult<LoggedInUser> {
+        let user_key = self.primary_provider.login_id(request).map(ThrottleKey::User);
+        let throttle_keys: Vec<ThrottleKey> = user_key.iter().cloned().chain(Self::source_key(request)).collect();
+
+        if let Err(err) = self.throttle.check(&throttle_keys) {
+            self.audit_throttled(audit_trail, &throttle_keys, &err);
+            return Err(err);
+        }
+
+        let user = match self.primary_provider.login(request).await {
+            Ok(user) => user,
+            Err(err) => {
+                if let Error::ApiInvalidCredentials(_) = err {
+                    self.record_failure(audit_trail, &throttle_keys);
+                }
+                return Err(err);
+            }
+        };
+
+        // Only forget the failures for the user, so that an attacker cannot
+        // reset the count for their address with an account of their own.
+        if let Some(key) = &user_key {
+            self.throttle.record_success(key);
+        }
 
         // The user has passed authentication, but may still not be
         // authorized to login as that requires a check against the policy
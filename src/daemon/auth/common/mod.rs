@@ -13,3 +13,11 @@ impl std::fmt::Display for NoResourceType {
 
 #[cfg(feature = "multi-user")]
 pub mod session;
+
+#[cfg(feature = "multi-user")]
+pub mod tenants;
+
+pub mod throttle;
+
+#[cfg(feature = "multi-user")]
+pub mod tokens;
@@ -64,6 +64,7 @@ iterable_enum! {
         CA_UPDATE,
         CA_ADMIN,
         CA_DELETE,
+        CHILD_REQUEST,
         ROUTES_READ,
         ROUTES_UPDATE,
         ROUTES_ANALYSIS,
@@ -72,6 +73,10 @@ iterable_enum! {
         ASPAS_ANALYSIS,
         RTA_LIST,
         RTA_READ,
-        RTA_UPDATE
+        RTA_UPDATE,
+        TOKEN_ADMIN,
+        POLICY_ADMIN,
+        TENANT_ADMIN,
+        USER_ADMIN
     }
 }
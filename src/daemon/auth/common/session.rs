@@ -18,6 +18,10 @@ pub struct ClientSession {
     pub id: String,
     pub attributes: HashMap<String, String>,
     pub secrets: HashMap<String, String>,
+
+    // Whether the user passed a second authentication factor at login.
+    #[serde(default)]
+    pub mfa: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -178,6 +182,30 @@ impl LoginSessionCache {
         secrets: HashMap<String, String>,
         crypt_state: &CryptState,
         expires_in: Option<Duration>,
+    ) -> KrillResult<Token> {
+        self.encode_session(id, attributes, secrets, false, crypt_state, expires_in)
+    }
+
+    /// Like [Self::encode], but records that the user passed a second
+    /// authentication factor at login.
+    pub fn encode_with_mfa(
+        &self,
+        id: &str,
+        attributes: &HashMap<String, String>,
+        crypt_state: &CryptState,
+        expires_in: Option<Duration>,
+    ) -> KrillResult<Token> {
+        self.encode_session(id, attributes, HashMap::new(), true, crypt_state, expires_in)
+    }
+
+    fn encode_session(
+        &self,
+        id: &str,
+        attributes: &HashMap<String, String>,
+        secrets: HashMap<String, String>,
+        mfa: bool,
+        crypt_state: &CryptState,
+        expires_in: Option<Duration>,
     ) -> KrillResult<Token> {
         let session = ClientSession {
             start_time: Self::time_now_secs_since_epoch()?,
@@ -185,6 +213,7 @@ impl LoginSessionCache {
             id: id.to_string(),
             attributes: attributes.clone(),
             secrets,
+            mfa,
         };
 
         debug!("Creating token for session: {:?}", &session);
@@ -304,6 +333,7 @@ mod tests {
         assert_eq!(item1.attributes, HashMap::new());
         assert_eq!(item1.expires_in, None);
         assert_eq!(item1.secrets, HashMap::new());
+        assert!(!item1.mfa);
 
         // Wait until after the cached item should have expired but as the cache
         // has not yet been swept the item should still be in the cache
@@ -0,0 +1,300 @@
+//! Storage of tenants, which bind users to a set of CAs.
+//!
+//! A user bound to a tenant is given a "tenant" attribute with the name of
+//! the tenant, and a "tenant_cas" attribute listing the CAs of the tenant.
+//! Both are evaluated by the Oso policy, which limits the user to these CAs
+//! and denies permissions that are not scoped to a CA, such as managing the
+//! publication server.
+
+use std::{collections::HashMap, path::Path, sync::RwLock};
+
+use crate::commons::{
+    actor::ActorDef,
+    api::{Handle, TenantDefinition, TenantInfo, TenantList},
+    error::Error,
+    eventsourcing::{KeyStoreKey, KeyValueStore},
+    KrillResult,
+};
+
+//------------ TenantStore ---------------------------------------------------
+
+pub struct TenantStore {
+    store: KeyValueStore,
+    tenants: RwLock<HashMap<String, TenantInfo>>,
+}
+
+impl TenantStore {
+    pub fn new(work_dir: &Path, namespace: &str) -> KrillResult<Self> {
+        let store = KeyValueStore::disk(work_dir, namespace)?;
+
+        let mut tenants = HashMap::new();
+        for key in store.keys(None, ".json")? {
+            if let Some(tenant) = store.get::<TenantInfo>(&key)? {
+                tenants.insert(tenant.name().to_string(), tenant);
+            }
+        }
+
+        Ok(TenantStore {
+            store,
+            tenants: RwLock::new(tenants),
+        })
+    }
+
+    pub fn create(&self, definition: TenantDefinition) -> KrillResult<TenantInfo> {
+        Self::validate(&definition)?;
+
+        let mut tenants = self.tenants.write().unwrap();
+        if tenants.contains_key(definition.name()) {
+            return Err(Error::TenantDuplicate(definition.name().to_string()));
+        }
+
+        let tenant = TenantInfo::new(definition);
+        self.store.store(&Self::key(tenant.name()), &tenant)?;
+        tenants.insert(tenant.name().to_string(), tenant.clone());
+
+        info!("Tenant '{}' created", tenant.name());
+        Ok(tenant)
+    }
+
+    /// Replaces the CAs of an existing tenant.
+    pub fn update(&self, definition: TenantDefinition) -> KrillResult<TenantInfo> {
+        Self::validate(&definition)?;
+
+        let mut tenants = self.tenants.write().unwrap();
+        let tenant = tenants
+            .get_mut(definition.name())
+            .ok_or_else(|| Error::TenantUnknown(definition.name().to_string()))?;
+
+        tenant.set_cas(definition.cas().clone());
+        self.store.store(&Self::key(tenant.name()), tenant)?;
+
+        info!("Tenant '{}' updated", tenant.name());
+        Ok(tenant.clone())
+    }
+
+    pub fn delete(&self, name: &str) -> KrillResult<()> {
+        let mut tenants = self.tenants.write().unwrap();
+        if tenants.remove(name).is_none() {
+            return Err(Error::TenantUnknown(name.to_string()));
+        }
+        self.store.drop_key(&Self::key(name))?;
+
+        info!("Tenant '{}' deleted", name);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> KrillResult<TenantInfo> {
+        self.tenants
+            .read()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::TenantUnknown(name.to_string()))
+    }
+
+    /// Lists all tenants, by name.
+    pub fn list(&self) -> TenantList {
+        let tenants = self.tenants.read().unwrap();
+        let mut infos: Vec<TenantInfo> = tenants.values().cloned().collect();
+        infos.sort_by(|a, b| a.name().cmp(b.name()));
+        TenantList::new(infos)
+    }
+
+    /// Returns true if no tenants are defined.
+    pub fn is_empty(&self) -> bool {
+        self.tenants.read().unwrap().is_empty()
+    }
+
+    /// Binds a user to a tenant. A user can be bound to one tenant only, as
+    /// the CAs of different customers should never be mixed up.
+    pub fn add_user(&self, name: &str, user: &str) -> KrillResult<()> {
+        if user.is_empty() {
+            return Err(Error::TenantInvalid("empty user name".to_string()));
+        }
+
+        let mut tenants = self.tenants.write().unwrap();
+
+        if let Some(other) = tenants.values().find(|t| t.name() != name && t.has_user(user)) {
+            return Err(Error::TenantInvalid(format!(
+                "user '{}' is already bound to tenant '{}'",
+                user,
+                other.name()
+            )));
+        }
+
+        let tenant = tenants
+            .get_mut(name)
+            .ok_or_else(|| Error::TenantUnknown(name.to_string()))?;
+
+        tenant.add_user(user.to_string());
+        self.store.store(&Self::key(name), tenant)?;
+
+        info!("User '{}' bound to tenant '{}'", user, name);
+        Ok(())
+    }
+
+    pub fn remove_user(&self, name: &str, user: &str) -> KrillResult<()> {
+        let mut tenants = self.tenants.write().unwrap();
+        let tenant = tenants
+            .get_mut(name)
+            .ok_or_else(|| Error::TenantUnknown(name.to_string()))?;
+
+        if tenant.remove_user(user) {
+            self.store.store(&Self::key(name), tenant)?;
+            info!("User '{}' removed from tenant '{}'", user, name);
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the previous handle of a renamed CA in all tenants that own
+    /// it, so that their users keep access to it. Returns the names of the
+    /// updated tenants.
+    pub fn rename_ca(&self, from: &Handle, to: &Handle) -> KrillResult<Vec<String>> {
+        let mut tenants = self.tenants.write().unwrap();
+
+        let mut updated = vec![];
+        for tenant in tenants.values_mut() {
+            let mut renamed = tenant.clone();
+            if renamed.rename_ca(from, to) {
+                self.store.store(&Self::key(renamed.name()), &renamed)?;
+                *tenant = renamed;
+                info!(
+                    "Tenant '{}' updated for CA '{}' renamed to '{}'",
+                    tenant.name(),
+                    from,
+                    to
+                );
+                updated.push(tenant.name().to_string());
+            }
+        }
+
+        updated.sort();
+        Ok(updated)
+    }
+
+    /// Adds the tenant attributes to the definition of a user actor, if the
+    /// user is bound to a tenant. Any existing tenant attributes are replaced
+    /// so that they cannot be injected through an authentication provider.
+    pub fn apply(&self, def: ActorDef) -> ActorDef {
+        if !def.is_user {
+            return def;
+        }
+
+        let tenants = self.tenants.read().unwrap();
+        match tenants.values().find(|t| t.has_user(def.name.as_str())) {
+            Some(tenant) => {
+                let cas: Vec<&str> = tenant.cas().iter().map(|ca| ca.as_str()).collect();
+                let cas = cas.join(",");
+                def.with_attribute("tenant", tenant.name())
+                    .with_attribute("tenant_cas", &cas)
+            }
+            None => def.without_attribute("tenant").without_attribute("tenant_cas"),
+        }
+    }
+
+    fn validate(definition: &TenantDefinition) -> KrillResult<()> {
+        let name = definition.name();
+        if name.is_empty()
+            || name.len() > 255
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::TenantInvalid(format!(
+                "name '{}' must be 1-255 characters of a-z, A-Z, 0-9, '-' or '_'",
+                name
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn key(name: &str) -> KeyStoreKey {
+        KeyStoreKey::simple(format!("{}.json", name))
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use crate::test;
+
+    fn definition(name: &str, cas: &[&str]) -> TenantDefinition {
+        TenantDefinition::new(
+            name.to_string(),
+            cas.iter().map(|ca| Handle::from_str(ca).unwrap()).collect(),
+        )
+    }
+
+    fn user(name: &str) -> ActorDef {
+        ActorDef::user(name.to_string(), HashMap::new(), None)
+    }
+
+    #[test]
+    fn bind_users_to_tenants() {
+        test::test_under_tmp(|d| {
+            let store = TenantStore::new(&d, "tenants").unwrap();
+
+            store.create(definition("customer1", &["ca1", "ca2"])).unwrap();
+            store.create(definition("customer2", &["ca3"])).unwrap();
+            assert!(store.create(definition("customer1", &[])).is_err());
+
+            store.add_user("customer1", "alice@example.com").unwrap();
+            assert!(store.add_user("customer2", "alice@example.com").is_err());
+            assert!(store.add_user("unknown", "bob@example.com").is_err());
+
+            let attributes = store.apply(user("alice@example.com")).attributes.as_map();
+            assert_eq!(attributes.get("tenant").unwrap(), "customer1");
+            assert_eq!(attributes.get("tenant_cas").unwrap(), "ca1,ca2");
+
+            // the state survives a restart
+            store.update(definition("customer1", &["ca1"])).unwrap();
+            let store = TenantStore::new(&d, "tenants").unwrap();
+            let attributes = store.apply(user("alice@example.com")).attributes.as_map();
+            assert_eq!(attributes.get("tenant_cas").unwrap(), "ca1");
+
+            // users not bound to a tenant cannot claim one
+            let mut claimed = HashMap::new();
+            claimed.insert("tenant".to_string(), "customer2".to_string());
+            let def = ActorDef::user("bob@example.com".to_string(), claimed, None);
+            assert!(store.apply(def).attributes.as_map().get("tenant").is_none());
+
+            store.remove_user("customer1", "alice@example.com").unwrap();
+            assert!(store.apply(user("alice@example.com")).attributes.as_map().is_empty());
+
+            store.delete("customer2").unwrap();
+            assert!(store.get("customer2").is_err());
+            assert_eq!(store.list().tenants().len(), 1);
+        })
+    }
+
+    #[test]
+    fn rename_ca_in_tenants() {
+        test::test_under_tmp(|d| {
+            let store = TenantStore::new(&d, "tenants").unwrap();
+
+            store.create(definition("customer1", &["ca1", "ca2"])).unwrap();
+            store.create(definition("customer2", &["ca3"])).unwrap();
+            store.add_user("customer1", "alice@example.com").unwrap();
+
+            let from = Handle::from_str("ca2").unwrap();
+            let to = Handle::from_str("renamed").unwrap();
+
+            assert_eq!(vec!["customer1".to_string()], store.rename_ca(&from, &to).unwrap());
+            assert!(store.rename_ca(&from, &to).unwrap().is_empty());
+
+            // users of the tenant keep access under the new name, also after a restart
+            let store = TenantStore::new(&d, "tenants").unwrap();
+            let attributes = store.apply(user("alice@example.com")).attributes.as_map();
+            assert_eq!(attributes.get("tenant_cas").unwrap(), "ca1,renamed");
+            assert_eq!(
+                store.get("customer2").unwrap().cas(),
+                &vec![Handle::from_str("ca3").unwrap()]
+            );
+        })
+    }
+}
@@ -0,0 +1,307 @@
+//! Throttling of failed authentication attempts.
+//!
+//! Failures are counted per user name, when known, and per source address.
+//! After a number of free failures, each further failure blocks the user name
+//! or source address for a delay that doubles with every failure, up to a
+//! maximum. After more failures the user name or source address is locked
+//! out for a longer time. While blocked, attempts are rejected without
+//! checking the credentials, so that guessing cannot continue. Failures are
+//! forgotten after a while without further failures, or when the user logs
+//! in successfully.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    commons::{error::Error, KrillResult},
+    daemon::config::LoginThrottleConfig,
+};
+
+//------------ ThrottleKey ---------------------------------------------------
+
+/// What failures are counted for.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ThrottleKey {
+    User(String),
+    Source(IpAddr),
+}
+
+impl fmt::Display for ThrottleKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ThrottleKey::User(user) => write!(f, "user '{}'", user),
+            ThrottleKey::Source(addr) => write!(f, "source {}", addr),
+        }
+    }
+}
+
+//------------ ThrottledFailure ----------------------------------------------
+
+/// The consequence of a failed attempt for one key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ThrottledFailure {
+    pub key: ThrottleKey,
+    pub failures: u32,
+    pub blocked_seconds: u64,
+    pub locked_out: bool,
+}
+
+impl fmt::Display for ThrottledFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} failed to authenticate {} times", self.key, self.failures)?;
+        if self.locked_out {
+            write!(f, ", locked out for {} seconds", self.blocked_seconds)
+        } else if self.blocked_seconds > 0 {
+            write!(f, ", blocked for {} seconds", self.blocked_seconds)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+//------------ LoginThrottleStats --------------------------------------------
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LoginThrottleStats {
+    pub failures: u64,
+    pub throttled: u64,
+    pub lockouts: u64,
+    pub blocked: usize,
+}
+
+//------------ LoginThrottle -------------------------------------------------
+
+#[derive(Clone, Debug, Default)]
+struct FailureRecord {
+    failures: u32,
+    last_failure: u64,
+    blocked_until: u64,
+}
+
+pub struct LoginThrottle {
+    config: LoginThrottleConfig,
+    records: Mutex<HashMap<ThrottleKey, FailureRecord>>,
+    failures: AtomicU64,
+    throttled: AtomicU64,
+    lockouts: AtomicU64,
+}
+
+impl LoginThrottle {
+    pub fn new(config: LoginThrottleConfig) -> Self {
+        LoginThrottle {
+            config,
+            records: Mutex::new(HashMap::new()),
+            failures: AtomicU64::new(0),
+            throttled: AtomicU64::new(0),
+            lockouts: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns an error if any of the keys is currently blocked.
+    pub fn check(&self, keys: &[ThrottleKey]) -> KrillResult<()> {
+        self.check_at(keys, now_secs())
+    }
+
+    fn check_at(&self, keys: &[ThrottleKey], now: u64) -> KrillResult<()> {
+        if !self.config.auth_throttle_enabled {
+            return Ok(());
+        }
+
+        let records = self.records.lock().unwrap();
+        let retry_after = keys
+            .iter()
+            .filter_map(|key| records.get(key))
+            .map(|record| record.blocked_until.saturating_sub(now))
+            .max()
+            .unwrap_or(0);
+
+        if retry_after > 0 {
+            self.throttled.fetch_add(1, Ordering::Relaxed);
+            Err(Error::ApiLoginThrottled(retry_after))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Records a failed attempt for all keys, and returns the consequences.
+    pub fn record_failure(&self, keys: &[ThrottleKey]) -> Vec<ThrottledFailure> {
+        self.record_failure_at(keys, now_secs())
+    }
+
+    fn record_failure_at(&self, keys: &[ThrottleKey], now: u64) -> Vec<ThrottledFailure> {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        if !self.config.auth_throttle_enabled {
+            return vec![];
+        }
+
+        let config = &self.config;
+        let mut records = self.records.lock().unwrap();
+
+        // Forget old failures, so that the records cannot grow forever.
+        records.retain(|_, record| {
+            record.blocked_until > now || record.last_failure + config.auth_throttle_window_seconds > now
+        });
+
+        keys.iter()
+            .map(|key| {
+                let record = records.entry(key.clone()).or_default();
+                record.failures += 1;
+                record.last_failure = now;
+
+                let locked_out = record.failures >= config.auth_throttle_lockout_failures;
+                let blocked_seconds = if locked_out {
+                    config.auth_throttle_lockout_seconds
+                } else if record.failures > config.auth_throttle_free_failures {
+                    let doublings = (record.failures - config.auth_throttle_free_failures - 1).min(63);
+                    config
+                        .auth_throttle_base_delay_seconds
+                        .saturating_mul(1u64.checked_shl(doublings).unwrap_or(u64::MAX))
+                        .min(config.auth_throttle_max_delay_seconds)
+                } else {
+                    0
+                };
+
+                if locked_out && record.blocked_until <= now {
+                    self.lockouts.fetch_add(1, Ordering::Relaxed);
+                    warn!("Too many failed authentication attempts, {} locked out", key);
+                }
+                record.blocked_until = record.blocked_until.max(now + blocked_seconds);
+
+                ThrottledFailure {
+                    key: key.clone(),
+                    failures: record.failures,
+                    blocked_seconds,
+                    locked_out,
+                }
+            })
+            .collect()
+    }
+
+    /// Forgets the failures for a key, after a successful login.
+    pub fn record_success(&self, key: &ThrottleKey) {
+        self.records.lock().unwrap().remove(key);
+    }
+
+    pub fn stats(&self) -> LoginThrottleStats {
+        let now = now_secs();
+        LoginThrottleStats {
+            failures: self.failures.load(Ordering::Relaxed),
+            throttled: self.throttled.load(Ordering::Relaxed),
+            lockouts: self.lockouts.load(Ordering::Relaxed),
+            blocked: self
+                .records
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|record| record.blocked_until > now)
+                .count(),
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle() -> LoginThrottle {
+        LoginThrottle::new(LoginThrottleConfig {
+            auth_throttle_enabled: true,
+            auth_throttle_free_failures: 2,
+            auth_throttle_base_delay_seconds: 1,
+            auth_throttle_max_delay_seconds: 4,
+            auth_throttle_lockout_failures: 6,
+            auth_throttle_lockout_seconds: 100,
+            auth_throttle_window_seconds: 1000,
+            auth_trusted_proxies: vec![],
+        })
+    }
+
+    #[test]
+    fn backoff_and_lockout() {
+        let throttle = throttle();
+        let alice = ThrottleKey::User("alice".to_string());
+        let source = ThrottleKey::Source("192.0.2.1".parse().unwrap());
+        let keys = vec![alice.clone(), source.clone()];
+
+        let blocked = |failures: Vec<ThrottledFailure>| failures[0].blocked_seconds;
+
+        // free failures, then exponential backoff up to the maximum
+        assert_eq!(blocked(throttle.record_failure_at(&keys, 1000)), 0);
+        assert_eq!(blocked(throttle.record_failure_at(&keys, 1000)), 0);
+        assert!(throttle.check_at(&keys, 1000).is_ok());
+
+        assert_eq!(blocked(throttle.record_failure_at(&keys, 1000)), 1);
+        assert!(throttle.check_at(&keys, 1000).is_err());
+        assert!(throttle.check_at(&keys, 1001).is_ok());
+
+        assert_eq!(blocked(throttle.record_failure_at(&keys, 1001)), 2);
+        assert_eq!(blocked(throttle.record_failure_at(&keys, 1003)), 4);
+        assert!(throttle.check_at(&[source.clone()], 1005).is_err());
+
+        // a lockout after too many failures
+        let failures = throttle.record_failure_at(&keys, 1007);
+        assert!(failures[0].locked_out);
+        assert!(throttle.check_at(&[alice.clone()], 1106).is_err());
+        assert!(throttle.check_at(&[alice.clone()], 1107).is_ok());
+
+        // other users and sources are not affected
+        let bob = ThrottleKey::User("bob".to_string());
+        assert!(throttle.check_at(&[bob.clone()], 1010).is_ok());
+
+        // success resets the user, but not the source
+        throttle.record_success(&alice);
+        assert!(throttle.check_at(&[alice], 1010).is_ok());
+        assert!(throttle.check_at(&[source], 1010).is_err());
+
+        let stats = throttle.stats();
+        assert_eq!(stats.failures, 6);
+        assert_eq!(stats.lockouts, 2);
+        assert_eq!(stats.throttled, 4);
+    }
+
+    #[test]
+    fn forget_old_failures() {
+        let throttle = throttle();
+        let keys = vec![ThrottleKey::User("alice".to_string())];
+
+        for _ in 0..3 {
+            throttle.record_failure_at(&keys, 1000);
+        }
+        assert!(throttle.check_at(&keys, 1000).is_err());
+
+        // after the window the count starts from zero again
+        let failures = throttle.record_failure_at(&keys, 3000);
+        assert_eq!(failures[0].failures, 1);
+        assert!(throttle.check_at(&keys, 3000).is_ok());
+    }
+
+    #[test]
+    fn disabled() {
+        let mut config = throttle().config;
+        config.auth_throttle_enabled = false;
+        let throttle = LoginThrottle::new(config);
+        let keys = vec![ThrottleKey::User("alice".to_string())];
+
+        for _ in 0..10 {
+            assert!(throttle.record_failure_at(&keys, 1000).is_empty());
+        }
+        assert!(throttle.check_at(&keys, 1000).is_ok());
+    }
+}
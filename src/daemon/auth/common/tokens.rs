@@ -0,0 +1,452 @@
+//! Storage and verification of scoped API tokens.
+//!
+//! Only a SHA-256 hash of each token is persisted. An authenticated token is
+//! mapped to an actor named "token:<name>" with a "permissions" attribute
+//! listing the granted permissions, and an "inc_cas" attribute if the token
+//! is limited to specific CAs. Both are evaluated by the Oso policy.
+
+use std::{collections::HashMap, path::Path, str::FromStr, sync::RwLock};
+
+use crate::{
+    commons::{
+        actor::{Actor, ActorDef},
+        api::{ApiTokenCreated, ApiTokenInfo, ApiTokenList, ApiTokenRequest, Handle, Timestamp, Token},
+        error::Error,
+        eventsourcing::{KeyStoreKey, KeyValueStore},
+        util::sha256,
+        KrillResult,
+    },
+    constants::NO_RESOURCE,
+    daemon::auth::common::permissions::Permission,
+};
+
+const TOKEN_BYTES: usize = 32;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct StoredApiToken {
+    hash: String,
+    info: ApiTokenInfo,
+}
+
+//------------ ApiTokenStore -------------------------------------------------
+
+pub struct ApiTokenStore {
+    store: KeyValueStore,
+    tokens: RwLock<HashMap<String, StoredApiToken>>,
+}
+
+impl ApiTokenStore {
+    pub fn new(work_dir: &Path, namespace: &str) -> KrillResult<Self> {
+        let store = KeyValueStore::disk(work_dir, namespace)?;
+
+        let mut tokens = HashMap::new();
+        for key in store.keys(None, ".json")? {
+            if let Some(stored) = store.get::<StoredApiToken>(&key)? {
+                tokens.insert(stored.info.name().to_string(), stored);
+            }
+        }
+
+        Ok(ApiTokenStore {
+            store,
+            tokens: RwLock::new(tokens),
+        })
+    }
+
+    /// Creates a new token and returns it. Note that the token itself is not
+    /// kept, so this is the only time that it can be shown.
+    ///
+    /// The creator must itself be allowed all requested permissions on all
+    /// requested CAs, or on all current CAs if the token is not limited to
+    /// specific CAs. This prevents that tokens are used to escalate
+    /// privileges.
+    pub fn create(
+        &self,
+        request: ApiTokenRequest,
+        creator: &Actor,
+        current_cas: &[Handle],
+    ) -> KrillResult<ApiTokenCreated> {
+        Self::validate(&request)?;
+        Self::check_grantable(&request, creator, current_cas)?;
+        let created_by = creator.name();
+
+        let mut tokens = self.tokens.write().unwrap();
+
+        // Names are never re-used, not even when a token was revoked, so that
+        // the history of the use of a token name remains unambiguous.
+        if tokens.contains_key(request.name()) {
+            return Err(Error::ApiTokenDuplicate(request.name().to_string()));
+        }
+
+        let token = Self::random_token()?;
+        let stored = StoredApiToken {
+            hash: Self::hash(&token),
+            info: ApiTokenInfo::new(request, created_by.to_string()),
+        };
+
+        self.store.store(&Self::key(stored.info.name()), &stored)?;
+
+        let info = stored.info.clone();
+        tokens.insert(info.name().to_string(), stored);
+
+        info!("API token '{}' created by {}", info.name(), info.created_by());
+        Ok(ApiTokenCreated::new(token, info))
+    }
+
+    /// Lists all tokens, including revoked and expired tokens, by name.
+    pub fn list(&self) -> ApiTokenList {
+        let tokens = self.tokens.read().unwrap();
+        let mut infos: Vec<ApiTokenInfo> = tokens.values().map(|stored| stored.info.clone()).collect();
+        infos.sort_by(|a, b| a.name().cmp(b.name()));
+        ApiTokenList::new(infos)
+    }
+
+    /// Revokes a token. The token is kept, so that it shows up as revoked.
+    pub fn revoke(&self, name: &str) -> KrillResult<()> {
+        let mut tokens = self.tokens.write().unwrap();
+
+        let stored = tokens
+            .get_mut(name)
+            .ok_or_else(|| Error::ApiTokenUnknown(name.to_string()))?;
+
+        if stored.info.revoked().is_none() {
+            stored.info.revoke();
+            self.store.store(&Self::key(name), stored)?;
+            info!("API token '{}' revoked", name);
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the previous handle of a renamed CA in all tokens that are
+    /// limited to it, so that they keep access to it. Returns the names of
+    /// the updated tokens.
+    pub fn rename_ca(&self, from: &Handle, to: &Handle) -> KrillResult<Vec<String>> {
+        let mut tokens = self.tokens.write().unwrap();
+
+        let mut updated = vec![];
+        for stored in tokens.values_mut() {
+            let mut renamed = stored.clone();
+            if renamed.info.rename_ca(from, to) {
+                self.store.store(&Self::key(renamed.info.name()), &renamed)?;
+                *stored = renamed;
+                info!(
+                    "API token '{}' updated for CA '{}' renamed to '{}'",
+                    stored.info.name(),
+                    from,
+                    to
+                );
+                updated.push(stored.info.name().to_string());
+            }
+        }
+
+        updated.sort();
+        Ok(updated)
+    }
+
+    /// Returns the actor for the given token, if it is a known scoped API
+    /// token. Returns an error if the token was revoked or has expired.
+    pub fn authenticate(&self, token: &Token) -> KrillResult<Option<ActorDef>> {
+        let hash = Self::hash(token);
+        let tokens = self.tokens.read().unwrap();
+
+        match tokens.values().find(|stored| stored.hash == hash) {
+            None => Ok(None),
+            Some(stored) => {
+                let info = &stored.info;
+                if info.revoked().is_some() {
+                    Err(Error::ApiInvalidCredentials(format!(
+                        "API token '{}' has been revoked",
+                        info.name()
+                    )))
+                } else if info.is_expired() {
+                    Err(Error::ApiInvalidCredentials(format!(
+                        "API token '{}' has expired",
+                        info.name()
+                    )))
+                } else {
+                    Ok(Some(Self::actor_def(info)))
+                }
+            }
+        }
+    }
+
+    fn actor_def(info: &ApiTokenInfo) -> ActorDef {
+        let mut attributes = HashMap::new();
+        attributes.insert("permissions".to_string(), info.permissions().join(","));
+        if let Some(cas) = info.cas() {
+            let cas: Vec<&str> = cas.iter().map(|ca| ca.as_str()).collect();
+            attributes.insert("inc_cas".to_string(), cas.join(","));
+        }
+
+        ActorDef::user(format!("token:{}", info.name()), attributes, None)
+    }
+
+    fn check_grantable(request: &ApiTokenRequest, creator: &Actor, current_cas: &[Handle]) -> KrillResult<()> {
+        let cas = match request.cas() {
+            Some(cas) => cas.as_slice(),
+            None => {
+                // A token without a CA limit also gives access to CAs that
+                // are added later, so it can only be created by an actor
+                // that is not limited to specific CAs itself.
+                let limits = ["inc_cas", "exc_cas", "tenant", "tenant_cas"];
+                if let Some(limit) = limits.iter().find(|attr| creator.attribute(attr.to_string()).is_some()) {
+                    return Err(Error::ApiInsufficientRights(format!(
+                        "User '{}' is limited to specific CAs ('{}'), so it can only create tokens for specific CAs",
+                        creator.name(),
+                        limit
+                    )));
+                }
+                current_cas
+            }
+        };
+
+        for permission in request.permissions() {
+            let permission = Permission::from_str(permission).map_err(Error::ApiTokenInvalid)?;
+
+            let mut allowed = creator.is_allowed(permission.clone(), NO_RESOURCE)?;
+            for ca in cas {
+                allowed = allowed && creator.is_allowed(permission.clone(), ca.clone())?;
+            }
+
+            if !allowed {
+                return Err(Error::ApiInsufficientRights(format!(
+                    "User '{}' cannot grant permission '{}' which it does not have itself",
+                    creator.name(),
+                    permission
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate(request: &ApiTokenRequest) -> KrillResult<()> {
+        let name = request.name();
+        if name.is_empty()
+            || name.len() > 255
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::ApiTokenInvalid(format!(
+                "name '{}' must be 1-255 characters of a-z, A-Z, 0-9, '-' or '_'",
+                name
+            )));
+        }
+
+        if request.permissions().is_empty() {
+            return Err(Error::ApiTokenInvalid("no permissions given".to_string()));
+        }
+
+        for permission in request.permissions() {
+            Permission::from_str(permission).map_err(Error::ApiTokenInvalid)?;
+        }
+
+        if let Some(cas) = request.cas() {
+            if cas.is_empty() {
+                return Err(Error::ApiTokenInvalid(
+                    "empty list of CAs given, omit it to allow all CAs".to_string(),
+                ));
+            }
+        }
+
+        if let Some(expires) = request.expires() {
+            if expires < Timestamp::now() {
+                return Err(Error::ApiTokenInvalid("expiry time is in the past".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn random_token() -> KrillResult<Token> {
+        let mut bytes = [0; TOKEN_BYTES];
+        openssl::rand::rand_bytes(&mut bytes).map_err(|e| Error::custom(format!("Cannot create token: {}", e)))?;
+        Ok(Token::from(hex::encode(bytes)))
+    }
+
+    fn hash(token: &Token) -> String {
+        hex::encode(sha256(token.as_ref().as_bytes()))
+    }
+
+    fn key(name: &str) -> KeyStoreKey {
+        KeyStoreKey::simple(format!("{}.json", name))
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Arc};
+
+    use super::*;
+
+    use crate::{
+        daemon::{auth::policy::AuthPolicy, config::Config},
+        test,
+    };
+
+    fn request(name: &str, permissions: &[&str], cas: Option<&[&str]>) -> ApiTokenRequest {
+        ApiTokenRequest::new(
+            name.to_string(),
+            permissions.iter().map(|p| p.to_string()).collect(),
+            cas.map(|cas| cas.iter().map(|ca| Handle::from_str(ca).unwrap()).collect()),
+            None,
+        )
+    }
+
+    fn actor(d: &Path, def: ActorDef) -> Actor {
+        let policy = AuthPolicy::new(Arc::new(Config::test(d, false, false, false))).unwrap();
+        Actor::new(def, policy)
+    }
+
+    fn admin(d: &Path) -> Actor {
+        let mut attributes = HashMap::new();
+        attributes.insert("role".to_string(), "admin".to_string());
+        actor(d, ActorDef::user("admin".to_string(), attributes, None))
+    }
+
+    fn cas(cas: &[&str]) -> Vec<Handle> {
+        cas.iter().map(|ca| Handle::from_str(ca).unwrap()).collect()
+    }
+
+    #[test]
+    fn create_authenticate_and_revoke() {
+        test::test_under_tmp(|d| {
+            let store = ApiTokenStore::new(&d, "api_tokens").unwrap();
+            let admin = admin(&d);
+            let current_cas = cas(&["ca1", "ca2"]);
+
+            let created = store
+                .create(
+                    request("automation", &["ROUTES_UPDATE"], Some(&["ca1", "ca2"])),
+                    &admin,
+                    &current_cas,
+                )
+                .unwrap();
+            let token = created.token().clone();
+
+            let def = store.authenticate(&token).unwrap().unwrap();
+            assert_eq!(def.name.as_str(), "token:automation");
+            let attributes = def.attributes.as_map();
+            assert_eq!(attributes.get("permissions").unwrap(), "ROUTES_UPDATE");
+            assert_eq!(attributes.get("inc_cas").unwrap(), "ca1,ca2");
+
+            assert!(store.authenticate(&Token::from("unknown")).unwrap().is_none());
+
+            // names cannot be re-used
+            assert!(store
+                .create(request("automation", &["CA_READ"], None), &admin, &current_cas)
+                .is_err());
+
+            // only the hash is persisted, and the state survives a restart
+            let content = std::fs::read_to_string(d.join("api_tokens").join("automation.json")).unwrap();
+            assert!(!content.contains(token.as_ref()));
+
+            store.revoke("automation").unwrap();
+            let store = ApiTokenStore::new(&d, "api_tokens").unwrap();
+            assert!(store.authenticate(&token).is_err());
+            assert!(store.list().tokens()[0].revoked().is_some());
+            assert!(store.revoke("unknown").is_err());
+        })
+    }
+
+    #[test]
+    fn reject_invalid_requests() {
+        test::test_under_tmp(|d| {
+            let store = ApiTokenStore::new(&d, "api_tokens").unwrap();
+            let admin = admin(&d);
+            let current_cas = cas(&["ca1"]);
+
+            assert!(store
+                .create(request("", &["CA_READ"], None), &admin, &current_cas)
+                .is_err());
+            assert!(store
+                .create(request("a/b", &["CA_READ"], None), &admin, &current_cas)
+                .is_err());
+            assert!(store.create(request("a", &[], None), &admin, &current_cas).is_err());
+            assert!(store
+                .create(request("a", &["CA_WRITE"], None), &admin, &current_cas)
+                .is_err());
+            assert!(store
+                .create(request("a", &["CA_READ"], Some(&[])), &admin, &current_cas)
+                .is_err());
+        })
+    }
+
+    #[test]
+    fn scoped_token_cannot_escalate() {
+        test::test_under_tmp(|d| {
+            let store = ApiTokenStore::new(&d, "api_tokens").unwrap();
+            let current_cas = cas(&["ca1", "ca2"]);
+
+            let created = store
+                .create(
+                    request("token-admin", &["TOKEN_ADMIN", "CA_READ"], Some(&["ca1"])),
+                    &admin(&d),
+                    &current_cas,
+                )
+                .unwrap();
+            let def = store.authenticate(created.token()).unwrap().unwrap();
+            let scoped = actor(&d, def);
+
+            // a token that is not limited to CAs would also cover ca2, and
+            // any CA added later
+            assert!(store
+                .create(request("all-cas", &["CA_READ"], None), &scoped, &current_cas)
+                .is_err());
+
+            // a CA that the creator cannot access
+            assert!(store
+                .create(request("other-ca", &["CA_READ"], Some(&["ca2"])), &scoped, &current_cas)
+                .is_err());
+
+            // a permission that the creator does not have
+            assert!(store
+                .create(
+                    request("more-rights", &["ROUTES_UPDATE"], Some(&["ca1"])),
+                    &scoped,
+                    &current_cas
+                )
+                .is_err());
+
+            // a subset of what the creator has is fine
+            assert!(store
+                .create(request("subset", &["CA_READ"], Some(&["ca1"])), &scoped, &current_cas)
+                .is_ok());
+        })
+    }
+
+    #[test]
+    fn rename_ca_in_tokens() {
+        test::test_under_tmp(|d| {
+            let store = ApiTokenStore::new(&d, "api_tokens").unwrap();
+            let admin = admin(&d);
+            let current_cas = cas(&["ca1", "ca2"]);
+
+            let limited = store
+                .create(
+                    request("limited", &["CA_READ"], Some(&["ca1", "ca2"])),
+                    &admin,
+                    &current_cas,
+                )
+                .unwrap();
+            store
+                .create(request("other", &["CA_READ"], Some(&["ca2"])), &admin, &current_cas)
+                .unwrap();
+            store
+                .create(request("all", &["CA_READ"], None), &admin, &current_cas)
+                .unwrap();
+
+            let from = Handle::from_str("ca1").unwrap();
+            let to = Handle::from_str("renamed").unwrap();
+
+            assert_eq!(vec!["limited".to_string()], store.rename_ca(&from, &to).unwrap());
+            assert!(store.rename_ca(&from, &to).unwrap().is_empty());
+
+            // the token keeps access under the new name, also after a restart
+            let store = ApiTokenStore::new(&d, "api_tokens").unwrap();
+            let def = store.authenticate(limited.token()).unwrap().unwrap();
+            assert_eq!(def.attributes.as_map().get("inc_cas").unwrap(), "renamed,ca2");
+        })
+    }
+}
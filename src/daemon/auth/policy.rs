@@ -1,36 +1,54 @@
-use std::{io::Read, str::FromStr, sync::Arc};
+use std::{
+    io::Read,
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
 
 use oso::{Oso, PolarClass, PolarValue, ToPolar};
 
 use crate::{
     commons::{
         actor::Actor,
-        api::Handle,
+        api::{Handle, PolicyExplanation, PolicyRuleResult},
         error::{Error, KrillIoError},
         KrillResult,
     },
-    constants::{ACTOR_DEF_ADMIN_TOKEN, ACTOR_DEF_ANON, ACTOR_DEF_KRILL, ACTOR_DEF_TESTBED},
+    constants::{ACTOR_DEF_ADMIN_TOKEN, ACTOR_DEF_ANON, ACTOR_DEF_KRILL, ACTOR_DEF_TESTBED, NO_RESOURCE},
     daemon::{
         auth::common::{permissions::Permission, NoResourceType},
         config::Config,
     },
 };
 
+/// The authorization policy. Clones share the same policy, so that a reload
+/// of the policy affects all of them.
 #[derive(Clone)]
 pub struct AuthPolicy {
-    oso: Arc<Oso>,
+    oso: Arc<RwLock<Arc<Oso>>>,
+    config: Arc<Config>,
 }
 
-impl std::ops::Deref for AuthPolicy {
-    type Target = Arc<Oso>;
+impl AuthPolicy {
+    pub fn new(config: Arc<Config>) -> KrillResult<Self> {
+        let oso = Self::load(&config)?;
+        Ok(AuthPolicy {
+            oso: Arc::new(RwLock::new(Arc::new(oso))),
+            config,
+        })
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.oso
+    /// Loads the built-in and user-defined policies again, and replaces the
+    /// current policy with them if they pass the same checks as at startup.
+    /// If not, then the current policy remains in use.
+    pub fn reload(&self) -> KrillResult<()> {
+        info!("Reloading authorization policy");
+        let oso = Self::load(&self.config)?;
+        *self.oso.write().unwrap() = Arc::new(oso);
+        info!("Reloaded authorization policy");
+        Ok(())
     }
-}
 
-impl AuthPolicy {
-    pub fn new(config: Arc<Config>) -> KrillResult<Self> {
+    fn load(config: &Config) -> KrillResult<Oso> {
         let mut oso = Oso::new();
         oso.register_class(Actor::get_polar_class()).unwrap();
         oso.register_class(Handle::get_polar_class()).unwrap();
@@ -84,7 +102,11 @@ impl AuthPolicy {
         // have the "testbed" role.
         Self::exec_query(&mut oso, r#"actor_has_role(Actor.builtin("testbed"), "testbed")"#)?;
 
-        Ok(AuthPolicy { oso: Arc::new(oso) })
+        Ok(oso)
+    }
+
+    fn current(&self) -> Arc<Oso> {
+        self.oso.read().unwrap().clone()
     }
 
     pub fn is_allowed<U, A, R>(&self, actor: U, action: A, resource: R) -> Result<bool, Error>
@@ -93,11 +115,118 @@ impl AuthPolicy {
         A: ToPolar,
         R: ToPolar,
     {
-        self.oso
+        self.current()
             .is_allowed(actor, action, resource)
             .map_err(|err| Error::custom(format!("Internal error while checking access against policy: {}", err)))
     }
 
+    /// Explains whether the actor may perform the permission, on the CA if
+    /// given, by evaluating the rules of the built-in policy that make up
+    /// the decision. If the decision cannot be attributed to one of these
+    /// rules it must have been made by a user-defined rule.
+    pub fn explain(&self, actor: Actor, permission: Permission, ca: Option<Handle>) -> KrillResult<PolicyExplanation> {
+        let oso = self.current();
+
+        let (allowed, disallowed, can_access_ca) = match &ca {
+            Some(ca) => (
+                self.is_allowed(actor.clone(), permission.clone(), ca.clone())?,
+                Self::rule_holds(
+                    oso.query_rule("disallow", (actor.clone(), permission.clone(), ca.clone())),
+                    "disallow",
+                )?,
+                Some(Self::rule_holds(
+                    oso.query_rule("actor_can_access_ca", (actor.clone(), ca.clone())),
+                    "actor_can_access_ca",
+                )?),
+            ),
+            None => (
+                self.is_allowed(actor.clone(), permission.clone(), NO_RESOURCE)?,
+                Self::rule_holds(
+                    oso.query_rule("disallow", (actor.clone(), permission.clone(), NO_RESOURCE)),
+                    "disallow",
+                )?,
+                None,
+            ),
+        };
+        let actor_allow = Self::rule_holds(
+            oso.query_rule("actor_allow", (actor.clone(), permission.clone())),
+            "actor_allow",
+        )?;
+
+        let mut roles = vec![];
+        let query = oso
+            .query_rule(
+                "actor_has_role",
+                (actor.clone(), PolarValue::Variable("role".to_string())),
+            )
+            .map_err(|err| Error::custom(format!("Could not query policy rule 'actor_has_role': {}", err)))?;
+        for result in query {
+            let result = result
+                .map_err(|err| Error::custom(format!("Could not query policy rule 'actor_has_role': {}", err)))?;
+            if let Ok(role) = result.get_typed::<String>("role") {
+                if !roles.contains(&role) {
+                    roles.push(role);
+                }
+            }
+        }
+
+        let mut rules = vec![
+            PolicyRuleResult::new("disallow".to_string(), disallowed),
+            PolicyRuleResult::new("actor_allow".to_string(), actor_allow),
+        ];
+        if let Some(can_access_ca) = can_access_ca {
+            rules.push(PolicyRuleResult::new("actor_can_access_ca".to_string(), can_access_ca));
+        }
+
+        let mut granting_role = None;
+        for role in &roles {
+            let holds = Self::rule_holds(
+                oso.query_rule("role_allow", (role.clone(), permission.clone())),
+                "role_allow",
+            )?;
+            if holds && granting_role.is_none() {
+                granting_role = Some(role.clone());
+            }
+            rules.push(PolicyRuleResult::new(format!("role_allow(\"{}\")", role), holds));
+        }
+
+        let decided_by = if allowed {
+            if !actor_allow || can_access_ca == Some(false) || disallowed {
+                "a user-defined allow rule".to_string()
+            } else if let Some(role) = granting_role {
+                format!("role_allow: role '{}' grants {}", role, permission)
+            } else {
+                format!("actor_allow: the actor is granted {} by its attributes", permission)
+            }
+        } else if disallowed {
+            format!("disallow: a disallow rule denies {}", permission)
+        } else if !actor_allow {
+            format!("actor_allow: no role or attribute of the actor grants {}", permission)
+        } else if can_access_ca == Some(false) {
+            format!(
+                "actor_can_access_ca: the actor may not access CA '{}'",
+                ca.as_ref().map(|ca| ca.as_str()).unwrap_or_default()
+            )
+        } else {
+            "no allow rule matched".to_string()
+        };
+
+        Ok(PolicyExplanation::new(allowed, decided_by, roles, rules))
+    }
+
+    fn rule_holds(query: Result<oso::Query, oso::OsoError>, rule: &str) -> KrillResult<bool> {
+        let mut query =
+            query.map_err(|err| Error::custom(format!("Could not query policy rule '{}': {}", rule, err)))?;
+        match query.next() {
+            Some(Ok(_)) => Ok(true),
+            Some(Err(err)) => Err(Error::custom(format!(
+                "Could not query policy rule '{}': {}",
+                rule, err
+            ))),
+            None => Ok(false),
+        }
+    }
+
     fn load_internal_policy(oso: &mut Oso, bytes: &[u8], fname: &str) -> KrillResult<()> {
         trace!("Loading Polar policy '{}'", fname);
         oso.load_str(
@@ -119,7 +248,7 @@ impl AuthPolicy {
         Ok(())
     }
 
-    fn load_user_policy(config: Arc<Config>, oso: &mut Oso) -> KrillResult<()> {
+    fn load_user_policy(config: &Config, oso: &mut Oso) -> KrillResult<()> {
         for policy in config.auth_policies.iter() {
             info!("Loading user-defined authorization policy file {:?}", policy);
             let fname = policy.file_name().unwrap().to_str().unwrap();
@@ -211,6 +340,7 @@ impl PolarClass for Permission {
         Self::get_polar_class_builder()
             .set_constructor(|perm_name: String| -> Permission { Permission::from_str(&perm_name).unwrap() })
             .set_equality_check(|left: &Permission, right: &Permission| *left == *right)
+            .add_attribute_getter("name", |instance| instance.to_string())
             .build()
     }
 
@@ -218,3 +348,94 @@ impl PolarClass for Permission {
         oso::Class::builder()
     }
 }
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{collections::HashMap, path::Path};
+
+    use crate::test;
+
+    const DISALLOW_JOE: &str = r#"
+disallow(actor: Actor, action: Permission, _resource) if
+    actor.name = "joe" and
+    action = CA_READ;
+"#;
+
+    fn policy(d: &Path, user_policy: &str) -> AuthPolicy {
+        let path = d.join("custom.polar");
+        std::fs::write(&path, user_policy).unwrap();
+
+        let mut config = Config::test(d, false, false, false);
+        config.auth_policies = vec![path];
+        AuthPolicy::new(Arc::new(config)).unwrap()
+    }
+
+    fn actor(name: &str, attributes: &[(&str, &str)]) -> Actor {
+        let attributes: HashMap<String, String> =
+            attributes.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Actor::test_from_details(name.to_string(), attributes)
+    }
+
+    fn ca1() -> Handle {
+        Handle::from_str("ca1").unwrap()
+    }
+
+    #[test]
+    fn reload_keeps_current_policy_on_error() {
+        test::test_under_tmp(|d| {
+            let policy = policy(&d, DISALLOW_JOE);
+            let joe = actor("joe", &[("role", "readonly")]);
+            assert!(!policy.is_allowed(joe.clone(), Permission::CA_READ, ca1()).unwrap());
+
+            // an invalid policy file is rejected, and the current policy remains
+            std::fs::write(d.join("custom.polar"), "disallow(actor: Actor, action if").unwrap();
+            assert!(policy.reload().is_err());
+            assert!(!policy.is_allowed(joe.clone(), Permission::CA_READ, ca1()).unwrap());
+
+            // clones share the reloaded policy
+            let clone = policy.clone();
+            std::fs::write(d.join("custom.polar"), "# no user-defined rules").unwrap();
+            policy.reload().unwrap();
+            assert!(clone.is_allowed(joe, Permission::CA_READ, ca1()).unwrap());
+        })
+    }
+
+    #[test]
+    fn explain_decisions() {
+        test::test_under_tmp(|d| {
+            let policy = policy(&d, DISALLOW_JOE);
+
+            let admin = actor("alice", &[("role", "admin")]);
+            let explanation = policy.explain(admin, Permission::CA_UPDATE, Some(ca1())).unwrap();
+            assert!(explanation.allowed());
+            assert_eq!(explanation.decided_by(), "role_allow: role 'admin' grants CA_UPDATE");
+            assert_eq!(explanation.roles(), &vec!["admin".to_string()]);
+
+            let readonly = actor("bob", &[("role", "readonly")]);
+            let explanation = policy.explain(readonly, Permission::CA_UPDATE, Some(ca1())).unwrap();
+            assert!(!explanation.allowed());
+            assert!(explanation.decided_by().starts_with("actor_allow:"));
+
+            let joe = actor("joe", &[("role", "readonly")]);
+            let explanation = policy.explain(joe, Permission::CA_READ, Some(ca1())).unwrap();
+            assert!(!explanation.allowed());
+            assert!(explanation.decided_by().starts_with("disallow:"));
+            assert!(explanation
+                .rules()
+                .iter()
+                .any(|rule| rule.rule() == "disallow" && rule.holds()));
+
+            let limited = actor("carol", &[("role", "readonly"), ("inc_cas", "ca2")]);
+            let explanation = policy.explain(limited, Permission::CA_READ, Some(ca1())).unwrap();
+            assert!(!explanation.allowed());
+            assert_eq!(
+                explanation.decided_by(),
+                "actor_can_access_ca: the actor may not access CA 'ca1'"
+            );
+        })
+    }
+}
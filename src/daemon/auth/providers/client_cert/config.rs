@@ -0,0 +1,20 @@
+use std::{collections::HashMap, path::PathBuf};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigAuthClientCerts {
+    pub ca: PathBuf,
+
+    #[serde(default)]
+    pub required: bool,
+
+    #[serde(default)]
+    pub names: HashMap<String, ConfigClientCertDetails>,
+}
+
+/// The Krill user attributes for clients presenting a certificate with a
+/// given name.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigClientCertDetails {
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+}
@@ -0,0 +1,221 @@
+//! Authentication of API clients using the certificate they presented when
+//! setting up the TLS connection.
+//!
+//! The certificate itself is verified against the configured client CA
+//! certificates during the TLS handshake. Here we only map its names to the
+//! attributes configured for them: the DNS names, email addresses and URIs in
+//! the subject alternative name extension are tried in that order, followed
+//! by the common name of the subject. The first name that is configured
+//! determines the actor.
+
+use std::collections::HashMap;
+
+use openssl::{nid::Nid, x509::X509};
+
+use crate::{
+    commons::{actor::ActorDef, error::Error, KrillResult},
+    daemon::{
+        auth::providers::client_cert::config::{ConfigAuthClientCerts, ConfigClientCertDetails},
+        http::tls::ClientCertificate,
+    },
+};
+
+pub struct ClientCertAuthProvider {
+    required: bool,
+    names: HashMap<String, ConfigClientCertDetails>,
+}
+
+impl ClientCertAuthProvider {
+    pub fn new(config: &ConfigAuthClientCerts) -> Self {
+        ClientCertAuthProvider {
+            required: config.required,
+            names: config.names.clone(),
+        }
+    }
+
+    /// Returns an error if a client certificate is required, and the request
+    /// is for the API but its connection did not use a certificate. Other
+    /// requests, e.g. from RFC 6492 children, RFC 8181 publishers and RRDP
+    /// clients, never need a certificate.
+    pub fn check_required(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<()> {
+        if self.required
+            && request.uri().path().starts_with("/api/")
+            && request.extensions().get::<ClientCertificate>().is_none()
+        {
+            Err(Error::ApiInvalidCredentials(
+                "A client certificate is required to use the API".to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the actor for the client certificate used for the connection
+    /// of the request, if any and if one of its names is configured.
+    pub fn authenticate(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<Option<ActorDef>> {
+        let cert = match request.extensions().get::<ClientCertificate>() {
+            Some(cert) => cert,
+            None => return Ok(None),
+        };
+
+        let names = match Self::cert_names(cert) {
+            Some(names) => names,
+            None => {
+                warn!("Cannot parse verified client certificate");
+                return Ok(None);
+            }
+        };
+
+        match names.iter().find(|name| self.names.contains_key(name.as_str())) {
+            Some(name) => {
+                trace!("Client certificate matched configured name '{}'", name);
+                let details = &self.names[name.as_str()];
+                Ok(Some(ActorDef::user(name.clone(), details.attributes.clone(), None)))
+            }
+            None => {
+                debug!(
+                    "No configured name for client certificate with names: {}",
+                    names.join(", ")
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the names in the certificate in order of preference.
+    fn cert_names(cert: &ClientCertificate) -> Option<Vec<String>> {
+        let x509 = X509::from_der(cert.to_der()).ok()?;
+        let mut names = vec![];
+
+        if let Some(alt_names) = x509.subject_alt_names() {
+            names.extend(alt_names.iter().filter_map(|n| n.dnsname()).map(str::to_string));
+            names.extend(alt_names.iter().filter_map(|n| n.email()).map(str::to_string));
+            names.extend(alt_names.iter().filter_map(|n| n.uri()).map(str::to_string));
+        }
+
+        for entry in x509.subject_name().entries_by_nid(Nid::COMMONNAME) {
+            if let Ok(cn) = std::str::from_utf8(entry.data().as_slice()) {
+                names.push(cn.to_string());
+            }
+        }
+
+        Some(names)
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bytes::Bytes;
+    use openssl::{
+        asn1::Asn1Time,
+        hash::MessageDigest,
+        pkey::PKey,
+        rsa::Rsa,
+        x509::{extension::SubjectAlternativeName, X509NameBuilder},
+    };
+
+    use crate::daemon::auth::providers::client_cert::config::ConfigAuthClientCerts;
+
+    fn make_cert(cn: &str, dns: Option<&str>) -> ClientCertificate {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, cn).unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        if let Some(dns) = dns {
+            let san = SubjectAlternativeName::new()
+                .dns(dns)
+                .build(&builder.x509v3_context(None, None))
+                .unwrap();
+            builder.append_extension(san).unwrap();
+        }
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+
+        ClientCertificate::new(Bytes::from(builder.build().to_der().unwrap()))
+    }
+
+    fn request(cert: Option<ClientCertificate>) -> hyper::Request<hyper::Body> {
+        request_for("/api/v1/cas", cert)
+    }
+
+    fn request_for(path: &str, cert: Option<ClientCertificate>) -> hyper::Request<hyper::Body> {
+        let mut request = hyper::Request::builder().uri(path).body(hyper::Body::empty()).unwrap();
+        if let Some(cert) = cert {
+            request.extensions_mut().insert(cert);
+        }
+        request
+    }
+
+    fn provider() -> ClientCertAuthProvider {
+        provider_with_required(false)
+    }
+
+    fn provider_with_required(required: bool) -> ClientCertAuthProvider {
+        let config: ConfigAuthClientCerts = toml::from_str(&format!(
+            r#"
+            ca = "/tmp/ca.pem"
+            required = {}
+            [names]
+            "automation.example.com" = {{ attributes = {{ role = "admin" }} }}
+            "deploy" = {{ attributes = {{ role = "readwrite", inc_cas = "ca1" }} }}
+            "#,
+            required
+        ))
+        .unwrap();
+        ClientCertAuthProvider::new(&config)
+    }
+
+    #[test]
+    fn map_certificate_names() {
+        let provider = provider();
+
+        // the subject alternative name takes precedence over the common name
+        let cert = make_cert("deploy", Some("automation.example.com"));
+        let def = provider.authenticate(&request(Some(cert))).unwrap().unwrap();
+        assert_eq!(def.name.as_str(), "automation.example.com");
+        assert_eq!(def.attributes.as_map().get("role").unwrap(), "admin");
+
+        // fall back to the common name
+        let cert = make_cert("deploy", Some("other.example.com"));
+        let def = provider.authenticate(&request(Some(cert))).unwrap().unwrap();
+        assert_eq!(def.name.as_str(), "deploy");
+        assert_eq!(def.attributes.as_map().get("inc_cas").unwrap(), "ca1");
+
+        // unknown names and missing certificates do not authenticate
+        let cert = make_cert("unknown", None);
+        assert!(provider.authenticate(&request(Some(cert))).unwrap().is_none());
+        assert!(provider.authenticate(&request(None)).unwrap().is_none());
+    }
+
+    #[test]
+    fn require_certificate_for_api_only() {
+        let provider = provider_with_required(true);
+
+        let cert = make_cert("deploy", None);
+        assert!(provider.check_required(&request(Some(cert))).is_ok());
+        assert!(provider.check_required(&request(None)).is_err());
+
+        // RFC 6492 children, RFC 8181 publishers and RRDP clients do not
+        // present a client certificate
+        assert!(provider.check_required(&request_for("/rfc6492/ca", None)).is_ok());
+        assert!(provider.check_required(&request_for("/rfc8181/ca", None)).is_ok());
+        assert!(provider
+            .check_required(&request_for("/rrdp/notification.xml", None))
+            .is_ok());
+
+        let provider = provider_with_required(false);
+        assert!(provider.check_required(&request(None)).is_ok());
+    }
+}
@@ -13,3 +13,38 @@ pub struct ConfigUserDetails {
 
     pub salt: Option<String>,
 }
+
+/// The requirements for passwords of users that are added through the API.
+/// Users in the config file are not checked, as Krill only ever sees the hash
+/// of their password.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ConfigPasswordPolicy {
+    #[serde(default = "ConfigPasswordPolicy::default_min_length")]
+    pub min_length: usize,
+
+    #[serde(default)]
+    pub require_mixed_case: bool,
+
+    #[serde(default)]
+    pub require_digit: bool,
+
+    #[serde(default)]
+    pub require_symbol: bool,
+}
+
+impl ConfigPasswordPolicy {
+    fn default_min_length() -> usize {
+        12
+    }
+}
+
+impl Default for ConfigPasswordPolicy {
+    fn default() -> Self {
+        ConfigPasswordPolicy {
+            min_length: Self::default_min_length(),
+            require_mixed_case: false,
+            require_digit: false,
+            require_symbol: false,
+        }
+    }
+}
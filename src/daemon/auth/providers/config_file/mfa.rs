@@ -0,0 +1,370 @@
+//! TOTP (RFC 6238) second factor for users of the config file provider.
+//!
+//! A user enrols by generating a secret, which is shown once together with a
+//! set of recovery codes, and then confirms the enrolment with a code from an
+//! authenticator app. Only then is a code required at login. The secret has to
+//! be kept to verify codes, but only a SHA-256 hash of each recovery code is
+//! persisted. A recovery code can be used once, instead of a TOTP code.
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::RwLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use openssl::{hash::MessageDigest, memcmp, pkey::PKey, sign::Signer};
+
+use crate::{
+    commons::{
+        api::{MfaEnrolment, MfaStatus},
+        error::Error,
+        eventsourcing::{KeyStoreKey, KeyValueStore},
+        util::sha256,
+        KrillResult,
+    },
+    daemon::http::auth::url_encode,
+};
+
+const SECRET_BYTES: usize = 20;
+const TIME_STEP_SECS: u64 = 30;
+const CODE_DIGITS: u32 = 6;
+const ALLOWED_DRIFT_STEPS: u64 = 1;
+const RECOVERY_CODES: usize = 10;
+const RECOVERY_CODE_BYTES: usize = 5;
+const ISSUER: &str = "Krill";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct StoredMfa {
+    user: String,
+    secret: String,
+    confirmed: bool,
+    recovery_codes: Vec<String>,
+
+    // The last time step for which a code was accepted, so that a code cannot
+    // be replayed.
+    last_step: u64,
+}
+
+//------------ MfaStore ------------------------------------------------------
+
+pub struct MfaStore {
+    store: KeyValueStore,
+    users: RwLock<HashMap<String, StoredMfa>>,
+}
+
+impl MfaStore {
+    pub fn new(work_dir: &Path, namespace: &str) -> KrillResult<Self> {
+        let store = KeyValueStore::disk(work_dir, namespace)?;
+
+        let mut users = HashMap::new();
+        for key in store.keys(None, ".json")? {
+            if let Some(stored) = store.get::<StoredMfa>(&key)? {
+                users.insert(stored.user.clone(), stored);
+            }
+        }
+
+        Ok(MfaStore {
+            store,
+            users: RwLock::new(users),
+        })
+    }
+
+    /// Starts the enrolment of a user, replacing any earlier enrolment that
+    /// was not confirmed. A confirmed enrolment must be disabled first.
+    pub fn enrol(&self, user: &str) -> KrillResult<MfaEnrolment> {
+        let mut users = self.users.write().unwrap();
+        if users.get(user).map(|mfa| mfa.confirmed).unwrap_or(false) {
+            return Err(Error::UserInvalid(format!(
+                "user '{}' is already enrolled, disable the current second factor first",
+                user
+            )));
+        }
+
+        let secret = base32_encode(&random_bytes(SECRET_BYTES)?);
+        let recovery_codes = (0..RECOVERY_CODES)
+            .map(|_| {
+                let code = hex::encode(random_bytes(RECOVERY_CODE_BYTES)?);
+                Ok(format!("{}-{}", &code[..5], &code[5..]))
+            })
+            .collect::<KrillResult<Vec<String>>>()?;
+
+        let uri = format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&digits={}&period={}",
+            ISSUER,
+            url_encode(user)?,
+            secret,
+            ISSUER,
+            CODE_DIGITS,
+            TIME_STEP_SECS
+        );
+
+        let stored = StoredMfa {
+            user: user.to_string(),
+            secret: secret.clone(),
+            confirmed: false,
+            recovery_codes: recovery_codes
+                .iter()
+                .map(|code| Self::hash_recovery_code(code.as_str()))
+                .collect(),
+            last_step: 0,
+        };
+        self.store.store(&Self::key(user), &stored)?;
+        users.insert(user.to_string(), stored);
+
+        info!("User '{}' started TOTP enrolment", user);
+        Ok(MfaEnrolment::new(secret, uri, recovery_codes))
+    }
+
+    /// Confirms the enrolment of a user with a TOTP code, after which a code
+    /// is required at login.
+    pub fn confirm(&self, user: &str, code: &str) -> KrillResult<()> {
+        let mut users = self.users.write().unwrap();
+        let mfa = users
+            .get_mut(user)
+            .ok_or_else(|| Error::UserInvalid(format!("user '{}' has not started enrolment", user)))?;
+
+        let mut updated = mfa.clone();
+        if !Self::verify_totp(&mut updated, code, now_secs())? {
+            return Err(Error::UserInvalid("incorrect TOTP code".to_string()));
+        }
+        updated.confirmed = true;
+
+        self.store.store(&Self::key(user), &updated)?;
+        *mfa = updated;
+
+        info!("User '{}' enabled TOTP", user);
+        Ok(())
+    }
+
+    /// Removes the second factor of a user.
+    pub fn disable(&self, user: &str) -> KrillResult<()> {
+        let mut users = self.users.write().unwrap();
+        if users.remove(user).is_some() {
+            self.store.drop_key(&Self::key(user))?;
+            info!("TOTP disabled for user '{}'", user);
+        }
+        Ok(())
+    }
+
+    /// Returns whether a code is required for the user to login.
+    pub fn is_enabled(&self, user: &str) -> bool {
+        self.users
+            .read()
+            .unwrap()
+            .get(user)
+            .map(|mfa| mfa.confirmed)
+            .unwrap_or(false)
+    }
+
+    pub fn status(&self, user: &str) -> MfaStatus {
+        match self.users.read().unwrap().get(user) {
+            Some(mfa) if mfa.confirmed => MfaStatus::new(true, mfa.recovery_codes.len()),
+            _ => MfaStatus::new(false, 0),
+        }
+    }
+
+    /// Verifies a TOTP code or a recovery code for a user with a confirmed
+    /// enrolment. A recovery code is used up when it is accepted.
+    pub fn verify(&self, user: &str, code: &str) -> KrillResult<bool> {
+        self.verify_at(user, code, now_secs())
+    }
+
+    fn verify_at(&self, user: &str, code: &str, now: u64) -> KrillResult<bool> {
+        let mut users = self.users.write().unwrap();
+        let mfa = match users.get_mut(user) {
+            Some(mfa) if mfa.confirmed => mfa,
+            _ => return Ok(false),
+        };
+
+        let mut updated = mfa.clone();
+        let accepted = if code.len() == CODE_DIGITS as usize && code.chars().all(|c| c.is_ascii_digit()) {
+            Self::verify_totp(&mut updated, code, now)?
+        } else {
+            let hash = Self::hash_recovery_code(code);
+            let before = updated.recovery_codes.len();
+            updated.recovery_codes.retain(|c| c != &hash);
+            if updated.recovery_codes.len() != before {
+                warn!(
+                    "User '{}' used a recovery code, {} left",
+                    user,
+                    updated.recovery_codes.len()
+                );
+                true
+            } else {
+                false
+            }
+        };
+
+        if accepted {
+            self.store.store(&Self::key(user), &updated)?;
+            *mfa = updated;
+        }
+
+        Ok(accepted)
+    }
+
+    /// Checks the code against the time steps around now, allowing for some
+    /// clock drift, but never accepts a step that was already used.
+    fn verify_totp(mfa: &mut StoredMfa, code: &str, now: u64) -> KrillResult<bool> {
+        let secret = base32_decode(&mfa.secret)
+            .ok_or_else(|| Error::custom(format!("Invalid TOTP secret stored for user '{}'", mfa.user)))?;
+
+        let current = now / TIME_STEP_SECS;
+        for step in current.saturating_sub(ALLOWED_DRIFT_STEPS)..=current + ALLOWED_DRIFT_STEPS {
+            if step <= mfa.last_step {
+                continue;
+            }
+            let expected = totp_code(&secret, step)?;
+            if expected.len() == code.len() && memcmp::eq(expected.as_bytes(), code.as_bytes()) {
+                mfa.last_step = step;
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn hash_recovery_code(code: &str) -> String {
+        let normalized: String = code
+            .chars()
+            .filter(|c| *c != '-' && !c.is_whitespace())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        hex::encode(sha256(normalized.as_bytes()))
+    }
+
+    fn key(user: &str) -> KeyStoreKey {
+        KeyStoreKey::simple(format!("{}.json", user))
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn random_bytes(len: usize) -> KrillResult<Vec<u8>> {
+    let mut bytes = vec![0; len];
+    openssl::rand::rand_bytes(&mut bytes).map_err(|e| Error::custom(format!("Cannot create TOTP secret: {}", e)))?;
+    Ok(bytes)
+}
+
+/// Computes the TOTP code for a time step, as described in RFC 6238 and
+/// RFC 4226 (HOTP) using HMAC-SHA1, which is what authenticator apps use.
+fn totp_code(secret: &[u8], step: u64) -> KrillResult<String> {
+    let hmac = || -> Result<Vec<u8>, openssl::error::ErrorStack> {
+        let key = PKey::hmac(secret)?;
+        let mut signer = Signer::new(MessageDigest::sha1(), &key)?;
+        signer.update(&step.to_be_bytes())?;
+        signer.sign_to_vec()
+    };
+    let hash = hmac().map_err(|e| Error::custom(format!("Cannot compute TOTP code: {}", e)))?;
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = ((hash[offset] as u32 & 0x7f) << 24)
+        | ((hash[offset + 1] as u32) << 16)
+        | ((hash[offset + 2] as u32) << 8)
+        | (hash[offset + 3] as u32);
+
+    Ok(format!(
+        "{:0width$}",
+        binary % 10u32.pow(CODE_DIGITS),
+        width = CODE_DIGITS as usize
+    ))
+}
+
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Encodes as RFC 4648 base32 without padding, as expected in otpauth URIs.
+fn base32_encode(data: &[u8]) -> String {
+    let mut res = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in data {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            res.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        res.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    res
+}
+
+fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut res = vec![];
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in encoded.trim_end_matches('=').bytes() {
+        let value = BASE32_ALPHABET.iter().position(|a| *a == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            res.push((buffer >> bits) as u8);
+        }
+    }
+    Some(res)
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test;
+
+    #[test]
+    fn totp_matches_rfc6238_test_vectors() {
+        // RFC 6238 Appendix B uses 8 digit codes, we use the last 6 digits.
+        let secret = b"12345678901234567890";
+        assert_eq!(totp_code(secret, 59 / TIME_STEP_SECS).unwrap(), "287082");
+        assert_eq!(totp_code(secret, 1111111109 / TIME_STEP_SECS).unwrap(), "081804");
+        assert_eq!(totp_code(secret, 2000000000 / TIME_STEP_SECS).unwrap(), "279037");
+
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_decode("MZXW6YTBOI======").unwrap(), b"foobar");
+    }
+
+    #[test]
+    fn enrol_and_verify() {
+        test::test_under_tmp(|d| {
+            let store = MfaStore::new(&d, "mfa").unwrap();
+            let enrolment = store.enrol("alice@example.com").unwrap();
+            assert!(!store.is_enabled("alice@example.com"));
+
+            let secret = base32_decode(enrolment.secret()).unwrap();
+            let now = now_secs();
+            let code = totp_code(&secret, now / TIME_STEP_SECS).unwrap();
+            store.confirm("alice@example.com", &code).unwrap();
+            assert!(store.is_enabled("alice@example.com"));
+
+            // codes cannot be replayed, but the next one is accepted
+            assert!(!store.verify_at("alice@example.com", &code, now).unwrap());
+            let next = totp_code(&secret, now / TIME_STEP_SECS + 1).unwrap();
+            assert!(store.verify_at("alice@example.com", &next, now).unwrap());
+
+            // recovery codes can be used once
+            let recovery = &enrolment.recovery_codes()[0];
+            assert!(store.verify("alice@example.com", recovery).unwrap());
+            assert!(!store.verify("alice@example.com", recovery).unwrap());
+            assert_eq!(
+                store.status("alice@example.com").recovery_codes_left(),
+                RECOVERY_CODES - 1
+            );
+
+            // the state survives a restart
+            let store = MfaStore::new(&d, "mfa").unwrap();
+            assert!(store.enrol("alice@example.com").is_err());
+            store.disable("alice@example.com").unwrap();
+            assert!(!store.is_enabled("alice@example.com"));
+        })
+    }
+}
@@ -1,2 +1,4 @@
 pub mod config;
+pub mod mfa;
 pub mod provider;
+pub mod users;
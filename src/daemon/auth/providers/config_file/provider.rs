@@ -3,14 +3,20 @@ use std::{collections::HashMap, sync::Arc};
 use urlparse::{urlparse, GetQuery};
 
 use crate::{
-    commons::{actor::ActorDef, api::Token, error::Error, util::httpclient, KrillResult},
-    constants::{PW_HASH_LOG_N, PW_HASH_P, PW_HASH_R},
+    commons::{
+        actor::ActorDef,
+        api::{LocalUserInfo, LocalUserList, LocalUserRequest, MfaEnrolment, MfaStatus, Token},
+        error::Error,
+        util::httpclient,
+        KrillResult,
+    },
+    constants::{MFA_DIR, PW_HASH_LOG_N, PW_HASH_P, PW_HASH_R, USERS_DIR},
     daemon::{
         auth::common::{
             crypt::{self, CryptState},
             session::*,
         },
-        auth::providers::config_file::config::ConfigUserDetails,
+        auth::providers::config_file::{config::ConfigUserDetails, mfa::MfaStore, users::LocalUserStore},
         auth::{Auth, LoggedInUser},
         config::Config,
         http::HttpResponse,
@@ -24,6 +30,12 @@ use crate::{
 // Lagosta could change this path without requiring that we update to match.
 const LAGOSTA_LOGIN_ROUTE_PATH: &str = "/login?withId=true";
 const LOGIN_SESSION_STATE_KEY_PATH: &str = "login_session_state.key"; // TODO: decide on proper location
+const PASSWORD_CHANGE_REQUIRED_ATTR: &str = "password_change_required";
+const MFA_ATTR: &str = "mfa";
+
+// The TOTP or recovery code is given in a header rather than in the query
+// string, so that it does not end up in access logs.
+const MFA_CODE_HEADER: &str = "X-Krill-OTP";
 
 struct UserDetails {
     password_hash: Token,
@@ -51,8 +63,13 @@ fn get_checked_config_user(id: &str, user: &ConfigUserDetails) -> KrillResult<Us
     })
 }
 
+/// Authenticates users defined in the `[auth_users]` section of the config
+/// file, and local users that are managed through the API. Local users take
+/// precedence, users in the config file are a read-only fallback.
 pub struct ConfigFileAuthProvider {
     users: HashMap<String, UserDetails>,
+    local_users: LocalUserStore,
+    mfa: MfaStore,
     session_key: CryptState,
     session_cache: Arc<LoginSessionCache>,
     fake_password_hash: String,
@@ -61,25 +78,26 @@ pub struct ConfigFileAuthProvider {
 
 impl ConfigFileAuthProvider {
     pub fn new(config: Arc<Config>, session_cache: Arc<LoginSessionCache>) -> KrillResult<Self> {
-        match &config.auth_users {
-            Some(auth_users) => {
-                let mut users = HashMap::new();
-                for (k, v) in auth_users.iter() {
-                    users.insert(k.clone(), get_checked_config_user(k, v)?);
-                }
-
-                let session_key = Self::init_session_key(config.clone())?;
-
-                Ok(ConfigFileAuthProvider {
-                    users,
-                    session_key,
-                    session_cache,
-                    fake_password_hash: hex::encode("fake password hash"),
-                    fake_salt: hex::encode("fake salt"),
-                })
+        let mut users = HashMap::new();
+        if let Some(auth_users) = &config.auth_users {
+            for (k, v) in auth_users.iter() {
+                users.insert(k.clone(), get_checked_config_user(k, v)?);
             }
-            None => Err(Error::ConfigError("Missing [auth_users] config section!".into())),
         }
+
+        let local_users = LocalUserStore::new(&config.data_dir, USERS_DIR, config.auth_password_policy.clone())?;
+        let mfa = MfaStore::new(&config.data_dir, MFA_DIR)?;
+        let session_key = Self::init_session_key(config.clone())?;
+
+        Ok(ConfigFileAuthProvider {
+            users,
+            local_users,
+            mfa,
+            session_key,
+            session_cache,
+            fake_password_hash: hex::encode("fake password hash"),
+            fake_salt: hex::encode("fake salt"),
+        })
     }
 
     fn init_session_key(config: Arc<Config>) -> KrillResult<CryptState> {
@@ -98,6 +116,44 @@ impl ConfigFileAuthProvider {
         }
         None
     }
+
+    /// Returns the id of the user trying to login, if given.
+    pub fn login_id(&self, request: &hyper::Request<hyper::Body>) -> Option<String> {
+        match self.get_auth(request) {
+            Some(Auth::IdAndPasswordHash { id, .. }) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Returns the TOTP or recovery code given at login, if any.
+    fn get_mfa_code(request: &hyper::Request<hyper::Body>) -> Option<String> {
+        let code = request.headers().get(MFA_CODE_HEADER)?.to_str().ok()?.trim();
+        if code.is_empty() {
+            None
+        } else {
+            Some(code.to_string())
+        }
+    }
+
+    /// Checks the second factor of a user that has otherwise logged in
+    /// successfully, and returns whether one was required.
+    fn check_mfa(&self, id: &str, request: &hyper::Request<hyper::Body>) -> KrillResult<bool> {
+        if !self.mfa.is_enabled(id) {
+            return Ok(false);
+        }
+
+        match Self::get_mfa_code(request) {
+            None => Err(Error::ApiMfaRequired),
+            Some(code) => {
+                if self.mfa.verify(id, &code)? {
+                    Ok(true)
+                } else {
+                    trace!("Incorrect TOTP or recovery code for user {}", id);
+                    Err(Error::ApiInvalidCredentials("Incorrect credentials".to_string()))
+                }
+            }
+        }
+    }
 }
 
 impl ConfigFileAuthProvider {
@@ -114,7 +170,20 @@ impl ConfigFileAuthProvider {
 
                 trace!("id={}, attributes={:?}", &session.id, &session.attributes);
 
-                Ok(Some(ActorDef::user(session.id, session.attributes, None)))
+                // Local users may have been removed or disabled, or may have
+                // changed their password, since they logged in.
+                let mut attributes = session.attributes;
+                if let Some(user) = self.local_users.get(&session.id) {
+                    if user.info().disabled() {
+                        return Err(Error::ApiInvalidCredentials("User is disabled".to_string()));
+                    }
+                    Self::set_password_change_required(&mut attributes, user.info());
+                } else if !self.users.contains_key(&session.id) {
+                    return Err(Error::ApiInvalidCredentials("Unknown user".to_string()));
+                }
+                Self::set_mfa(&mut attributes, session.mfa);
+
+                Ok(Some(ActorDef::user(session.id, attributes, None)))
             }
             _ => Ok(None),
         };
@@ -137,9 +206,11 @@ impl ConfigFileAuthProvider {
 
             // Do NOT bail out if the user is not known because then the unknown user path would return very quickly
             // compared to the known user path and timing differences can aid attackers.
-            let (user_password_hash, user_salt) = match self.users.get(&id) {
-                Some(user) => (user.password_hash.to_string(), user.salt.clone()),
-                None => (self.fake_password_hash.clone(), self.fake_salt.clone()),
+            let local_user = self.local_users.get(&id);
+            let (user_password_hash, user_salt) = match (&local_user, self.users.get(&id)) {
+                (Some(user), _) => (user.password_hash().to_string(), user.salt().to_string()),
+                (None, Some(user)) => (user.password_hash.to_string(), user.salt.clone()),
+                (None, None) => (self.fake_password_hash.clone(), self.fake_salt.clone()),
             };
 
             // The password has already been hashed once with a weak salt (weak because it is known to the
@@ -163,15 +234,36 @@ impl ConfigFileAuthProvider {
                 // And now finally check the user, so that both known and unknown user code paths do the same work
                 // and don't result in an obvious timing difference between the two scenarios which could potentially
                 // be used to discover user names.
-                if let Some(user) = self.users.get(&id) {
-                    let api_token =
+                let attributes = match (local_user, self.users.get(&id)) {
+                    (Some(user), _) => {
+                        if user.info().disabled() {
+                            trace!("Disabled user {}", id);
+                            return Err(Error::ApiInvalidCredentials("User is disabled".to_string()));
+                        }
+                        let mut attributes = user.info().attributes().clone();
+                        Self::set_password_change_required(&mut attributes, user.info());
+                        Some(attributes)
+                    }
+                    (None, Some(user)) => Some(user.attributes.clone()),
+                    (None, None) => None,
+                };
+
+                if let Some(mut attributes) = attributes {
+                    let mfa = self.check_mfa(&id, request)?;
+                    Self::set_mfa(&mut attributes, mfa);
+
+                    let api_token = if mfa {
+                        self.session_cache
+                            .encode_with_mfa(&id, &attributes, &self.session_key, None)?
+                    } else {
                         self.session_cache
-                            .encode(&id, &user.attributes, HashMap::new(), &self.session_key, None)?;
+                            .encode(&id, &attributes, HashMap::new(), &self.session_key, None)?
+                    };
 
                     Ok(LoggedInUser {
                         token: api_token,
                         id: id.to_string(),
-                        attributes: user.attributes.clone(),
+                        attributes,
                     })
                 } else {
                     trace!("Incorrect password for user {}", id);
@@ -206,3 +298,148 @@ impl ConfigFileAuthProvider {
         Ok(HttpResponse::text_no_cache("/".into()))
     }
 }
+
+/// # Manage local users
+///
+impl ConfigFileAuthProvider {
+    /// Lists the local users, followed by the users in the config file that
+    /// are not overridden by a local user.
+    pub fn user_list(&self) -> LocalUserList {
+        let mut users = self.local_users.list();
+
+        let mut config_users: Vec<LocalUserInfo> = self
+            .users
+            .iter()
+            .filter(|(id, _)| self.local_users.get(id).is_none())
+            .map(|(id, user)| LocalUserInfo::config_file(id.clone(), user.attributes.clone()))
+            .collect();
+        config_users.sort_by(|a, b| a.id().cmp(b.id()));
+        users.append(&mut config_users);
+
+        LocalUserList::new(users)
+    }
+
+    pub fn user_add(&self, request: LocalUserRequest) -> KrillResult<LocalUserInfo> {
+        if self.users.contains_key(request.id()) {
+            return Err(Error::UserDuplicate(request.id().to_string()));
+        }
+        self.local_users.add(request)
+    }
+
+    pub fn user_remove(&self, id: &str) -> KrillResult<()> {
+        self.check_not_read_only(id)?;
+        self.local_users.remove(id)?;
+        self.mfa.disable(id)
+    }
+
+    pub fn user_set_disabled(&self, id: &str, disabled: bool) -> KrillResult<LocalUserInfo> {
+        self.check_not_read_only(id)?;
+        self.local_users.set_disabled(id, disabled)
+    }
+
+    pub fn user_reset_password(&self, id: &str, password: &str) -> KrillResult<()> {
+        self.check_not_read_only(id)?;
+        self.local_users.reset_password(id, password)
+    }
+
+    pub fn user_change_password(&self, id: &str, password: &str) -> KrillResult<()> {
+        self.check_not_read_only(id)?;
+        self.local_users.change_password(id, password)
+    }
+
+    fn check_not_read_only(&self, id: &str) -> KrillResult<()> {
+        if self.local_users.get(id).is_none() && self.users.contains_key(id) {
+            Err(Error::UserInvalid(format!(
+                "user '{}' is defined in the config file and cannot be changed through the API",
+                id
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn set_mfa(attributes: &mut HashMap<String, String>, mfa: bool) {
+        if mfa {
+            attributes.insert(MFA_ATTR.to_string(), "true".to_string());
+        } else {
+            attributes.remove(MFA_ATTR);
+        }
+    }
+
+    fn set_password_change_required(attributes: &mut HashMap<String, String>, user: &LocalUserInfo) {
+        if user.password_change_required() {
+            attributes.insert(PASSWORD_CHANGE_REQUIRED_ATTR.to_string(), "true".to_string());
+        } else {
+            attributes.remove(PASSWORD_CHANGE_REQUIRED_ATTR);
+        }
+    }
+}
+
+/// # Manage the second factor of users
+///
+impl ConfigFileAuthProvider {
+    pub fn mfa_status(&self, id: &str) -> KrillResult<MfaStatus> {
+        self.check_known_user(id)?;
+        Ok(self.mfa.status(id))
+    }
+
+    pub fn mfa_enrol(&self, id: &str) -> KrillResult<MfaEnrolment> {
+        self.check_known_user(id)?;
+        self.mfa.enrol(id)
+    }
+
+    pub fn mfa_confirm(&self, id: &str, code: &str) -> KrillResult<()> {
+        self.check_known_user(id)?;
+        self.mfa.confirm(id, code)
+    }
+
+    /// Disables the second factor of a user, who must prove to still have it
+    /// so that a stolen login session is not enough.
+    pub fn mfa_disable(&self, id: &str, code: &str) -> KrillResult<()> {
+        self.check_known_user(id)?;
+        if self.mfa.is_enabled(id) && !self.mfa.verify(id, code)? {
+            return Err(Error::UserInvalid("incorrect TOTP or recovery code".to_string()));
+        }
+        self.mfa.disable(id)
+    }
+
+    /// Removes the second factor of a user that lost it, on behalf of an
+    /// admin.
+    pub fn mfa_reset(&self, id: &str) -> KrillResult<()> {
+        self.check_known_user(id)?;
+        self.mfa.disable(id)
+    }
+
+    fn check_known_user(&self, id: &str) -> KrillResult<()> {
+        if self.local_users.get(id).is_some() || self.users.contains_key(id) {
+            Ok(())
+        } else {
+            Err(Error::UserUnknown(id.to_string()))
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn login_request(otp: Option<&str>) -> hyper::Request<hyper::Body> {
+        let mut builder = hyper::Request::post("https://localhost:3000/auth/login?id=joe&otp=123456");
+        if let Some(otp) = otp {
+            builder = builder.header(MFA_CODE_HEADER, otp);
+        }
+        builder.body(hyper::Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn mfa_code_from_header_only() {
+        assert_eq!(None, ConfigFileAuthProvider::get_mfa_code(&login_request(None)));
+        assert_eq!(None, ConfigFileAuthProvider::get_mfa_code(&login_request(Some(" "))));
+        assert_eq!(
+            Some("654321".to_string()),
+            ConfigFileAuthProvider::get_mfa_code(&login_request(Some("654321")))
+        );
+    }
+}
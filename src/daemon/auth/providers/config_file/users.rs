@@ -0,0 +1,333 @@
+//! Storage of local users that are managed through the API.
+//!
+//! The password of a local user is hashed in the same way as the password of
+//! a user in the config file (see `krillc config user`): first with a weak salt
+//! known to the client, and then again with a strong random salt that is only
+//! known to Krill. So users log in the same way, wherever they are defined.
+
+use std::{collections::HashMap, path::Path, sync::RwLock};
+
+use crate::{
+    commons::{
+        api::{LocalUserInfo, LocalUserRequest},
+        error::Error,
+        eventsourcing::{KeyStoreKey, KeyValueStore},
+        KrillResult,
+    },
+    constants::{PW_HASH_LOG_N, PW_HASH_P, PW_HASH_R},
+    daemon::auth::providers::config_file::config::ConfigPasswordPolicy,
+};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StoredLocalUser {
+    password_hash: String,
+    salt: String,
+    info: LocalUserInfo,
+}
+
+impl StoredLocalUser {
+    pub fn password_hash(&self) -> &str {
+        &self.password_hash
+    }
+
+    pub fn salt(&self) -> &str {
+        &self.salt
+    }
+
+    pub fn info(&self) -> &LocalUserInfo {
+        &self.info
+    }
+}
+
+//------------ LocalUserStore ------------------------------------------------
+
+pub struct LocalUserStore {
+    store: KeyValueStore,
+    users: RwLock<HashMap<String, StoredLocalUser>>,
+    policy: ConfigPasswordPolicy,
+}
+
+impl LocalUserStore {
+    pub fn new(work_dir: &Path, namespace: &str, policy: ConfigPasswordPolicy) -> KrillResult<Self> {
+        let store = KeyValueStore::disk(work_dir, namespace)?;
+
+        let mut users = HashMap::new();
+        for key in store.keys(None, ".json")? {
+            if let Some(stored) = store.get::<StoredLocalUser>(&key)? {
+                users.insert(stored.info.id().to_string(), stored);
+            }
+        }
+
+        Ok(LocalUserStore {
+            store,
+            users: RwLock::new(users),
+            policy,
+        })
+    }
+
+    /// Adds a user. The user must change the password at the next login.
+    pub fn add(&self, request: LocalUserRequest) -> KrillResult<LocalUserInfo> {
+        Self::validate_id(request.id())?;
+        self.check_policy(request.password())?;
+
+        let mut users = self.users.write().unwrap();
+        if users.contains_key(request.id()) {
+            return Err(Error::UserDuplicate(request.id().to_string()));
+        }
+
+        let (password_hash, salt) = hash_password(request.id(), request.password());
+        let stored = StoredLocalUser {
+            password_hash,
+            salt,
+            info: LocalUserInfo::new(request.id().to_string(), request.attributes().clone()),
+        };
+
+        self.store.store(&Self::key(request.id()), &stored)?;
+        users.insert(request.id().to_string(), stored.clone());
+
+        info!("User '{}' added", request.id());
+        Ok(stored.info)
+    }
+
+    pub fn remove(&self, id: &str) -> KrillResult<()> {
+        let mut users = self.users.write().unwrap();
+        if users.remove(id).is_none() {
+            return Err(Error::UserUnknown(id.to_string()));
+        }
+        self.store.drop_key(&Self::key(id))?;
+
+        info!("User '{}' removed", id);
+        Ok(())
+    }
+
+    /// Disables or enables a user. Disabled users can no longer log in, and
+    /// their existing login sessions are no longer accepted.
+    pub fn set_disabled(&self, id: &str, disabled: bool) -> KrillResult<LocalUserInfo> {
+        self.update(id, |user| {
+            user.info.set_disabled(disabled);
+            Ok(())
+        })?;
+
+        info!("User '{}' {}", id, if disabled { "disabled" } else { "enabled" });
+        self.get_info(id)
+    }
+
+    /// Sets a new password chosen by an admin. The user must change it at the
+    /// next login.
+    pub fn reset_password(&self, id: &str, password: &str) -> KrillResult<()> {
+        self.check_policy(password)?;
+        self.update(id, |user| {
+            let (password_hash, salt) = hash_password(id, password);
+            user.password_hash = password_hash;
+            user.salt = salt;
+            user.info.set_password_change_required(true);
+            Ok(())
+        })?;
+
+        info!("Password reset for user '{}'", id);
+        Ok(())
+    }
+
+    /// Sets a new password chosen by the user. This must differ from the
+    /// current password.
+    pub fn change_password(&self, id: &str, password: &str) -> KrillResult<()> {
+        self.check_policy(password)?;
+        self.update(id, |user| {
+            if hash_with_salt(&interim_hash(id, password), &hex::decode(&user.salt).unwrap()) == user.password_hash {
+                return Err(Error::UserInvalid(
+                    "the new password must differ from the current password".to_string(),
+                ));
+            }
+
+            let (password_hash, salt) = hash_password(id, password);
+            user.password_hash = password_hash;
+            user.salt = salt;
+            user.info.set_password_change_required(false);
+            Ok(())
+        })?;
+
+        info!("Password changed by user '{}'", id);
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<StoredLocalUser> {
+        self.users.read().unwrap().get(id).cloned()
+    }
+
+    pub fn get_info(&self, id: &str) -> KrillResult<LocalUserInfo> {
+        self.get(id)
+            .map(|user| user.info)
+            .ok_or_else(|| Error::UserUnknown(id.to_string()))
+    }
+
+    /// Lists all users, by id.
+    pub fn list(&self) -> Vec<LocalUserInfo> {
+        let users = self.users.read().unwrap();
+        let mut infos: Vec<LocalUserInfo> = users.values().map(|user| user.info.clone()).collect();
+        infos.sort_by(|a, b| a.id().cmp(b.id()));
+        infos
+    }
+
+    fn update<F>(&self, id: &str, op: F) -> KrillResult<()>
+    where
+        F: FnOnce(&mut StoredLocalUser) -> KrillResult<()>,
+    {
+        let mut users = self.users.write().unwrap();
+        let user = users.get_mut(id).ok_or_else(|| Error::UserUnknown(id.to_string()))?;
+
+        let mut updated = user.clone();
+        op(&mut updated)?;
+        self.store.store(&Self::key(id), &updated)?;
+        *user = updated;
+
+        Ok(())
+    }
+
+    fn check_policy(&self, password: &str) -> KrillResult<()> {
+        let policy = &self.policy;
+        let password = password.trim();
+
+        let mut problems = vec![];
+        if password.chars().count() < policy.min_length {
+            problems.push(format!("be at least {} characters long", policy.min_length));
+        }
+        if policy.require_mixed_case
+            && !(password.chars().any(char::is_lowercase) && password.chars().any(char::is_uppercase))
+        {
+            problems.push("contain both lower and upper case letters".to_string());
+        }
+        if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            problems.push("contain a digit".to_string());
+        }
+        if policy.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            problems.push("contain a symbol".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::UserInvalid(format!("the password must {}", problems.join(", "))))
+        }
+    }
+
+    fn validate_id(id: &str) -> KrillResult<()> {
+        if id.is_empty()
+            || id.len() > 255
+            || !id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | '+'))
+            || id.starts_with('.')
+        {
+            return Err(Error::UserInvalid(format!(
+                "id '{}' must be 1-255 characters of a-z, A-Z, 0-9, '-', '_', '.', '@' or '+'",
+                id
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn key(id: &str) -> KeyStoreKey {
+        KeyStoreKey::simple(format!("{}.json", id))
+    }
+}
+
+/// Returns the hex encoded hash and strong salt for the given password, as
+/// `krillc config user` does.
+fn hash_password(id: &str, password: &str) -> (String, String) {
+    let mut strong_salt: [u8; 32] = [0; 32];
+    openssl::rand::rand_bytes(&mut strong_salt).unwrap();
+
+    let password_hash = hash_with_salt(&interim_hash(id, password), &strong_salt);
+    (password_hash, hex::encode(strong_salt))
+}
+
+/// Hashes the password with the weak salt that the client browser knows how
+/// to construct. Lagosta does NFKC normalization, so we need to do the same.
+fn interim_hash(id: &str, password: &str) -> [u8; 32] {
+    use unicode_normalization::UnicodeNormalization;
+
+    let user_id = id.nfkc().collect::<String>();
+    let password = password.trim().nfkc().collect::<String>();
+    let weak_salt = format!("krill-lagosta-{}", user_id).nfkc().collect::<String>();
+
+    let params = scrypt::Params::new(PW_HASH_LOG_N, PW_HASH_R, PW_HASH_P).unwrap();
+    let mut interim_hash: [u8; 32] = [0; 32];
+    scrypt::scrypt(password.as_bytes(), weak_salt.as_bytes(), &params, &mut interim_hash).unwrap();
+    interim_hash
+}
+
+fn hash_with_salt(interim_hash: &[u8], strong_salt: &[u8]) -> String {
+    let params = scrypt::Params::new(PW_HASH_LOG_N, PW_HASH_R, PW_HASH_P).unwrap();
+    let mut final_hash: [u8; 32] = [0; 32];
+    scrypt::scrypt(interim_hash, strong_salt, &params, &mut final_hash).unwrap();
+    hex::encode(final_hash)
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test;
+
+    fn request(id: &str, password: &str) -> LocalUserRequest {
+        LocalUserRequest::new(id.to_string(), password.to_string(), HashMap::new())
+    }
+
+    #[test]
+    fn manage_local_users() {
+        test::test_under_tmp(|d| {
+            let policy = ConfigPasswordPolicy {
+                require_digit: true,
+                ..ConfigPasswordPolicy::default()
+            };
+            let store = LocalUserStore::new(&d, "users", policy.clone()).unwrap();
+
+            assert!(store.add(request("alice@example.com", "short1")).is_err());
+            assert!(store
+                .add(request("alice@example.com", "long enough but no digit"))
+                .is_err());
+            assert!(store.add(request("../alice", "correct horse battery 1")).is_err());
+
+            let info = store
+                .add(request("alice@example.com", "correct horse battery 1"))
+                .unwrap();
+            assert!(info.password_change_required());
+            assert!(store
+                .add(request("alice@example.com", "correct horse battery 2"))
+                .is_err());
+
+            // the stored hash matches what a client would send for the password
+            let stored = store.get("alice@example.com").unwrap();
+            let interim = interim_hash("alice@example.com", "correct horse battery 1");
+            assert_eq!(
+                hash_with_salt(&interim, &hex::decode(stored.salt()).unwrap()),
+                stored.password_hash()
+            );
+
+            assert!(store
+                .change_password("alice@example.com", "correct horse battery 1")
+                .is_err());
+            store
+                .change_password("alice@example.com", "correct horse battery 3")
+                .unwrap();
+            assert!(!store.get_info("alice@example.com").unwrap().password_change_required());
+
+            // the state survives a restart
+            store.set_disabled("alice@example.com", true).unwrap();
+            let store = LocalUserStore::new(&d, "users", policy).unwrap();
+            assert!(store.get_info("alice@example.com").unwrap().disabled());
+
+            store
+                .reset_password("alice@example.com", "correct horse battery 4")
+                .unwrap();
+            assert!(store.get_info("alice@example.com").unwrap().password_change_required());
+
+            store.remove("alice@example.com").unwrap();
+            assert!(store.remove("alice@example.com").is_err());
+            assert!(store.list().is_empty());
+        })
+    }
+}
@@ -0,0 +1,62 @@
+use crate::commons::api::Handle;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigAuthLdap {
+    pub url: String,
+
+    #[serde(default)]
+    pub starttls: bool,
+
+    #[serde(default)]
+    pub insecure: bool,
+
+    pub user_dn: String,
+
+    #[serde(default)]
+    pub user_search_base: Option<String>,
+
+    #[serde(default = "default_user_search_filter")]
+    pub user_search_filter: String,
+
+    #[serde(default)]
+    pub group_search_base: Option<String>,
+
+    #[serde(default = "default_group_search_filter")]
+    pub group_search_filter: String,
+
+    #[serde(default = "default_group_attribute")]
+    pub group_attribute: String,
+
+    #[serde(default)]
+    pub groups: Vec<ConfigAuthLdapGroup>,
+
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+pub fn default_user_search_filter() -> String {
+    "(uid={username})".to_string()
+}
+
+pub fn default_group_search_filter() -> String {
+    "(|(member={user_dn})(uniqueMember={user_dn})(memberUid={username}))".to_string()
+}
+
+pub fn default_group_attribute() -> String {
+    "cn".to_string()
+}
+
+fn default_timeout_seconds() -> u64 {
+    10
+}
+
+/// Maps membership of an LDAP group to Krill user attributes.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConfigAuthLdapGroup {
+    pub group: String,
+
+    pub role: String,
+
+    #[serde(default)]
+    pub cas: Vec<Handle>,
+}
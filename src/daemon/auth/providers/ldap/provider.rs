@@ -0,0 +1,432 @@
+//! Authentication of users against an LDAP directory, e.g. OpenLDAP or
+//! Microsoft Active Directory.
+//!
+//! Users are authenticated by binding to the directory as the user, using the
+//! given password. Their group memberships, found using the `memberOf`
+//! attribute of their entry and/or by searching for groups that list them as
+//! member, are then mapped to a role and the set of CAs they may access. The
+//! resulting attributes are kept in a login session, like is done by the
+//! [ConfigFileAuthProvider](crate::daemon::auth::providers::ConfigFileAuthProvider).
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use ldap3::{dn_escape, ldap_escape, Ldap, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
+
+use crate::{
+    commons::{actor::ActorDef, error::Error, util::httpclient, KrillResult},
+    daemon::{
+        auth::common::{
+            crypt::{self, CryptState},
+            session::*,
+        },
+        auth::providers::ldap::config::*,
+        auth::LoggedInUser,
+        config::Config,
+        http::HttpResponse,
+    },
+};
+
+// Lagosta hashes the password entered in its login form when an id is asked
+// for as well, so it cannot be used to bind. Instead we use the plain login
+// form and expect the user to enter "username:password". API clients can use
+// HTTP Basic authentication.
+const LAGOSTA_LOGIN_ROUTE_PATH: &str = "/login";
+const LOGIN_SESSION_STATE_KEY_PATH: &str = "login_session_state.key"; // TODO: decide on proper location
+
+// Result code for invalid credentials, see RFC 4511 Appendix A.1.
+const LDAP_RC_INVALID_CREDENTIALS: u32 = 49;
+
+pub struct LdapAuthProvider {
+    config: ConfigAuthLdap,
+    session_key: CryptState,
+    session_cache: Arc<LoginSessionCache>,
+}
+
+impl LdapAuthProvider {
+    pub fn new(config: Arc<Config>, session_cache: Arc<LoginSessionCache>) -> KrillResult<Self> {
+        match &config.auth_ldap {
+            Some(ldap_config) => {
+                if !ldap_config.user_dn.contains("{username}") {
+                    return Err(Error::ConfigError(
+                        "The [auth_ldap] user_dn setting must contain {username}".into(),
+                    ));
+                }
+
+                let session_key = Self::init_session_key(config.clone())?;
+
+                Ok(LdapAuthProvider {
+                    config: ldap_config.clone(),
+                    session_key,
+                    session_cache,
+                })
+            }
+            None => Err(Error::ConfigError("Missing [auth_ldap] config section!".into())),
+        }
+    }
+
+    fn init_session_key(config: Arc<Config>) -> KrillResult<CryptState> {
+        let key_path = config.data_dir.join(LOGIN_SESSION_STATE_KEY_PATH);
+        info!("Initializing login session encryption key {}", &key_path.display());
+        crypt::crypt_init(key_path.as_path())
+    }
+
+    /// Gets the username and password from either an HTTP Basic
+    /// authorization header, or a bearer token in the form
+    /// "username:password" as submitted by the Lagosta login form.
+    fn get_credentials(request: &hyper::Request<hyper::Body>) -> Option<(String, String)> {
+        let header = request.headers().get(hyper::header::AUTHORIZATION)?.to_str().ok()?;
+
+        let credentials = if let Some(basic) = header.strip_prefix("Basic ") {
+            String::from_utf8(base64::decode(basic.trim()).ok()?).ok()?
+        } else {
+            header.strip_prefix("Bearer ")?.trim().to_string()
+        };
+
+        let mut parts = credentials.splitn(2, ':');
+        let username = parts.next()?;
+        let password = parts.next()?;
+
+        // Never allow an empty password, as most LDAP servers treat a
+        // simple bind with an empty password as an anonymous bind which
+        // succeeds without checking anything.
+        if username.is_empty() || password.is_empty() {
+            None
+        } else {
+            Some((username.to_string(), password.to_string()))
+        }
+    }
+
+    /// Returns the username of the user trying to login, if given.
+    pub fn login_id(&self, request: &hyper::Request<hyper::Body>) -> Option<String> {
+        Self::get_credentials(request).map(|(username, _)| username)
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.config.timeout_seconds)
+    }
+
+    fn user_dn(&self, username: &str) -> String {
+        self.config.user_dn.replace("{username}", &dn_escape(username))
+    }
+
+    /// Binds as the user and returns the names of the groups it is a member
+    /// of.
+    async fn bind_and_get_groups(&self, username: &str, password: &str) -> KrillResult<Vec<String>> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(self.timeout())
+            .set_starttls(self.config.starttls)
+            .set_no_tls_verify(self.config.insecure);
+
+        let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &self.config.url)
+            .await
+            .map_err(|e| Error::ApiAuthTransientError(format!("LDAP: cannot connect to {}: {}", self.config.url, e)))?;
+        ldap3::drive!(conn);
+        ldap.with_timeout(self.timeout());
+
+        let bind_dn = self.user_dn(username);
+        let bind_res = ldap
+            .simple_bind(&bind_dn, password)
+            .await
+            .map_err(|e| Error::ApiAuthTransientError(format!("LDAP: bind failed: {}", e)))?;
+
+        let res = match bind_res.rc {
+            0 => self.get_groups(&mut ldap, username, &bind_dn).await,
+            LDAP_RC_INVALID_CREDENTIALS => {
+                trace!("LDAP: invalid credentials for {}", bind_dn);
+                Err(Error::ApiInvalidCredentials("Incorrect credentials".to_string()))
+            }
+            rc => Err(Error::ApiAuthPermanentError(format!(
+                "LDAP: bind failed with result code {}: {}",
+                rc, bind_res.text
+            ))),
+        };
+
+        let _ = ldap.unbind().await;
+
+        res
+    }
+
+    async fn get_groups(&self, ldap: &mut Ldap, username: &str, bind_dn: &str) -> KrillResult<Vec<String>> {
+        let search_err = |e: ldap3::LdapError| Error::ApiAuthPermanentError(format!("LDAP: search failed: {}", e));
+
+        // Find the user entry, either by search or by reading the bind DN
+        // directly, to learn its DN and any memberOf values.
+        let (base, scope, filter) = match &self.config.user_search_base {
+            Some(base) => (
+                base.as_str(),
+                Scope::Subtree,
+                self.config
+                    .user_search_filter
+                    .replace("{username}", &ldap_escape(username)),
+            ),
+            None => (bind_dn, Scope::Base, "(objectClass=*)".to_string()),
+        };
+
+        let (entries, _) = ldap
+            .search(base, scope, &filter, vec!["memberOf"])
+            .await
+            .and_then(|res| res.success())
+            .map_err(search_err)?;
+
+        let user =
+            entries.into_iter().next().map(SearchEntry::construct).ok_or_else(|| {
+                Error::ApiAuthPermanentError(format!("LDAP: cannot find user entry for {}", username))
+            })?;
+
+        let mut groups: Vec<String> = Self::attribute_values(&user, "memberOf")
+            .iter()
+            .map(|dn| Self::first_rdn_value(dn))
+            .collect();
+
+        if let Some(group_base) = &self.config.group_search_base {
+            let filter = self
+                .config
+                .group_search_filter
+                .replace("{user_dn}", &ldap_escape(user.dn.as_str()))
+                .replace("{username}", &ldap_escape(username));
+
+            let (entries, _) = ldap
+                .search(group_base, Scope::Subtree, &filter, vec![&self.config.group_attribute])
+                .await
+                .and_then(|res| res.success())
+                .map_err(search_err)?;
+
+            for entry in entries.into_iter().map(SearchEntry::construct) {
+                groups.extend(Self::attribute_values(&entry, &self.config.group_attribute));
+            }
+        }
+
+        trace!("LDAP: user {} is a member of groups: {:?}", username, groups);
+        Ok(groups)
+    }
+
+    fn attribute_values(entry: &SearchEntry, name: &str) -> Vec<String> {
+        entry
+            .attrs
+            .iter()
+            .filter(|(attr, _)| attr.eq_ignore_ascii_case(name))
+            .flat_map(|(_, values)| values.clone())
+            .collect()
+    }
+
+    /// Returns the value of the first RDN of a DN, e.g. "admins" for
+    /// "cn=admins,ou=groups,dc=example,dc=org".
+    fn first_rdn_value(dn: &str) -> String {
+        let rdn = dn.split(',').next().unwrap_or(dn);
+        match rdn.find('=') {
+            Some(pos) => rdn[pos + 1..].trim().to_string(),
+            None => rdn.trim().to_string(),
+        }
+    }
+}
+
+/// Determines the user attributes for the groups that a user is a member
+/// of. The first mapping in the configuration that matches any of the groups
+/// wins. Users who are not in any mapped group get no attributes at all, and
+/// are therefore not permitted to login by the default policy.
+fn attributes_for_groups(mappings: &[ConfigAuthLdapGroup], groups: &[String]) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+
+    if let Some(mapping) = mappings
+        .iter()
+        .find(|mapping| groups.iter().any(|group| group.eq_ignore_ascii_case(&mapping.group)))
+    {
+        attributes.insert("role".to_string(), mapping.role.clone());
+        if !mapping.cas.is_empty() {
+            let cas: Vec<&str> = mapping.cas.iter().map(|ca| ca.as_str()).collect();
+            attributes.insert("inc_cas".to_string(), cas.join(","));
+        }
+    }
+
+    attributes
+}
+
+impl LdapAuthProvider {
+    pub fn authenticate(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<Option<ActorDef>> {
+        if log_enabled!(log::Level::Trace) {
+            trace!("Attempting to authenticate the request..");
+        }
+
+        let res = match httpclient::get_bearer_token(request) {
+            Some(token) => {
+                // see if we can decode, decrypt and deserialize the users token
+                // into a login session structure
+                let session = self.session_cache.decode(token, &self.session_key, true)?;
+
+                trace!("id={}, attributes={:?}", &session.id, &session.attributes);
+
+                Ok(Some(ActorDef::user(session.id, session.attributes, None)))
+            }
+            _ => Ok(None),
+        };
+
+        if log_enabled!(log::Level::Trace) {
+            trace!("Authentication result: {:?}", res);
+        }
+
+        res
+    }
+
+    pub fn get_login_url(&self) -> KrillResult<HttpResponse> {
+        // Direct Lagosta to show the user the Lagosta API token login form
+        Ok(HttpResponse::text_no_cache(LAGOSTA_LOGIN_ROUTE_PATH.into()))
+    }
+
+    pub async fn login(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<LoggedInUser> {
+        match Self::get_credentials(request) {
+            Some((username, password)) => {
+                let groups = self.bind_and_get_groups(&username, &password).await?;
+                let attributes = attributes_for_groups(&self.config.groups, &groups);
+
+                let api_token =
+                    self.session_cache
+                        .encode(&username, &attributes, HashMap::new(), &self.session_key, None)?;
+
+                Ok(LoggedInUser {
+                    token: api_token,
+                    id: username,
+                    attributes,
+                })
+            }
+            None => {
+                trace!("Missing or incomplete credentials for login attempt");
+                Err(Error::ApiInvalidCredentials("Missing credentials".to_string()))
+            }
+        }
+    }
+
+    pub fn logout(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<HttpResponse> {
+        match httpclient::get_bearer_token(request) {
+            Some(token) => {
+                self.session_cache.remove(&token);
+
+                if let Ok(Some(actor)) = self.authenticate(request) {
+                    info!("User logged out: {}", actor.name.as_str());
+                }
+            }
+            _ => {
+                warn!("Unexpectedly received a logout request without a session token.");
+            }
+        }
+
+        // Logout is complete, direct Lagosta to show the user the Lagosta
+        // index page
+        Ok(HttpResponse::text_no_cache("/".into()))
+    }
+}
+
+//------------ Tests --------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use crate::commons::api::Handle;
+
+    fn request(authorization: &str) -> hyper::Request<hyper::Body> {
+        hyper::Request::builder()
+            .header(hyper::header::AUTHORIZATION, authorization)
+            .body(hyper::Body::empty())
+            .unwrap()
+    }
+
+    fn mapping(group: &str, role: &str, cas: &[&str]) -> ConfigAuthLdapGroup {
+        ConfigAuthLdapGroup {
+            group: group.to_string(),
+            role: role.to_string(),
+            cas: cas.iter().map(|ca| Handle::from_str(ca).unwrap()).collect(),
+        }
+    }
+
+    #[test]
+    fn get_credentials() {
+        let basic = format!("Basic {}", base64::encode("joe:secret:with:colons"));
+        assert_eq!(
+            LdapAuthProvider::get_credentials(&request(&basic)),
+            Some(("joe".to_string(), "secret:with:colons".to_string()))
+        );
+        assert_eq!(
+            LdapAuthProvider::get_credentials(&request("Bearer joe:secret")),
+            Some(("joe".to_string(), "secret".to_string()))
+        );
+
+        // an empty password would result in an unauthenticated bind
+        assert_eq!(LdapAuthProvider::get_credentials(&request("Bearer joe:")), None);
+        assert_eq!(LdapAuthProvider::get_credentials(&request("Bearer :secret")), None);
+        assert_eq!(LdapAuthProvider::get_credentials(&request("Bearer some-token")), None);
+    }
+
+    #[test]
+    fn first_rdn_value() {
+        assert_eq!(
+            LdapAuthProvider::first_rdn_value("CN=Krill Admins,OU=Groups,DC=example,DC=org"),
+            "Krill Admins"
+        );
+        assert_eq!(LdapAuthProvider::first_rdn_value("admins"), "admins");
+    }
+
+    #[test]
+    fn map_groups_to_attributes() {
+        let mappings = vec![
+            mapping("krill-admins", "admin", &[]),
+            mapping("ca1-operators", "readwrite", &["ca1", "ca2"]),
+            mapping("krill-readers", "readonly", &[]),
+        ];
+
+        let groups = |groups: &[&str]| groups.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+
+        let attrs = attributes_for_groups(&mappings, &groups(&["staff", "Krill-Admins", "ca1-operators"]));
+        assert_eq!(attrs.get("role").unwrap(), "admin");
+        assert!(attrs.get("inc_cas").is_none());
+
+        let attrs = attributes_for_groups(&mappings, &groups(&["krill-readers", "ca1-operators"]));
+        assert_eq!(attrs.get("role").unwrap(), "readwrite");
+        assert_eq!(attrs.get("inc_cas").unwrap(), "ca1,ca2");
+
+        assert!(attributes_for_groups(&mappings, &groups(&["staff"])).is_empty());
+    }
+
+    /// Requires a local OpenLDAP server, see test-resources/ldap/krill.ldif
+    #[tokio::test]
+    #[ignore]
+    async fn bind_against_openldap() {
+        let config = ConfigAuthLdap {
+            url: "ldap://localhost:1389".to_string(),
+            starttls: false,
+            insecure: false,
+            user_dn: "uid={username},ou=users,dc=example,dc=org".to_string(),
+            user_search_base: None,
+            user_search_filter: default_user_search_filter(),
+            group_search_base: Some("ou=groups,dc=example,dc=org".to_string()),
+            group_search_filter: default_group_search_filter(),
+            group_attribute: default_group_attribute(),
+            groups: vec![
+                mapping("krill-admins", "admin", &[]),
+                mapping("ca1-operators", "readwrite", &["ca1"]),
+            ],
+            timeout_seconds: 5,
+        };
+
+        let provider = LdapAuthProvider {
+            config,
+            session_key: CryptState::from_key_vec(vec![0; 32]).unwrap(),
+            session_cache: Arc::new(LoginSessionCache::new()),
+        };
+
+        let joe = provider.login(&request("Bearer joe:joe-secret")).await.unwrap();
+        assert_eq!(joe.attributes.get("role").unwrap(), "admin");
+
+        let basic = format!("Basic {}", base64::encode("jane:jane-secret"));
+        let jane = provider.login(&request(&basic)).await.unwrap();
+        assert_eq!(jane.attributes.get("role").unwrap(), "readwrite");
+        assert_eq!(jane.attributes.get("inc_cas").unwrap(), "ca1");
+
+        let jim = provider.login(&request("Bearer jim:jim-secret")).await.unwrap();
+        assert!(jim.attributes.is_empty());
+
+        assert!(provider.login(&request("Bearer joe:wrong")).await.is_err());
+        assert!(provider.login(&request("Bearer nobody:secret")).await.is_err());
+    }
+}
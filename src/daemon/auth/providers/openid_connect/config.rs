@@ -30,6 +30,9 @@ pub struct ConfigAuthOpenIDConnect {
 
     #[serde(default)]
     pub insecure: bool,
+
+    #[serde(default = "default_refresh_claims")]
+    pub refresh_claims: bool,
 }
 
 fn default_prompt_for_login() -> bool {
@@ -37,6 +40,12 @@ fn default_prompt_for_login() -> bool {
     true
 }
 
+fn default_refresh_claims() -> bool {
+    // On by default so that changes at the provider, e.g. removing a user from a group, take effect without waiting
+    // for the user to logout.
+    true
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct ConfigAuthOpenIDConnectClaim {
     pub source: Option<ConfigAuthOpenIDConnectClaimSource>,
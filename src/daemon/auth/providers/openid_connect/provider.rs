@@ -27,7 +27,10 @@ use std::{
     },
     ops::Deref,
     path::Path,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 use tokio::sync::{RwLock, RwLockReadGuard};
@@ -43,8 +46,8 @@ use openidconnect::{
         CoreResponseType, CoreRevocableToken,
     },
     AccessToken, AuthenticationFlow, AuthorizationCode, ClientId, ClientSecret, CsrfToken, IssuerUrl, Nonce,
-    OAuth2TokenResponse, RedirectUrl, RefreshToken, RequestTokenError, RevocationErrorResponseType, RevocationUrl,
-    Scope, UserInfoError,
+    NonceVerifier, OAuth2TokenResponse, RedirectUrl, RefreshToken, RequestTokenError, RevocationErrorResponseType,
+    RevocationUrl, Scope, UserInfoError,
 };
 
 use urlparse::{urlparse, GetQuery};
@@ -149,6 +152,7 @@ pub struct OpenIDConnectAuthProvider {
     session_cache: Arc<LoginSessionCache>,
     session_key: CryptState,
     conn: Arc<RwLock<Option<ProviderConnectionProperties>>>,
+    warned_no_refreshed_id_token: AtomicBool,
 }
 
 impl OpenIDConnectAuthProvider {
@@ -160,6 +164,7 @@ impl OpenIDConnectAuthProvider {
             session_cache,
             session_key,
             conn: Arc::new(RwLock::new(None)),
+            warned_no_refreshed_id_token: AtomicBool::new(false),
         })
     }
 
@@ -528,10 +533,14 @@ impl OpenIDConnectAuthProvider {
         }
     }
 
-    /// Try refreshing the token once with the OIDC Provider and return either the new token, or the Error received from
-    /// the OpenID Connect Provider. This Error is FOR INTERNAL CONSUMPTION only. The caller of this function is
-    /// responsible for creating end-user error messages, logging and (optionally) retrying.
-    async fn try_refresh_token(&self, session: &ClientSession) -> Result<Auth, CoreErrorResponseType> {
+    /// Try refreshing the token once with the OIDC Provider and return either the new token and the (possibly changed)
+    /// attributes of the user, or the Error received from the OpenID Connect Provider. This Error is FOR INTERNAL
+    /// CONSUMPTION only. The caller of this function is responsible for creating end-user error messages, logging and
+    /// (optionally) retrying.
+    async fn try_refresh_token(
+        &self,
+        session: &ClientSession,
+    ) -> Result<(Auth, HashMap<String, String>), CoreErrorResponseType> {
         let refresh_token = &session.secrets.get(TokenKind::RefreshToken.into()).ok_or_else(|| {
             CoreErrorResponseType::Extension(
                 "Internal error: Token refresh attempted without a refresh token".to_string(),
@@ -555,9 +564,17 @@ impl OpenIDConnectAuthProvider {
 
         match token_response {
             Ok(token_response) => {
+                // Release the connection, as re-evaluating the claims needs it again.
+                drop(lock_guard);
+
+                let attributes = self
+                    .refresh_attributes(session, &token_response)
+                    .await?
+                    .unwrap_or_else(|| session.attributes.clone());
+
                 let new_token_res = self.session_cache.encode(
                     &session.id,
-                    &session.attributes,
+                    &attributes,
                     secrets_from_token_response(&token_response),
                     &self.session_key,
                     token_response.expires_in(),
@@ -567,7 +584,7 @@ impl OpenIDConnectAuthProvider {
                     Ok(new_token) => {
                         // The new token was successfully acquired from the OpenID Connect Provider,
                         // and early returned.
-                        Ok(Auth::Bearer(new_token))
+                        Ok((Auth::Bearer(new_token), attributes))
                     }
                     Err(err) => Err(CoreErrorResponseType::Extension(format!(
                         "Internal error: Error while encoding the refreshed token {}",
@@ -613,6 +630,80 @@ impl OpenIDConnectAuthProvider {
         }
     }
 
+    /// Re-evaluate the claims of a user after refreshing their token, so that changes at the provider (e.g. removing
+    /// the user from a group) take effect without waiting for the user to logout. Returns None if the attributes from
+    /// login should be kept, because this is disabled or because the provider did not issue a new ID token.
+    ///
+    /// Providers are not required to issue a new ID token on refresh (OpenID Connect Core 1.0 section 12.2). We do
+    /// not fall back to the userinfo endpoint in that case, because the "id" claim, and any claims with an ID token
+    /// source, can then not be verified again. Instead the attributes from login are kept, and this is logged once.
+    async fn refresh_attributes(
+        &self,
+        session: &ClientSession,
+        token_response: &FlexibleTokenResponse,
+    ) -> Result<Option<HashMap<String, String>>, CoreErrorResponseType> {
+        fn claims_error(err: Error) -> CoreErrorResponseType {
+            CoreErrorResponseType::Extension(format!("Error while refreshing the claims: {}", err))
+        }
+
+        let oidc_conf = self.oidc_conf().map_err(claims_error)?;
+        if oidc_conf.refresh_claims
+            && token_response.extra_fields().id_token().is_none()
+            && !self.warned_no_refreshed_id_token.swap(true, Ordering::Relaxed)
+        {
+            warn!(
+                "OpenID Connect: The provider did not issue a new ID token when refreshing the token of user \"{}\". \
+                 Claims cannot be re-evaluated and the attributes from login are kept until users login again.",
+                &session.id
+            );
+        }
+        if !oidc_conf.refresh_claims || token_response.extra_fields().id_token().is_none() {
+            trace!(
+                "OpenID Connect: Keeping the attributes from login for user \"{}\"",
+                &session.id
+            );
+            return Ok(None);
+        }
+
+        // A refreshed ID token does not need to contain a nonce, and if it does then it is that of the original
+        // authentication request which we no longer have. See OpenID Connect Core 1.0 section 12.2.
+        let id_token_claims = self
+            .get_token_id_claims(token_response, |_: Option<&Nonce>| -> Result<(), String> { Ok(()) })
+            .await
+            .map_err(claims_error)?;
+        let user_info_claims = self.get_user_info_claims(token_response).await.map_err(claims_error)?;
+
+        let claims_conf = with_default_claims(&oidc_conf.claims);
+
+        // The refreshed claims must still be about the same user, otherwise the session is over.
+        if let Some(id_claim_conf) = claims_conf.get("id") {
+            let id = self
+                .extract_claim(id_claim_conf, id_token_claims, user_info_claims.as_ref())
+                .map_err(claims_error)?;
+            if id.as_deref() != Some(session.id.as_str()) {
+                warn!(
+                    "OpenID Connect: Refreshed claims are not for user \"{}\" but for {:?}",
+                    &session.id, id
+                );
+                return Err(CoreErrorResponseType::InvalidGrant);
+            }
+        }
+
+        let user = self.config.auth_users.as_ref().and_then(|users| users.get(&session.id));
+        let attributes = self
+            .resolve_claims(claims_conf, user, id_token_claims, user_info_claims, &session.id)
+            .map_err(claims_error)?;
+
+        if attributes != session.attributes {
+            info!(
+                "OpenID Connect: Attributes of user \"{}\" changed at the provider",
+                &session.id
+            );
+        }
+
+        Ok(Some(attributes))
+    }
+
     fn extract_claim(
         &self,
         claim_conf: &ConfigAuthOpenIDConnectClaim,
@@ -708,16 +799,8 @@ impl OpenIDConnectAuthProvider {
             })?;
             debug!("Search result in {:?}: '{:?}'", source, &result);
 
-            // Did the JMESPath search find a match?
-            if !matches!(*result, jmespath::Variable::Null) {
-                // Yes. Is it a JMESPath String type?
-                if let Some(result_str) = result.as_string() {
-                    // Yes. Is it non-empty after trimming leading and trailing whitespace?
-                    if !result_str.trim().is_empty() {
-                        // Yes
-                        return Ok(Some(result_str.clone()));
-                    }
-                }
+            if let Some(value) = claim_value(&result) {
+                return Ok(Some(value));
             }
         }
 
@@ -924,10 +1007,10 @@ impl OpenIDConnectAuthProvider {
     }
 
     #[allow(clippy::needless_lifetimes)] // clippy says it can be elided, but.. it seems like it's a false positive.
-    async fn get_token_id_claims<'a>(
+    async fn get_token_id_claims<'a, N: NonceVerifier>(
         &self,
         token_response: &'a FlexibleTokenResponse,
-        nonce_hash: Nonce,
+        nonce_verifier: N,
     ) -> KrillResult<&'a FlexibleIdTokenClaims> {
         let lock_guard = self.get_connection().await?;
         let conn = lock_guard.deref().as_ref().unwrap(); // safe to unwrap as was tested in get_connection()
@@ -950,7 +1033,7 @@ impl OpenIDConnectAuthProvider {
                     None,
                 )
             })? // happens if the server only supports OAuth2
-            .claims(&id_token_verifier, &nonce_hash)
+            .claims(&id_token_verifier, nonce_verifier)
             .map_err(|e| {
                 OpenIDConnectAuthProvider::internal_error(
                     format!("OpenID Connect: ID token verification failed: {}", e.to_string()),
@@ -1147,13 +1230,13 @@ impl OpenIDConnectAuthProvider {
                 }
 
                 // Token needs refresh and we have a refresh token, try to refresh
-                let new_auth = match self.try_refresh_token(&session).await {
-                    Ok(auth) => {
+                let (new_auth, attributes) = match self.try_refresh_token(&session).await {
+                    Ok(res) => {
                         trace!(
                             "OpenID Connect: Successfully refreshed token for user \"{}\"",
                             &session.id
                         );
-                        auth
+                        res
                     }
                     Err(err) => {
                         trace!("OpenID Connect: RFC 6749 5.2 Error response returned...");
@@ -1211,7 +1294,7 @@ impl OpenIDConnectAuthProvider {
                     }
                 };
 
-                Ok(Some(ActorDef::user(session.id, session.attributes, Some(new_auth))))
+                Ok(Some(ActorDef::user(session.id, attributes, Some(new_auth))))
             }
             _ => Ok(None),
         };
@@ -1509,7 +1592,7 @@ impl OpenIDConnectAuthProvider {
                 // https://openid.net/specs/openid-connect-core-1_0.html#NonceNotes
                 let nonce_hash = Nonce::new(base64::encode_config(sha256(nonce.as_bytes()), base64::URL_SAFE_NO_PAD));
 
-                let id_token_claims = self.get_token_id_claims(&token_response, nonce_hash).await?;
+                let id_token_claims = self.get_token_id_claims(&token_response, &nonce_hash).await?;
 
                 // TODO: There's also a suggestion to verify the access token
                 // received above using the at_hash claim in the ID token, if
@@ -1746,6 +1829,41 @@ fn with_default_claims(claims: &Option<ConfigAuthOpenIDConnectClaims>) -> Config
     claims
 }
 
+/// Returns the value of a claim found by a JMESPath search, if it is a non-empty
+/// string, or an array with non-empty string values.
+fn claim_value(result: &jmespath::Variable) -> Option<String> {
+    // Is it a JMESPath String type?
+    if let Some(result_str) = result.as_string() {
+        // Yes. Is it non-empty after trimming leading and trailing whitespace?
+        if !result_str.trim().is_empty() {
+            // Yes
+            return Some(result_str.clone());
+        }
+    }
+    // No. Is it a JMESPath Array type, e.g. a multi-valued groups claim?
+    else if let Some(result_array) = result.as_array() {
+        // Yes. Use the non-empty string values as a comma-separated value string, as is the convention for
+        // attributes like "inc_cas" in the policy. Values which contain a comma cannot be represented.
+        let values = result_array
+            .iter()
+            .filter_map(|value| value.as_string())
+            .map(|value| value.trim())
+            .filter(|value| {
+                if value.contains(',') {
+                    warn!("Skipping claim value '{}' as it contains a comma", value);
+                }
+                !value.is_empty() && !value.contains(',')
+            })
+            .collect::<Vec<&str>>();
+
+        if !values.is_empty() {
+            return Some(values.join(","));
+        }
+    }
+
+    None
+}
+
 // Based on: https://github.com/ramosbugs/openidconnect-rs/blob/main/examples/google.rs#L38
 pub fn stringify_cause_chain<F: std::error::Error>(fail: F) -> String {
     let mut cause_chain = String::new();
@@ -1759,3 +1877,34 @@ pub fn stringify_cause_chain<F: std::error::Error>(fail: F) -> String {
     }
     cause_chain
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(json_str: &str, expr: &str) -> Option<String> {
+        let runtime = jmespathext::init_runtime();
+        let claims = jmespath::Variable::from_json(json_str).unwrap();
+        let result = runtime.compile(expr).unwrap().search(&claims).unwrap();
+        claim_value(&result)
+    }
+
+    #[test]
+    fn claim_value_joins_multi_valued_claims() {
+        let json_str = r#"
+        {
+            "role": " admin ",
+            "empty": " ",
+            "groups": ["ca1", " ca2 ", "", "ca3,ca4", 42, "ca5"],
+            "no_groups": ["", "a,b"]
+        }
+        "#;
+
+        assert_eq!(Some(" admin ".to_string()), search(json_str, "role"));
+        assert_eq!(None, search(json_str, "empty"));
+        assert_eq!(None, search(json_str, "missing"));
+        assert_eq!(Some("ca1,ca2,ca5".to_string()), search(json_str, "groups"));
+        assert_eq!(None, search(json_str, "no_groups"));
+        assert_eq!(Some("ca1".to_string()), search(json_str, "groups[0]"));
+    }
+}
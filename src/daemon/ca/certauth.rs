@@ -5,10 +5,10 @@ use chrono::Duration;
 
 use rpki::{
     repository::{
-        cert::{Cert, KeyUsage, Overclaim, TbsCert},
+        cert::Cert,
         crypto::{KeyIdentifier, PublicKey},
         rta::RtaBuilder,
-        x509::{Serial, Time, Validity},
+        x509::{Time, Validity},
     },
     uri,
 };
@@ -17,26 +17,31 @@ use crate::{
     commons::{
         api::{
             self, AspaCustomer, AspaDefinitionList, AspaDefinitionUpdates, AspaProvidersUpdate, CertAuthInfo,
-            ChildHandle, EntitlementClass, Entitlements, Handle, IdCertPem, IssuanceRequest, IssuedCert, ObjectName,
-            ParentCaContact, ParentHandle, RcvdCert, RepoInfo, RepositoryContact, RequestResourceLimit,
-            ResourceClassName, ResourceSet, Revocation, RevocationRequest, RevocationResponse, RoaDefinition, RtaList,
-            RtaName, RtaPrepResponse, SigningCert, StorableCaCommand, TaCertDetails, TrustAnchorLocator,
+            ChildHandle, ChildRequestInfo, ChildRequestList, ChildResourceShrink, ChildSuspendReason, ChildSuspension,
+            ChildSuspensionPolicy, EntitlementClass, Entitlements, Handle, IdCertPem, IssuanceRequest, IssuedCert,
+            KeyRollPolicy, KeyRollStatus, ObjectName, ParentCaContact, ParentHandle, RcvdCert, RepoInfo,
+            RepositoryContact, RepositoryMirrorName, RequestResourceLimit, ResourceClassName, ResourceSet,
+            ResourceTemplate, ResourceTemplateList, ResourceTemplateName, Revocation, RevocationRequest,
+            RevocationResponse, RoaDefinition, RtaList, RtaName, RtaPrepResponse, SigningCert, StorableCaCommand,
+            TaCertDetails, TaKeyRoll, TaKeyRollStatus, Timestamp, Token, UndelegatedResources,
         },
-        crypto::{CsrInfo, IdCert, IdCertBuilder, KrillSigner, ProtocolCms, ProtocolCmsBuilder},
+        crypto::{CsrInfo, IdCert, IdCertBuilder, KrillSigner, ProtocolCms, ProtocolCmsBuilder, SignSupport},
         error::{Error, RoaDeltaError},
         eventsourcing::{Aggregate, StoredEvent},
         remote::{rfc6492, rfc8183},
         KrillResult,
     },
-    constants::test_mode_enabled,
+    constants::{test_mode_enabled, CA_SUSPEND_MIN_HOURS, CHILD_REQUESTS_HOURLY_MAX, CHILD_REQUESTS_MAX},
     daemon::{
         ca::{
-            events::ChildCertificateUpdates, ta_handle, AspaDefinitions, CaEvt, CaEvtDet, ChildDetails, Cmd, CmdDet,
-            DropReason, Ini, PreparedRta, ResourceClass, ResourceTaggedAttestation, RouteAuthorization,
-            RouteAuthorizationUpdates, Routes, RtaContentRequest, RtaPrepareRequest, Rtas, SignedRta,
+            events::ChildCertificateUpdates, ta_handle, AspaDefinitions, CaEvt, CaEvtDet, CertifiedKey, ChildDetails,
+            Cmd, CmdDet, DropReason, Ini, KeyRollStep, PendingChild, PreparedRta, ResourceClass,
+            ResourceTaggedAttestation, RouteAuthorization, RouteAuthorizationUpdates, Routes, RtaContentRequest,
+            RtaPrepareRequest, Rtas, SignedRta, TrustAnchorKeys,
         },
         config::{Config, IssuanceTimingConfig},
     },
+    ta,
 };
 
 //------------ Rfc8183Id ---------------------------------------------------
@@ -60,6 +65,10 @@ impl Rfc8183Id {
 }
 
 impl Rfc8183Id {
+    pub fn cert(&self) -> &IdCert {
+        &self.cert
+    }
+
     pub fn key_hash(&self) -> String {
         self.cert.ski_hex()
     }
@@ -94,12 +103,21 @@ pub struct CertAuth {
     id: Rfc8183Id, // Used for RFC 6492 (up-down) and RFC 8181 (publication)
 
     repository: Option<RepositoryContact>,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default = "HashMap::new")]
+    repository_mirrors: HashMap<RepositoryMirrorName, RepositoryContact>,
     parents: HashMap<ParentHandle, ParentCaContact>,
 
     next_class_name: u32,
     resources: HashMap<ResourceClassName, ResourceClass>,
 
     children: HashMap<ChildHandle, ChildDetails>,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty", default = "HashMap::new")]
+    child_requests: HashMap<ChildHandle, PendingChild>,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty", default = "HashMap::new")]
+    resource_templates: HashMap<ResourceTemplateName, ResourceSet>,
+
     routes: Routes,
 
     #[serde(skip_serializing_if = "Rtas::is_empty", default = "Rtas::default")]
@@ -111,6 +129,15 @@ pub struct CertAuth {
     )]
     aspas: AspaDefinitions,
 
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    keyroll_policy: Option<KeyRollPolicy>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    suspension_policy: Option<ChildSuspensionPolicy>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    ta_key_roll: Option<TaKeyRoll>,
+
     #[serde(skip_serializing, default = "CertAuthStatus::default")]
     status: CertAuthStatus,
 }
@@ -142,16 +169,22 @@ impl Aggregate for CertAuth {
             id,
 
             repository,
+            repository_mirrors: HashMap::new(),
             parents,
 
             next_class_name,
             resources,
 
             children,
+            child_requests: HashMap::new(),
+            resource_templates: HashMap::new(),
 
             routes,
             rtas,
             aspas,
+            keyroll_policy: None,
+            suspension_policy: None,
+            ta_key_roll: None,
             status: CertAuthStatus::Active,
         })
     }
@@ -173,6 +206,27 @@ impl Aggregate for CertAuth {
                 self.next_class_name += 1;
                 self.resources.insert(rcn.clone(), ResourceClass::for_ta(rcn, key_id));
             }
+            CaEvtDet::TrustAnchorKeyRollStarted { successor, started } => {
+                self.ta_key_roll = Some(TaKeyRoll::Staging {
+                    successor,
+                    since: started,
+                });
+            }
+            CaEvtDet::TrustAnchorKeyRollActivated { activated } => {
+                if let Some(TaKeyRoll::Staging { successor, .. }) = self.ta_key_roll.take() {
+                    if let Some(ParentCaContact::Ta(predecessor)) =
+                        self.parents.insert(ta_handle(), ParentCaContact::Ta(successor))
+                    {
+                        self.ta_key_roll = Some(TaKeyRoll::Activated {
+                            predecessor,
+                            since: activated,
+                        });
+                    }
+                }
+            }
+            CaEvtDet::TrustAnchorKeyRollFinished => {
+                self.ta_key_roll = None;
+            }
 
             //-----------------------------------------------------------------------
             // Being a parent
@@ -245,14 +299,56 @@ impl Aggregate for CertAuth {
                 self.children.get_mut(&child).unwrap().set_resources(resources)
             }
 
+            CaEvtDet::ChildShrinkNoticeSet { child, days } => {
+                self.children.get_mut(&child).unwrap().set_shrink_notice_days(days)
+            }
+            CaEvtDet::ChildShrinkScheduled { child, shrink } => {
+                self.children.get_mut(&child).unwrap().set_shrink(Some(shrink))
+            }
+            CaEvtDet::ChildShrinkCancelled { child } => self.children.get_mut(&child).unwrap().set_shrink(None),
+
             CaEvtDet::ChildRemoved { child } => {
                 self.children.remove(&child);
+                self.child_requests.remove(&child);
             }
 
-            CaEvtDet::ChildSuspended { child } => self.children.get_mut(&child).unwrap().suspend(),
+            CaEvtDet::ChildSuspended { child, suspension } => {
+                self.children.get_mut(&child).unwrap().suspend(suspension)
+            }
 
             CaEvtDet::ChildUnsuspended { child } => self.children.get_mut(&child).unwrap().unsuspend(),
 
+            CaEvtDet::SuspensionPolicyUpdated { policy } => self.suspension_policy = policy,
+
+            CaEvtDet::ChildSuspensionPolicyUpdated { child, policy } => {
+                self.children.get_mut(&child).unwrap().set_suspension_policy(policy)
+            }
+
+            CaEvtDet::ChildTemplateSet { child, template } => {
+                self.children.get_mut(&child).unwrap().set_template(template)
+            }
+
+            CaEvtDet::ResourceTemplateUpdated { name, resources } => {
+                self.resource_templates.insert(name, resources);
+            }
+            CaEvtDet::ResourceTemplateRemoved { name } => {
+                self.resource_templates.remove(&name);
+            }
+
+            CaEvtDet::ChildRequestSubmitted {
+                child,
+                id_cert,
+                token_hash,
+                submitted,
+            } => {
+                self.child_requests
+                    .insert(child, PendingChild::new(id_cert, token_hash, submitted));
+            }
+            CaEvtDet::ChildRequestApproved { child } => self.child_requests.get_mut(&child).unwrap().approve(),
+            CaEvtDet::ChildRequestRemoved { child } => {
+                self.child_requests.remove(&child);
+            }
+
             //-----------------------------------------------------------------------
             // Being a child
             //-----------------------------------------------------------------------
@@ -319,38 +415,42 @@ impl Aggregate for CertAuth {
             CaEvtDet::KeyRollPendingKeyAdded {
                 resource_class_name,
                 pending_key_id: pending_key,
+                changed,
             } => {
                 self.resources
                     .get_mut(&resource_class_name)
                     .unwrap()
-                    .pending_key_id_added(pending_key);
+                    .pending_key_id_added(pending_key, changed);
             }
             CaEvtDet::KeyPendingToNew {
                 resource_class_name,
                 new_key,
+                changed,
             } => {
                 self.resources
                     .get_mut(&resource_class_name)
                     .unwrap()
-                    .pending_key_to_new(new_key);
+                    .pending_key_to_new(new_key, changed);
             }
             CaEvtDet::KeyPendingToActive {
                 resource_class_name,
                 current_key,
+                changed,
             } => {
                 self.resources
                     .get_mut(&resource_class_name)
                     .unwrap()
-                    .pending_key_to_active(current_key);
+                    .pending_key_to_active(current_key, changed);
             }
             CaEvtDet::KeyRollActivated {
                 resource_class_name,
                 revoke_req,
+                changed,
             } => {
                 self.resources
                     .get_mut(&resource_class_name)
                     .unwrap()
-                    .new_key_activated(revoke_req);
+                    .new_key_activated(revoke_req, changed);
             }
             CaEvtDet::KeyRollFinished { resource_class_name } => {
                 self.resources.get_mut(&resource_class_name).unwrap().old_key_removed();
@@ -360,6 +460,9 @@ impl Aggregate for CertAuth {
                 // server side. The revocation requests are picked up by the `MessageQueue`
                 // listener.
             }
+            CaEvtDet::KeyRollPolicyUpdated { policy } => {
+                self.keyroll_policy = policy;
+            }
 
             //-----------------------------------------------------------------------
             // Route Authorizations
@@ -403,6 +506,20 @@ impl Aggregate for CertAuth {
                 }
                 self.repository = Some(contact);
             }
+            CaEvtDet::RepoMirrorAdded { name, contact } => {
+                self.repository_mirrors.insert(name, contact);
+            }
+            CaEvtDet::RepoMirrorRemoved { name } => {
+                self.repository_mirrors.remove(&name);
+            }
+
+            //-----------------------------------------------------------------------
+            // Rename
+            //-----------------------------------------------------------------------
+            CaEvtDet::Renamed { .. } => {
+                // Nothing to do, the handle was already updated when this CA was
+                // moved to its new handle.
+            }
 
             //-----------------------------------------------------------------------
             // Resource Tagged Attestations
@@ -437,8 +554,21 @@ impl Aggregate for CertAuth {
             CmdDet::ChildCertify(child, request, config, signer) => self.child_certify(child, request, &config, signer),
             CmdDet::ChildRevokeKey(child, request) => self.child_revoke_key(child, request),
             CmdDet::ChildRemove(child) => self.child_remove(&child),
-            CmdDet::ChildSuspendInactive(child) => self.child_suspend_inactive(&child),
+            CmdDet::ChildSuspendInactive(child, reason) => self.child_suspend_inactive(&child, reason),
             CmdDet::ChildUnsuspend(child) => self.child_unsuspend(&child),
+            CmdDet::SuspensionPolicyUpdate(policy) => self.suspension_policy_update(policy),
+            CmdDet::ChildSuspensionPolicyUpdate(child, policy) => self.child_suspension_policy_update(&child, policy),
+            CmdDet::ChildApplyTemplate(child, template) => self.child_apply_template(&child, template),
+            CmdDet::ChildShrinkNoticeSet(child, days) => self.child_shrink_notice_set(&child, days),
+            CmdDet::ChildShrinkEnforce(child) => self.child_shrink_enforce(&child),
+            CmdDet::ResourceTemplateUpdate(name, resources) => self.resource_template_update(name, resources),
+            CmdDet::ResourceTemplateRemove(name) => self.resource_template_remove(name),
+            CmdDet::ChildRequestSubmit(child, id_cert, token_hash) => {
+                self.child_request_submit(child, id_cert, token_hash)
+            }
+            CmdDet::ChildRequestApprove(child, resources) => self.child_request_approve(child, resources),
+            CmdDet::ChildRequestReject(child) => self.child_request_reject(child),
+            CmdDet::ChildRequestComplete(child, token) => self.child_request_complete(child, token),
 
             // being a child
             CmdDet::GenerateNewIdKey(signer) => self.generate_new_id_key(signer),
@@ -458,6 +588,10 @@ impl Aggregate for CertAuth {
             CmdDet::KeyRollInitiate(duration, signer) => self.keyroll_initiate(duration, signer),
             CmdDet::KeyRollActivate(duration, config, signer) => self.keyroll_activate(duration, config, signer),
             CmdDet::KeyRollFinish(rcn, response) => self.keyroll_finish(rcn, response),
+            CmdDet::KeyRollPolicyUpdate(policy) => self.keyroll_policy_update(policy),
+            CmdDet::TaKeyRollInitiate(signer) => self.ta_keyroll_initiate(signer.deref()),
+            CmdDet::TaKeyRollActivate(config, signer) => self.ta_keyroll_activate(&config, signer.deref()),
+            CmdDet::TaKeyRollFinish(config) => self.ta_keyroll_finish(&config),
 
             // Route Authorizations
             CmdDet::RouteAuthorizationsUpdate(updates, config, signer) => {
@@ -479,6 +613,11 @@ impl Aggregate for CertAuth {
 
             // Republish
             CmdDet::RepoUpdate(contact, signer) => self.update_repo(contact, &signer),
+            CmdDet::RepoMirrorAdd(name, contact) => self.repo_mirror_add(name, contact),
+            CmdDet::RepoMirrorRemove(name) => self.repo_mirror_remove(name),
+
+            // Rename
+            CmdDet::Renamed(previous) => self.renamed(previous),
 
             // Resource Tagged Attestations
             CmdDet::RtaMultiPrepare(name, request, signer) => self.rta_multi_prep(name, request, signer.deref()),
@@ -540,6 +679,8 @@ impl CertAuth {
             resources,
             children,
             suspended_children,
+            self.keyroll_policy.clone(),
+            self.suspension_policy.clone(),
         )
     }
 
@@ -584,6 +725,23 @@ impl CertAuth {
         }
         resources
     }
+
+    /// Returns the part of the given resources which is not held by this CA.
+    fn resources_not_held(&self, resources: &ResourceSet) -> ResourceSet {
+        resources.difference(&self.all_resources()).added().clone()
+    }
+
+    /// Returns the currently received resources, per parent.
+    pub fn resources_per_parent(&self) -> HashMap<ParentHandle, ResourceSet> {
+        let mut res: HashMap<ParentHandle, ResourceSet> = HashMap::new();
+        for rc in self.resources.values() {
+            if let Some(rc_resources) = rc.current_resources() {
+                let parent_resources = res.entry(rc.parent_handle().clone()).or_default();
+                *parent_resources = parent_resources.union(rc_resources);
+            }
+        }
+        res
+    }
 }
 
 /// # Publishing
@@ -615,50 +773,176 @@ impl CertAuth {
 
         let key = signer.create_key()?;
 
-        let resources = ResourceSet::all_resources();
+        let ta_cert_details = ta::make_ta_cert_details(&key, &repo_info, uris, rsync_uri, &signer)?;
 
-        let cert = {
-            let serial: Serial = signer.random_serial()?;
+        info!("Created Trust Anchor");
 
-            let pub_key = signer.get_key_info(&key).map_err(Error::signer)?;
-            let name = pub_key.to_subject_name();
+        Ok(vec![StoredEvent::new(
+            &self.handle,
+            self.version,
+            CaEvtDet::TrustAnchorMade { ta_cert_details },
+        )])
+    }
+
+    /// Returns the details of the current key and certificate of this Trust
+    /// Anchor. Returns an error if this CA is not a Trust Anchor.
+    pub fn ta_cert_details(&self) -> KrillResult<&TaCertDetails> {
+        match self.parents.get(&ta_handle()) {
+            Some(ParentCaContact::Ta(details)) => Ok(details),
+            _ => Err(Error::TaNotInitialized),
+        }
+    }
+
+    pub fn ta_key_roll(&self) -> Option<&TaKeyRoll> {
+        self.ta_key_roll.as_ref()
+    }
+
+    pub fn ta_keyroll_status(&self, transition_days: i64) -> KrillResult<TaKeyRollStatus> {
+        let current_key = self.ta_cert_details()?.cert().subject_key_identifier();
+        Ok(TaKeyRollStatus::new(
+            current_key,
+            self.ta_key_roll.clone(),
+            transition_days,
+        ))
+    }
+
+    /// Returns the content of the TAK objects which should be published under
+    /// each key of this Trust Anchor. This is empty unless a key roll is in
+    /// progress, in which case each key refers to the other.
+    pub fn trust_anchor_keys(&self) -> HashMap<KeyIdentifier, TrustAnchorKeys> {
+        let mut res = HashMap::new();
+
+        if let (Ok(current), Some(roll)) = (self.ta_cert_details(), self.ta_key_roll.as_ref()) {
+            let (old, new) = match roll {
+                TaKeyRoll::Staging { successor, .. } => (current, successor),
+                TaKeyRoll::Activated { predecessor, .. } => (predecessor, current),
+            };
 
-            let mut cert = TbsCert::new(
-                serial,
-                name.clone(),
-                Validity::new(Time::five_minutes_ago(), Time::years_from_now(100)),
-                Some(name),
-                pub_key.clone(),
-                KeyUsage::Ca,
-                Overclaim::Refuse,
+            res.insert(
+                old.cert().subject_key_identifier(),
+                TrustAnchorKeys::new(old.clone(), None, Some(new.clone())),
             );
+            res.insert(
+                new.cert().subject_key_identifier(),
+                TrustAnchorKeys::new(new.clone(), Some(old.clone()), None),
+            );
+        }
 
-            cert.set_basic_ca(Some(true));
+        res
+    }
 
-            let ns = ResourceClassName::default().to_string();
+    /// Creates a successor key and certificate, and stages the successor key
+    /// in the resource class of this Trust Anchor. The successor certificate
+    /// is published next to the current certificate, using the name of its
+    /// key.
+    fn ta_keyroll_initiate(&self, signer: &KrillSigner) -> KrillResult<Vec<CaEvt>> {
+        let current = self.ta_cert_details()?;
+        let (rcn, rc) = self.ta_resource_class()?;
 
-            cert.set_ca_repository(Some(repo_info.ca_repository(&ns)));
-            cert.set_rpki_manifest(Some(repo_info.rpki_manifest(&ns, &pub_key.key_identifier())));
-            cert.set_rpki_notify(Some(repo_info.rpki_notify()));
+        if self.ta_key_roll.is_some() || !rc.key_roll_possible() {
+            return Err(Error::TaKeyRollInProgress);
+        }
 
-            cert.set_as_resources(resources.to_as_resources());
-            cert.set_v4_resources(resources.to_ip_resources_v4());
-            cert.set_v6_resources(resources.to_ip_resources_v6());
+        let tal = current.tal();
+        let rsync_uri = tal
+            .rsync_uri()
+            .ok_or_else(|| Error::custom("Cannot roll the key of a Trust Anchor without an rsync URI in its TAL"))?;
 
-            signer.sign_cert(cert, &key)?
-        };
+        let key = signer.create_key()?;
+        let cert_name = ObjectName::new(&key, "cer");
 
-        let tal = TrustAnchorLocator::new(uris, rsync_uri, &cert);
+        let mut uris = vec![];
+        for uri in tal.uris() {
+            let uri = uri::Https::from_string(Self::ta_sibling_uri(uri.as_str(), &cert_name)).map_err(Error::custom)?;
+            uris.push(uri);
+        }
+        let rsync_uri =
+            uri::Rsync::from_string(Self::ta_sibling_uri(rsync_uri.as_str(), &cert_name)).map_err(Error::custom)?;
 
-        let ta_cert_details = TaCertDetails::new(cert, resources, tal);
+        let successor = ta::make_ta_cert_details(&key, self.repository_info()?, uris, Some(rsync_uri.clone()), signer)?;
+        let rcvd_cert = RcvdCert::new(successor.cert().clone(), rsync_uri, ResourceSet::all_resources());
 
-        info!("Created Trust Anchor");
+        info!("Started key roll for Trust Anchor, successor key: {}", key);
 
-        Ok(vec![StoredEvent::new(
-            &self.handle,
-            self.version,
-            CaEvtDet::TrustAnchorMade { ta_cert_details },
-        )])
+        Ok(self.events_from_details(vec![
+            CaEvtDet::KeyRollPendingKeyAdded {
+                resource_class_name: rcn.clone(),
+                pending_key_id: key,
+                changed: Some(Timestamp::now()),
+            },
+            CaEvtDet::KeyPendingToNew {
+                resource_class_name: rcn.clone(),
+                new_key: CertifiedKey::create(rcvd_cert),
+                changed: Some(Timestamp::now()),
+            },
+            CaEvtDet::TrustAnchorKeyRollStarted {
+                successor,
+                started: Timestamp::now(),
+            },
+        ]))
+    }
+
+    /// Makes the successor key the current key of this Trust Anchor, once the
+    /// transition period since the start of the key roll has passed. Child
+    /// certificates and other objects are re-issued under the new key.
+    fn ta_keyroll_activate(&self, config: &Config, signer: &KrillSigner) -> KrillResult<Vec<CaEvt>> {
+        self.ta_cert_details()?;
+        match &self.ta_key_roll {
+            Some(TaKeyRoll::Staging { since, .. }) => Self::ta_keyroll_transition_ended(*since, config)?,
+            _ => return Err(Error::TaKeyRollNotStaged),
+        }
+
+        let (_, rc) = self.ta_resource_class()?;
+        let mut details = rc.keyroll_activate(Duration::seconds(0), &config.issuance_timing, signer)?;
+        details.push(CaEvtDet::TrustAnchorKeyRollActivated {
+            activated: Timestamp::now(),
+        });
+
+        info!("Activated successor key for Trust Anchor");
+
+        Ok(self.events_from_details(details))
+    }
+
+    /// Retires the predecessor key of this Trust Anchor, once the transition
+    /// period since its activation has passed.
+    fn ta_keyroll_finish(&self, config: &Config) -> KrillResult<Vec<CaEvt>> {
+        self.ta_cert_details()?;
+        match &self.ta_key_roll {
+            Some(TaKeyRoll::Activated { since, .. }) => Self::ta_keyroll_transition_ended(*since, config)?,
+            _ => return Err(Error::TaKeyRollNotActivated),
+        }
+
+        let (_, rc) = self.ta_resource_class()?;
+        let details = vec![rc.keyroll_finish()?, CaEvtDet::TrustAnchorKeyRollFinished];
+
+        info!("Retired predecessor key for Trust Anchor");
+
+        Ok(self.events_from_details(details))
+    }
+
+    fn ta_keyroll_transition_ended(since: Timestamp, config: &Config) -> KrillResult<()> {
+        let ends = since + Duration::days(config.ta_key_roll_transition_days);
+        if ends > Timestamp::now() {
+            Err(Error::TaKeyRollTooEarly(ends.to_rfc3339()))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn ta_resource_class(&self) -> KrillResult<(&ResourceClassName, &ResourceClass)> {
+        self.resources
+            .iter()
+            .next()
+            .ok_or_else(|| Error::ResourceClassUnknown(ResourceClassName::default()))
+    }
+
+    // Returns the URI for an object with the given name in the same
+    // directory as the object that the given URI refers to.
+    fn ta_sibling_uri(uri: &str, name: &ObjectName) -> String {
+        match uri.rfind('/') {
+            Some(idx) => format!("{}{}", &uri[..=idx], name),
+            None => format!("{}{}", uri, name),
+        }
     }
 }
 
@@ -781,6 +1065,14 @@ impl CertAuth {
             }
         }
 
+        // If the child is about to lose resources in this class, then tell it that its certificate
+        // will not be valid beyond that time. Certificates issued to it will be limited accordingly.
+        if let Some(shrink_not_after) = child.shrink_not_after(&child_resources) {
+            if shrink_not_after < not_after {
+                not_after = shrink_not_after;
+            }
+        }
+
         Some(EntitlementClass::new(
             rcn.clone(),
             issuer,
@@ -803,13 +1095,205 @@ impl CertAuth {
         self.children.keys()
     }
 
+    /// Returns the resources of a resource template, or an error if it is unknown.
+    fn get_resource_template(&self, name: &ResourceTemplateName) -> KrillResult<&ResourceSet> {
+        self.resource_templates
+            .get(name)
+            .ok_or_else(|| Error::CaResourceTemplateUnknown(self.handle.clone(), name.clone()))
+    }
+
+    /// Returns all resource templates of this CA, sorted by name.
+    pub fn resource_templates(&self) -> ResourceTemplateList {
+        let mut templates: Vec<ResourceTemplate> = self
+            .resource_templates
+            .iter()
+            .map(|(name, resources)| ResourceTemplate::new(name.clone(), resources.clone()))
+            .collect();
+        templates.sort_by(|a, b| a.name().cmp(b.name()));
+        ResourceTemplateList::new(templates)
+    }
+
+    /// Returns the resources held by this CA which are not delegated to any child.
+    pub fn undelegated_resources(&self) -> UndelegatedResources {
+        let delegated = self.children.values().fold(ResourceSet::default(), |delegated, child| {
+            delegated.union(child.resources())
+        });
+        UndelegatedResources::new(self.all_resources(), delegated)
+    }
+
+    /// Adds or updates a resource template. The resources of all children which
+    /// use the template are updated as well.
+    fn resource_template_update(&self, name: ResourceTemplateName, resources: ResourceSet) -> KrillResult<Vec<CaEvt>> {
+        let not_held = self.resources_not_held(&resources);
+        if !not_held.is_empty() {
+            return Err(Error::CaResourceTemplateExtraResources(
+                self.handle.clone(),
+                name,
+                not_held,
+            ));
+        }
+
+        if self.resource_templates.get(&name) == Some(&resources) {
+            debug!("CA '{}' update resource template '{}' has no effect", self.handle, name);
+            return Ok(vec![]);
+        }
+
+        info!(
+            "CA '{}' updated resource template '{}' to: {}",
+            self.handle, name, resources
+        );
+
+        let mut res = vec![];
+        for (child_handle, child) in self.children.iter() {
+            if child.template() == Some(&name) {
+                res.append(&mut self.child_resources_events(child_handle, child, resources.clone()));
+            }
+        }
+        res.insert(0, CaEvtDet::ResourceTemplateUpdated { name, resources });
+
+        Ok(self.events_from_details(res))
+    }
+
+    /// Removes a resource template, unless it is still used by any child.
+    fn resource_template_remove(&self, name: ResourceTemplateName) -> KrillResult<Vec<CaEvt>> {
+        self.get_resource_template(&name)?;
+
+        if self.children.values().any(|child| child.template() == Some(&name)) {
+            Err(Error::CaResourceTemplateInUse(self.handle.clone(), name))
+        } else {
+            info!("CA '{}' removed resource template '{}'", self.handle, name);
+            Ok(self.events_from_details(vec![CaEvtDet::ResourceTemplateRemoved { name }]))
+        }
+    }
+
+    /// Returns all pending, or approved but not yet completed, child requests.
+    /// Expired requests are left out.
+    pub fn child_requests(&self) -> ChildRequestList {
+        let mut requests: Vec<ChildRequestInfo> = self
+            .child_requests
+            .iter()
+            .filter(|(_, pending)| !pending.is_expired())
+            .map(|(child, pending)| pending.as_info(child.clone()))
+            .collect();
+        requests.sort_by_key(|request| request.submitted());
+        ChildRequestList::new(requests)
+    }
+
+    /// Returns a pending child request, or an error if there is none or
+    /// if it has expired.
+    pub fn get_child_request(&self, child: &ChildHandle) -> KrillResult<&PendingChild> {
+        self.child_requests
+            .get(child)
+            .filter(|pending| !pending.is_expired())
+            .ok_or_else(|| Error::CaChildRequestUnknown(self.handle.clone(), child.clone()))
+    }
+
+    /// Records a request by a prospective child. Returns an error if the
+    /// child already exists, if there is already a request for it, if there
+    /// are too many outstanding requests, or if too many requests were
+    /// submitted in the last hour.
+    ///
+    /// Expired requests are removed here, so that they no longer count
+    /// towards the limits, and a child can submit its request again.
+    fn child_request_submit(&self, child: ChildHandle, id_cert: IdCert, token_hash: String) -> KrillResult<Vec<CaEvt>> {
+        let now = Timestamp::now();
+        let hour_ago = now.minus_hours(1);
+
+        let expired: Vec<ChildHandle> = self
+            .child_requests
+            .iter()
+            .filter(|(_, pending)| pending.is_expired())
+            .map(|(child, _)| child.clone())
+            .collect();
+
+        let pending = self.child_requests.values().filter(|pending| !pending.is_expired());
+        let outstanding = pending.clone().filter(|pending| !pending.is_approved()).count();
+        let recent = pending.filter(|pending| pending.submitted() > hour_ago).count();
+
+        if self.has_child(&child) {
+            Err(Error::CaChildDuplicate(self.handle.clone(), child))
+        } else if self.get_child_request(&child).is_ok() {
+            Err(Error::CaChildRequestDuplicate(self.handle.clone(), child))
+        } else if outstanding >= CHILD_REQUESTS_MAX {
+            Err(Error::CaChildRequestsExceeded(self.handle.clone()))
+        } else if recent >= CHILD_REQUESTS_HOURLY_MAX {
+            Err(Error::CaChildRequestsThrottled(self.handle.clone()))
+        } else {
+            let mut details: Vec<CaEvtDet> = expired
+                .into_iter()
+                .map(|child| {
+                    info!("CA '{}' removed expired request for child '{}'", self.handle, child);
+                    CaEvtDet::ChildRequestRemoved { child }
+                })
+                .collect();
+
+            info!("CA '{}' received request for child '{}'", self.handle, child);
+            details.push(CaEvtDet::ChildRequestSubmitted {
+                child,
+                id_cert,
+                token_hash,
+                submitted: now,
+            });
+            Ok(self.events_from_details(details))
+        }
+    }
+
+    /// Approves a pending child request, and adds the child with the
+    /// given resources.
+    fn child_request_approve(&self, child: ChildHandle, resources: ResourceSet) -> KrillResult<Vec<CaEvt>> {
+        let pending = self.get_child_request(&child)?;
+
+        // Use the normal checks for adding a child, this also prevents that
+        // an already approved request is approved again.
+        self.child_add(child.clone(), pending.id_cert().clone(), resources.clone())?;
+
+        info!("CA '{}' approved request for child '{}'", self.handle, child);
+        Ok(self.events_from_details(vec![
+            CaEvtDet::ChildAdded {
+                child: child.clone(),
+                id_cert: pending.id_cert().clone(),
+                resources,
+            },
+            CaEvtDet::ChildRequestApproved { child },
+        ]))
+    }
+
+    /// Rejects a pending child request. Requests which were already approved
+    /// cannot be rejected, the child should be removed instead.
+    fn child_request_reject(&self, child: ChildHandle) -> KrillResult<Vec<CaEvt>> {
+        let pending = self.get_child_request(&child)?;
+        if pending.is_approved() {
+            return Err(Error::CaChildDuplicate(self.handle.clone(), child));
+        }
+
+        info!("CA '{}' rejected request for child '{}'", self.handle, child);
+        Ok(self.events_from_details(vec![CaEvtDet::ChildRequestRemoved { child }]))
+    }
+
+    /// Completes an approved child request when the child retrieves its
+    /// parent response, so that the one-time token can no longer be used.
+    fn child_request_complete(&self, child: ChildHandle, token: Token) -> KrillResult<Vec<CaEvt>> {
+        // Do not reveal whether there is a request for this child if the token is wrong.
+        let pending = self.get_child_request(&child)?;
+        if !pending.matches_token(&token) {
+            Err(Error::CaChildRequestUnknown(self.handle.clone(), child))
+        } else if !pending.is_approved() {
+            Err(Error::CaChildRequestNotApproved(self.handle.clone(), child))
+        } else {
+            info!("CA '{}' handed out parent response to child '{}'", self.handle, child);
+            Ok(self.events_from_details(vec![CaEvtDet::ChildRequestRemoved { child }]))
+        }
+    }
+
     /// Adds the child, returns an error if the child is a duplicate,
     /// or if the resources are empty, or not held by this CA.
     fn child_add(&self, child: ChildHandle, id_cert: IdCert, resources: ResourceSet) -> KrillResult<Vec<CaEvt>> {
+        let not_held = self.resources_not_held(&resources);
+
         if resources.is_empty() {
             Err(Error::CaChildMustHaveResources(self.handle.clone(), child))
-        } else if !self.all_resources().contains(&resources) {
-            Err(Error::CaChildExtraResources(self.handle.clone(), child))
+        } else if !not_held.is_empty() {
+            Err(Error::CaChildExtraResources(self.handle.clone(), child, not_held))
         } else if self.has_child(&child) {
             Err(Error::CaChildDuplicate(self.handle.clone(), child))
         } else {
@@ -890,7 +1374,16 @@ impl CertAuth {
         let child = self.get_child(child)?;
         child.resources().apply_limit(&limit)?;
 
-        my_rc.issue_cert(csr_info, child.resources(), limit, issuance_timing, signer)
+        let mut validity = SignSupport::sign_validity_weeks(issuance_timing.timing_child_certificate_valid_weeks);
+        if let Some(rc_resources) = my_rc.current_resources() {
+            if let Some(shrink_not_after) = child.shrink_not_after(&rc_resources.intersection(child.resources())) {
+                if shrink_not_after < validity.not_after() {
+                    validity = Validity::new(validity.not_before(), shrink_not_after);
+                }
+            }
+        }
+
+        my_rc.issue_cert(csr_info, child.resources(), limit, validity, signer)
     }
 
     /// Updates child Resource entitlements.
@@ -898,36 +1391,210 @@ impl CertAuth {
     /// This does not yet revoke / reissue / republish anything.
     /// Also, this is a no-op if the child already has these resources.
     fn child_update_resources(&self, child_handle: &Handle, resources: ResourceSet) -> KrillResult<Vec<CaEvt>> {
-        if !self.all_resources().contains(&resources) {
-            Err(Error::CaChildExtraResources(self.handle.clone(), child_handle.clone()))
+        let not_held = self.resources_not_held(&resources);
+
+        if !not_held.is_empty() {
+            Err(Error::CaChildExtraResources(
+                self.handle.clone(),
+                child_handle.clone(),
+                not_held,
+            ))
         } else {
             let child = self.get_child(child_handle)?;
 
-            let resources_diff = resources.difference(child.resources());
+            let mut res = vec![];
+
+            // Explicitly set resources replace the resource template used for the child, if any.
+            if child.template().is_some() {
+                res.push(CaEvtDet::ChildTemplateSet {
+                    child: child_handle.clone(),
+                    template: None,
+                });
+            }
+
+            res.append(&mut self.child_resources_events(child_handle, child, resources));
+
+            Ok(self.events_from_details(res))
+        }
+    }
+
+    /// Returns the events needed to set the resources of a child.
+    ///
+    /// If the child has a notice period and would lose resources, then their removal
+    /// is scheduled instead. The child remains entitled to its current resources, and
+    /// any added resources, until the scheduled removal is enforced.
+    fn child_resources_events(
+        &self,
+        child_handle: &ChildHandle,
+        child: &ChildDetails,
+        resources: ResourceSet,
+    ) -> Vec<CaEvtDet> {
+        let mut res = vec![];
+
+        let removed = child.resources().difference(&resources).added().clone();
+
+        match child.shrink_notice_days() {
+            Some(days) if !removed.is_empty() => {
+                if child.shrink().map(|shrink| shrink.resources()) == Some(&resources) {
+                    debug!(
+                        "CA '{}' removal of resources from child '{}' was already scheduled",
+                        self.handle, child_handle
+                    );
+                    return res;
+                }
+
+                let entitled = child.resources().union(&resources);
+                if &entitled != child.resources() {
+                    res.push(CaEvtDet::ChildUpdatedResources {
+                        child: child_handle.clone(),
+                        resources: entitled,
+                    });
+                }
+
+                // Do not extend the notice period for resources which the child was already told about.
+                let effective = match child.shrink() {
+                    Some(shrink) if shrink.removed().contains(&removed) => shrink.effective(),
+                    _ => Timestamp::now() + Duration::days(days.into()),
+                };
 
-            if !resources_diff.is_empty() {
                 info!(
-                    "CA '{}' update child '{}' resources: {}",
-                    self.handle, child_handle, resources_diff
+                    "CA '{}' will remove resources from child '{}' at {}: {}",
+                    self.handle,
+                    child_handle,
+                    effective.to_rfc3339(),
+                    removed
                 );
 
-                Ok(vec![CaEvtDet::child_updated_resources(
-                    &self.handle,
-                    self.version,
-                    child_handle.clone(),
-                    resources,
-                )])
-            } else {
-                // Using 'debug' here, because there are possible use cases where updating the child resources to some expected
-                // resource set should be considered a no-op without complaints. E.g. if there is a background job calling
-                // the API and setting entitlements.
-                debug!(
-                    "CA '{}' update child '{}' resources has no effect, child already holds all resources",
-                    self.handle, child_handle
+                res.push(CaEvtDet::ChildShrinkScheduled {
+                    child: child_handle.clone(),
+                    shrink: ChildResourceShrink::new(resources, removed, effective),
+                });
+            }
+            _ => {
+                let resources_diff = resources.difference(child.resources());
+
+                if !resources_diff.is_empty() {
+                    info!(
+                        "CA '{}' update child '{}' resources: {}",
+                        self.handle, child_handle, resources_diff
+                    );
+
+                    res.push(CaEvtDet::ChildUpdatedResources {
+                        child: child_handle.clone(),
+                        resources,
+                    });
+                } else if child.shrink().is_some() {
+                    info!(
+                        "CA '{}' cancelled scheduled removal of resources from child '{}'",
+                        self.handle, child_handle
+                    );
+                    res.push(CaEvtDet::ChildShrinkCancelled {
+                        child: child_handle.clone(),
+                    });
+                } else {
+                    // Using 'debug' here, because there are possible use cases where updating the child resources to some expected
+                    // resource set should be considered a no-op without complaints. E.g. if there is a background job calling
+                    // the API and setting entitlements.
+                    debug!(
+                        "CA '{}' update child '{}' resources has no effect, child already holds all resources",
+                        self.handle, child_handle
+                    );
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Sets the number of days notice given to a child before resources are removed
+    /// from it. Removing the notice period does not affect an already scheduled removal.
+    fn child_shrink_notice_set(&self, child_handle: &ChildHandle, days: Option<u32>) -> KrillResult<Vec<CaEvt>> {
+        let child = self.get_child(child_handle)?;
+
+        if child.shrink_notice_days() == days {
+            Ok(vec![])
+        } else {
+            Ok(self.events_from_details(vec![CaEvtDet::ChildShrinkNoticeSet {
+                child: child_handle.clone(),
+                days,
+            }]))
+        }
+    }
+
+    /// Removes resources from a child if their scheduled removal is due. This is a
+    /// no-op otherwise.
+    fn child_shrink_enforce(&self, child_handle: &ChildHandle) -> KrillResult<Vec<CaEvt>> {
+        let child = self.get_child(child_handle)?;
+
+        match child.shrink() {
+            Some(shrink) if shrink.is_due() => {
+                info!(
+                    "CA '{}' removed resources from child '{}' after notice period: {}",
+                    self.handle,
+                    child_handle,
+                    shrink.removed()
                 );
-                Ok(vec![])
+                Ok(self.events_from_details(vec![CaEvtDet::ChildUpdatedResources {
+                    child: child_handle.clone(),
+                    resources: shrink.resources().clone(),
+                }]))
             }
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Returns all children with a scheduled removal of resources.
+    pub fn child_shrinks(&self) -> Vec<(&ChildHandle, &ChildResourceShrink)> {
+        let mut shrinks: Vec<_> = self
+            .children
+            .iter()
+            .filter_map(|(handle, child)| child.shrink().map(|shrink| (handle, shrink)))
+            .collect();
+        shrinks.sort_by(|a, b| a.0.cmp(b.0));
+        shrinks
+    }
+
+    /// Returns the children for which a scheduled removal of resources is due.
+    pub fn child_shrinks_due(&self) -> Vec<ChildHandle> {
+        self.child_shrinks()
+            .into_iter()
+            .filter(|(_, shrink)| shrink.is_due())
+            .map(|(handle, _)| handle.clone())
+            .collect()
+    }
+
+    /// Links a child to a resource template, and sets its resources to those
+    /// of the template.
+    fn child_apply_template(&self, child_handle: &ChildHandle, name: ResourceTemplateName) -> KrillResult<Vec<CaEvt>> {
+        let resources = self.get_resource_template(&name)?;
+        let child = self.get_child(child_handle)?;
+
+        // The CA may have lost resources since the template was last updated.
+        let not_held = self.resources_not_held(resources);
+        if !not_held.is_empty() {
+            return Err(Error::CaChildExtraResources(
+                self.handle.clone(),
+                child_handle.clone(),
+                not_held,
+            ));
+        }
+
+        let mut res = vec![];
+
+        if child.template() != Some(&name) {
+            info!(
+                "CA '{}' set resource template for child '{}' to '{}'",
+                self.handle, child_handle, name
+            );
+            res.push(CaEvtDet::ChildTemplateSet {
+                child: child_handle.clone(),
+                template: Some(name),
+            });
         }
+
+        res.append(&mut self.child_resources_events(child_handle, child, resources.clone()));
+
+        Ok(self.events_from_details(res))
     }
 
     /// Updates child IdCert
@@ -1039,9 +1706,13 @@ impl CertAuth {
     // period of time (hours).
     //
     // When a child is suspended we need to:
-    // - mark it as suspended
+    // - mark it as suspended, and record why
     // - withdraw all certificates issued to it (suspend them)
-    fn child_suspend_inactive(&self, child_handle: &ChildHandle) -> KrillResult<Vec<CaEvt>> {
+    fn child_suspend_inactive(
+        &self,
+        child_handle: &ChildHandle,
+        reason: ChildSuspendReason,
+    ) -> KrillResult<Vec<CaEvt>> {
         let mut res = vec![];
 
         let child = self.get_child(child_handle)?;
@@ -1082,8 +1753,14 @@ impl CertAuth {
         // to suspend above. If not this is a no-op - the child has not yet requested
         // any certificates so there is nothing to suspend.
         if !res.is_empty() {
-            info!("CA '{}' suspended inactive child '{}'", handle, child_handle);
-            res.push(CaEvtDet::child_suspended(handle, version, child_handle.clone()));
+            info!("CA '{}' suspended child '{}': {}", handle, child_handle, reason);
+            let suspension = ChildSuspension::new(reason);
+            res.push(CaEvtDet::child_suspended(
+                handle,
+                version,
+                child_handle.clone(),
+                suspension,
+            ));
         }
 
         Ok(res)
@@ -1156,6 +1833,87 @@ impl CertAuth {
         Ok(res)
     }
 
+    /// Sets or clears the suspension policy for inactive children of this CA.
+    fn suspension_policy_update(&self, policy: Option<ChildSuspensionPolicy>) -> KrillResult<Vec<CaEvt>> {
+        if let Some(policy) = &policy {
+            Self::verify_suspension_policy(policy)?;
+        }
+
+        if policy == self.suspension_policy {
+            return Ok(vec![]);
+        }
+
+        Ok(vec![StoredEvent::new(
+            self.handle(),
+            self.version,
+            CaEvtDet::SuspensionPolicyUpdated { policy },
+        )])
+    }
+
+    /// Sets or clears the suspension policy for a specific child.
+    fn child_suspension_policy_update(
+        &self,
+        child_handle: &ChildHandle,
+        policy: Option<ChildSuspensionPolicy>,
+    ) -> KrillResult<Vec<CaEvt>> {
+        let child = self.get_child(child_handle)?;
+
+        if let Some(policy) = &policy {
+            Self::verify_suspension_policy(policy)?;
+        }
+
+        if policy.as_ref() == child.suspension_policy() {
+            return Ok(vec![]);
+        }
+
+        Ok(vec![StoredEvent::new(
+            self.handle(),
+            self.version,
+            CaEvtDet::ChildSuspensionPolicyUpdated {
+                child: child_handle.clone(),
+                policy,
+            },
+        )])
+    }
+
+    fn verify_suspension_policy(policy: &ChildSuspensionPolicy) -> KrillResult<()> {
+        if let ChildSuspensionPolicy::Inactive {
+            suspend_after_hours,
+            warn_after_hours,
+        } = policy
+        {
+            if i64::from(*suspend_after_hours) < CA_SUSPEND_MIN_HOURS {
+                return Err(Error::CaChildSuspensionPolicyInvalid(format!(
+                    "children can only be suspended after {} hours or more",
+                    CA_SUSPEND_MIN_HOURS
+                )));
+            }
+            if let Some(warn_after_hours) = warn_after_hours {
+                if *warn_after_hours == 0 || warn_after_hours >= suspend_after_hours {
+                    return Err(Error::CaChildSuspensionPolicyInvalid(
+                        "the warning must come before the suspension".to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the suspension policy for inactive children of this CA, if it
+    /// overrides the configuration.
+    pub fn suspension_policy(&self) -> Option<&ChildSuspensionPolicy> {
+        self.suspension_policy.as_ref()
+    }
+
+    /// Returns the suspension policy that applies to the given child: its own
+    /// policy if it has one, or else the policy of this CA, if any.
+    pub fn child_suspension_policy(&self, child_handle: &ChildHandle) -> Option<&ChildSuspensionPolicy> {
+        self.children
+            .get(child_handle)
+            .and_then(|child| child.suspension_policy())
+            .or_else(|| self.suspension_policy())
+    }
+
     /// Returns `true` if the child is known, `false` otherwise. No errors.
     fn has_child(&self, child_handle: &Handle) -> bool {
         self.children.contains_key(child_handle)
@@ -1596,6 +2354,70 @@ impl CertAuth {
 
         Ok(vec![StoredEvent::new(self.handle(), self.version, finish_details)])
     }
+
+    /// Sets or clears the policy for automated key rolls. The rolls themselves are
+    /// driven by the scheduler, using the normal key roll commands.
+    fn keyroll_policy_update(&self, policy: Option<KeyRollPolicy>) -> KrillResult<Vec<CaEvt>> {
+        if self.is_ta() {
+            return Err(Error::KeyRollPolicyInvalid(
+                "automated key rolls are not supported for a trust anchor".to_string(),
+            ));
+        }
+
+        if let Some(policy) = &policy {
+            if policy.max_age_days() == 0 {
+                return Err(Error::KeyRollPolicyInvalid(
+                    "the maximum key age must be at least one day".to_string(),
+                ));
+            }
+            if policy.staging() >= policy.max_age() {
+                return Err(Error::KeyRollPolicyInvalid(
+                    "the staging period must be shorter than the maximum key age".to_string(),
+                ));
+            }
+        }
+
+        if policy == self.keyroll_policy {
+            return Ok(vec![]);
+        }
+
+        Ok(vec![StoredEvent::new(
+            self.handle(),
+            self.version,
+            CaEvtDet::KeyRollPolicyUpdated { policy },
+        )])
+    }
+
+    pub fn keyroll_policy(&self) -> Option<&KeyRollPolicy> {
+        self.keyroll_policy.as_ref()
+    }
+
+    /// Returns the key roll status for all resource classes of this CA.
+    pub fn keyroll_status(&self) -> KeyRollStatus {
+        let mut resource_classes: Vec<_> = self.resources.values().map(|rc| rc.keyroll_status()).collect();
+        resource_classes.sort_by(|a, b| a.name().cmp(b.name()));
+        KeyRollStatus::new(self.handle.clone(), resource_classes)
+    }
+
+    /// Returns the next step of an automated key roll for each resource class
+    /// where there is something to do, together with the parent of the class.
+    pub fn keyroll_steps(
+        &self,
+        policy: &KeyRollPolicy,
+        now: Time,
+    ) -> Vec<(ResourceClassName, ParentHandle, KeyRollStep)> {
+        if self.is_ta() {
+            return vec![];
+        }
+
+        self.resources
+            .iter()
+            .filter_map(|(rcn, rc)| {
+                rc.keyroll_step(policy, now)
+                    .map(|step| (rcn.clone(), rc.parent_handle().clone(), step))
+            })
+            .collect()
+    }
 }
 
 /// # Publishing
@@ -1637,6 +2459,87 @@ impl CertAuth {
         evt_dets.push(CaEvtDet::RepoUpdated { contact });
         Ok(self.events_from_details(evt_dets))
     }
+
+    /// Returns the secondary repositories to which this CA publishes copies
+    /// of all its objects.
+    pub fn repository_mirrors(&self) -> &HashMap<RepositoryMirrorName, RepositoryContact> {
+        &self.repository_mirrors
+    }
+
+    /// Add a repository mirror:
+    ///    - The CA must have a primary repository
+    ///    - The mirror must use the same base URI as the primary repository,
+    ///      because objects are published under the same URIs
+    ///    - The mirror cannot be the primary repository, or another mirror
+    fn repo_mirror_add(&self, name: RepositoryMirrorName, contact: RepositoryContact) -> KrillResult<Vec<CaEvt>> {
+        if self.repository_mirrors.contains_key(&name) {
+            return Err(Error::CaRepoMirrorDuplicate(self.handle.clone(), name));
+        }
+
+        let primary = self.repository_contact()?;
+        if primary == &contact {
+            return Err(Error::CaRepoMirrorInvalid(
+                self.handle.clone(),
+                name,
+                "this is the primary repository".to_string(),
+            ));
+        }
+
+        if let Some((existing, _)) = self.repository_mirrors.iter().find(|(_, mirror)| *mirror == &contact) {
+            let msg = format!("this repository is already used by mirror '{}'", existing);
+            return Err(Error::CaRepoMirrorInvalid(self.handle.clone(), name, msg));
+        }
+
+        let primary_base = primary.repo_info().base_uri();
+        let mirror_base = contact.repo_info().base_uri();
+        if primary_base != mirror_base {
+            let msg = format!(
+                "base URI '{}' differs from base URI '{}' of the primary repository",
+                mirror_base, primary_base
+            );
+            return Err(Error::CaRepoMirrorInvalid(self.handle.clone(), name, msg));
+        }
+
+        info!(
+            "CA '{}' added repository mirror '{}' with service URI: {}",
+            self.handle,
+            name,
+            contact.service_uri()
+        );
+
+        Ok(self.events_from_details(vec![CaEvtDet::RepoMirrorAdded { name, contact }]))
+    }
+
+    /// Remove a repository mirror. Note that the CaManager will try to withdraw
+    /// the objects published there, but this is best effort only.
+    fn repo_mirror_remove(&self, name: RepositoryMirrorName) -> KrillResult<Vec<CaEvt>> {
+        if !self.repository_mirrors.contains_key(&name) {
+            return Err(Error::CaRepoMirrorUnknown(self.handle.clone(), name));
+        }
+
+        info!("CA '{}' removed repository mirror '{}'", self.handle, name);
+
+        Ok(self.events_from_details(vec![CaEvtDet::RepoMirrorRemoved { name }]))
+    }
+}
+
+/// # Renaming
+///
+impl CertAuth {
+    /// Records that this CA was renamed. By the time this is called the CA
+    /// was already moved to its new handle, see `CaManager::ca_rename`.
+    fn renamed(&self, previous: Handle) -> KrillResult<Vec<CaEvt>> {
+        if previous == self.handle {
+            return Err(Error::CaRenameNotAllowed(
+                self.handle.clone(),
+                "the new name is the same as the current name".to_string(),
+            ));
+        }
+
+        info!("CA '{}' was renamed from '{}'", self.handle, previous);
+
+        Ok(self.events_from_details(vec![CaEvtDet::Renamed { previous }]))
+    }
 }
 
 /// # Managing Route Authorizations
@@ -2153,7 +3056,12 @@ impl CertAuth {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test;
+    use crate::{
+        commons::{actor::Actor, remote::rfc8183::ServiceUri},
+        constants::{ACTOR_DEF_TEST, CHILD_REQUEST_EXPIRY_HOURS},
+        daemon::ca::IniDet,
+        test,
+    };
 
     #[test]
     fn generate_id_cert() {
@@ -2163,4 +3071,304 @@ mod tests {
             id.cert.validate_ta().unwrap();
         });
     }
+
+    fn submit(ca: &mut CertAuth, child: &str, token: &Token, signer: &KrillSigner) -> KrillResult<()> {
+        let id_cert = Rfc8183Id::generate(signer)?.cert;
+        let cmd = CmdDet::child_request_submit(
+            ca.handle(),
+            test::handle(child),
+            id_cert,
+            PendingChild::hash_token(token),
+            &Actor::test_from_def(ACTOR_DEF_TEST),
+        );
+        for event in ca.process_command(cmd)? {
+            ca.apply(event);
+        }
+        Ok(())
+    }
+
+    /// Adds a request as if it had been submitted the given number of hours ago.
+    fn submitted_before(ca: &mut CertAuth, child: &str, hours: i64, signer: &KrillSigner) {
+        let event = StoredEvent::new(
+            ca.handle(),
+            ca.version(),
+            CaEvtDet::ChildRequestSubmitted {
+                child: test::handle(child),
+                id_cert: Rfc8183Id::generate(signer).unwrap().cert,
+                token_hash: PendingChild::hash_token(&Token::from("token")),
+                submitted: Timestamp::now_minus_hours(hours),
+            },
+        );
+        ca.apply(event);
+    }
+
+    fn ca(signer: &KrillSigner) -> CertAuth {
+        CertAuth::init(IniDet::init(&test::handle("parent"), signer).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn child_request_submit_reject_and_complete() {
+        test::test_under_tmp(|d| {
+            let signer = KrillSigner::build(&d).unwrap();
+            let mut ca = ca(&signer);
+            let actor = Actor::test_from_def(ACTOR_DEF_TEST);
+
+            let token = PendingChild::random_token().unwrap();
+            submit(&mut ca, "child", &token, &signer).unwrap();
+            assert_eq!(1, ca.child_requests().requests().len());
+
+            match submit(&mut ca, "child", &token, &signer) {
+                Err(Error::CaChildRequestDuplicate(_, _)) => {}
+                res => panic!("Expected duplicate request error, got: {:?}", res),
+            }
+
+            // The token cannot be used before the request is approved, and a
+            // wrong token does not reveal that there is a request.
+            let complete =
+                |token: Token| CmdDet::child_request_complete(ca.handle(), test::handle("child"), token, &actor);
+            match ca.process_command(complete(token.clone())) {
+                Err(Error::CaChildRequestNotApproved(_, _)) => {}
+                res => panic!("Expected not approved error, got: {:?}", res),
+            }
+            match ca.process_command(complete(Token::from("wrong"))) {
+                Err(Error::CaChildRequestUnknown(_, _)) => {}
+                res => panic!("Expected unknown request error, got: {:?}", res),
+            }
+
+            let reject = CmdDet::child_request_reject(ca.handle(), test::handle("child"), &actor);
+            for event in ca.process_command(reject).unwrap() {
+                ca.apply(event);
+            }
+            assert!(ca.child_requests().requests().is_empty());
+            assert!(ca.get_child_request(&test::handle("child")).is_err());
+        });
+    }
+
+    #[test]
+    fn child_requests_expire() {
+        test::test_under_tmp(|d| {
+            let signer = KrillSigner::build(&d).unwrap();
+            let mut ca = ca(&signer);
+
+            submitted_before(&mut ca, "expired", CHILD_REQUEST_EXPIRY_HOURS + 1, &signer);
+            submitted_before(&mut ca, "recent", CHILD_REQUEST_EXPIRY_HOURS - 1, &signer);
+
+            assert!(ca.get_child_request(&test::handle("expired")).is_err());
+            assert!(ca.get_child_request(&test::handle("recent")).is_ok());
+            assert_eq!(1, ca.child_requests().requests().len());
+
+            // The expired request is removed when a new request is submitted,
+            // and the same child may submit a new request.
+            let token = PendingChild::random_token().unwrap();
+            submit(&mut ca, "expired", &token, &signer).unwrap();
+
+            let pending = ca.get_child_request(&test::handle("expired")).unwrap();
+            assert!(pending.matches_token(&token));
+            assert!(!pending.is_expired());
+            assert_eq!(2, ca.child_requests().requests().len());
+        });
+    }
+
+    #[test]
+    fn child_requests_throttled() {
+        test::test_under_tmp(|d| {
+            let signer = KrillSigner::build(&d).unwrap();
+            let mut ca = ca(&signer);
+            let token = Token::from("token");
+
+            // Requests submitted more than an hour ago do not count.
+            submitted_before(&mut ca, "earlier", 2, &signer);
+
+            for nr in 0..CHILD_REQUESTS_HOURLY_MAX {
+                submit(&mut ca, &format!("child-{}", nr), &token, &signer).unwrap();
+            }
+
+            match submit(&mut ca, "one-too-many", &token, &signer) {
+                Err(Error::CaChildRequestsThrottled(_)) => {}
+                res => panic!("Expected throttled error, got: {:?}", res),
+            }
+            assert_eq!(CHILD_REQUESTS_HOURLY_MAX + 1, ca.child_requests().requests().len());
+        });
+    }
+
+    fn command(ca: &mut CertAuth, cmd: Cmd) -> Vec<CaEvt> {
+        let events = ca.process_command(cmd).unwrap();
+        for event in events.clone() {
+            ca.apply(event);
+        }
+        events
+    }
+
+    /// Creates a Trust Anchor CA holding all resources, without involving
+    /// a CaManager or repository.
+    fn trust_anchor(config: &Arc<Config>, signer: &Arc<KrillSigner>) -> CertAuth {
+        let actor = Actor::test_from_def(ACTOR_DEF_TEST);
+        let handle = ta_handle();
+        let mut ca = CertAuth::init(IniDet::init(&handle, signer.deref()).unwrap()).unwrap();
+
+        let repo_id = Rfc8183Id::generate(signer).unwrap().cert;
+        let contact = RepositoryContact::new(rfc8183::RepositoryResponse::new(
+            None,
+            handle.clone(),
+            repo_id,
+            ServiceUri::try_from("https://localhost/rfc8181/ta".to_string()).unwrap(),
+            RepoInfo::new(
+                test::rsync("rsync://localhost/repo/"),
+                test::https("https://localhost/rrdp/notification.xml"),
+            ),
+        ));
+        command(&mut ca, CmdDet::update_repo(&handle, contact, signer.clone(), &actor));
+
+        let ta_aia = test::rsync("rsync://localhost/ta/ta.cer");
+        command(
+            &mut ca,
+            CmdDet::make_trust_anchor(
+                &handle,
+                vec![test::https("https://localhost/ta/ta.cer")],
+                Some(ta_aia.clone()),
+                signer.clone(),
+                &actor,
+            ),
+        );
+
+        let ta_cert = ca.parent(&handle).unwrap().to_ta_cert().clone();
+        let rcvd_cert = RcvdCert::new(ta_cert, ta_aia, ResourceSet::all_resources());
+        command(
+            &mut ca,
+            CmdDet::upd_received_cert(
+                &handle,
+                ResourceClassName::default(),
+                rcvd_cert,
+                config.clone(),
+                signer.clone(),
+                &actor,
+            ),
+        );
+
+        ca
+    }
+
+    #[test]
+    fn child_resources_shrink() {
+        test::test_under_tmp(|d| {
+            let signer = Arc::new(KrillSigner::build(&d).unwrap());
+            let config = Arc::new(Config::test(&d, false, false, false));
+            let timing = &config.issuance_timing;
+            let actor = Actor::test_from_def(ACTOR_DEF_TEST);
+
+            let mut ca = trust_anchor(&config, &signer);
+            let handle = ca.handle().clone();
+
+            let child = test::handle("child");
+            let child_id = Rfc8183Id::generate(&signer).unwrap().cert;
+            let slash_16 = test::ipv4_resources("10.0.0.0/16");
+            let slash_23 = test::ipv4_resources("10.0.0.0/23");
+            let slash_24 = test::ipv4_resources("10.0.0.0/24");
+
+            command(
+                &mut ca,
+                CmdDet::child_add(&handle, child.clone(), child_id, slash_16.clone(), &actor),
+            );
+            command(
+                &mut ca,
+                CmdDet::child_shrink_notice_set(&handle, child.clone(), Some(30), &actor),
+            );
+
+            let update = |resources: &ResourceSet| {
+                CmdDet::child_update_resources(&handle, child.clone(), resources.clone(), &actor)
+            };
+
+            // Removing resources schedules the removal, the child keeps its resources for now.
+            command(&mut ca, update(&slash_24));
+            let effective = {
+                let details = ca.get_child(&child).unwrap();
+                assert_eq!(&slash_16, details.resources());
+
+                let shrink = details.shrink().unwrap();
+                assert_eq!(&slash_24, shrink.resources());
+                assert_eq!(&slash_16.difference(&slash_24).added().clone(), shrink.removed());
+                assert!(shrink.effective() > Timestamp::now_plus_hours(29 * 24));
+                assert!(shrink.effective() <= Timestamp::now_plus_hours(30 * 24));
+                shrink.effective()
+            };
+            assert_eq!(
+                vec![child.clone()],
+                ca.child_shrinks()
+                    .into_iter()
+                    .map(|(h, _)| h.clone())
+                    .collect::<Vec<_>>()
+            );
+            assert!(ca.child_shrinks_due().is_empty());
+
+            // Entitlements and new certificates do not go beyond the effective time.
+            let entitlements = ca.list(&child, timing).unwrap();
+            assert_eq!(Time::from(effective), entitlements.classes()[0].not_after());
+
+            let child_key = signer.create_key().unwrap();
+            let csr = signer
+                .sign_csr(ca.repository_info().unwrap(), "child", &child_key)
+                .unwrap();
+            let issued = ca
+                .issue_child_certificate(
+                    &child,
+                    ResourceClassName::default(),
+                    CsrInfo::try_from(&csr).unwrap(),
+                    RequestResourceLimit::default(),
+                    timing,
+                    &signer,
+                )
+                .unwrap();
+            assert_eq!(Time::from(effective), issued.validity().not_after());
+
+            // Updating to the same resources again is a no-op.
+            assert!(command(&mut ca, update(&slash_24)).is_empty());
+
+            // Removing fewer resources, already announced, keeps the effective time,
+            // even if the notice period was extended in the meantime.
+            command(
+                &mut ca,
+                CmdDet::child_shrink_notice_set(&handle, child.clone(), Some(60), &actor),
+            );
+            command(&mut ca, update(&slash_23));
+            let shrink = ca.get_child(&child).unwrap().shrink().unwrap();
+            assert_eq!(&slash_23, shrink.resources());
+            assert_eq!(effective, shrink.effective());
+
+            // Enforcing is a no-op until the removal is due.
+            assert!(command(&mut ca, CmdDet::child_shrink_enforce(&handle, child.clone(), &actor)).is_empty());
+            assert_eq!(&slash_16, ca.get_child(&child).unwrap().resources());
+
+            // Restoring the resources cancels the removal.
+            command(&mut ca, update(&slash_16));
+            let details = ca.get_child(&child).unwrap();
+            assert!(details.shrink().is_none());
+            assert_eq!(&slash_16, details.resources());
+            assert!(ca.child_shrinks().is_empty());
+
+            // A removal which is due is enforced.
+            let event = StoredEvent::new(
+                &handle,
+                ca.version(),
+                CaEvtDet::ChildShrinkScheduled {
+                    child: child.clone(),
+                    shrink: ChildResourceShrink::new(
+                        slash_24.clone(),
+                        slash_16.difference(&slash_24).added().clone(),
+                        Timestamp::now_minus_hours(1),
+                    ),
+                },
+            );
+            ca.apply(event);
+            assert_eq!(vec![child.clone()], ca.child_shrinks_due());
+
+            // A due removal no longer limits the validity of certificates.
+            assert!(ca.get_child(&child).unwrap().shrink_not_after(&slash_16).is_none());
+
+            command(&mut ca, CmdDet::child_shrink_enforce(&handle, child.clone(), &actor));
+            let details = ca.get_child(&child).unwrap();
+            assert_eq!(&slash_24, details.resources());
+            assert!(details.shrink().is_none());
+            assert!(ca.child_shrinks_due().is_empty());
+        });
+    }
 }
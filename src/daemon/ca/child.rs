@@ -7,13 +7,16 @@ use rpki::repository::{crypto::KeyIdentifier, x509::Time};
 use crate::{
     commons::{
         api::{
-            ChildCaInfo, ChildHandle, ChildState, HexEncodedHash, IssuedCert, ReplacedObject, ResourceClassName,
-            ResourceSet, Revocation, SuspendedCert, UnsuspendedCert,
+            ChildCaInfo, ChildHandle, ChildRequestInfo, ChildResourceShrink, ChildState, ChildSuspension,
+            ChildSuspensionPolicy, HexEncodedHash, IssuedCert, ReplacedObject, ResourceClassName, ResourceSet,
+            ResourceTemplateName, Revocation, SuspendedCert, Timestamp, Token, UnsuspendedCert,
         },
         crypto::{CsrInfo, IdCert, KrillSigner, SignSupport},
         error::Error,
+        util::sha256,
         KrillResult,
     },
+    constants::CHILD_REQUEST_EXPIRY_HOURS,
     daemon::{
         ca::{CertifiedKey, ChildCertificateUpdates},
         config::IssuanceTimingConfig,
@@ -45,6 +48,21 @@ pub struct ChildDetails {
     id_cert: IdCert,
     resources: ResourceSet,
     used_keys: HashMap<KeyIdentifier, UsedKeyState>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    template: Option<ResourceTemplateName>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    shrink_notice_days: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    shrink: Option<ChildResourceShrink>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    suspension_policy: Option<ChildSuspensionPolicy>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    suspension: Option<ChildSuspension>,
 }
 
 impl ChildDetails {
@@ -54,6 +72,11 @@ impl ChildDetails {
             id_cert,
             resources,
             used_keys: HashMap::new(),
+            template: None,
+            shrink_notice_days: None,
+            shrink: None,
+            suspension_policy: None,
+            suspension: None,
         }
     }
 
@@ -61,12 +84,31 @@ impl ChildDetails {
         self.state == ChildState::Suspended
     }
 
-    pub fn suspend(&mut self) {
+    /// Marks this child as suspended. The suspension is None for children
+    /// suspended before the reason was recorded.
+    pub fn suspend(&mut self, suspension: Option<ChildSuspension>) {
         self.state = ChildState::Suspended;
+        self.suspension = suspension;
     }
 
     pub fn unsuspend(&mut self) {
         self.state = ChildState::Active;
+        self.suspension = None;
+    }
+
+    /// Returns when and why this child was suspended, if it is.
+    pub fn suspension(&self) -> Option<&ChildSuspension> {
+        self.suspension.as_ref()
+    }
+
+    /// Returns the suspension policy for this child, if it overrides the
+    /// policy of the CA.
+    pub fn suspension_policy(&self) -> Option<&ChildSuspensionPolicy> {
+        self.suspension_policy.as_ref()
+    }
+
+    pub fn set_suspension_policy(&mut self, policy: Option<ChildSuspensionPolicy>) {
+        self.suspension_policy = policy;
     }
 
     pub fn id_cert(&self) -> &IdCert {
@@ -81,8 +123,49 @@ impl ChildDetails {
         &self.resources
     }
 
+    /// Sets the resources for this child. This replaces any scheduled
+    /// removal of resources.
     pub fn set_resources(&mut self, resources: ResourceSet) {
         self.resources = resources;
+        self.shrink = None;
+    }
+
+    /// Returns the number of days notice given before resources are
+    /// removed from this child, if any.
+    pub fn shrink_notice_days(&self) -> Option<u32> {
+        self.shrink_notice_days
+    }
+
+    pub fn set_shrink_notice_days(&mut self, days: Option<u32>) {
+        self.shrink_notice_days = days;
+    }
+
+    /// Returns the scheduled removal of resources from this child, if any.
+    pub fn shrink(&self) -> Option<&ChildResourceShrink> {
+        self.shrink.as_ref()
+    }
+
+    pub fn set_shrink(&mut self, shrink: Option<ChildResourceShrink>) {
+        self.shrink = shrink;
+    }
+
+    /// Returns the time until which certificates for the given resources
+    /// may be valid, if the child is about to lose any of them. Returns None
+    /// if the removal is already due, because it will be enforced shortly.
+    pub fn shrink_not_after(&self, resources: &ResourceSet) -> Option<Time> {
+        self.shrink
+            .as_ref()
+            .filter(|shrink| !shrink.is_due() && !shrink.removed().intersection(resources).is_empty())
+            .map(|shrink| shrink.effective().into())
+    }
+
+    /// Returns the name of the resource template used for this child, if any.
+    pub fn template(&self) -> Option<&ResourceTemplateName> {
+        self.template.as_ref()
+    }
+
+    pub fn set_template(&mut self, template: Option<ResourceTemplateName>) {
+        self.template = template;
     }
 
     pub fn issued(&self, rcn: &ResourceClassName) -> Vec<KeyIdentifier> {
@@ -128,7 +211,87 @@ impl ChildDetails {
 
 impl From<ChildDetails> for ChildCaInfo {
     fn from(details: ChildDetails) -> Self {
-        ChildCaInfo::new(details.state, (&details.id_cert).into(), details.resources)
+        ChildCaInfo::new(
+            details.state,
+            (&details.id_cert).into(),
+            details.resources,
+            details.template,
+            details.shrink_notice_days,
+            details.shrink,
+        )
+        .with_suspension(details.suspension_policy, details.suspension)
+    }
+}
+
+//------------ PendingChild ------------------------------------------------
+
+/// The number of random bytes used for one-time child request tokens.
+const CHILD_REQUEST_TOKEN_BYTES: usize = 32;
+
+/// A request by a prospective child CA to be added under a parent CA.
+///
+/// Only the hash of the one-time token given to the child is kept, the
+/// token itself is needed to retrieve the parent response after approval.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PendingChild {
+    id_cert: IdCert,
+    token_hash: String,
+    submitted: Timestamp,
+    #[serde(default)]
+    approved: bool,
+}
+
+impl PendingChild {
+    pub fn new(id_cert: IdCert, token_hash: String, submitted: Timestamp) -> Self {
+        PendingChild {
+            id_cert,
+            token_hash,
+            submitted,
+            approved: false,
+        }
+    }
+
+    pub fn id_cert(&self) -> &IdCert {
+        &self.id_cert
+    }
+
+    pub fn submitted(&self) -> Timestamp {
+        self.submitted
+    }
+
+    pub fn is_approved(&self) -> bool {
+        self.approved
+    }
+
+    /// Returns true if the request was not approved in time. Approved
+    /// requests do not expire, because the child was already added.
+    pub fn is_expired(&self) -> bool {
+        !self.approved && self.submitted < Timestamp::now_minus_hours(CHILD_REQUEST_EXPIRY_HOURS)
+    }
+
+    pub fn approve(&mut self) {
+        self.approved = true;
+    }
+
+    /// Returns true if the given token is the one issued for this request.
+    pub fn matches_token(&self, token: &Token) -> bool {
+        Self::hash_token(token) == self.token_hash
+    }
+
+    /// Creates a new random one-time token for a child request.
+    pub fn random_token() -> KrillResult<Token> {
+        let mut bytes = [0; CHILD_REQUEST_TOKEN_BYTES];
+        openssl::rand::rand_bytes(&mut bytes).map_err(|e| Error::custom(format!("Cannot create token: {}", e)))?;
+        Ok(Token::from(hex::encode(bytes)))
+    }
+
+    /// Returns the hex encoded sha256 hash of a token.
+    pub fn hash_token(token: &Token) -> String {
+        hex::encode(sha256(token.as_ref().as_bytes()))
+    }
+
+    pub fn as_info(&self, child: ChildHandle) -> ChildRequestInfo {
+        ChildRequestInfo::new(child, (&self.id_cert).into(), self.submitted, self.approved)
     }
 }
 
@@ -279,7 +442,7 @@ impl ChildCertificates {
             limit,
             Some(replaced),
             signing_key,
-            issuance_timing.timing_child_certificate_valid_weeks,
+            SignSupport::sign_validity_weeks(issuance_timing.timing_child_certificate_valid_weeks),
             signer,
         )?;
 
@@ -8,9 +8,10 @@ use crate::{
     commons::{
         actor::Actor,
         api::{
-            AspaCustomer, AspaDefinitionUpdates, AspaProvidersUpdate, ChildHandle, Entitlements, Handle,
-            IssuanceRequest, ParentCaContact, ParentHandle, RcvdCert, RepositoryContact, ResourceClassName,
-            ResourceSet, RevocationRequest, RevocationResponse, RtaName, StorableCaCommand, StorableRcEntitlement,
+            AspaCustomer, AspaDefinitionUpdates, AspaProvidersUpdate, ChildHandle, ChildSuspendReason,
+            ChildSuspensionPolicy, Entitlements, Handle, IssuanceRequest, KeyRollPolicy, ParentCaContact, ParentHandle,
+            RcvdCert, RepositoryContact, RepositoryMirrorName, ResourceClassName, ResourceSet, ResourceTemplateName,
+            RevocationRequest, RevocationResponse, RtaName, StorableCaCommand, StorableRcEntitlement, Token,
         },
         crypto::{IdCert, KrillSigner},
         eventsourcing::{self, StoredCommand},
@@ -41,6 +42,17 @@ pub enum CmdDet {
     // ------------------------------------------------------------
     MakeTrustAnchor(Vec<uri::Https>, Option<uri::Rsync>, Arc<KrillSigner>),
 
+    // Start a key roll for the Trust Anchor: create a successor key and a self-signed
+    // certificate for it, and publish Trust Anchor Key (TAK) objects under both keys.
+    TaKeyRollInitiate(Arc<KrillSigner>),
+
+    // Activate the successor key, after the transition period since initiation. The
+    // successor becomes the current TA certificate and re-issues all child certificates.
+    TaKeyRollActivate(Arc<Config>, Arc<KrillSigner>),
+
+    // Retire the predecessor key, after the transition period since activation.
+    TaKeyRollFinish(Arc<Config>),
+
     // ------------------------------------------------------------
     // Being a parent
     // ------------------------------------------------------------
@@ -68,13 +80,54 @@ pub enum CmdDet {
     // When a child is inactive it is assumed that they no longer maintain their repository.
     // The certificate(s) issued to the child will be removed (and revoked) until
     // the child is seen again and unsuspended (see below).
-    ChildSuspendInactive(ChildHandle),
+    ChildSuspendInactive(ChildHandle, ChildSuspendReason),
 
     // Unsuspend a child (when it contacts the server again). I.e. mark it as active once
     // again and republish existing certificates provided that they are not expired, or
     // about to expire, and do not claim resources no longer associated with this child.
     ChildUnsuspend(ChildHandle),
 
+    // Set or clear the suspension policy for inactive children of this CA. The
+    // policy overrides the global configuration.
+    SuspensionPolicyUpdate(Option<ChildSuspensionPolicy>),
+
+    // Set or clear the suspension policy for a specific child. The policy
+    // overrides the policy of the CA.
+    ChildSuspensionPolicyUpdate(ChildHandle, Option<ChildSuspensionPolicy>),
+
+    // Link a child to a named resource template, and set its resources to those
+    // of the template. The link is removed when the child's resources are updated
+    // explicitly.
+    ChildApplyTemplate(ChildHandle, ResourceTemplateName),
+
+    // Set the number of days notice given before resources are removed from a
+    // child, or None to remove resources immediately.
+    ChildShrinkNoticeSet(ChildHandle, Option<u32>),
+
+    // Remove the resources from a child for which the notice period has passed.
+    ChildShrinkEnforce(ChildHandle),
+
+    // Add or update a named resource template. Children using the template get
+    // the updated resources.
+    ResourceTemplateUpdate(ResourceTemplateName, ResourceSet),
+
+    // Remove a resource template, provided that no child uses it.
+    ResourceTemplateRemove(ResourceTemplateName),
+
+    // Record a request by a prospective child to be added under this CA. Only
+    // the hash of the one-time token used to retrieve the response is kept.
+    ChildRequestSubmit(ChildHandle, IdCert, String),
+
+    // Approve a pending child request, adding the child with the given resources.
+    ChildRequestApprove(ChildHandle, ResourceSet),
+
+    // Reject, i.e. remove, a pending child request.
+    ChildRequestReject(ChildHandle),
+
+    // Remove an approved child request, when the child retrieves the parent
+    // response using its one-time token.
+    ChildRequestComplete(ChildHandle, Token),
+
     // ------------------------------------------------------------
     // Being a child (only allowed if this CA is not self-signed)
     // ------------------------------------------------------------
@@ -130,6 +183,10 @@ pub enum CmdDet {
     // class has been revoked. I.e. remove the old key, and withdraw the crl and mft for it.
     KeyRollFinish(ResourceClassName, RevocationResponse),
 
+    // Set or clear the policy for automated key rolls. With a policy the scheduler
+    // will initiate and activate key rolls for this CA.
+    KeyRollPolicyUpdate(Option<KeyRollPolicy>),
+
     // ------------------------------------------------------------
     // ROA Support
     // ------------------------------------------------------------
@@ -171,6 +228,21 @@ pub enum CmdDet {
     // Update the repository where this CA publishes
     RepoUpdate(RepositoryContact, Arc<KrillSigner>),
 
+    // Add a secondary repository to which all objects are published as well
+    RepoMirrorAdd(RepositoryMirrorName, RepositoryContact),
+
+    // Remove a secondary repository
+    RepoMirrorRemove(RepositoryMirrorName),
+
+    // ------------------------------------------------------------
+    // Renaming
+    // ------------------------------------------------------------
+
+    // Record that this CA was renamed from the given handle. The CA itself is
+    // moved to its new handle by the store, this makes the rename visible in
+    // its history, and lets listeners carry over state kept for the old handle.
+    Renamed(Handle),
+
     // ------------------------------------------------------------
     // Resource Tagged Attestations
     // ------------------------------------------------------------
@@ -207,6 +279,9 @@ impl From<CmdDet> for StorableCaCommand {
             // Being a TA
             // ------------------------------------------------------------
             CmdDet::MakeTrustAnchor(_, _, _) => StorableCaCommand::MakeTrustAnchor,
+            CmdDet::TaKeyRollInitiate(_) => StorableCaCommand::TaKeyRollInitiate,
+            CmdDet::TaKeyRollActivate(_, _) => StorableCaCommand::TaKeyRollActivate,
+            CmdDet::TaKeyRollFinish(_) => StorableCaCommand::TaKeyRollFinish,
 
             // ------------------------------------------------------------
             // Being a parent
@@ -235,8 +310,31 @@ impl From<CmdDet> for StorableCaCommand {
             }
             CmdDet::ChildRevokeKey(child, revoke_req) => StorableCaCommand::ChildRevokeKey { child, revoke_req },
             CmdDet::ChildRemove(child) => StorableCaCommand::ChildRemove { child },
-            CmdDet::ChildSuspendInactive(child) => StorableCaCommand::ChildSuspendInactive { child },
+            CmdDet::ChildSuspendInactive(child, reason) => StorableCaCommand::ChildSuspendInactive {
+                child,
+                reason: Some(reason),
+            },
             CmdDet::ChildUnsuspend(child) => StorableCaCommand::ChildUnsuspend { child },
+            CmdDet::SuspensionPolicyUpdate(policy) => StorableCaCommand::SuspensionPolicyUpdate { policy },
+            CmdDet::ChildSuspensionPolicyUpdate(child, policy) => {
+                StorableCaCommand::ChildSuspensionPolicyUpdate { child, policy }
+            }
+            CmdDet::ChildApplyTemplate(child, template) => StorableCaCommand::ChildApplyTemplate { child, template },
+            CmdDet::ChildShrinkNoticeSet(child, days) => StorableCaCommand::ChildShrinkNoticeSet { child, days },
+            CmdDet::ChildShrinkEnforce(child) => StorableCaCommand::ChildShrinkEnforce { child },
+            CmdDet::ResourceTemplateUpdate(name, resources) => {
+                StorableCaCommand::ResourceTemplateUpdate { name, resources }
+            }
+            CmdDet::ResourceTemplateRemove(name) => StorableCaCommand::ResourceTemplateRemove { name },
+            CmdDet::ChildRequestSubmit(child, id_cert, _) => StorableCaCommand::ChildRequestSubmit {
+                child,
+                ski: id_cert.ski_hex(),
+            },
+            CmdDet::ChildRequestApprove(child, resources) => {
+                StorableCaCommand::ChildRequestApprove { child, resources }
+            }
+            CmdDet::ChildRequestReject(child) => StorableCaCommand::ChildRequestReject { child },
+            CmdDet::ChildRequestComplete(child, _) => StorableCaCommand::ChildRequestComplete { child },
 
             // ------------------------------------------------------------
             // Being a child
@@ -281,6 +379,7 @@ impl From<CmdDet> for StorableCaCommand {
                 staged_for_seconds: staged_for.num_seconds(),
             },
             CmdDet::KeyRollFinish(resource_class_name, _) => StorableCaCommand::KeyRollFinish { resource_class_name },
+            CmdDet::KeyRollPolicyUpdate(policy) => StorableCaCommand::KeyRollPolicyUpdate { policy },
 
             // ------------------------------------------------------------
             // ROA Support
@@ -306,6 +405,16 @@ impl From<CmdDet> for StorableCaCommand {
             CmdDet::RepoUpdate(contact, _) => StorableCaCommand::RepoUpdate {
                 service_uri: contact.service_uri().clone(),
             },
+            CmdDet::RepoMirrorAdd(name, contact) => StorableCaCommand::RepoMirrorAdd {
+                name,
+                service_uri: contact.service_uri().clone(),
+            },
+            CmdDet::RepoMirrorRemove(name) => StorableCaCommand::RepoMirrorRemove { name },
+
+            // ------------------------------------------------------------
+            // Renaming
+            // ------------------------------------------------------------
+            CmdDet::Renamed(previous) => StorableCaCommand::Renamed { previous },
 
             // ------------------------------------------------------------
             // Resource Tagged Attestations
@@ -329,6 +438,18 @@ impl CmdDet {
         eventsourcing::SentCommand::new(handle, None, CmdDet::MakeTrustAnchor(uris, rsync_uri, signer), actor)
     }
 
+    pub fn ta_key_roll_init(handle: &Handle, signer: Arc<KrillSigner>, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::TaKeyRollInitiate(signer), actor)
+    }
+
+    pub fn ta_key_roll_activate(handle: &Handle, config: Arc<Config>, signer: Arc<KrillSigner>, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::TaKeyRollActivate(config, signer), actor)
+    }
+
+    pub fn ta_key_roll_finish(handle: &Handle, config: Arc<Config>, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::TaKeyRollFinish(config), actor)
+    }
+
     /// Adds a child to this CA. Will return an error in case you try
     /// to give the child resources not held by the CA.
     pub fn child_add(
@@ -396,14 +517,109 @@ impl CmdDet {
         eventsourcing::SentCommand::new(handle, None, CmdDet::ChildRemove(child_handle), actor)
     }
 
-    pub fn child_suspend_inactive(handle: &Handle, child_handle: ChildHandle, actor: &Actor) -> Cmd {
-        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildSuspendInactive(child_handle), actor)
+    pub fn child_suspend_inactive(
+        handle: &Handle,
+        child_handle: ChildHandle,
+        reason: ChildSuspendReason,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildSuspendInactive(child_handle, reason), actor)
     }
 
     pub fn child_unsuspend(handle: &Handle, child_handle: ChildHandle, actor: &Actor) -> Cmd {
         eventsourcing::SentCommand::new(handle, None, CmdDet::ChildUnsuspend(child_handle), actor)
     }
 
+    pub fn suspension_policy_update(handle: &Handle, policy: Option<ChildSuspensionPolicy>, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::SuspensionPolicyUpdate(policy), actor)
+    }
+
+    pub fn child_suspension_policy_update(
+        handle: &Handle,
+        child_handle: ChildHandle,
+        policy: Option<ChildSuspensionPolicy>,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::ChildSuspensionPolicyUpdate(child_handle, policy),
+            actor,
+        )
+    }
+
+    pub fn child_apply_template(
+        handle: &Handle,
+        child_handle: ChildHandle,
+        template: ResourceTemplateName,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildApplyTemplate(child_handle, template), actor)
+    }
+
+    pub fn child_shrink_notice_set(
+        handle: &Handle,
+        child_handle: ChildHandle,
+        days: Option<u32>,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildShrinkNoticeSet(child_handle, days), actor)
+    }
+
+    pub fn child_shrink_enforce(handle: &Handle, child_handle: ChildHandle, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildShrinkEnforce(child_handle), actor)
+    }
+
+    pub fn resource_template_update(
+        handle: &Handle,
+        name: ResourceTemplateName,
+        resources: ResourceSet,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ResourceTemplateUpdate(name, resources), actor)
+    }
+
+    pub fn resource_template_remove(handle: &Handle, name: ResourceTemplateName, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ResourceTemplateRemove(name), actor)
+    }
+
+    pub fn child_request_submit(
+        handle: &Handle,
+        child_handle: ChildHandle,
+        id_cert: IdCert,
+        token_hash: String,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::ChildRequestSubmit(child_handle, id_cert, token_hash),
+            actor,
+        )
+    }
+
+    pub fn child_request_approve(
+        handle: &Handle,
+        child_handle: ChildHandle,
+        resources: ResourceSet,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::ChildRequestApprove(child_handle, resources),
+            actor,
+        )
+    }
+
+    pub fn child_request_reject(handle: &Handle, child_handle: ChildHandle, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildRequestReject(child_handle), actor)
+    }
+
+    pub fn child_request_complete(handle: &Handle, child_handle: ChildHandle, token: Token, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildRequestComplete(child_handle, token), actor)
+    }
+
     pub fn update_id(handle: &Handle, signer: Arc<KrillSigner>, actor: &Actor) -> Cmd {
         eventsourcing::SentCommand::new(handle, None, CmdDet::GenerateNewIdKey(signer), actor)
     }
@@ -488,10 +704,31 @@ impl CmdDet {
         eventsourcing::SentCommand::new(handle, None, CmdDet::KeyRollFinish(rcn, res), actor)
     }
 
+    pub fn key_roll_policy_update(handle: &Handle, policy: Option<KeyRollPolicy>, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::KeyRollPolicyUpdate(policy), actor)
+    }
+
     pub fn update_repo(handle: &Handle, contact: RepositoryContact, signer: Arc<KrillSigner>, actor: &Actor) -> Cmd {
         eventsourcing::SentCommand::new(handle, None, CmdDet::RepoUpdate(contact, signer), actor)
     }
 
+    pub fn repo_mirror_add(
+        handle: &Handle,
+        name: RepositoryMirrorName,
+        contact: RepositoryContact,
+        actor: &Actor,
+    ) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::RepoMirrorAdd(name, contact), actor)
+    }
+
+    pub fn repo_mirror_remove(handle: &Handle, name: RepositoryMirrorName, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::RepoMirrorRemove(name), actor)
+    }
+
+    pub fn renamed(handle: &Handle, previous: Handle, actor: &Actor) -> Cmd {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::Renamed(previous), actor)
+    }
+
     //-------------------------------------------------------------------------------
     // Route Authorizations
     //-------------------------------------------------------------------------------
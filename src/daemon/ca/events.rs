@@ -5,10 +5,11 @@ use rpki::repository::crypto::KeyIdentifier;
 use crate::{
     commons::{
         api::{
-            AspaCustomer, AspaDefinition, AspaProvidersUpdate, ChildHandle, Handle, IssuanceRequest, IssuedCert,
-            ObjectName, ParentCaContact, ParentHandle, ParentResourceClassName, RcvdCert, RepositoryContact,
-            ResourceClassName, ResourceSet, RevocationRequest, RevokedObject, RoaAggregateKey, RtaName, SuspendedCert,
-            TaCertDetails, UnsuspendedCert,
+            AspaCustomer, AspaDefinition, AspaProvidersUpdate, ChildHandle, ChildResourceShrink, ChildSuspension,
+            ChildSuspensionPolicy, Handle, IssuanceRequest, IssuedCert, KeyRollPolicy, ObjectName, ParentCaContact,
+            ParentHandle, ParentResourceClassName, RcvdCert, RepositoryContact, RepositoryMirrorName,
+            ResourceClassName, ResourceSet, ResourceTemplateName, RevocationRequest, RevokedObject, RoaAggregateKey,
+            RtaName, SuspendedCert, TaCertDetails, Timestamp, UnsuspendedCert,
         },
         crypto::{IdCert, KrillSigner},
         eventsourcing::StoredEvent,
@@ -523,6 +524,18 @@ pub enum CaEvtDet {
     TrustAnchorMade {
         ta_cert_details: TaCertDetails,
     },
+    /// A successor key was certified by a Trust Anchor. It is staged, and
+    /// TAK objects are published to announce it.
+    TrustAnchorKeyRollStarted {
+        successor: TaCertDetails,
+        started: Timestamp,
+    },
+    /// The successor key of a Trust Anchor became its current key.
+    TrustAnchorKeyRollActivated {
+        activated: Timestamp,
+    },
+    /// The predecessor key of a Trust Anchor was retired.
+    TrustAnchorKeyRollFinished,
 
     // Being a parent Events
     /// A child was added to this (parent) CA
@@ -568,11 +581,82 @@ pub enum CaEvtDet {
     // (Un)Suspend a child events
     ChildSuspended {
         child: ChildHandle,
+        // Explains when and why the child was suspended. Not present in events
+        // stored by earlier versions of Krill.
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        suspension: Option<ChildSuspension>,
     },
     ChildUnsuspended {
         child: ChildHandle,
     },
 
+    // Suspension policy events
+    /// The suspension policy for inactive children of this CA was set or cleared.
+    SuspensionPolicyUpdated {
+        policy: Option<ChildSuspensionPolicy>,
+    },
+    /// The suspension policy for a specific child was set or cleared.
+    ChildSuspensionPolicyUpdated {
+        child: ChildHandle,
+        policy: Option<ChildSuspensionPolicy>,
+    },
+
+    /// A child was linked to a resource template, or unlinked when its
+    /// resources were set explicitly.
+    ChildTemplateSet {
+        child: ChildHandle,
+        template: Option<ResourceTemplateName>,
+    },
+
+    // Scheduled resource removal events
+    /// The notice period before resources are removed from a child was set or cleared.
+    ChildShrinkNoticeSet {
+        child: ChildHandle,
+        days: Option<u32>,
+    },
+    /// Resources will be removed from a child once the shrink is effective. Until
+    /// then the child keeps its current resources. Note that a ChildUpdatedResources
+    /// event replaces any scheduled shrink.
+    ChildShrinkScheduled {
+        child: ChildHandle,
+        shrink: ChildResourceShrink,
+    },
+    /// A scheduled shrink was cancelled, because the child's resources were set to
+    /// its current resources.
+    ChildShrinkCancelled {
+        child: ChildHandle,
+    },
+
+    // Resource template events
+    /// A resource template was added or updated.
+    ResourceTemplateUpdated {
+        name: ResourceTemplateName,
+        resources: ResourceSet,
+    },
+    ResourceTemplateRemoved {
+        name: ResourceTemplateName,
+    },
+
+    // Child onboarding events
+    /// A prospective child submitted a request to be added to this CA. Only
+    /// a hash of the one-time token for retrieving the response is kept.
+    ChildRequestSubmitted {
+        child: ChildHandle,
+        id_cert: IdCert,
+        token_hash: String,
+        submitted: Timestamp,
+    },
+    /// A pending child request was approved. This event is accompanied by
+    /// a ChildAdded event for the child.
+    ChildRequestApproved {
+        child: ChildHandle,
+    },
+    /// A child request was rejected, or completed because the child
+    /// retrieved the parent response.
+    ChildRequestRemoved {
+        child: ChildHandle,
+    },
+
     // Being a child Events
     IdUpdated {
         id: Rfc8183Id,
@@ -617,6 +701,11 @@ pub enum CaEvtDet {
         // this key.
         resource_class_name: ResourceClassName,
         pending_key_id: KeyIdentifier,
+        // When the key state changed, i.e. the start of the staging period, or of
+        // the age of the current key. Not present in events stored by earlier
+        // versions of Krill.
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        changed: Option<Timestamp>,
     },
     KeyPendingToNew {
         // A pending key is marked as 'new' when it has received its (first) certificate.
@@ -625,6 +714,11 @@ pub enum CaEvtDet {
         // become the active key. However, in practice this time can be shortened.
         resource_class_name: ResourceClassName,
         new_key: CertifiedKey, // pending key which received a certificate becomes 'new', i.e. it is staged.
+        // When the key state changed, i.e. the start of the staging period, or of
+        // the age of the current key. Not present in events stored by earlier
+        // versions of Krill.
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        changed: Option<Timestamp>,
     },
     KeyPendingToActive {
         // When a new resource class is created it will have a single pending key only which
@@ -633,6 +727,11 @@ pub enum CaEvtDet {
         // first activation.
         resource_class_name: ResourceClassName,
         current_key: CertifiedKey, // there was no current key, pending becomes active without staging when cert is received.
+        // When the key state changed, i.e. the start of the staging period, or of
+        // the age of the current key. Not present in events stored by earlier
+        // versions of Krill.
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        changed: Option<Timestamp>,
     },
     KeyRollActivated {
         // When a 'new' key is activated (becomes current), the previous current key will be
@@ -641,6 +740,11 @@ pub enum CaEvtDet {
         // are tracked in separate `RoasUpdated` and `ChildCertificatesUpdated` events.
         resource_class_name: ResourceClassName,
         revoke_req: RevocationRequest,
+        // When the key state changed, i.e. the start of the staging period, or of
+        // the age of the current key. Not present in events stored by earlier
+        // versions of Krill.
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        changed: Option<Timestamp>,
     },
     KeyRollFinished {
         // The key roll is finished when the parent confirms that the old key is revoked.
@@ -655,6 +759,11 @@ pub enum CaEvtDet {
         resource_class_name: ResourceClassName,
         revoke_req: RevocationRequest,
     },
+    KeyRollPolicyUpdated {
+        // Sets or clears the policy under which the scheduler rolls the keys of
+        // this CA automatically.
+        policy: Option<KeyRollPolicy>,
+    },
 
     // Route Authorizations
     RouteAuthorizationAdded {
@@ -698,6 +807,21 @@ pub enum CaEvtDet {
         // requesting certificates when it knows which URIs it can use.
         contact: RepositoryContact,
     },
+    RepoMirrorAdded {
+        // Adds a secondary repository to which all objects are published in
+        // addition to the primary repository.
+        name: RepositoryMirrorName,
+        contact: RepositoryContact,
+    },
+    RepoMirrorRemoved {
+        name: RepositoryMirrorName,
+    },
+
+    // Rename
+    Renamed {
+        // Records the handle this CA had before it was renamed.
+        previous: Handle,
+    },
 
     // Rta
     //
@@ -759,15 +883,6 @@ impl CaEvtDet {
         StoredEvent::new(handle, version, CaEvtDet::ChildUpdatedIdCert { child, id_cert })
     }
 
-    pub(super) fn child_updated_resources(
-        handle: &Handle,
-        version: u64,
-        child: ChildHandle,
-        resources: ResourceSet,
-    ) -> CaEvt {
-        StoredEvent::new(handle, version, CaEvtDet::ChildUpdatedResources { child, resources })
-    }
-
     pub(super) fn child_certificate_issued(
         handle: &Handle,
         version: u64,
@@ -824,8 +939,20 @@ impl CaEvtDet {
         StoredEvent::new(handle, version, CaEvtDet::ChildRemoved { child })
     }
 
-    pub(super) fn child_suspended(handle: &Handle, version: u64, child: ChildHandle) -> CaEvt {
-        StoredEvent::new(handle, version, CaEvtDet::ChildSuspended { child })
+    pub(super) fn child_suspended(
+        handle: &Handle,
+        version: u64,
+        child: ChildHandle,
+        suspension: ChildSuspension,
+    ) -> CaEvt {
+        StoredEvent::new(
+            handle,
+            version,
+            CaEvtDet::ChildSuspended {
+                child,
+                suspension: Some(suspension),
+            },
+        )
     }
 
     pub(super) fn child_unsuspended(handle: &Handle, version: u64, child: ChildHandle) -> CaEvt {
@@ -842,6 +969,16 @@ impl fmt::Display for CaEvtDet {
                 "turn into TA with key (hash) {}",
                 ta_cert_details.cert().subject_key_identifier()
             ),
+            CaEvtDet::TrustAnchorKeyRollStarted { successor, started } => write!(
+                f,
+                "started TA key roll to successor key {} at {}",
+                successor.cert().subject_key_identifier(),
+                started.to_rfc3339()
+            ),
+            CaEvtDet::TrustAnchorKeyRollActivated { activated } => {
+                write!(f, "activated TA successor key at {}", activated.to_rfc3339())
+            }
+            CaEvtDet::TrustAnchorKeyRollFinished => write!(f, "retired TA predecessor key"),
 
             // Being a parent Events
             CaEvtDet::ChildAdded {
@@ -922,9 +1059,65 @@ impl fmt::Display for CaEvtDet {
                 write!(f, "updated child '{}' resources to '{}'", child, resources)
             }
             CaEvtDet::ChildRemoved { child } => write!(f, "removed child '{}'", child),
-            CaEvtDet::ChildSuspended { child } => write!(f, "suspended child '{}'", child),
+            CaEvtDet::ChildSuspended { child, suspension } => match suspension {
+                Some(suspension) => write!(f, "suspended child '{}': {}", child, suspension.reason()),
+                None => write!(f, "suspended child '{}'", child),
+            },
             CaEvtDet::ChildUnsuspended { child } => write!(f, "unsuspended child '{}'", child),
 
+            // Suspension policy events
+            CaEvtDet::SuspensionPolicyUpdated { policy } => match policy {
+                Some(policy) => write!(f, "set suspension policy for inactive children to: {}", policy),
+                None => write!(f, "removed suspension policy for inactive children"),
+            },
+            CaEvtDet::ChildSuspensionPolicyUpdated { child, policy } => match policy {
+                Some(policy) => write!(f, "set suspension policy for child '{}' to: {}", child, policy),
+                None => write!(f, "removed suspension policy for child '{}'", child),
+            },
+
+            CaEvtDet::ChildTemplateSet { child, template } => match template {
+                Some(template) => write!(f, "set resource template for child '{}' to '{}'", child, template),
+                None => write!(f, "removed resource template for child '{}'", child),
+            },
+
+            // Scheduled resource removal events
+            CaEvtDet::ChildShrinkNoticeSet { child, days } => match days {
+                Some(days) => write!(
+                    f,
+                    "set notice period for resource removals from child '{}' to {} days",
+                    child, days
+                ),
+                None => write!(f, "removed notice period for resource removals from child '{}'", child),
+            },
+            CaEvtDet::ChildShrinkScheduled { child, shrink } => {
+                write!(f, "scheduled removal of resources from child '{}': {}", child, shrink)
+            }
+            CaEvtDet::ChildShrinkCancelled { child } => {
+                write!(f, "cancelled scheduled removal of resources from child '{}'", child)
+            }
+
+            // Resource template events
+            CaEvtDet::ResourceTemplateUpdated { name, resources } => {
+                write!(f, "updated resource template '{}' to '{}'", name, resources)
+            }
+            CaEvtDet::ResourceTemplateRemoved { name } => write!(f, "removed resource template '{}'", name),
+
+            // Child onboarding events
+            CaEvtDet::ChildRequestSubmitted {
+                child,
+                id_cert,
+                submitted,
+                ..
+            } => write!(
+                f,
+                "received request for child '{}' with id (hash) '{}' at {}",
+                child,
+                id_cert.ski_hex(),
+                submitted.to_rfc3339()
+            ),
+            CaEvtDet::ChildRequestApproved { child } => write!(f, "approved request for child '{}'", child),
+            CaEvtDet::ChildRequestRemoved { child } => write!(f, "removed request for child '{}'", child),
+
             // Being a child Events
             CaEvtDet::IdUpdated { id } => write!(f, "updated RFC8183 id to key '{}'", id.key_hash()),
             CaEvtDet::ParentAdded { parent, contact } => {
@@ -978,6 +1171,7 @@ impl fmt::Display for CaEvtDet {
             CaEvtDet::KeyRollPendingKeyAdded {
                 resource_class_name,
                 pending_key_id,
+                ..
             } => {
                 write!(
                     f,
@@ -988,6 +1182,7 @@ impl fmt::Display for CaEvtDet {
             CaEvtDet::KeyPendingToNew {
                 resource_class_name,
                 new_key,
+                ..
             } => write!(
                 f,
                 "key roll: moving pending key '{}' to new state under resource class '{}'",
@@ -997,6 +1192,7 @@ impl fmt::Display for CaEvtDet {
             CaEvtDet::KeyPendingToActive {
                 resource_class_name,
                 current_key,
+                ..
             } => write!(
                 f,
                 "activating pending key '{}' under resource class '{}'",
@@ -1006,6 +1202,7 @@ impl fmt::Display for CaEvtDet {
             CaEvtDet::KeyRollActivated {
                 resource_class_name,
                 revoke_req,
+                ..
             } => write!(
                 f,
                 "key roll: activated new key, requested revocation of '{}' under resource class '{}'",
@@ -1024,6 +1221,10 @@ impl fmt::Display for CaEvtDet {
                 resource_class_name,
                 revoke_req.key()
             ),
+            CaEvtDet::KeyRollPolicyUpdated { policy } => match policy {
+                Some(policy) => write!(f, "key roll: set policy to {}", policy),
+                None => write!(f, "key roll: removed policy, keys are rolled manually"),
+            },
 
             // Route Authorizations
             CaEvtDet::RouteAuthorizationAdded { auth } => write!(f, "added ROA: '{}'", auth),
@@ -1084,6 +1285,18 @@ impl fmt::Display for CaEvtDet {
             CaEvtDet::RepoUpdated { contact } => {
                 write!(f, "updated repository to remote server: {}", contact.service_uri())
             }
+            CaEvtDet::RepoMirrorAdded { name, contact } => {
+                write!(
+                    f,
+                    "added repository mirror '{}' at remote server: {}",
+                    name,
+                    contact.service_uri()
+                )
+            }
+            CaEvtDet::RepoMirrorRemoved { name } => write!(f, "removed repository mirror '{}'", name),
+
+            // Rename
+            CaEvtDet::Renamed { previous } => write!(f, "renamed from '{}'", previous),
 
             // Rta
             CaEvtDet::RtaPrepared { name, prepared } => {
@@ -9,7 +9,7 @@ use crate::{
         api::{
             ActiveInfo, CertifiedKeyInfo, EntitlementClass, Handle, IssuanceRequest, PendingInfo, PendingKeyInfo,
             RcvdCert, RepoInfo, RequestResourceLimit, ResourceClassKeysInfo, ResourceClassName, ResourceSet,
-            RevocationRequest, RollNewInfo, RollOldInfo, RollPendingInfo,
+            RevocationRequest, RollNewInfo, RollOldInfo, RollPendingInfo, Timestamp,
         },
         crypto::KrillSigner,
         error::Error,
@@ -530,6 +530,7 @@ impl KeyState {
                     CaEvtDet::KeyRollPendingKeyAdded {
                         resource_class_name: resource_class_name.clone(),
                         pending_key_id,
+                        changed: Some(Timestamp::now()),
                     },
                     CaEvtDet::CertificateRequested {
                         resource_class_name,
@@ -556,6 +557,7 @@ impl KeyState {
                 Ok(CaEvtDet::KeyRollActivated {
                     resource_class_name,
                     revoke_req,
+                    changed: Some(Timestamp::now()),
                 })
             }
             _ => Err(Error::KeyUseNoNewKey),
@@ -592,3 +594,21 @@ impl KeyState {
         }
     }
 }
+
+//------------ KeyRollStep ---------------------------------------------------
+
+/// The next step of an automated key roll in a resource class.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KeyRollStep {
+    /// The current key is older than the policy allows, a roll should start.
+    Initiate,
+
+    /// The new key is waiting for a certificate from the parent.
+    AwaitCertificate,
+
+    /// The new key was staged long enough and can be activated.
+    Activate { staged_since: Time },
+
+    /// The old key is waiting for the parent to confirm its revocation.
+    AwaitRevocation,
+}
@@ -7,17 +7,27 @@ use tokio::sync::Mutex;
 use bytes::Bytes;
 use chrono::Duration;
 
-use rpki::{repository::crypto::KeyIdentifier, uri};
+use rpki::{
+    repository::{crypto::KeyIdentifier, x509::Time},
+    uri,
+};
 
 use crate::{
     commons::{
         actor::Actor,
         api::{
-            self, AddChildRequest, AspaCustomer, AspaDefinitionList, AspaDefinitionUpdates, AspaProvidersUpdate,
-            Base64, CaCommandDetails, CaCommandResult, CertAuthList, CertAuthSummary, ChildCaInfo, ChildHandle,
-            CommandHistory, CommandHistoryCriteria, Entitlements, Handle, IssuanceRequest, IssuanceResponse, ListReply,
-            ParentCaContact, ParentCaReq, ParentHandle, PublishDelta, RcvdCert, RepositoryContact, ResourceClassName,
-            ResourceSet, RevocationRequest, RevocationResponse, RtaName, StoredEffect, UpdateChildRequest,
+            self, threshold_string, AddChildRequest, AspaCustomer, AspaDefinitionList, AspaDefinitionUpdates,
+            AspaProvidersUpdate, Base64, CaCommandDetails, CaCommandResult, CertAuthList, CertAuthRenameReport,
+            CertAuthRenameStep, CertAuthRenameUpdate, CertAuthSummary, ChildCaInfo, ChildConnectionStats, ChildHandle,
+            ChildInactivityEvent, ChildInactivityNotification, ChildRequestList, ChildRequestReceipt,
+            ChildResourceShrink, ChildShrinkInfo, ChildShrinkList, ChildSuspendReason, ChildSuspensionPolicy,
+            ChildSuspensionThresholds, CommandHistory, CommandHistoryCriteria, Entitlements, Handle, IssuanceRequest,
+            IssuanceResponse, KeyRollBlocker, KeyRollPhase, KeyRollPolicy, KeyRollStatus, ListReply, ParentCaContact,
+            ParentCaReq, ParentHandle, ParentStatuses, PublishDelta, RcvdCert, RepoStatus, RepositoryContact,
+            RepositoryMirror, RepositoryMirrorList, RepositoryMirrorName, ResourceClassName, ResourceSet,
+            ResourceTemplate, ResourceTemplateList, ResourceTemplateName, RevocationRequest, RevocationResponse,
+            RoaChangeType, RoaDefinition, RoaHistory, RoaHistoryChange, RtaName, StoredEffect, TaKeyRollStatus, Token,
+            UndelegatedResources, UpdateChildRequest,
         },
         api::{rrdp::PublishElement, Timestamp},
         crypto::{IdCert, KrillSigner, ProtocolCms, ProtocolCmsBuilder},
@@ -28,30 +38,39 @@ use crate::{
         util::httpclient,
         KrillResult,
     },
-    constants::{CASERVER_DIR, REQUEUE_DELAY_SECONDS, STATUS_DIR},
+    constants::{CASERVER_DIR, KEYROLL_STAGING_HOURS_DFLT, REQUEUE_DELAY_SECONDS, STATUS_DIR, TA_PROXY_DIR},
     daemon::{
         auth::common::permissions::Permission,
         ca::{
-            self, ta_handle, CaObjectsStore, CaStatus, CertAuth, Cmd, CmdDet, DeprecatedRepository, IniDet,
-            ResourceTaggedAttestation, RouteAuthorizationUpdates, RtaContentRequest, RtaPrepareRequest, StatusStore,
+            self, ta_handle, CaEvtDet, CaObjectsStore, CaStatus, CertAuth, ChildDetails, Cmd, CmdDet,
+            DeprecatedRepository, IniDet, KeyRollStep, PendingChild, ResourceTaggedAttestation,
+            RouteAuthorizationUpdates, RtaContentRequest, RtaPrepareRequest, StatusStore,
         },
         config::Config,
         mq::MessageQueue,
     },
     pubd::RepositoryManager,
+    ta::{
+        ProvisioningRequest, TrustAnchorProxy, TrustAnchorProxyCommand, TrustAnchorProxyCommandDetails,
+        TrustAnchorProxyInfo, TrustAnchorProxyInitDetails, TrustAnchorSignedRequest, TrustAnchorSignedResponse,
+        TrustAnchorSignerInfo,
+    },
 };
 
 //------------ CaLocks ------------------------------------------------------
 
-pub struct CaLockMap(HashMap<Handle, tokio::sync::RwLock<()>>);
+pub struct CaLockMap(HashMap<Handle, Arc<tokio::sync::RwLock<()>>>);
 
 impl CaLockMap {
-    fn create_ca_lock(&mut self, ca: &Handle) {
-        self.0.insert(ca.clone(), tokio::sync::RwLock::new(()));
+    fn create_ca_lock(&mut self, ca: &Handle) -> Arc<tokio::sync::RwLock<()>> {
+        self.0
+            .entry(ca.clone())
+            .or_insert_with(|| Arc::new(tokio::sync::RwLock::new(())))
+            .clone()
     }
 
-    fn has_ca(&self, ca: &Handle) -> bool {
-        self.0.contains_key(ca)
+    fn get_ca_lock(&self, ca: &Handle) -> Option<Arc<tokio::sync::RwLock<()>>> {
+        self.0.get(ca).cloned()
     }
 
     fn drop_ca_lock(&mut self, ca: &Handle) {
@@ -65,18 +84,20 @@ impl Default for CaLockMap {
     }
 }
 
-pub struct CaLock<'a> {
-    map: tokio::sync::RwLockReadGuard<'a, CaLockMap>,
-    ca: Handle,
+/// The lock for a single CA. This does not keep the map of all locks locked,
+/// so that a CA lock can be held while the locks of other CAs are created or
+/// dropped, e.g. when a CA is renamed.
+pub struct CaLock {
+    lock: Arc<tokio::sync::RwLock<()>>,
 }
 
-impl CaLock<'_> {
+impl CaLock {
     async fn read(&self) -> tokio::sync::RwLockReadGuard<'_, ()> {
-        self.map.0.get(&self.ca).unwrap().read().await
+        self.lock.read().await
     }
 
     async fn write(&self) -> tokio::sync::RwLockWriteGuard<'_, ()> {
-        self.map.0.get(&self.ca).unwrap().write().await
+        self.lock.write().await
     }
 }
 
@@ -93,22 +114,13 @@ impl Default for CaLocks {
 }
 
 impl CaLocks {
-    async fn ca(&self, ca: &Handle) -> CaLock<'_> {
-        // self.create_lock_if_needed(ca).await;
-        {
-            let map = self.locks.read().await;
-            if map.has_ca(ca) {
-                return CaLock { map, ca: ca.clone() };
-            }
+    async fn ca(&self, ca: &Handle) -> CaLock {
+        if let Some(lock) = self.locks.read().await.get_ca_lock(ca) {
+            return CaLock { lock };
         }
 
-        {
-            let mut lock = self.locks.write().await;
-            lock.create_ca_lock(ca);
-        }
-
-        let map = self.locks.read().await;
-        CaLock { map, ca: ca.clone() }
+        let lock = self.locks.write().await.create_ca_lock(ca);
+        CaLock { lock }
     }
 
     async fn drop_ca(&self, ca: &Handle) {
@@ -122,6 +134,7 @@ impl CaLocks {
 #[derive(Clone)]
 pub struct CaManager {
     ca_store: Arc<AggregateStore<CertAuth>>,
+    ta_proxy_store: Arc<AggregateStore<TrustAnchorProxy>>,
     ca_objects_store: Arc<CaObjectsStore>,
     status_store: Arc<Mutex<StatusStore>>,
     locks: Arc<CaLocks>,
@@ -179,8 +192,12 @@ impl CaManager {
         // are locked individually.
         let locks = Arc::new(CaLocks::default());
 
+        // Create the AggregateStore for the online proxy of an offline Trust Anchor, if used.
+        let ta_proxy_store = AggregateStore::<TrustAnchorProxy>::disk(&config.data_dir, TA_PROXY_DIR)?;
+
         Ok(CaManager {
             ca_store: Arc::new(ca_store),
+            ta_proxy_store: Arc::new(ta_proxy_store),
             ca_objects_store,
             status_store: Arc::new(Mutex::new(status_store)),
             locks,
@@ -212,7 +229,7 @@ impl CaManager {
         let ta_handle = ca::ta_handle();
         let lock = self.locks.ca(&ta_handle).await;
         let _ = lock.write().await;
-        if self.ca_store.has(&ta_handle)? {
+        if self.ca_store.has(&ta_handle)? || self.ta_proxy_store.has(&ta_handle)? {
             Err(Error::TaAlreadyInitialized)
         } else {
             // init normal CA
@@ -264,6 +281,58 @@ impl CaManager {
     pub async fn republish_all(&self) -> KrillResult<Vec<Handle>> {
         self.ca_objects_store.reissue_all()
     }
+
+    /// Republish the given CAs, see `republish_all`.
+    pub async fn republish(&self, cas: &[Handle]) -> KrillResult<Vec<Handle>> {
+        self.ca_objects_store.reissue(cas)
+    }
+}
+
+/// # Embedded Trust Anchor key rolls
+///
+/// The key of the embedded Trust Anchor is rolled in three phases, following
+/// RFC 9691: the successor key is announced in TAK objects, it is activated
+/// after a transition period, and the predecessor key is retired after another
+/// transition period.
+impl CaManager {
+    /// Returns the key roll status of the embedded Trust Anchor.
+    pub async fn ta_keyroll_status(&self) -> KrillResult<TaKeyRollStatus> {
+        self.get_embedded_ta()
+            .await?
+            .ta_keyroll_status(self.config.ta_key_roll_transition_days)
+    }
+
+    /// Creates a successor key and certificate for the embedded Trust Anchor.
+    pub async fn ta_keyroll_init(&self, actor: &Actor) -> KrillResult<()> {
+        self.get_embedded_ta().await?;
+        let cmd = CmdDet::ta_key_roll_init(&ta_handle(), self.signer.clone(), actor);
+        self.send_command(cmd).await?;
+        Ok(())
+    }
+
+    /// Activates the successor key of the embedded Trust Anchor.
+    pub async fn ta_keyroll_activate(&self, actor: &Actor) -> KrillResult<()> {
+        self.get_embedded_ta().await?;
+        let cmd = CmdDet::ta_key_roll_activate(&ta_handle(), self.config.clone(), self.signer.clone(), actor);
+        self.send_command(cmd).await?;
+        Ok(())
+    }
+
+    /// Retires the predecessor key of the embedded Trust Anchor.
+    pub async fn ta_keyroll_finish(&self, actor: &Actor) -> KrillResult<()> {
+        self.get_embedded_ta().await?;
+        let cmd = CmdDet::ta_key_roll_finish(&ta_handle(), self.config.clone(), actor);
+        self.send_command(cmd).await?;
+        Ok(())
+    }
+
+    async fn get_embedded_ta(&self) -> KrillResult<Arc<CertAuth>> {
+        if !self.ca_store.has(&ta_handle())? {
+            Err(Error::TaNotInitialized)
+        } else {
+            self.get_trust_anchor().await
+        }
+    }
 }
 
 /// # CA instances and identity
@@ -309,6 +378,18 @@ impl CaManager {
         ))
     }
 
+    /// Gets the handles of the CAs in the actor's CA list on which it has
+    /// the given permission.
+    pub fn ca_handles_permitted(&self, actor: &Actor, permission: Permission) -> KrillResult<Vec<Handle>> {
+        Ok(self
+            .ca_list(actor)?
+            .cas()
+            .iter()
+            .map(|ca| ca.handle().clone())
+            .filter(|handle| matches!(actor.is_allowed(permission.clone(), handle.clone()), Ok(true)))
+            .collect())
+    }
+
     /// Gets a CA by the given handle, returns an `Err(ServerError::UnknownCA)` if it
     /// does not exist.
     pub async fn get_ca(&self, handle: &Handle) -> KrillResult<Arc<CertAuth>> {
@@ -372,8 +453,12 @@ impl CaManager {
             repos.push(deprecated.into());
         }
 
+        for mirror in ca.repository_mirrors().values() {
+            repos.push(mirror.clone());
+        }
+
         for repo_contact in repos {
-            if self.ca_repo_sync(ca_handle, &repo_contact, vec![]).await.is_err() {
+            if self.ca_repo_sync(ca_handle, &repo_contact, None, vec![]).await.is_err() {
                 info!(
                     "Could not clean up deprecated repository. This is fine - objects there are no longer referenced."
                 );
@@ -388,6 +473,254 @@ impl CaManager {
         Ok(())
     }
 
+    /// Renames a CA. The CA, its objects and its status are copied to the new
+    /// handle, and only removed under the old handle once that succeeded. Tasks
+    /// queued for it are carried over. The keys and objects of the CA are not
+    /// changed by this.
+    ///
+    /// Local child CAs get an updated parent contact, because the service URI
+    /// of the renamed CA changes. If the CA publishes in the local repository,
+    /// then a publisher for the new handle is added there, and the CA moves to
+    /// it using key rolls, so that its base URI will match its new handle.
+    ///
+    /// Remote parties know the CA by the names and service URIs exchanged in
+    /// RFC 8183. The returned report lists the steps that remain to be done
+    /// by the operator to update these.
+    pub async fn ca_rename(
+        &self,
+        handle: &Handle,
+        new_handle: Handle,
+        service_uri: &uri::Https,
+        repo_manager: &Arc<RepositoryManager>,
+        actor: &Actor,
+    ) -> KrillResult<CertAuthRenameReport> {
+        let ta_handle = ta_handle();
+        if handle == &ta_handle {
+            return Err(Error::CaRenameNotAllowed(
+                handle.clone(),
+                "the Trust Anchor cannot be renamed".to_string(),
+            ));
+        } else if new_handle == ta_handle {
+            return Err(Error::CaRenameNotAllowed(
+                handle.clone(),
+                format!("the name '{}' is reserved for the Trust Anchor", ta_handle),
+            ));
+        } else if self.testbed_enabled() && handle == &ca::testbed_ca_handle() {
+            return Err(Error::CaRenameNotAllowed(
+                handle.clone(),
+                "the testbed CA cannot be renamed".to_string(),
+            ));
+        } else if handle == &new_handle {
+            return Err(Error::CaRenameNotAllowed(
+                handle.clone(),
+                "the new name is the same as the current name".to_string(),
+            ));
+        }
+
+        // Hold the locks for both handles during the whole rename, so that no
+        // commands for the CA are processed while it is copied, and none can be
+        // lost when the old handle is removed. They are always taken in the same
+        // order, so that two renames involving the same handles cannot deadlock.
+        // While these are held, commands for either handle are sent to the store
+        // directly, as send_command and get_ca would wait for them forever.
+        let (first, second) = if handle.as_str() < new_handle.as_str() {
+            (handle, &new_handle)
+        } else {
+            (&new_handle, handle)
+        };
+        let first_lock = self.locks.ca(first).await;
+        let _first = first_lock.write().await;
+        let second_lock = self.locks.ca(second).await;
+        let _second = second_lock.write().await;
+
+        if self.has_ca(&new_handle)? {
+            return Err(Error::CaDuplicate(new_handle));
+        }
+
+        let ca = self
+            .ca_store
+            .get_latest(handle)
+            .map_err(|_| Error::CaUnknown(handle.clone()))?;
+
+        warn!(
+            "Renaming CA '{}' to '{}' as requested by: {}",
+            handle, new_handle, actor
+        );
+
+        // Find the local child CAs which use the service URI of this CA, before it changes.
+        let previous_service_uri = Self::rfc6492_service_uri(handle, service_uri);
+        let mut local_children = vec![];
+        for other in self.ca_store.list()? {
+            if &other == handle || other == new_handle {
+                continue;
+            }
+            let other_ca = self.get_ca(&other).await?;
+            for parent in other_ca.parents() {
+                if let Ok(ParentCaContact::Rfc6492(response)) = other_ca.parent(parent) {
+                    if response.service_uri() == &previous_service_uri {
+                        local_children.push((other.clone(), parent.clone(), response.clone()));
+                    }
+                }
+            }
+        }
+
+        // Copy the CA, its objects and its status to the new handle, and record
+        // the rename in its history. Nothing is removed under the old handle until
+        // all of this succeeded, so a failed rename leaves the CA as it was and can
+        // be retried. Copying the aggregate cleans up after itself if it fails.
+        self.ca_store.copy_aggregate(handle, &new_handle)?;
+        if let Err(e) = self.ca_rename_copy_data(handle, &new_handle, actor).await {
+            if let Err(cleanup_err) = self.ca_rename_remove_data(&new_handle).await {
+                error!(
+                    "Could not remove '{}' after renaming CA '{}' failed: {}",
+                    new_handle, handle, cleanup_err
+                );
+            }
+            return Err(e);
+        }
+        if let Err(e) = self.ca_rename_remove_data(handle).await {
+            error!(
+                "CA '{}' was renamed to '{}', but its data under the old name could not be removed: {}",
+                handle, new_handle, e
+            );
+        }
+
+        let mut updated = vec![];
+        let mut next_steps = vec![];
+
+        // Give local children a parent contact with the new service URI.
+        let new_service_uri = Self::rfc6492_service_uri(&new_handle, service_uri);
+        let mut updated_children = vec![];
+        for (child, parent, response) in local_children {
+            let response = rfc8183::ParentResponse::new(
+                response.tag().cloned(),
+                response.id_cert().clone(),
+                new_handle.clone(),
+                response.child_handle().clone(),
+                new_service_uri.clone(),
+            );
+            updated_children.push(response.child_handle().clone());
+
+            let contact = ParentCaContact::for_rfc6492(response);
+            self.send_command(CmdDet::update_parent(&child, parent.clone(), contact, actor))
+                .await?;
+
+            updated.push(CertAuthRenameUpdate::ChildParentContact { child, parent });
+        }
+
+        // Remote children will need a new parent response.
+        for child in ca.children() {
+            if !updated_children.contains(child) {
+                next_steps.push(CertAuthRenameStep::ChildParentResponse { child: child.clone() });
+            }
+        }
+
+        // Parents know this CA by the child handle they gave it. This keeps working.
+        for parent in ca.parents() {
+            if let Ok(ParentCaContact::Rfc6492(response)) = ca.parent(parent) {
+                next_steps.push(CertAuthRenameStep::ParentChildRequest {
+                    parent: parent.clone(),
+                    child: response.child_handle().clone(),
+                });
+            }
+        }
+
+        // Move to a new publisher in the local repository, if that is used.
+        if let Ok(contact) = ca.repository_contact() {
+            let publisher = contact.response().publisher_handle().clone();
+            let is_local = repo_manager
+                .repository_response(&publisher)
+                .map(|response| response.service_uri() == contact.service_uri())
+                .unwrap_or(false);
+
+            if !is_local {
+                next_steps.push(CertAuthRenameStep::RepositoryPublisherRequest { publisher });
+            } else if !ca.repository_mirrors().is_empty() {
+                next_steps.push(CertAuthRenameStep::PublisherNotMoved {
+                    publisher,
+                    reason: "repository mirrors must use the same base URI as the primary repository".to_string(),
+                });
+            } else if repo_manager.get_publisher_details(&new_handle).is_ok() {
+                next_steps.push(CertAuthRenameStep::PublisherNotMoved {
+                    publisher,
+                    reason: format!("a publisher named '{}' already exists", new_handle),
+                });
+            } else {
+                match self
+                    .ca_rename_move_publisher(&new_handle, ca.id_cert(), repo_manager, actor)
+                    .await
+                {
+                    Ok(()) => {
+                        updated.push(CertAuthRenameUpdate::Publisher {
+                            publisher: new_handle.clone(),
+                        });
+                        next_steps.push(CertAuthRenameStep::RemovePublisher { publisher });
+                    }
+                    Err(e) => next_steps.push(CertAuthRenameStep::PublisherNotMoved {
+                        publisher,
+                        reason: e.to_string(),
+                    }),
+                }
+            }
+        }
+
+        Ok(CertAuthRenameReport::new(
+            handle.clone(),
+            new_handle,
+            updated,
+            next_steps,
+        ))
+    }
+
+    /// Copies the objects and status of a renamed CA to its new handle, and
+    /// records the rename in its history.
+    async fn ca_rename_copy_data(&self, handle: &Handle, new_handle: &Handle, actor: &Actor) -> KrillResult<()> {
+        self.ca_objects_store.copy_ca_objects(handle, new_handle)?;
+        self.status_store.lock().await.copy_ca(handle, new_handle).await?;
+        self.ca_store
+            .command(CmdDet::renamed(new_handle, handle.clone(), actor))?;
+        Ok(())
+    }
+
+    /// Removes all data stored for a CA handle when it is renamed: the old
+    /// handle once the rename is done, or the new handle if it failed.
+    async fn ca_rename_remove_data(&self, handle: &Handle) -> KrillResult<()> {
+        self.ca_store.drop_aggregate(handle)?;
+        self.ca_objects_store.remove_ca_objects(handle)?;
+        self.status_store.lock().await.remove_ca(handle).await?;
+        self.locks.drop_ca(handle).await;
+        Ok(())
+    }
+
+    /// Adds a publisher for a renamed CA to the local repository, and moves the
+    /// CA there. The publisher is removed again if the CA cannot be moved. The
+    /// caller holds the lock for the CA.
+    async fn ca_rename_move_publisher(
+        &self,
+        handle: &Handle,
+        id_cert: &IdCert,
+        repo_manager: &Arc<RepositoryManager>,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let request = rfc8183::PublisherRequest::new(None, handle.clone(), id_cert.clone());
+        repo_manager.create_publisher(request, actor)?;
+
+        let contact = RepositoryContact::new(repo_manager.repository_response(handle)?);
+        let cmd = CmdDet::update_repo(handle, contact, self.signer.clone(), actor);
+
+        if let Err(e) = self.ca_store.command(cmd) {
+            if let Err(remove_err) = repo_manager.remove_publisher(handle.clone(), actor) {
+                warn!(
+                    "Could not remove publisher '{}' after failing to move CA there: {}",
+                    handle, remove_err
+                );
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
     /// Re-synchronize the CAs and CaStatus
     ///
     /// - remove any surplus CA status entries
@@ -474,6 +807,44 @@ impl CaManager {
             }
         }
     }
+
+    /// Reconstructs the history of all ROA definition changes for a CA, based
+    /// on the events that followed its ROA update commands.
+    pub async fn ca_roa_history(&self, handle: &Handle) -> KrillResult<RoaHistory> {
+        let ca_lock = self.locks.ca(handle).await;
+        let _lock = ca_lock.read().await;
+
+        let mut crit = CommandHistoryCriteria::default();
+        crit.set_includes(&["cmd-ca-roas-updated"]);
+        crit.set_unlimited_rows();
+
+        let mut history = RoaHistory::default();
+
+        for record in self.ca_store.command_history(handle, crit)?.commands() {
+            let time = Timestamp::new(record.timestamp / 1000);
+            for version in record.effect.events().into_iter().flatten() {
+                let evt: ca::CaEvt = self.ca_store.get_event(handle, *version)?.ok_or_else(|| {
+                    Error::Custom(format!("Cannot find evt: {} in history for CA: {}", version, handle))
+                })?;
+
+                let (change, auth) = match evt.into_details() {
+                    CaEvtDet::RouteAuthorizationAdded { auth } => (RoaChangeType::Added, auth),
+                    CaEvtDet::RouteAuthorizationRemoved { auth } => (RoaChangeType::Removed, auth),
+                    _ => continue,
+                };
+
+                history.push(RoaHistoryChange::new(
+                    time,
+                    record.actor.clone(),
+                    record.key.clone(),
+                    change,
+                    auth.into(),
+                ));
+            }
+        }
+
+        Ok(history)
+    }
 }
 
 /// # CAs as parents
@@ -525,9 +896,7 @@ impl CaManager {
         service_uri: &uri::Https,
     ) -> KrillResult<rfc8183::ParentResponse> {
         let ca = self.get_ca(ca).await?;
-        let service_uri = format!("{}rfc6492/{}", service_uri.to_string(), ca.handle());
-        let service_uri = uri::Https::from_string(service_uri).unwrap();
-        let service_uri = rfc8183::ServiceUri::Https(service_uri);
+        let service_uri = Self::rfc6492_service_uri(ca.handle(), service_uri);
 
         Ok(rfc8183::ParentResponse::new(
             None,
@@ -538,12 +907,23 @@ impl CaManager {
         ))
     }
 
+    /// Returns the RFC 6492 service URI that children use to contact a CA.
+    fn rfc6492_service_uri(ca: &Handle, service_uri: &uri::Https) -> rfc8183::ServiceUri {
+        let service_uri = format!("{}rfc6492/{}", service_uri.to_string(), ca);
+        let service_uri = uri::Https::from_string(service_uri).unwrap();
+        rfc8183::ServiceUri::Https(service_uri)
+    }
+
     /// Update a child under this CA. The submitted `UpdateChildRequest` can contain a
     /// new `IdCert`, or `ResourceSet`, or both. When resources are updated, the existing
     /// resource entitlements are replaced by the new value - i.e. this is not a delta
     /// and it affects all Internet Number Resource (INR) types (IPv4, IPV6, ASN). Setting
     /// resource entitlements beyond the resources held by the parent CA will return
-    /// an `Error::CaChildExtraResources`.
+    /// an `Error::CaChildExtraResources`. When a resource template is given, the child
+    /// is linked to it and gets the resources of the template. The template is applied
+    /// after any explicit resources, so it takes precedence. A notice period for the
+    /// removal of resources is set first, so that it applies to resources removed in
+    /// the same request.
     pub async fn ca_child_update(
         &self,
         ca: &Handle,
@@ -551,20 +931,34 @@ impl CaManager {
         req: UpdateChildRequest,
         actor: &Actor,
     ) -> KrillResult<()> {
-        let (id_opt, resources_opt, suspend_opt) = req.unpack();
+        let (id_opt, resources_opt, suspend_opt, template_opt, shrink_notice_opt) = req.unpack();
 
         if let Some(id) = id_opt {
             self.send_command(CmdDet::child_update_id(ca, child.clone(), id, actor))
                 .await?;
         }
+        if let Some(days) = shrink_notice_opt {
+            let days = if days == 0 { None } else { Some(days) };
+            self.send_command(CmdDet::child_shrink_notice_set(ca, child.clone(), days, actor))
+                .await?;
+        }
         if let Some(resources) = resources_opt {
             self.send_command(CmdDet::child_update_resources(ca, child.clone(), resources, actor))
                 .await?;
         }
+        if let Some(template) = template_opt {
+            self.send_command(CmdDet::child_apply_template(ca, child.clone(), template, actor))
+                .await?;
+        }
         if let Some(suspend) = suspend_opt {
             if suspend {
-                self.send_command(CmdDet::child_suspend_inactive(ca, child, actor))
-                    .await?;
+                self.send_command(CmdDet::child_suspend_inactive(
+                    ca,
+                    child,
+                    ChildSuspendReason::Manual,
+                    actor,
+                ))
+                .await?;
             } else {
                 self.send_command(CmdDet::child_unsuspend(ca, child, actor)).await?;
             }
@@ -581,6 +975,209 @@ impl CaManager {
         Ok(())
     }
 
+    /// Submits a request by a prospective child to be added under this CA. Returns
+    /// a receipt with the one-time token which the child will need to retrieve
+    /// its parent response once the request is approved.
+    pub async fn ca_child_request_submit(
+        &self,
+        ca: &Handle,
+        req: rfc8183::ChildRequest,
+        actor: &Actor,
+    ) -> KrillResult<ChildRequestReceipt> {
+        let (_tag, child, id_cert) = req.unpack();
+
+        let token = PendingChild::random_token()?;
+        let token_hash = PendingChild::hash_token(&token);
+
+        self.send_command(CmdDet::child_request_submit(
+            ca,
+            child.clone(),
+            id_cert,
+            token_hash,
+            actor,
+        ))
+        .await?;
+
+        Ok(ChildRequestReceipt::new(ca.clone(), child, token))
+    }
+
+    /// Lists the pending, and approved but not yet retrieved, child requests for a CA.
+    pub async fn ca_child_requests(&self, ca: &Handle) -> KrillResult<ChildRequestList> {
+        Ok(self.get_ca(ca).await?.child_requests())
+    }
+
+    /// Approves a pending child request, adding the child with the given resources.
+    pub async fn ca_child_request_approve(
+        &self,
+        ca: &Handle,
+        child: ChildHandle,
+        resources: ResourceSet,
+        service_uri: &uri::Https,
+        actor: &Actor,
+    ) -> KrillResult<ParentCaContact> {
+        self.send_command(CmdDet::child_request_approve(ca, child.clone(), resources, actor))
+            .await?;
+
+        self.ca_parent_contact(ca, child, service_uri).await
+    }
+
+    /// Rejects a pending child request.
+    pub async fn ca_child_request_reject(&self, ca: &Handle, child: ChildHandle, actor: &Actor) -> KrillResult<()> {
+        self.send_command(CmdDet::child_request_reject(ca, child, actor))
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the parent response to a child whose request was approved, provided
+    /// that the one-time token matches. The token cannot be used again.
+    pub async fn ca_child_request_complete(
+        &self,
+        ca: &Handle,
+        child: ChildHandle,
+        token: Token,
+        service_uri: &uri::Https,
+        actor: &Actor,
+    ) -> KrillResult<rfc8183::ParentResponse> {
+        self.send_command(CmdDet::child_request_complete(ca, child.clone(), token, actor))
+            .await?;
+
+        self.ca_parent_response(ca, child, service_uri).await
+    }
+
+    /// Returns the resource templates of this CA.
+    pub async fn ca_resource_templates(&self, ca: &Handle) -> KrillResult<ResourceTemplateList> {
+        Ok(self.get_ca(ca).await?.resource_templates())
+    }
+
+    /// Adds or updates a resource template. Children which use the template
+    /// will get the updated resources. Setting resources beyond the resources
+    /// held by the CA will return an `Error::CaResourceTemplateExtraResources`.
+    pub async fn ca_resource_template_update(
+        &self,
+        ca: &Handle,
+        template: ResourceTemplate,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let (name, resources) = template.unpack();
+        self.send_command(CmdDet::resource_template_update(ca, name, resources, actor))
+            .await?;
+        Ok(())
+    }
+
+    /// Removes a resource template, provided that no child uses it.
+    pub async fn ca_resource_template_remove(
+        &self,
+        ca: &Handle,
+        name: ResourceTemplateName,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_command(CmdDet::resource_template_remove(ca, name, actor))
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the resources held by this CA which are not delegated to any child.
+    pub async fn ca_undelegated_resources(&self, ca: &Handle) -> KrillResult<UndelegatedResources> {
+        Ok(self.get_ca(ca).await?.undelegated_resources())
+    }
+
+    /// Sets or clears the suspension policy for inactive children of a CA.
+    pub async fn ca_suspension_policy_update(
+        &self,
+        ca: &Handle,
+        policy: Option<ChildSuspensionPolicy>,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_command(CmdDet::suspension_policy_update(ca, policy, actor))
+            .await?;
+        Ok(())
+    }
+
+    /// Sets or clears the suspension policy for a specific child of a CA.
+    pub async fn ca_child_suspension_policy_update(
+        &self,
+        ca: &Handle,
+        child: ChildHandle,
+        policy: Option<ChildSuspensionPolicy>,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        self.send_command(CmdDet::child_suspension_policy_update(ca, child, policy, actor))
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the scheduled removals of resources from children of this CA, including
+    /// the ROAs of the children which will become invalid, if they are known.
+    pub async fn ca_child_shrinks(&self, ca: &Handle) -> KrillResult<ChildShrinkList> {
+        let ca = self.get_ca(ca).await?;
+
+        let mut shrinks = vec![];
+        for (child_handle, shrink) in ca.child_shrinks() {
+            let child = ca.get_child(child_handle)?;
+            let invalidated_roas = self.child_invalidated_roas(child_handle, child, shrink).await?;
+            shrinks.push(ChildShrinkInfo::new(
+                child_handle.clone(),
+                shrink.clone(),
+                invalidated_roas,
+            ));
+        }
+
+        Ok(ChildShrinkList::new(shrinks))
+    }
+
+    /// Returns the ROAs of a child CA which are valid under its current resources, but
+    /// not under the resources it will have after the shrink. This is only known if the
+    /// child CA lives in this Krill instance, otherwise None is returned.
+    async fn child_invalidated_roas(
+        &self,
+        child_handle: &ChildHandle,
+        child: &ChildDetails,
+        shrink: &ChildResourceShrink,
+    ) -> KrillResult<Option<Vec<RoaDefinition>>> {
+        if !self.has_ca(child_handle)? {
+            return Ok(None);
+        }
+
+        let child_ca = self.get_ca(child_handle).await?;
+        if child_ca.id_cert() != child.id_cert() {
+            // Another CA with the same name, not our child.
+            return Ok(None);
+        }
+
+        let invalidated = child_ca
+            .roa_definitions()
+            .into_iter()
+            .filter(|roa| {
+                let address = roa.as_roa_ip_address();
+                child.resources().contains_roa_address(&address) && !shrink.resources().contains_roa_address(&address)
+            })
+            .collect();
+
+        Ok(Some(invalidated))
+    }
+
+    /// Removes resources from the children of all CAs for which the notice period has passed.
+    pub async fn cas_child_shrinks_enforce_all(&self, actor: &Actor) {
+        match self.ca_store.list() {
+            Err(e) => error!("Could not get CA list for removing resources from children: {}", e),
+            Ok(cas) => {
+                for ca in cas {
+                    if let Err(e) = self.ca_child_shrinks_enforce(&ca, actor).await {
+                        error!("Removing resources from children of CA '{}' failed: {}", ca, e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn ca_child_shrinks_enforce(&self, ca: &Handle, actor: &Actor) -> KrillResult<()> {
+        for child in self.get_ca(ca).await?.child_shrinks_due() {
+            self.send_command(CmdDet::child_shrink_enforce(ca, child, actor))
+                .await?;
+        }
+        Ok(())
+    }
+
     /// Processes an RFC 6492 request sent to this CA:
     /// - parses the message bytes
     /// - validates the request
@@ -593,6 +1190,10 @@ impl CaManager {
         user_agent: Option<String>,
         actor: &Actor,
     ) -> KrillResult<Bytes> {
+        if ca_handle == &ta_handle() && self.ta_proxy_store.has(ca_handle)? {
+            return self.ta_proxy_rfc6492(msg_bytes, actor).await;
+        }
+
         let ca = self.get_ca(ca_handle).await?;
 
         let msg = match ProtocolCms::decode(msg_bytes.as_ref(), false) {
@@ -804,17 +1405,24 @@ impl CaManager {
     ///
     /// Note: this function can be called manually through the API, but is normally
     ///       triggered in the background, every 10 mins by default, or as configured
-    ///       by 'ca_refresh' in the configuration.
+    ///       by 'ca_refresh' in the configuration. Only the CAs that the actor may
+    ///       access are refreshed.
     pub async fn cas_refresh_all(&self, started: Timestamp, actor: &Actor) {
-        if let Ok(cas) = self.ca_store.list() {
-            let mut updates = vec![];
+        if let Ok(cas) = self.ca_list(actor) {
+            let handles: Vec<Handle> = cas.cas().iter().map(|ca| ca.handle().clone()).collect();
+            self.cas_refresh(&handles, started, actor).await;
+        }
+    }
 
-            for ca_handle in cas {
-                updates.push(self.cas_refresh_single(ca_handle, started, actor));
-            }
+    /// Refresh the given CAs, see `cas_refresh_all`.
+    pub async fn cas_refresh(&self, cas: &[Handle], started: Timestamp, actor: &Actor) {
+        let mut updates = vec![];
 
-            join_all(updates).await;
+        for ca in cas {
+            updates.push(self.cas_refresh_single(ca.clone(), started, actor));
         }
+
+        join_all(updates).await;
     }
 
     /// Refresh a single CA with its parents, and possibly suspend inactive children.
@@ -823,48 +1431,184 @@ impl CaManager {
         self.ca_suspend_inactive_children(&ca_handle, started, actor).await;
     }
 
-    /// Suspend child CAs
+    /// Warns about, and suspends, inactive child CAs.
+    ///
+    /// The thresholds for a child follow from its own suspension policy, or the
+    /// policy of the CA, or else the configuration. Children that are exempt are
+    /// never suspended.
     async fn ca_suspend_inactive_children(&self, ca_handle: &Handle, started: Timestamp, actor: &Actor) {
-        // Set threshold hours if it was configured AND this server has been started
-        // longer ago than the hours specified. Otherwise we risk that *all* children
-        // without prior recorded status are suspended on upgrade, or that *all* children
-        // are suspended if the server had been down for more than the threshold hours.
-        let threshold_seconds = self
-            .config
-            .suspend_child_after_inactive_seconds()
-            .filter(|secs| started < Timestamp::now_minus_seconds(*secs));
-
-        // suspend inactive children, if so configured
-        if let Some(threshold_seconds) = threshold_seconds {
-            if let Ok(ca_status) = self.get_ca_status(ca_handle).await {
-                let connections = ca_status.get_children_connection_stats();
-
-                for child in connections.suspension_candidates(threshold_seconds) {
-                    let threshold_string = if threshold_seconds >= 3600 {
-                        format!("{} hours", threshold_seconds / 3600)
-                    } else {
-                        format!("{} seconds", threshold_seconds)
-                    };
-
-                    info!(
-                        "Child '{}' under CA '{}' was inactive for more than {}. Will suspend it.",
-                        child, ca_handle, threshold_string
-                    );
-                    if let Err(e) = self
-                        .status_store
-                        .lock()
-                        .await
-                        .set_child_suspended(ca_handle, &child)
-                        .await
-                    {
-                        panic!("System level error encountered while updating ca status: {}", e);
-                    }
+        let (ca, ca_status) = match (self.get_ca(ca_handle).await, self.get_ca_status(ca_handle).await) {
+            (Ok(ca), Ok(ca_status)) => (ca, ca_status),
+            _ => return,
+        };
 
-                    let req = UpdateChildRequest::suspend();
-                    if let Err(e) = self.ca_child_update(ca_handle, child, req, actor).await {
-                        error!("Could not suspend inactive child, error: {}", e);
-                    }
-                }
+        for (child, status) in ca_status.children() {
+            if ca.get_child(child).is_err() {
+                continue;
+            }
+
+            let thresholds = match self.child_suspension_thresholds(&ca, child) {
+                Some(thresholds) => thresholds,
+                None => {
+                    if status.inactivity_warning().is_some() {
+                        // The child became exempt after it was warned.
+                        if let Err(e) = self
+                            .status_store
+                            .lock()
+                            .await
+                            .clear_child_inactivity_warning(ca_handle, child)
+                            .await
+                        {
+                            panic!("System level error encountered while updating ca status: {}", e);
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            let last_seen = match status.last_exchange() {
+                Some(exchange) => exchange.timestamp(),
+                None => continue,
+            };
+
+            // Only act on a threshold if this server has been started longer ago than
+            // that threshold. Otherwise we risk that *all* children without prior recorded
+            // status are suspended on upgrade, or that *all* children are suspended if the
+            // server had been down for more than the threshold.
+            let exceeds = |seconds: i64| {
+                started < Timestamp::now_minus_seconds(seconds)
+                    && ChildConnectionStats::new(child.clone(), status.last_exchange().cloned(), status.child_state())
+                        .is_suspension_candidate(seconds)
+            };
+
+            let suspend_seconds = thresholds.suspend_seconds();
+
+            if exceeds(suspend_seconds) {
+                self.ca_child_suspend_inactive(ca_handle, child, last_seen, suspend_seconds, actor)
+                    .await;
+            } else if let Some(warn_seconds) = thresholds.warn_seconds() {
+                if status.inactivity_warning().is_none() && exceeds(warn_seconds) {
+                    self.ca_child_warn_inactive(ca_handle, child, last_seen, suspend_seconds)
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// Returns the inactivity thresholds for a child, or None if it is exempt
+    /// from suspension, or suspension is not configured.
+    fn child_suspension_thresholds(&self, ca: &CertAuth, child: &ChildHandle) -> Option<ChildSuspensionThresholds> {
+        match ca.child_suspension_policy(child) {
+            Some(policy) => policy.thresholds(),
+            None => self
+                .config
+                .suspend_child_after_inactive_seconds()
+                .map(|suspend_seconds| {
+                    ChildSuspensionThresholds::new(
+                        suspend_seconds,
+                        self.config.suspend_child_warn_after_inactive_seconds(),
+                    )
+                }),
+        }
+    }
+
+    /// Raises an issue for a child that will be suspended if it stays inactive,
+    /// and notifies the configured webhook, if any.
+    async fn ca_child_warn_inactive(
+        &self,
+        ca_handle: &Handle,
+        child: &ChildHandle,
+        last_seen: Timestamp,
+        suspend_seconds: i64,
+    ) {
+        warn!(
+            "Child '{}' under CA '{}' was last seen at {}. Will suspend it after {} of inactivity.",
+            child,
+            ca_handle,
+            last_seen.to_rfc3339(),
+            threshold_string(suspend_seconds)
+        );
+
+        let issue = Error::CaChildInactive(
+            ca_handle.clone(),
+            child.clone(),
+            format!(
+                "last seen at {}, will be suspended after {}",
+                last_seen.to_rfc3339(),
+                threshold_string(suspend_seconds)
+            ),
+        );
+        if let Err(e) = self
+            .status_store
+            .lock()
+            .await
+            .set_child_inactivity_warning(ca_handle, child, &issue)
+            .await
+        {
+            panic!("System level error encountered while updating ca status: {}", e);
+        }
+
+        self.notify_child_inactivity(ChildInactivityNotification::new(
+            ChildInactivityEvent::Warning,
+            ca_handle.clone(),
+            child.clone(),
+            last_seen,
+            suspend_seconds,
+        ))
+        .await;
+    }
+
+    /// Suspends an inactive child, and notifies the configured webhook, if any.
+    async fn ca_child_suspend_inactive(
+        &self,
+        ca_handle: &Handle,
+        child: &ChildHandle,
+        last_seen: Timestamp,
+        suspend_seconds: i64,
+        actor: &Actor,
+    ) {
+        info!(
+            "Child '{}' under CA '{}' was inactive for more than {}. Will suspend it.",
+            child,
+            ca_handle,
+            threshold_string(suspend_seconds)
+        );
+        if let Err(e) = self
+            .status_store
+            .lock()
+            .await
+            .set_child_suspended(ca_handle, child)
+            .await
+        {
+            panic!("System level error encountered while updating ca status: {}", e);
+        }
+
+        let reason = ChildSuspendReason::Inactive {
+            last_seen,
+            threshold_seconds: suspend_seconds,
+        };
+        let cmd = CmdDet::child_suspend_inactive(ca_handle, child.clone(), reason, actor);
+        if let Err(e) = self.send_command(cmd).await {
+            error!("Could not suspend inactive child, error: {}", e);
+            return;
+        }
+
+        self.notify_child_inactivity(ChildInactivityNotification::new(
+            ChildInactivityEvent::Suspended,
+            ca_handle.clone(),
+            child.clone(),
+            last_seen,
+            suspend_seconds,
+        ))
+        .await;
+    }
+
+    /// Posts a notification about an inactive child to the configured webhook.
+    /// Failures are logged, they do not stop the suspension process.
+    async fn notify_child_inactivity(&self, notification: ChildInactivityNotification) {
+        if let Some(uri) = self.config.suspend_child_webhook() {
+            if let Err(e) = httpclient::post_json(uri.as_str(), &notification, None).await {
+                error!("Could not post notification to '{}' that {}: {}", uri, notification, e);
             }
         }
     }
@@ -1455,17 +2199,43 @@ impl CaManager {
     pub async fn cas_repo_sync_all(&self, actor: &Actor) {
         match self.ca_list(actor) {
             Ok(ca_list) => {
-                for ca in ca_list.cas() {
-                    let ca_handle = ca.handle();
-                    if let Err(e) = self.cas_repo_sync_single(ca_handle).await {
-                        error!(
-                            "Could not synchronize CA '{}' with its repository/-ies. Error: {}",
-                            ca_handle, e
-                        );
+                let handles: Vec<Handle> = ca_list.cas().iter().map(|ca| ca.handle().clone()).collect();
+                self.cas_repo_sync(&handles).await;
+            }
+            Err(e) => error!("Could not get CA list! {}", e),
+        }
+
+        if let Err(e) = self.ta_proxy_repo_sync().await {
+            error!(
+                "Could not synchronize Trust Anchor proxy with its repository. Error: {}",
+                e
+            );
+        }
+    }
+
+    /// Synchronize the given CAs with their repositories and repository mirrors,
+    /// see `cas_repo_sync_all`.
+    pub async fn cas_repo_sync(&self, cas: &[Handle]) {
+        for ca_handle in cas {
+            if let Err(e) = self.cas_repo_sync_single(ca_handle).await {
+                error!(
+                    "Could not synchronize CA '{}' with its repository/-ies. Error: {}",
+                    ca_handle, e
+                );
+            }
+            match self.ca_repo_mirror_names(ca_handle) {
+                Ok(mirrors) => {
+                    for mirror in mirrors {
+                        if let Err(e) = self.ca_repo_mirror_sync(ca_handle, &mirror).await {
+                            error!(
+                                "Could not synchronize CA '{}' with its repository mirror '{}'. Error: {}",
+                                ca_handle, mirror, e
+                            );
+                        }
                     }
                 }
+                Err(e) => error!("Could not get repository mirrors for CA '{}'. Error: {}", ca_handle, e),
             }
-            Err(e) => error!("Could not get CA list! {}", e),
         }
     }
 
@@ -1482,10 +2252,13 @@ impl CaManager {
     /// fail. When there have been 5 failed attempts, then the old repository
     /// is assumed to be unreachable and it will be dropped - i.e. the CA will
     /// no longer try to clean up objects.
+    ///
+    /// Note that repository mirrors are not synchronized here, because they
+    /// have their own status and retry schedule. See `ca_repo_mirror_sync`.
     pub async fn cas_repo_sync_single(&self, ca_handle: &Handle) -> KrillResult<()> {
         // Note that this is a no-op for new CAs which do not yet have any repository configured.
         for (repo_contact, ca_elements) in self.ca_repo_elements(ca_handle).await? {
-            self.ca_repo_sync(ca_handle, &repo_contact, ca_elements).await?;
+            self.ca_repo_sync(ca_handle, &repo_contact, None, ca_elements).await?;
         }
 
         // Clean-up of old repos
@@ -1496,7 +2269,7 @@ impl CaManager {
                 ca_handle
             );
 
-            if let Err(e) = self.ca_repo_sync(ca_handle, deprecated.contact(), vec![]).await {
+            if let Err(e) = self.ca_repo_sync(ca_handle, deprecated.contact(), None, vec![]).await {
                 warn!("Could not clean up deprecated repository: {}", e);
 
                 if deprecated.clean_attempts() < 5 {
@@ -1511,44 +2284,169 @@ impl CaManager {
         Ok(())
     }
 
+    /// Synchronize a CA with one of its repository mirrors, i.e. publish all
+    /// objects which the CA publishes in its current primary repository. This
+    /// is a no-op if the mirror no longer exists, e.g. because it was removed
+    /// while a synchronization was still scheduled.
+    pub async fn ca_repo_mirror_sync(&self, ca_handle: &Handle, mirror: &RepositoryMirrorName) -> KrillResult<()> {
+        let ca_objects = self.ca_objects_store.ca_objects(ca_handle)?;
+
+        match ca_objects.mirrors().get(mirror) {
+            None => {
+                debug!(
+                    "Skip synchronizing removed repository mirror '{}' for CA '{}'",
+                    mirror, ca_handle
+                );
+                Ok(())
+            }
+            Some(contact) => {
+                self.ca_repo_sync(ca_handle, contact, Some(mirror), ca_objects.mirror_publish_elements())
+                    .await
+            }
+        }
+    }
+
+    /// Returns the names of the repository mirrors of a CA.
+    pub fn ca_repo_mirror_names(&self, ca_handle: &Handle) -> KrillResult<Vec<RepositoryMirrorName>> {
+        Ok(self
+            .ca_objects_store
+            .ca_objects(ca_handle)?
+            .mirrors()
+            .keys()
+            .cloned()
+            .collect())
+    }
+
+    /// Returns the repository mirrors of a CA, including their status.
+    pub async fn ca_repo_mirrors(&self, ca_handle: &Handle) -> KrillResult<RepositoryMirrorList> {
+        let ca = self.get_ca(ca_handle).await?;
+        let status = self.get_ca_status(ca_handle).await?;
+
+        let mut mirrors: Vec<RepositoryMirror> = ca
+            .repository_mirrors()
+            .iter()
+            .map(|(name, contact)| {
+                let mirror_status = status.mirrors().get(name).cloned().unwrap_or_default();
+                RepositoryMirror::new(name.clone(), contact.service_uri().clone(), mirror_status)
+            })
+            .collect();
+        mirrors.sort_by(|a, b| a.name().cmp(b.name()));
+
+        Ok(RepositoryMirrorList::new(mirrors))
+    }
+
+    /// Add a repository mirror to a CA. The mirror must respond to a list
+    /// query before it is added. Objects will be published to the mirror
+    /// in the background.
+    pub async fn ca_repo_mirror_add(
+        &self,
+        ca_handle: Handle,
+        name: RepositoryMirrorName,
+        contact: RepositoryContact,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let reply = self
+            .send_rfc8181_and_validate_response(
+                &ca_handle,
+                contact.response(),
+                rfc8181::Message::list_query().into_bytes(),
+            )
+            .await
+            .map_err(|e| Error::CaRepoIssue(ca_handle.clone(), e.to_string()))?;
+
+        match reply {
+            rfc8181::ReplyMessage::ListReply(_) => {
+                let cmd = CmdDet::repo_mirror_add(&ca_handle, name, contact, actor);
+                self.send_command(cmd).await?;
+                Ok(())
+            }
+            rfc8181::ReplyMessage::ErrorReply(e) => {
+                Err(Error::CaRepoIssue(ca_handle, format!("Got error reply: {}", e)))
+            }
+            rfc8181::ReplyMessage::SuccessReply => Err(Error::CaRepoIssue(
+                ca_handle,
+                "Got success reply to list query?!".to_string(),
+            )),
+        }
+    }
+
+    /// Remove a repository mirror from a CA, and try to withdraw all objects
+    /// which were published there. Failure to withdraw the objects is logged,
+    /// but otherwise ignored - they are no longer referenced by anything.
+    pub async fn ca_repo_mirror_remove(
+        &self,
+        ca_handle: Handle,
+        name: RepositoryMirrorName,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let contact = self
+            .get_ca(&ca_handle)
+            .await?
+            .repository_mirrors()
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| Error::CaRepoMirrorUnknown(ca_handle.clone(), name.clone()))?;
+
+        let cmd = CmdDet::repo_mirror_remove(&ca_handle, name.clone(), actor);
+        self.send_command(cmd).await?;
+
+        if let Err(e) = self.ca_repo_sync(&ca_handle, &contact, Some(&name), vec![]).await {
+            info!(
+                "Could not clean up removed repository mirror '{}' for CA '{}'. This is fine - objects there are no longer referenced. Error: {}",
+                name, ca_handle, e
+            );
+        }
+
+        self.status_store.lock().await.remove_mirror(&ca_handle, &name).await
+    }
+
+    /// Synchronize a CA with a repository. The outcome is recorded in the
+    /// status for the primary repository, unless a mirror is given.
     async fn ca_repo_sync(
         &self,
         ca_handle: &Handle,
         repo_contact: &RepositoryContact,
+        mirror: Option<&RepositoryMirrorName>,
         publish_elements: Vec<PublishElement>,
     ) -> KrillResult<()> {
-        let list_reply = self.send_rfc8181_list(ca_handle, repo_contact.response()).await?;
+        let list_reply = self
+            .send_rfc8181_list(ca_handle, repo_contact.response(), mirror)
+            .await?;
 
-        #[allow(clippy::mutable_key_type)]
-        let delta = {
-            let elements: HashMap<_, _> = list_reply.into_elements().into_iter().map(|el| el.unpack()).collect();
+        let delta = Self::publish_delta(list_reply, publish_elements);
 
-            let mut all_objects: HashMap<_, _> = publish_elements.into_iter().map(|el| el.unpack()).collect();
+        self.send_rfc8181_delta(ca_handle, repo_contact.response(), mirror, delta)
+            .await?;
 
-            let mut withdraws = vec![];
-            let mut updates = vec![];
-            for (uri, hash) in elements.into_iter() {
-                match all_objects.remove(&uri) {
-                    Some(base64) => {
-                        if base64.to_encoded_hash() != hash {
-                            updates.push(Update::new(None, uri, base64, hash))
-                        }
+        Ok(())
+    }
+
+    /// Returns the delta needed to get from the objects currently published
+    /// in a repository, according to its list reply, to the given elements.
+    #[allow(clippy::mutable_key_type)]
+    fn publish_delta(list_reply: ListReply, publish_elements: Vec<PublishElement>) -> PublishDelta {
+        let elements: HashMap<_, _> = list_reply.into_elements().into_iter().map(|el| el.unpack()).collect();
+
+        let mut all_objects: HashMap<_, _> = publish_elements.into_iter().map(|el| el.unpack()).collect();
+
+        let mut withdraws = vec![];
+        let mut updates = vec![];
+        for (uri, hash) in elements.into_iter() {
+            match all_objects.remove(&uri) {
+                Some(base64) => {
+                    if base64.to_encoded_hash() != hash {
+                        updates.push(Update::new(None, uri, base64, hash))
                     }
-                    None => withdraws.push(Withdraw::new(None, uri, hash)),
                 }
+                None => withdraws.push(Withdraw::new(None, uri, hash)),
             }
-            let publishes = all_objects
-                .into_iter()
-                .map(|(uri, base64)| Publish::new(None, uri, base64))
-                .collect();
-
-            PublishDelta::new(publishes, updates, withdraws)
-        };
-
-        self.send_rfc8181_delta(ca_handle, repo_contact.response(), delta)
-            .await?;
+        }
+        let publishes = all_objects
+            .into_iter()
+            .map(|(uri, base64)| Publish::new(None, uri, base64))
+            .collect();
 
-        Ok(())
+        PublishDelta::new(publishes, updates, withdraws)
     }
 
     /// Get the current objects for a CA for each repository that it's using.
@@ -1595,7 +2493,7 @@ impl CaManager {
     ) -> KrillResult<()> {
         if check_repo {
             // First verify that this repository can be reached and responds to a list request.
-            self.send_rfc8181_list(&handle, new_contact.response())
+            self.send_rfc8181_list(&handle, new_contact.response(), None)
                 .await
                 .map_err(|e| Error::CaRepoIssue(handle.clone(), e.to_string()))?;
         }
@@ -1608,6 +2506,7 @@ impl CaManager {
         &self,
         ca_handle: &Handle,
         repository: &rfc8183::RepositoryResponse,
+        mirror: Option<&RepositoryMirrorName>,
     ) -> KrillResult<ListReply> {
         let uri = repository.service_uri();
 
@@ -1619,7 +2518,7 @@ impl CaManager {
                 self.status_store
                     .lock()
                     .await
-                    .set_status_repo_failure(ca_handle, uri.clone(), &e)
+                    .set_status_repo_failure(ca_handle, mirror, uri.clone(), &e)
                     .await?;
                 return Err(e);
             }
@@ -1637,7 +2536,7 @@ impl CaManager {
                 self.status_store
                     .lock()
                     .await
-                    .set_status_repo_success(ca_handle, uri.clone(), next_update)
+                    .set_status_repo_success(ca_handle, mirror, uri.clone(), next_update)
                     .await?;
                 Ok(list_reply)
             }
@@ -1646,7 +2545,7 @@ impl CaManager {
                 self.status_store
                     .lock()
                     .await
-                    .set_status_repo_failure(ca_handle, uri.clone(), &err)
+                    .set_status_repo_failure(ca_handle, mirror, uri.clone(), &err)
                     .await?;
                 Err(err)
             }
@@ -1655,7 +2554,7 @@ impl CaManager {
                 self.status_store
                     .lock()
                     .await
-                    .set_status_repo_failure(ca_handle, uri.clone(), &err)
+                    .set_status_repo_failure(ca_handle, mirror, uri.clone(), &err)
                     .await?;
                 Err(err)
             }
@@ -1666,6 +2565,7 @@ impl CaManager {
         &self,
         ca_handle: &Handle,
         repository: &rfc8183::RepositoryResponse,
+        mirror: Option<&RepositoryMirrorName>,
         delta: PublishDelta,
     ) -> KrillResult<()> {
         let message = rfc8181::Message::publish_delta_query(delta);
@@ -1680,7 +2580,7 @@ impl CaManager {
                 self.status_store
                     .lock()
                     .await
-                    .set_status_repo_failure(ca_handle, uri.clone(), &e)
+                    .set_status_repo_failure(ca_handle, mirror, uri.clone(), &e)
                     .await?;
                 return Err(e);
             }
@@ -1693,7 +2593,10 @@ impl CaManager {
                 // We probably should.. though it should be extremely rare and short-lived to
                 // have more than one repository.
                 let ca_objects = self.ca_objects_store.ca_objects(ca_handle)?;
-                let published = ca_objects.all_publish_elements();
+                let published = match mirror {
+                    None => ca_objects.all_publish_elements(),
+                    Some(_) => ca_objects.mirror_publish_elements(),
+                };
                 let next_update = ca_objects
                     .closest_next_update()
                     .unwrap_or_else(|| Timestamp::now_plus_hours(self.config.republish_hours()));
@@ -1701,7 +2604,7 @@ impl CaManager {
                 self.status_store
                     .lock()
                     .await
-                    .set_status_repo_published(ca_handle, uri.clone(), published, next_update)
+                    .set_status_repo_published(ca_handle, mirror, uri.clone(), published, next_update)
                     .await?;
                 Ok(())
             }
@@ -1710,7 +2613,7 @@ impl CaManager {
                 self.status_store
                     .lock()
                     .await
-                    .set_status_repo_failure(ca_handle, uri.clone(), &err)
+                    .set_status_repo_failure(ca_handle, mirror, uri.clone(), &err)
                     .await?;
                 Err(err)
             }
@@ -1719,7 +2622,7 @@ impl CaManager {
                 self.status_store
                     .lock()
                     .await
-                    .set_status_repo_failure(ca_handle, uri.clone(), &err)
+                    .set_status_repo_failure(ca_handle, mirror, uri.clone(), &err)
                     .await?;
                 Err(err)
             }
@@ -1754,6 +2657,312 @@ impl CaManager {
     }
 }
 
+/// # Trust Anchor proxy
+///
+/// The proxy is the online part of an offline Trust Anchor. It uses the
+/// reserved 'ta' handle, and can therefore not be used together with an
+/// embedded Trust Anchor.
+impl CaManager {
+    /// Initializes the Trust Anchor proxy.
+    pub async fn ta_proxy_init(&self) -> KrillResult<()> {
+        let ta_handle = ta_handle();
+        let lock = self.locks.ca(&ta_handle).await;
+        let _ = lock.write().await;
+
+        if self.testbed_enabled() || self.ca_store.has(&ta_handle)? {
+            Err(Error::TaAlreadyInitialized)
+        } else if self.ta_proxy_store.has(&ta_handle)? {
+            Err(Error::TaProxyAlreadyInitialized)
+        } else {
+            let init = TrustAnchorProxyInitDetails::init(&ta_handle, self.signer.deref())?;
+            self.ta_proxy_store.add(init)?;
+            Ok(())
+        }
+    }
+
+    /// Gets the Trust Anchor proxy, or an error if it was not initialized.
+    pub async fn ta_proxy_get(&self) -> KrillResult<Arc<TrustAnchorProxy>> {
+        let ta_handle = ta_handle();
+        let lock = self.locks.ca(&ta_handle).await;
+        let _ = lock.read().await;
+
+        if !self.ta_proxy_store.has(&ta_handle)? {
+            Err(Error::TaProxyNotInitialized)
+        } else {
+            self.ta_proxy_store
+                .get_latest(&ta_handle)
+                .map_err(Error::AggregateStoreError)
+        }
+    }
+
+    /// Send a command to the Trust Anchor proxy.
+    async fn send_ta_proxy_command(&self, cmd: TrustAnchorProxyCommand) -> KrillResult<Arc<TrustAnchorProxy>> {
+        if !self.ta_proxy_store.has(cmd.handle())? {
+            return Err(Error::TaProxyNotInitialized);
+        }
+        let lock = self.locks.ca(cmd.handle()).await;
+        let _ = lock.write().await;
+        self.ta_proxy_store.command(cmd)
+    }
+
+    pub async fn ta_proxy_publisher_request(&self) -> KrillResult<rfc8183::PublisherRequest> {
+        Ok(self.ta_proxy_get().await?.publisher_request())
+    }
+
+    /// Adds the repository for the Trust Anchor proxy. This must be done
+    /// before the signer is initialized, because the Trust Anchor
+    /// certificate includes the repository URIs.
+    pub async fn ta_proxy_repository_update(&self, contact: RepositoryContact, actor: &Actor) -> KrillResult<()> {
+        let proxy = self.ta_proxy_get().await?;
+
+        // First verify that this repository can be reached and responds to a list request.
+        self.send_ta_proxy_rfc8181(&proxy, contact.response(), rfc8181::Message::list_query().into_bytes())
+            .await
+            .map_err(|e| Error::CaRepoIssue(proxy.handle().clone(), e.to_string()))?;
+
+        let cmd = TrustAnchorProxyCommandDetails::add_repository(proxy.handle(), contact, actor);
+        self.send_ta_proxy_command(cmd).await?;
+        Ok(())
+    }
+
+    /// Returns the information needed to initialize the signer.
+    pub async fn ta_proxy_info(&self) -> KrillResult<TrustAnchorProxyInfo> {
+        self.ta_proxy_get().await?.info()
+    }
+
+    pub async fn ta_proxy_signer_add(&self, info: TrustAnchorSignerInfo, actor: &Actor) -> KrillResult<()> {
+        let cmd = TrustAnchorProxyCommandDetails::add_signer(&ta_handle(), info, actor);
+        self.send_ta_proxy_command(cmd).await?;
+        Ok(())
+    }
+
+    pub async fn ta_proxy_signer_info(&self) -> KrillResult<TrustAnchorSignerInfo> {
+        self.ta_proxy_get().await?.get_signer().map(|signer| signer.clone())
+    }
+
+    /// Creates a new request for the signer, including all requests from
+    /// children which are currently pending.
+    pub async fn ta_proxy_signer_make_request(&self, actor: &Actor) -> KrillResult<TrustAnchorSignedRequest> {
+        let cmd = TrustAnchorProxyCommandDetails::make_signer_request(&ta_handle(), self.signer.clone(), actor);
+        let proxy = self.send_ta_proxy_command(cmd).await?;
+        proxy.open_request().map(|exchange| exchange.signed_request.clone())
+    }
+
+    /// Returns the current open request for the signer.
+    pub async fn ta_proxy_signer_get_request(&self) -> KrillResult<TrustAnchorSignedRequest> {
+        let proxy = self.ta_proxy_get().await?;
+        proxy.open_request().map(|exchange| exchange.signed_request.clone())
+    }
+
+    /// Processes the response from the signer, and publishes the updated
+    /// Trust Anchor objects.
+    pub async fn ta_proxy_signer_process_response(
+        &self,
+        signed_response: TrustAnchorSignedResponse,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let proxy = self.ta_proxy_get().await?;
+        let response = signed_response.validate(proxy.get_signer()?.id_cert())?;
+
+        let cmd =
+            TrustAnchorProxyCommandDetails::process_signer_response(proxy.handle(), response, signed_response, actor);
+        self.send_ta_proxy_command(cmd).await?;
+
+        self.ta_proxy_repo_sync().await
+    }
+
+    /// Adds a child to the Trust Anchor and returns the contact that the
+    /// child will need to contact the Trust Anchor proxy.
+    pub async fn ta_proxy_children_add(
+        &self,
+        req: AddChildRequest,
+        service_uri: &uri::Https,
+        actor: &Actor,
+    ) -> KrillResult<ParentCaContact> {
+        info!("Trust Anchor proxy process add child request: {}", &req);
+        let (child_handle, child_res, id_cert) = req.unpack();
+
+        let cmd =
+            TrustAnchorProxyCommandDetails::add_child(&ta_handle(), child_handle.clone(), id_cert, child_res, actor);
+        let proxy = self.send_ta_proxy_command(cmd).await?;
+
+        let service_uri = format!("{}rfc6492/{}", service_uri.to_string(), proxy.handle());
+        let service_uri = uri::Https::from_string(service_uri).unwrap();
+        let service_uri = rfc8183::ServiceUri::Https(service_uri);
+
+        Ok(ParentCaContact::for_rfc6492(rfc8183::ParentResponse::new(
+            None,
+            proxy.id().cert().clone(),
+            proxy.handle().clone(),
+            child_handle,
+            service_uri,
+        )))
+    }
+
+    pub async fn ta_proxy_history(&self, crit: CommandHistoryCriteria) -> KrillResult<CommandHistory> {
+        let proxy = self.ta_proxy_get().await?;
+        Ok(self.ta_proxy_store.command_history(proxy.handle(), crit)?)
+    }
+
+    /// Synchronizes the Trust Anchor proxy with its repository. This is a
+    /// no-op if there is no proxy, or if no signer response was processed yet.
+    pub async fn ta_proxy_repo_sync(&self) -> KrillResult<()> {
+        if !self.ta_proxy_store.has(&ta_handle())? {
+            return Ok(());
+        }
+
+        let proxy = self.ta_proxy_get().await?;
+        let elements = proxy.publish_elements();
+        if elements.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(next_update) = proxy.next_update() {
+            let threshold = Time::now()
+                + Duration::weeks(
+                    self.config
+                        .issuance_timing
+                        .timing_child_certificate_reissue_weeks_before,
+                );
+            if next_update < threshold {
+                warn!(
+                    "Trust Anchor manifest and CRL will expire on {}, please process a new signer request.",
+                    next_update.to_rfc3339()
+                );
+            }
+        }
+
+        let repository = proxy.repository()?.response().clone();
+
+        let list_reply = match self
+            .send_ta_proxy_rfc8181(&proxy, &repository, rfc8181::Message::list_query().into_bytes())
+            .await?
+        {
+            rfc8181::ReplyMessage::ListReply(list_reply) => list_reply,
+            rfc8181::ReplyMessage::SuccessReply => return Err(Error::custom("Got success reply to list query?!")),
+            rfc8181::ReplyMessage::ErrorReply(e) => return Err(Error::Custom(format!("Got error reply: {}", e))),
+        };
+
+        let delta = Self::publish_delta(list_reply, elements);
+        let message = rfc8181::Message::publish_delta_query(delta);
+
+        match self
+            .send_ta_proxy_rfc8181(&proxy, &repository, message.into_bytes())
+            .await?
+        {
+            rfc8181::ReplyMessage::SuccessReply => Ok(()),
+            rfc8181::ReplyMessage::ErrorReply(e) => Err(Error::Custom(format!("Got error reply: {}", e))),
+            rfc8181::ReplyMessage::ListReply(_) => Err(Error::custom("Got list reply to delta query?!")),
+        }
+    }
+
+    async fn send_ta_proxy_rfc8181(
+        &self,
+        proxy: &TrustAnchorProxy,
+        repository: &rfc8183::RepositoryResponse,
+        msg: Bytes,
+    ) -> KrillResult<rfc8181::ReplyMessage> {
+        let cms_logger = CmsLogger::for_rfc8181_sent(self.config.rfc8181_log_dir.as_ref(), proxy.handle());
+
+        let response = self
+            .send_protocol_msg_and_validate(
+                &proxy.id().key_id(),
+                repository.service_uri(),
+                repository.id_cert(),
+                rfc8181::CONTENT_TYPE,
+                msg,
+                Some(&cms_logger),
+            )
+            .await?;
+
+        rfc8181::Message::from_signed_message(&response)
+            .map_err(Error::custom)?
+            .into_reply()
+            .map_err(Error::custom)
+    }
+
+    /// Processes an RFC 6492 request sent to the Trust Anchor proxy.
+    ///
+    /// List requests are answered directly. Issue and revoke requests can
+    /// only be answered directly if the signer already processed them, or
+    /// if there is nothing to do. Otherwise they are queued for the signer,
+    /// and the child is told that the request is scheduled for processing
+    /// (1104), so that it will try again later.
+    async fn ta_proxy_rfc6492(&self, msg_bytes: Bytes, actor: &Actor) -> KrillResult<Bytes> {
+        let proxy = self.ta_proxy_get().await?;
+        let timing = &self.config.issuance_timing;
+
+        let msg = ProtocolCms::decode(msg_bytes.as_ref(), false).map_err(|e| {
+            Error::custom(format!(
+                "Could not decode RFC6492 message for: {}, msg: {}, err: {}",
+                proxy.handle(),
+                Base64::from_content(msg_bytes.as_ref()),
+                e
+            ))
+        })?;
+
+        let content = rfc6492::Message::from_signed_message(&msg)?;
+        msg.validate(proxy.get_child(content.sender())?.id_cert())
+            .map_err(|_| Error::Rfc6492SignatureInvalid)?;
+
+        let (child_handle, recipient, content) = content.unpack();
+
+        let response = match content {
+            rfc6492::Content::Qry(rfc6492::Qry::List) => {
+                let entitlements = proxy.entitlements(&child_handle, timing)?;
+                rfc6492::Message::list_response(child_handle, recipient, entitlements)
+            }
+            rfc6492::Content::Qry(rfc6492::Qry::Issue(req)) => {
+                match proxy.issuance_response(&child_handle, &req, timing)? {
+                    Some(res) => rfc6492::Message::issue_response(child_handle, recipient, res),
+                    None => {
+                        let request = ProvisioningRequest::Issue { request: req };
+                        let cmd = TrustAnchorProxyCommandDetails::add_child_request(
+                            proxy.handle(),
+                            child_handle.clone(),
+                            request,
+                            actor,
+                        );
+                        self.send_ta_proxy_command(cmd).await?;
+                        rfc6492::Message::not_performed_response(
+                            child_handle,
+                            recipient,
+                            rfc6492::NotPerformedResponse::_1104(),
+                        )?
+                    }
+                }
+            }
+            rfc6492::Content::Qry(rfc6492::Qry::Revoke(req)) => {
+                if proxy.get_child(&child_handle)?.issued(req.key()).is_none() {
+                    let res = (&req).into();
+                    rfc6492::Message::revoke_response(child_handle, recipient, res)
+                } else {
+                    let request = ProvisioningRequest::Revoke { request: req };
+                    let cmd = TrustAnchorProxyCommandDetails::add_child_request(
+                        proxy.handle(),
+                        child_handle.clone(),
+                        request,
+                        actor,
+                    );
+                    self.send_ta_proxy_command(cmd).await?;
+                    rfc6492::Message::not_performed_response(
+                        child_handle,
+                        recipient,
+                        rfc6492::NotPerformedResponse::_1104(),
+                    )?
+                }
+            }
+            _ => return Err(Error::custom("Unsupported RFC6492 message")),
+        };
+
+        Ok(
+            ProtocolCmsBuilder::create(&proxy.id().key_id(), self.signer.deref(), response.into_bytes())
+                .map_err(Error::signer)?
+                .as_bytes(),
+        )
+    }
+}
+
 /// # Support sending RFC 6492 and 8181 'protocol' messages, and verifying responses.
 ///
 impl CaManager {
@@ -1985,4 +3194,366 @@ impl CaManager {
         self.send_command(activate_cmd).await?;
         Ok(())
     }
+
+    /// Activate new keys on request of an operator. Unless forced, this is refused if
+    /// any resource class that is rolling its key has a blocker, e.g. because its new
+    /// key is still staged, or the repository did not yet publish its objects.
+    pub async fn ca_keyroll_activate_checked(&self, handle: Handle, force: bool, actor: &Actor) -> KrillResult<()> {
+        if !force {
+            let status = self.ca_keyroll_status(&handle).await?;
+            if let Some(issue) = status.activation_issue() {
+                return Err(Error::KeyRollActivateRefused(handle, issue));
+            }
+        }
+
+        self.ca_keyroll_activate(handle, Duration::seconds(0), actor).await
+    }
+
+    /// Returns the key roll status for each resource class of a CA, including anything
+    /// that blocks the next step of its roll. The staging period and publication check
+    /// follow the key roll policy of the CA, or the RFC 6489 defaults if it has none.
+    pub async fn ca_keyroll_status(&self, handle: &Handle) -> KrillResult<KeyRollStatus> {
+        let ca = self.get_ca(handle).await?;
+        let ca_status = self.get_ca_status(handle).await?;
+
+        let (staging, require_publication) = match ca.keyroll_policy() {
+            Some(policy) => (policy.staging(), policy.require_publication()),
+            None => (Duration::hours(KEYROLL_STAGING_HOURS_DFLT), true),
+        };
+
+        let repo_failure = ca_status.repo().to_failure_opt();
+        let now = Timestamp::now();
+
+        let mut status = ca.keyroll_status();
+        for rc in status.resource_classes_mut() {
+            let parent_failure = ca_status.parents().get(rc.parent()).and_then(|s| s.to_failure_opt());
+
+            match rc.phase() {
+                KeyRollPhase::Active => {}
+                KeyRollPhase::Pending | KeyRollPhase::RollPending => {
+                    rc.add_blocker(KeyRollBlocker::AwaitingCertificate);
+                }
+                KeyRollPhase::RollNew => {
+                    let staged_since = rc.phase_since();
+                    if staged_since + staging > now {
+                        rc.add_blocker(KeyRollBlocker::Staging {
+                            until: staged_since + staging,
+                        });
+                    }
+
+                    let published = ca_status.repo().published_since(staged_since);
+                    rc.set_staged_objects_published(published);
+                    if !published && require_publication {
+                        rc.add_blocker(KeyRollBlocker::AwaitingPublication);
+                    }
+
+                    if let Some(failure) = &repo_failure {
+                        rc.add_blocker(KeyRollBlocker::RepositoryUnreachable {
+                            reason: failure.msg().to_string(),
+                        });
+                    }
+                }
+                KeyRollPhase::RollOld => {
+                    rc.add_blocker(KeyRollBlocker::AwaitingRevocation);
+                }
+            }
+
+            // The parent is needed for certificates and revocations, but not to activate
+            // a new key that is already certified.
+            if rc.phase() != KeyRollPhase::Active && rc.phase() != KeyRollPhase::RollNew {
+                if let Some(failure) = parent_failure {
+                    let parent = rc.parent().clone();
+                    rc.add_blocker(KeyRollBlocker::ParentUnreachable {
+                        parent,
+                        reason: failure.msg().to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(status)
+    }
+
+    /// Set or clear the policy for automated key rolls of a CA.
+    pub async fn ca_keyroll_policy_update(
+        &self,
+        handle: Handle,
+        policy: Option<KeyRollPolicy>,
+        actor: &Actor,
+    ) -> KrillResult<()> {
+        let clear_issue = policy.is_none();
+
+        let cmd = CmdDet::key_roll_policy_update(&handle, policy, actor);
+        self.send_command(cmd).await?;
+
+        if clear_issue {
+            self.status_store.lock().await.clear_keyroll_issue(&handle).await?;
+        }
+        Ok(())
+    }
+
+    /// Drive the automated key rolls for all CAs which have a key roll policy. This
+    /// function is called by the scheduler. It will log issues, but will not fail on
+    /// errors with individual CAs.
+    pub async fn cas_keyroll_auto_all(&self, actor: &Actor) {
+        match self.ca_store.list() {
+            Err(e) => error!("Could not get CA list for automated key rolls: {}", e),
+            Ok(cas) => {
+                for ca in cas {
+                    if let Err(e) = self.ca_keyroll_auto(&ca, actor).await {
+                        error!("Automated key roll for CA '{}' failed: {}", ca, e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Take the next steps in the automated key roll of a CA, if it has a policy.
+    ///
+    /// A roll is initiated when the current key is older than the maximum age, and
+    /// the new key is activated after the staging period, and - if the policy says so -
+    /// after the repository confirmed that the objects of the new key were published.
+    /// Requesting and revoking certificates is then handled by the normal synchronization
+    /// with the parent, triggered by the key roll events.
+    ///
+    /// If the parent or the repository cannot be reached at any step, then the roll
+    /// is paused and a key roll issue is reported for the CA until they can be reached
+    /// again. Otherwise the roll would leave the CA stuck with a key that cannot be
+    /// certified or with objects that cannot be published.
+    async fn ca_keyroll_auto(&self, handle: &Handle, actor: &Actor) -> KrillResult<()> {
+        let ca = self.get_ca(handle).await?;
+        let policy = match ca.keyroll_policy() {
+            Some(policy) => policy.clone(),
+            None => return Ok(()),
+        };
+
+        let status = self.get_ca_status(handle).await?;
+        let steps = ca.keyroll_steps(&policy, Time::now());
+
+        let actions = match Self::keyroll_auto_actions(handle, steps, &policy, status.repo(), status.parents()) {
+            Ok(actions) => actions,
+            Err(issue) => {
+                let error = Error::KeyRollPaused(handle.clone(), issue);
+                warn!("{}", error);
+                return self.status_store.lock().await.set_keyroll_issue(handle, &error).await;
+            }
+        };
+
+        self.status_store.lock().await.clear_keyroll_issue(handle).await?;
+
+        if actions.initiate {
+            info!("Automated key roll: initiating key roll for CA '{}'", handle);
+            self.ca_keyroll_init(handle.clone(), policy.max_age(), actor).await?;
+        }
+
+        if actions.activate {
+            info!("Automated key roll: activating new key(s) for CA '{}'", handle);
+            self.ca_keyroll_activate(handle.clone(), policy.staging(), actor)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Decides what an automated key roll should do now, given the next step
+    /// in each resource class of the CA. Returns the reason why the roll is
+    /// paused instead, if a parent or the repository cannot be reached.
+    fn keyroll_auto_actions(
+        handle: &Handle,
+        steps: Vec<(ResourceClassName, ParentHandle, KeyRollStep)>,
+        policy: &KeyRollPolicy,
+        repo: &RepoStatus,
+        parents: &ParentStatuses,
+    ) -> Result<KeyRollAutoActions, String> {
+        let repo_failure = repo.to_failure_opt();
+
+        let mut initiate = false;
+        let mut activate = false;
+        let mut await_publication = false;
+
+        for (rcn, parent, step) in steps {
+            if let Some(failure) = parents.get(&parent).and_then(|s| s.to_failure_opt()) {
+                return Err(format!("parent '{}' is unreachable: {}", parent, failure));
+            }
+
+            match step {
+                KeyRollStep::Initiate | KeyRollStep::Activate { .. } if repo_failure.is_some() => {
+                    return Err(format!("repository is unreachable: {}", repo_failure.as_ref().unwrap()));
+                }
+                KeyRollStep::Initiate => initiate = true,
+                KeyRollStep::Activate { staged_since } => {
+                    if !policy.require_publication() || repo.published_since(staged_since.into()) {
+                        activate = true;
+                    } else {
+                        debug!(
+                            "Automated key roll for CA '{}' waits for publication of the new key in RC '{}'",
+                            handle, rcn
+                        );
+                        await_publication = true;
+                    }
+                }
+                KeyRollStep::AwaitCertificate | KeyRollStep::AwaitRevocation => {
+                    // Nothing to do, the pending requests are sent when the CA synchronizes
+                    // with its parent.
+                }
+            }
+        }
+
+        // Only activate once all staged keys were published, because activation
+        // applies to all resource classes.
+        Ok(KeyRollAutoActions {
+            initiate,
+            activate: activate && !await_publication,
+        })
+    }
+}
+
+/// The actions an automated key roll of a CA should take.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct KeyRollAutoActions {
+    initiate: bool,
+    activate: bool,
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::TryFrom, str::FromStr};
+
+    use super::*;
+
+    use crate::commons::api::{ErrorResponse, ServiceUri};
+
+    fn uri() -> ServiceUri {
+        ServiceUri::try_from("https://localhost/rfc6492/ca/".to_string()).unwrap()
+    }
+
+    fn error() -> ErrorResponse {
+        ErrorResponse::new("sys-http-client", "connection refused")
+    }
+
+    fn actions(
+        steps: Vec<KeyRollStep>,
+        policy: &KeyRollPolicy,
+        repo: &RepoStatus,
+        parents: &ParentStatuses,
+    ) -> Result<KeyRollAutoActions, String> {
+        let steps = steps
+            .into_iter()
+            .enumerate()
+            .map(|(nr, step)| {
+                (
+                    ResourceClassName::from(nr as u32),
+                    Handle::from_str("parent").unwrap(),
+                    step,
+                )
+            })
+            .collect();
+        CaManager::keyroll_auto_actions(&Handle::from_str("ca").unwrap(), steps, policy, repo, parents)
+    }
+
+    #[test]
+    fn keyroll_auto_actions() {
+        let policy = KeyRollPolicy::new(365, 24, true);
+        let policy_no_publication = KeyRollPolicy::new(365, 24, false);
+
+        let staged_since = Time::from(Timestamp::now_minus_hours(25));
+        let activate = KeyRollStep::Activate { staged_since };
+
+        let mut published = RepoStatus::default();
+        published.set_published(uri(), vec![], Timestamp::now_plus_hours(1));
+
+        let mut unreachable_repo = RepoStatus::default();
+        unreachable_repo.set_failure(uri(), error());
+
+        let parents = ParentStatuses::default();
+        let mut unreachable_parent = ParentStatuses::default();
+        unreachable_parent.set_failure(&Handle::from_str("parent").unwrap(), &uri(), error(), 60);
+
+        // Nothing to do
+        assert_eq!(
+            Ok(KeyRollAutoActions::default()),
+            actions(vec![], &policy, &published, &parents)
+        );
+        assert_eq!(
+            Ok(KeyRollAutoActions::default()),
+            actions(
+                vec![KeyRollStep::AwaitCertificate, KeyRollStep::AwaitRevocation],
+                &policy,
+                &published,
+                &parents
+            )
+        );
+
+        // Initiate a roll for an old key
+        assert_eq!(
+            Ok(KeyRollAutoActions {
+                initiate: true,
+                activate: false
+            }),
+            actions(vec![KeyRollStep::Initiate], &policy, &published, &parents)
+        );
+
+        // Activate a staged key after it was published, or without waiting
+        // for publication if the policy allows it
+        assert_eq!(
+            Ok(KeyRollAutoActions {
+                initiate: false,
+                activate: true
+            }),
+            actions(vec![activate.clone()], &policy, &published, &parents)
+        );
+        assert_eq!(
+            Ok(KeyRollAutoActions::default()),
+            actions(vec![activate.clone()], &policy, &RepoStatus::default(), &parents)
+        );
+        assert_eq!(
+            Ok(KeyRollAutoActions {
+                initiate: false,
+                activate: true
+            }),
+            actions(
+                vec![activate.clone()],
+                &policy_no_publication,
+                &RepoStatus::default(),
+                &parents
+            )
+        );
+
+        // Do not activate any key while one is still waiting for publication
+        let staged_later = KeyRollStep::Activate {
+            staged_since: Time::from(Timestamp::now_plus_hours(1)),
+        };
+        assert_eq!(
+            Ok(KeyRollAutoActions::default()),
+            actions(vec![activate.clone(), staged_later], &policy, &published, &parents)
+        );
+
+        // Pause if the parent or the repository cannot be reached
+        assert!(
+            actions(vec![KeyRollStep::Initiate], &policy, &published, &unreachable_parent)
+                .unwrap_err()
+                .contains("parent 'parent' is unreachable")
+        );
+        assert!(actions(vec![activate.clone()], &policy, &unreachable_repo, &parents)
+            .unwrap_err()
+            .contains("repository is unreachable"));
+        assert!(
+            actions(vec![KeyRollStep::Initiate], &policy, &unreachable_repo, &parents)
+                .unwrap_err()
+                .contains("repository is unreachable")
+        );
+
+        // Waiting for the parent does not need the repository
+        assert_eq!(
+            Ok(KeyRollAutoActions::default()),
+            actions(
+                vec![KeyRollStep::AwaitCertificate],
+                &policy,
+                &unreachable_repo,
+                &parents
+            )
+        );
+    }
 }
@@ -40,6 +40,9 @@ pub use self::rta::*;
 mod status;
 pub use self::status::*;
 
+mod tak;
+pub use self::tak::*;
+
 pub const TA_NAME: &str = "ta"; // reserved for TA
 pub const TESTBED_CA_NAME: &str = "testbed"; // reserved for testbed mode
 
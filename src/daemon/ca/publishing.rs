@@ -26,7 +26,7 @@ use crate::{
     commons::{
         api::{
             rrdp::PublishElement, Base64, Handle, IssuedCert, ObjectName, RcvdCert, RepositoryContact,
-            ResourceClassName, Revocation, Revocations, Timestamp,
+            RepositoryMirrorName, ResourceClassName, Revocation, Revocations, Timestamp,
         },
         crypto::KrillSigner,
         error::Error,
@@ -35,7 +35,7 @@ use crate::{
     },
     constants::CA_OBJECTS_DIR,
     daemon::{
-        ca::{CaEvt, CertAuth, CertifiedKey, ChildCertificateUpdates, RoaUpdates},
+        ca::{CaEvt, CertAuth, CertifiedKey, ChildCertificateUpdates, PublishedTak, RoaUpdates, TrustAnchorKeys},
         config::{Config, IssuanceTimingConfig},
     },
 };
@@ -105,12 +105,14 @@ impl PreSaveEventListener<CertAuth> for CaObjectsStore {
                     super::CaEvtDet::KeyPendingToActive {
                         resource_class_name,
                         current_key,
+                        ..
                     } => {
                         objects.add_class(resource_class_name, current_key, timing, signer)?;
                     }
                     super::CaEvtDet::KeyPendingToNew {
                         resource_class_name,
                         new_key,
+                        ..
                     } => {
                         objects.keyroll_stage(resource_class_name, new_key, timing, signer)?;
                     }
@@ -139,6 +141,17 @@ impl PreSaveEventListener<CertAuth> for CaObjectsStore {
                     super::CaEvtDet::RepoUpdated { contact } => {
                         objects.update_repo(contact);
                     }
+                    super::CaEvtDet::RepoMirrorAdded { name, contact } => {
+                        objects.add_mirror(name, contact);
+                    }
+                    super::CaEvtDet::RepoMirrorRemoved { name } => {
+                        objects.remove_mirror(name);
+                    }
+                    super::CaEvtDet::TrustAnchorKeyRollStarted { .. }
+                    | super::CaEvtDet::TrustAnchorKeyRollActivated { .. }
+                    | super::CaEvtDet::TrustAnchorKeyRollFinished => {
+                        objects.update_taks(&ca.trust_anchor_keys(), timing, signer)?;
+                    }
                     _ => {}
                 }
             }
@@ -217,12 +230,45 @@ impl CaObjectsStore {
             .map_err(Error::KeyValueError)
     }
 
+    /// Copies the objects of a CA to a new handle, when the CA is renamed. The
+    /// objects under the old handle are kept until they are removed explicitly.
+    pub fn copy_ca_objects(&self, from: &Handle, to: &Handle) -> KrillResult<()> {
+        let lock = self.store.write().unwrap();
+
+        let mut objects = lock
+            .get::<CaObjects>(&Self::key(from))
+            .map_err(Error::KeyValueError)?
+            .unwrap_or_else(|| CaObjects::new(to.clone(), None, HashMap::new()));
+        objects.ca = to.clone();
+
+        lock.store(&Self::key(to), &objects).map_err(Error::KeyValueError)?;
+
+        Ok(())
+    }
+
+    /// Removes the objects of a CA, e.g. when they were copied to the new
+    /// handle of a renamed CA.
+    pub fn remove_ca_objects(&self, ca: &Handle) -> KrillResult<()> {
+        self.store
+            .write()
+            .unwrap()
+            .drop_key(&Self::key(ca))
+            .map_err(Error::KeyValueError)
+    }
+
     // Re-issue MFT and CRL for all CAs *if needed*, returns all CAs which were
     // updated.
     pub fn reissue_all(&self) -> KrillResult<Vec<Handle>> {
+        self.reissue(&self.cas()?)
+    }
+
+    // Re-issue MFT and CRL for the given CAs *if needed*, returns all CAs which
+    // were updated. CAs which have no objects yet are skipped.
+    pub fn reissue(&self, cas: &[Handle]) -> KrillResult<Vec<Handle>> {
+        let known = self.cas()?;
         let mut res = vec![];
-        for ca in self.cas()? {
-            self.with_ca_objects(&ca, |objects| {
+        for ca in cas.iter().filter(|ca| known.contains(ca)) {
+            self.with_ca_objects(ca, |objects| {
                 if objects.re_issue_if_required(&self.config.issuance_timing, &self.signer)? {
                     res.push(ca.clone())
                 }
@@ -243,6 +289,9 @@ pub struct CaObjects {
 
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     deprecated_repos: Vec<DeprecatedRepository>,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    mirrors: HashMap<RepositoryMirrorName, RepositoryContact>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -332,6 +381,7 @@ impl CaObjects {
             repo,
             classes,
             deprecated_repos: vec![],
+            mirrors: HashMap::new(),
         }
     }
 
@@ -384,6 +434,27 @@ impl CaObjects {
         closest
     }
 
+    /// Returns the PublishElements for the current repository only. These are
+    /// the elements which are mirrored to secondary repositories.
+    pub fn mirror_publish_elements(&self) -> Vec<PublishElement> {
+        match &self.repo {
+            None => vec![],
+            Some(repo) => self.repo_elements_map().remove(repo).unwrap_or_default(),
+        }
+    }
+
+    pub fn mirrors(&self) -> &HashMap<RepositoryMirrorName, RepositoryContact> {
+        &self.mirrors
+    }
+
+    fn add_mirror(&mut self, name: &RepositoryMirrorName, contact: &RepositoryContact) {
+        self.mirrors.insert(name.clone(), contact.clone());
+    }
+
+    fn remove_mirror(&mut self, name: &RepositoryMirrorName) {
+        self.mirrors.remove(name);
+    }
+
     pub fn deprecated_repos(&self) -> &Vec<DeprecatedRepository> {
         &self.deprecated_repos
     }
@@ -513,6 +584,20 @@ impl CaObjects {
         self.get_class_mut(rcn)?.update_received_cert(cert)
     }
 
+    // Update the TAK objects of a Trust Anchor. Keys which are not included
+    // will withdraw their TAK object, if they had one.
+    fn update_taks(
+        &mut self,
+        taks: &HashMap<KeyIdentifier, TrustAnchorKeys>,
+        timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<()> {
+        for resource_class_objects in self.classes.values_mut() {
+            resource_class_objects.update_taks(taks, timing, signer)?;
+        }
+        Ok(())
+    }
+
     /// Reissue the MFT and CRL in this set if needed, i.e. if it's close to the next
     /// update time, or in case the AIA has changed.. the latter really should not happen,
     /// but ultimately we have no control over this, so better safe.
@@ -641,6 +726,25 @@ impl ResourceClassObjects {
         self.keys.update_received_cert(updated_cert)
     }
 
+    fn update_taks(
+        &mut self,
+        taks: &HashMap<KeyIdentifier, TrustAnchorKeys>,
+        timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<()> {
+        match self.keys.borrow_mut() {
+            ResourceClassKeyState::Current(state) => state.current_set.update_tak(taks, timing, signer),
+            ResourceClassKeyState::Staging(state) => {
+                state.staging_set.update_tak(taks, timing, signer)?;
+                state.current_set.update_tak(taks, timing, signer)
+            }
+            ResourceClassKeyState::Old(state) => {
+                state.old_set.update_tak(taks, timing, signer)?;
+                state.current_set.update_tak(taks, timing, signer)
+            }
+        }
+    }
+
     fn update_roas(
         &mut self,
         roa_updates: &RoaUpdates,
@@ -843,20 +947,33 @@ impl CurrentKeyObjectSet {
         }
     }
 
+    /// Creates a new set with only a manifest and CRL for the given key.
+    pub fn create(key: &CertifiedKey, timing: &IssuanceTimingConfig, signer: &KrillSigner) -> KrillResult<Self> {
+        Ok(BasicKeyObjectSet::create(key, timing, signer)?.into())
+    }
+
     /// Adds all the elements for this set to the map which is passed on. It will use
     /// the default repository unless this key had an old repository set - as part of
     /// repository migration.
     #[allow(clippy::mutable_key_type)]
     fn add_elements(&self, map: &mut HashMap<RepositoryContact, Vec<PublishElement>>, dflt_repo: &RepositoryContact) {
         let repo = self.old_repo.as_ref().unwrap_or(dflt_repo);
+        map.entry(repo.clone())
+            .or_insert_with(Vec::new)
+            .append(&mut self.publish_elements());
+    }
 
+    /// Returns all the elements for this set, i.e. the manifest, CRL and
+    /// all ROAs, ASPAs and certificates it lists.
+    pub fn publish_elements(&self) -> Vec<PublishElement> {
         let base_uri = self.signing_cert.ca_repository();
         let mft_uri = base_uri.join(self.manifest.name().as_bytes()).unwrap();
         let crl_uri = base_uri.join(self.crl.name().as_bytes()).unwrap();
 
-        let elements = map.entry(repo.clone()).or_insert_with(Vec::new);
-        elements.push(PublishElement::new(Base64::from(&self.manifest.0), mft_uri));
-        elements.push(PublishElement::new(Base64::from(&self.crl.0), crl_uri));
+        let mut elements = vec![
+            PublishElement::new(Base64::from(&self.manifest.0), mft_uri),
+            PublishElement::new(Base64::from(&self.crl.0), crl_uri),
+        ];
 
         for (name, roa) in &self.roas {
             elements.push(PublishElement::new(
@@ -878,6 +995,28 @@ impl CurrentKeyObjectSet {
                 base_uri.join(name.as_bytes()).unwrap(),
             ));
         }
+
+        if let Some(tak) = &self.tak {
+            elements.push(PublishElement::new(
+                tak.content().clone(),
+                base_uri.join(tak.name().as_bytes()).unwrap(),
+            ));
+        }
+
+        elements
+    }
+
+    fn update_tak(
+        &mut self,
+        taks: &HashMap<KeyIdentifier, TrustAnchorKeys>,
+        timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<()> {
+        if self.basic.set_tak(taks, timing, signer)? {
+            self.reissue(timing, signer)
+        } else {
+            Ok(())
+        }
     }
 
     fn update_roas(
@@ -923,7 +1062,7 @@ impl CurrentKeyObjectSet {
         self.reissue(timing, signer)
     }
 
-    fn update_certs(
+    pub fn update_certs(
         &mut self,
         cert_updates: &ChildCertificateUpdates,
         timing: &IssuanceTimingConfig,
@@ -961,7 +1100,7 @@ impl CurrentKeyObjectSet {
         self.reissue(timing, signer)
     }
 
-    fn reissue(&mut self, timing: &IssuanceTimingConfig, signer: &KrillSigner) -> KrillResult<()> {
+    pub fn reissue(&mut self, timing: &IssuanceTimingConfig, signer: &KrillSigner) -> KrillResult<()> {
         self.revocations.purge();
 
         self.crl = self.reissue_crl(&self.revocations, timing, signer)?;
@@ -992,12 +1131,14 @@ impl CurrentKeyObjectSet {
             revocations,
             manifest,
             crl,
+            tak: self.tak.clone(),
             old_repo: self.old_repo.clone(),
         })
     }
 
     fn reissue_mft(&self, new_crl: &PublishedCrl, signer: &KrillSigner) -> KrillResult<PublishedManifest> {
         ManifestBuilder::with_objects(new_crl, &self.roas, &self.aspas, &self.certs)
+            .with_tak(self.tak.as_ref())
             .build_new_mft(&self.signing_cert, self.next(), signer)
             .map(|m| m.into())
     }
@@ -1143,6 +1284,8 @@ pub struct BasicKeyObjectSet {
     revocations: Revocations,
     manifest: PublishedManifest,
     crl: PublishedCrl,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    tak: Option<PublishedTak>,
     #[serde(skip_serializing_if = "Option::is_none")]
     old_repo: Option<RepositoryContact>,
 }
@@ -1161,6 +1304,7 @@ impl BasicKeyObjectSet {
             revocations,
             manifest,
             crl,
+            tak: None,
             old_repo: None,
         }
     }
@@ -1179,6 +1323,11 @@ impl BasicKeyObjectSet {
         let elements = map.entry(repo.clone()).or_insert_with(Vec::new);
         elements.push(PublishElement::new(Base64::from(&self.manifest.0), mft_uri));
         elements.push(PublishElement::new(Base64::from(&self.crl.0), crl_uri));
+
+        if let Some(tak) = &self.tak {
+            let tak_uri = base_uri.join(tak.name().as_bytes()).unwrap();
+            elements.push(PublishElement::new(tak.content().clone(), tak_uri));
+        }
     }
 
     fn create(key: &CertifiedKey, timing: &IssuanceTimingConfig, signer: &KrillSigner) -> KrillResult<Self> {
@@ -1238,10 +1387,49 @@ impl BasicKeyObjectSet {
             revocations,
             manifest,
             crl,
+            tak: self.tak.clone(),
             old_repo: self.old_repo.clone(),
         })
     }
 
+    fn update_tak(
+        &mut self,
+        taks: &HashMap<KeyIdentifier, TrustAnchorKeys>,
+        timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<()> {
+        if self.set_tak(taks, timing, signer)? {
+            *self = self.reissue(timing, signer)?;
+        }
+        Ok(())
+    }
+
+    // Sets the TAK object for this key, if it needs to change. Returns
+    // whether there was a change, in which case the manifest and CRL
+    // still need to be re-issued.
+    fn set_tak(
+        &mut self,
+        taks: &HashMap<KeyIdentifier, TrustAnchorKeys>,
+        timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<bool> {
+        let keys = taks.get(&self.signing_cert.subject_key_identifier());
+
+        if self.tak.as_ref().map(|tak| tak.keys()) == keys {
+            return Ok(false);
+        }
+
+        if let Some(old) = self.tak.take() {
+            self.revocations.add(old.revocation());
+        }
+
+        if let Some(keys) = keys {
+            self.tak = Some(keys.sign(&self.signing_cert, timing, signer)?);
+        }
+
+        Ok(true)
+    }
+
     fn reissue_crl(
         &self,
         revocations: &Revocations,
@@ -1259,6 +1447,7 @@ impl BasicKeyObjectSet {
 
     fn reissue_mft(&self, new_crl: &PublishedCrl, signer: &KrillSigner) -> KrillResult<PublishedManifest> {
         ManifestBuilder::with_crl_only(new_crl)
+            .with_tak(self.tak.as_ref())
             .build_new_mft(&self.signing_cert, self.next(), signer)
             .map(|m| m.into())
     }
@@ -1574,6 +1763,15 @@ impl ManifestBuilder {
         }
     }
 
+    /// Adds the TAK object of a Trust Anchor key, if there is one.
+    pub fn with_tak(mut self, tak: Option<&PublishedTak>) -> Self {
+        if let Some(tak) = tak {
+            self.entries
+                .insert(tak.name().clone().into(), mft_hash(tak.to_bytes().as_ref()));
+        }
+        self
+    }
+
     fn build_new_mft(self, signing_cert: &RcvdCert, number: u64, signer: &KrillSigner) -> KrillResult<Manifest> {
         let signing_key = signing_cert.cert().subject_public_key_info();
 
@@ -10,9 +10,9 @@ use rpki::repository::{
 use crate::{
     commons::{
         api::{
-            EntitlementClass, Handle, IssuanceRequest, IssuedCert, ParentHandle, RcvdCert, ReplacedObject, RepoInfo,
-            RequestResourceLimit, ResourceClassInfo, ResourceClassName, ResourceSet, RevocationRequest, SuspendedCert,
-            UnsuspendedCert,
+            EntitlementClass, Handle, IssuanceRequest, IssuedCert, KeyRollPhase, KeyRollPolicy, ParentHandle, RcvdCert,
+            ReplacedObject, RepoInfo, RequestResourceLimit, ResourceClassInfo, ResourceClassKeyRollStatus,
+            ResourceClassName, ResourceSet, RevocationRequest, SuspendedCert, Timestamp, UnsuspendedCert,
         },
         crypto::{CsrInfo, KrillSigner, SignSupport},
         error::Error,
@@ -22,7 +22,7 @@ use crate::{
         ca::events::RoaUpdates,
         ca::{
             self, ta_handle, AspaObjects, AspaObjectsUpdates, CaEvtDet, CertifiedKey, ChildCertificates, CurrentKey,
-            KeyState, NewKey, OldKey, PendingKey, Roas, Routes,
+            KeyRollStep, KeyState, NewKey, OldKey, PendingKey, Roas, Routes,
         },
         config::{Config, IssuanceTimingConfig},
     },
@@ -215,6 +215,7 @@ impl ResourceClass {
                     let mut events = vec![CaEvtDet::KeyPendingToActive {
                         resource_class_name: self.name.clone(),
                         current_key,
+                        changed: Some(Timestamp::now()),
                     }];
 
                     if roa_updates.contains_changes() {
@@ -243,6 +244,7 @@ impl ResourceClass {
                     Ok(vec![CaEvtDet::KeyPendingToNew {
                         resource_class_name: self.name.clone(),
                         new_key,
+                        changed: Some(Timestamp::now()),
                     }])
                 } else {
                     self.update_rcvd_cert_current(handle, current, rcvd_cert, all_routes, all_aspas, config, signer)
@@ -439,48 +441,62 @@ impl ResourceClass {
     }
 
     /// Adds a pending key.
-    pub fn pending_key_id_added(&mut self, key_id: KeyIdentifier) {
+    pub fn pending_key_id_added(&mut self, key_id: KeyIdentifier, changed: Option<Timestamp>) {
         match &self.key_state {
             KeyState::Active(current) => {
                 let pending = PendingKey::new(key_id);
-                self.key_state = KeyState::RollPending(pending, current.clone())
+                self.key_state = KeyState::RollPending(pending, current.clone());
+                self.key_changed(changed);
             }
             _ => panic!("Should never create event to add key when roll in progress"),
         }
     }
 
-    /// Moves a pending key to new
-    pub fn pending_key_to_new(&mut self, new: CertifiedKey) {
+    /// Moves a pending key to new, its staging period starts now.
+    pub fn pending_key_to_new(&mut self, new: CertifiedKey, changed: Option<Timestamp>) {
         match &self.key_state {
             KeyState::RollPending(_pending, current) => {
                 self.key_state = KeyState::RollNew(new, current.clone());
+                self.key_changed(changed);
             }
             _ => panic!("Cannot move pending to new, if state is not roll pending"),
         }
     }
 
     /// Moves a pending key to current
-    pub fn pending_key_to_active(&mut self, new: CertifiedKey) {
+    pub fn pending_key_to_active(&mut self, new: CertifiedKey, changed: Option<Timestamp>) {
         match &self.key_state {
             KeyState::Pending(_pending) => {
                 self.key_state = KeyState::Active(new);
+                self.key_changed(changed);
             }
             _ => panic!("Cannot move pending to active, if state is not pending"),
         }
     }
 
     /// Activates the new key
-    pub fn new_key_activated(&mut self, revoke_req: RevocationRequest) {
+    pub fn new_key_activated(&mut self, revoke_req: RevocationRequest, changed: Option<Timestamp>) {
         match &self.key_state {
             KeyState::RollNew(new, current) => {
                 let old_key = OldKey::new(current.clone(), revoke_req);
                 self.key_state = KeyState::RollOld(new.clone(), old_key);
+                self.key_changed(changed);
             }
             _ => panic!("Should never create event to activate key when no roll in progress"),
         }
     }
 
+    /// Records when the key state changed, as recorded in the event. Events
+    /// stored by earlier versions of Krill do not include this, in which case
+    /// the previous time is kept.
+    fn key_changed(&mut self, changed: Option<Timestamp>) {
+        if let Some(changed) = changed {
+            self.last_key_change = changed.into();
+        }
+    }
+
     /// Removes the old key, we return the to the state where there is one active key.
+    /// The age of the current key is still counted from its activation.
     pub fn old_key_removed(&mut self) {
         match &self.key_state {
             KeyState::RollOld(current, _old) => {
@@ -561,6 +577,74 @@ impl ResourceClass {
         }
     }
 
+    /// Returns the next step of an automated key roll under the given policy, if
+    /// there is anything to do. Note that the age of the current key is counted
+    /// from the moment that it was activated, or certified if it was the first key.
+    pub fn keyroll_step(&self, policy: &KeyRollPolicy, now: Time) -> Option<KeyRollStep> {
+        match &self.key_state {
+            KeyState::Pending(_) => None,
+            KeyState::Active(_) => {
+                if self.last_key_change + policy.max_age() <= now {
+                    Some(KeyRollStep::Initiate)
+                } else {
+                    None
+                }
+            }
+            KeyState::RollPending(_, _) => Some(KeyRollStep::AwaitCertificate),
+            KeyState::RollNew(_, _) => {
+                if self.last_key_change + policy.staging() <= now {
+                    Some(KeyRollStep::Activate {
+                        staged_since: self.last_key_change,
+                    })
+                } else {
+                    None
+                }
+            }
+            KeyState::RollOld(_, _) => Some(KeyRollStep::AwaitRevocation),
+        }
+    }
+
+    /// Returns the key roll status of this resource class. Blockers which depend on
+    /// the status of the parent and repository are left to the caller.
+    pub fn keyroll_status(&self) -> ResourceClassKeyRollStatus {
+        let (phase, current, new, new_certified, old) = match &self.key_state {
+            KeyState::Pending(pending) => (KeyRollPhase::Pending, None, Some(pending.key_id()), false, None),
+            KeyState::Active(current) => (KeyRollPhase::Active, Some(current.key_id()), None, false, None),
+            KeyState::RollPending(pending, current) => (
+                KeyRollPhase::RollPending,
+                Some(current.key_id()),
+                Some(pending.key_id()),
+                false,
+                None,
+            ),
+            KeyState::RollNew(new, current) => (
+                KeyRollPhase::RollNew,
+                Some(current.key_id()),
+                Some(new.key_id()),
+                true,
+                None,
+            ),
+            KeyState::RollOld(current, old) => (
+                KeyRollPhase::RollOld,
+                Some(current.key_id()),
+                None,
+                false,
+                Some(old.key_id()),
+            ),
+        };
+
+        ResourceClassKeyRollStatus::new(
+            self.name.clone(),
+            self.parent_handle.clone(),
+            phase,
+            self.last_key_change.into(),
+            current.cloned(),
+            new.cloned(),
+            new_certified,
+            old.cloned(),
+        )
+    }
+
     /// Finish a key roll, withdraw the old key
     pub fn keyroll_finish(&self) -> KrillResult<CaEvtDet> {
         match &self.key_state {
@@ -589,7 +673,7 @@ impl ResourceClass {
         csr: CsrInfo,
         child_resources: &ResourceSet,
         limit: RequestResourceLimit,
-        issuance_timing: &IssuanceTimingConfig,
+        validity: Validity,
         signer: &KrillSigner,
     ) -> KrillResult<IssuedCert> {
         let signing_key = self.get_current_key()?;
@@ -597,15 +681,7 @@ impl ResourceClass {
         let resources = parent_resources.intersection(child_resources);
         let replaces = self.certificates.get_issued(&csr.key_id()).map(ReplacedObject::from);
 
-        let issued = SignSupport::make_issued_cert(
-            csr,
-            &resources,
-            limit,
-            replaces,
-            signing_key,
-            issuance_timing.timing_child_certificate_valid_weeks,
-            signer,
-        )?;
+        let issued = SignSupport::make_issued_cert(csr, &resources, limit, replaces, signing_key, validity, signer)?;
 
         Ok(issued)
     }
@@ -738,3 +814,124 @@ impl ResourceClass {
         Ok(ee)
     }
 }
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::test;
+
+    use super::*;
+
+    fn certified_key() -> CertifiedKey {
+        let der = include_bytes!("../../../test-resources/ta.cer");
+        let cert = Cert::decode(Bytes::from_static(der)).unwrap();
+        let uri = test::rsync("rsync://localhost/ta/ta.cer");
+        CertifiedKey::create(RcvdCert::new(cert, uri, ResourceSet::all_resources()))
+    }
+
+    #[test]
+    fn keyroll_step() {
+        let policy = KeyRollPolicy::new(10, 24, true);
+        let rcn = ResourceClassName::default();
+        let key = certified_key();
+        let key_id = *key.key_id();
+
+        let mut rc = ResourceClass::create(
+            rcn.clone(),
+            rcn.to_string(),
+            test::handle("parent"),
+            rcn.clone(),
+            key_id,
+        );
+        assert_eq!(None, rc.keyroll_step(&policy, Time::now()));
+
+        // The age of the first key counts from its activation
+        let activated = Timestamp::now_minus_hours(24 * 30);
+        rc.pending_key_to_active(key.clone(), Some(activated));
+        assert_eq!(
+            None,
+            rc.keyroll_step(&policy, Time::from(activated.plus_hours(24 * 10 - 1)))
+        );
+        assert_eq!(
+            Some(KeyRollStep::Initiate),
+            rc.keyroll_step(&policy, Time::from(activated.plus_hours(24 * 10)))
+        );
+
+        rc.pending_key_id_added(key_id, Some(activated.plus_hours(24 * 10)));
+        assert_eq!(
+            Some(KeyRollStep::AwaitCertificate),
+            rc.keyroll_step(&policy, Time::now())
+        );
+
+        // The staging period starts when the new key is certified
+        let staged = activated.plus_hours(24 * 11);
+        rc.pending_key_to_new(key.clone(), Some(staged));
+        assert_eq!(None, rc.keyroll_step(&policy, Time::from(staged.plus_hours(23))));
+        assert_eq!(
+            Some(KeyRollStep::Activate {
+                staged_since: Time::from(staged)
+            }),
+            rc.keyroll_step(&policy, Time::from(staged.plus_hours(24)))
+        );
+
+        let rolled = staged.plus_hours(24);
+        rc.new_key_activated(RevocationRequest::new(rcn, key_id), Some(rolled));
+        assert_eq!(
+            Some(KeyRollStep::AwaitRevocation),
+            rc.keyroll_step(&policy, Time::now())
+        );
+
+        // The age of the new key counts from its activation
+        rc.old_key_removed();
+        assert_eq!(
+            None,
+            rc.keyroll_step(&policy, Time::from(rolled.plus_hours(24 * 10 - 1)))
+        );
+        assert_eq!(
+            Some(KeyRollStep::Initiate),
+            rc.keyroll_step(&policy, Time::from(rolled.plus_hours(24 * 10)))
+        );
+    }
+
+    #[test]
+    fn keyroll_step_uses_time_from_events() {
+        let policy = KeyRollPolicy::new(10, 24, true);
+        let rcn = ResourceClassName::default();
+        let key = certified_key();
+        let key_id = *key.key_id();
+
+        let activated = Timestamp::now_minus_hours(24 * 30);
+        let added = activated.plus_hours(24 * 10);
+        let staged = activated.plus_hours(24 * 11);
+
+        // Applying the same events at different times gives the same state
+        let replay = || {
+            let mut rc = ResourceClass::create(
+                rcn.clone(),
+                rcn.to_string(),
+                test::handle("parent"),
+                rcn.clone(),
+                key_id,
+            );
+            rc.pending_key_to_active(key.clone(), Some(activated));
+            rc.pending_key_id_added(key_id, Some(added));
+            rc.pending_key_to_new(key.clone(), Some(staged));
+            rc
+        };
+        let mut rc = replay();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert_eq!(rc, replay());
+
+        // Events stored by earlier versions have no time, then the time of
+        // the previous change is kept
+        rc.new_key_activated(RevocationRequest::new(rcn.clone(), key_id), None);
+        assert_eq!(Time::from(staged), rc.last_key_change);
+        assert_eq!(
+            Some(KeyRollStep::AwaitRevocation),
+            rc.keyroll_step(&policy, Time::now())
+        );
+    }
+}
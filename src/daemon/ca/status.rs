@@ -5,7 +5,7 @@ use tokio::sync::RwLock;
 use crate::commons::{
     api::{
         rrdp::PublishElement, ChildConnectionStats, ChildHandle, ChildStatus, ChildrenConnectionStats, Entitlements,
-        ErrorResponse, Handle, ParentHandle, ParentStatuses, RepoStatus, Timestamp,
+        ErrorResponse, Handle, ParentHandle, ParentStatuses, RepoStatus, RepositoryMirrorName, Timestamp,
     },
     error::Error,
     eventsourcing::{KeyStoreKey, KeyValueStore},
@@ -19,9 +19,13 @@ use crate::commons::{
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct CaStatus {
     repo: RepoStatus,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default = "HashMap::new")]
+    mirrors: HashMap<RepositoryMirrorName, RepoStatus>,
     parents: ParentStatuses,
     #[serde(skip_serializing_if = "HashMap::is_empty", default = "HashMap::new")]
     children: HashMap<ChildHandle, ChildStatus>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    keyroll_issue: Option<ErrorResponse>,
 }
 
 impl CaStatus {
@@ -42,6 +46,12 @@ impl CaStatus {
         &self.repo
     }
 
+    /// Returns the status of the secondary repositories of this CA. Note that
+    /// there is no entry for a mirror until an exchange with it was attempted.
+    pub fn mirrors(&self) -> &HashMap<RepositoryMirrorName, RepoStatus> {
+        &self.mirrors
+    }
+
     pub fn parents(&self) -> &ParentStatuses {
         &self.parents
     }
@@ -49,14 +59,21 @@ impl CaStatus {
     pub fn children(&self) -> &HashMap<ChildHandle, ChildStatus> {
         &self.children
     }
+
+    /// Returns the reason why an automated key roll is paused, if it is.
+    pub fn keyroll_issue(&self) -> Option<&ErrorResponse> {
+        self.keyroll_issue.as_ref()
+    }
 }
 
 impl Default for CaStatus {
     fn default() -> Self {
         CaStatus {
             repo: RepoStatus::default(),
+            mirrors: HashMap::new(),
             parents: ParentStatuses::default(),
             children: HashMap::new(),
+            keyroll_issue: None,
         }
     }
 }
@@ -190,6 +207,24 @@ impl StatusStore {
             .await
     }
 
+    /// Records a warning that a child will be suspended if it remains inactive. The
+    /// warning is cleared whenever a new success or failure is recorded for the child.
+    pub async fn set_child_inactivity_warning(
+        &self,
+        ca: &Handle,
+        child: &ChildHandle,
+        error: &Error,
+    ) -> KrillResult<()> {
+        let error_response = Self::error_to_error_res(error);
+        self.update_ca_child_status(ca, child, |status| status.set_inactivity_warning(error_response))
+            .await
+    }
+
+    pub async fn clear_child_inactivity_warning(&self, ca: &Handle, child: &ChildHandle) -> KrillResult<()> {
+        self.update_ca_child_status(ca, child, |status| status.clear_inactivity_warning())
+            .await
+    }
+
     /// Adds a child with default status values if the child is missing
     pub async fn set_child_default_if_missing(&self, ca: &Handle, child: &ChildHandle) -> KrillResult<()> {
         self.update_ca_child_status(ca, child, |_status| {}).await
@@ -201,16 +236,25 @@ impl StatusStore {
     pub async fn remove_ca(&self, ca: &Handle) -> KrillResult<()> {
         let mut cache = self.cache.write().await;
 
-        if !cache.contains_key(ca) {
-            Ok(()) // idempotent
-        } else {
-            let scope = ca.as_str();
+        // Dropping a scope that does not exist is a no-op, so this is idempotent. Do not rely
+        // on the cache here: the status may be stored even if it was never loaded.
+        self.store.drop_scope(ca.as_str())?; // will fail in case of I/O errors only
+        cache.remove(ca);
+
+        Ok(())
+    }
+
+    /// Copies the status of a CA to a new handle, when the CA is renamed. The
+    /// status under the old handle is kept until `remove_ca` is called for it.
+    pub async fn copy_ca(&self, from: &Handle, to: &Handle) -> KrillResult<()> {
+        let mut cache = self.cache.write().await;
+
+        let status: CaStatus = self.store.get(&Self::status_key(from))?.unwrap_or_default();
 
-            self.store.drop_scope(scope)?; // will fail in case of I/O errors only
-            cache.remove(ca);
+        self.store.store(&Self::status_key(to), &status)?;
+        cache.insert(to.clone(), Arc::new(status));
 
-            Ok(())
-        }
+        Ok(())
     }
 
     /// Removes a child for the given CA.
@@ -221,33 +265,64 @@ impl StatusStore {
         .await
     }
 
-    pub async fn set_status_repo_failure(&self, ca: &Handle, uri: ServiceUri, error: &Error) -> KrillResult<()> {
+    /// Sets a repository failure, for the primary repository of the CA if
+    /// mirror is None, or else for the given repository mirror.
+    pub async fn set_status_repo_failure(
+        &self,
+        ca: &Handle,
+        mirror: Option<&RepositoryMirrorName>,
+        uri: ServiceUri,
+        error: &Error,
+    ) -> KrillResult<()> {
         let error_response = Self::error_to_error_res(error);
-        self.update_ca_status(ca, |status| status.repo.set_failure(uri, error_response))
+        self.update_ca_repo_status(ca, mirror, |status| status.set_failure(uri, error_response))
             .await
     }
 
     pub async fn set_status_repo_success(
         &self,
         ca: &Handle,
+        mirror: Option<&RepositoryMirrorName>,
         uri: ServiceUri,
         next_update: Timestamp,
     ) -> KrillResult<()> {
-        self.update_ca_status(ca, |status| status.repo.set_last_updated(uri, next_update))
+        self.update_ca_repo_status(ca, mirror, |status| status.set_last_updated(uri, next_update))
             .await
     }
 
     pub async fn set_status_repo_published(
         &self,
         ca: &Handle,
+        mirror: Option<&RepositoryMirrorName>,
         uri: ServiceUri,
         published: Vec<PublishElement>,
         next_update: Timestamp,
     ) -> KrillResult<()> {
-        self.update_ca_status(ca, |status| status.repo.set_published(uri, published, next_update))
+        self.update_ca_repo_status(ca, mirror, |status| status.set_published(uri, published, next_update))
             .await
     }
 
+    /// Removes the status of a repository mirror for the given CA.
+    pub async fn remove_mirror(&self, ca: &Handle, mirror: &RepositoryMirrorName) -> KrillResult<()> {
+        self.update_ca_status(ca, |status| {
+            status.mirrors.remove(mirror);
+        })
+        .await
+    }
+
+    pub async fn set_keyroll_issue(&self, ca: &Handle, error: &Error) -> KrillResult<()> {
+        let error_response = Self::error_to_error_res(error);
+        self.update_ca_status(ca, |status| status.keyroll_issue = Some(error_response))
+            .await
+    }
+
+    pub async fn clear_keyroll_issue(&self, ca: &Handle) -> KrillResult<()> {
+        if self.get_ca_status(ca).await?.keyroll_issue.is_none() {
+            return Ok(());
+        }
+        self.update_ca_status(ca, |status| status.keyroll_issue = None).await
+    }
+
     async fn update_ca_status<F>(&self, ca: &Handle, op: F) -> KrillResult<()>
     where
         F: FnOnce(&mut CaStatus),
@@ -281,6 +356,22 @@ impl StatusStore {
         .await
     }
 
+    async fn update_ca_repo_status<F>(
+        &self,
+        ca: &Handle,
+        mirror: Option<&RepositoryMirrorName>,
+        op: F,
+    ) -> KrillResult<()>
+    where
+        F: FnOnce(&mut RepoStatus),
+    {
+        self.update_ca_status(ca, |status| match mirror {
+            None => op(&mut status.repo),
+            Some(mirror) => op(status.mirrors.entry(mirror.clone()).or_default()),
+        })
+        .await
+    }
+
     fn error_to_error_res(error: &Error) -> ErrorResponse {
         match error {
             Error::HttpClientError(httpclient::Error::ErrorWithJson(_, res)) => res.clone(),
@@ -0,0 +1,240 @@
+//! Trust Anchor Key (TAK) objects, see RFC 9691.
+//!
+//! A TAK object is published under each key of a Trust Anchor during a key
+//! roll. It lists the current key of the TA, and its successor or predecessor,
+//! so that relying parties can learn about the new key and update their TAL.
+use bcder::{encode, encode::Values, Captured, Mode, OctetString, Oid, Tag};
+use bytes::Bytes;
+
+use rpki::repository::{crypto::KeyIdentifier, sigobj::SignedObjectBuilder, x509::Time};
+
+use crate::{
+    commons::{
+        api::{Base64, ObjectName, RcvdCert, Revocation, TaCertDetails},
+        crypto::{KrillSigner, SignSupport},
+        KrillResult,
+    },
+    daemon::config::IssuanceTimingConfig,
+};
+
+/// The content type of TAK objects: id-ct-signedTAL (1.2.840.113549.1.9.16.1.50)
+const SIGNED_TAL_CONTENT_TYPE: &[u8] = &[42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 50];
+
+//------------ TrustAnchorKeys -----------------------------------------------
+
+/// The content of a TAK object: the current key of the Trust Anchor and,
+/// while a key roll is in progress, its predecessor or successor.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorKeys {
+    current: TaCertDetails,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    predecessor: Option<TaCertDetails>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    successor: Option<TaCertDetails>,
+}
+
+impl TrustAnchorKeys {
+    pub fn new(current: TaCertDetails, predecessor: Option<TaCertDetails>, successor: Option<TaCertDetails>) -> Self {
+        TrustAnchorKeys {
+            current,
+            predecessor,
+            successor,
+        }
+    }
+
+    pub fn current_key(&self) -> KeyIdentifier {
+        self.current.cert().subject_key_identifier()
+    }
+
+    /// Returns the DER encoded TAK content.
+    pub fn encode(&self) -> Bytes {
+        Captured::from_values(
+            Mode::Der,
+            encode::sequence((
+                // The version is left out, as it is the default (0).
+                Self::encode_key(&self.current),
+                self.predecessor
+                    .as_ref()
+                    .map(|key| encode::sequence_as(Tag::CTX_0, Self::encode_key(key))),
+                self.successor
+                    .as_ref()
+                    .map(|key| encode::sequence_as(Tag::CTX_1, Self::encode_key(key))),
+            )),
+        )
+        .into_bytes()
+    }
+
+    // TAKey ::= SEQUENCE {
+    //     comments SEQUENCE SIZE (0..MAX) OF UTF8String,
+    //     certificateURIs SEQUENCE SIZE (1..MAX) OF CertificateURI,
+    //     subjectPublicKeyInfo SubjectPublicKeyInfo
+    // }
+    fn encode_key(details: &TaCertDetails) -> impl encode::Values + '_ {
+        let tal = details.tal();
+
+        let mut uris: Vec<String> = tal.uris().iter().map(|uri| uri.to_string()).collect();
+        if let Some(rsync_uri) = tal.rsync_uri() {
+            uris.push(rsync_uri.to_string());
+        }
+
+        let uris: Vec<_> = uris
+            .into_iter()
+            .map(|uri| OctetString::encode_slice_as(Bytes::from(uri), Tag::IA5_STRING))
+            .collect();
+
+        encode::sequence((
+            encode::sequence(Vec::<Captured>::new()), // we do not use comments
+            encode::sequence(uris),
+            details.cert().subject_public_key_info().encode_ref(),
+        ))
+    }
+
+    /// Signs a TAK object for these keys, using the current key. The EE
+    /// certificate uses the same validity time as ROAs.
+    pub fn sign(
+        &self,
+        signing_cert: &RcvdCert,
+        timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<PublishedTak> {
+        let signing_key = self.current_key();
+        let name = ObjectName::new(&signing_key, "tak");
+
+        let crl_uri = signing_cert.crl_uri();
+        let aia = signing_cert.uri();
+        let tak_uri = signing_cert.uri_for_name(&name);
+
+        let mut object_builder = SignedObjectBuilder::new(
+            signer.random_serial()?,
+            SignSupport::sign_validity_weeks(timing.timing_roa_valid_weeks),
+            crl_uri,
+            aia.clone(),
+            tak_uri,
+        );
+        object_builder.set_issuer(Some(signing_cert.cert().subject().clone()));
+        object_builder.set_signing_time(Some(Time::now()));
+
+        let object = signer.sign_object(
+            Oid(Bytes::from_static(SIGNED_TAL_CONTENT_TYPE)),
+            self.encode(),
+            object_builder,
+            &signing_key,
+        )?;
+
+        let revocation = Revocation::from(object.cert());
+        let content = Base64::from_content(object.encode_ref().to_captured(Mode::Der).as_slice());
+
+        Ok(PublishedTak {
+            keys: self.clone(),
+            name,
+            content,
+            revocation,
+        })
+    }
+}
+
+//------------ PublishedTak --------------------------------------------------
+
+/// A signed TAK object, as it is published by a key of the Trust Anchor.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublishedTak {
+    keys: TrustAnchorKeys,
+    name: ObjectName,
+    content: Base64,
+    revocation: Revocation,
+}
+
+impl PublishedTak {
+    pub fn keys(&self) -> &TrustAnchorKeys {
+        &self.keys
+    }
+
+    pub fn name(&self) -> &ObjectName {
+        &self.name
+    }
+
+    pub fn content(&self) -> &Base64 {
+        &self.content
+    }
+
+    pub fn revocation(&self) -> Revocation {
+        self.revocation
+    }
+
+    pub fn to_bytes(&self) -> Bytes {
+        self.content.to_bytes()
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use bcder::decode;
+    use rpki::repository::{cert::Cert, crypto::PublicKey};
+
+    use crate::{
+        commons::api::{ResourceSet, TrustAnchorLocator},
+        test,
+    };
+
+    use super::*;
+
+    fn ta_cert_details(name: &str) -> TaCertDetails {
+        let der = include_bytes!("../../../test-resources/ta.cer");
+        let cert = Cert::decode(Bytes::from_static(der)).unwrap();
+        let uri = test::https(&format!("https://localhost/ta/{}", name));
+        let rsync_uri = Some(test::rsync(&format!("rsync://localhost/ta/{}", name)));
+        let tal = TrustAnchorLocator::new(vec![uri], rsync_uri, &cert);
+        TaCertDetails::new(cert, ResourceSet::all_resources(), tal)
+    }
+
+    fn take_key<S: decode::Source>(cons: &mut decode::Constructed<S>) -> Result<Vec<Bytes>, S::Err> {
+        cons.take_sequence(|cons| {
+            cons.take_sequence(|cons| cons.skip_all())?;
+            let uris = cons.take_sequence(|cons| {
+                let mut uris = vec![];
+                while let Some(uri) = cons.take_opt_value_if(Tag::IA5_STRING, OctetString::from_content)? {
+                    uris.push(uri.to_bytes());
+                }
+                Ok(uris)
+            })?;
+            PublicKey::take_from(cons)?;
+            Ok(uris)
+        })
+    }
+
+    #[test]
+    fn encode_tak_with_successor() {
+        let keys = TrustAnchorKeys::new(ta_cert_details("ta.cer"), None, Some(ta_cert_details("next.cer")));
+
+        let (current, predecessor, successor) = Mode::Der
+            .decode(keys.encode(), |cons| {
+                cons.take_sequence(|cons| {
+                    let current = take_key(cons)?;
+                    let predecessor = cons.take_opt_constructed_if(Tag::CTX_0, take_key)?;
+                    let successor = cons.take_opt_constructed_if(Tag::CTX_1, take_key)?;
+                    Ok((current, predecessor, successor))
+                })
+            })
+            .unwrap();
+
+        assert_eq!(
+            current,
+            vec![
+                Bytes::from_static(b"https://localhost/ta/ta.cer"),
+                Bytes::from_static(b"rsync://localhost/ta/ta.cer")
+            ]
+        );
+        assert!(predecessor.is_none());
+        assert_eq!(
+            successor.unwrap(),
+            vec![
+                Bytes::from_static(b"https://localhost/ta/next.cer"),
+                Bytes::from_static(b"rsync://localhost/ta/next.cer")
+            ]
+        );
+    }
+}
@@ -28,7 +28,12 @@ use crate::{
 };
 
 #[cfg(feature = "multi-user")]
-use crate::daemon::auth::providers::{config_file::config::ConfigAuthUsers, openid_connect::ConfigAuthOpenIDConnect};
+use crate::daemon::auth::providers::{
+    client_cert::config::ConfigAuthClientCerts,
+    config_file::config::{ConfigAuthUsers, ConfigPasswordPolicy},
+    ldap::config::ConfigAuthLdap,
+    openid_connect::ConfigAuthOpenIDConnect,
+};
 
 //------------ ConfigDefaults ------------------------------------------------
 
@@ -78,6 +83,10 @@ impl ConfigDefaults {
         "daemon".to_string()
     }
 
+    fn audit_log_type() -> AuditLogType {
+        AuditLogType::None
+    }
+
     fn auth_type() -> AuthType {
         AuthType::AdminToken
     }
@@ -113,6 +122,14 @@ impl ConfigDefaults {
         25
     }
 
+    fn ta_key_roll_transition_days() -> i64 {
+        30
+    }
+
+    fn child_onboarding() -> ChildOnboardingMode {
+        ChildOnboardingMode::Disabled
+    }
+
     fn post_limit_api() -> u64 {
         256 * 1024 // 256kB
     }
@@ -133,6 +150,14 @@ impl ConfigDefaults {
         None
     }
 
+    fn bgp_source() -> BgpSourceType {
+        BgpSourceType::RisDumps
+    }
+
+    fn bgp_refresh_minutes() -> i64 {
+        BGP_RIS_REFRESH_MINUTES
+    }
+
     fn bgp_risdumps_enabled() -> bool {
         true
     }
@@ -243,6 +268,11 @@ pub struct Config {
     #[serde(default = "ConfigDefaults::syslog_facility")]
     syslog_facility: String,
 
+    #[serde(default = "ConfigDefaults::audit_log_type")]
+    pub audit_log_type: AuditLogType,
+
+    audit_log_file: Option<PathBuf>,
+
     #[serde(default = "ConfigDefaults::admin_token", alias = "auth_token")]
     pub admin_token: Token,
 
@@ -260,9 +290,19 @@ pub struct Config {
     #[cfg(feature = "multi-user")]
     pub auth_users: Option<ConfigAuthUsers>,
 
+    #[cfg(feature = "multi-user")]
+    #[serde(default)]
+    pub auth_password_policy: ConfigPasswordPolicy,
+
     #[cfg(feature = "multi-user")]
     pub auth_openidconnect: Option<ConfigAuthOpenIDConnect>,
 
+    #[cfg(feature = "multi-user")]
+    pub auth_ldap: Option<ConfigAuthLdap>,
+
+    #[cfg(feature = "multi-user")]
+    pub auth_client_certs: Option<ConfigAuthClientCerts>,
+
     #[serde(default = "ConfigDefaults::ca_refresh_seconds", alias = "ca_refresh")]
     pub ca_refresh_seconds: u32,
 
@@ -273,6 +313,12 @@ pub struct Config {
     suspend_child_after_inactive_seconds: Option<i64>,
     suspend_child_after_inactive_hours: Option<i64>,
 
+    #[serde(skip)]
+    suspend_child_warn_after_inactive_seconds: Option<i64>,
+    suspend_child_warn_after_inactive_hours: Option<i64>,
+
+    suspend_child_webhook: Option<uri::Https>,
+
     #[serde(default = "ConfigDefaults::post_limit_api")]
     pub post_limit_api: u64,
 
@@ -288,6 +334,15 @@ pub struct Config {
     #[serde(default = "ConfigDefaults::rfc6492_log_dir")]
     pub rfc6492_log_dir: Option<PathBuf>,
 
+    // BGP announcements source
+    #[serde(default = "ConfigDefaults::bgp_source")]
+    pub bgp_source: BgpSourceType,
+    pub bgp_source_file: Option<PathBuf>,
+    pub bgp_bmp_listen: Option<SocketAddr>,
+    #[serde(default = "ConfigDefaults::bgp_refresh_minutes")]
+    pub bgp_refresh_minutes: i64,
+    bgp_min_peers: Option<u32>,
+
     // RIS BGP
     #[serde(default = "ConfigDefaults::bgp_risdumps_enabled")]
     pub bgp_risdumps_enabled: bool,
@@ -312,6 +367,15 @@ pub struct Config {
     #[serde(flatten)]
     pub metrics: MetricsConfig,
 
+    #[serde(flatten)]
+    pub login_throttle: LoginThrottleConfig,
+
+    #[serde(default = "ConfigDefaults::ta_key_roll_transition_days")]
+    pub ta_key_roll_transition_days: i64,
+
+    #[serde(default = "ConfigDefaults::child_onboarding")]
+    pub child_onboarding: ChildOnboardingMode,
+
     pub testbed: Option<TestBed>,
 }
 
@@ -338,6 +402,25 @@ pub struct IssuanceTimingConfig {
 }
 
 impl IssuanceTimingConfig {
+    /// Creates the timing used by an offline Trust Anchor signer. Its
+    /// manifest and CRL are only re-issued when an operator processes a
+    /// request, so they are given a next update time in weeks rather than
+    /// hours. Jitter is kept to a single hour.
+    pub fn for_ta_signer(mft_next_update_weeks: i64, child_certificate_valid_weeks: i64) -> Self {
+        IssuanceTimingConfig {
+            timing_publish_next_hours: mft_next_update_weeks * 7 * 24,
+            timing_publish_next_jitter_hours: 1,
+            timing_publish_hours_before_next: ConfigDefaults::timing_publish_hours_before_next(),
+            timing_child_certificate_valid_weeks: child_certificate_valid_weeks,
+            timing_child_certificate_reissue_weeks_before:
+                ConfigDefaults::timing_child_certificate_reissue_weeks_before(),
+            timing_roa_valid_weeks: ConfigDefaults::timing_roa_valid_weeks(),
+            timing_roa_reissue_weeks_before: ConfigDefaults::timing_roa_reissue_weeks_before(),
+            timing_aspa_valid_weeks: ConfigDefaults::timing_aspa_valid_weeks(),
+            timing_aspa_reissue_weeks_before: ConfigDefaults::timing_aspa_reissue_weeks_before(),
+        }
+    }
+
     /// Returns the next update time based on configuration:
     ///
     /// now + timing_publish_next_hours + random(0..timing_publish_next_jitter_hours)
@@ -416,6 +499,82 @@ impl RepositoryRetentionConfig {
     }
 }
 
+/// Throttling of failed login and API authentication attempts, per user
+/// name and per source address.
+///
+/// The source address is taken from the headers set by a proxy server, if
+/// the request comes from one of the trusted proxies. The same address is
+/// recorded in the audit log.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LoginThrottleConfig {
+    #[serde(default = "LoginThrottleConfig::dflt_auth_throttle_enabled")]
+    pub auth_throttle_enabled: bool,
+    #[serde(default = "LoginThrottleConfig::dflt_auth_throttle_free_failures")]
+    pub auth_throttle_free_failures: u32,
+    #[serde(default = "LoginThrottleConfig::dflt_auth_throttle_base_delay_seconds")]
+    pub auth_throttle_base_delay_seconds: u64,
+    #[serde(default = "LoginThrottleConfig::dflt_auth_throttle_max_delay_seconds")]
+    pub auth_throttle_max_delay_seconds: u64,
+    #[serde(default = "LoginThrottleConfig::dflt_auth_throttle_lockout_failures")]
+    pub auth_throttle_lockout_failures: u32,
+    #[serde(default = "LoginThrottleConfig::dflt_auth_throttle_lockout_seconds")]
+    pub auth_throttle_lockout_seconds: u64,
+    #[serde(default = "LoginThrottleConfig::dflt_auth_throttle_window_seconds")]
+    pub auth_throttle_window_seconds: u64,
+    #[serde(default)]
+    pub auth_trusted_proxies: Vec<IpAddr>,
+}
+
+impl LoginThrottleConfig {
+    fn dflt_auth_throttle_enabled() -> bool {
+        true
+    }
+
+    // Allow a few typos before slowing down further attempts.
+    fn dflt_auth_throttle_free_failures() -> u32 {
+        3
+    }
+
+    // The delay after the first failure beyond the free failures. This
+    // doubles with every further failure.
+    fn dflt_auth_throttle_base_delay_seconds() -> u64 {
+        1
+    }
+
+    fn dflt_auth_throttle_max_delay_seconds() -> u64 {
+        60
+    }
+
+    // Lock out after this many failures in a row, for the lockout time.
+    fn dflt_auth_throttle_lockout_failures() -> u32 {
+        10
+    }
+
+    fn dflt_auth_throttle_lockout_seconds() -> u64 {
+        900 // 15 minutes
+    }
+
+    // Forget failures after this time without further failures.
+    fn dflt_auth_throttle_window_seconds() -> u64 {
+        3600 // 1 hour
+    }
+}
+
+impl Default for LoginThrottleConfig {
+    fn default() -> Self {
+        LoginThrottleConfig {
+            auth_throttle_enabled: Self::dflt_auth_throttle_enabled(),
+            auth_throttle_free_failures: Self::dflt_auth_throttle_free_failures(),
+            auth_throttle_base_delay_seconds: Self::dflt_auth_throttle_base_delay_seconds(),
+            auth_throttle_max_delay_seconds: Self::dflt_auth_throttle_max_delay_seconds(),
+            auth_throttle_lockout_failures: Self::dflt_auth_throttle_lockout_failures(),
+            auth_throttle_lockout_seconds: Self::dflt_auth_throttle_lockout_seconds(),
+            auth_throttle_window_seconds: Self::dflt_auth_throttle_window_seconds(),
+            auth_trusted_proxies: vec![],
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct MetricsConfig {
     #[serde(default)] // false
@@ -530,9 +689,45 @@ impl Config {
         }
     }
 
+    /// The number of seconds of inactivity after which a warning is raised
+    /// for a child, before it is suspended.
+    pub fn suspend_child_warn_after_inactive_seconds(&self) -> Option<i64> {
+        match self.suspend_child_warn_after_inactive_seconds {
+            Some(seconds) => Some(seconds),
+            None => self.suspend_child_warn_after_inactive_hours.map(|hours| hours * 3600),
+        }
+    }
+
+    /// The URI to which notifications are posted when children are warned
+    /// about inactivity, or suspended.
+    pub fn suspend_child_webhook(&self) -> Option<&uri::Https> {
+        self.suspend_child_webhook.as_ref()
+    }
+
+    /// The minimum number of peers that should see an announcement before
+    /// it is used in the BGP analysis. Defaults depend on the BGP source.
+    pub fn bgp_min_peers(&self) -> u32 {
+        self.bgp_min_peers.unwrap_or(match self.bgp_source {
+            BgpSourceType::RisDumps => BGP_RIS_MIN_PEERS,
+            _ => 1,
+        })
+    }
+
     pub fn testbed(&self) -> Option<&TestBed> {
         self.testbed.as_ref()
     }
+
+    /// The path of the audit log file. Defaults to "audit.log" in the data
+    /// directory.
+    pub fn audit_log_file(&self) -> PathBuf {
+        self.audit_log_file
+            .clone()
+            .unwrap_or_else(|| self.data_dir.join("audit.log"))
+    }
+
+    pub fn syslog_facility(&self) -> Result<Facility, ConfigError> {
+        Facility::from_str(&self.syslog_facility).map_err(|_| ConfigError::other("Invalid syslog_facility"))
+    }
 }
 
 /// # Create
@@ -553,6 +748,8 @@ impl Config {
         let mut log_file = data_dir.clone();
         log_file.push("krill.log");
         let syslog_facility = ConfigDefaults::syslog_facility();
+        let audit_log_type = AuditLogType::None;
+        let audit_log_file = None;
         let auth_type = AuthType::AdminToken;
         let admin_token = Token::from("secret");
         #[cfg(feature = "multi-user")]
@@ -562,7 +759,13 @@ impl Config {
         #[cfg(feature = "multi-user")]
         let auth_users = None;
         #[cfg(feature = "multi-user")]
+        let auth_password_policy = ConfigPasswordPolicy::default();
+        #[cfg(feature = "multi-user")]
         let auth_openidconnect = None;
+        #[cfg(feature = "multi-user")]
+        let auth_ldap = None;
+        #[cfg(feature = "multi-user")]
+        let auth_client_certs = None;
         let ca_refresh_seconds = if enable_ca_refresh { 1 } else { 86400 };
         let ca_refresh_parents_batch_size = 10;
         let post_limit_api = ConfigDefaults::post_limit_api();
@@ -579,6 +782,8 @@ impl Config {
             Some(dir)
         };
 
+        let bgp_source = ConfigDefaults::bgp_source();
+        let bgp_refresh_minutes = ConfigDefaults::bgp_refresh_minutes();
         let bgp_risdumps_enabled = false;
         let bgp_risdumps_v4_uri = ConfigDefaults::bgp_risdumps_v4_uri();
         let bgp_risdumps_v6_uri = ConfigDefaults::bgp_risdumps_v6_uri();
@@ -625,6 +830,13 @@ impl Config {
             metrics_hide_roa_details: false,
         };
 
+        // Tests deliberately fail to authenticate, and should not be slowed
+        // down or locked out because of that.
+        let login_throttle = LoginThrottleConfig {
+            auth_throttle_enabled: false,
+            ..LoginThrottleConfig::default()
+        };
+
         let testbed = if enable_testbed {
             Some(TestBed::new(
                 test::rsync("rsync://localhost/ta/ta.cer"),
@@ -637,6 +849,13 @@ impl Config {
         };
 
         let suspend_child_after_inactive_seconds = if enable_suspend { Some(3) } else { None };
+        let suspend_child_warn_after_inactive_seconds = if enable_suspend { Some(1) } else { None };
+
+        // Let tests go through all phases of a TA key roll without waiting.
+        let ta_key_roll_transition_days = 0;
+
+        // Allow tests to onboard children without setting up tokens.
+        let child_onboarding = ChildOnboardingMode::Open;
 
         Config {
             ip,
@@ -650,6 +869,8 @@ impl Config {
             log_type,
             log_file,
             syslog_facility,
+            audit_log_type,
+            audit_log_file,
             admin_token,
             auth_type,
             #[cfg(feature = "multi-user")]
@@ -659,16 +880,30 @@ impl Config {
             #[cfg(feature = "multi-user")]
             auth_users,
             #[cfg(feature = "multi-user")]
+            auth_password_policy,
+            #[cfg(feature = "multi-user")]
             auth_openidconnect,
+            #[cfg(feature = "multi-user")]
+            auth_ldap,
+            #[cfg(feature = "multi-user")]
+            auth_client_certs,
             ca_refresh_seconds,
             ca_refresh_parents_batch_size,
             suspend_child_after_inactive_seconds,
             suspend_child_after_inactive_hours: None,
+            suspend_child_warn_after_inactive_seconds,
+            suspend_child_warn_after_inactive_hours: None,
+            suspend_child_webhook: None,
             post_limit_api,
             post_limit_rfc8181,
             rfc8181_log_dir,
             post_limit_rfc6492,
             rfc6492_log_dir,
+            bgp_source,
+            bgp_source_file: None,
+            bgp_bmp_listen: None,
+            bgp_refresh_minutes,
+            bgp_min_peers: None,
             bgp_risdumps_enabled,
             bgp_risdumps_v4_uri,
             bgp_risdumps_v6_uri,
@@ -677,6 +912,9 @@ impl Config {
             issuance_timing,
             repository_retention,
             metrics,
+            login_throttle,
+            ta_key_roll_transition_days,
+            child_onboarding,
             testbed,
         }
     }
@@ -821,6 +1059,10 @@ impl Config {
             return Err(ConfigError::other("timing_child_certificate_reissue_weeks_before must be smaller than timing_child_certificate_valid_weeks"));
         }
 
+        if self.ta_key_roll_transition_days < 0 {
+            return Err(ConfigError::other("ta_key_roll_transition_days must not be negative"));
+        }
+
         if self.issuance_timing.timing_roa_valid_weeks < 2 {
             return Err(ConfigError::other("timing_roa_valid_weeks must be at least 2"));
         }
@@ -835,6 +1077,50 @@ impl Config {
             ));
         }
 
+        if self.login_throttle.auth_throttle_lockout_failures <= self.login_throttle.auth_throttle_free_failures {
+            return Err(ConfigError::other(
+                "auth_throttle_lockout_failures must be larger than auth_throttle_free_failures",
+            ));
+        }
+
+        if self.login_throttle.auth_throttle_base_delay_seconds > self.login_throttle.auth_throttle_max_delay_seconds {
+            return Err(ConfigError::other(
+                "auth_throttle_base_delay_seconds must be at most auth_throttle_max_delay_seconds",
+            ));
+        }
+
+        #[cfg(feature = "multi-user")]
+        if let Some(client_certs) = &self.auth_client_certs {
+            if !client_certs.ca.is_file() {
+                return Err(ConfigError::Other(format!(
+                    "[auth_client_certs] ca file '{}' does not exist",
+                    client_certs.ca.display()
+                )));
+            }
+        }
+
+        match self.bgp_source {
+            BgpSourceType::RisDumps => {}
+            BgpSourceType::Mrt | BgpSourceType::Csv | BgpSourceType::Json => {
+                if self.bgp_source_file.is_none() {
+                    return Err(ConfigError::other(
+                        "bgp_source_file must be set when bgp_source is \"mrt\", \"csv\" or \"json\"",
+                    ));
+                }
+            }
+            BgpSourceType::Bmp => {
+                if self.bgp_bmp_listen.is_none() {
+                    return Err(ConfigError::other(
+                        "bgp_bmp_listen must be set when bgp_source is \"bmp\"",
+                    ));
+                }
+            }
+        }
+
+        if self.bgp_refresh_minutes < 1 {
+            return Err(ConfigError::other("bgp_refresh_minutes must be at least 1"));
+        }
+
         if let Some(threshold) = self.suspend_child_after_inactive_hours {
             if threshold < CA_SUSPEND_MIN_HOURS {
                 return Err(ConfigError::Other(format!(
@@ -844,6 +1130,17 @@ impl Config {
             }
         }
 
+        if let Some(warn) = self.suspend_child_warn_after_inactive_hours {
+            match self.suspend_child_after_inactive_hours {
+                Some(threshold) if warn > 0 && warn < threshold => {}
+                _ => {
+                    return Err(ConfigError::other(
+                        "suspend_child_warn_after_inactive_hours must be lower than suspend_child_after_inactive_hours",
+                    ))
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -863,8 +1160,7 @@ impl Config {
             LogType::File => self.file_logger(&self.log_file),
             LogType::Stderr => self.stderr_logger(),
             LogType::Syslog => {
-                let facility = Facility::from_str(&self.syslog_facility)
-                    .map_err(|_| ConfigError::other("Invalid syslog_facility"))?;
+                let facility = self.syslog_facility()?;
                 self.syslog_logger(facility)
             }
         }
@@ -897,6 +1193,25 @@ impl Config {
     /// Creates a syslog logger and configures correctly.
     #[cfg(unix)]
     fn syslog_logger(&self, facility: syslog::Facility) -> Result<(), ConfigError> {
+        match Self::syslog_connect(facility) {
+            Ok(logger) => self
+                .fern_logger()
+                .chain(logger)
+                .apply()
+                .map_err(|e| ConfigError::Other(format!("Failed to init syslog: {}", e))),
+            Err(err) => {
+                let msg = format!("Cannot connect to syslog: {}", err);
+                Err(ConfigError::Other(msg))
+            }
+        }
+    }
+
+    /// Connects to the local syslog daemon, trying a unix socket first, and
+    /// then TCP and UDP on localhost.
+    #[cfg(unix)]
+    pub fn syslog_connect<T: fmt::Display>(
+        facility: syslog::Facility,
+    ) -> syslog::Result<syslog::Logger<syslog::LoggerBackend, T, syslog::Formatter3164>> {
         let process = env::current_exe()
             .ok()
             .and_then(|path| {
@@ -912,20 +1227,9 @@ impl Config {
             process,
             pid,
         };
-        let logger = syslog::unix(formatter.clone())
+        syslog::unix(formatter.clone())
             .or_else(|_| syslog::tcp(formatter.clone(), ("127.0.0.1", 601)))
-            .or_else(|_| syslog::udp(formatter, ("127.0.0.1", 0), ("127.0.0.1", 514)));
-        match logger {
-            Ok(logger) => self
-                .fern_logger()
-                .chain(logger)
-                .apply()
-                .map_err(|e| ConfigError::Other(format!("Failed to init syslog: {}", e))),
-            Err(err) => {
-                let msg = format!("Cannot connect to syslog: {}", err);
-                Err(ConfigError::Other(msg))
-            }
-        }
+            .or_else(|_| syslog::udp(formatter, ("127.0.0.1", 0), ("127.0.0.1", 514)))
     }
 
     /// Creates and returns a fern logger with log level tweaks
@@ -1049,6 +1353,36 @@ impl<'de> Deserialize<'de> for LogType {
     }
 }
 
+//------------ AuditLogType --------------------------------------------------
+
+/// The target to write the audit log to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AuditLogType {
+    None,
+    File,
+    Json,
+    Syslog,
+}
+
+impl<'de> Deserialize<'de> for AuditLogType {
+    fn deserialize<D>(d: D) -> Result<AuditLogType, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(d)?;
+        match string.as_str() {
+            "none" => Ok(AuditLogType::None),
+            "file" => Ok(AuditLogType::File),
+            "json" => Ok(AuditLogType::Json),
+            "syslog" => Ok(AuditLogType::Syslog),
+            _ => Err(de::Error::custom(format!(
+                "expected \"none\", \"file\", \"json\" or \"syslog\", found : \"{}\"",
+                string
+            ))),
+        }
+    }
+}
+
 //------------ HttpsMode -----------------------------------------------------
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -1075,6 +1409,79 @@ impl<'de> Deserialize<'de> for HttpsMode {
     }
 }
 
+//------------ BgpSourceType ------------------------------------------------
+
+/// The source of BGP announcements used for ROA vs BGP analysis.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BgpSourceType {
+    RisDumps,
+    Mrt,
+    Bmp,
+    Csv,
+    Json,
+}
+
+impl<'de> Deserialize<'de> for BgpSourceType {
+    fn deserialize<D>(d: D) -> Result<BgpSourceType, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(d)?;
+        match string.as_str() {
+            "ris" => Ok(BgpSourceType::RisDumps),
+            "mrt" => Ok(BgpSourceType::Mrt),
+            "bmp" => Ok(BgpSourceType::Bmp),
+            "csv" => Ok(BgpSourceType::Csv),
+            "json" => Ok(BgpSourceType::Json),
+            _ => Err(de::Error::custom(format!(
+                "expected \"ris\", \"mrt\", \"bmp\", \"csv\" or \"json\", found: \"{}\"",
+                string
+            ))),
+        }
+    }
+}
+
+//------------ ChildOnboardingMode ------------------------------------------
+
+/// Determines whether prospective child CAs can submit requests to be added
+/// under a CA in this server, and who may do so.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChildOnboardingMode {
+    /// Children can only be added by the operator.
+    Disabled,
+
+    /// Anyone may submit a child request for approval.
+    Open,
+
+    /// Child requests must be submitted using a token with the
+    /// CHILD_REQUEST permission for the parent CA.
+    Token,
+}
+
+impl ChildOnboardingMode {
+    pub fn is_enabled(&self) -> bool {
+        *self != ChildOnboardingMode::Disabled
+    }
+}
+
+impl<'de> Deserialize<'de> for ChildOnboardingMode {
+    fn deserialize<D>(d: D) -> Result<ChildOnboardingMode, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(d)?;
+        match string.as_str() {
+            "disabled" => Ok(ChildOnboardingMode::Disabled),
+            "open" => Ok(ChildOnboardingMode::Open),
+            "token" => Ok(ChildOnboardingMode::Token),
+            _ => Err(de::Error::custom(format!(
+                "expected \"disabled\", \"open\" or \"token\", found: \"{}\"",
+                string
+            ))),
+        }
+    }
+}
+
 //------------ AuthType -----------------------------------------------------
 
 /// The target to log to.
@@ -1085,6 +1492,8 @@ pub enum AuthType {
     ConfigFile,
     #[cfg(feature = "multi-user")]
     OpenIDConnect,
+    #[cfg(feature = "multi-user")]
+    Ldap,
 }
 
 impl<'de> Deserialize<'de> for AuthType {
@@ -1099,12 +1508,14 @@ impl<'de> Deserialize<'de> for AuthType {
             "config-file" => Ok(AuthType::ConfigFile),
             #[cfg(feature = "multi-user")]
             "openid-connect" => Ok(AuthType::OpenIDConnect),
+            #[cfg(feature = "multi-user")]
+            "ldap" => Ok(AuthType::Ldap),
             _ => {
                 #[cfg(not(feature = "multi-user"))]
                 let msg = format!("expected \"admin-token\", found: \"{}\"", string);
                 #[cfg(feature = "multi-user")]
                 let msg = format!(
-                    "expected \"config-file\", \"admin-token\", or \"openid-connect\", found: \"{}\"",
+                    "expected \"config-file\", \"admin-token\", \"openid-connect\" or \"ldap\", found: \"{}\"",
                     string
                 );
                 Err(de::Error::custom(msg))
@@ -1300,6 +1711,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_require_file_for_local_bgp_source() {
+        env::set_var(KRILL_ENV_ADMIN_TOKEN, "secret");
+
+        let c: Config = toml::from_slice(b"").unwrap();
+        assert_eq!(c.bgp_min_peers(), BGP_RIS_MIN_PEERS);
+
+        let c: Config = toml::from_slice(b"bgp_min_peers = 2").unwrap();
+        assert_eq!(c.bgp_min_peers(), 2);
+
+        let c: Config = toml::from_slice(b"bgp_source = \"mrt\"").unwrap();
+        assert!(c.verify().is_err());
+
+        let c: Config = toml::from_slice(b"bgp_source = \"mrt\"\nbgp_source_file = \"/tmp/rib.mrt\"").unwrap();
+        assert_eq!(c.bgp_source, BgpSourceType::Mrt);
+        assert_eq!(c.bgp_min_peers(), 1);
+        assert!(c.verify().is_ok());
+
+        let c: Config = toml::from_slice(b"bgp_source = \"bmp\"\nbgp_bmp_listen = \"127.0.0.1:11019\"").unwrap();
+        assert_eq!(c.bgp_bmp_listen, Some(([127, 0, 0, 1], 11019).into()));
+        assert!(c.verify().is_ok());
+    }
+
     #[test]
     fn config_should_accept_and_warn_about_auth_token() {
         let old_config = b"auth_token = \"secret\"";
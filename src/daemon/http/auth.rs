@@ -11,6 +11,7 @@ use {
     urlparse::quote,
 };
 
+pub const AUTH_PATH_PREFIX: &str = "/auth/";
 pub const AUTH_CALLBACK_ENDPOINT: &str = "/auth/callback";
 pub const AUTH_LOGIN_ENDPOINT: &str = "/auth/login";
 pub const AUTH_LOGOUT_ENDPOINT: &str = "/auth/logout";
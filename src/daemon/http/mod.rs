@@ -1,9 +1,14 @@
-use std::{convert::TryInto, io, str::from_utf8, str::FromStr};
+use std::{convert::TryInto, io, net::IpAddr, str::from_utf8, str::FromStr};
 
 use bytes::{Buf, BufMut, Bytes};
 use serde::{de::DeserializeOwned, Serialize};
 
-use hyper::{body::HttpBody, header::USER_AGENT, http::uri::PathAndQuery, Body, HeaderMap, Method, StatusCode};
+use hyper::{
+    body::HttpBody,
+    header::{FORWARDED, USER_AGENT},
+    http::uri::PathAndQuery,
+    Body, HeaderMap, Method, StatusCode,
+};
 
 use crate::{
     commons::{
@@ -367,7 +372,11 @@ impl Request {
 
     pub async fn upgrade_from_anonymous(&mut self, actor_def: ActorDef) {
         if self.actor.is_anonymous() {
+            let audit_trail = self.actor.audit_trail();
             self.actor = self.state.actor_from_def(actor_def);
+            if let Some(trail) = audit_trail {
+                self.actor = self.actor.clone().with_audit_trail(trail);
+            }
             info!(
                 "Permitted anonymous actor to become actor '{}' for the duration of this request",
                 self.actor.name()
@@ -504,7 +513,7 @@ impl Request {
     }
 
     pub async fn login(&self) -> KrillResult<LoggedInUser> {
-        self.state.login(&self.request).await
+        self.state.login(&self.request, &self.actor).await
     }
 
     pub async fn logout(&self) -> KrillResult<HttpResponse> {
@@ -512,6 +521,93 @@ impl Request {
     }
 }
 
+//------------ ClientAddr ----------------------------------------------------
+
+/// The address of the client that sent a request, used to throttle failed
+/// authentication attempts and recorded in the audit log.
+///
+/// This is the address of the TCP peer, unless that is one of the configured
+/// trusted proxies. In that case the address is taken from the "Forwarded"
+/// header, or else the "X-Forwarded-For" header. These list the addresses of
+/// the client and of each proxy in between. The list is followed back from
+/// the TCP peer for as long as the addresses are trusted proxies, so that a
+/// client cannot choose its own address by sending these headers itself.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ClientAddr(IpAddr);
+
+impl ClientAddr {
+    pub fn resolve(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> Self {
+        let mut addr = peer;
+        if trusted_proxies.contains(&addr) {
+            for hop in Self::forwarded_for(headers).into_iter().rev() {
+                match hop {
+                    Some(hop) => {
+                        addr = hop;
+                        if !trusted_proxies.contains(&addr) {
+                            break;
+                        }
+                    }
+                    // An unknown or obfuscated address, use the address of
+                    // the proxy that added it.
+                    None => break,
+                }
+            }
+        }
+        ClientAddr(addr)
+    }
+
+    /// Returns the client address for a request, if it was resolved.
+    pub fn from_request<B>(request: &hyper::Request<B>) -> Option<IpAddr> {
+        request.extensions().get::<ClientAddr>().map(|addr| addr.0)
+    }
+
+    /// Returns the forwarded addresses from the headers, client first.
+    fn forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+        let forwarded = headers.get_all(FORWARDED);
+        if forwarded.iter().next().is_some() {
+            forwarded
+                .iter()
+                .flat_map(|value| value.to_str().unwrap_or("").split(','))
+                .map(|element| {
+                    element
+                        .split(';')
+                        .filter_map(|pair| {
+                            let mut parts = pair.splitn(2, '=');
+                            let name = parts.next()?.trim();
+                            let value = parts.next()?.trim();
+                            if name.eq_ignore_ascii_case("for") {
+                                Some(value)
+                            } else {
+                                None
+                            }
+                        })
+                        .next()
+                        .and_then(Self::parse_node)
+                })
+                .collect()
+        } else {
+            headers
+                .get_all("x-forwarded-for")
+                .iter()
+                .flat_map(|value| value.to_str().unwrap_or("").split(','))
+                .map(|node| Self::parse_node(node.trim()))
+                .collect()
+        }
+    }
+
+    /// Parses a node, e.g. 192.0.2.1, "192.0.2.1:4711" or "[2001:db8::1]:4711".
+    fn parse_node(node: &str) -> Option<IpAddr> {
+        let node = node.trim_matches('"');
+        if let Ok(addr) = IpAddr::from_str(node) {
+            Some(addr)
+        } else if let Some(bracketed) = node.strip_prefix('[') {
+            IpAddr::from_str(&bracketed[..bracketed.find(']')?]).ok()
+        } else {
+            IpAddr::from_str(node.splitn(2, ':').next()?).ok()
+        }
+    }
+}
+
 //------------ RequestPath ---------------------------------------------------
 
 #[derive(Clone, Debug)]
@@ -581,3 +677,76 @@ impl RequestPath {
         self.next().map(|s| T::from_str(s).ok()).flatten()
     }
 }
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use hyper::header::HeaderValue;
+
+    fn addr(s: &str) -> IpAddr {
+        IpAddr::from_str(s).unwrap()
+    }
+
+    fn headers(name: &'static str, values: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn client_addr_resolve() {
+        let proxy = addr("127.0.0.1");
+        let trusted = vec![proxy, addr("10.0.0.1")];
+        let none: Vec<IpAddr> = vec![];
+
+        let xff = headers("x-forwarded-for", &["203.0.113.9, 192.0.2.1"]);
+
+        // Headers are ignored unless the peer is a trusted proxy.
+        assert_eq!(ClientAddr(proxy), ClientAddr::resolve(proxy, &xff, &none));
+        assert_eq!(
+            ClientAddr(addr("198.51.100.1")),
+            ClientAddr::resolve(addr("198.51.100.1"), &xff, &trusted)
+        );
+
+        // The last untrusted address is used, a client cannot pick its own.
+        assert_eq!(
+            ClientAddr(addr("192.0.2.1")),
+            ClientAddr::resolve(proxy, &xff, &trusted)
+        );
+
+        let xff = headers("x-forwarded-for", &["203.0.113.9", "192.0.2.1, 10.0.0.1"]);
+        assert_eq!(
+            ClientAddr(addr("192.0.2.1")),
+            ClientAddr::resolve(proxy, &xff, &trusted)
+        );
+
+        // Forwarded takes precedence, and may contain ports and IPv6.
+        let mut both = headers("forwarded", &["for=192.0.2.60;proto=https, For=\"[2001:db8::1]:4711\""]);
+        both.append("x-forwarded-for", HeaderValue::from_static("203.0.113.9"));
+        assert_eq!(
+            ClientAddr(addr("2001:db8::1")),
+            ClientAddr::resolve(proxy, &both, &trusted)
+        );
+
+        let forwarded = headers("forwarded", &["for=\"192.0.2.60:8080\""]);
+        assert_eq!(
+            ClientAddr(addr("192.0.2.60")),
+            ClientAddr::resolve(proxy, &forwarded, &trusted)
+        );
+
+        // An unknown address leaves the address of the proxy that added it.
+        let forwarded = headers("forwarded", &["for=192.0.2.60, for=unknown"]);
+        assert_eq!(ClientAddr(proxy), ClientAddr::resolve(proxy, &forwarded, &trusted));
+
+        // Without headers the proxy itself is the client.
+        assert_eq!(
+            ClientAddr(proxy),
+            ClientAddr::resolve(proxy, &HeaderMap::new(), &trusted)
+        );
+    }
+}
@@ -22,14 +22,15 @@ use hyper::{
     http::HeaderValue,
     server::conn::AddrIncoming,
     service::{make_service_fn, service_fn},
-    Method,
+    Method, StatusCode,
 };
 
 use crate::{
     commons::{
         api::{
-            AspaDefinitionUpdates, BgpStats, ChildHandle, CommandHistoryCriteria, Handle, ParentCaContact, ParentCaReq,
-            ParentHandle, PublisherList, RepositoryContact, RoaDefinitionUpdates, RtaName, Token,
+            AspaDefinitionUpdates, BgpStats, CertAuthRename, ChildHandle, CommandHistoryCriteria, Handle,
+            ParentCaContact, ParentCaReq, ParentHandle, PublisherList, RepositoryContact, RepositoryMirrorName,
+            RoaDefinitionUpdates, RtaName, TaKeyRoll, Timestamp, Token, TypedPrefix,
         },
         bgp::BgpAnalysisAdvice,
         error::Error,
@@ -43,19 +44,23 @@ use crate::{
         NO_RESOURCE,
     },
     daemon::{
+        audit::AuditContext,
         auth::common::permissions::Permission,
         auth::Auth,
-        ca::{CaStatus, RouteAuthorizationUpdates, TA_NAME},
-        config::Config,
+        ca::{ta_handle, CaStatus, RouteAuthorizationUpdates, TA_NAME},
+        config::{ChildOnboardingMode, Config},
         http::{
-            auth::auth, statics::statics, testbed::testbed, tls, tls_keys, HttpResponse, Request, RequestPath,
-            RoutingResult,
+            auth::auth, statics::statics, testbed::testbed, tls, tls_keys, ClientAddr, HttpResponse, Request,
+            RequestPath, RoutingResult,
         },
         krillserver::KrillServer,
     },
     upgrades::{post_start_upgrade, pre_start_upgrade, update_storage_version},
 };
 
+#[cfg(feature = "multi-user")]
+use crate::commons::api::{MfaCode, PasswordChange, TenantDefinition};
+
 //------------ State -----------------------------------------------------
 
 pub type State = Arc<KrillServer>;
@@ -138,11 +143,25 @@ pub async fn start_krill_daemon(config: Arc<Config>) -> Result<(), Error> {
 
     let state = Arc::new(krill);
 
-    let service = make_service_fn(move |_| {
+    // Reload the authorization policy when receiving SIGHUP
+    #[cfg(all(unix, feature = "multi-user"))]
+    reload_policy_on_sighup(state.clone())?;
+
+    let trusted_proxies = Arc::new(config.login_throttle.auth_trusted_proxies.clone());
+
+    let service = make_service_fn(move |conn: &tls::TlsStream| {
         let state = state.clone();
+        let trusted_proxies = trusted_proxies.clone();
+        let client_cert = conn.client_certificate();
+        let remote_addr = conn.remote_addr();
         async move {
-            Ok::<_, Infallible>(service_fn(move |req: hyper::Request<hyper::Body>| {
+            Ok::<_, Infallible>(service_fn(move |mut req: hyper::Request<hyper::Body>| {
                 let state = state.clone();
+                let client_addr = ClientAddr::resolve(remote_addr.ip(), req.headers(), &trusted_proxies);
+                req.extensions_mut().insert(client_addr);
+                if let Some(cert) = client_cert.get() {
+                    req.extensions_mut().insert(cert);
+                }
                 map_requests(req, state)
             }))
         }
@@ -150,10 +169,19 @@ pub async fn start_krill_daemon(config: Arc<Config>) -> Result<(), Error> {
 
     tls_keys::create_key_cert_if_needed(&config.data_dir).map_err(|e| Error::HttpsSetup(format!("{}", e)))?;
 
-    let server_config_builder = tls::TlsConfigBuilder::new()
+    #[allow(unused_mut)]
+    let mut server_config_builder = tls::TlsConfigBuilder::new()
         .cert_path(tls_keys::cert_file_path(&config.data_dir))
         .key_path(tls_keys::key_file_path(&config.data_dir));
-    let server_config = server_config_builder.build().unwrap();
+
+    #[cfg(feature = "multi-user")]
+    if let Some(client_certs) = &config.auth_client_certs {
+        server_config_builder = server_config_builder.client_ca_path(&client_certs.ca);
+    }
+
+    let server_config = server_config_builder
+        .build()
+        .map_err(|e| Error::HttpsSetup(format!("{}", e)))?;
 
     let incoming = AddrIncoming::bind(&config.socket_addr()).map_err(|e| {
         Error::Custom(format!(
@@ -176,6 +204,29 @@ pub async fn start_krill_daemon(config: Arc<Config>) -> Result<(), Error> {
     Ok(())
 }
 
+/// Spawns a task that reloads the authorization policy whenever a SIGHUP is
+/// received. Failures are logged, and leave the current policy in place.
+#[cfg(all(unix, feature = "multi-user"))]
+fn reload_policy_on_sighup(krill: Arc<KrillServer>) -> Result<(), Error> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups =
+        signal(SignalKind::hangup()).map_err(|e| Error::Custom(format!("Could not listen for SIGHUP: {}", e)))?;
+
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            if let Err(e) = krill.policy_reload() {
+                error!(
+                    "Could not reload authorization policy, keeping the current policy: {}",
+                    e
+                );
+            }
+        }
+    });
+
+    Ok(())
+}
+
 struct RequestLogger {
     req_method: hyper::Method,
     req_path: String,
@@ -225,6 +276,8 @@ impl RequestLogger {
 
 async fn map_requests(req: hyper::Request<hyper::Body>, state: State) -> Result<hyper::Response<hyper::Body>, Error> {
     let logger = RequestLogger::begin(&req);
+    let audit_context = AuditContext::from_request(&req);
+    let audit_state = state.clone();
 
     let req = Request::new(req, state).await;
 
@@ -232,6 +285,10 @@ async fn map_requests(req: hyper::Request<hyper::Body>, state: State) -> Result<
     // refreshing.
     let new_auth = req.actor().new_auth();
 
+    // Keep the actor, which shares its audit trail with the actor used for
+    // handling the request.
+    let actor = req.actor();
+
     // We used to use .or_else() here but that causes a large recursive call
     // tree due to these calls being to async functions, large enough with the
     // given Request object passed each time that it eventually resulted in
@@ -257,6 +314,9 @@ async fn map_requests(req: hyper::Request<hyper::Body>, state: State) -> Result<
     if let Err(req) = res {
         res = rfc6492(req).await;
     }
+    if let Err(req) = res {
+        res = onboarding(req).await;
+    }
     if let Err(req) = res {
         res = statics(req).await;
     }
@@ -282,6 +342,12 @@ async fn map_requests(req: hyper::Request<hyper::Body>, state: State) -> Result<
     // Log the request and the response.
     logger.end(res.as_ref());
 
+    let outcome = match &res {
+        Ok(response) => response.status().as_u16(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+    };
+    audit_state.audit(&audit_context, &actor, outcome);
+
     res.map(|res| res.response())
 }
 
@@ -364,6 +430,7 @@ pub async fn health(req: Request) -> RoutingResult {
 pub async fn metrics(req: Request) -> RoutingResult {
     if req.is_get() && req.path().segment().starts_with("metrics") {
         let server = req.state();
+        let actor = req.actor();
 
         struct AllBgpStats {
             announcements_valid: HashMap<Handle, usize>,
@@ -425,7 +492,46 @@ pub async fn metrics(req: Request) -> RoutingResult {
             ));
         }
 
-        if let Ok(cas_stats) = server.cas_stats().await {
+        let throttle_stats = server.login_throttle_stats();
+        res.push('\n');
+        res.push_str("# HELP krill_auth_login_failures_total number of failed login and authentication attempts\n");
+        res.push_str("# TYPE krill_auth_login_failures_total counter\n");
+        res.push_str(&format!(
+            "krill_auth_login_failures_total {}\n",
+            throttle_stats.failures
+        ));
+
+        res.push('\n');
+        res.push_str(
+            "# HELP krill_auth_login_throttled_total number of attempts rejected because of earlier failures\n",
+        );
+        res.push_str("# TYPE krill_auth_login_throttled_total counter\n");
+        res.push_str(&format!(
+            "krill_auth_login_throttled_total {}\n",
+            throttle_stats.throttled
+        ));
+
+        res.push('\n');
+        res.push_str("# HELP krill_auth_lockouts_total number of times a user or source address was locked out\n");
+        res.push_str("# TYPE krill_auth_lockouts_total counter\n");
+        res.push_str(&format!("krill_auth_lockouts_total {}\n", throttle_stats.lockouts));
+
+        res.push('\n');
+        res.push_str("# HELP krill_auth_blocked number of users and source addresses currently blocked\n");
+        res.push_str("# TYPE krill_auth_blocked gauge\n");
+        res.push_str(&format!("krill_auth_blocked {}\n", throttle_stats.blocked));
+
+        let stats_details_visible = server.stats_details_visible(&actor);
+
+        if !stats_details_visible {
+            // Only show the aggregate number of CAs, but no CA names.
+            if let Ok(number_cas) = server.cas_count() {
+                res.push('\n');
+                res.push_str("# HELP krill_cas number of cas in krill\n");
+                res.push_str("# TYPE krill_cas gauge\n");
+                res.push_str(&format!("krill_cas {}\n", number_cas));
+            }
+        } else if let Ok(cas_stats) = server.cas_stats(&actor).await {
             let number_cas = cas_stats.len();
 
             res.push('\n');
@@ -539,6 +645,24 @@ pub async fn metrics(req: Request) -> RoutingResult {
                             ca, timestamp
                         ));
                     }
+
+                    // Only show mirror metrics if any CA uses repository mirrors.
+                    if ca_status_map.values().any(|status| !status.mirrors().is_empty()) {
+                        res.push('\n');
+                        res.push_str("# HELP krill_ca_ps_mirror_success status of last CA to Publication Server mirror connection (0=issue, 1=success)\n");
+                        res.push_str("# TYPE krill_ca_ps_mirror_success gauge\n");
+                        for (ca, status) in ca_status_map.iter() {
+                            for (mirror, mirror_status) in status.mirrors().iter() {
+                                if let Some(exchange) = mirror_status.last_exchange() {
+                                    let value = if exchange.was_success() { 1 } else { 0 };
+                                    res.push_str(&format!(
+                                        "krill_ca_ps_mirror_success{{ca=\"{}\", mirror=\"{}\"}} {}\n",
+                                        ca, mirror, value
+                                    ));
+                                }
+                            }
+                        }
+                    }
                 }
 
                 // Do not show child metrics if none of the CAs has any children..
@@ -749,7 +873,15 @@ pub async fn metrics(req: Request) -> RoutingResult {
             }
         }
 
-        if let Ok(stats) = server.repo_stats() {
+        // Anonymous actors get aggregate repository numbers, even if they may
+        // not see the details per publisher.
+        let repo_stats = if server.repo_stats_visible(&actor) || actor.is_anonymous() {
+            server.repo_stats().ok()
+        } else {
+            None
+        };
+
+        if let Some(stats) = repo_stats {
             let publishers = stats.get_publishers();
 
             res.push('\n');
@@ -771,7 +903,7 @@ pub async fn metrics(req: Request) -> RoutingResult {
             res.push_str("# TYPE krill_repo_rrdp_serial counter\n");
             res.push_str(&format!("krill_repo_rrdp_serial {}\n", stats.serial()));
 
-            if !server.config.metrics.metrics_hide_publisher_details {
+            if stats_details_visible && !server.config.metrics.metrics_hide_publisher_details {
                 res.push('\n');
                 res.push_str("# HELP krill_repo_objects number of objects in repository for publisher\n");
                 res.push_str("# TYPE krill_repo_objects gauge\n");
@@ -849,7 +981,9 @@ async fn ta(req: Request) -> RoutingResult {
             "/ta/ta.tal" => tal(req).await,
             "/testbed.tal" => tal(req).await,
             "/ta/ta.cer" => ta_cer(req).await,
-            _ => Err(req),
+            "/ta/successor.tal" => ta_key_roll_tal(req, true).await,
+            "/ta/predecessor.tal" => ta_key_roll_tal(req, false).await,
+            _ => ta_cer_by_name(req).await,
         },
         _ => Err(req),
     }
@@ -869,6 +1003,39 @@ pub async fn ta_cer(req: Request) -> RoutingResult {
     }
 }
 
+/// Serves the TAL of the successor key while it is staged, or of the
+/// predecessor key after the successor was activated.
+async fn ta_key_roll_tal(req: Request, successor: bool) -> RoutingResult {
+    match (req.state().ta_key_roll().await, successor) {
+        (Some(TaKeyRoll::Staging { successor, .. }), true) => {
+            Ok(HttpResponse::text(format!("{}", successor.tal()).into_bytes()))
+        }
+        (Some(TaKeyRoll::Activated { predecessor, .. }), false) => {
+            Ok(HttpResponse::text(format!("{}", predecessor.tal()).into_bytes()))
+        }
+        _ => render_unknown_resource(),
+    }
+}
+
+/// Serves the certificates of the TA keys by name, as they are used in
+/// the TALs for the keys during a key roll.
+async fn ta_cer_by_name(req: Request) -> RoutingResult {
+    let name = req
+        .path
+        .full()
+        .strip_prefix("/ta/")
+        .filter(|name| name.ends_with(".cer") && !name.contains('/'))
+        .map(|name| name.to_string());
+
+    match name {
+        None => Err(req),
+        Some(name) => match req.state().trust_anchor_cert_by_name(&name).await {
+            Some(cert) => Ok(HttpResponse::cert(cert.to_captured().to_vec())),
+            None => render_unknown_resource(),
+        },
+    }
+}
+
 //------------ Provisioning (RFC6492) ----------------------------------------
 
 /// Process an RFC 6492 request
@@ -904,8 +1071,8 @@ async fn stats(req: Request) -> RoutingResult {
     match *req.method() {
         Method::GET => match req.path().full() {
             "/stats/info" => render_json(req.state().server_info()),
-            "/stats/repo" => render_json_res(req.state().repo_stats()),
-            "/stats/cas" => render_json_res(req.state().cas_stats().await),
+            "/stats/repo" if req.state().repo_stats_visible(&req.actor()) => render_json_res(req.state().repo_stats()),
+            "/stats/cas" => render_json_res(req.state().cas_stats(&req.actor()).await),
             _ => Err(req),
         },
         _ => Err(req),
@@ -1009,6 +1176,7 @@ macro_rules! aa {
                 //   "Invalid credentials: Invalid credentials: Session expired"
                 match err {
                     Error::ApiInvalidCredentials(_)
+                    | Error::ApiLoginThrottled(_)
                     | Error::ApiInsufficientRights(_)
                     | Error::ApiAuthPermanentError(_)
                     | Error::ApiAuthTransientError(_)
@@ -1021,6 +1189,78 @@ macro_rules! aa {
     }};
 }
 
+/// Public endpoints for prospective child CAs, used if child onboarding is enabled:
+///   POST /onboarding/<ca>/child_request            submit an RFC 8183 child request
+///   POST /onboarding/<ca>/<child>/parent_response  get the parent response using the one-time token
+async fn onboarding(req: Request) -> RoutingResult {
+    if req.path().segment() == "onboarding" {
+        let mut path = req.path().clone();
+        let ca = match path.path_arg() {
+            Some(ca) => ca,
+            None => return render_error(Error::ApiInvalidHandle),
+        };
+
+        match path.next() {
+            Some("child_request") if req.is_post() => onboarding_child_request(req, ca).await,
+            Some(child) => match (ChildHandle::from_str(child), path.next()) {
+                (Ok(child), Some("parent_response")) if req.is_post() => {
+                    onboarding_parent_response(req, ca, child).await
+                }
+                _ => render_unknown_method(),
+            },
+            None => render_unknown_method(),
+        }
+    } else {
+        Err(req)
+    }
+}
+
+async fn onboarding_child_request(req: Request, ca: Handle) -> RoutingResult {
+    if req.state().config.child_onboarding == ChildOnboardingMode::Token {
+        aa!(
+            req,
+            Permission::CHILD_REQUEST,
+            ca.clone(),
+            onboarding_child_request_submit(req, ca).await
+        )
+    } else {
+        onboarding_child_request_submit(req, ca).await
+    }
+}
+
+async fn onboarding_child_request_submit(req: Request, ca: Handle) -> RoutingResult {
+    let actor = req.actor();
+    let server = req.state().clone();
+
+    let bytes = match req.api_bytes().await {
+        Ok(bytes) => bytes,
+        Err(e) => return render_error(e),
+    };
+
+    match rfc8183::ChildRequest::validate(bytes.as_ref()) {
+        Ok(child_req) => render_json_res(server.ca_child_request_submit(&ca, child_req, &actor).await),
+        Err(e) => render_error(Error::CaChildRequestInvalidXml(ca, e.to_string())),
+    }
+}
+
+/// The one-time token is expected as the plain text body. It is not passed
+/// in the Authorization header, because it is not an API token.
+async fn onboarding_parent_response(req: Request, ca: Handle, child: ChildHandle) -> RoutingResult {
+    let actor = req.actor();
+    let server = req.state().clone();
+
+    let token = match req.api_bytes().await.map(|bytes| String::from_utf8(bytes.to_vec())) {
+        Ok(Ok(token)) => Token::from(token.trim()),
+        Ok(Err(e)) => return render_error(Error::custom(e)),
+        Err(e) => return render_error(e),
+    };
+
+    match server.ca_child_request_complete(&ca, child, token, &actor).await {
+        Ok(res) => Ok(HttpResponse::xml(res.encode_vec())),
+        Err(e) => render_error(e),
+    }
+}
+
 /// Maps the API methods
 async fn api(req: Request) -> RoutingResult {
     if !req.path().full().starts_with("/api/v1") {
@@ -1039,6 +1279,19 @@ async fn api(req: Request) -> RoutingResult {
                         Some("bulk") => api_bulk(req, &mut path).await,
                         Some("cas") => api_cas(req, &mut path).await,
                         Some("pubd") => aa!(req, Permission::PUB_ADMIN, api_publication_server(req, &mut path).await),
+                        Some("ta") => aa!(req, Permission::CA_ADMIN, api_ta(req, &mut path).await),
+                        #[cfg(feature = "multi-user")]
+                        Some("tokens") => aa!(req, Permission::TOKEN_ADMIN, api_tokens(req, &mut path).await),
+                        #[cfg(feature = "multi-user")]
+                        Some("policy") => aa!(req, Permission::POLICY_ADMIN, api_policy(req, &mut path).await),
+                        #[cfg(feature = "multi-user")]
+                        Some("tenants") => aa!(req, Permission::TENANT_ADMIN, api_tenants(req, &mut path).await),
+                        #[cfg(feature = "multi-user")]
+                        Some("users") => aa!(req, Permission::USER_ADMIN, api_users(req, &mut path).await),
+                        #[cfg(feature = "multi-user")]
+                        Some("password") => api_password(req).await,
+                        #[cfg(feature = "multi-user")]
+                        Some("mfa") => api_mfa(req, &mut path).await,
                         _ => render_unknown_method(),
                     }
                 })
@@ -1062,12 +1315,196 @@ async fn api_authorized(req: Request) -> RoutingResult {
     )
 }
 
+#[cfg(feature = "multi-user")]
+async fn api_tokens(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match path.next() {
+        None => match *req.method() {
+            Method::GET => render_json(req.state().api_token_list()),
+            Method::POST => {
+                let actor = req.actor();
+                let server = req.state().clone();
+                match req.json().await {
+                    Ok(token_req) => render_json_res(server.api_token_create(token_req, &actor)),
+                    Err(e) => render_error(e),
+                }
+            }
+            _ => render_unknown_method(),
+        },
+        Some(name) => {
+            let name = name.to_string();
+            match *req.method() {
+                Method::DELETE if path.next().is_none() => render_empty_res(req.state().api_token_revoke(&name)),
+                _ => render_unknown_method(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "multi-user")]
+async fn api_policy(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match (path.next(), path.next()) {
+        (Some("reload"), None) => match *req.method() {
+            Method::POST => render_empty_res(req.state().policy_reload()),
+            _ => render_unknown_method(),
+        },
+        (Some("explain"), None) => match *req.method() {
+            Method::POST => {
+                let server = req.state().clone();
+                match req.json().await {
+                    Ok(explain_req) => render_json_res(server.policy_explain(explain_req)),
+                    Err(e) => render_error(e),
+                }
+            }
+            _ => render_unknown_method(),
+        },
+        _ => render_unknown_method(),
+    }
+}
+
+#[cfg(feature = "multi-user")]
+async fn api_tenants(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match path.next() {
+        None => match *req.method() {
+            Method::GET => render_json(req.state().tenant_list()),
+            Method::POST => {
+                let server = req.state().clone();
+                match req.json().await {
+                    Ok(definition) => render_json_res(server.tenant_create(definition)),
+                    Err(e) => render_error(e),
+                }
+            }
+            _ => render_unknown_method(),
+        },
+        Some(name) => {
+            let name = name.to_string();
+            match (path.next(), path.next(), path.next()) {
+                (None, _, _) => match *req.method() {
+                    Method::GET => render_json_res(req.state().tenant_get(&name)),
+                    Method::POST => {
+                        let server = req.state().clone();
+                        match req.json::<TenantDefinition>().await {
+                            Ok(definition) if definition.name() == name => {
+                                render_json_res(server.tenant_update(definition))
+                            }
+                            Ok(definition) => render_error(Error::TenantInvalid(format!(
+                                "name '{}' does not match tenant '{}'",
+                                definition.name(),
+                                name
+                            ))),
+                            Err(e) => render_error(e),
+                        }
+                    }
+                    Method::DELETE => render_empty_res(req.state().tenant_delete(&name)),
+                    _ => render_unknown_method(),
+                },
+                (Some("users"), Some(user), None) => {
+                    let user = user.to_string();
+                    match *req.method() {
+                        Method::POST => render_empty_res(req.state().tenant_add_user(&name, &user)),
+                        Method::DELETE => render_empty_res(req.state().tenant_remove_user(&name, &user)),
+                        _ => render_unknown_method(),
+                    }
+                }
+                _ => render_unknown_method(),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "multi-user")]
+async fn api_users(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match path.next() {
+        None => match *req.method() {
+            Method::GET => render_json_res(req.state().user_list()),
+            Method::POST => {
+                let server = req.state().clone();
+                match req.json().await {
+                    Ok(request) => render_json_res(server.user_add(request)),
+                    Err(e) => render_error(e),
+                }
+            }
+            _ => render_unknown_method(),
+        },
+        Some(id) => {
+            let id = id.to_string();
+            match (path.next(), path.next()) {
+                (None, _) => match *req.method() {
+                    Method::DELETE => render_empty_res(req.state().user_remove(&id)),
+                    _ => render_unknown_method(),
+                },
+                (Some("disable"), None) => match *req.method() {
+                    Method::POST => render_json_res(req.state().user_set_disabled(&id, true)),
+                    _ => render_unknown_method(),
+                },
+                (Some("enable"), None) => match *req.method() {
+                    Method::POST => render_json_res(req.state().user_set_disabled(&id, false)),
+                    _ => render_unknown_method(),
+                },
+                (Some("password"), None) => match *req.method() {
+                    Method::POST => {
+                        let server = req.state().clone();
+                        match req.json::<PasswordChange>().await {
+                            Ok(change) => render_empty_res(server.user_reset_password(&id, change.password())),
+                            Err(e) => render_error(e),
+                        }
+                    }
+                    _ => render_unknown_method(),
+                },
+                (Some("mfa"), None) => match *req.method() {
+                    Method::DELETE => render_empty_res(req.state().user_mfa_reset(&id)),
+                    _ => render_unknown_method(),
+                },
+                _ => render_unknown_method(),
+            }
+        }
+    }
+}
+
+/// Lets a local user change their own password. This only requires LOGIN, so
+/// that users who must change their password are able to.
+#[cfg(feature = "multi-user")]
+async fn api_password(req: Request) -> RoutingResult {
+    match *req.method() {
+        Method::POST => {
+            let server = req.state().clone();
+            let actor = req.actor();
+            match req.json::<PasswordChange>().await {
+                Ok(change) => render_empty_res(server.user_change_password(change.password(), &actor)),
+                Err(e) => render_error(e),
+            }
+        }
+        _ => render_unknown_method(),
+    }
+}
+
+/// Lets a user of the config file provider manage their own second factor.
+/// This only requires LOGIN, like changing the password.
+#[cfg(feature = "multi-user")]
+async fn api_mfa(req: Request, path: &mut RequestPath) -> RoutingResult {
+    let server = req.state().clone();
+    let actor = req.actor();
+    match (path.next(), req.method().clone()) {
+        (None, Method::GET) => render_json_res(server.mfa_status(&actor)),
+        (Some("enrol"), Method::POST) => render_json_res(server.mfa_enrol(&actor)),
+        (Some("confirm"), Method::POST) => match req.json::<MfaCode>().await {
+            Ok(code) => render_empty_res(server.mfa_confirm(code.code(), &actor)),
+            Err(e) => render_error(e),
+        },
+        (Some("disable"), Method::POST) => match req.json::<MfaCode>().await {
+            Ok(code) => render_empty_res(server.mfa_disable(code.code(), &actor)),
+            Err(e) => render_error(e),
+        },
+        _ => render_unknown_method(),
+    }
+}
+
 async fn api_bulk(req: Request, path: &mut RequestPath) -> RoutingResult {
     match path.full() {
         "/api/v1/bulk/cas/issues" => api_all_ca_issues(req).await,
         "/api/v1/bulk/cas/sync/parent" => api_refresh_all(req).await,
         "/api/v1/bulk/cas/sync/repo" => api_resync_all(req).await,
         "/api/v1/bulk/cas/publish" => api_republish_all(req).await,
+        "/api/v1/bulk/cas/bgp/simulate" => api_bgp_simulate(req).await,
         _ => render_unknown_method(),
     }
 }
@@ -1083,15 +1520,19 @@ async fn api_cas(req: Request, path: &mut RequestPath) -> RoutingResult {
                 },
                 Some("aspas") => api_ca_aspas(req, path, ca).await,
                 Some("children") => api_ca_children(req, path, ca).await,
+                Some("child_requests") => api_ca_child_requests(req, path, ca).await,
                 Some("history") => api_ca_history(req, path, ca).await,
 
                 Some("id") => api_ca_id(req, path, ca).await,
                 Some("issues") => api_ca_issues(req, ca).await,
                 Some("keys") => api_ca_keys(req, path, ca).await,
                 Some("parents") => api_ca_parents(req, path, ca).await,
+                Some("rename") => api_ca_rename(req, ca).await,
                 Some("repo") => api_ca_repo(req, path, ca).await,
+                Some("resource_templates") => api_ca_resource_templates(req, path, ca).await,
                 Some("routes") => api_ca_routes(req, path, ca).await,
                 Some("stats") => api_ca_stats(req, path, ca).await,
+                Some("suspension_policy") => api_ca_suspension_policy(req, ca, None).await,
                 Some("sync") => api_ca_sync(req, path, ca).await,
 
                 Some("rta") => api_ca_rta(req, path, ca).await,
@@ -1109,9 +1550,22 @@ async fn api_cas(req: Request, path: &mut RequestPath) -> RoutingResult {
 
 async fn api_ca_keys(req: Request, path: &mut RequestPath, ca: Handle) -> RoutingResult {
     match *req.method() {
+        Method::GET => match path.next() {
+            Some("roll_status") => api_ca_kr_status(req, ca).await,
+            _ => render_unknown_method(),
+        },
         Method::POST => match path.next() {
             Some("roll_init") => api_ca_kr_init(req, ca).await,
-            Some("roll_activate") => api_ca_kr_activate(req, ca).await,
+            Some("roll_activate") => match path.next() {
+                None => api_ca_kr_activate(req, ca, false).await,
+                Some("force") => api_ca_kr_activate(req, ca, true).await,
+                _ => render_unknown_method(),
+            },
+            Some("roll_policy") => api_ca_kr_policy_update(req, ca).await,
+            _ => render_unknown_method(),
+        },
+        Method::DELETE => match path.next() {
+            Some("roll_policy") => api_ca_kr_policy_remove(req, ca).await,
             _ => render_unknown_method(),
         },
         _ => render_unknown_method(),
@@ -1143,6 +1597,20 @@ async fn api_ca_repo(req: Request, path: &mut RequestPath, ca: Handle) -> Routin
             _ => render_unknown_method(),
         },
         Some("status") => api_ca_repo_status(req, ca).await,
+        Some("mirrors") => match path.next() {
+            None => match *req.method() {
+                Method::GET => api_ca_repo_mirrors(req, ca).await,
+                _ => render_unknown_method(),
+            },
+            Some(mirror) => {
+                let mirror = mirror.to_string();
+                match *req.method() {
+                    Method::POST => api_ca_repo_mirror_add(req, ca, mirror).await,
+                    Method::DELETE => api_ca_repo_mirror_remove(req, ca, mirror).await,
+                    _ => render_unknown_method(),
+                }
+            }
+        },
         _ => render_unknown_method(),
     }
 }
@@ -1159,6 +1627,14 @@ async fn api_ca_routes(req: Request, path: &mut RequestPath, ca: Handle) -> Rout
             _ => render_unknown_method(),
         },
         Some("analysis") => api_ca_routes_analysis(req, path, ca).await,
+        Some("history") => match *req.method() {
+            Method::GET => api_ca_routes_history(req, path, ca).await,
+            _ => render_unknown_method(),
+        },
+        Some("diff") => match *req.method() {
+            Method::GET => api_ca_routes_diff(req, path, ca).await,
+            _ => render_unknown_method(),
+        },
         _ => render_unknown_method(),
     }
 }
@@ -1167,6 +1643,8 @@ async fn api_ca_stats(req: Request, path: &mut RequestPath, ca: Handle) -> Routi
     match path.next() {
         Some("children") => match path.next() {
             Some("connections") => api_ca_stats_child_connections(req, ca).await,
+            Some("undelegated") => api_ca_stats_child_undelegated(req, ca).await,
+            Some("shrinks") => api_ca_stats_child_shrinks(req, ca).await,
             _ => render_unknown_method(),
         },
         _ => render_unknown_method(),
@@ -1207,6 +1685,140 @@ async fn api_publication_server(req: Request, path: &mut RequestPath) -> Routing
     }
 }
 
+//------------ Admin: Trust Anchor --------------------------------------------
+
+async fn api_ta(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match path.next() {
+        Some("proxy") => api_ta_proxy(req, path).await,
+        Some("keyroll") => api_ta_keyroll(req, path).await,
+        _ => render_unknown_method(),
+    }
+}
+
+async fn api_ta_keyroll(req: Request, path: &mut RequestPath) -> RoutingResult {
+    let actor = req.actor();
+    match (req.method().clone(), path.next()) {
+        (Method::GET, None) => render_json_res(req.state().ta_keyroll_status().await),
+        (Method::POST, Some("init")) => render_empty_res(req.state().ta_keyroll_init(&actor).await),
+        (Method::POST, Some("activate")) => render_empty_res(req.state().ta_keyroll_activate(&actor).await),
+        (Method::POST, Some("finish")) => render_empty_res(req.state().ta_keyroll_finish(&actor).await),
+        _ => render_unknown_method(),
+    }
+}
+
+async fn api_ta_proxy(req: Request, path: &mut RequestPath) -> RoutingResult {
+    match path.next() {
+        Some("init") => match *req.method() {
+            Method::POST => render_empty_res(req.state().ta_proxy_init().await),
+            _ => render_unknown_method(),
+        },
+        Some("info") => match *req.method() {
+            Method::GET => render_json_res(req.state().ta_proxy_info().await),
+            _ => render_unknown_method(),
+        },
+        Some("repo") => match path.next() {
+            Some("request.json") => match *req.method() {
+                Method::GET => render_json_res(req.state().ta_proxy_publisher_request().await),
+                _ => render_unknown_method(),
+            },
+            Some("request.xml") => match *req.method() {
+                Method::GET => match req.state().ta_proxy_publisher_request().await {
+                    Ok(res) => Ok(HttpResponse::xml(res.encode_vec())),
+                    Err(e) => render_error(e),
+                },
+                _ => render_unknown_method(),
+            },
+            None => match *req.method() {
+                Method::POST => {
+                    let actor = req.actor();
+                    let server = req.state().clone();
+                    match req
+                        .api_bytes()
+                        .await
+                        .map(|bytes| extract_repository_contact(&ta_handle(), bytes))
+                    {
+                        Ok(Ok(contact)) => render_empty_res(server.ta_proxy_repository_update(contact, &actor).await),
+                        Ok(Err(e)) | Err(e) => render_error(e),
+                    }
+                }
+                _ => render_unknown_method(),
+            },
+            _ => render_unknown_method(),
+        },
+        Some("signer") => match path.next() {
+            Some("add") => match *req.method() {
+                Method::POST => {
+                    let actor = req.actor();
+                    let server = req.state().clone();
+                    match req.json().await {
+                        Ok(info) => render_empty_res(server.ta_proxy_signer_add(info, &actor).await),
+                        Err(e) => render_error(e),
+                    }
+                }
+                _ => render_unknown_method(),
+            },
+            Some("request") => match *req.method() {
+                Method::GET => render_json_res(req.state().ta_proxy_signer_get_request().await),
+                Method::POST => {
+                    let actor = req.actor();
+                    render_json_res(req.state().ta_proxy_signer_make_request(&actor).await)
+                }
+                _ => render_unknown_method(),
+            },
+            Some("response") => match *req.method() {
+                Method::POST => {
+                    let actor = req.actor();
+                    let server = req.state().clone();
+                    match req.json().await {
+                        Ok(response) => {
+                            render_empty_res(server.ta_proxy_signer_process_response(response, &actor).await)
+                        }
+                        Err(e) => render_error(e),
+                    }
+                }
+                _ => render_unknown_method(),
+            },
+            _ => render_unknown_method(),
+        },
+        Some("children") => match *req.method() {
+            Method::POST => {
+                let actor = req.actor();
+                let server = req.state().clone();
+                match req.json().await {
+                    Ok(child_req) => render_json_res(server.ta_proxy_children_add(child_req, &actor).await),
+                    Err(e) => render_error(e),
+                }
+            }
+            _ => render_unknown_method(),
+        },
+        Some("history") => match *req.method() {
+            Method::GET => {
+                // /api/v1/ta/proxy/history  /<rows>/<offset>/<after>/<before>
+                let mut crit = CommandHistoryCriteria::default();
+
+                if let Some(rows) = path.path_arg() {
+                    crit.set_rows(rows);
+                }
+
+                if let Some(offset) = path.path_arg() {
+                    crit.set_offset(offset);
+                }
+
+                if let Some(after) = path.path_arg() {
+                    crit.set_after(after);
+                }
+
+                if let Some(before) = path.path_arg() {
+                    crit.set_before(before);
+                }
+                render_json_res(req.state().ta_proxy_history(crit).await)
+            }
+            _ => render_unknown_method(),
+        },
+        _ => render_unknown_method(),
+    }
+}
+
 async fn api_publishers(req: Request, path: &mut RequestPath) -> RoutingResult {
     match *req.method() {
         Method::GET => match path.path_arg() {
@@ -1366,6 +1978,24 @@ async fn api_ca_stats_child_connections(req: Request, ca: Handle) -> RoutingResu
     )
 }
 
+async fn api_ca_stats_child_undelegated(req: Request, ca: Handle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::CA_READ,
+        ca.clone(),
+        render_json_res(req.state().ca_stats_child_undelegated(&ca).await)
+    )
+}
+
+async fn api_ca_stats_child_shrinks(req: Request, ca: Handle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::CA_READ,
+        ca.clone(),
+        render_json_res(req.state().ca_stats_child_shrinks(&ca).await)
+    )
+}
+
 async fn api_ca_parent_contact(req: Request, ca: Handle, child: ChildHandle) -> RoutingResult {
     aa!(
         req,
@@ -1405,6 +2035,22 @@ async fn api_all_ca_issues(req: Request) -> RoutingResult {
     }
 }
 
+/// Simulates the effect of ROA and resource changes for any number of CAs
+/// on all announcements for resources held by CAs in this Krill.
+async fn api_bgp_simulate(req: Request) -> RoutingResult {
+    match *req.method() {
+        Method::POST => aa!(req, Permission::ROUTES_ANALYSIS, {
+            let actor = req.actor();
+            let server = req.state().clone();
+            match req.json().await {
+                Err(e) => render_error(e),
+                Ok(request) => render_json_res(server.cas_bgp_simulate(request, &actor).await),
+            }
+        }),
+        _ => render_unknown_method(),
+    }
+}
+
 /// Returns the health (state) for a given CA.
 async fn api_ca_issues(req: Request, ca: Handle) -> RoutingResult {
     match *req.method() {
@@ -1472,6 +2118,23 @@ async fn api_ca_delete(req: Request, handle: Handle) -> RoutingResult {
     )
 }
 
+async fn api_ca_rename(req: Request, handle: Handle) -> RoutingResult {
+    match *req.method() {
+        Method::POST => aa!(req, Permission::CA_DELETE, handle.clone(), {
+            aa!(req, Permission::CA_CREATE, {
+                let actor = req.actor();
+                let server = req.state().clone();
+
+                match req.json::<CertAuthRename>().await {
+                    Ok(rename) => render_json_res(server.ca_rename(&handle, rename, &actor).await),
+                    Err(e) => render_error(e),
+                }
+            })
+        }),
+        _ => render_unknown_method(),
+    }
+}
+
 async fn api_ca_my_parent_contact(req: Request, ca: Handle, parent: ParentHandle) -> RoutingResult {
     aa!(
         req,
@@ -1531,6 +2194,7 @@ async fn api_ca_children(req: Request, path: &mut RequestPath, ca: Handle) -> Ro
             Some("contact") => api_ca_parent_contact(req, ca, child).await,
             Some("parent_response.json") => api_ca_parent_res_json(req, ca, child).await,
             Some("parent_response.xml") => api_ca_parent_res_xml(req, ca, child).await,
+            Some("suspension_policy") => api_ca_suspension_policy(req, ca, Some(child)).await,
             _ => render_unknown_method(),
         },
         None => match *req.method() {
@@ -1540,6 +2204,97 @@ async fn api_ca_children(req: Request, path: &mut RequestPath, ca: Handle) -> Ro
     }
 }
 
+/// Set or remove the suspension policy for inactive children of a CA, or for
+/// a specific child if one is given.
+async fn api_ca_suspension_policy(req: Request, ca: Handle, child: Option<ChildHandle>) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, ca.clone(), {
+        let actor = req.actor();
+        let server = req.state().clone();
+
+        let policy = match *req.method() {
+            Method::POST => match req.json().await {
+                Ok(policy) => Some(policy),
+                Err(e) => return render_error(e),
+            },
+            Method::DELETE => None,
+            _ => return render_unknown_method(),
+        };
+
+        match child {
+            Some(child) => render_empty_res(
+                server
+                    .ca_child_suspension_policy_update(&ca, child, policy, &actor)
+                    .await,
+            ),
+            None => render_empty_res(server.ca_suspension_policy_update(&ca, policy, &actor).await),
+        }
+    })
+}
+
+async fn api_ca_child_requests(req: Request, path: &mut RequestPath, ca: Handle) -> RoutingResult {
+    match path.path_arg() {
+        Some(child) => match path.next() {
+            None if req.is_delete() => api_ca_child_request_reject(req, ca, child).await,
+            Some("approve") if req.is_post() => api_ca_child_request_approve(req, ca, child).await,
+            _ => render_unknown_method(),
+        },
+        None if req.is_get() => aa!(
+            req,
+            Permission::CA_READ,
+            ca.clone(),
+            render_json_res(req.state().ca_child_requests(&ca).await)
+        ),
+        None => render_unknown_method(),
+    }
+}
+
+async fn api_ca_child_request_approve(req: Request, ca: Handle, child: ChildHandle) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, ca.clone(), {
+        let actor = req.actor();
+        let server = req.state().clone();
+        match req.json().await {
+            Ok(resources) => render_json_res(server.ca_child_request_approve(&ca, child, resources, &actor).await),
+            Err(e) => render_error(e),
+        }
+    })
+}
+
+async fn api_ca_child_request_reject(req: Request, ca: Handle, child: ChildHandle) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, ca.clone(), {
+        let actor = req.actor();
+        render_empty_res(req.state().ca_child_request_reject(&ca, child, &actor).await)
+    })
+}
+
+async fn api_ca_resource_templates(req: Request, path: &mut RequestPath, ca: Handle) -> RoutingResult {
+    match path.path_arg() {
+        Some(name) => match path.next() {
+            None if req.is_delete() => aa!(req, Permission::CA_UPDATE, ca.clone(), {
+                let actor = req.actor();
+                render_empty_res(req.state().ca_resource_template_remove(&ca, name, &actor).await)
+            }),
+            _ => render_unknown_method(),
+        },
+        None => match *req.method() {
+            Method::GET => aa!(
+                req,
+                Permission::CA_READ,
+                ca.clone(),
+                render_json_res(req.state().ca_resource_templates(&ca).await)
+            ),
+            Method::POST => aa!(req, Permission::CA_UPDATE, ca.clone(), {
+                let actor = req.actor();
+                let server = req.state().clone();
+                match req.json().await {
+                    Ok(template) => render_empty_res(server.ca_resource_template_update(&ca, template, &actor).await),
+                    Err(e) => render_error(e),
+                }
+            }),
+            _ => render_unknown_method(),
+        },
+    }
+}
+
 async fn api_ca_history_commands(req: Request, path: &mut RequestPath, handle: Handle) -> RoutingResult {
     match *req.method() {
         Method::GET => aa!(req, Permission::CA_READ, handle.clone(), {
@@ -1715,6 +2470,38 @@ async fn api_ca_repo_update(req: Request, handle: Handle) -> RoutingResult {
     })
 }
 
+async fn api_ca_repo_mirrors(req: Request, handle: Handle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::CA_READ,
+        handle.clone(),
+        render_json_res(req.state().ca_repo_mirrors(&handle).await)
+    )
+}
+
+async fn api_ca_repo_mirror_add(req: Request, handle: Handle, mirror: RepositoryMirrorName) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, handle.clone(), {
+        let actor = req.actor();
+        let server = req.state().clone();
+
+        match req
+            .api_bytes()
+            .await
+            .map(|bytes| extract_repository_contact(&handle, bytes))
+        {
+            Ok(Ok(contact)) => render_empty_res(server.ca_repo_mirror_add(handle, mirror, contact, &actor).await),
+            Ok(Err(e)) | Err(e) => render_error(e),
+        }
+    })
+}
+
+async fn api_ca_repo_mirror_remove(req: Request, handle: Handle, mirror: RepositoryMirrorName) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, handle.clone(), {
+        let actor = req.actor();
+        render_empty_res(req.state().ca_repo_mirror_remove(handle, mirror, &actor).await)
+    })
+}
+
 async fn api_ca_parent_add_or_update(req: Request, ca: Handle, parent_override: Option<Handle>) -> RoutingResult {
     aa!(req, Permission::CA_UPDATE, ca.clone(), {
         let actor = req.actor();
@@ -1779,11 +2566,43 @@ async fn api_ca_kr_init(req: Request, ca: Handle) -> RoutingResult {
     })
 }
 
-/// Force key activation for all new keys, i.e. use a staging period of 0 seconds.
-async fn api_ca_kr_activate(req: Request, ca: Handle) -> RoutingResult {
+/// Activate all new keys. Unless forced, this is refused if the staging period did not
+/// pass yet, or the repository did not yet publish the staged objects.
+async fn api_ca_kr_activate(req: Request, ca: Handle, force: bool) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, ca.clone(), {
+        let actor = req.actor();
+        render_empty_res(req.state().ca_keyroll_activate(ca, force, &actor).await)
+    })
+}
+
+/// Show the key roll status for each resource class of a CA.
+async fn api_ca_kr_status(req: Request, ca: Handle) -> RoutingResult {
+    aa!(
+        req,
+        Permission::CA_READ,
+        ca.clone(),
+        render_json_res(req.state().ca_keyroll_status(&ca).await)
+    )
+}
+
+/// Set the policy for automated key rolls for a CA.
+async fn api_ca_kr_policy_update(req: Request, ca: Handle) -> RoutingResult {
+    aa!(req, Permission::CA_UPDATE, ca.clone(), {
+        let actor = req.actor();
+        let state = req.state().clone();
+
+        match req.json().await {
+            Err(e) => render_error(e),
+            Ok(policy) => render_empty_res(state.ca_keyroll_policy_update(ca, Some(policy), &actor).await),
+        }
+    })
+}
+
+/// Remove the policy for automated key rolls for a CA, i.e. go back to manual key rolls.
+async fn api_ca_kr_policy_remove(req: Request, ca: Handle) -> RoutingResult {
     aa!(req, Permission::CA_UPDATE, ca.clone(), {
         let actor = req.actor();
-        render_empty_res(req.state().ca_keyroll_activate(ca, &actor).await)
+        render_empty_res(req.state().ca_keyroll_policy_update(ca, None, &actor).await)
     })
 }
 
@@ -1896,6 +2715,37 @@ async fn api_ca_routes_show(req: Request, ca: Handle) -> RoutingResult {
     })
 }
 
+/// Show the history of changes to the route authorizations for this CA
+/// /api/v1/cas/{ca}/routes/history[/<prefix>]
+async fn api_ca_routes_history(req: Request, path: &mut RequestPath, ca: Handle) -> RoutingResult {
+    aa!(req, Permission::ROUTES_READ, ca.clone(), {
+        // The prefix contains a '/', so it spans the remaining path segments.
+        match path.remaining().trim_matches('/') {
+            "" => render_json_res(req.state().ca_routes_history(&ca, None).await),
+            prefix => match TypedPrefix::from_str(prefix) {
+                Ok(prefix) => render_json_res(req.state().ca_routes_history(&ca, Some(prefix)).await),
+                Err(_) => render_error(Error::ApiInvalidPrefix),
+            },
+        }
+    })
+}
+
+/// Show the difference between the route authorizations for this CA at two
+/// moments in time, given as seconds since the epoch.
+/// /api/v1/cas/{ca}/routes/diff/<from>/<to>
+async fn api_ca_routes_diff(req: Request, path: &mut RequestPath, ca: Handle) -> RoutingResult {
+    aa!(req, Permission::ROUTES_READ, ca.clone(), {
+        match (path.path_arg(), path.path_arg()) {
+            (Some(from), Some(to)) => render_json_res(
+                req.state()
+                    .ca_routes_diff(&ca, Timestamp::new(from), Timestamp::new(to))
+                    .await,
+            ),
+            _ => render_error(Error::ApiInvalidSeconds),
+        }
+    })
+}
+
 /// Show the state of ROAs vs BGP for this CA
 async fn api_ca_routes_analysis(req: Request, path: &mut RequestPath, ca: Handle) -> RoutingResult {
     aa!(req, Permission::ROUTES_ANALYSIS, ca.clone(), {
@@ -1929,21 +2779,48 @@ async fn api_ca_routes_analysis(req: Request, path: &mut RequestPath, ca: Handle
 
 //------------ Admin: Force republish ----------------------------------------
 
+/// Returns the CAs that a bulk operation should run on. Actors with CA_ADMIN
+/// run bulk operations on all CAs, in which case `None` is returned. Other
+/// actors, e.g. users bound to a tenant, run them on the CAs in their CA list
+/// which they may update, and are refused if there are none.
+fn bulk_cas(req: &Request) -> Result<Option<Vec<Handle>>, HttpResponse> {
+    let actor = req.actor();
+    match actor.is_allowed(Permission::CA_ADMIN, NO_RESOURCE) {
+        Ok(true) => Ok(None),
+        Ok(false) => match req.state().ca_handles_permitted(&actor, Permission::CA_UPDATE) {
+            Ok(cas) if cas.is_empty() => Err(HttpResponse::forbidden(format!(
+                "User '{}' does not have permission '{}' on any CA",
+                actor.name(),
+                Permission::CA_UPDATE
+            ))),
+            Ok(cas) => Ok(Some(cas)),
+            Err(e) => Err(HttpResponse::response_from_error(e)),
+        },
+        Err(e) => Err(HttpResponse::response_from_error(e)),
+    }
+}
+
 async fn api_republish_all(req: Request) -> RoutingResult {
     match *req.method() {
-        Method::POST => aa!(req, Permission::CA_ADMIN, {
-            render_empty_res(req.state().republish_all().await)
-        }),
+        Method::POST => match bulk_cas(&req) {
+            Ok(None) => render_empty_res(req.state().republish_all().await),
+            Ok(Some(cas)) => render_empty_res(req.state().republish(&cas).await),
+            Err(response) => Ok(response),
+        },
         _ => render_unknown_method(),
     }
 }
 
 async fn api_resync_all(req: Request) -> RoutingResult {
     match *req.method() {
-        Method::POST => aa!(req, Permission::CA_ADMIN, {
-            let actor = req.actor();
-            render_empty_res(req.state().cas_repo_sync_all(&actor).await)
-        }),
+        Method::POST => match bulk_cas(&req) {
+            Ok(None) => {
+                let actor = req.actor();
+                render_empty_res(req.state().cas_repo_sync_all(&actor).await)
+            }
+            Ok(Some(cas)) => render_empty_res(req.state().cas_repo_sync(&cas).await),
+            Err(response) => Ok(response),
+        },
         _ => render_unknown_method(),
     }
 }
@@ -1951,10 +2828,14 @@ async fn api_resync_all(req: Request) -> RoutingResult {
 /// Refresh all CAs
 async fn api_refresh_all(req: Request) -> RoutingResult {
     match *req.method() {
-        Method::POST => aa!(req, Permission::CA_ADMIN, {
+        Method::POST => {
             let actor = req.actor();
-            render_empty_res(req.state().cas_refresh_all(&actor).await)
-        }),
+            match bulk_cas(&req) {
+                Ok(None) => render_empty_res(req.state().cas_refresh_all(&actor).await),
+                Ok(Some(cas)) => render_empty_res(req.state().cas_refresh(&cas, &actor).await),
+                Err(response) => Ok(response),
+            }
+        }
         _ => render_unknown_method(),
     }
 }
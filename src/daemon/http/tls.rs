@@ -21,13 +21,16 @@ use std::{
     net::SocketAddr,
     path::{Path, PathBuf},
     pin::Pin,
-    sync::Arc,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
 };
 
+use bytes::Bytes;
 use futures::ready;
 use tokio::io::{AsyncRead, AsyncWrite};
-use tokio_rustls::rustls::{NoClientAuth, ServerConfig, TLSError};
+use tokio_rustls::rustls::{
+    AllowAnyAnonymousOrAuthenticatedClient, NoClientAuth, RootCertStore, ServerConfig, Session, TLSError,
+};
 
 use hyper::server::{
     accept::Accept,
@@ -86,6 +89,8 @@ pub(crate) enum TlsConfigError {
     EmptyKey,
     /// An error from an invalid key
     InvalidKey(TLSError),
+    /// An Error parsing the client CA certificates
+    ClientCaParseError,
 }
 
 impl std::fmt::Display for TlsConfigError {
@@ -97,6 +102,7 @@ impl std::fmt::Display for TlsConfigError {
             TlsConfigError::RsaParseError => write!(f, "rsa parse error"),
             TlsConfigError::EmptyKey => write!(f, "key contains no private key"),
             TlsConfigError::InvalidKey(err) => write!(f, "key contains an invalid key, {}", err),
+            TlsConfigError::ClientCaParseError => write!(f, "client CA certificate parse error"),
         }
     }
 }
@@ -107,6 +113,7 @@ impl std::error::Error for TlsConfigError {}
 pub(crate) struct TlsConfigBuilder {
     cert: Box<dyn Read + Send + Sync>,
     key: Box<dyn Read + Send + Sync>,
+    client_ca: Option<Box<dyn Read + Send + Sync>>,
 }
 
 impl std::fmt::Debug for TlsConfigBuilder {
@@ -121,6 +128,7 @@ impl TlsConfigBuilder {
         TlsConfigBuilder {
             key: Box::new(io::empty()),
             cert: Box::new(io::empty()),
+            client_ca: None,
         }
     }
 
@@ -142,6 +150,19 @@ impl TlsConfigBuilder {
         self
     }
 
+    /// Specify the file path for the CA certificates used to verify client
+    /// certificates. If set, clients are asked for a certificate, but they
+    /// are not required to present one: RFC 6492, RFC 8181 and RRDP clients
+    /// use the same listener. Whether the API requires a certificate is
+    /// checked per request instead.
+    pub(crate) fn client_ca_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.client_ca = Some(Box::new(LazyFile {
+            path: path.as_ref().into(),
+            file: None,
+        }));
+        self
+    }
+
     pub(crate) fn build(mut self) -> Result<ServerConfig, TlsConfigError> {
         let mut cert_rdr = BufReader::new(self.cert);
         let cert = tokio_rustls::rustls::internal::pemfile::certs(&mut cert_rdr)
@@ -173,7 +194,22 @@ impl TlsConfigBuilder {
             }
         };
 
-        let mut config = ServerConfig::new(NoClientAuth::new());
+        let client_cert_verifier = match self.client_ca {
+            Some(client_ca) => {
+                let mut roots = RootCertStore::empty();
+                let (valid, _) = roots
+                    .add_pem_file(&mut BufReader::new(client_ca))
+                    .map_err(|()| TlsConfigError::ClientCaParseError)?;
+                if valid == 0 {
+                    return Err(TlsConfigError::ClientCaParseError);
+                }
+
+                AllowAnyAnonymousOrAuthenticatedClient::new(roots)
+            }
+            None => NoClientAuth::new(),
+        };
+
+        let mut config = ServerConfig::new(client_cert_verifier);
         config.set_single_cert(cert, key).map_err(TlsConfigError::InvalidKey)?;
         config.set_protocols(&["h2".into(), "http/1.1".into()]);
         Ok(config)
@@ -213,6 +249,47 @@ impl Transport for TlsStream {
     }
 }
 
+//------------ ClientCertificate ---------------------------------------------
+
+/// The DER encoded end-entity certificate presented by a TLS client, which
+/// has been verified against the configured client CA certificates. This is
+/// added to the extensions of each request on the connection.
+#[derive(Clone, Debug)]
+pub struct ClientCertificate(Bytes);
+
+impl ClientCertificate {
+    pub fn new(der: Bytes) -> Self {
+        ClientCertificate(der)
+    }
+
+    pub fn to_der(&self) -> &Bytes {
+        &self.0
+    }
+}
+
+/// Holds the client certificate for a connection, which only becomes known
+/// when the TLS handshake is done. This happens before the first request on
+/// the connection is read.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ClientCertificateSlot(Arc<Mutex<Option<ClientCertificate>>>);
+
+impl ClientCertificateSlot {
+    fn set(&self, stream: &tokio_rustls::server::TlsStream<AddrStream>) {
+        let cert = stream
+            .get_ref()
+            .1
+            .get_peer_certificates()
+            .and_then(|certs| certs.into_iter().next())
+            .map(|cert| ClientCertificate::new(Bytes::from(cert.0)));
+
+        *self.0.lock().unwrap() = cert;
+    }
+
+    pub(crate) fn get(&self) -> Option<ClientCertificate> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
 enum State {
     Handshaking(tokio_rustls::Accept<AddrStream>),
     Streaming(tokio_rustls::server::TlsStream<AddrStream>),
@@ -223,15 +300,28 @@ enum State {
 // TlsStream implements AsyncRead/AsyncWrite handshaking tokio_rustls::Accept first
 pub(crate) struct TlsStream {
     state: State,
+    client_cert: ClientCertificateSlot,
+    remote_addr: SocketAddr,
 }
 
 impl TlsStream {
     fn new(stream: AddrStream, config: Arc<ServerConfig>) -> TlsStream {
+        let remote_addr = stream.remote_addr();
         let accept = tokio_rustls::TlsAcceptor::from(config).accept(stream);
         TlsStream {
             state: State::Handshaking(accept),
+            client_cert: ClientCertificateSlot::default(),
+            remote_addr,
         }
     }
+
+    pub(crate) fn remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+
+    pub(crate) fn client_certificate(&self) -> ClientCertificateSlot {
+        self.client_cert.clone()
+    }
 }
 
 impl AsyncRead for TlsStream {
@@ -240,6 +330,7 @@ impl AsyncRead for TlsStream {
         match pin.state {
             State::Handshaking(ref mut accept) => match ready!(Pin::new(accept).poll(cx)) {
                 Ok(mut stream) => {
+                    pin.client_cert.set(&stream);
                     let result = Pin::new(&mut stream).poll_read(cx, buf);
                     pin.state = State::Streaming(stream);
                     result
@@ -257,6 +348,7 @@ impl AsyncWrite for TlsStream {
         match pin.state {
             State::Handshaking(ref mut accept) => match ready!(Pin::new(accept).poll(cx)) {
                 Ok(mut stream) => {
+                    pin.client_cert.set(&stream);
                     let result = Pin::new(&mut stream).poll_write(cx, buf);
                     pin.state = State::Streaming(stream);
                     result
@@ -12,37 +12,58 @@ use crate::{
         api::{
             AddChildRequest, AllCertAuthIssues, AspaCustomer, AspaDefinitionList, AspaDefinitionUpdates,
             AspaProvidersUpdate, CaCommandDetails, CaRepoDetails, CertAuthInfo, CertAuthInit, CertAuthIssues,
-            CertAuthList, CertAuthStats, ChildCaInfo, ChildHandle, ChildrenConnectionStats, CommandHistory,
-            CommandHistoryCriteria, Handle, ListReply, ParentCaContact, ParentCaReq, ParentHandle,
-            PublicationServerUris, PublishDelta, PublisherDetails, PublisherHandle, RepositoryContact, ResourceSet,
-            RoaDefinition, RoaDefinitionUpdates, RtaList, RtaName, RtaPrepResponse, ServerInfo, TaCertDetails,
-            Timestamp, UpdateChildRequest,
+            CertAuthList, CertAuthRename, CertAuthRenameReport, CertAuthStats, ChildCaInfo, ChildHandle,
+            ChildRequestList, ChildRequestReceipt, ChildShrinkList, ChildSuspensionPolicy, ChildrenConnectionStats,
+            CommandHistory, CommandHistoryCriteria, Handle, KeyRollPolicy, KeyRollStatus, ListReply, ObjectName,
+            ParentCaContact, ParentCaReq, ParentHandle, PublicationServerUris, PublishDelta, PublisherDetails,
+            PublisherHandle, RepositoryContact, RepositoryMirrorList, RepositoryMirrorName, ResourceSet,
+            ResourceTemplate, ResourceTemplateList, ResourceTemplateName, RoaDefinition, RoaDefinitionUpdates,
+            RoaHistory, RtaList, RtaName, RtaPrepResponse, ServerInfo, TaCertDetails, TaKeyRoll, TaKeyRollStatus,
+            Timestamp, Token, TypedPrefix, UndelegatedResources, UpdateChildRequest,
+        },
+        bgp::{
+            resources_after, BgpAnalyser, BgpAnalysisReport, BgpAnalysisSuggestion, BgpAnnouncementSource,
+            BgpSimulationReport, BgpSimulationRequest, BmpListener, CaRoutes, LocalFileFormat, LocalFileLoader,
+            MrtFileLoader, RisDumpLoader,
         },
-        bgp::{BgpAnalyser, BgpAnalysisReport, BgpAnalysisSuggestion},
         crypto::KrillSigner,
+        error::Error,
         eventsourcing::CommandKey,
         remote::rfc8183,
         KrillEmptyResult, KrillResult,
     },
     constants::*,
     daemon::{
-        auth::{providers::AdminTokenAuthProvider, Authorizer, LoggedInUser},
+        audit::{AuditContext, AuditLog, AuditTrail},
+        auth::{
+            common::{permissions::Permission, throttle::LoginThrottleStats},
+            providers::AdminTokenAuthProvider,
+            Authorizer, LoggedInUser,
+        },
         ca::{
             self, ta_handle, testbed_ca_handle, CaStatus, ResourceTaggedAttestation, RouteAuthorizationUpdates,
             RtaContentRequest, RtaPrepareRequest,
         },
-        config::{AuthType, Config},
+        config::{AuthType, BgpSourceType, Config},
         http::HttpResponse,
         mq::MessageQueue,
         scheduler::Scheduler,
     },
     pubd::{RepoStats, RepositoryManager},
+    ta::{TrustAnchorProxyInfo, TrustAnchorSignedRequest, TrustAnchorSignedResponse, TrustAnchorSignerInfo},
 };
 
 #[cfg(feature = "multi-user")]
-use crate::daemon::auth::{
-    common::session::LoginSessionCache,
-    providers::{ConfigFileAuthProvider, OpenIDConnectAuthProvider},
+use crate::{
+    commons::api::{
+        ApiTokenCreated, ApiTokenList, ApiTokenRequest, CertAuthRenameStep, CertAuthRenameUpdate, LocalUserInfo,
+        LocalUserList, LocalUserRequest, MfaEnrolment, MfaStatus, PolicyExplainRequest, PolicyExplanation,
+        TenantDefinition, TenantInfo, TenantList,
+    },
+    daemon::auth::{
+        common::session::LoginSessionCache,
+        providers::{ConfigFileAuthProvider, LdapAuthProvider, OpenIDConnectAuthProvider},
+    },
 };
 
 //------------ KrillServer ---------------------------------------------------
@@ -58,6 +79,9 @@ pub struct KrillServer {
     // Component responsible for API authorization checks
     authorizer: Authorizer,
 
+    // Records the authorization checks for API requests, if configured
+    audit_log: Option<AuditLog>,
+
     // Publication server, with configured publishers
     repo_manager: Arc<RepositoryManager>,
 
@@ -127,9 +151,16 @@ impl KrillServer {
                 config.clone(),
                 OpenIDConnectAuthProvider::new(config.clone(), login_session_cache.clone())?.into(),
             )?,
+            #[cfg(feature = "multi-user")]
+            AuthType::Ldap => Authorizer::new(
+                config.clone(),
+                LdapAuthProvider::new(config.clone(), login_session_cache.clone())?.into(),
+            )?,
         };
         let system_actor = authorizer.actor_from_def(ACTOR_DEF_KRILL);
 
+        let audit_log = AuditLog::create(&config)?;
+
         // for now, support that existing embedded repositories are still supported.
         // this should be removed in future after people have had a chance to separate.
         let repo_manager = Arc::new(RepositoryManager::build(config.clone(), signer.clone())?);
@@ -198,9 +229,9 @@ impl KrillServer {
         }
 
         let bgp_analyser = Arc::new(BgpAnalyser::new(
-            config.bgp_risdumps_enabled,
-            &config.bgp_risdumps_v4_uri,
-            &config.bgp_risdumps_v6_uri,
+            Self::bgp_announcement_source(&config)?,
+            config.bgp_refresh_minutes,
+            config.bgp_min_peers(),
         ));
 
         let scheduler = Scheduler::build(
@@ -217,6 +248,7 @@ impl KrillServer {
             service_uri,
             work_dir: work_dir.clone(),
             authorizer,
+            audit_log,
             repo_manager,
             ca_manager,
             bgp_analyser,
@@ -229,6 +261,47 @@ impl KrillServer {
         })
     }
 
+    /// Returns the configured source for BGP announcements, if any. Note that
+    /// this will start listening for BMP connections if so configured.
+    fn bgp_announcement_source(config: &Config) -> KrillResult<Option<BgpAnnouncementSource>> {
+        let source_file = || {
+            // presence of the file is checked when the config is verified
+            config.bgp_source_file.clone().unwrap_or_default()
+        };
+
+        let source = match config.bgp_source {
+            BgpSourceType::RisDumps => {
+                if config.bgp_risdumps_enabled {
+                    Some(BgpAnnouncementSource::RisDumps(RisDumpLoader::new(
+                        &config.bgp_risdumps_v4_uri,
+                        &config.bgp_risdumps_v6_uri,
+                    )))
+                } else {
+                    None
+                }
+            }
+            BgpSourceType::Mrt => Some(BgpAnnouncementSource::Mrt(MrtFileLoader::new(source_file()))),
+            BgpSourceType::Csv => Some(BgpAnnouncementSource::LocalFile(LocalFileLoader::new(
+                source_file(),
+                LocalFileFormat::Csv,
+            ))),
+            BgpSourceType::Json => Some(BgpAnnouncementSource::LocalFile(LocalFileLoader::new(
+                source_file(),
+                LocalFileFormat::Json,
+            ))),
+            BgpSourceType::Bmp => match config.bgp_bmp_listen {
+                Some(addr) => {
+                    let listener = BmpListener::start(addr)
+                        .map_err(|e| Error::Custom(format!("Cannot start BMP listener: {}", e)))?;
+                    Some(BgpAnnouncementSource::Bmp(listener))
+                }
+                None => None,
+            },
+        };
+
+        Ok(source)
+    }
+
     pub fn service_base_uri(&self) -> &uri::Https {
         &self.service_uri
     }
@@ -245,7 +318,20 @@ impl KrillServer {
     }
 
     pub async fn actor_from_request(&self, request: &hyper::Request<hyper::Body>) -> Actor {
-        self.authorizer.actor_from_request(request).await
+        let audit_trail = self.audit_log.as_ref().map(|_| AuditTrail::default());
+        let actor = self.authorizer.actor_from_request(request, audit_trail.as_ref()).await;
+        match audit_trail {
+            Some(trail) => actor.with_audit_trail(trail),
+            None => actor,
+        }
+    }
+
+    /// Writes the authorization checks made for the actor of a request to the
+    /// audit log, if configured.
+    pub fn audit(&self, context: &AuditContext, actor: &Actor, outcome: u16) {
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.record(context, actor, outcome);
+        }
     }
 
     pub fn actor_from_def(&self, actor_def: ActorDef) -> Actor {
@@ -256,8 +342,12 @@ impl KrillServer {
         self.authorizer.get_login_url().await
     }
 
-    pub async fn login(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<LoggedInUser> {
-        self.authorizer.login(request).await
+    pub async fn login(&self, request: &hyper::Request<hyper::Body>, actor: &Actor) -> KrillResult<LoggedInUser> {
+        self.authorizer.login(request, actor.audit_trail().as_ref()).await
+    }
+
+    pub fn login_throttle_stats(&self) -> LoginThrottleStats {
+        self.authorizer.login_throttle_stats()
     }
 
     pub async fn logout(&self, request: &hyper::Request<hyper::Body>) -> KrillResult<HttpResponse> {
@@ -274,6 +364,171 @@ impl KrillServer {
     }
 }
 
+/// # Manage scoped API tokens
+///
+#[cfg(feature = "multi-user")]
+impl KrillServer {
+    /// Creates a new scoped API token. See `ApiTokenStore::create` for
+    /// the rights that the creating actor needs.
+    pub fn api_token_create(&self, request: ApiTokenRequest, actor: &Actor) -> KrillResult<ApiTokenCreated> {
+        let current_cas: Vec<Handle> = self
+            .ca_list(&self.system_actor)?
+            .cas()
+            .iter()
+            .map(|summary| summary.handle().clone())
+            .collect();
+
+        self.authorizer.api_tokens().create(request, actor, &current_cas)
+    }
+
+    pub fn api_token_list(&self) -> ApiTokenList {
+        self.authorizer.api_tokens().list()
+    }
+
+    pub fn api_token_revoke(&self, name: &str) -> KrillResult<()> {
+        self.authorizer.api_tokens().revoke(name)
+    }
+}
+
+/// # Manage the authorization policy
+///
+#[cfg(feature = "multi-user")]
+impl KrillServer {
+    /// Reloads the authorization policy. The current policy remains in use
+    /// if the new policy cannot be loaded or fails its self checks.
+    pub fn policy_reload(&self) -> KrillEmptyResult {
+        self.authorizer.reload_policy()
+    }
+
+    pub fn policy_explain(&self, request: PolicyExplainRequest) -> KrillResult<PolicyExplanation> {
+        self.authorizer.explain_policy(request)
+    }
+}
+
+/// # Manage tenants
+///
+#[cfg(feature = "multi-user")]
+impl KrillServer {
+    pub fn tenant_list(&self) -> TenantList {
+        self.authorizer.tenants().list()
+    }
+
+    pub fn tenant_get(&self, name: &str) -> KrillResult<TenantInfo> {
+        self.authorizer.tenants().get(name)
+    }
+
+    pub fn tenant_create(&self, definition: TenantDefinition) -> KrillResult<TenantInfo> {
+        self.authorizer.tenants().create(definition)
+    }
+
+    pub fn tenant_update(&self, definition: TenantDefinition) -> KrillResult<TenantInfo> {
+        self.authorizer.tenants().update(definition)
+    }
+
+    pub fn tenant_delete(&self, name: &str) -> KrillEmptyResult {
+        self.authorizer.tenants().delete(name)
+    }
+
+    pub fn tenant_add_user(&self, name: &str, user: &str) -> KrillEmptyResult {
+        self.authorizer.tenants().add_user(name, user)
+    }
+
+    pub fn tenant_remove_user(&self, name: &str, user: &str) -> KrillEmptyResult {
+        self.authorizer.tenants().remove_user(name, user)
+    }
+
+    /// Updates the tenants and scoped API tokens that refer to a renamed CA,
+    /// so that they keep access to it under its new name.
+    fn ca_rename_access(&self, report: &mut CertAuthRenameReport) {
+        let (previous, handle) = (report.previous().clone(), report.handle().clone());
+
+        match self.authorizer.tenants().rename_ca(&previous, &handle) {
+            Ok(tenants) => {
+                for tenant in tenants {
+                    report.add_update(CertAuthRenameUpdate::Tenant { tenant });
+                }
+            }
+            Err(e) => report.add_next_step(CertAuthRenameStep::AccessNotUpdated {
+                reason: format!("updating tenants failed: {}", e),
+            }),
+        }
+
+        match self.authorizer.api_tokens().rename_ca(&previous, &handle) {
+            Ok(tokens) => {
+                for token in tokens {
+                    report.add_update(CertAuthRenameUpdate::ApiToken { token });
+                }
+            }
+            Err(e) => report.add_next_step(CertAuthRenameStep::AccessNotUpdated {
+                reason: format!("updating API tokens failed: {}", e),
+            }),
+        }
+    }
+}
+
+/// # Manage local users
+///
+#[cfg(feature = "multi-user")]
+impl KrillServer {
+    pub fn user_list(&self) -> KrillResult<LocalUserList> {
+        Ok(self.authorizer.local_users()?.user_list())
+    }
+
+    pub fn user_add(&self, request: LocalUserRequest) -> KrillResult<LocalUserInfo> {
+        self.authorizer.local_users()?.user_add(request)
+    }
+
+    pub fn user_remove(&self, id: &str) -> KrillEmptyResult {
+        self.authorizer.local_users()?.user_remove(id)
+    }
+
+    pub fn user_set_disabled(&self, id: &str, disabled: bool) -> KrillResult<LocalUserInfo> {
+        self.authorizer.local_users()?.user_set_disabled(id, disabled)
+    }
+
+    pub fn user_reset_password(&self, id: &str, password: &str) -> KrillEmptyResult {
+        self.authorizer.local_users()?.user_reset_password(id, password)
+    }
+
+    /// Changes the password of the local user that is the actor.
+    pub fn user_change_password(&self, password: &str, actor: &Actor) -> KrillEmptyResult {
+        if !actor.is_user() {
+            return Err(Error::UserInvalid(format!(
+                "'{}' is not a user and has no password",
+                actor.name()
+            )));
+        }
+        self.authorizer
+            .local_users()?
+            .user_change_password(actor.name(), password)
+    }
+
+    pub fn user_mfa_reset(&self, id: &str) -> KrillEmptyResult {
+        self.authorizer.local_users()?.mfa_reset(id)
+    }
+}
+
+/// # Manage the second factor of the actor
+///
+#[cfg(feature = "multi-user")]
+impl KrillServer {
+    pub fn mfa_status(&self, actor: &Actor) -> KrillResult<MfaStatus> {
+        self.authorizer.local_users()?.mfa_status(actor.name())
+    }
+
+    pub fn mfa_enrol(&self, actor: &Actor) -> KrillResult<MfaEnrolment> {
+        self.authorizer.local_users()?.mfa_enrol(actor.name())
+    }
+
+    pub fn mfa_confirm(&self, code: &str, actor: &Actor) -> KrillEmptyResult {
+        self.authorizer.local_users()?.mfa_confirm(actor.name(), code)
+    }
+
+    pub fn mfa_disable(&self, code: &str, actor: &Actor) -> KrillEmptyResult {
+        self.authorizer.local_users()?.mfa_disable(actor.name(), code)
+    }
+}
+
 /// # Configure publishers
 impl KrillServer {
     /// Returns the repository server stats
@@ -281,6 +536,35 @@ impl KrillServer {
         self.repo_manager.repo_stats()
     }
 
+    /// Returns whether the actor may see the repository stats. As for the CA
+    /// stats, anonymous actors may see them unless tenants are used, but
+    /// authenticated actors only if they may list the publishers.
+    pub fn repo_stats_visible(&self, actor: &Actor) -> bool {
+        if actor.is_anonymous() {
+            self.stats_details_visible(actor)
+        } else {
+            matches!(actor.is_allowed(Permission::PUB_LIST, NO_RESOURCE), Ok(true))
+        }
+    }
+
+    /// Returns whether the actor may see statistics per CA or publisher.
+    /// Anonymous actors may not once any tenant is defined, because this
+    /// would show them the CAs of all tenants. They only get aggregate
+    /// numbers instead.
+    pub fn stats_details_visible(&self, actor: &Actor) -> bool {
+        !actor.is_anonymous() || !self.tenants_in_use()
+    }
+
+    #[cfg(feature = "multi-user")]
+    fn tenants_in_use(&self) -> bool {
+        !self.authorizer.tenants().is_empty()
+    }
+
+    #[cfg(not(feature = "multi-user"))]
+    fn tenants_in_use(&self) -> bool {
+        false
+    }
+
     /// Returns all current publishers.
     pub fn publishers(&self) -> KrillResult<Vec<Handle>> {
         self.repo_manager.publishers()
@@ -329,7 +613,17 @@ impl KrillServer {
 /// # Being a parent
 ///
 impl KrillServer {
+    /// Returns the details of the embedded Trust Anchor, or of the offline
+    /// Trust Anchor if the Trust Anchor proxy has a signer.
     pub async fn ta(&self) -> KrillResult<TaCertDetails> {
+        if !self.ca_manager.has_ca(&ta_handle())? {
+            return self
+                .ca_manager
+                .ta_proxy_signer_info()
+                .await
+                .map(|signer| signer.ta_cert_details().clone());
+        }
+
         let ta = self.ca_manager.get_ca(&ta_handle()).await?;
         if let ParentCaContact::Ta(ta) = ta.parent(&ta_handle()).unwrap() {
             Ok(ta.clone())
@@ -342,6 +636,45 @@ impl KrillServer {
         self.ta().await.ok().map(|details| details.cert().clone())
     }
 
+    /// Returns the details of the key of the embedded Trust Anchor which is
+    /// being rolled to, or from, if a key roll is in progress.
+    pub async fn ta_key_roll(&self) -> Option<TaKeyRoll> {
+        let ta = self.ca_manager.get_trust_anchor().await.ok()?;
+        ta.ta_key_roll().cloned()
+    }
+
+    /// Returns the certificate of the embedded Trust Anchor by the name it
+    /// uses in the TAL during a key roll, i.e. '<key identifier>.cer'.
+    pub async fn trust_anchor_cert_by_name(&self, name: &str) -> Option<Cert> {
+        let mut certs = vec![self.ta().await.ok()?];
+        match self.ta_key_roll().await {
+            Some(TaKeyRoll::Staging { successor, .. }) => certs.push(successor),
+            Some(TaKeyRoll::Activated { predecessor, .. }) => certs.push(predecessor),
+            None => {}
+        }
+
+        certs
+            .into_iter()
+            .map(|details| details.cert().clone())
+            .find(|cert| ObjectName::from(cert).to_string() == name)
+    }
+
+    pub async fn ta_keyroll_status(&self) -> KrillResult<TaKeyRollStatus> {
+        self.ca_manager.ta_keyroll_status().await
+    }
+
+    pub async fn ta_keyroll_init(&self, actor: &Actor) -> KrillEmptyResult {
+        self.ca_manager.ta_keyroll_init(actor).await
+    }
+
+    pub async fn ta_keyroll_activate(&self, actor: &Actor) -> KrillEmptyResult {
+        self.ca_manager.ta_keyroll_activate(actor).await
+    }
+
+    pub async fn ta_keyroll_finish(&self, actor: &Actor) -> KrillEmptyResult {
+        self.ca_manager.ta_keyroll_finish(actor).await
+    }
+
     /// Adds a child to a CA and returns the ParentCaInfo that the child
     /// will need to contact this CA for resource requests.
     pub async fn ca_add_child(
@@ -403,6 +736,95 @@ impl KrillServer {
         Ok(child)
     }
 
+    /// Submits a request by a prospective child, provided that child onboarding
+    /// is enabled on this server.
+    pub async fn ca_child_request_submit(
+        &self,
+        ca: &Handle,
+        req: rfc8183::ChildRequest,
+        actor: &Actor,
+    ) -> KrillResult<ChildRequestReceipt> {
+        if !self.config.child_onboarding.is_enabled() {
+            return Err(Error::ChildOnboardingDisabled);
+        }
+        self.ca_manager.ca_child_request_submit(ca, req, actor).await
+    }
+
+    /// Lists the pending child requests under the CA.
+    pub async fn ca_child_requests(&self, ca: &Handle) -> KrillResult<ChildRequestList> {
+        self.ca_manager.ca_child_requests(ca).await
+    }
+
+    /// Approves a pending child request and returns the ParentCaContact for the child.
+    pub async fn ca_child_request_approve(
+        &self,
+        ca: &Handle,
+        child: ChildHandle,
+        resources: ResourceSet,
+        actor: &Actor,
+    ) -> KrillResult<ParentCaContact> {
+        self.ca_manager
+            .ca_child_request_approve(ca, child, resources, &self.service_uri, actor)
+            .await
+    }
+
+    /// Rejects a pending child request.
+    pub async fn ca_child_request_reject(&self, ca: &Handle, child: ChildHandle, actor: &Actor) -> KrillEmptyResult {
+        self.ca_manager.ca_child_request_reject(ca, child, actor).await
+    }
+
+    /// Returns the parent response for an approved child request, in exchange
+    /// for the one-time token given to the child.
+    pub async fn ca_child_request_complete(
+        &self,
+        ca: &Handle,
+        child: ChildHandle,
+        token: Token,
+        actor: &Actor,
+    ) -> KrillResult<rfc8183::ParentResponse> {
+        if !self.config.child_onboarding.is_enabled() {
+            return Err(Error::ChildOnboardingDisabled);
+        }
+        self.ca_manager
+            .ca_child_request_complete(ca, child, token, &self.service_uri, actor)
+            .await
+    }
+
+    /// Returns the resource templates of the CA.
+    pub async fn ca_resource_templates(&self, ca: &Handle) -> KrillResult<ResourceTemplateList> {
+        self.ca_manager.ca_resource_templates(ca).await
+    }
+
+    /// Adds or updates a resource template under the CA.
+    pub async fn ca_resource_template_update(
+        &self,
+        ca: &Handle,
+        template: ResourceTemplate,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager.ca_resource_template_update(ca, template, actor).await
+    }
+
+    /// Removes a resource template from the CA.
+    pub async fn ca_resource_template_remove(
+        &self,
+        ca: &Handle,
+        name: ResourceTemplateName,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager.ca_resource_template_remove(ca, name, actor).await
+    }
+
+    /// Show the resources of the CA which are not delegated to any child.
+    pub async fn ca_stats_child_undelegated(&self, ca: &Handle) -> KrillResult<UndelegatedResources> {
+        self.ca_manager.ca_undelegated_resources(ca).await
+    }
+
+    /// Show the scheduled removals of resources from children under the CA.
+    pub async fn ca_stats_child_shrinks(&self, ca: &Handle) -> KrillResult<ChildShrinkList> {
+        self.ca_manager.ca_child_shrinks(ca).await
+    }
+
     /// Show children stats under the CA.
     pub async fn ca_stats_child_connections(&self, ca: &Handle) -> KrillResult<ChildrenConnectionStats> {
         self.ca_manager
@@ -412,6 +834,56 @@ impl KrillServer {
     }
 }
 
+/// # Trust Anchor proxy
+///
+impl KrillServer {
+    pub async fn ta_proxy_init(&self) -> KrillEmptyResult {
+        self.ca_manager.ta_proxy_init().await
+    }
+
+    pub async fn ta_proxy_publisher_request(&self) -> KrillResult<rfc8183::PublisherRequest> {
+        self.ca_manager.ta_proxy_publisher_request().await
+    }
+
+    pub async fn ta_proxy_repository_update(&self, contact: RepositoryContact, actor: &Actor) -> KrillEmptyResult {
+        self.ca_manager.ta_proxy_repository_update(contact, actor).await
+    }
+
+    pub async fn ta_proxy_info(&self) -> KrillResult<TrustAnchorProxyInfo> {
+        self.ca_manager.ta_proxy_info().await
+    }
+
+    pub async fn ta_proxy_signer_add(&self, info: TrustAnchorSignerInfo, actor: &Actor) -> KrillEmptyResult {
+        self.ca_manager.ta_proxy_signer_add(info, actor).await
+    }
+
+    pub async fn ta_proxy_signer_make_request(&self, actor: &Actor) -> KrillResult<TrustAnchorSignedRequest> {
+        self.ca_manager.ta_proxy_signer_make_request(actor).await
+    }
+
+    pub async fn ta_proxy_signer_get_request(&self) -> KrillResult<TrustAnchorSignedRequest> {
+        self.ca_manager.ta_proxy_signer_get_request().await
+    }
+
+    pub async fn ta_proxy_signer_process_response(
+        &self,
+        response: TrustAnchorSignedResponse,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager.ta_proxy_signer_process_response(response, actor).await
+    }
+
+    pub async fn ta_proxy_children_add(&self, req: AddChildRequest, actor: &Actor) -> KrillResult<ParentCaContact> {
+        self.ca_manager
+            .ta_proxy_children_add(req, &self.service_uri, actor)
+            .await
+    }
+
+    pub async fn ta_proxy_history(&self, crit: CommandHistoryCriteria) -> KrillResult<CommandHistory> {
+        self.ca_manager.ta_proxy_history(crit).await
+    }
+}
+
 /// # Being a child
 ///
 impl KrillServer {
@@ -448,10 +920,24 @@ impl KrillServer {
 /// # Stats and status of CAS
 ///
 impl KrillServer {
-    pub async fn cas_stats(&self) -> KrillResult<HashMap<Handle, CertAuthStats>> {
+    /// Returns the stats for all CAs the actor may see. Stats are public, so
+    /// anonymous actors see all CAs unless tenants are used. Authenticated
+    /// actors, e.g. users bound to a tenant, only see the CAs they have
+    /// access to.
+    pub async fn cas_stats(&self, actor: &Actor) -> KrillResult<HashMap<Handle, CertAuthStats>> {
         let mut res = HashMap::new();
 
-        for ca in self.ca_list(&self.system_actor)?.cas() {
+        let actor = if !self.stats_details_visible(actor) {
+            return Err(Error::ApiInsufficientRights(
+                "CA statistics are not available to anonymous users when tenants are used".to_string(),
+            ));
+        } else if actor.is_anonymous() {
+            &self.system_actor
+        } else {
+            actor
+        };
+
+        for ca in self.ca_list(actor)?.cas() {
             // can't fail really, but to be sure
             if let Ok(ca) = self.ca_manager.get_ca(ca.handle()).await {
                 let roas = ca.roa_definitions();
@@ -476,6 +962,11 @@ impl KrillServer {
         Ok(res)
     }
 
+    /// Returns the number of CAs, for aggregate statistics.
+    pub fn cas_count(&self) -> KrillResult<usize> {
+        Ok(self.ca_list(&self.system_actor)?.cas().len())
+    }
+
     pub async fn all_ca_issues(&self, actor: &Actor) -> KrillResult<AllCertAuthIssues> {
         let mut all_issues = AllCertAuthIssues::default();
         for ca in self.ca_list(actor)?.cas() {
@@ -497,12 +988,28 @@ impl KrillServer {
             issues.add_repo_issue(error)
         }
 
+        for (mirror, status) in ca_status.mirrors().iter() {
+            if let Some(error) = status.to_failure_opt() {
+                issues.add_repo_mirror_issue(mirror.clone(), error)
+            }
+        }
+
         for (parent, status) in ca_status.parents().iter() {
             if let Some(error) = status.to_failure_opt() {
                 issues.add_parent_issue(parent.clone(), error)
             }
         }
 
+        if let Some(error) = ca_status.keyroll_issue() {
+            issues.add_keyroll_issue(error.clone())
+        }
+
+        for (child, status) in ca_status.children().iter() {
+            if let Some(error) = status.inactivity_warning() {
+                issues.add_child_issue(child.clone(), error.clone())
+            }
+        }
+
         Ok(issues)
     }
 }
@@ -516,12 +1023,24 @@ impl KrillServer {
         Ok(())
     }
 
+    /// Republish the given CAs if they need it.
+    pub async fn republish(&self, cas: &[Handle]) -> KrillEmptyResult {
+        self.ca_manager.republish(cas).await?;
+        Ok(())
+    }
+
     /// Re-sync all CAs with their repositories
     pub async fn cas_repo_sync_all(&self, actor: &Actor) -> KrillEmptyResult {
         self.ca_manager.cas_repo_sync_all(actor).await;
         Ok(())
     }
 
+    /// Re-sync the given CAs with their repositories
+    pub async fn cas_repo_sync(&self, cas: &[Handle]) -> KrillEmptyResult {
+        self.ca_manager.cas_repo_sync(cas).await;
+        Ok(())
+    }
+
     /// Re-sync a specific CA with its repository
     pub async fn cas_repo_sync_single(&self, ca: &Handle) -> KrillEmptyResult {
         self.ca_manager.cas_repo_sync_single(ca).await?;
@@ -534,6 +1053,12 @@ impl KrillServer {
         Ok(())
     }
 
+    /// Refresh the given CAs: ask for updates and shrink as needed.
+    pub async fn cas_refresh(&self, cas: &[Handle], actor: &Actor) -> KrillEmptyResult {
+        self.ca_manager.cas_refresh(cas, self.started, actor).await;
+        Ok(())
+    }
+
     /// Refresh a specific CA with its parents
     pub async fn cas_refresh_single(&self, ca_handle: Handle, actor: &Actor) -> KrillEmptyResult {
         self.ca_manager.cas_refresh_single(ca_handle, self.started, actor).await;
@@ -548,6 +1073,12 @@ impl KrillServer {
         self.ca_manager.ca_list(actor)
     }
 
+    /// Returns the handles of the CAs in the actor's CA list on which it has
+    /// the given permission.
+    pub fn ca_handles_permitted(&self, actor: &Actor, permission: Permission) -> KrillResult<Vec<Handle>> {
+        self.ca_manager.ca_handles_permitted(actor, permission)
+    }
+
     /// Returns the public CA info for a CA, or NONE if the CA cannot be found.
     pub async fn ca_info(&self, handle: &Handle) -> KrillResult<CertAuthInfo> {
         self.ca_manager.get_ca(handle).await.map(|ca| ca.as_ca_info())
@@ -566,6 +1097,28 @@ impl KrillServer {
         self.ca_manager.delete_ca(ca_handle, actor).await
     }
 
+    /// Renames a CA. Local children, the local repository, and tenants and API
+    /// tokens referring to the CA are updated where possible. The report lists
+    /// what was updated and the steps that remain to be done with remote
+    /// parents, children and repositories.
+    pub async fn ca_rename(
+        &self,
+        handle: &Handle,
+        rename: CertAuthRename,
+        actor: &Actor,
+    ) -> KrillResult<CertAuthRenameReport> {
+        #[allow(unused_mut)]
+        let mut report = self
+            .ca_manager
+            .ca_rename(handle, rename.unpack(), &self.service_uri, &self.repo_manager, actor)
+            .await?;
+
+        #[cfg(feature = "multi-user")]
+        self.ca_rename_access(&mut report);
+
+        Ok(report)
+    }
+
     /// Returns the parent contact for a CA and parent, or NONE if either the CA or the parent cannot be found.
     pub async fn ca_my_parent_contact(&self, handle: &Handle, parent: &ParentHandle) -> KrillResult<ParentCaContact> {
         let ca = self.ca_manager.get_ca(handle).await?;
@@ -604,6 +1157,32 @@ impl KrillServer {
         Ok(self.ca_manager.update_repo(handle, contact, true, actor).await?)
     }
 
+    /// Returns the repository mirrors for a CA, and their status.
+    pub async fn ca_repo_mirrors(&self, handle: &Handle) -> KrillResult<RepositoryMirrorList> {
+        self.ca_manager.ca_repo_mirrors(handle).await
+    }
+
+    /// Add a repository mirror for a CA, or return an error. (see `CertAuth::repo_mirror_add`)
+    pub async fn ca_repo_mirror_add(
+        &self,
+        handle: Handle,
+        name: RepositoryMirrorName,
+        contact: RepositoryContact,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        Ok(self.ca_manager.ca_repo_mirror_add(handle, name, contact, actor).await?)
+    }
+
+    /// Remove a repository mirror from a CA, or return an error.
+    pub async fn ca_repo_mirror_remove(
+        &self,
+        handle: Handle,
+        name: RepositoryMirrorName,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        Ok(self.ca_manager.ca_repo_mirror_remove(handle, name, actor).await?)
+    }
+
     pub async fn ca_update_id(&self, handle: Handle, actor: &Actor) -> KrillEmptyResult {
         Ok(self.ca_manager.ca_update_id(handle, actor).await?)
     }
@@ -615,13 +1194,50 @@ impl KrillServer {
             .await?)
     }
 
-    pub async fn ca_keyroll_activate(&self, handle: Handle, actor: &Actor) -> KrillEmptyResult {
+    pub async fn ca_keyroll_activate(&self, handle: Handle, force: bool, actor: &Actor) -> KrillEmptyResult {
         Ok(self
             .ca_manager
-            .ca_keyroll_activate(handle, Duration::seconds(0), actor)
+            .ca_keyroll_activate_checked(handle, force, actor)
             .await?)
     }
 
+    pub async fn ca_keyroll_status(&self, handle: &Handle) -> KrillResult<KeyRollStatus> {
+        self.ca_manager.ca_keyroll_status(handle).await
+    }
+
+    /// Set the policy for automated key rolls of a CA, or remove it with `None`.
+    pub async fn ca_keyroll_policy_update(
+        &self,
+        handle: Handle,
+        policy: Option<KeyRollPolicy>,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        Ok(self.ca_manager.ca_keyroll_policy_update(handle, policy, actor).await?)
+    }
+
+    /// Set the suspension policy for inactive children of a CA, or remove it with `None`.
+    pub async fn ca_suspension_policy_update(
+        &self,
+        ca: &Handle,
+        policy: Option<ChildSuspensionPolicy>,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager.ca_suspension_policy_update(ca, policy, actor).await
+    }
+
+    /// Set the suspension policy for a specific child of a CA, or remove it with `None`.
+    pub async fn ca_child_suspension_policy_update(
+        &self,
+        ca: &Handle,
+        child: ChildHandle,
+        policy: Option<ChildSuspensionPolicy>,
+        actor: &Actor,
+    ) -> KrillEmptyResult {
+        self.ca_manager
+            .ca_child_suspension_policy_update(ca, child, policy, actor)
+            .await
+    }
+
     pub async fn rfc6492(
         &self,
         handle: Handle,
@@ -680,6 +1296,28 @@ impl KrillServer {
         Ok(ca.roa_definitions())
     }
 
+    /// Returns the history of ROA definition changes for a CA, optionally
+    /// limited to the definitions covering a prefix.
+    pub async fn ca_routes_history(&self, handle: &Handle, prefix: Option<TypedPrefix>) -> KrillResult<RoaHistory> {
+        let history = self.ca_manager.ca_roa_history(handle).await?;
+        Ok(match prefix {
+            Some(prefix) => history.covering(&prefix),
+            None => history,
+        })
+    }
+
+    /// Returns the difference between the ROA definitions configured for a CA
+    /// at two moments in time.
+    pub async fn ca_routes_diff(
+        &self,
+        handle: &Handle,
+        from: Timestamp,
+        to: Timestamp,
+    ) -> KrillResult<RoaDefinitionUpdates> {
+        let history = self.ca_manager.ca_roa_history(handle).await?;
+        Ok(history.diff(from, to))
+    }
+
     pub async fn ca_routes_bgp_analysis(&self, handle: &Handle) -> KrillResult<BgpAnalysisReport> {
         let ca = self.ca_manager.get_ca(handle).await?;
         let definitions = ca.roa_definitions();
@@ -727,6 +1365,71 @@ impl KrillServer {
             .await)
     }
 
+    /// Simulates the effect of the proposed ROA and resource changes on the
+    /// validity of announcements across all CAs in this Krill instance.
+    pub async fn cas_bgp_simulate(
+        &self,
+        request: BgpSimulationRequest,
+        actor: &Actor,
+    ) -> KrillResult<BgpSimulationReport> {
+        let mut received = HashMap::new();
+        let mut before = vec![];
+
+        let cas = self.ca_list(actor)?;
+        for change in request.changes() {
+            if !cas.cas().iter().any(|ca| ca.handle() == change.ca()) {
+                return Err(Error::CaUnknown(change.ca().clone()));
+            }
+        }
+
+        for ca in cas.cas() {
+            let ca = self.ca_manager.get_ca(ca.handle()).await?;
+            received.insert(ca.handle().clone(), ca.resources_per_parent());
+            before.push(CaRoutes::new(
+                ca.handle().clone(),
+                ca.all_resources(),
+                ca.roa_definitions(),
+            ));
+        }
+
+        let mut proposed_resources = HashMap::new();
+        let mut proposed_roas = HashMap::new();
+
+        for change in request.changes() {
+            let ca = self.ca_manager.get_ca(change.ca()).await?;
+
+            if let Some(updates) = change.roas() {
+                let updates: RouteAuthorizationUpdates = updates.clone().into();
+                let (would_be_routes, _) = ca.update_authorizations(&updates.into_explicit())?;
+                let roas: Vec<RoaDefinition> = would_be_routes
+                    .into_authorizations()
+                    .into_iter()
+                    .map(|a| a.into())
+                    .collect();
+                proposed_roas.insert(change.ca().clone(), roas);
+            }
+
+            if let Some(resources) = change.resources() {
+                proposed_resources.insert(change.ca().clone(), resources.clone());
+            }
+        }
+
+        let mut resources = resources_after(&received, &proposed_resources);
+
+        let after: Vec<CaRoutes> = before
+            .iter()
+            .map(|ca| {
+                CaRoutes::new(
+                    ca.handle().clone(),
+                    resources.remove(ca.handle()).unwrap_or_else(|| ca.resources().clone()),
+                    proposed_roas.remove(ca.handle()).unwrap_or_else(|| ca.roas().clone()),
+                )
+            })
+            .collect();
+
+        Ok(self.bgp_analyser.simulate(&before, &after).await)
+    }
+
     /// Re-issue ROA objects so that they will use short subjects (see issue #700)
     pub async fn force_renew_roas(&self) -> KrillResult<()> {
         self.ca_manager.force_renew_roas_all(self.system_actor()).await
@@ -13,7 +13,7 @@ use rpki::repository::x509::Time;
 
 use crate::{
     commons::{
-        api::{Handle, ParentHandle, ResourceClassName, RevocationRequest},
+        api::{Handle, ParentHandle, RepositoryMirrorName, ResourceClassName, RevocationRequest},
         eventsourcing::{self, Event},
     },
     daemon::ca::{CaEvt, CaEvtDet, CertAuth},
@@ -35,6 +35,16 @@ pub enum QueueTask {
         due: Time,
     },
 
+    SyncRepoMirror {
+        ca: Handle,
+        mirror: RepositoryMirrorName,
+    },
+    RescheduleSyncRepoMirror {
+        ca: Handle,
+        mirror: RepositoryMirrorName,
+        due: Time,
+    },
+
     SyncParent {
         ca: Handle,
         parent: ParentHandle,
@@ -68,6 +78,16 @@ impl fmt::Display for QueueTask {
                 ca,
                 due.to_rfc3339()
             ),
+            QueueTask::SyncRepoMirror { ca, mirror } => {
+                write!(f, "synchronize repo mirror '{}' for '{}'", mirror, ca)
+            }
+            QueueTask::RescheduleSyncRepoMirror { ca, mirror, due } => write!(
+                f,
+                "reschedule failed synchronize repo mirror '{}' for '{}' at: {}",
+                mirror,
+                ca,
+                due.to_rfc3339()
+            ),
             QueueTask::SyncParent { ca, parent } => write!(f, "synchronize CA '{}' with parent '{}'", ca, parent),
             QueueTask::RescheduleSyncParent { ca, parent, due } => write!(
                 f,
@@ -126,6 +146,62 @@ impl MessageQueue {
         self.schedule(QueueTask::RescheduleSyncRepo { ca, due });
     }
 
+    /// Schedules that a CA synchronizes with one of its repository mirrors.
+    pub fn schedule_sync_repo_mirror(&self, ca: Handle, mirror: RepositoryMirrorName) {
+        self.schedule(QueueTask::SyncRepoMirror { ca, mirror });
+    }
+
+    /// RE-Schedules that a CA synchronizes with one of its repository mirrors.
+    /// If a resynchronization was already planned for this mirror, then that
+    /// one is kept, so that failing mirrors do not build up tasks.
+    pub fn reschedule_sync_repo_mirror(&self, ca: Handle, mirror: RepositoryMirrorName, due: Time) {
+        let mut q = self.q.write().unwrap();
+        let planned = q.iter().any(|existing| match existing {
+            QueueTask::RescheduleSyncRepoMirror {
+                ca: existing_ca,
+                mirror: existing_mirror,
+                ..
+            } => existing_ca == &ca && existing_mirror == &mirror,
+            _ => false,
+        });
+        if !planned {
+            q.push_back(QueueTask::RescheduleSyncRepoMirror { ca, mirror, due });
+        }
+    }
+
+    fn drop_sync_repo_mirror(&self, dropping_ca: &Handle, mirror_to_drop: &RepositoryMirrorName) {
+        let mut q = self.q.write().unwrap();
+        q.retain(|existing| match existing {
+            QueueTask::SyncRepoMirror { ca, mirror } | QueueTask::RescheduleSyncRepoMirror { ca, mirror, .. } => {
+                dropping_ca != ca || mirror_to_drop != mirror
+            }
+            _ => true,
+        });
+    }
+
+    /// Moves tasks planned for a CA under its previous handle to its new handle,
+    /// so that they are not lost when the CA is renamed.
+    fn rename_ca(&self, previous: &Handle, handle: &Handle) {
+        let mut q = self.q.write().unwrap();
+        for task in q.iter_mut() {
+            match task {
+                QueueTask::ServerStarted => {}
+                QueueTask::SyncRepo { ca }
+                | QueueTask::RescheduleSyncRepo { ca, .. }
+                | QueueTask::SyncRepoMirror { ca, .. }
+                | QueueTask::RescheduleSyncRepoMirror { ca, .. }
+                | QueueTask::SyncParent { ca, .. }
+                | QueueTask::RescheduleSyncParent { ca, .. }
+                | QueueTask::ResourceClassRemoved { ca, .. }
+                | QueueTask::UnexpectedKey { ca, .. } => {
+                    if ca == previous {
+                        *ca = handle.clone();
+                    }
+                }
+            }
+        }
+    }
+
     pub fn schedule_sync_parent(&self, ca: Handle, parent: ParentHandle) {
         self.schedule(QueueTask::SyncParent { ca, parent });
     }
@@ -160,13 +236,19 @@ impl eventsourcing::PostSaveEventListener<CertAuth> for MessageQueue {
                 | CaEvtDet::ChildKeyRevoked { .. }
                 | CaEvtDet::KeyPendingToNew { .. }
                 | CaEvtDet::KeyPendingToActive { .. }
-                | CaEvtDet::KeyRollFinished { .. } => self.schedule_sync_repo(handle.clone()),
+                | CaEvtDet::KeyRollFinished { .. }
+                | CaEvtDet::TrustAnchorKeyRollStarted { .. }
+                | CaEvtDet::TrustAnchorKeyRollActivated { .. }
+                | CaEvtDet::TrustAnchorKeyRollFinished => self.schedule_sync_repo(handle.clone()),
 
                 CaEvtDet::KeyRollActivated {
                     resource_class_name, ..
                 } => {
-                    if let Ok(parent) = ca.parent_for_rc(resource_class_name) {
-                        self.schedule_sync_parent(handle.clone(), parent.clone());
+                    // A Trust Anchor revokes its old key itself, when its key roll is finished.
+                    if !ca.is_ta() {
+                        if let Ok(parent) = ca.parent_for_rc(resource_class_name) {
+                            self.schedule_sync_parent(handle.clone(), parent.clone());
+                        }
                     }
                     self.schedule_sync_repo(handle.clone());
                 }
@@ -202,6 +284,16 @@ impl eventsourcing::PostSaveEventListener<CertAuth> for MessageQueue {
                     revocation_request: revoke_req.clone(),
                 }),
 
+                CaEvtDet::RepoMirrorAdded { name, .. } => {
+                    self.schedule_sync_repo_mirror(handle.clone(), name.clone());
+                }
+                CaEvtDet::RepoMirrorRemoved { name } => self.drop_sync_repo_mirror(handle, name),
+
+                CaEvtDet::Renamed { previous } => {
+                    self.rename_ca(previous, handle);
+                    self.schedule_sync_repo(handle.clone());
+                }
+
                 CaEvtDet::ParentAdded { parent, .. } => {
                     self.schedule_sync_parent(handle.clone(), parent.clone());
                 }
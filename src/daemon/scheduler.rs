@@ -14,12 +14,12 @@ use rpki::repository::x509::Time;
 use crate::{
     commons::{
         actor::Actor,
-        api::{Handle, ParentHandle, Timestamp},
+        api::{Handle, ParentHandle, RepositoryMirrorName, Timestamp},
         bgp::BgpAnalyser,
     },
     constants::{
-        test_mode_enabled, REQUEUE_DELAY_SECONDS, SCHEDULER_INTERVAL_SECONDS_REPUBLISH,
-        SCHEDULER_INTERVAL_SECONDS_ROA_RENEW,
+        test_mode_enabled, REQUEUE_DELAY_SECONDS, SCHEDULER_INTERVAL_SECONDS_CHILD_SHRINK,
+        SCHEDULER_INTERVAL_SECONDS_KEYROLL, SCHEDULER_INTERVAL_SECONDS_REPUBLISH, SCHEDULER_INTERVAL_SECONDS_ROA_RENEW,
     },
     daemon::{
         ca::CaManager,
@@ -51,6 +51,14 @@ pub struct Scheduler {
     #[allow(dead_code)] // just need to keep this in scope
     cas_refresh: ScheduleHandle,
 
+    /// Responsible for the automated key rolls of CAs which have a key roll policy.
+    #[allow(dead_code)] // just need to keep this in scope
+    cas_keyroll: ScheduleHandle,
+
+    /// Responsible for removing resources from children once the notice period has passed.
+    #[allow(dead_code)] // just need to keep this in scope
+    cas_child_shrink: ScheduleHandle,
+
     /// Responsible for refreshing announcement information
     #[allow(dead_code)] // just need to keep this in scope
     announcements_refresh: ScheduleHandle,
@@ -74,6 +82,8 @@ impl Scheduler {
 
         let cas_republish = make_cas_republish(ca_manager.clone(), event_queue);
         let cas_objects_renew = make_cas_objects_renew(ca_manager.clone(), actor.clone());
+        let cas_keyroll = make_cas_keyroll(ca_manager.clone(), actor.clone());
+        let cas_child_shrink = make_cas_child_shrink(ca_manager.clone(), actor.clone());
         let cas_refresh = make_cas_refresh(ca_manager, config.ca_refresh_seconds, actor.clone());
 
         let announcements_refresh = make_announcements_refresh(bgp_analyser);
@@ -86,6 +96,8 @@ impl Scheduler {
             cas_republish,
             cas_objects_renew,
             cas_refresh,
+            cas_keyroll,
+            cas_child_shrink,
             announcements_refresh,
             #[cfg(feature = "multi-user")]
             login_cache_sweeper_sh,
@@ -117,6 +129,16 @@ fn make_cas_event_triggers(event_queue: Arc<MessageQueue>, ca_manager: Arc<CaMan
                             event_queue.reschedule_sync_repo(ca, due);
                         }
                     }
+                    QueueTask::SyncRepoMirror { ca, mirror } => {
+                        try_sync_repo_mirror(&event_queue, &ca_manager, ca, mirror).await
+                    }
+                    QueueTask::RescheduleSyncRepoMirror { ca, mirror, due } => {
+                        if Time::now() > due {
+                            try_sync_repo_mirror(&event_queue, &ca_manager, ca, mirror).await
+                        } else {
+                            event_queue.reschedule_sync_repo_mirror(ca, mirror, due);
+                        }
+                    }
                     QueueTask::SyncParent { ca, parent } => {
                         try_sync_parent(&event_queue, &ca_manager, ca, parent, &actor).await
                     }
@@ -192,7 +214,42 @@ async fn try_sync_repo(event_queue: &Arc<MessageQueue>, ca_manager: Arc<CaManage
         };
 
         error!("Failed to publish for '{}' will reschedule, error: {}", ca, e);
-        event_queue.reschedule_sync_repo(ca, requeue_time);
+        event_queue.reschedule_sync_repo(ca.clone(), requeue_time);
+    }
+
+    // Mirrors are synchronized regardless of the outcome for the primary
+    // repository, and each is rescheduled independently if it fails.
+    match ca_manager.ca_repo_mirror_names(&ca) {
+        Ok(mirrors) => {
+            for mirror in mirrors {
+                try_sync_repo_mirror(event_queue, &ca_manager, ca.clone(), mirror).await;
+            }
+        }
+        Err(e) => error!("Could not get repository mirrors for '{}', error: {}", ca, e),
+    }
+}
+
+/// Try to synchronize a CA with one of its repository mirrors, reschedule if this fails
+async fn try_sync_repo_mirror(
+    event_queue: &Arc<MessageQueue>,
+    ca_manager: &CaManager,
+    ca: Handle,
+    mirror: RepositoryMirrorName,
+) {
+    debug!("Synchronize CA '{}' with repository mirror '{}'", ca, mirror);
+
+    if let Err(e) = ca_manager.ca_repo_mirror_sync(&ca, &mirror).await {
+        let requeue_time = if test_mode_enabled() {
+            requeue_time_test()
+        } else {
+            requeue_time()
+        };
+
+        warn!(
+            "Failed to publish to repository mirror '{}' for '{}' will reschedule, error: {}",
+            mirror, ca, e
+        );
+        event_queue.reschedule_sync_repo_mirror(ca, mirror, requeue_time);
     }
 }
 
@@ -267,8 +324,32 @@ fn make_cas_refresh(ca_server: Arc<CaManager>, refresh_rate: u32, actor: Actor)
     })
 }
 
+fn make_cas_keyroll(ca_server: Arc<CaManager>, actor: Actor) -> ScheduleHandle {
+    SkippingScheduler::run(SCHEDULER_INTERVAL_SECONDS_KEYROLL, "CA automated key roll", move || {
+        let rt = Runtime::new().unwrap();
+        rt.block_on(async {
+            debug!("Triggering automated key rolls for all CAs, note this may be a no-op");
+            ca_server.cas_keyroll_auto_all(&actor).await;
+        })
+    })
+}
+
+fn make_cas_child_shrink(ca_server: Arc<CaManager>, actor: Actor) -> ScheduleHandle {
+    SkippingScheduler::run(
+        SCHEDULER_INTERVAL_SECONDS_CHILD_SHRINK,
+        "CA child resource removal",
+        move || {
+            let rt = Runtime::new().unwrap();
+            rt.block_on(async {
+                debug!("Triggering scheduled resource removals for children of all CAs, note this may be a no-op");
+                ca_server.cas_child_shrinks_enforce_all(&actor).await;
+            })
+        },
+    )
+}
+
 fn make_announcements_refresh(bgp_analyser: Arc<BgpAnalyser>) -> ScheduleHandle {
-    SkippingScheduler::run(5, "update RIS BGP info", move || {
+    SkippingScheduler::run(5, "update BGP announcements", move || {
         let rt = Runtime::new().unwrap();
         rt.block_on(async {
             if let Err(e) = bgp_analyser.update().await {
@@ -0,0 +1,432 @@
+//! Types shared between the online Trust Anchor proxy and the offline
+//! Trust Anchor signer.
+//!
+//! The proxy and signer never talk to each other directly. Instead the proxy
+//! produces a [`TrustAnchorSignedRequest`], which an operator carries over to
+//! the (air-gapped) signer. The signer processes it and produces a
+//! [`TrustAnchorSignedResponse`] that is carried back and imported by the proxy.
+//! Both messages are CMS signed using the RFC 8183 identity keys of the
+//! sender, so that they can be verified by the receiving side.
+use std::fmt;
+
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Serialize};
+
+use rpki::{
+    repository::{
+        cert::{KeyUsage, Overclaim, TbsCert},
+        crypto::KeyIdentifier,
+        x509::{Serial, Time, Validity},
+    },
+    uri,
+};
+
+use crate::{
+    commons::{
+        api::{
+            Base64, ChildHandle, IssuanceRequest, IssuedCert, RepoInfo, ResourceClassName, ResourceSet,
+            RevocationRequest, RevocationResponse, TaCertDetails, TrustAnchorLocator,
+        },
+        crypto::{IdCert, KrillSigner, ProtocolCms, ProtocolCmsBuilder},
+        error::Error,
+        KrillResult,
+    },
+    daemon::ca::CurrentKeyObjectSet,
+};
+
+//------------ Nonce ---------------------------------------------------------
+
+/// Unique identifier for an exchange between the proxy and the signer. The
+/// signer refuses to process a request with a nonce it has seen before, and
+/// the proxy only accepts a response for the nonce of its open request.
+pub type Nonce = String;
+
+pub fn new_nonce() -> Nonce {
+    uuid::Uuid::new_v4().to_string()
+}
+
+//------------ TrustAnchorProxyInfo ------------------------------------------
+
+/// Information about the proxy needed to initialize the signer: the ID
+/// certificate used to sign requests, and the repository where the Trust
+/// Anchor will publish.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorProxyInfo {
+    id_cert: IdCert,
+    repo_info: RepoInfo,
+}
+
+impl TrustAnchorProxyInfo {
+    pub fn new(id_cert: IdCert, repo_info: RepoInfo) -> Self {
+        TrustAnchorProxyInfo { id_cert, repo_info }
+    }
+
+    pub fn id_cert(&self) -> &IdCert {
+        &self.id_cert
+    }
+
+    pub fn repo_info(&self) -> &RepoInfo {
+        &self.repo_info
+    }
+
+    pub fn unpack(self) -> (IdCert, RepoInfo) {
+        (self.id_cert, self.repo_info)
+    }
+}
+
+impl fmt::Display for TrustAnchorProxyInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "ID key: {}", self.id_cert.ski_hex())?;
+        writeln!(f, "rsync base URI: {}", self.repo_info.base_uri())?;
+        writeln!(f, "RRDP notification URI: {}", self.repo_info.rpki_notify())
+    }
+}
+
+//------------ TrustAnchorSignerInfo -----------------------------------------
+
+/// Information about the signer needed by the proxy: the ID certificate
+/// used to sign responses, and the Trust Anchor certificate and TAL.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorSignerInfo {
+    id_cert: IdCert,
+    ta_cert_details: TaCertDetails,
+    ta_cert_uri: uri::Rsync,
+}
+
+impl TrustAnchorSignerInfo {
+    pub fn new(id_cert: IdCert, ta_cert_details: TaCertDetails, ta_cert_uri: uri::Rsync) -> Self {
+        TrustAnchorSignerInfo {
+            id_cert,
+            ta_cert_details,
+            ta_cert_uri,
+        }
+    }
+
+    pub fn id_cert(&self) -> &IdCert {
+        &self.id_cert
+    }
+
+    pub fn ta_cert_details(&self) -> &TaCertDetails {
+        &self.ta_cert_details
+    }
+
+    /// The rsync URI where the Trust Anchor certificate is published, as
+    /// used in the TAL.
+    pub fn ta_cert_uri(&self) -> &uri::Rsync {
+        &self.ta_cert_uri
+    }
+}
+
+impl fmt::Display for TrustAnchorSignerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "ID key: {}", self.id_cert.ski_hex())?;
+        writeln!(f, "TA key: {}", self.ta_cert_details.cert().subject_key_identifier())?;
+        writeln!(f)?;
+        writeln!(f, "TAL:")?;
+        writeln!(f, "{}", self.ta_cert_details.tal())
+    }
+}
+
+//------------ ProvisioningRequest -------------------------------------------
+
+/// A single RFC 6492 request from a child, queued by the proxy until it can
+/// be processed by the signer.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ProvisioningRequest {
+    Issue { request: IssuanceRequest },
+    Revoke { request: RevocationRequest },
+}
+
+impl ProvisioningRequest {
+    /// Returns the resource class and key the request is for.
+    pub fn key(&self) -> (&ResourceClassName, KeyIdentifier) {
+        match self {
+            ProvisioningRequest::Issue { request } => {
+                (request.class_name(), request.csr().public_key().key_identifier())
+            }
+            ProvisioningRequest::Revoke { request } => (request.class_name(), *request.key()),
+        }
+    }
+}
+
+impl fmt::Display for ProvisioningRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProvisioningRequest::Issue { request } => write!(f, "issue: {}", request),
+            ProvisioningRequest::Revoke { request } => write!(f, "revoke: {}", request),
+        }
+    }
+}
+
+//------------ ProvisioningResponse ------------------------------------------
+
+/// The signer's answer to a single `ProvisioningRequest`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ProvisioningResponse {
+    Issued { issued: IssuedCert },
+    Revoked { response: RevocationResponse },
+}
+
+impl fmt::Display for ProvisioningResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProvisioningResponse::Issued { issued } => write!(
+                f,
+                "issued certificate for key '{}' with resources '{}'",
+                issued.cert().subject_key_identifier(),
+                issued.resource_set()
+            ),
+            ProvisioningResponse::Revoked { response } => write!(
+                f,
+                "revoked key '{}' in class '{}'",
+                response.key(),
+                response.class_name()
+            ),
+        }
+    }
+}
+
+//------------ TrustAnchorChildRequests --------------------------------------
+
+/// All queued requests for a single child, along with the resources that
+/// the child is entitled to.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorChildRequests {
+    pub child: ChildHandle,
+    pub resources: ResourceSet,
+    pub requests: Vec<ProvisioningRequest>,
+}
+
+//------------ TrustAnchorChildResponses -------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorChildResponses {
+    pub child: ChildHandle,
+    pub responses: Vec<ProvisioningResponse>,
+}
+
+//------------ TrustAnchorSignerRequest --------------------------------------
+
+/// A request from the proxy to the signer. Even a request without any child
+/// requests is useful, as the signer will always re-issue its manifest and
+/// CRL when processing a request.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorSignerRequest {
+    pub nonce: Nonce,
+    pub child_requests: Vec<TrustAnchorChildRequests>,
+}
+
+impl TrustAnchorSignerRequest {
+    pub fn sign(&self, key: &KeyIdentifier, signer: &KrillSigner) -> KrillResult<TrustAnchorSignedRequest> {
+        TrustAnchorSignedMessage::create(self, key, signer).map(TrustAnchorSignedRequest)
+    }
+}
+
+impl fmt::Display for TrustAnchorSignerRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Nonce: {}", self.nonce)?;
+        if self.child_requests.is_empty() {
+            writeln!(f, "No child requests, only the manifest and CRL will be re-issued.")
+        } else {
+            for child_requests in &self.child_requests {
+                writeln!(
+                    f,
+                    "Child '{}' (resources: {}):",
+                    child_requests.child, child_requests.resources
+                )?;
+                for request in &child_requests.requests {
+                    writeln!(f, "  {}", request)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+//------------ TrustAnchorSignerResponse -------------------------------------
+
+/// The response from the signer, containing the newly issued manifest, CRL
+/// and child certificates in `objects`, as well as the responses for each
+/// child request.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorSignerResponse {
+    pub nonce: Nonce,
+    pub objects: CurrentKeyObjectSet,
+    pub child_responses: Vec<TrustAnchorChildResponses>,
+}
+
+impl TrustAnchorSignerResponse {
+    pub fn sign(&self, key: &KeyIdentifier, signer: &KrillSigner) -> KrillResult<TrustAnchorSignedResponse> {
+        TrustAnchorSignedMessage::create(self, key, signer).map(TrustAnchorSignedResponse)
+    }
+}
+
+impl fmt::Display for TrustAnchorSignerResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Nonce: {}", self.nonce)?;
+        writeln!(
+            f,
+            "Manifest and CRL next update: {}",
+            self.objects.next_update_time().to_rfc3339()
+        )?;
+        for child_responses in &self.child_responses {
+            writeln!(f, "Child '{}':", child_responses.child)?;
+            for response in &child_responses.responses {
+                writeln!(f, "  {}", response)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//------------ TrustAnchorSignedMessage --------------------------------------
+
+/// A JSON structure wrapped in a CMS signed by the sender's ID key.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorSignedMessage {
+    signed: Base64,
+}
+
+impl TrustAnchorSignedMessage {
+    fn create<T: Serialize>(content: &T, key: &KeyIdentifier, signer: &KrillSigner) -> KrillResult<Self> {
+        let json = serde_json::to_string(content).map_err(Error::JsonError)?;
+        let cms = ProtocolCmsBuilder::create(key, signer, Bytes::from(json))
+            .map_err(Error::signer)?
+            .as_bytes();
+
+        Ok(TrustAnchorSignedMessage {
+            signed: Base64::from_content(&cms),
+        })
+    }
+
+    /// Validates the CMS against the sender's ID certificate and returns
+    /// the content.
+    fn validate<T: DeserializeOwned>(&self, sender: &IdCert) -> Result<T, String> {
+        let bytes = self.signed.to_bytes();
+        let cms = ProtocolCms::decode(bytes.as_ref(), false).map_err(|e| format!("cannot decode CMS: {}", e))?;
+        cms.validate(sender).map_err(|e| format!("invalid signature: {}", e))?;
+
+        serde_json::from_slice(cms.content().to_bytes().as_ref()).map_err(|e| format!("cannot parse content: {}", e))
+    }
+}
+
+//------------ TrustAnchorSignedRequest --------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorSignedRequest(TrustAnchorSignedMessage);
+
+impl TrustAnchorSignedRequest {
+    /// Returns the request if it was validly signed by the proxy.
+    pub fn validate(&self, proxy_id: &IdCert) -> KrillResult<TrustAnchorSignerRequest> {
+        self.0.validate(proxy_id).map_err(Error::TaSignerRequestInvalid)
+    }
+}
+
+/// Shows the JSON that is to be carried over to the signer.
+impl fmt::Display for TrustAnchorSignedRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", serde_json::to_string_pretty(self).map_err(|_| fmt::Error)?)
+    }
+}
+
+//------------ TrustAnchorSignedResponse -------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorSignedResponse(TrustAnchorSignedMessage);
+
+impl TrustAnchorSignedResponse {
+    /// Returns the response if it was validly signed by the signer.
+    pub fn validate(&self, signer_id: &IdCert) -> KrillResult<TrustAnchorSignerResponse> {
+        self.0.validate(signer_id).map_err(Error::TaProxyResponseInvalid)
+    }
+}
+
+/// Shows the JSON that is to be carried over to the proxy.
+impl fmt::Display for TrustAnchorSignedResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", serde_json::to_string_pretty(self).map_err(|_| fmt::Error)?)
+    }
+}
+
+//------------ Trust Anchor certificate --------------------------------------
+
+/// Creates a self-signed Trust Anchor certificate for all resources using
+/// the given key, and returns it along with its TAL.
+pub fn make_ta_cert_details(
+    key: &KeyIdentifier,
+    repo_info: &RepoInfo,
+    tal_uris: Vec<uri::Https>,
+    tal_rsync_uri: Option<uri::Rsync>,
+    signer: &KrillSigner,
+) -> KrillResult<TaCertDetails> {
+    let resources = ResourceSet::all_resources();
+
+    let cert = {
+        let serial: Serial = signer.random_serial()?;
+
+        let pub_key = signer.get_key_info(key).map_err(Error::signer)?;
+        let name = pub_key.to_subject_name();
+
+        let mut cert = TbsCert::new(
+            serial,
+            name.clone(),
+            Validity::new(Time::five_minutes_ago(), Time::years_from_now(100)),
+            Some(name),
+            pub_key.clone(),
+            KeyUsage::Ca,
+            Overclaim::Refuse,
+        );
+
+        cert.set_basic_ca(Some(true));
+
+        let ns = ResourceClassName::default().to_string();
+
+        cert.set_ca_repository(Some(repo_info.ca_repository(&ns)));
+        cert.set_rpki_manifest(Some(repo_info.rpki_manifest(&ns, &pub_key.key_identifier())));
+        cert.set_rpki_notify(Some(repo_info.rpki_notify()));
+
+        cert.set_as_resources(resources.to_as_resources());
+        cert.set_v4_resources(resources.to_ip_resources_v4());
+        cert.set_v6_resources(resources.to_ip_resources_v6());
+
+        signer.sign_cert(cert, key)?
+    };
+
+    let tal = TrustAnchorLocator::new(tal_uris, tal_rsync_uri, &cert);
+
+    Ok(TaCertDetails::new(cert, resources, tal))
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{commons::crypto::IdCertBuilder, test::*};
+
+    use super::*;
+
+    #[test]
+    fn signed_request_validates_for_signing_id_only() {
+        test_under_tmp(|d| {
+            let signer = KrillSigner::build(&d).unwrap();
+
+            let proxy_key = signer.create_key().unwrap();
+            let proxy_id = IdCertBuilder::new_ta_id_cert(&proxy_key, &signer).unwrap();
+
+            let other_key = signer.create_key().unwrap();
+            let other_id = IdCertBuilder::new_ta_id_cert(&other_key, &signer).unwrap();
+
+            let request = TrustAnchorSignerRequest {
+                nonce: new_nonce(),
+                child_requests: vec![],
+            };
+            let signed = request.sign(&proxy_key, &signer).unwrap();
+
+            assert_eq!(request, signed.validate(&proxy_id).unwrap());
+            assert!(signed.validate(&other_id).is_err());
+        });
+    }
+}
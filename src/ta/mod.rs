@@ -0,0 +1,13 @@
+//! Support for an offline Trust Anchor.
+//!
+//! The Trust Anchor is split into an online proxy, which runs inside Krill,
+//! and an offline signer, which is managed using the `krillta` binary. See
+//! the `common` module for the exchange between them.
+mod common;
+pub use self::common::*;
+
+mod proxy;
+pub use self::proxy::*;
+
+mod signer;
+pub use self::signer::*;
@@ -0,0 +1,898 @@
+//! The online Trust Anchor proxy.
+//!
+//! The proxy runs inside Krill. It acts as the parent for the children of
+//! the Trust Anchor, and publishes the Trust Anchor's objects. It does not
+//! have access to the Trust Anchor key. Instead, requests from children are
+//! queued until the operator creates a signed request for the offline
+//! signer, and they are answered once the signed response is imported.
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use chrono::Duration;
+
+use rpki::repository::{crypto::KeyIdentifier, x509::Time};
+
+use crate::{
+    commons::{
+        actor::Actor,
+        api::{
+            rrdp::PublishElement, ChildHandle, EntitlementClass, Entitlements, Handle, IssuanceRequest,
+            IssuanceResponse, IssuedCert, RepositoryContact, ResourceClassName, ResourceSet, SigningCert,
+            StorableTrustAnchorProxyCommand, Timestamp,
+        },
+        crypto::{IdCert, KrillSigner},
+        error::Error,
+        eventsourcing::{Aggregate, CommandDetails, SentCommand, StoredEvent},
+        remote::rfc8183,
+        KrillResult,
+    },
+    daemon::{
+        ca::{CurrentKeyObjectSet, Rfc8183Id},
+        config::IssuanceTimingConfig,
+    },
+    ta::{
+        new_nonce, ProvisioningRequest, ProvisioningResponse, TrustAnchorChildRequests, TrustAnchorProxyInfo,
+        TrustAnchorSignedRequest, TrustAnchorSignedResponse, TrustAnchorSignerInfo, TrustAnchorSignerRequest,
+        TrustAnchorSignerResponse,
+    },
+};
+
+//------------ TrustAnchorProxyInitDetails -----------------------------------
+
+pub type TrustAnchorProxyIni = StoredEvent<TrustAnchorProxyInitDetails>;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorProxyInitDetails {
+    id: Rfc8183Id,
+}
+
+impl TrustAnchorProxyInitDetails {
+    pub fn init(handle: &Handle, signer: &KrillSigner) -> KrillResult<TrustAnchorProxyIni> {
+        let id = Rfc8183Id::generate(signer)?;
+        Ok(StoredEvent::new(handle, 0, TrustAnchorProxyInitDetails { id }))
+    }
+}
+
+impl fmt::Display for TrustAnchorProxyInitDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Initialized Trust Anchor proxy with ID key '{}'", self.id.key_hash())
+    }
+}
+
+//------------ TrustAnchorChild ----------------------------------------------
+
+/// A child of the Trust Anchor as known to the proxy.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorChild {
+    id_cert: IdCert,
+    resources: ResourceSet,
+    #[serde(default)]
+    issued: HashMap<KeyIdentifier, IssuedCert>,
+    #[serde(default)]
+    pending: HashMap<KeyIdentifier, ProvisioningRequest>,
+}
+
+impl TrustAnchorChild {
+    fn new(id_cert: IdCert, resources: ResourceSet) -> Self {
+        TrustAnchorChild {
+            id_cert,
+            resources,
+            issued: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn id_cert(&self) -> &IdCert {
+        &self.id_cert
+    }
+
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
+    }
+
+    pub fn issued(&self, key: &KeyIdentifier) -> Option<&IssuedCert> {
+        self.issued.get(key)
+    }
+
+    pub fn is_pending(&self, request: &ProvisioningRequest) -> bool {
+        let (_, key) = request.key();
+        self.pending.get(&key) == Some(request)
+    }
+}
+
+//------------ TrustAnchorProxySignerExchange --------------------------------
+
+/// A request that was sent to the signer, and - once it is imported - the
+/// response that was received.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorProxySignerExchange {
+    pub time: Timestamp,
+    pub request: TrustAnchorSignerRequest,
+    pub signed_request: TrustAnchorSignedRequest,
+}
+
+//------------ TrustAnchorProxyEvent -----------------------------------------
+
+pub type TrustAnchorProxyEvent = StoredEvent<TrustAnchorProxyEventDetails>;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[allow(clippy::large_enum_variant)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum TrustAnchorProxyEventDetails {
+    RepositoryAdded {
+        contact: RepositoryContact,
+    },
+    SignerAdded {
+        signer: TrustAnchorSignerInfo,
+    },
+    ChildAdded {
+        child: ChildHandle,
+        id_cert: IdCert,
+        resources: ResourceSet,
+    },
+    ChildRequestAdded {
+        child: ChildHandle,
+        request: ProvisioningRequest,
+    },
+    SignerRequestMade {
+        exchange: TrustAnchorProxySignerExchange,
+    },
+    SignerResponseProcessed {
+        response: TrustAnchorSignerResponse,
+        signed_response: TrustAnchorSignedResponse,
+    },
+}
+
+impl fmt::Display for TrustAnchorProxyEventDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrustAnchorProxyEventDetails::RepositoryAdded { contact } => write!(f, "Added repository: {}", contact),
+            TrustAnchorProxyEventDetails::SignerAdded { signer } => write!(
+                f,
+                "Added signer with ID key '{}' and TA key '{}'",
+                signer.id_cert().ski_hex(),
+                signer.ta_cert_details().cert().subject_key_identifier()
+            ),
+            TrustAnchorProxyEventDetails::ChildAdded { child, resources, .. } => {
+                write!(f, "Added child '{}' with resources '{}'", child, resources)
+            }
+            TrustAnchorProxyEventDetails::ChildRequestAdded { child, request } => {
+                write!(f, "Queued request from child '{}': {}", child, request)
+            }
+            TrustAnchorProxyEventDetails::SignerRequestMade { exchange } => {
+                write!(f, "Created signer request with nonce '{}'", exchange.request.nonce)
+            }
+            TrustAnchorProxyEventDetails::SignerResponseProcessed { response, .. } => {
+                write!(f, "Processed signer response for nonce '{}'", response.nonce)
+            }
+        }
+    }
+}
+
+//------------ TrustAnchorProxyCommand ---------------------------------------
+
+pub type TrustAnchorProxyCommand = SentCommand<TrustAnchorProxyCommandDetails>;
+
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum TrustAnchorProxyCommandDetails {
+    AddRepository(RepositoryContact),
+    AddSigner(TrustAnchorSignerInfo),
+    AddChild(ChildHandle, IdCert, ResourceSet),
+    AddChildRequest(ChildHandle, ProvisioningRequest),
+    MakeSignerRequest(Arc<KrillSigner>),
+    ProcessSignerResponse(TrustAnchorSignerResponse, TrustAnchorSignedResponse),
+}
+
+impl CommandDetails for TrustAnchorProxyCommandDetails {
+    type Event = TrustAnchorProxyEvent;
+    type StorableDetails = StorableTrustAnchorProxyCommand;
+
+    fn store(&self) -> Self::StorableDetails {
+        match self {
+            TrustAnchorProxyCommandDetails::AddRepository(contact) => StorableTrustAnchorProxyCommand::AddRepository {
+                service_uri: contact.service_uri().clone(),
+            },
+            TrustAnchorProxyCommandDetails::AddSigner(signer) => StorableTrustAnchorProxyCommand::AddSigner {
+                ski: signer.id_cert().ski_hex(),
+            },
+            TrustAnchorProxyCommandDetails::AddChild(child, id_cert, resources) => {
+                StorableTrustAnchorProxyCommand::AddChild {
+                    child: child.clone(),
+                    ski: id_cert.ski_hex(),
+                    resources: resources.clone(),
+                }
+            }
+            TrustAnchorProxyCommandDetails::AddChildRequest(child, request) => {
+                let (rcn, ki) = request.key();
+                let resource_class_name = rcn.clone();
+                match request {
+                    ProvisioningRequest::Issue { .. } => StorableTrustAnchorProxyCommand::AddChildIssueRequest {
+                        child: child.clone(),
+                        resource_class_name,
+                        ki,
+                    },
+                    ProvisioningRequest::Revoke { .. } => StorableTrustAnchorProxyCommand::AddChildRevokeRequest {
+                        child: child.clone(),
+                        resource_class_name,
+                        ki,
+                    },
+                }
+            }
+            TrustAnchorProxyCommandDetails::MakeSignerRequest(_) => StorableTrustAnchorProxyCommand::MakeSignerRequest,
+            TrustAnchorProxyCommandDetails::ProcessSignerResponse(response, _) => {
+                StorableTrustAnchorProxyCommand::ProcessSignerResponse {
+                    nonce: response.nonce.clone(),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for TrustAnchorProxyCommandDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.store().fmt(f)
+    }
+}
+
+impl TrustAnchorProxyCommandDetails {
+    pub fn add_repository(handle: &Handle, contact: RepositoryContact, actor: &Actor) -> TrustAnchorProxyCommand {
+        SentCommand::new(
+            handle,
+            None,
+            TrustAnchorProxyCommandDetails::AddRepository(contact),
+            actor,
+        )
+    }
+
+    pub fn add_signer(handle: &Handle, signer: TrustAnchorSignerInfo, actor: &Actor) -> TrustAnchorProxyCommand {
+        SentCommand::new(handle, None, TrustAnchorProxyCommandDetails::AddSigner(signer), actor)
+    }
+
+    pub fn add_child(
+        handle: &Handle,
+        child: ChildHandle,
+        id_cert: IdCert,
+        resources: ResourceSet,
+        actor: &Actor,
+    ) -> TrustAnchorProxyCommand {
+        SentCommand::new(
+            handle,
+            None,
+            TrustAnchorProxyCommandDetails::AddChild(child, id_cert, resources),
+            actor,
+        )
+    }
+
+    pub fn add_child_request(
+        handle: &Handle,
+        child: ChildHandle,
+        request: ProvisioningRequest,
+        actor: &Actor,
+    ) -> TrustAnchorProxyCommand {
+        SentCommand::new(
+            handle,
+            None,
+            TrustAnchorProxyCommandDetails::AddChildRequest(child, request),
+            actor,
+        )
+    }
+
+    pub fn make_signer_request(handle: &Handle, signer: Arc<KrillSigner>, actor: &Actor) -> TrustAnchorProxyCommand {
+        SentCommand::new(
+            handle,
+            None,
+            TrustAnchorProxyCommandDetails::MakeSignerRequest(signer),
+            actor,
+        )
+    }
+
+    /// The response is expected to have been validated already, using
+    /// [`TrustAnchorSignedResponse::validate`]. It will be validated again
+    /// when the command is processed.
+    pub fn process_signer_response(
+        handle: &Handle,
+        response: TrustAnchorSignerResponse,
+        signed_response: TrustAnchorSignedResponse,
+        actor: &Actor,
+    ) -> TrustAnchorProxyCommand {
+        SentCommand::new(
+            handle,
+            None,
+            TrustAnchorProxyCommandDetails::ProcessSignerResponse(response, signed_response),
+            actor,
+        )
+    }
+}
+
+//------------ TrustAnchorProxy ----------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorProxy {
+    handle: Handle,
+    version: u64,
+    id: Rfc8183Id,
+    repository: Option<RepositoryContact>,
+    signer: Option<TrustAnchorSignerInfo>,
+    children: HashMap<ChildHandle, TrustAnchorChild>,
+    open_request: Option<TrustAnchorProxySignerExchange>,
+    objects: Option<CurrentKeyObjectSet>,
+}
+
+/// # Event Sourcing support
+///
+impl Aggregate for TrustAnchorProxy {
+    type Command = TrustAnchorProxyCommand;
+    type StorableCommandDetails = StorableTrustAnchorProxyCommand;
+    type Event = TrustAnchorProxyEvent;
+    type InitEvent = TrustAnchorProxyIni;
+    type Error = Error;
+
+    fn init(event: Self::InitEvent) -> Result<Self, Self::Error> {
+        let (handle, _version, details) = event.unpack();
+
+        Ok(TrustAnchorProxy {
+            handle,
+            version: 1,
+            id: details.id,
+            repository: None,
+            signer: None,
+            children: HashMap::new(),
+            open_request: None,
+            objects: None,
+        })
+    }
+
+    fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn apply(&mut self, event: Self::Event) {
+        self.version += 1;
+        match event.into_details() {
+            TrustAnchorProxyEventDetails::RepositoryAdded { contact } => self.repository = Some(contact),
+            TrustAnchorProxyEventDetails::SignerAdded { signer } => self.signer = Some(signer),
+            TrustAnchorProxyEventDetails::ChildAdded {
+                child,
+                id_cert,
+                resources,
+            } => {
+                self.children.insert(child, TrustAnchorChild::new(id_cert, resources));
+            }
+            TrustAnchorProxyEventDetails::ChildRequestAdded { child, request } => {
+                if let Some(child) = self.children.get_mut(&child) {
+                    let (_, key) = request.key();
+                    child.pending.insert(key, request);
+                }
+            }
+            TrustAnchorProxyEventDetails::SignerRequestMade { exchange } => self.open_request = Some(exchange),
+            TrustAnchorProxyEventDetails::SignerResponseProcessed { response, .. } => {
+                // Requests which were included in the request are now done, unless
+                // the child sent a different request for the same key in the meantime.
+                if let Some(open_request) = self.open_request.take() {
+                    for child_requests in open_request.request.child_requests {
+                        if let Some(child) = self.children.get_mut(&child_requests.child) {
+                            for request in child_requests.requests {
+                                if child.is_pending(&request) {
+                                    let (_, key) = request.key();
+                                    child.pending.remove(&key);
+                                }
+                            }
+                        }
+                    }
+                }
+
+                for child_responses in response.child_responses {
+                    if let Some(child) = self.children.get_mut(&child_responses.child) {
+                        for child_response in child_responses.responses {
+                            match child_response {
+                                ProvisioningResponse::Issued { issued } => {
+                                    let key = issued.cert().subject_key_identifier();
+                                    child.issued.insert(key, issued);
+                                }
+                                ProvisioningResponse::Revoked { response } => {
+                                    child.issued.remove(response.key());
+                                }
+                            }
+                        }
+                    }
+                }
+
+                self.objects = Some(response.objects);
+            }
+        }
+    }
+
+    fn process_command(&self, command: Self::Command) -> Result<Vec<Self::Event>, Self::Error> {
+        info!(
+            "Sending command to Trust Anchor proxy '{}', version: {}: {}",
+            self.handle, self.version, command
+        );
+
+        match command.into_details() {
+            TrustAnchorProxyCommandDetails::AddRepository(contact) => self.add_repository(contact),
+            TrustAnchorProxyCommandDetails::AddSigner(signer) => self.add_signer(signer),
+            TrustAnchorProxyCommandDetails::AddChild(child, id_cert, resources) => {
+                self.add_child(child, id_cert, resources)
+            }
+            TrustAnchorProxyCommandDetails::AddChildRequest(child, request) => self.add_child_request(child, request),
+            TrustAnchorProxyCommandDetails::MakeSignerRequest(signer) => self.make_signer_request(&signer),
+            TrustAnchorProxyCommandDetails::ProcessSignerResponse(response, signed_response) => {
+                self.process_signer_response(response, signed_response)
+            }
+        }
+    }
+}
+
+/// # Process commands
+///
+impl TrustAnchorProxy {
+    fn event(&self, details: TrustAnchorProxyEventDetails) -> KrillResult<Vec<TrustAnchorProxyEvent>> {
+        Ok(vec![StoredEvent::new(&self.handle, self.version, details)])
+    }
+
+    /// Adds the repository. This can only be done before the signer is
+    /// added, because the Trust Anchor certificate refers to it.
+    fn add_repository(&self, contact: RepositoryContact) -> KrillResult<Vec<TrustAnchorProxyEvent>> {
+        if self.signer.is_some() {
+            Err(Error::TaProxyHasSigner)
+        } else {
+            self.event(TrustAnchorProxyEventDetails::RepositoryAdded { contact })
+        }
+    }
+
+    fn add_signer(&self, signer: TrustAnchorSignerInfo) -> KrillResult<Vec<TrustAnchorProxyEvent>> {
+        if self.signer.is_some() {
+            Err(Error::TaProxyHasSigner)
+        } else if self.repository.is_none() {
+            Err(Error::TaProxyHasNoRepository)
+        } else {
+            self.event(TrustAnchorProxyEventDetails::SignerAdded { signer })
+        }
+    }
+
+    fn add_child(
+        &self,
+        child: ChildHandle,
+        id_cert: IdCert,
+        resources: ResourceSet,
+    ) -> KrillResult<Vec<TrustAnchorProxyEvent>> {
+        if resources.is_empty() {
+            Err(Error::CaChildMustHaveResources(self.handle.clone(), child))
+        } else if self.children.contains_key(&child) {
+            Err(Error::CaChildDuplicate(self.handle.clone(), child))
+        } else {
+            info!(
+                "Trust Anchor proxy added child '{}' with resources '{}'",
+                child, resources
+            );
+            self.event(TrustAnchorProxyEventDetails::ChildAdded {
+                child,
+                id_cert,
+                resources,
+            })
+        }
+    }
+
+    /// Queues a request for a child. This is a no-op if the same request
+    /// was already queued.
+    fn add_child_request(
+        &self,
+        child_handle: ChildHandle,
+        request: ProvisioningRequest,
+    ) -> KrillResult<Vec<TrustAnchorProxyEvent>> {
+        let child = self.get_child(&child_handle)?;
+        if child.is_pending(&request) {
+            Ok(vec![])
+        } else {
+            self.event(TrustAnchorProxyEventDetails::ChildRequestAdded {
+                child: child_handle,
+                request,
+            })
+        }
+    }
+
+    /// Creates a signed request for the signer, including all pending child
+    /// requests. Only one request can be open at any time.
+    fn make_signer_request(&self, signer: &KrillSigner) -> KrillResult<Vec<TrustAnchorProxyEvent>> {
+        self.get_signer()?;
+
+        if self.open_request.is_some() {
+            return Err(Error::TaProxyHasOpenRequest);
+        }
+
+        let mut child_requests = vec![];
+        for (child, details) in &self.children {
+            if !details.pending.is_empty() {
+                child_requests.push(TrustAnchorChildRequests {
+                    child: child.clone(),
+                    resources: details.resources.clone(),
+                    requests: details.pending.values().cloned().collect(),
+                });
+            }
+        }
+
+        let request = TrustAnchorSignerRequest {
+            nonce: new_nonce(),
+            child_requests,
+        };
+        let signed_request = request.sign(&self.id.key_id(), signer)?;
+
+        self.event(TrustAnchorProxyEventDetails::SignerRequestMade {
+            exchange: TrustAnchorProxySignerExchange {
+                time: Timestamp::now(),
+                request,
+                signed_request,
+            },
+        })
+    }
+
+    /// Validates the response from the signer and makes sure that it is for
+    /// the currently open request.
+    fn process_signer_response(
+        &self,
+        response: TrustAnchorSignerResponse,
+        signed_response: TrustAnchorSignedResponse,
+    ) -> KrillResult<Vec<TrustAnchorProxyEvent>> {
+        let signer = self.get_signer()?;
+        let open_request = self.open_request.as_ref().ok_or(Error::TaProxyNoOpenRequest)?;
+
+        if signed_response.validate(signer.id_cert())? != response {
+            return Err(Error::TaProxyResponseInvalid(
+                "response does not match signed content".to_string(),
+            ));
+        }
+
+        if response.nonce != open_request.request.nonce {
+            return Err(Error::TaProxyResponseInvalid(format!(
+                "response is for request '{}', but the open request is '{}'",
+                response.nonce, open_request.request.nonce
+            )));
+        }
+
+        info!(
+            "Trust Anchor proxy processed signer response for request '{}'",
+            response.nonce
+        );
+
+        self.event(TrustAnchorProxyEventDetails::SignerResponseProcessed {
+            response,
+            signed_response,
+        })
+    }
+}
+
+/// # Accessors
+///
+impl TrustAnchorProxy {
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    pub fn id(&self) -> &Rfc8183Id {
+        &self.id
+    }
+
+    pub fn publisher_request(&self) -> rfc8183::PublisherRequest {
+        rfc8183::PublisherRequest::new(None, self.handle.clone(), self.id.cert().clone())
+    }
+
+    pub fn repository(&self) -> KrillResult<&RepositoryContact> {
+        self.repository.as_ref().ok_or(Error::TaProxyHasNoRepository)
+    }
+
+    /// Returns the information needed to initialize the signer.
+    pub fn info(&self) -> KrillResult<TrustAnchorProxyInfo> {
+        let repo_info = self.repository()?.repo_info().clone();
+        Ok(TrustAnchorProxyInfo::new(self.id.cert().clone(), repo_info))
+    }
+
+    pub fn get_signer(&self) -> KrillResult<&TrustAnchorSignerInfo> {
+        self.signer.as_ref().ok_or(Error::TaProxyHasNoSigner)
+    }
+
+    pub fn get_child(&self, child: &ChildHandle) -> KrillResult<&TrustAnchorChild> {
+        self.children
+            .get(child)
+            .ok_or_else(|| Error::CaChildUnknown(self.handle.clone(), child.clone()))
+    }
+
+    pub fn open_request(&self) -> KrillResult<&TrustAnchorProxySignerExchange> {
+        self.open_request.as_ref().ok_or(Error::TaProxyNoOpenRequest)
+    }
+
+    /// Returns the objects to publish: the Trust Anchor's manifest, CRL and
+    /// issued certificates. This is empty until the first signer response
+    /// was processed.
+    pub fn publish_elements(&self) -> Vec<PublishElement> {
+        self.objects
+            .as_ref()
+            .map(|objects| objects.publish_elements())
+            .unwrap_or_default()
+    }
+
+    /// Returns the next update time of the current manifest and CRL, if
+    /// any. A new signer request must be processed before this time.
+    pub fn next_update(&self) -> Option<Time> {
+        self.objects.as_ref().map(|objects| objects.next_update_time())
+    }
+
+    /// Returns the RFC 6492 entitlements for a child. The Trust Anchor has a
+    /// single resource class.
+    pub fn entitlements(&self, child_handle: &ChildHandle, timing: &IssuanceTimingConfig) -> KrillResult<Entitlements> {
+        let signer = self.get_signer()?;
+        let child = self.get_child(child_handle)?;
+
+        let ta_cert = signer.ta_cert_details().cert();
+        let issuer = SigningCert::new(signer.ta_cert_uri().clone(), ta_cert.clone());
+
+        let child_resources = signer.ta_cert_details().resources().intersection(&child.resources);
+        if child_resources.is_empty() {
+            return Ok(Entitlements::new(vec![]));
+        }
+
+        // Like normal CAs, only tell the child about a later not after time if
+        // its current certificate(s) will expire soon. See CertAuth::entitlement_class.
+        let mut not_after = Time::now() + Duration::weeks(timing.timing_child_certificate_valid_weeks);
+        let threshold = Time::now() + Duration::weeks(timing.timing_child_certificate_reissue_weeks_before);
+
+        let mut issued_certs = vec![];
+        for issued in child.issued.values() {
+            issued_certs.push(issued.clone());
+
+            let expires = issued.validity().not_after();
+            if expires > threshold {
+                not_after = expires;
+            }
+        }
+
+        Ok(Entitlements::new(vec![EntitlementClass::new(
+            ResourceClassName::default(),
+            issuer,
+            child_resources,
+            not_after,
+            issued_certs,
+        )]))
+    }
+
+    /// Returns an issuance response for the request if a certificate was
+    /// already issued for the key in the request, and it is still current:
+    /// i.e. it has the resources the child is entitled to, and it does not
+    /// expire soon. Returns `None` if the request should be (re-)queued for
+    /// the signer.
+    pub fn issuance_response(
+        &self,
+        child_handle: &ChildHandle,
+        request: &IssuanceRequest,
+        timing: &IssuanceTimingConfig,
+    ) -> KrillResult<Option<IssuanceResponse>> {
+        let signer = self.get_signer()?;
+        let child = self.get_child(child_handle)?;
+
+        let key = request.csr().public_key().key_identifier();
+        let issued = match child.issued(&key) {
+            Some(issued) => issued,
+            None => return Ok(None),
+        };
+
+        let entitled = signer.ta_cert_details().resources().intersection(&child.resources);
+        let expected = match entitled.apply_limit(request.limit()) {
+            Ok(expected) => expected,
+            Err(_) => return Ok(None),
+        };
+
+        let threshold = Time::now() + Duration::weeks(timing.timing_child_certificate_reissue_weeks_before);
+        if issued.resource_set() != &expected || issued.validity().not_after() <= threshold {
+            return Ok(None);
+        }
+
+        Ok(self
+            .entitlements(child_handle, timing)?
+            .classes()
+            .iter()
+            .find(|class| class.class_name() == request.class_name())
+            .cloned()
+            .and_then(|class| class.into_issuance_response(request.csr().public_key())))
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use std::convert::TryFrom;
+
+    use crate::{
+        commons::{
+            api::{RepoInfo, RequestResourceLimit},
+            crypto::IdCertBuilder,
+            remote::rfc8183::ServiceUri,
+        },
+        constants::{enable_test_mode, ACTOR_DEF_TEST},
+        daemon::{ca::ta_handle, config::Config},
+        ta::{TrustAnchorSigner, TrustAnchorSignerCommandDetails, TrustAnchorSignerInitDetails},
+        test::*,
+    };
+
+    use super::*;
+
+    fn actor() -> Actor {
+        Actor::test_from_def(ACTOR_DEF_TEST)
+    }
+
+    fn proxy_command(proxy: &mut TrustAnchorProxy, command: TrustAnchorProxyCommand) -> KrillResult<()> {
+        for event in proxy.process_command(command)? {
+            proxy.apply(event);
+        }
+        Ok(())
+    }
+
+    /// Lets the signer process the open request of the proxy, and returns
+    /// the response.
+    fn signer_process(
+        ta_signer: &mut TrustAnchorSigner,
+        proxy: &TrustAnchorProxy,
+        timing: &IssuanceTimingConfig,
+        signer: &Arc<KrillSigner>,
+    ) -> (TrustAnchorSignerResponse, TrustAnchorSignedResponse) {
+        let signed_request = proxy.open_request().unwrap().signed_request.clone();
+        let command = TrustAnchorSignerCommandDetails::process_request(
+            &ta_handle(),
+            signed_request,
+            timing.clone(),
+            signer.clone(),
+            &actor(),
+        );
+        for event in ta_signer.process_command(command).unwrap() {
+            ta_signer.apply(event);
+        }
+        let exchange = ta_signer.last_exchange().unwrap();
+        (exchange.response.clone(), exchange.signed_response.clone())
+    }
+
+    #[test]
+    fn proxy_signer_exchange() {
+        test_under_tmp(|d| {
+            enable_test_mode();
+            let signer = Arc::new(KrillSigner::build(&d).unwrap());
+            let timing = Config::test(&d, false, false, false).issuance_timing;
+            let ta = ta_handle();
+
+            let mut proxy = TrustAnchorProxy::init(TrustAnchorProxyInitDetails::init(&ta, &signer).unwrap()).unwrap();
+
+            let repo_key = signer.create_key().unwrap();
+            let repo_id = IdCertBuilder::new_ta_id_cert(&repo_key, &signer).unwrap();
+            let repo_info = RepoInfo::new(
+                rsync("rsync://localhost/repo/ta/"),
+                https("https://localhost/rrdp/notification.xml"),
+            );
+            let service_uri = ServiceUri::try_from("https://localhost/rfc8181/ta".to_string()).unwrap();
+            let contact = RepositoryContact::new(rfc8183::RepositoryResponse::new(
+                None,
+                ta.clone(),
+                repo_id,
+                service_uri,
+                repo_info,
+            ));
+            proxy_command(
+                &mut proxy,
+                TrustAnchorProxyCommandDetails::add_repository(&ta, contact, &actor()),
+            )
+            .unwrap();
+
+            let mut ta_signer = TrustAnchorSigner::init(
+                TrustAnchorSignerInitDetails::init(
+                    &ta,
+                    proxy.info().unwrap(),
+                    vec![https("https://localhost/ta/ta.cer")],
+                    rsync("rsync://localhost/ta/ta.cer"),
+                    &timing,
+                    &signer,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            proxy_command(
+                &mut proxy,
+                TrustAnchorProxyCommandDetails::add_signer(&ta, ta_signer.info(), &actor()),
+            )
+            .unwrap();
+
+            // Add a child and let it request a certificate.
+            let child = handle("child");
+            let child_id_key = signer.create_key().unwrap();
+            let child_id = IdCertBuilder::new_ta_id_cert(&child_id_key, &signer).unwrap();
+            proxy_command(
+                &mut proxy,
+                TrustAnchorProxyCommandDetails::add_child(
+                    &ta,
+                    child.clone(),
+                    child_id,
+                    ipv4_resources("10.0.0.0/8"),
+                    &actor(),
+                ),
+            )
+            .unwrap();
+
+            let child_key = signer.create_key().unwrap();
+            let child_repo_info = RepoInfo::new(
+                rsync("rsync://localhost/repo/child/"),
+                https("https://localhost/rrdp/notification.xml"),
+            );
+            let csr = signer.sign_csr(&child_repo_info, "0", &child_key).unwrap();
+            let request = ProvisioningRequest::Issue {
+                request: IssuanceRequest::new(ResourceClassName::default(), RequestResourceLimit::default(), csr),
+            };
+            proxy_command(
+                &mut proxy,
+                TrustAnchorProxyCommandDetails::add_child_request(&ta, child.clone(), request.clone(), &actor()),
+            )
+            .unwrap();
+            assert!(proxy.get_child(&child).unwrap().is_pending(&request));
+
+            // Nothing is published until the first response is processed.
+            assert!(proxy.publish_elements().is_empty());
+            assert!(proxy.next_update().is_none());
+
+            // Proxy -> signer -> proxy
+            proxy_command(
+                &mut proxy,
+                TrustAnchorProxyCommandDetails::make_signer_request(&ta, signer.clone(), &actor()),
+            )
+            .unwrap();
+            let (response, signed_response) = signer_process(&mut ta_signer, &proxy, &timing, &signer);
+            proxy_command(
+                &mut proxy,
+                TrustAnchorProxyCommandDetails::process_signer_response(
+                    &ta,
+                    response.clone(),
+                    signed_response.clone(),
+                    &actor(),
+                ),
+            )
+            .unwrap();
+
+            assert!(proxy.open_request().is_err());
+            let ta_child = proxy.get_child(&child).unwrap();
+            assert!(!ta_child.is_pending(&request));
+            assert!(ta_child.issued(&child_key).is_some());
+
+            let names: Vec<String> = proxy
+                .publish_elements()
+                .iter()
+                .map(|element| element.uri().to_string())
+                .collect();
+            assert_eq!(3, names.len());
+            assert!(names.iter().any(|name| name.ends_with(".mft")));
+            assert!(names.iter().any(|name| name.ends_with(".crl")));
+            assert!(names.iter().any(|name| name.ends_with(".cer")));
+            assert!(proxy.next_update().unwrap() > Time::now());
+
+            // A new request is open, so the previous response must be refused.
+            proxy_command(
+                &mut proxy,
+                TrustAnchorProxyCommandDetails::make_signer_request(&ta, signer.clone(), &actor()),
+            )
+            .unwrap();
+            let open_nonce = proxy.open_request().unwrap().request.nonce.clone();
+
+            match proxy_command(
+                &mut proxy,
+                TrustAnchorProxyCommandDetails::process_signer_response(&ta, response, signed_response, &actor()),
+            ) {
+                Err(Error::TaProxyResponseInvalid(msg)) => assert!(msg.contains("response is for request")),
+                _ => panic!("Expected response with mismatched nonce to be rejected"),
+            }
+            assert_eq!(open_nonce, proxy.open_request().unwrap().request.nonce);
+
+            // The response for the open request is accepted.
+            let (response, signed_response) = signer_process(&mut ta_signer, &proxy, &timing, &signer);
+            proxy_command(
+                &mut proxy,
+                TrustAnchorProxyCommandDetails::process_signer_response(&ta, response, signed_response, &actor()),
+            )
+            .unwrap();
+            assert!(proxy.open_request().is_err());
+        });
+    }
+}
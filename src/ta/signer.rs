@@ -0,0 +1,664 @@
+//! The offline Trust Anchor signer.
+//!
+//! The signer holds the Trust Anchor key. It is meant to run on an air-gapped
+//! system using the `krillta` command line tool, and it only ever acts on
+//! signed requests from the known Trust Anchor proxy.
+use std::{convert::TryFrom, fmt, path::Path, sync::Arc};
+
+use rpki::uri;
+
+use crate::{
+    commons::{
+        actor::Actor,
+        api::{
+            CommandHistory, CommandHistoryCriteria, Handle, RcvdCert, ResourceClassName,
+            StorableTrustAnchorSignerCommand, TaCertDetails, Timestamp,
+        },
+        crypto::{CsrInfo, IdCert, KrillSigner, SignSupport},
+        error::Error,
+        eventsourcing::{Aggregate, AggregateStore, CommandDetails, SentCommand, StoredEvent},
+        KrillResult,
+    },
+    constants::{test_mode_enabled, ACTOR_DEF_KRILLTA, TA_SIGNER_DIR},
+    daemon::{
+        ca::{ta_handle, CertifiedKey, ChildCertificateUpdates, CurrentKeyObjectSet, Rfc8183Id},
+        config::IssuanceTimingConfig,
+    },
+    ta::{
+        make_ta_cert_details, Nonce, ProvisioningRequest, ProvisioningResponse, TrustAnchorChildResponses,
+        TrustAnchorProxyInfo, TrustAnchorSignedRequest, TrustAnchorSignedResponse, TrustAnchorSignerInfo,
+        TrustAnchorSignerRequest, TrustAnchorSignerResponse,
+    },
+};
+
+//------------ TrustAnchorSignerInitDetails ----------------------------------
+
+pub type TrustAnchorSignerIni = StoredEvent<TrustAnchorSignerInitDetails>;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorSignerInitDetails {
+    id: Rfc8183Id,
+    proxy_id: IdCert,
+    ta_cert_details: TaCertDetails,
+    ta_key: CertifiedKey,
+    objects: CurrentKeyObjectSet,
+}
+
+impl TrustAnchorSignerInitDetails {
+    /// Creates a new ID key and Trust Anchor key, and a self-signed Trust
+    /// Anchor certificate that is published under the proxy's repository.
+    pub fn init(
+        handle: &Handle,
+        proxy_info: TrustAnchorProxyInfo,
+        tal_uris: Vec<uri::Https>,
+        tal_rsync_uri: uri::Rsync,
+        timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<TrustAnchorSignerIni> {
+        let (proxy_id, repo_info) = proxy_info.unpack();
+
+        let id = Rfc8183Id::generate(signer)?;
+
+        let key = signer.create_key()?;
+        let ta_cert_details = make_ta_cert_details(&key, &repo_info, tal_uris, Some(tal_rsync_uri.clone()), signer)?;
+
+        let rcvd_cert = RcvdCert::new(
+            ta_cert_details.cert().clone(),
+            tal_rsync_uri,
+            ta_cert_details.resources().clone(),
+        );
+        let ta_key = CertifiedKey::create(rcvd_cert);
+
+        let objects = CurrentKeyObjectSet::create(&ta_key, timing, signer)?;
+
+        Ok(StoredEvent::new(
+            handle,
+            0,
+            TrustAnchorSignerInitDetails {
+                id,
+                proxy_id,
+                ta_cert_details,
+                ta_key,
+                objects,
+            },
+        ))
+    }
+}
+
+impl fmt::Display for TrustAnchorSignerInitDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Initialized Trust Anchor signer with TA key '{}' for proxy with ID key '{}'",
+            self.ta_key.key_id(),
+            self.proxy_id.ski_hex()
+        )
+    }
+}
+
+//------------ TrustAnchorSignerExchange -------------------------------------
+
+/// A processed request and the response that was produced for it. The
+/// complete list of exchanges is the audit log of the signer.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorSignerExchange {
+    pub time: Timestamp,
+    pub request: TrustAnchorSignerRequest,
+    pub signed_request: TrustAnchorSignedRequest,
+    pub response: TrustAnchorSignerResponse,
+    pub signed_response: TrustAnchorSignedResponse,
+}
+
+impl fmt::Display for TrustAnchorSignerExchange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Time: {}", self.time.to_rfc3339())?;
+        writeln!(f, "Request:")?;
+        write!(f, "{}", self.request)?;
+        writeln!(f, "Response:")?;
+        write!(f, "{}", self.response)
+    }
+}
+
+//------------ TrustAnchorSignerExchanges ------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorSignerExchanges(Vec<TrustAnchorSignerExchange>);
+
+impl fmt::Display for TrustAnchorSignerExchanges {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            writeln!(f, "No requests were processed yet.")
+        } else {
+            for exchange in &self.0 {
+                writeln!(f, "{}", exchange)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+//------------ TrustAnchorSignerEvent ----------------------------------------
+
+pub type TrustAnchorSignerEvent = StoredEvent<TrustAnchorSignerEventDetails>;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[allow(clippy::large_enum_variant)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum TrustAnchorSignerEventDetails {
+    RequestProcessed { exchange: TrustAnchorSignerExchange },
+}
+
+impl fmt::Display for TrustAnchorSignerEventDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrustAnchorSignerEventDetails::RequestProcessed { exchange } => {
+                write!(f, "Processed request with nonce '{}'", exchange.request.nonce)
+            }
+        }
+    }
+}
+
+//------------ TrustAnchorSignerCommand --------------------------------------
+
+pub type TrustAnchorSignerCommand = SentCommand<TrustAnchorSignerCommandDetails>;
+
+#[derive(Clone, Debug)]
+pub enum TrustAnchorSignerCommandDetails {
+    ProcessRequest(TrustAnchorSignedRequest, IssuanceTimingConfig, Arc<KrillSigner>),
+}
+
+impl CommandDetails for TrustAnchorSignerCommandDetails {
+    type Event = TrustAnchorSignerEvent;
+    type StorableDetails = StorableTrustAnchorSignerCommand;
+
+    fn store(&self) -> Self::StorableDetails {
+        match self {
+            TrustAnchorSignerCommandDetails::ProcessRequest(..) => StorableTrustAnchorSignerCommand::ProcessRequest,
+        }
+    }
+}
+
+impl fmt::Display for TrustAnchorSignerCommandDetails {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.store().fmt(f)
+    }
+}
+
+impl TrustAnchorSignerCommandDetails {
+    pub fn process_request(
+        handle: &Handle,
+        signed_request: TrustAnchorSignedRequest,
+        timing: IssuanceTimingConfig,
+        signer: Arc<KrillSigner>,
+        actor: &Actor,
+    ) -> TrustAnchorSignerCommand {
+        SentCommand::new(
+            handle,
+            None,
+            TrustAnchorSignerCommandDetails::ProcessRequest(signed_request, timing, signer),
+            actor,
+        )
+    }
+}
+
+//------------ TrustAnchorSigner ---------------------------------------------
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustAnchorSigner {
+    handle: Handle,
+    version: u64,
+    id: Rfc8183Id,
+    proxy_id: IdCert,
+    ta_cert_details: TaCertDetails,
+    ta_key: CertifiedKey,
+    objects: CurrentKeyObjectSet,
+    exchanges: Vec<TrustAnchorSignerExchange>,
+}
+
+/// # Event Sourcing support
+///
+impl Aggregate for TrustAnchorSigner {
+    type Command = TrustAnchorSignerCommand;
+    type StorableCommandDetails = StorableTrustAnchorSignerCommand;
+    type Event = TrustAnchorSignerEvent;
+    type InitEvent = TrustAnchorSignerIni;
+    type Error = Error;
+
+    fn init(event: Self::InitEvent) -> Result<Self, Self::Error> {
+        let (handle, _version, details) = event.unpack();
+
+        Ok(TrustAnchorSigner {
+            handle,
+            version: 1,
+            id: details.id,
+            proxy_id: details.proxy_id,
+            ta_cert_details: details.ta_cert_details,
+            ta_key: details.ta_key,
+            objects: details.objects,
+            exchanges: vec![],
+        })
+    }
+
+    fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn apply(&mut self, event: Self::Event) {
+        self.version += 1;
+        match event.into_details() {
+            TrustAnchorSignerEventDetails::RequestProcessed { exchange } => {
+                self.objects = exchange.response.objects.clone();
+                self.exchanges.push(exchange);
+            }
+        }
+    }
+
+    fn process_command(&self, command: Self::Command) -> Result<Vec<Self::Event>, Self::Error> {
+        info!(
+            "Sending command to Trust Anchor signer '{}', version: {}: {}",
+            self.handle, self.version, command
+        );
+
+        match command.into_details() {
+            TrustAnchorSignerCommandDetails::ProcessRequest(signed_request, timing, signer) => {
+                self.process_request(signed_request, &timing, &signer)
+            }
+        }
+    }
+}
+
+/// # Process requests
+///
+impl TrustAnchorSigner {
+    /// Processes a signed request from the proxy. All child requests are
+    /// processed, and the manifest and CRL are always re-issued. Returns an
+    /// error if the request was not signed by the known proxy, if it was
+    /// processed before, or if any of the child requests cannot be honoured.
+    fn process_request(
+        &self,
+        signed_request: TrustAnchorSignedRequest,
+        timing: &IssuanceTimingConfig,
+        signer: &KrillSigner,
+    ) -> KrillResult<Vec<TrustAnchorSignerEvent>> {
+        let request = signed_request.validate(&self.proxy_id)?;
+
+        if self.has_processed(&request.nonce) {
+            return Err(Error::TaSignerRequestInvalid(format!(
+                "request with nonce '{}' was already processed",
+                request.nonce
+            )));
+        }
+
+        let mut issued_certs = vec![];
+        let mut removed_keys = vec![];
+        let mut child_responses = vec![];
+
+        for child_requests in &request.child_requests {
+            let mut responses = vec![];
+
+            for provisioning_request in &child_requests.requests {
+                match provisioning_request {
+                    ProvisioningRequest::Issue { request } => {
+                        let (rcn, limit, csr) = request.clone().unpack();
+
+                        if rcn != ResourceClassName::default() {
+                            return Err(Error::TaSignerRequestInvalid(format!(
+                                "child '{}' requested a certificate for unknown resource class '{}'",
+                                child_requests.child, rcn
+                            )));
+                        }
+
+                        let csr_info = CsrInfo::try_from(&csr)?;
+                        if !csr_info.global_uris() && !test_mode_enabled() {
+                            return Err(Error::invalid_csr(
+                                "MUST use hostnames in URIs for certificate requests.",
+                            ));
+                        }
+
+                        let issued = SignSupport::make_issued_cert(
+                            csr_info,
+                            &child_requests.resources,
+                            limit,
+                            None,
+                            &self.ta_key,
+                            SignSupport::sign_validity_weeks(timing.timing_child_certificate_valid_weeks),
+                            signer,
+                        )?;
+
+                        issued_certs.push(issued.clone());
+                        responses.push(ProvisioningResponse::Issued { issued });
+                    }
+                    ProvisioningRequest::Revoke { request } => {
+                        removed_keys.push(*request.key());
+                        responses.push(ProvisioningResponse::Revoked {
+                            response: request.into(),
+                        });
+                    }
+                }
+            }
+
+            child_responses.push(TrustAnchorChildResponses {
+                child: child_requests.child.clone(),
+                responses,
+            });
+        }
+
+        let mut objects = self.objects.clone();
+        let updates = ChildCertificateUpdates::new(issued_certs, removed_keys, vec![], vec![]);
+        objects.update_certs(&updates, timing, signer)?;
+
+        let response = TrustAnchorSignerResponse {
+            nonce: request.nonce.clone(),
+            objects,
+            child_responses,
+        };
+
+        let signed_response = response.sign(&self.id.key_id(), signer)?;
+
+        info!("Trust Anchor signer processed request with nonce '{}'", request.nonce);
+
+        let exchange = TrustAnchorSignerExchange {
+            time: Timestamp::now(),
+            request,
+            signed_request,
+            response,
+            signed_response,
+        };
+
+        Ok(vec![StoredEvent::new(
+            &self.handle,
+            self.version,
+            TrustAnchorSignerEventDetails::RequestProcessed { exchange },
+        )])
+    }
+
+    fn has_processed(&self, nonce: &Nonce) -> bool {
+        self.exchanges.iter().any(|exchange| &exchange.request.nonce == nonce)
+    }
+}
+
+/// # Accessors
+///
+impl TrustAnchorSigner {
+    pub fn info(&self) -> TrustAnchorSignerInfo {
+        TrustAnchorSignerInfo::new(
+            self.id.cert().clone(),
+            self.ta_cert_details.clone(),
+            self.ta_key.incoming_cert().uri().clone(),
+        )
+    }
+
+    pub fn exchanges(&self) -> TrustAnchorSignerExchanges {
+        TrustAnchorSignerExchanges(self.exchanges.clone())
+    }
+
+    pub fn last_exchange(&self) -> Option<&TrustAnchorSignerExchange> {
+        self.exchanges.last()
+    }
+}
+
+//------------ TrustAnchorSignerManager --------------------------------------
+
+/// Manages the Trust Anchor signer in a local data directory. This is used
+/// by the `krillta` command line tool, there is no server involved.
+pub struct TrustAnchorSignerManager {
+    store: AggregateStore<TrustAnchorSigner>,
+    signer: Arc<KrillSigner>,
+    actor: Actor,
+}
+
+impl TrustAnchorSignerManager {
+    pub fn create(data_dir: &Path) -> KrillResult<Self> {
+        let store = AggregateStore::disk(data_dir, TA_SIGNER_DIR)?;
+        let signer = Arc::new(KrillSigner::build(data_dir)?);
+        let actor = Actor::local(ACTOR_DEF_KRILLTA);
+
+        Ok(TrustAnchorSignerManager { store, signer, actor })
+    }
+
+    /// Initializes the signer. Returns an error if it was already initialized.
+    pub fn init(
+        &self,
+        proxy_info: TrustAnchorProxyInfo,
+        tal_uris: Vec<uri::Https>,
+        tal_rsync_uri: uri::Rsync,
+        timing: &IssuanceTimingConfig,
+    ) -> KrillResult<TrustAnchorSignerInfo> {
+        let handle = ta_handle();
+        if self.store.has(&handle)? {
+            return Err(Error::TaSignerAlreadyInitialized);
+        }
+
+        let init =
+            TrustAnchorSignerInitDetails::init(&handle, proxy_info, tal_uris, tal_rsync_uri, timing, &self.signer)?;
+        let ta_signer = self.store.add(init)?;
+
+        Ok(ta_signer.info())
+    }
+
+    pub fn show(&self) -> KrillResult<TrustAnchorSignerInfo> {
+        self.get_signer().map(|ta_signer| ta_signer.info())
+    }
+
+    /// Processes a signed request from the proxy and returns the signed
+    /// response that needs to be imported by the proxy.
+    pub fn process(
+        &self,
+        signed_request: TrustAnchorSignedRequest,
+        timing: IssuanceTimingConfig,
+    ) -> KrillResult<TrustAnchorSignerExchange> {
+        self.get_signer()?;
+
+        let cmd = TrustAnchorSignerCommandDetails::process_request(
+            &ta_handle(),
+            signed_request,
+            timing,
+            self.signer.clone(),
+            &self.actor,
+        );
+        let ta_signer = self.store.command(cmd)?;
+
+        ta_signer
+            .last_exchange()
+            .cloned()
+            .ok_or_else(|| Error::custom("Trust Anchor signer did not record the exchange"))
+    }
+
+    /// Returns the last exchange, e.g. in case the response file was lost.
+    pub fn last_exchange(&self) -> KrillResult<TrustAnchorSignerExchange> {
+        self.get_signer()?
+            .last_exchange()
+            .cloned()
+            .ok_or_else(|| Error::custom("No requests were processed yet"))
+    }
+
+    pub fn exchanges(&self) -> KrillResult<TrustAnchorSignerExchanges> {
+        self.get_signer().map(|ta_signer| ta_signer.exchanges())
+    }
+
+    pub fn history(&self, crit: CommandHistoryCriteria) -> KrillResult<CommandHistory> {
+        Ok(self.store.command_history(&ta_handle(), crit)?)
+    }
+
+    fn get_signer(&self) -> KrillResult<Arc<TrustAnchorSigner>> {
+        let handle = ta_handle();
+        if self.store.has(&handle)? {
+            Ok(self.store.get_latest(&handle)?)
+        } else {
+            Err(Error::TaSignerNotInitialized)
+        }
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use rpki::repository::{crypto::KeyIdentifier, x509::Time};
+
+    use crate::{
+        commons::{
+            api::{IssuanceRequest, RepoInfo, RequestResourceLimit},
+            crypto::IdCertBuilder,
+        },
+        constants::{enable_test_mode, ACTOR_DEF_TEST},
+        daemon::config::Config,
+        ta::{new_nonce, TrustAnchorChildRequests},
+        test::*,
+    };
+
+    use super::*;
+
+    fn ta_repo_info() -> RepoInfo {
+        RepoInfo::new(
+            rsync("rsync://localhost/repo/ta/"),
+            https("https://localhost/rrdp/notification.xml"),
+        )
+    }
+
+    /// Creates a Trust Anchor signer for a proxy which uses the given ID key.
+    fn ta_signer(proxy_key: &KeyIdentifier, timing: &IssuanceTimingConfig, signer: &KrillSigner) -> TrustAnchorSigner {
+        let proxy_id = IdCertBuilder::new_ta_id_cert(proxy_key, signer).unwrap();
+        let init = TrustAnchorSignerInitDetails::init(
+            &ta_handle(),
+            TrustAnchorProxyInfo::new(proxy_id, ta_repo_info()),
+            vec![https("https://localhost/ta/ta.cer")],
+            rsync("rsync://localhost/ta/ta.cer"),
+            timing,
+            signer,
+        )
+        .unwrap();
+        TrustAnchorSigner::init(init).unwrap()
+    }
+
+    /// Creates a request to issue a certificate to a child.
+    fn issue_request(rcn: ResourceClassName, signer: &KrillSigner) -> ProvisioningRequest {
+        let child_repo_info = RepoInfo::new(
+            rsync("rsync://localhost/repo/child/"),
+            https("https://localhost/rrdp/notification.xml"),
+        );
+        let key = signer.create_key().unwrap();
+        let csr = signer.sign_csr(&child_repo_info, "0", &key).unwrap();
+        ProvisioningRequest::Issue {
+            request: IssuanceRequest::new(rcn, RequestResourceLimit::default(), csr),
+        }
+    }
+
+    fn process(
+        ta_signer: &TrustAnchorSigner,
+        signed_request: TrustAnchorSignedRequest,
+        timing: &IssuanceTimingConfig,
+        signer: &Arc<KrillSigner>,
+    ) -> KrillResult<Vec<TrustAnchorSignerEvent>> {
+        let command = TrustAnchorSignerCommandDetails::process_request(
+            &ta_handle(),
+            signed_request,
+            timing.clone(),
+            signer.clone(),
+            &Actor::test_from_def(ACTOR_DEF_TEST),
+        );
+        ta_signer.process_command(command)
+    }
+
+    #[test]
+    fn process_request_issues_certs_mft_and_crl() {
+        test_under_tmp(|d| {
+            enable_test_mode();
+            let signer = Arc::new(KrillSigner::build(&d).unwrap());
+            let timing = Config::test(&d, false, false, false).issuance_timing;
+
+            let proxy_key = signer.create_key().unwrap();
+            let mut ta_signer = ta_signer(&proxy_key, &timing, &signer);
+
+            let request = TrustAnchorSignerRequest {
+                nonce: new_nonce(),
+                child_requests: vec![TrustAnchorChildRequests {
+                    child: handle("child"),
+                    resources: ipv4_resources("10.0.0.0/8"),
+                    requests: vec![issue_request(ResourceClassName::default(), &signer)],
+                }],
+            };
+            let signed_request = request.sign(&proxy_key, &signer).unwrap();
+
+            for event in process(&ta_signer, signed_request.clone(), &timing, &signer).unwrap() {
+                ta_signer.apply(event);
+            }
+
+            let exchange = ta_signer.last_exchange().unwrap();
+            assert_eq!(request.nonce, exchange.response.nonce);
+            assert_eq!(1, exchange.response.child_responses.len());
+
+            let names: Vec<String> = exchange
+                .response
+                .objects
+                .publish_elements()
+                .iter()
+                .map(|element| element.uri().to_string())
+                .collect();
+            assert_eq!(3, names.len());
+            assert!(names.iter().any(|name| name.ends_with(".mft")));
+            assert!(names.iter().any(|name| name.ends_with(".crl")));
+            assert!(names.iter().any(|name| name.ends_with(".cer")));
+            assert!(exchange.response.objects.next_update_time() > Time::now());
+
+            // The response is signed by the signer's ID key.
+            let validated = exchange.signed_response.validate(ta_signer.info().id_cert()).unwrap();
+            assert_eq!(exchange.response, validated);
+
+            // The same request cannot be processed twice.
+            match process(&ta_signer, signed_request, &timing, &signer) {
+                Err(Error::TaSignerRequestInvalid(msg)) => assert!(msg.contains("already processed")),
+                _ => panic!("Expected replayed request to be rejected"),
+            }
+        });
+    }
+
+    #[test]
+    fn process_request_rejects_unknown_resource_class() {
+        test_under_tmp(|d| {
+            enable_test_mode();
+            let signer = Arc::new(KrillSigner::build(&d).unwrap());
+            let timing = Config::test(&d, false, false, false).issuance_timing;
+
+            let proxy_key = signer.create_key().unwrap();
+            let ta_signer = ta_signer(&proxy_key, &timing, &signer);
+
+            let request = TrustAnchorSignerRequest {
+                nonce: new_nonce(),
+                child_requests: vec![TrustAnchorChildRequests {
+                    child: handle("child"),
+                    resources: ipv4_resources("10.0.0.0/8"),
+                    requests: vec![issue_request(ResourceClassName::from(1), &signer)],
+                }],
+            };
+            let signed_request = request.sign(&proxy_key, &signer).unwrap();
+
+            match process(&ta_signer, signed_request, &timing, &signer) {
+                Err(Error::TaSignerRequestInvalid(msg)) => assert!(msg.contains("unknown resource class")),
+                _ => panic!("Expected request for unknown resource class to be rejected"),
+            }
+        });
+    }
+
+    #[test]
+    fn process_request_rejects_other_signing_key() {
+        test_under_tmp(|d| {
+            let signer = Arc::new(KrillSigner::build(&d).unwrap());
+            let timing = Config::test(&d, false, false, false).issuance_timing;
+
+            let proxy_key = signer.create_key().unwrap();
+            let ta_signer = ta_signer(&proxy_key, &timing, &signer);
+
+            let other_key = signer.create_key().unwrap();
+            let request = TrustAnchorSignerRequest {
+                nonce: new_nonce(),
+                child_requests: vec![],
+            };
+            let signed_request = request.sign(&other_key, &signer).unwrap();
+
+            assert!(process(&ta_signer, signed_request, &timing, &signer).is_err());
+            assert!(ta_signer.last_exchange().is_none());
+        });
+    }
+}
@@ -26,10 +26,11 @@ use crate::{
     commons::{
         api::{
             AddChildRequest, AspaCustomer, AspaDefinition, AspaDefinitionList, AspaProvidersUpdate, CertAuthInfo,
-            CertAuthInit, CertifiedKeyInfo, ChildHandle, Handle, ObjectName, ParentCaContact, ParentCaReq,
+            CertAuthInit, CertAuthIssues, CertAuthRename, CertAuthRenameReport, CertifiedKeyInfo, ChildCaInfo,
+            ChildHandle, ChildSuspensionPolicy, Handle, KeyRollStatus, ObjectName, ParentCaContact, ParentCaReq,
             ParentHandle, ParentStatuses, PublicationServerUris, PublisherDetails, PublisherHandle, PublisherList,
-            RepositoryContact, ResourceClassKeysInfo, ResourceClassName, ResourceSet, RoaDefinition,
-            RoaDefinitionUpdates, RtaList, RtaName, RtaPrepResponse, TypedPrefix, UpdateChildRequest,
+            RepositoryContact, RepositoryMirrorList, ResourceClassKeysInfo, ResourceClassName, ResourceSet,
+            RoaDefinition, RoaDefinitionUpdates, RtaList, RtaName, RtaPrepResponse, TypedPrefix, UpdateChildRequest,
         },
         bgp::{Announcement, BgpAnalysisReport, BgpAnalysisSuggestion},
         crypto::SignSupport,
@@ -152,6 +153,13 @@ async fn start_krill_with_error_trap(config: Arc<Config>) {
 /// Starts a krill pubd for testing on its own port, and its
 /// own temp dir for storage.
 pub async fn start_krill_pubd() -> PathBuf {
+    start_krill_pubd_with_rsync_base("rsync://localhost/dedicated-repo/").await
+}
+
+/// Starts a dedicated publication server using the given rsync base URI. If
+/// this is the same as for the embedded repository, then CAs can use the
+/// dedicated server as a mirror of the embedded repository.
+pub async fn start_krill_pubd_with_rsync_base(rsync_base: &str) -> PathBuf {
     let dir = tmp_dir();
     let mut config = test_config(&dir, false, false, false);
     init_config(&config);
@@ -162,7 +170,7 @@ pub async fn start_krill_pubd() -> PathBuf {
 
     // Initialize the repository using separate URIs
     let uris = {
-        let rsync_base = uri::Rsync::from_str("rsync://localhost/dedicated-repo/").unwrap();
+        let rsync_base = uri::Rsync::from_str(rsync_base).unwrap();
         let rrdp_base_uri = uri::Https::from_str("https://localhost:3001/test-rrdp/").unwrap();
         PublicationServerUris::new(rrdp_base_uri, rsync_base)
     };
@@ -231,6 +239,21 @@ pub async fn ca_unsuspend_child(ca: &Handle, child: &ChildHandle) {
     .await;
 }
 
+pub async fn ca_child_info(ca: &Handle, child: &ChildHandle) -> ChildCaInfo {
+    match krill_admin(Command::CertAuth(CaCommand::ChildInfo(ca.clone(), child.clone()))).await {
+        ApiResponse::ChildInfo(info) => info,
+        _ => panic!("Expected child info"),
+    }
+}
+
+pub async fn ca_child_suspension_policy(ca: &Handle, child: &ChildHandle, policy: Option<ChildSuspensionPolicy>) {
+    let command = match policy {
+        Some(policy) => CaCommand::SuspensionPolicySet(ca.clone(), Some(child.clone()), policy),
+        None => CaCommand::SuspensionPolicyClear(ca.clone(), Some(child.clone())),
+    };
+    krill_admin(Command::CertAuth(command)).await;
+}
+
 pub async fn init_ca(handle: &Handle) {
     krill_admin(Command::CertAuth(CaCommand::Init(CertAuthInit::new(handle.clone())))).await;
 }
@@ -239,6 +262,14 @@ pub async fn delete_ca(ca: &Handle) {
     krill_admin(Command::CertAuth(CaCommand::Delete(ca.clone()))).await;
 }
 
+pub async fn ca_rename(ca: &Handle, new_handle: &Handle) -> CertAuthRenameReport {
+    let rename = CertAuthRename::new(new_handle.clone());
+    match krill_admin(Command::CertAuth(CaCommand::Rename(ca.clone(), rename))).await {
+        ApiResponse::CertAuthRenamed(report) => report,
+        _ => panic!("Expected CA rename report"),
+    }
+}
+
 pub async fn ca_repo_update_rfc8181(handle: &Handle, response: RepositoryResponse) {
     krill_admin(Command::CertAuth(CaCommand::RepoUpdate(
         handle.clone(),
@@ -680,6 +711,30 @@ pub async fn repo_update(ca: &Handle, contact: RepositoryContact) {
     krill_admin(command).await;
 }
 
+pub async fn repo_mirror_add(ca: &Handle, name: &str, contact: RepositoryContact) {
+    let command = Command::CertAuth(CaCommand::RepoMirrorAdd(ca.clone(), name.to_string(), contact));
+    krill_admin(command).await;
+}
+
+pub async fn repo_mirror_remove(ca: &Handle, name: &str) {
+    let command = Command::CertAuth(CaCommand::RepoMirrorRemove(ca.clone(), name.to_string()));
+    krill_admin(command).await;
+}
+
+pub async fn repo_mirrors(ca: &Handle) -> RepositoryMirrorList {
+    match krill_admin(Command::CertAuth(CaCommand::RepoMirrorsList(ca.clone()))).await {
+        ApiResponse::RepoMirrors(mirrors) => mirrors,
+        _ => panic!("Expected repository mirrors"),
+    }
+}
+
+pub async fn ca_issues(ca: &Handle) -> CertAuthIssues {
+    match krill_admin(Command::CertAuth(CaCommand::Issues(Some(ca.clone())))).await {
+        ApiResponse::CertAuthIssues(issues) => issues,
+        _ => panic!("Expected CA issues"),
+    }
+}
+
 pub async fn embedded_repository_response(publisher: &PublisherHandle) -> rfc8183::RepositoryResponse {
     let command = PubServerCommand::RepositoryResponse(publisher.clone());
     match krill_embedded_pubd_admin(command).await {
@@ -813,8 +868,32 @@ pub async fn ca_roll_init(handle: &Handle) {
     krill_admin(Command::CertAuth(CaCommand::KeyRollInit(handle.clone()))).await;
 }
 
+// Tests do not wait for the staging period, so activation is forced.
 pub async fn ca_roll_activate(handle: &Handle) {
-    krill_admin(Command::CertAuth(CaCommand::KeyRollActivate(handle.clone()))).await;
+    krill_admin(Command::CertAuth(CaCommand::KeyRollActivate(handle.clone(), true))).await;
+}
+
+/// Tries to activate the new key without forcing it, and returns the label
+/// of the error if activation was refused.
+pub async fn ca_roll_activate_unforced(handle: &Handle) -> Option<String> {
+    let options = Options::new(
+        https(KRILL_SERVER_URI),
+        "secret",
+        ReportFormat::Json,
+        Command::CertAuth(CaCommand::KeyRollActivate(handle.clone(), false)),
+    );
+    match KrillClient::process(options).await {
+        Ok(_) => None,
+        Err(Error::HttpClientError(httpclient::Error::ErrorWithJson(_, res))) => Some(res.label().to_string()),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+pub async fn ca_roll_status(handle: &Handle) -> KeyRollStatus {
+    match krill_admin(Command::CertAuth(CaCommand::KeyRollStatus(handle.clone()))).await {
+        ApiResponse::KeyRollStatus(status) => status,
+        _ => panic!("Expected key roll status"),
+    }
 }
 
 pub async fn state_becomes_new_key(handle: &Handle) -> bool {
@@ -278,19 +278,23 @@ impl TryFrom<OldCaEvtDet> for CaEvtDet {
                 CaEvtDet::KeyRollPendingKeyAdded {
                     resource_class_name,
                     pending_key_id,
+                    changed: None,
                 }
             }
             OldCaEvtDet::KeyPendingToNew(resource_class_name, new_key, _delta) => CaEvtDet::KeyPendingToNew {
                 resource_class_name,
                 new_key: new_key.into(),
+                changed: None,
             },
             OldCaEvtDet::KeyPendingToActive(resource_class_name, current_key, _delta) => CaEvtDet::KeyPendingToActive {
                 resource_class_name,
                 current_key: current_key.into(),
+                changed: None,
             },
             OldCaEvtDet::KeyRollActivated(resource_class_name, revoke_req) => CaEvtDet::KeyRollActivated {
                 resource_class_name,
                 revoke_req,
+                changed: None,
             },
             OldCaEvtDet::KeyRollFinished(resource_class_name, _delta) => {
                 CaEvtDet::KeyRollFinished { resource_class_name }
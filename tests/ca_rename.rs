@@ -0,0 +1,180 @@
+//! Test that a CA can be renamed, and that its local child and its
+//! publisher in the embedded repository follow.
+//!
+use std::{fs, str::FromStr, time::Duration};
+
+use tokio::time::sleep;
+
+use krill::{
+    commons::api::{
+        CertAuthRenameStep, CertAuthRenameUpdate, ObjectName, ResourceClassName, ResourceSet, RoaDefinition,
+        RoaDefinitionUpdates,
+    },
+    test::*,
+};
+
+#[tokio::test]
+async fn ca_rename() {
+    init_logging();
+
+    info("##################################################################");
+    info("#                                                                #");
+    info("#                     --= Test CA Rename =--                     #");
+    info("#                                                                #");
+    info("##################################################################");
+
+    //  Uses the following lay-out:
+    //
+    //                  TA
+    //                   |
+    //                testbed
+    //                   |
+    //                  CA1  (renamed to CA1-new)
+    //                   |
+    //                  CA2
+
+    info("##################################################################");
+    info("#                                                                #");
+    info("#                      Start Krill                               #");
+    info("#                                                                #");
+    info("##################################################################");
+    info("");
+    let krill_dir = start_krill_with_default_test_config(true, false, false).await;
+
+    let testbed = handle("testbed");
+
+    let ca1 = handle("CA1");
+    let ca1_new = handle("CA1-new");
+    let ca1_res = ipv4_resources("10.0.0.0/16");
+    let ca1_route_definition = RoaDefinition::from_str("10.0.0.0/16-16 => 65000").unwrap();
+
+    let ca2 = handle("CA2");
+    let ca2_res = ipv4_resources("10.0.0.0/24");
+
+    let rcn_0 = ResourceClassName::from(0);
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all_resources()).await);
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#   Set up CA1 under testbed with a ROA, and CA2 under CA1       #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        set_up_ca_with_repo(&ca1).await;
+        set_up_ca_under_parent_with_resources(&ca1, &testbed, &ca1_res).await;
+
+        let mut updates = RoaDefinitionUpdates::empty();
+        updates.add(ca1_route_definition);
+        ca_route_authorizations_update(&ca1, updates).await;
+
+        set_up_ca_with_repo(&ca2).await;
+        set_up_ca_under_parent_with_resources(&ca2, &ca1, &ca2_res).await;
+
+        let mut expected_files = expected_mft_and_crl(&ca1, &rcn_0).await;
+        expected_files.push(ObjectName::from(&ca1_route_definition).to_string());
+        expected_files.push(expected_issued_cer(&ca2, &rcn_0).await);
+
+        assert!(will_publish_embedded("CA1 should publish MFT, CRL, ROA and CA2 cert", &ca1, &expected_files).await);
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#   Rename CA1 to CA1-new, and expect that CA2 and the embedded  #");
+        info("#   repository were updated, while the testbed parent and the   #");
+        info("#   old publisher are left for the operator                     #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let report = ca_rename(&ca1, &ca1_new).await;
+
+        assert_eq!(&ca1, report.previous());
+        assert_eq!(&ca1_new, report.handle());
+
+        assert!(report.updated().contains(&CertAuthRenameUpdate::ChildParentContact {
+            child: ca2.clone(),
+            parent: ca1.clone(),
+        }));
+        assert!(report.updated().contains(&CertAuthRenameUpdate::Publisher {
+            publisher: ca1_new.clone(),
+        }));
+
+        assert!(report.next_steps().contains(&CertAuthRenameStep::ParentChildRequest {
+            parent: testbed.clone(),
+            child: ca1.clone(),
+        }));
+        assert!(report
+            .next_steps()
+            .contains(&CertAuthRenameStep::RemovePublisher { publisher: ca1.clone() }));
+
+        assert_eq!(&ca1_new, ca_details(&ca1_new).await.handle());
+        assert!(ca_contains_resources(&ca1_new, &ca1_res).await);
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#   Expect that CA2 can still synchronise with its parent, now  #");
+        info("#   using the service URI for CA1-new                           #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        cas_refresh_single(&ca2).await;
+
+        let mut synced = false;
+        for _ in 0..30 {
+            let statuses = parent_statuses(&ca2).await;
+            if let Some(exchange) = statuses.get(&ca1).and_then(|status| status.last_exchange()) {
+                if exchange.was_success() && exchange.uri().to_string().ends_with("rfc6492/CA1-new") {
+                    synced = true;
+                    break;
+                }
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+        assert!(synced, "CA2 should synchronise with CA1-new");
+        assert!(ca_contains_resources(&ca2, &ca2_res).await);
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#   Expect that CA1-new moves to its new publisher using a key  #");
+        info("#   roll, and no longer publishes under the old publisher       #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        assert!(state_becomes_new_key(&ca1_new).await);
+
+        let expected_files = expected_new_key_mft_and_crl(&ca1_new, &rcn_0).await;
+        assert!(
+            will_publish_embedded(
+                "CA1-new should publish the MFT and CRL for its new key under the new publisher",
+                &ca1_new,
+                &expected_files
+            )
+            .await
+        );
+
+        ca_roll_activate(&ca1_new).await;
+        assert!(state_becomes_active(&ca1_new).await);
+
+        let mut expected_files = expected_mft_and_crl(&ca1_new, &rcn_0).await;
+        expected_files.push(ObjectName::from(&ca1_route_definition).to_string());
+        expected_files.push(expected_issued_cer(&ca2, &rcn_0).await);
+        assert!(
+            will_publish_embedded(
+                "CA1-new should publish MFT, CRL, ROA and CA2 cert",
+                &ca1_new,
+                &expected_files
+            )
+            .await
+        );
+
+        assert!(will_publish_embedded("CA1 should no longer publish anything", &ca1, &[]).await);
+    }
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
@@ -0,0 +1,67 @@
+//! Test that tenants and scoped API tokens keep access to a CA after it is
+//! renamed.
+#![cfg(feature = "multi-user")]
+
+use std::fs;
+
+use krill::{
+    cli::{
+        options::{Command, TenantCommand, TokenCommand},
+        report::ApiResponse,
+    },
+    commons::{
+        api::{ApiTokenRequest, CertAuthRenameUpdate, TenantDefinition},
+        util::httpclient,
+    },
+    test::*,
+};
+
+#[tokio::test]
+async fn ca_rename_access() {
+    init_logging();
+
+    let krill_dir = start_krill_with_default_test_config(false, false, false).await;
+
+    let ca = handle("ca-tenant");
+    let ca_new = handle("ca-tenant-new");
+    let ca_other = handle("ca-other");
+
+    init_ca(&ca).await;
+    init_ca(&ca_other).await;
+
+    let tenant = TenantDefinition::new("customer1".to_string(), vec![ca.clone(), ca_other.clone()]);
+    krill_admin(Command::Tenants(TenantCommand::Add(tenant))).await;
+
+    let request = ApiTokenRequest::new(
+        "reader".to_string(),
+        vec!["CA_READ".to_string()],
+        Some(vec![ca.clone()]),
+        None,
+    );
+    let token = match krill_admin(Command::Token(TokenCommand::Create(request))).await {
+        ApiResponse::ApiTokenCreated(created) => created.token().clone(),
+        _ => panic!("Expected created token"),
+    };
+
+    let report = ca_rename(&ca, &ca_new).await;
+    assert!(report.updated().contains(&CertAuthRenameUpdate::Tenant {
+        tenant: "customer1".to_string()
+    }));
+    assert!(report.updated().contains(&CertAuthRenameUpdate::ApiToken {
+        token: "reader".to_string()
+    }));
+    assert!(report.next_steps().is_empty());
+
+    match krill_admin(Command::Tenants(TenantCommand::Show("customer1".to_string()))).await {
+        ApiResponse::Tenant(tenant) => assert_eq!(&vec![ca_new.clone(), ca_other.clone()], tenant.cas()),
+        _ => panic!("Expected tenant"),
+    }
+
+    // The token can read the CA under its new name, and nothing else.
+    let uri = |ca| format!("{}api/v1/cas/{}", KRILL_SERVER_URI, ca);
+    assert!(httpclient::get_text(&uri(&ca_new), Some(&token)).await.is_ok());
+    assert!(httpclient::get_text(&uri(&ca), Some(&token)).await.is_err());
+    assert!(httpclient::get_text(&uri(&ca_other), Some(&token)).await.is_err());
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
@@ -0,0 +1,69 @@
+//! Test that commands sent to a CA while it is being renamed are either
+//! processed before the rename, and then end up in the renamed CA, or are
+//! refused because the CA no longer exists under its old name.
+use std::{fs, str::FromStr};
+
+use futures::future::join_all;
+
+use krill::{
+    cli::{
+        options::{CaCommand, Command, Options},
+        report::{ApiResponse, ReportFormat},
+        KrillClient,
+    },
+    commons::api::{Handle, RoaDefinition, RoaDefinitionUpdates},
+    test::*,
+};
+
+async fn update_roas(ca: &Handle, definition: RoaDefinition) -> bool {
+    let mut updates = RoaDefinitionUpdates::empty();
+    updates.add(definition);
+
+    let command = Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(ca.clone(), updates));
+    let options = Options::new(https(KRILL_SERVER_URI), "secret", ReportFormat::Json, command);
+    KrillClient::process(options).await.is_ok()
+}
+
+#[tokio::test]
+async fn ca_rename_concurrent() {
+    init_logging();
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false).await;
+
+    let testbed = handle("testbed");
+    let ca = handle("CA1");
+    let ca_new = handle("CA1-new");
+
+    set_up_ca_with_repo(&ca).await;
+    set_up_ca_under_parent_with_resources(&ca, &testbed, &ipv4_resources("10.0.0.0/16")).await;
+
+    // Send ROA updates for the old handle while the CA is renamed.
+    let definitions: Vec<RoaDefinition> = (0..10)
+        .map(|nr| RoaDefinition::from_str(&format!("10.0.{}.0/24 => 65000", nr)).unwrap())
+        .collect();
+
+    let updates = join_all(definitions.iter().map(|def| update_roas(&ca, *def)));
+    let (results, report) = tokio::join!(updates, ca_rename(&ca, &ca_new));
+    assert_eq!(&ca_new, report.handle());
+
+    // Every update that succeeded is kept in the renamed CA, and nothing else.
+    let roas = match krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsList(ca_new.clone()))).await {
+        ApiResponse::RouteAuthorizations(roas) => roas.to_string(),
+        _ => panic!("Expected ROA definitions"),
+    };
+    for (def, accepted) in definitions.iter().zip(results) {
+        assert_eq!(accepted, roas.contains(&def.to_string()), "ROA {}", def);
+    }
+
+    // Nothing was left or re-created under the old handle.
+    match krill_admin(Command::CertAuth(CaCommand::List)).await {
+        ApiResponse::CertAuths(list) => {
+            assert!(list.cas().iter().any(|summary| summary.handle() == &ca_new));
+            assert!(!list.cas().iter().any(|summary| summary.handle() == &ca));
+        }
+        _ => panic!("Expected CA list"),
+    }
+    assert!(!update_roas(&ca, definitions[0]).await);
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
@@ -0,0 +1,103 @@
+//! Test that a prospective child can submit its request to a parent CA, and
+//! retrieve its parent response with the one-time token once the operator
+//! approved the request.
+use std::fs;
+
+use bytes::Bytes;
+
+use krill::{
+    cli::{
+        options::{CaCommand, Command},
+        report::ApiResponse,
+    },
+    commons::{
+        api::{ChildRequestList, ChildRequestReceipt, Handle, Token},
+        remote::rfc8183,
+        util::httpclient::{self, Error},
+    },
+    test::*,
+};
+
+async fn submit(parent: &Handle, child: &Handle) -> Result<ChildRequestReceipt, Error> {
+    let uri = format!("{}onboarding/{}/child_request", KRILL_SERVER_URI, parent);
+    let xml = Bytes::from(request(child).await.encode_vec());
+    let bytes = httpclient::post_binary_with_full_ua(&uri, &xml, "application/xml").await?;
+    Ok(serde_json::from_slice(bytes.as_ref()).unwrap())
+}
+
+async fn parent_response(parent: &Handle, child: &Handle, token: &Token) -> Result<rfc8183::ParentResponse, Error> {
+    let uri = format!("{}onboarding/{}/{}/parent_response", KRILL_SERVER_URI, parent, child);
+    let token = Bytes::from(token.as_ref().to_string());
+    let bytes = httpclient::post_binary_with_full_ua(&uri, &token, "text/plain").await?;
+    Ok(rfc8183::ParentResponse::validate(bytes.as_ref()).unwrap())
+}
+
+async fn child_requests(parent: &Handle) -> ChildRequestList {
+    match krill_admin(Command::CertAuth(CaCommand::ChildRequests(parent.clone()))).await {
+        ApiResponse::ChildRequests(list) => list,
+        _ => panic!("Expected child requests"),
+    }
+}
+
+#[tokio::test]
+async fn child_onboarding() {
+    init_logging();
+
+    let krill_dir = start_krill_with_default_test_config(true, false, false).await;
+
+    let testbed = handle("testbed");
+    let parent = handle("parent");
+    let approved = handle("approved");
+    let rejected = handle("rejected");
+
+    set_up_ca_with_repo(&parent).await;
+    set_up_ca_under_parent_with_resources(&parent, &testbed, &ipv4_resources("10.0.0.0/16")).await;
+
+    init_ca(&approved).await;
+    init_ca(&rejected).await;
+
+    // Submit requests, the same child cannot submit twice.
+    let receipt = submit(&parent, &approved).await.unwrap();
+    assert_eq!(&parent, receipt.parent());
+    assert_eq!(&approved, receipt.child());
+    assert!(submit(&parent, &approved).await.is_err());
+
+    let rejected_receipt = submit(&parent, &rejected).await.unwrap();
+    assert_eq!(2, child_requests(&parent).await.requests().len());
+
+    // The parent response cannot be retrieved until the request is approved.
+    assert!(parent_response(&parent, &approved, receipt.token()).await.is_err());
+
+    krill_admin(Command::CertAuth(CaCommand::ChildRequestApprove(
+        parent.clone(),
+        approved.clone(),
+        ipv4_resources("10.0.0.0/24"),
+    )))
+    .await;
+    assert!(ca_details(&parent).await.children().contains(&approved));
+
+    // A wrong token is refused, the right token works exactly once.
+    assert!(parent_response(&parent, &approved, &Token::from("wrong"))
+        .await
+        .is_err());
+
+    let response = parent_response(&parent, &approved, receipt.token()).await.unwrap();
+    assert_eq!(&parent, response.parent_handle());
+    assert_eq!(&approved, response.child_handle());
+
+    assert!(parent_response(&parent, &approved, receipt.token()).await.is_err());
+
+    // A rejected request is removed, and its token can no longer be used.
+    krill_admin(Command::CertAuth(CaCommand::ChildRequestReject(
+        parent.clone(),
+        rejected.clone(),
+    )))
+    .await;
+    assert!(child_requests(&parent).await.requests().is_empty());
+    assert!(!ca_details(&parent).await.children().contains(&rejected));
+    assert!(parent_response(&parent, &rejected, rejected_receipt.token())
+        .await
+        .is_err());
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
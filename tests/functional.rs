@@ -7,8 +7,8 @@ use bytes::Bytes;
 
 use krill::{
     commons::api::{
-        AspaCustomer, AspaDefinition, AspaDefinitionList, AspaProvidersUpdate, ObjectName, ResourceClassName,
-        ResourceSet, RoaDefinition, RoaDefinitionUpdates, RtaList,
+        AspaCustomer, AspaDefinition, AspaDefinitionList, AspaProvidersUpdate, KeyRollBlocker, ObjectName,
+        ResourceClassName, ResourceSet, RoaDefinition, RoaDefinitionUpdates, RtaList,
     },
     daemon::ca::ta_handle,
     test::*,
@@ -300,9 +300,28 @@ async fn functional() {
             .await
         );
 
+        // The new key is staged for 24 hours by default, so activation is
+        // refused unless it is forced.
+        let status = ca_roll_status(&ca1).await;
+        assert!(status.resource_classes().iter().all(|rc| rc
+            .blockers()
+            .iter()
+            .any(|blocker| matches!(blocker, KeyRollBlocker::Staging { .. }))));
+        assert_eq!(
+            Some("key-roll-activate-refused".to_string()),
+            ca_roll_activate_unforced(&ca1).await
+        );
+        assert!(state_becomes_new_key(&ca1).await);
+
         ca_roll_activate(&ca1).await;
         assert!(state_becomes_active(&ca1).await);
 
+        // There is no new key to activate anymore
+        assert_eq!(
+            Some("key-roll-activate-refused".to_string()),
+            ca_roll_activate_unforced(&ca1).await
+        );
+
         let mut expected_files = expected_mft_and_crl(&ca1, &rcn_0).await;
         expected_files.push(expected_issued_cer(&ca3, &rcn_0).await);
         expected_files.push(ObjectName::from(&ca1_route_definition).to_string());
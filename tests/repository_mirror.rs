@@ -0,0 +1,145 @@
+//! Test that a CA can mirror its objects to a secondary repository.
+//!
+use std::{fs, str::FromStr, time::Duration};
+
+use tokio::time::sleep;
+
+use krill::{
+    commons::api::{
+        ObjectName, RepositoryContact, ResourceClassName, ResourceSet, RoaDefinition, RoaDefinitionUpdates,
+    },
+    test::*,
+};
+
+#[tokio::test]
+async fn repository_mirror() {
+    init_logging();
+
+    info("##################################################################");
+    info("#                                                                #");
+    info("#                --= Test Repository Mirrors =--                 #");
+    info("#                                                                #");
+    info("##################################################################");
+
+    info("##################################################################");
+    info("#                                                                #");
+    info("#                      Start Krill                               #");
+    info("#                                                                #");
+    info("##################################################################");
+    info("");
+    let krill_dir = start_krill_with_default_test_config(true, false, false).await;
+
+    info("##################################################################");
+    info("#                                                                #");
+    info("# Start a Secondary Publication Server which uses the same base  #");
+    info("# rsync URI as the embedded repository, so that it can mirror it #");
+    info("#                                                                #");
+    info("##################################################################");
+    info("");
+    let pubd_dir = start_krill_pubd_with_rsync_base("rsync://localhost/repo/").await;
+
+    let testbed = handle("testbed");
+
+    let ca1 = handle("CA1");
+    let ca1_res = ipv4_resources("10.0.0.0/16");
+    let ca1_route_definition = RoaDefinition::from_str("10.0.0.0/16-16 => 65000").unwrap();
+    let ca1_route_definition_2 = RoaDefinition::from_str("10.0.0.0/16-16 => 65001").unwrap();
+
+    let rcn_0 = ResourceClassName::from(0);
+
+    let mirror = "backup";
+
+    assert!(ca_contains_resources(&testbed, &ResourceSet::all_resources()).await);
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#           Set up CA1 under testbed, and create a ROA           #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        set_up_ca_with_repo(&ca1).await;
+        set_up_ca_under_parent_with_resources(&ca1, &testbed, &ca1_res).await;
+
+        let mut updates = RoaDefinitionUpdates::empty();
+        updates.add(ca1_route_definition);
+        ca_route_authorizations_update(&ca1, updates).await;
+
+        let mut expected_files = expected_mft_and_crl(&ca1, &rcn_0).await;
+        expected_files.push(ObjectName::from(&ca1_route_definition).to_string());
+
+        assert!(will_publish_embedded("CA1 should publish MFT, CRL and ROA", &ca1, &expected_files).await);
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#    Add the secondary publication server as a mirror for CA1    #");
+        info("#    and expect that all objects are published there as well    #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let publisher_request = publisher_request(&ca1).await;
+        dedicated_repo_add_publisher(publisher_request).await;
+        let response = dedicated_repository_response(&ca1).await;
+
+        // Wait a tiny bit.. when we add a mirror we check that it's available or
+        // it will be rejected.
+        sleep(Duration::from_secs(1)).await;
+
+        repo_mirror_add(&ca1, mirror, RepositoryContact::new(response)).await;
+
+        let mut expected_files = expected_mft_and_crl(&ca1, &rcn_0).await;
+        expected_files.push(ObjectName::from(&ca1_route_definition).to_string());
+
+        assert!(will_publish_dedicated("CA1 should mirror MFT, CRL and ROA", &ca1, &expected_files).await);
+
+        let mirrors = repo_mirrors(&ca1).await;
+        assert_eq!(1, mirrors.mirrors().len());
+        assert_eq!(mirror, mirrors.mirrors()[0].name());
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#   Add a ROA and expect that it is published in both repos      #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        let mut updates = RoaDefinitionUpdates::empty();
+        updates.add(ca1_route_definition_2);
+        ca_route_authorizations_update(&ca1, updates).await;
+
+        let mut expected_files = expected_mft_and_crl(&ca1, &rcn_0).await;
+        expected_files.push(ObjectName::from(&ca1_route_definition).to_string());
+        expected_files.push(ObjectName::from(&ca1_route_definition_2).to_string());
+
+        assert!(will_publish_embedded("CA1 should publish both ROAs", &ca1, &expected_files).await);
+        assert!(will_publish_dedicated("CA1 should mirror both ROAs", &ca1, &expected_files).await);
+
+        assert!(ca_issues(&ca1).await.repo_mirror_issues().is_empty());
+    }
+
+    {
+        info("##################################################################");
+        info("#                                                                #");
+        info("#   Remove the mirror, and expect that objects are withdrawn     #");
+        info("#   there, while they remain published in the embedded repo      #");
+        info("#                                                                #");
+        info("##################################################################");
+        info("");
+        repo_mirror_remove(&ca1, mirror).await;
+
+        assert!(will_publish_dedicated("CA1 should no longer mirror anything", &ca1, &[]).await);
+        assert!(repo_mirrors(&ca1).await.mirrors().is_empty());
+
+        let mut expected_files = expected_mft_and_crl(&ca1, &rcn_0).await;
+        expected_files.push(ObjectName::from(&ca1_route_definition).to_string());
+        expected_files.push(ObjectName::from(&ca1_route_definition_2).to_string());
+
+        assert!(will_publish_embedded("CA1 should still publish both ROAs", &ca1, &expected_files).await);
+    }
+
+    let _ = fs::remove_dir_all(krill_dir);
+    let _ = fs::remove_dir_all(pubd_dir);
+}
@@ -3,7 +3,7 @@
 use std::fs;
 
 use krill::{
-    commons::api::{ChildHandle, Handle, ResourceSet},
+    commons::api::{ChildHandle, ChildSuspendReason, ChildSuspensionPolicy, Handle, ResourceSet},
     test::*,
 };
 
@@ -75,6 +75,10 @@ async fn test_suspension() {
 
         cas_refresh_single(&testbed).await;
         expect_suspended(&testbed, &ca).await;
+
+        let info = ca_child_info(&testbed, &ca).await;
+        let reason = info.suspension().expect("suspension should be recorded").reason();
+        assert!(matches!(reason, ChildSuspendReason::Inactive { .. }));
     }
 
     // Let "CA" refresh with testbed, this should 'un-suspend' it.
@@ -83,10 +87,25 @@ async fn test_suspension() {
         expect_not_suspended(&testbed, &ca).await;
     }
 
+    // Children which are exempt are not suspended, however long they are inactive
+    {
+        ca_child_suspension_policy(&testbed, &ca, Some(ChildSuspensionPolicy::exempt())).await;
+        sleep_seconds(5).await;
+
+        cas_refresh_single(&testbed).await;
+        expect_not_suspended(&testbed, &ca).await;
+
+        ca_child_suspension_policy(&testbed, &ca, None).await;
+    }
+
     // CAs can also be suspended explicitly, regardless of their last known connection
     {
         ca_suspend_child(&testbed, &ca).await;
         expect_suspended(&testbed, &ca).await;
+
+        let info = ca_child_info(&testbed, &ca).await;
+        let reason = info.suspension().expect("suspension should be recorded").reason();
+        assert_eq!(reason, &ChildSuspendReason::Manual);
     }
 
     // And they can be manually unsuspended as well
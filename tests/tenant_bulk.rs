@@ -0,0 +1,66 @@
+//! Test that users bound to a tenant can run the bulk operations on CAs, and
+//! that these are limited to the CAs which they may update.
+#![cfg(feature = "multi-user")]
+
+use std::fs;
+
+use krill::{
+    cli::{
+        options::{Command, TenantCommand, TokenCommand},
+        report::ApiResponse,
+    },
+    commons::{
+        api::{ApiTokenRequest, Handle, TenantDefinition, Token},
+        util::httpclient,
+    },
+    test::*,
+};
+
+async fn create_token(name: &str, permissions: &[&str], cas: Vec<Handle>) -> Token {
+    let request = ApiTokenRequest::new(
+        name.to_string(),
+        permissions.iter().map(|perm| perm.to_string()).collect(),
+        Some(cas),
+        None,
+    );
+    match krill_admin(Command::Token(TokenCommand::Create(request))).await {
+        ApiResponse::ApiTokenCreated(created) => created.token().clone(),
+        _ => panic!("Expected created token"),
+    }
+}
+
+#[tokio::test]
+async fn tenant_bulk() {
+    init_logging();
+
+    let krill_dir = start_krill_with_default_test_config(false, false, false).await;
+
+    let ca_tenant = handle("ca-tenant");
+    let ca_other = handle("ca-other");
+
+    init_ca(&ca_tenant).await;
+    init_ca(&ca_other).await;
+
+    let tenant = TenantDefinition::new("customer1".to_string(), vec![ca_tenant.clone()]);
+    krill_admin(Command::Tenants(TenantCommand::Add(tenant))).await;
+
+    let updater = create_token("updater", &["CA_LIST", "CA_READ", "CA_UPDATE"], vec![ca_tenant.clone()]).await;
+    let reader = create_token("reader", &["CA_LIST", "CA_READ"], vec![ca_tenant.clone()]).await;
+    for user in &["token:updater", "token:reader"] {
+        krill_admin(Command::Tenants(TenantCommand::AddUser(
+            "customer1".to_string(),
+            user.to_string(),
+        )))
+        .await;
+    }
+
+    // A tenant user who may update its CAs can run the bulk operations on
+    // them, a tenant user who may only read them is refused.
+    for path in &["bulk/cas/sync/parent", "bulk/cas/sync/repo", "bulk/cas/publish"] {
+        let uri = format!("{}api/v1/{}", KRILL_SERVER_URI, path);
+        assert!(httpclient::post_empty(&uri, Some(&updater)).await.is_ok(), "{}", path);
+        assert!(httpclient::post_empty(&uri, Some(&reader)).await.is_err(), "{}", path);
+    }
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
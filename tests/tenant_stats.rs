@@ -0,0 +1,82 @@
+//! Test that the public statistics do not reveal the CAs of other tenants,
+//! neither to anonymous users, nor to users bound to a tenant.
+#![cfg(feature = "multi-user")]
+
+use std::fs;
+
+use krill::{
+    cli::{
+        options::{Command, TenantCommand, TokenCommand},
+        report::ApiResponse,
+    },
+    commons::{
+        api::{ApiTokenRequest, TenantDefinition},
+        util::httpclient,
+    },
+    test::*,
+};
+
+#[tokio::test]
+async fn tenant_stats() {
+    init_logging();
+
+    let krill_dir = start_krill_with_default_test_config(false, false, false).await;
+
+    let ca_tenant = handle("ca-tenant");
+    let ca_other = handle("ca-other");
+
+    init_ca(&ca_tenant).await;
+    init_ca(&ca_other).await;
+
+    // Without tenants anonymous users can still see the stats for all CAs.
+    let metrics = httpclient::get_text(&format!("{}metrics", KRILL_SERVER_URI), None)
+        .await
+        .unwrap();
+    assert!(metrics.contains("ca-other"));
+
+    let tenant = TenantDefinition::new("customer1".to_string(), vec![ca_tenant.clone()]);
+    krill_admin(Command::Tenants(TenantCommand::Add(tenant))).await;
+
+    let request = ApiTokenRequest::new(
+        "stats".to_string(),
+        vec!["CA_LIST".to_string(), "CA_READ".to_string()],
+        Some(vec![ca_tenant.clone()]),
+        None,
+    );
+    let token = match krill_admin(Command::Token(TokenCommand::Create(request))).await {
+        ApiResponse::ApiTokenCreated(created) => created.token().clone(),
+        _ => panic!("Expected created token"),
+    };
+    krill_admin(Command::Tenants(TenantCommand::AddUser(
+        "customer1".to_string(),
+        "token:stats".to_string(),
+    )))
+    .await;
+
+    // Anonymous users now only get aggregate numbers.
+    let metrics = httpclient::get_text(&format!("{}metrics", KRILL_SERVER_URI), None)
+        .await
+        .unwrap();
+    assert!(metrics.contains("krill_cas 2"));
+    assert!(!metrics.contains("ca-other"));
+    assert!(!metrics.contains("ca-tenant"));
+
+    assert!(httpclient::get_text(&format!("{}stats/cas", KRILL_SERVER_URI), None)
+        .await
+        .is_err());
+
+    // The tenant user only sees its own CA.
+    let stats = httpclient::get_text(&format!("{}stats/cas", KRILL_SERVER_URI), Some(&token))
+        .await
+        .unwrap();
+    assert!(stats.contains("ca-tenant"));
+    assert!(!stats.contains("ca-other"));
+
+    let metrics = httpclient::get_text(&format!("{}metrics", KRILL_SERVER_URI), Some(&token))
+        .await
+        .unwrap();
+    assert!(metrics.contains("ca-tenant"));
+    assert!(!metrics.contains("ca-other"));
+
+    let _ = fs::remove_dir_all(krill_dir);
+}
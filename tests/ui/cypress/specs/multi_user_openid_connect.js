@@ -421,4 +421,77 @@ describe('OpenID Connect provider with RP-Initiated logout', () => {
       cy.wait('@createCA', { responseTimeout: time_to_wait_ms }).its('response.statusCode').should('eq', expected_status_code)
     })
   )
+
+  it('CA access can be derived from a multi-valued groups claim', () => {
+    let user_name = 'user-with-groups'
+    let ca_name = 'group-ca-' + Date.now()
+
+    // login
+    cy.visit('/')
+    cy.url().should('not.include', Cypress.config('baseUrl'))
+    cy.contains('Mock OpenID Connect login form')
+    cy.get('input[name="username"]').clear().type(user_name)
+    cy.get('input[name="userattr1"]').clear().type('role')         // a role is required to be able to login
+    cy.get('input[name="userattrval1"]').clear().type('readwrite')
+    cy.get('input[name="userattr2"]').clear().type('groups')       // becomes a multi-valued "groups" claim
+    cy.get('input[name="userattrval2"]').clear().type('staff,krill-ca-' + ca_name)
+    cy.contains('Sign In').click()
+
+    // verify that the CA from the group claim was mapped to the "inc_cas" attribute
+    cy.contains('Sign In').should('not.exist')
+    cy.url().should('include', Cypress.config('baseUrl'))
+    cy.get('#userinfo').click()
+    cy.get('#userinfo_table').contains(user_name)
+    cy.get('#userinfo_table').contains(ca_name)
+
+    // which makes Lagosta think there are no CAs, so the create CA welcome page is shown
+    cy.contains('Welcome to Krill')
+  })
+
+  it('A role change at the provider takes effect when the token is refreshed', () => {
+    let user_name = 'user-demoted-at-provider'
+    let ca_name = 'demoted-ca-' + Date.now()
+
+    // Long enough to login before half of the lifetime has passed, after which Krill refreshes the token.
+    let token_secs = 6
+
+    // login
+    cy.visit('/')
+    cy.url().should('not.include', Cypress.config('baseUrl'))
+    cy.contains('Mock OpenID Connect login form')
+    cy.get('input[name="username"]').clear().type(user_name)
+    cy.get('input[name="userattr1"]').clear().type('role')         // a role is required to be able to login
+    cy.get('input[name="userattrval1"]').clear().type('readwrite')
+    cy.get('input[name="userattr2"]').clear().type('inc_cas')      // force the create CA welcome page to show
+    cy.get('input[name="userattrval2"]').clear().type(ca_name)     //   (by making Lagosta think there are no CAs)
+    cy.get('input[name="token_secs"]').clear().type(token_secs)    // control the lifetime of the issued access token
+    cy.contains('Sign In').click()
+
+    // record the approximate time at which the token was issued
+    let issued_at_ms = Date.now()
+
+    cy.contains('Sign In').should('not.exist')
+    cy.url().should('include', Cypress.config('baseUrl'))
+    cy.contains('Welcome to Krill')
+
+    // demote the user at the provider, as an administrator of the provider would
+    cy.request({
+      method: 'POST',
+      url: 'https://127.0.0.1:1818/test/set_user_attribute?username=' + user_name + '&attr=role&value=readonly',
+    }).its('status').should('eq', 200)
+
+    // wait until the token from login has expired, so that Krill must refresh it (a token refreshed before the
+    // demotion would also be due for refresh by then)
+    let time_elapsed_ms = Date.now() - issued_at_ms
+    cy.wait(Math.max(0, token_secs * 1000 - time_elapsed_ms + 1000))
+
+    // Creating a CA makes Krill refresh the token, and re-evaluate the claims, which should deny the now readonly user
+    cy.intercept({ method: 'POST', path: '/api/v1/cas'}).as('createCA')
+    cy.contains('CA Handle')
+    cy.get('form input[type="text"]').type(ca_name)
+    cy.contains('Create CA').click()
+    cy.contains('OK').click()
+
+    cy.wait('@createCA').its('response.statusCode').should('eq', 403)
+  })
 })
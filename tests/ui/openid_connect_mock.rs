@@ -50,6 +50,7 @@ pub struct CustomAdditionalClaims {
     role: Option<String>,
     inc_cas: Option<String>,
     exc_cas: Option<String>,
+    groups: Option<Vec<String>>,
 }
 impl AdditionalClaims for CustomAdditionalClaims {}
 
@@ -354,6 +355,11 @@ fn run_mock_openid_connect_server(config: OpenIDConnectMockConfig) {
                     role: user.attributes.get("role").map(|v| v.to_string()),
                     inc_cas: user.attributes.get("inc_cas").map(|v| v.to_string()),
                     exc_cas: user.attributes.get("exc_cas").map(|v| v.to_string()),
+                    // a multi-valued claim, given as a comma-separated "groups" attribute at login
+                    groups: user
+                        .attributes
+                        .get("groups")
+                        .map(|v| v.split(',').map(|group| group.to_string()).collect()),
                 },
             )
             // Optional: specify the user's e-mail address. This should only be provided if the
@@ -819,6 +825,41 @@ fn run_mock_openid_connect_server(config: OpenIDConnectMockConfig) {
         }
     }
 
+    /// Change an attribute of a known user, as an administrator of the provider would, so that tests can check that
+    /// the change takes effect when the token of the user is refreshed. An empty value removes the attribute.
+    fn handle_control_set_user_attribute_request(
+        request: Request,
+        url: Url,
+        known_users: &mut KnownUsers,
+    ) -> Result<(), Error> {
+        let query = url
+            .get_parsed_query()
+            .ok_or_else(|| Error::custom("Missing query parameters"))?;
+        let username = require_query_param(&query, "username")?;
+        let attr = require_query_param(&query, "attr")?;
+        let value = query.get_first_from_str("value").unwrap_or_default();
+
+        match known_users.get_mut(&username) {
+            Some(user) => {
+                info!("Setting attribute '{}' of user '{}' to '{}'", &attr, &username, &value);
+                if value.is_empty() {
+                    user.attributes.remove(&attr);
+                } else {
+                    user.attributes.insert(attr, value);
+                }
+                request
+                    .respond(Response::empty(StatusCode(200)))
+                    .map_err(|err: std::io::Error| Error::custom(format!("IO error: {}", err)))
+            }
+            None => {
+                info!("Unknown user '{}'", &username);
+                request
+                    .respond(Response::empty(StatusCode(400)))
+                    .map_err(|err: std::io::Error| Error::custom(format!("IO error: {}", err)))
+            }
+        }
+    }
+
     fn handle_token_request(
         mut request: Request,
         signing_key: &CoreRsaPrivateSigningKey,
@@ -1197,6 +1238,9 @@ fn run_mock_openid_connect_server(config: OpenIDConnectMockConfig) {
             (_, Method::Get, "/test/is_user_logged_in") => {
                 return handle_control_is_user_logged_in_request(request, url, login_sessions);
             }
+            (_, Method::Post, "/test/set_user_attribute") => {
+                return handle_control_set_user_attribute_request(request, url, known_users);
+            }
             (_, Method::Post, "/test/enable") => {
                 info!("Enabling all OpenID Connect endpoints!");
                 *enabled = true;